@@ -157,13 +157,12 @@ pub async fn run_accumulative_mode(
                 // Create agent for this autonomous run
                 let ui_writer = ConsoleUiWriter::new();
                 ui_writer.set_workspace_path(workspace_dir.clone());
-                let agent = Agent::new_autonomous_with_project_context_and_quiet(
-                    config.clone(),
-                    ui_writer,
-                    combined_content.clone(),
-                    cli.quiet,
-                )
-                .await?;
+                let agent = Agent::builder(config.clone(), ui_writer)
+                    .autonomous(true)
+                    .project_context(combined_content.clone())
+                    .quiet(cli.quiet)
+                    .build()
+                    .await?;
 
                 // Run autonomous mode with the accumulated requirements
                 let autonomous_result = tokio::select! {
@@ -291,13 +290,11 @@ async fn handle_command(
             // Create agent for interactive mode with requirements context
             let ui_writer = ConsoleUiWriter::new();
             ui_writer.set_workspace_path(workspace_dir.clone());
-            let agent = Agent::new_with_project_context_and_quiet(
-                config,
-                ui_writer,
-                chat_combined_content.clone(),
-                cli.quiet,
-            )
-            .await?;
+            let agent = Agent::builder(config, ui_writer)
+                .project_context(chat_combined_content.clone())
+                .quiet(cli.quiet)
+                .build()
+                .await?;
 
             // Run interactive mode
             run_interactive(
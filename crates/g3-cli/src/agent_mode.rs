@@ -6,7 +6,7 @@ use tracing::debug;
 use g3_core::ui_writer::UiWriter;
 use g3_core::Agent;
 
-use crate::project_files::{combine_project_content, read_agents_config, read_include_prompt, read_workspace_memory};
+use crate::project_files::{combine_project_content, read_agents_config, read_include_prompt, read_project_profile, read_workspace_memory};
 use crate::display::{LoadedContent, print_loaded_status, print_workspace_path};
 use crate::language_prompts::{get_language_prompts_for_workspace, get_agent_language_prompts_for_workspace_with_langs};
 use crate::simple_output::SimpleOutput;
@@ -93,6 +93,7 @@ pub async fn run_agent_mode(
 
     // Load config
     let mut config = g3_config::Config::load(flags.config.as_deref())?;
+    crate::otel::enable(&config.telemetry)?;
 
     // Apply chrome-headless flag override
     if flags.chrome_headless {
@@ -106,6 +107,14 @@ pub async fn run_agent_mode(
         config.webdriver.browser = g3_config::WebDriverBrowser::Safari;
     }
 
+    if let Some(level) = flags.auto_approve {
+        config.approval.auto_approve = level;
+    }
+
+    if flags.devcontainer {
+        config.shell.devcontainer = true;
+    }
+
     // Generate the combined system prompt (agent prompt + tool instructions)
     // Note: allow_multiple_tool_calls parameter is deprecated but kept for API compatibility
     let system_prompt = get_agent_system_prompt(&agent_prompt, true);
@@ -113,6 +122,7 @@ pub async fn run_agent_mode(
     // Load AGENTS.md and memory - same as normal mode
     let agents_content_opt = read_agents_config(&workspace_dir);
     let memory_content_opt = read_workspace_memory(&workspace_dir);
+    let profile_content = read_project_profile(&workspace_dir);
 
     // Read include prompt early so we can show it in the status line
     let include_prompt = read_include_prompt(flags.include_prompt.as_deref());
@@ -160,6 +170,7 @@ pub async fn run_agent_mode(
         memory_content_opt,
         language_content,
         include_prompt,
+        profile_content,
         &workspace_dir,
     );
 
@@ -168,8 +179,11 @@ pub async fn run_agent_mode(
     // Set agent mode on UI writer for visual differentiation (light gray tool names)
     ui_writer.set_agent_mode(true);
     ui_writer.set_workspace_path(workspace_dir.clone());
-    let mut agent =
-        Agent::new_with_custom_prompt(config, ui_writer, system_prompt, combined_content.clone()).await?;
+    let mut agent = Agent::builder(config, ui_writer)
+        .system_prompt(system_prompt)
+        .project_context(combined_content.clone())
+        .build()
+        .await?;
 
     // Set agent mode for session tracking
     agent.set_agent_mode(agent_name);
@@ -183,6 +197,10 @@ pub async fn run_agent_mode(
         agent.set_acd_enabled(true);
     }
 
+    if flags.dry_run {
+        agent.set_dry_run(true);
+    }
+
     // If resuming a session, restore context and TODO
     let initial_task = if let Some(ref incomplete_session) = resuming_session {
         // Restore the session context
@@ -2,11 +2,13 @@
 
 use anyhow::Result;
 use sha2::{Digest, Sha256};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 use tracing::debug;
 
+use g3_config::DiscoveryMode;
 use g3_core::error_handling::{classify_error, ErrorType, RecoverableError};
+use g3_core::fast_start::{self, FastStartSnapshot};
 use g3_core::project::Project;
 use g3_core::{Agent, DiscoveryOptions};
 
@@ -60,8 +62,23 @@ pub async fn run_autonomous(
     // Display appropriate message based on requirements source
     if project.requirements_text.is_some() {
         output.print("📋 Requirements loaded from --requirements flag");
-    } else {
-        output.print("📋 Requirements loaded from requirements.md");
+    } else if let Some(path) = &project.requirements_path {
+        output.print(&format!("📋 Requirements loaded from {}", path.display()));
+    }
+
+    // Structured requirements (YAML/JSON/Markdown with IDs) are tracked by ID
+    let parsed_requirements = g3_core::requirements::parse_requirements(
+        &requirements,
+        project.requirements_path.as_deref(),
+    );
+    if !parsed_requirements.is_empty() {
+        let ids: Vec<String> = parsed_requirements.iter().map(|r| r.id.clone()).collect();
+        output.print(&format!(
+            "📋 Parsed {} requirements: {}",
+            ids.len(),
+            ids.join(", ")
+        ));
+        agent.set_requirement_ids(ids);
     }
 
     // Calculate SHA256 of requirements
@@ -78,7 +95,7 @@ pub async fn run_autonomous(
     output.print("🔄 Starting coach-player feedback loop...");
 
     // Load fast-discovery messages before the loop starts (if enabled)
-    let (discovery_messages, discovery_working_dir) =
+    let (discovery_messages, discovery_working_dir, discovery_results) =
         load_discovery_messages(&agent, &output, &codebase_fast_start, &requirements).await;
     let has_discovery = !discovery_messages.is_empty();
 
@@ -134,6 +151,7 @@ pub async fn run_autonomous(
             has_discovery,
             &discovery_messages,
             discovery_working_dir.as_deref(),
+            discovery_results.as_deref(),
             turn,
             &turn_metrics,
             start_time,
@@ -235,6 +253,11 @@ pub async fn run_autonomous(
         output.print("🔄 Coach provided feedback for next iteration");
     }
 
+    if implementation_approved && agent.get_config().changelog.auto_update {
+        let report = session_report(&agent, project.workspace()).await;
+        record_changelog(&mut agent, &requirements, &report, show_prompt, show_code, &output).await;
+    }
+
     // Generate final report
     print_final_report(
         &output,
@@ -293,13 +316,65 @@ fn build_player_prompt(requirements: &str, requirements_sha: &str, coach_feedbac
     }
 }
 
-fn build_coach_prompt(requirements: &str) -> String {
+fn build_changelog_prompt(requirements: &str, report: &str, changelog_path: &str) -> String {
+    format!(
+        "The coach approved your implementation of these requirements:\n\n{}\n\nSession report:\n\n{}\n\nRecord the user-visible changes listed in the report in {} by calling the `changelog` tool once. Group entries under added/changed/deprecated/removed/fixed/security, one short past-tense sentence each. Do not make any other changes.",
+        requirements, report, changelog_path
+    )
+}
+
+/// What the approved session did: the player's plan with the status of each
+/// step, and the files changed in the workspace.
+async fn session_report(agent: &Agent<ConsoleUiWriter>, workspace: &Path) -> String {
+    let mut sections = Vec::new();
+    if let Some(plan) = agent.get_plan() {
+        sections.push(format!("Plan:\n{}", plan.format()));
+    }
+    let files = g3_core::utils::list_changed_files(workspace.to_str()).await;
+    if !files.is_empty() {
+        let list: Vec<String> = files.iter().map(|f| format!("- {}", f)).collect();
+        sections.push(format!("Files changed:\n{}", list.join("\n")));
+    }
+    if sections.is_empty() {
+        "(no plan was recorded and no changed files were found)".to_string()
+    } else {
+        sections.join("\n\n")
+    }
+}
+
+/// Ask the player to record the approved changes in the changelog.
+async fn record_changelog(
+    agent: &mut Agent<ConsoleUiWriter>,
+    requirements: &str,
+    report: &str,
+    show_prompt: bool,
+    show_code: bool,
+    output: &SimpleOutput,
+) {
+    let prompt = build_changelog_prompt(requirements, report, &agent.get_config().changelog.path);
+    output.print("\n📝 Recording changes in the changelog...");
+    if let Err(e) = agent
+        .execute_task_with_timing(&prompt, None, false, show_prompt, show_code, true, None)
+        .await
+    {
+        output.print(&format!("⚠️ Failed to update the changelog: {}", e));
+    }
+}
+
+fn build_coach_prompt(requirements: &str, player_plan: Option<&str>) -> String {
+    let plan_section = match player_plan {
+        Some(plan) => format!(
+            "\nPLAYER'S PLAN (the player's own working plan; check that it covers the requirements and that steps marked done really are):\n{}\n",
+            plan
+        ),
+        None => String::new(),
+    };
     format!(
         "You are G3 in coach mode. Your role is to critique and review implementations against requirements and provide concise, actionable feedback.
 
 REQUIREMENTS:
 {}
-
+{}
 IMPLEMENTATION REVIEW:
 Review the current state of the project and provide a concise critique focusing on:
 1. Whether the requirements are correctly implemented
@@ -321,7 +396,7 @@ If improvements are needed:
 - Respond with a brief summary listing ONLY the specific issues to fix
 
 Remember: Be clear in your review and concise in your feedback. APPROVE iff the implementation works and thoroughly fits the requirements (implementation > 95% complete). Be rigorous, especially by testing that all UI features work.",
-        requirements
+        requirements, plan_section
     )
 }
 
@@ -330,51 +405,103 @@ async fn load_discovery_messages(
     output: &SimpleOutput,
     codebase_fast_start: &Option<PathBuf>,
     requirements: &str,
-) -> (Vec<g3_providers::Message>, Option<String>) {
+) -> (
+    Vec<g3_providers::Message>,
+    Option<String>,
+    Option<Vec<String>>,
+) {
     if let Some(ref codebase_path) = codebase_fast_start {
+        let output_clone = output.clone();
+        let status_callback = g3_planner::status_messages(move |msg| output_clone.print(msg));
+        let discovery = &agent.get_config().discovery;
+
+        // A git URL is cloned first; the agent then works in the clone
+        let codebase_path = match g3_planner::resolve_codebase(
+            &codebase_path.to_string_lossy(),
+            Some(&status_callback),
+        ) {
+            Ok(path) => PathBuf::from(path),
+            Err(e) => {
+                output.print(&format!("⚠️ {}, skipping fast-start", e));
+                return (Vec::new(), None, None);
+            }
+        };
         let canonical_path = codebase_path
             .canonicalize()
             .unwrap_or_else(|_| codebase_path.clone());
-        let path_str = canonical_path.to_string_lossy();
+
+        // In a monorepo, only the targeted package is explored and worked in
+        let path_str = g3_planner::discovery_root(
+            &canonical_path.to_string_lossy(),
+            Some(requirements),
+            discovery.target.as_deref(),
+            Some(&status_callback),
+        );
         output.print(&format!(
             "🔍 Fast-discovery mode: will explore codebase at {}",
             path_str
         ));
 
-        match agent.get_provider() {
-            Ok(provider) => {
-                let output_clone = output.clone();
-                let status_callback: g3_planner::StatusCallback = Box::new(move |msg: &str| {
-                    output_clone.print(msg);
-                });
-                match g3_planner::get_initial_discovery_messages(
-                    &path_str,
-                    Some(requirements),
-                    provider,
-                    Some(&status_callback),
-                )
-                .await
-                {
-                    Ok(messages) => (messages, Some(path_str.to_string())),
-                    Err(e) => {
-                        output.print(&format!(
-                            "⚠️ LLM discovery failed: {}, skipping fast-start",
-                            e
-                        ));
-                        (Vec::new(), None)
+        if discovery.symbol_index {
+            g3_planner::update_symbol_index(&path_str, Some(&status_callback));
+        }
+
+        // Same commit as a previous session: reuse its discovery as is
+        let snapshot = fast_start::git_head(Path::new(&path_str))
+            .and_then(|head| FastStartSnapshot::load(&path_str, &head));
+        if let Some(snapshot) = snapshot {
+            output.print(&format!(
+                "⚡ Reusing fast-start snapshot from {} ({} commands at HEAD {})",
+                snapshot.created_at,
+                snapshot.entries.len(),
+                &snapshot.head[..snapshot.head.len().min(8)]
+            ));
+            return (
+                snapshot.messages(),
+                Some(path_str),
+                Some(snapshot.results()),
+            );
+        }
+
+        let messages = match discovery.mode {
+            DiscoveryMode::Heuristic => {
+                g3_planner::get_heuristic_discovery_messages(&path_str, Some(&status_callback))
+            }
+            DiscoveryMode::Llm => {
+                let llm_messages = match agent.get_provider() {
+                    Ok(provider) => {
+                        g3_planner::get_initial_discovery_messages(
+                            &path_str,
+                            Some(requirements),
+                            discovery.candidate_plans,
+                            &g3_planner::ExploreOptions::from(discovery),
+                            provider,
+                            Some(&status_callback),
+                        )
+                        .await
                     }
-                }
+                    Err(e) => Err(e),
+                };
+                // Offline or failing providers still get a useful fast start
+                llm_messages.or_else(|e| {
+                    output.print(&format!(
+                        "⚠️ LLM discovery failed: {}, falling back to heuristic discovery",
+                        e
+                    ));
+                    g3_planner::get_heuristic_discovery_messages(&path_str, Some(&status_callback))
+                })
             }
+        };
+
+        match messages {
+            Ok(messages) => (messages, Some(path_str), None),
             Err(e) => {
-                output.print(&format!(
-                    "⚠️ Could not get provider: {}, skipping fast-start",
-                    e
-                ));
-                (Vec::new(), None)
+                output.print(&format!("⚠️ Discovery failed: {}, skipping fast-start", e));
+                (Vec::new(), None, None)
             }
         }
     } else {
-        (Vec::new(), None)
+        (Vec::new(), None, None)
     }
 }
 
@@ -387,6 +514,7 @@ async fn execute_player_turn(
     has_discovery: bool,
     discovery_messages: &[g3_providers::Message],
     discovery_working_dir: Option<&str>,
+    discovery_results: Option<&[String]>,
     turn: usize,
     turn_metrics: &[TurnMetrics],
     start_time: Instant,
@@ -395,11 +523,26 @@ async fn execute_player_turn(
     const MAX_PLAYER_RETRIES: u32 = 3;
     let mut retry_count = 0;
 
+    // The player keeps its context between turns, so later turns only get
+    // what changed since the last discovery instead of the whole replay
+    if has_discovery && turn > 1 {
+        if let Some(path) = discovery_working_dir {
+            let output_clone = output.clone();
+            let status_callback = g3_planner::status_messages(move |msg| output_clone.print(msg));
+            if let Err(e) =
+                g3_planner::refresh_discovery(agent, path, None, Some(&status_callback)).await
+            {
+                output.print(&format!("⚠️ Discovery refresh failed: {}", e));
+            }
+        }
+    }
+
     loop {
-        let discovery_opts = if has_discovery {
+        let discovery_opts = if has_discovery && turn == 1 {
             Some(DiscoveryOptions {
                 messages: discovery_messages,
                 fast_start_path: discovery_working_dir,
+                cached_results: discovery_results,
             })
         } else {
             None
@@ -500,13 +643,15 @@ async fn execute_coach_turn(
 
     let ui_writer = ConsoleUiWriter::new();
     ui_writer.set_workspace_path(project.workspace().to_path_buf());
-    let mut coach_agent =
-        match Agent::new_autonomous_with_project_context_and_quiet(coach_config, ui_writer, None, quiet)
-            .await
-        {
-            Ok(a) => a,
-            Err(e) => return CoachTurnResult::Panic(e),
-        };
+    let mut coach_agent = match Agent::builder(coach_config, ui_writer)
+        .autonomous(true)
+        .quiet(quiet)
+        .build()
+        .await
+    {
+        Ok(a) => a,
+        Err(e) => return CoachTurnResult::Panic(e),
+    };
 
     coach_agent.print_provider_banner("Coach");
 
@@ -519,7 +664,8 @@ async fn execute_coach_turn(
         turn, max_turns
     ));
 
-    let coach_prompt = build_coach_prompt(requirements);
+    let player_plan = player_agent.get_plan().map(|plan| plan.format());
+    let coach_prompt = build_coach_prompt(requirements, player_plan.as_deref());
 
     output.print(&format!(
         "🎓 Starting coach review... (elapsed: {})",
@@ -533,6 +679,8 @@ async fn execute_coach_turn(
             Some(DiscoveryOptions {
                 messages: discovery_messages,
                 fast_start_path: discovery_working_dir,
+                // The coach reviews the player's changes, so it runs the commands again
+                cached_results: None,
             })
         } else {
             None
@@ -648,8 +796,8 @@ fn print_no_requirements_error(
 ) {
     output.print("❌ Error: requirements.md not found in workspace directory");
     output.print("   Please either:");
-    output.print("   1. Create a requirements.md file with your project requirements");
-    output.print("   2. Or use the --requirements flag to provide requirements text directly:");
+    output.print("   1. Create a requirements.md (or requirements.yaml/.json) file with your project requirements");
+    output.print("   2. Or use the --requirements flag to provide requirements text or a file directly:");
     output.print("      g3 --autonomous --requirements \"Your requirements here\"");
     output.print("");
 
@@ -1,6 +1,6 @@
 //! CLI argument parsing for G3.
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
 /// Flags that apply across all execution modes (interactive, agent, autonomous).
@@ -30,6 +30,59 @@ pub struct CommonFlags {
     pub acd: bool,
     /// Load a project from the given path at startup
     pub project: Option<PathBuf>,
+    /// Risky tool calls to run without asking (overrides approval.auto_approve)
+    pub auto_approve: Option<g3_config::ApprovalLevel>,
+    /// Preview changing tool calls instead of executing them
+    pub dry_run: bool,
+    /// Run commands inside the workspace's devcontainer
+    pub devcontainer: bool,
+}
+
+/// Subcommands that replace the default interactive/single-shot modes.
+#[derive(Subcommand, Clone, Debug)]
+pub enum Command {
+    /// Cut a release: version bump, changelog, commit and tag, publish dry-run.
+    /// Each step asks for approval first.
+    Release {
+        /// Version to release, or a bump level: major, minor, patch
+        #[arg(default_value = "patch")]
+        version: String,
+
+        /// Approve steps without prompting when the approval policy allows
+        /// their commands
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Show which g3 session produced a commit, to find its transcript
+    Trace {
+        /// Commit to trace (SHA, branch, tag or any git revision)
+        #[arg(default_value = "HEAD")]
+        rev: String,
+    },
+    /// Link a commit to a g3 session (the session its trailer names, or the current one)
+    LinkCommit {
+        /// Commit to link
+        #[arg(default_value = "HEAD")]
+        rev: String,
+
+        /// Session to link it to
+        #[arg(long, value_name = "ID")]
+        session: Option<String>,
+
+        /// Install git hooks that link every commit made while a session is recent
+        #[arg(long)]
+        install_hooks: bool,
+    },
+    /// Serve an HTTP API for running sessions remotely, with events over SSE
+    Serve {
+        /// Address to listen on (default: [server] bind, 127.0.0.1:7340)
+        #[arg(long, value_name = "ADDR")]
+        bind: Option<String>,
+    },
+    /// Serve g3's tools and sessions over MCP on stdio, for other agents and editors
+    Mcp,
+    /// Run as an editor's agent over the Agent Client Protocol on stdio (Zed, ...)
+    Acp,
 }
 
 #[derive(Parser, Clone)]
@@ -72,7 +125,8 @@ pub struct Cli {
     #[arg(long, default_value = "5")]
     pub max_turns: usize,
 
-    /// Override requirements text for autonomous mode (instead of reading from requirements.md)
+    /// Override requirements for autonomous mode (instead of reading requirements.md): text, or
+    /// the path of a Markdown, YAML or JSON requirements file
     #[arg(long, value_name = "TEXT")]
     pub requirements: Option<String>,
 
@@ -120,8 +174,9 @@ pub struct Cli {
     #[arg(long)]
     pub no_git: bool,
 
-    /// Enable fast codebase discovery before first LLM turn
-    #[arg(long, value_name = "PATH")]
+    /// Enable fast codebase discovery before first LLM turn. A git URL is
+    /// shallow-cloned into ~/.g3/repos and explored there
+    #[arg(long, value_name = "PATH|URL")]
     pub codebase_fast_start: Option<PathBuf>,
 
     /// Run as a specialized agent (loads prompt from agents/<name>.md)
@@ -155,6 +210,33 @@ pub struct Cli {
     /// Load a project from the given path at startup (like /project but without auto-prompt)
     #[arg(long, value_name = "PATH")]
     pub project: Option<PathBuf>,
+
+    /// Risky tool calls that run without asking: none, workspace (deletions in
+    /// the workspace), network (also pushes and network changes) or all. The
+    /// rest are refused in autonomous mode
+    #[arg(long, value_name = "LEVEL")]
+    pub auto_approve: Option<g3_config::ApprovalLevel>,
+
+    /// Don't change anything: file edits, commands and other changing tool
+    /// calls are reported as diffs and command lines instead of executed, and
+    /// recorded in the session's dry_run.jsonl
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Run shell commands inside the workspace's devcontainer (from
+    /// .devcontainer/devcontainer.json) via docker exec, instead of on the
+    /// host (overrides shell.devcontainer)
+    #[arg(long)]
+    pub devcontainer: bool,
+
+    /// Print the agent's events as JSON lines (agent_response, tool_header,
+    /// tool_result, tool_timing, turn_telemetry, ...) instead of formatted
+    /// text, for programs driving g3. Logs go to stderr
+    #[arg(long, requires = "task", conflicts_with_all = ["autonomous", "auto", "chat", "agent"])]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
 }
 
 impl Cli {
@@ -172,6 +254,9 @@ impl Cli {
             no_auto_memory: self.no_auto_memory,
             acd: self.acd,
             project: self.project.clone(),
+            auto_approve: self.auto_approve,
+            dry_run: self.dry_run,
+            devcontainer: self.devcontainer,
         }
     }
 }
@@ -5,7 +5,7 @@
 use anyhow::Result;
 use std::path::Path;
 
-use g3_core::Agent;
+use g3_core::{compressed_file, Agent};
 
 use crate::simple_output::SimpleOutput;
 use crate::ui_writer_impl::ConsoleUiWriter;
@@ -47,7 +47,7 @@ pub fn extract_from_logs(
          Coach result response length: {} chars",
         session_id,
         log_file_path,
-        log_file_path.exists(),
+        compressed_file::exists(&log_file_path),
         coach_result.response.len()
     ))
 }
@@ -61,11 +61,11 @@ fn resolve_log_path(session_id: &str) -> std::path::PathBuf {
 ///
 /// Searches backwards for the last assistant message with substantial text content.
 fn try_extract_from_log(log_file_path: &Path) -> Option<String> {
-    if !log_file_path.exists() {
+    if !compressed_file::exists(log_file_path) {
         return None;
     }
 
-    let log_content = std::fs::read_to_string(log_file_path).ok()?;
+    let log_content = compressed_file::read_to_string(log_file_path).ok()?;
     let log_json: serde_json::Value = serde_json::from_str(&log_content).ok()?;
 
     let messages = log_json
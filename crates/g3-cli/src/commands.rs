@@ -61,18 +61,24 @@ pub async fn handle_command<W: UiWriter>(
             output.print("  /compact   - Trigger compaction (compacts conversation history)");
             output.print("  /thinnify  - Trigger context thinning (replaces large tool results with file references)");
             output.print("  /skinnify  - Trigger full context thinning (like /thinnify but for entire context, not just first third)");
+            output.print("  /pin       - Pin the last user message so compaction keeps it verbatim");
             output.print("  /clear     - Clear session and start fresh (discards continuation artifacts)");
             output.print("  /fragments - List dehydrated context fragments (ACD)");
             output.print("  /rehydrate - Restore a dehydrated fragment by ID");
             output.print("  /resume    - List and switch to a previous session");
+            output.print("  /resume <id> [--last N] - Resume a session by ID, optionally only its last N turns");
             output.print("  /research  - List pending/completed research tasks");
             output.print("  /research <id> - View a specific research report");
             output.print("  /research latest - View the most recent research report");
             output.print("  /project <path> - Load a project from the given absolute path");
             output.print("  /unproject - Unload the current project and reset context");
             output.print("  /dump      - Dump entire context window to file for debugging");
+            output.print("  /export [file] - Export the session transcript as Markdown, or HTML for .html files");
+            output.print("  /sync [push|pull [id]] - Push this session to the [sync] remote, or pull one (the last pushed by default) and resume it");
+            output.print("  /import <file> [format] - Continue a Claude Code, Aider or Cursor conversation (format: claude-code, aider, cursor)");
             output.print("  /readme    - Reload README.md and AGENTS.md from disk");
             output.print("  /stats     - Show detailed context and performance statistics");
+            output.print("  /rediscover [focus] - Re-run codebase discovery and inject an updated report");
             output.print("  /run <file> - Read file and execute as prompt");
             output.print("  /help      - Show this help message");
             output.print("  exit/quit  - Exit the interactive session");
@@ -104,6 +110,14 @@ pub async fn handle_command<W: UiWriter>(
             G3Status::thin_result(&result);
             Ok(true)
         }
+        "/pin" => {
+            if agent.pin_last_user_message() {
+                output.print("📌 Pinned the last user message; it will survive compaction verbatim.");
+            } else {
+                output.print("No user message to pin.");
+            }
+            Ok(true)
+        }
         "/fragments" => {
             if let Some(session_id) = agent.get_session_id() {
                 match g3_core::acd::list_fragments(session_id) {
@@ -278,8 +292,8 @@ pub async fn handle_command<W: UiWriter>(
                 dump_content.push_str(&format!("=== Message {} ===\n", i));
                 dump_content.push_str(&format!("Role: {:?}\n", msg.role));
                 dump_content.push_str(&format!("Kind: {:?}\n", msg.kind));
-                dump_content.push_str(&format!("Content ({} chars):\n", msg.content.len()));
-                dump_content.push_str(&msg.content);
+                dump_content.push_str(&format!("Content ({} chars):\n", msg.content_len()));
+                dump_content.push_str(&msg.full_content());
                 dump_content.push_str("\n\n");
             }
 
@@ -325,6 +339,22 @@ pub async fn handle_command<W: UiWriter>(
             output.print(&stats);
             Ok(true)
         }
+        cmd if cmd == "/rediscover" || cmd.starts_with("/rediscover ") => {
+            let focus = cmd.strip_prefix("/rediscover").unwrap_or("").trim();
+            let path = workspace_dir.to_string_lossy().to_string();
+            let output_clone = output.clone();
+            let status_callback = g3_planner::status_messages(move |msg| output_clone.print(msg));
+            let requirements = if focus.is_empty() { None } else { Some(focus) };
+            match g3_planner::rediscover(agent, &path, requirements, Some(&status_callback)).await {
+                Ok(tokens) => output.print(&format!(
+                    "🔄 Injected updated discovery report (~{} tokens, context at {:.0}%)",
+                    tokens,
+                    agent.get_context_window().percentage_used()
+                )),
+                Err(e) => output.print(&format!("❌ Rediscovery failed: {}", e)),
+            }
+            Ok(true)
+        }
         "/resume" => {
             output.print("📋 Scanning for available sessions...");
 
@@ -409,6 +439,117 @@ pub async fn handle_command<W: UiWriter>(
             }
             Ok(true)
         }
+        cmd if cmd.starts_with("/resume ") => {
+            let args: Vec<&str> = cmd.split_whitespace().skip(1).collect();
+            let (session_id, last_turns) = match args.as_slice() {
+                [id] => (*id, None),
+                [id, "--last", n] => match n.parse::<usize>() {
+                    Ok(n) => (*id, Some(n)),
+                    Err(_) => {
+                        output.print("Usage: /resume <session_id> [--last N]");
+                        return Ok(true);
+                    }
+                },
+                _ => {
+                    output.print("Usage: /resume <session_id> [--last N]");
+                    return Ok(true);
+                }
+            };
+            match agent.resume(session_id, last_turns) {
+                Ok(preview) if preview.full_context => {
+                    G3Status::resuming(session_id, Status::Done);
+                }
+                Ok(_) => G3Status::resuming_summary(session_id),
+                Err(e) => G3Status::resuming(session_id, Status::Error(e.to_string())),
+            }
+            Ok(true)
+        }
+        cmd if cmd == "/export" || cmd.starts_with("/export ") => {
+            let Some(session_id) = agent.get_session_id().map(str::to_string) else {
+                output.print("❌ No active session to export.");
+                return Ok(true);
+            };
+            let path = match cmd.strip_prefix("/export").map(str::trim) {
+                Some(path) if !path.is_empty() => std::path::PathBuf::from(path),
+                _ => g3_core::paths::get_session_logs_dir(&session_id).join("transcript.md"),
+            };
+            match g3_core::transcript_export::export_session(&session_id, &path) {
+                Ok(_) => output.print(&format!("📄 Transcript written to {}", path.display())),
+                Err(e) => output.print(&format!("❌ Failed to export transcript: {}", e)),
+            }
+            Ok(true)
+        }
+        cmd if cmd == "/sync" || cmd.starts_with("/sync ") => {
+            let Some(sync) = agent.session_sync() else {
+                output.print("❌ No sync remote configured. Set `remote` in the [sync] section of your config.");
+                return Ok(true);
+            };
+            let args: Vec<&str> = cmd.split_whitespace().skip(1).collect();
+            match args.as_slice() {
+                [] | ["push"] => {
+                    let Some(session_id) = agent.get_session_id().map(str::to_string) else {
+                        output.print("❌ No active session to push.");
+                        return Ok(true);
+                    };
+                    match sync.push(&session_id).await {
+                        Ok(()) => output.print(&format!("☁️  Pushed session {}", session_id)),
+                        Err(e) => output.print(&format!("❌ Failed to push session: {}", e)),
+                    }
+                }
+                ["pull"] | ["pull", _] => match sync.pull(args.get(1).copied()).await {
+                    Ok(session_id) => match agent.resume(&session_id, None) {
+                        Ok(preview) if preview.full_context => {
+                            G3Status::resuming(&session_id, Status::Done);
+                        }
+                        Ok(_) => G3Status::resuming_summary(&session_id),
+                        Err(e) => G3Status::resuming(&session_id, Status::Error(e.to_string())),
+                    },
+                    Err(e) => output.print(&format!("❌ Failed to pull session: {}", e)),
+                },
+                _ => output.print("Usage: /sync [push | pull [session_id]]"),
+            }
+            Ok(true)
+        }
+        cmd if cmd == "/import" || cmd.starts_with("/import ") => {
+            let args: Vec<&str> = cmd.split_whitespace().skip(1).collect();
+            let (path, format) = match args.as_slice() {
+                [path] => (*path, None),
+                [path, format] => match format.parse() {
+                    Ok(format) => (*path, Some(format)),
+                    Err(e) => {
+                        output.print(&format!("❌ {}", e));
+                        return Ok(true);
+                    }
+                },
+                _ => {
+                    output.print("Usage: /import <file> [claude-code|aider|cursor]");
+                    return Ok(true);
+                }
+            };
+            let total_tokens = agent.get_context_window().total_tokens;
+            match g3_core::session_import::import_transcript(
+                std::path::Path::new(path),
+                format,
+                total_tokens,
+            ) {
+                Ok(imported) => {
+                    let session_id = imported.continuation.session_id.clone();
+                    output.print(&format!(
+                        "📥 Imported {} messages from {} as session {}",
+                        imported.messages,
+                        imported.format.name(),
+                        session_id
+                    ));
+                    match agent.switch_to_session(&imported.continuation) {
+                        Ok(true) => G3Status::resuming(&session_id, Status::Done),
+                        Ok(false) => G3Status::resuming_summary(&session_id),
+                        Err(e) => G3Status::resuming(&session_id, Status::Error(e.to_string())),
+                    }
+                }
+                Err(e) => output.print(&format!("❌ Failed to import transcript: {}", e)),
+            }
+            Ok(true)
+        }
         cmd if cmd.starts_with("/project") => {
             let parts: Vec<&str> = cmd.splitn(2, ' ').collect();
             if parts.len() < 2 || parts[1].trim().is_empty() {
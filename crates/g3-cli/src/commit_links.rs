@@ -0,0 +1,62 @@
+//! `g3 trace` and `g3 link-commit`: from a commit to the session that
+//! produced it, and back.
+
+use anyhow::Result;
+
+use g3_core::{commit_links, paths, sessions};
+
+/// Run `g3 trace`: print the session that produced `rev`.
+pub fn run_trace(rev: &str) -> Result<()> {
+    let workspace = std::env::current_dir()?;
+    let Some(session_id) = commit_links::find_session(&workspace, rev)? else {
+        println!("No g3 session is linked to {}.", rev);
+        return Ok(());
+    };
+
+    println!("{} was made in session {}", rev, session_id);
+    match sessions::get_session_meta(&session_id) {
+        Ok(meta) => {
+            if let Some(description) = &meta.description {
+                println!("  Task: {}", description);
+            }
+            if let Some(last_active) = meta.last_active {
+                println!("  Last active: {}", last_active.format("%Y-%m-%d %H:%M"));
+            }
+            println!("  Commits: {}", meta.commits.len());
+            println!(
+                "  Files: {}",
+                paths::get_session_logs_dir(&session_id).display()
+            );
+            println!(
+                "\nRun g3 and /resume {} to continue it, or /export to write its transcript.",
+                session_id
+            );
+        }
+        Err(_) => println!(
+            "  The session isn't saved on this machine; fetch it with /sync pull {}.",
+            session_id
+        ),
+    }
+    Ok(())
+}
+
+/// Run `g3 link-commit`: link `rev` to a session, or install the hooks that
+/// do it on every commit.
+pub fn run_link_commit(rev: &str, session: Option<&str>, install_hooks: bool) -> Result<()> {
+    let workspace = std::env::current_dir()?;
+    if install_hooks {
+        for hook in commit_links::install_hooks(&workspace)? {
+            println!("Installed {}", hook.display());
+        }
+        return Ok(());
+    }
+
+    let (session_id, link) = commit_links::link_commit(&workspace, rev, session)?;
+    println!(
+        "Linked {} ({}) to session {}",
+        &link.sha[..link.sha.len().min(7)],
+        link.summary,
+        session_id
+    );
+    Ok(())
+}
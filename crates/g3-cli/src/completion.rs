@@ -3,7 +3,7 @@
 //! Provides:
 //! - Prompt highlighting (colorizes project name in blue)
 //! - Command completion for `/` commands at line start
-//! - File path completion for `./`, `../`, `~/`, `/` prefixes
+//! - File path completion for `./`, `../`, `~/`, `/` prefixes, and for `/run` and `/import`
 //! - Session ID completion for `/resume` command
 //! - Project name completion for `/project` command (from ~/projects/)
 
@@ -20,15 +20,20 @@ const COMMANDS: &[&str] = &[
     "/clear",
     "/compact",
     "/dump",
+    "/export",
     "/fragments",
     "/help",
+    "/import",
+    "/pin",
     "/project",
     "/readme",
+    "/rediscover",
     "/rehydrate",
     "/resume",
     "/run",
     "/skinnify",
     "/stats",
+    "/sync",
     "/thinnify",
     "/unproject",
 ];
@@ -266,8 +271,8 @@ impl Completer for G3Helper {
             return Ok((word_start, adjusted));
         }
         
-        // Case 3: Path argument for /run command
-        if line_to_cursor.starts_with("/run ") {
+        // Case 3: Path argument for /run and /import commands
+        if line_to_cursor.starts_with("/run ") || line_to_cursor.starts_with("/import ") {
             let path = self.strip_quotes(word);
             let (_, completions) = self.file_completer.complete(path, path.len(), ctx)?;
             // Cyan color for command argument completions
@@ -380,9 +385,10 @@ mod tests {
 
         let (start, matches) = helper.complete("/s", 2, &ctx).unwrap();
         assert_eq!(start, 0);
-        assert_eq!(matches.len(), 2);
+        assert_eq!(matches.len(), 3);
         assert!(matches.iter().any(|m| m.replacement == "/skinnify"));
         assert!(matches.iter().any(|m| m.replacement == "/stats"));
+        assert!(matches.iter().any(|m| m.replacement == "/sync"));
     }
 
     #[test]
@@ -201,6 +201,41 @@ pub fn print_project_heading(heading: &str) {
     println!();
 }
 
+/// Diff lines shown before the rest is summarized.
+const MAX_DIFF_LINES: usize = 60;
+
+/// Color a unified diff for the terminal: additions green, deletions red,
+/// hunk headers cyan, the rest dim. Lines past `MAX_DIFF_LINES` are counted
+/// instead of shown.
+pub fn colorize_diff(diff: &str) -> Vec<String> {
+    let lines: Vec<&str> = diff.lines().collect();
+    let mut colored: Vec<String> = lines
+        .iter()
+        .take(MAX_DIFF_LINES)
+        .map(|line| {
+            let color = if line.starts_with("+++") || line.starts_with("---") {
+                "\x1b[2m"
+            } else if line.starts_with('+') {
+                "\x1b[32m"
+            } else if line.starts_with('-') {
+                "\x1b[31m"
+            } else if line.starts_with("@@") {
+                "\x1b[36m"
+            } else {
+                "\x1b[2m"
+            };
+            format!("{}{}\x1b[0m", color, line)
+        })
+        .collect();
+    if lines.len() > MAX_DIFF_LINES {
+        colored.push(format!(
+            "\x1b[2m... {} more lines\x1b[0m",
+            lines.len() - MAX_DIFF_LINES
+        ));
+    }
+    colored
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -351,4 +386,17 @@ mod tests {
         let shortened = shorten_paths_in_command(command, Some(&workspace), Some((&project_path, "appa_estate")));
         assert_eq!(shortened, "cat appa_estate/status.md");
     }
+
+    #[test]
+    fn test_colorize_diff() {
+        let colored = colorize_diff("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-old\n+new\n");
+        assert_eq!(colored[0], "\x1b[2m--- a/x\x1b[0m");
+        assert_eq!(colored[3], "\x1b[31m-old\x1b[0m");
+        assert_eq!(colored[4], "\x1b[32m+new\x1b[0m");
+
+        let long: String = (0..100).map(|i| format!("+{}\n", i)).collect();
+        let colored = colorize_diff(&long);
+        assert_eq!(colored.len(), MAX_DIFF_LINES + 1);
+        assert!(colored[MAX_DIFF_LINES].contains("40 more lines"));
+    }
 }
@@ -282,6 +282,11 @@ pub async fn run_interactive<W: UiWriter>(
             println!("📋 {} research result(s) ready - injected into context", injected_count);
             println!();
         }
+        let verified_count = agent.inject_completed_verification();
+        if verified_count > 0 {
+            println!("🧪 {} verification result(s) ready - injected into context", verified_count);
+            println!();
+        }
 
         // Build prompt
         let prompt = build_prompt(in_multiline, agent_name, &active_project);
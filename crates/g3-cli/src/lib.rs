@@ -13,8 +13,10 @@ mod autonomous;
 mod cli_args;
 mod coach_feedback;
 mod commands;
+mod commit_links;
 mod display;
 mod interactive;
+mod otel;
 mod simple_output;
 mod task_execution;
 mod ui_writer_impl;
@@ -23,6 +25,7 @@ mod g3_status;
 mod template;
 mod completion;
 mod project;
+mod release;
 mod input_formatter;
 
 use anyhow::Result;
@@ -40,10 +43,10 @@ use accumulative::run_accumulative_mode;
 use agent_mode::run_agent_mode;
 use autonomous::run_autonomous;
 use interactive::run_interactive;
-use project_files::{combine_project_content, read_agents_config, read_include_prompt, read_workspace_memory};
+use project_files::{combine_project_content, read_agents_config, read_include_prompt, read_project_profile, read_workspace_memory};
 use simple_output::SimpleOutput;
 use ui_writer_impl::ConsoleUiWriter;
-use g3_core::ui_writer::UiWriter;
+use g3_core::ui_writer::{HeadlessUiWriter, UiWriter};
 use utils::{initialize_logging, load_config_with_cli_overrides, setup_workspace_directory};
 use template::process_template;
 use project::load_and_validate_project;
@@ -52,13 +55,37 @@ pub async fn run() -> Result<()> {
     let cli = Cli::parse();
 
     // Initialize logging FIRST (before any mode checks)
-    initialize_logging(cli.verbose);
+    // stdout carries the output of --json and the protocol of `g3 mcp` and `g3 acp`
+    let stdout_reserved = cli.json
+        || matches!(
+            cli.command,
+            Some(cli_args::Command::Mcp | cli_args::Command::Acp)
+        );
+    initialize_logging(cli.verbose, stdout_reserved);
+    // Exports the spans still buffered when g3 exits
+    let _flush_traces = otel::FlushGuard;
 
     if cli.codebase_fast_start.is_some() {
         print!("codebase_fast_start is temporarily disabled.");
         std::process::exit(1);
     }
 
+    match &cli.command {
+        Some(cli_args::Command::Release { version, yes }) => {
+            return release::run_release(&cli, version, *yes).await;
+        }
+        Some(cli_args::Command::Trace { rev }) => return commit_links::run_trace(rev),
+        Some(cli_args::Command::LinkCommit {
+            rev,
+            session,
+            install_hooks,
+        }) => return commit_links::run_link_commit(rev, session.as_deref(), *install_hooks),
+        Some(
+            cli_args::Command::Serve { .. } | cli_args::Command::Mcp | cli_args::Command::Acp,
+        )
+        | None => {}
+    }
+
     // Check if --list-agents was requested
     if cli.list_agents {
         let workspace_dir = cli.workspace.clone().unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
@@ -106,6 +133,7 @@ pub async fn run() -> Result<()> {
     let memory_content = read_workspace_memory(&workspace_dir);
     let language_content = language_prompts::get_language_prompts_for_workspace(&workspace_dir);
     let include_prompt = read_include_prompt(cli.include_prompt.as_deref());
+    let profile_content = read_project_profile(&workspace_dir);
 
     // Create project model
     let project = create_project(&cli, &workspace_dir)?;
@@ -116,9 +144,26 @@ pub async fn run() -> Result<()> {
 
     // Load configuration with CLI overrides
     let config = load_config_with_cli_overrides(&cli)?;
+    otel::enable(&config.telemetry)?;
 
     // Combine AGENTS.md and memory content
-    let combined_content = combine_project_content(agents_content, memory_content, language_content, include_prompt, &workspace_dir);
+    let combined_content = combine_project_content(agents_content, memory_content, language_content, include_prompt, profile_content, &workspace_dir);
+
+    if let Some(cli_args::Command::Serve { bind }) = &cli.command {
+        let generated_token = config.server.token.is_none();
+        let server = g3_server::Server::bind(config, combined_content, bind.as_deref()).await?;
+        println!("g3 server listening on http://{}", server.local_addr());
+        if generated_token {
+            println!("Token (set [server] token to choose one): {}", server.token());
+        }
+        return server.run().await;
+    }
+    if let Some(cli_args::Command::Mcp) = &cli.command {
+        return g3_server::serve_mcp(config, combined_content).await;
+    }
+    if let Some(cli_args::Command::Acp) = &cli.command {
+        return g3_server::serve_acp(config, combined_content).await;
+    }
 
     run_console_mode(cli, config, project, combined_content, workspace_dir).await
 }
@@ -160,25 +205,26 @@ async fn run_console_mode(
         return run_accumulative_mode(workspace_dir, cli, combined_content).await;
     }
 
+    if cli.json {
+        return run_json_task(cli, config, combined_content).await;
+    }
+
     let ui_writer = ConsoleUiWriter::new();
     ui_writer.set_workspace_path(workspace_dir.clone());
 
     let mut agent = if cli.autonomous {
-        Agent::new_autonomous_with_project_context_and_quiet(
-            config.clone(),
-            ui_writer,
-            combined_content.clone(),
-            cli.quiet,
-        )
-        .await?
+        Agent::builder(config.clone(), ui_writer)
+            .autonomous(true)
+            .project_context(combined_content.clone())
+            .quiet(cli.quiet)
+            .build()
+            .await?
     } else {
-        Agent::new_with_project_context_and_quiet(
-            config.clone(),
-            ui_writer,
-            combined_content.clone(),
-            cli.quiet,
-        )
-        .await?
+        Agent::builder(config.clone(), ui_writer)
+            .project_context(combined_content.clone())
+            .quiet(cli.quiet)
+            .build()
+            .await?
     };
 
     if cli.auto_memory {
@@ -187,6 +233,15 @@ async fn run_console_mode(
     if cli.acd {
         agent.set_acd_enabled(true);
     }
+    if cli.dry_run {
+        agent.set_dry_run(true);
+    }
+    if !config.shell.devcontainer
+        && !cli.quiet
+        && g3_execution::devcontainer::find(&workspace_dir).is_some()
+    {
+        println!("📦 This workspace has a devcontainer; run with --devcontainer to run commands inside it");
+    }
 
     // Load CLI project if --project flag was specified
     let initial_project: Option<project::Project> = if let Some(ref project_path) = cli.project {
@@ -259,3 +314,35 @@ async fn run_console_mode(
         .await
     }
 }
+
+/// Single-shot mode with `--json`: the agent's events go to stdout as JSON
+/// lines, the turn ending with a `turn_telemetry` event.
+async fn run_json_task(cli: Cli, config: Config, combined_content: Option<String>) -> Result<()> {
+    let task = process_template(cli.task.as_deref().unwrap_or_default());
+    let mut agent = Agent::builder(config, HeadlessUiWriter::new().full_output(false))
+        .project_context(combined_content)
+        .quiet(cli.quiet)
+        .build()
+        .await?;
+    if cli.acd {
+        agent.set_acd_enabled(true);
+    }
+    if cli.dry_run {
+        agent.set_dry_run(true);
+    }
+
+    let printer = tokio::spawn(g3_core::events::write_json_lines(
+        agent.subscribe_events(),
+        std::io::stdout(),
+    ));
+    let result = agent
+        .execute_task_with_timing(&task, None, false, cli.show_prompt, cli.show_code, false, None)
+        .await;
+    if let Ok(result) = &result {
+        agent.save_session_continuation(Some(result.response.clone()));
+    }
+    // Dropping the agent closes the event bus, which ends the printer
+    drop(agent);
+    printer.await??;
+    result.map(|_| ())
+}
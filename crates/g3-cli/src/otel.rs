@@ -0,0 +1,159 @@
+//! Export of the agent's spans to an OTLP/HTTP collector, when
+//! `[telemetry] otlp_endpoint` is set.
+//!
+//! Logging starts before the configuration is loaded, so `layer()` is
+//! installed with it, empty, and `enable` fills it in once the configuration
+//! is known. The layer is set at most once and never replaced, which keeps
+//! the `tracing-opentelemetry` layer reachable by downcasting (needed to
+//! place tasks in their session's trace).
+
+use anyhow::Result;
+use g3_config::TelemetryConfig;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use std::any::TypeId;
+use std::sync::OnceLock;
+use tracing::span::{Attributes, Id, Record};
+use tracing::Event;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::Registry;
+
+type OtelLayer = OpenTelemetryLayer<Registry, SdkTracer>;
+
+static LAYER: OnceLock<OtelLayer> = OnceLock::new();
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// The layer exporting spans once `enable` has been called. Must be the
+/// first layer on the `Registry`.
+pub struct DeferredOtelLayer;
+
+pub fn layer() -> DeferredOtelLayer {
+    DeferredOtelLayer
+}
+
+/// Start exporting spans if the configuration has an OTLP endpoint.
+pub fn enable(config: &TelemetryConfig) -> Result<()> {
+    let Some(endpoint) = &config.otlp_endpoint else {
+        return Ok(());
+    };
+    if PROVIDER.get().is_some() {
+        return Ok(());
+    }
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(traces_url(endpoint))
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build();
+    let _ = LAYER.set(tracing_opentelemetry::layer().with_tracer(provider.tracer("g3")));
+    let _ = PROVIDER.set(provider);
+    Ok(())
+}
+
+/// Flushes the spans not exported yet when dropped, at exit.
+pub struct FlushGuard;
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = PROVIDER.get() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Warning: Could not export the last traces: {}", e);
+            }
+        }
+    }
+}
+
+/// The collector's URL for traces, given its base URL.
+fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/traces", endpoint)
+    }
+}
+
+impl Layer<Registry> for DeferredOtelLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, Registry>) {
+        if let Some(layer) = LAYER.get() {
+            layer.on_new_span(attrs, id, ctx);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, Registry>) {
+        if let Some(layer) = LAYER.get() {
+            layer.on_record(id, values, ctx);
+        }
+    }
+
+    fn on_follows_from(&self, id: &Id, follows: &Id, ctx: Context<'_, Registry>) {
+        if let Some(layer) = LAYER.get() {
+            layer.on_follows_from(id, follows, ctx);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, Registry>) {
+        if let Some(layer) = LAYER.get() {
+            layer.on_event(event, ctx);
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, Registry>) {
+        if let Some(layer) = LAYER.get() {
+            layer.on_enter(id, ctx);
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, Registry>) {
+        if let Some(layer) = LAYER.get() {
+            layer.on_exit(id, ctx);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, Registry>) {
+        if let Some(layer) = LAYER.get() {
+            layer.on_close(id, ctx);
+        }
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            return Some(self as *const Self as *const ());
+        }
+        // The layer lives in a static that is never replaced, so pointers
+        // into it stay valid
+        LAYER
+            .get()
+            .and_then(|layer| unsafe { layer.downcast_raw(id) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traces_url() {
+        assert_eq!(
+            traces_url("http://localhost:4318"),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_url("http://localhost:4318/"),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_url("https://otel.example.com/v1/traces"),
+            "https://otel.example.com/v1/traces"
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! Project file reading utilities.
 //!
-//! Reads AGENTS.md and workspace memory files from the workspace.
+//! Reads AGENTS.md, the project profile and workspace memory files from the workspace.
 
 use std::path::Path;
 use tracing::error;
@@ -53,6 +53,22 @@ pub fn read_workspace_memory(workspace_dir: &Path) -> Option<String> {
     }
 }
 
+/// Read the project profile (.g3/profile.json) saved by discovery, refreshing it first.
+/// Returns the compact profile summary, or None if discovery hasn't saved one yet.
+pub fn read_project_profile(workspace_dir: &Path) -> Option<String> {
+    g3_planner::ProjectProfile::load(workspace_dir)?;
+
+    // Incremental: only rewritten when the detected facts changed
+    let profile = match g3_planner::refresh_profile(workspace_dir) {
+        Ok((profile, _)) => profile,
+        Err(e) => {
+            error!("Failed to refresh project profile: {}", e);
+            g3_planner::ProjectProfile::load(workspace_dir)?
+        }
+    };
+    Some(profile.to_context())
+}
+
 /// Read include prompt content from a specified file path.
 /// Returns formatted content with emoji prefix, or None if path is None or file doesn't exist.
 pub fn read_include_prompt(path: Option<&std::path::Path>) -> Option<String> {
@@ -80,21 +96,22 @@ pub fn read_include_prompt(path: Option<&std::path::Path>) -> Option<String> {
 /// Returns None if all inputs are None, otherwise joins non-None parts with double newlines.
 /// Prepends the current working directory to help the LLM avoid path hallucinations.
 /// 
-/// Order: Working Directory → AGENTS.md → Language prompts → Include prompt → Memory
+/// Order: Working Directory → AGENTS.md → Language prompts → Include prompt → Project profile → Memory
 pub fn combine_project_content(
     agents_content: Option<String>,
     memory_content: Option<String>,
     language_content: Option<String>,
     include_prompt: Option<String>,
+    profile_content: Option<String>,
     workspace_dir: &Path,
 ) -> Option<String> {
     // Always include working directory to prevent LLM from hallucinating paths
     let cwd_info = format!("📂 Working Directory: {}", workspace_dir.display());
     
-    // Order: cwd → agents → language → include_prompt → memory
+    // Order: cwd → agents → language → include_prompt → profile → memory
     // Include prompt comes BEFORE memory so memory is always last (most recent context)
     let parts: Vec<String> = [
-        Some(cwd_info), agents_content, language_content, include_prompt, memory_content
+        Some(cwd_info), agents_content, language_content, include_prompt, profile_content, memory_content
     ]
         .into_iter()
         .flatten()
@@ -125,7 +142,7 @@ pub fn extract_project_heading(project_context: &str) -> Option<String> {
         let trimmed = line.trim();
         
         // Skip emoji prefix lines
-        if trimmed.starts_with("📂") || trimmed.starts_with("🤖") || trimmed.starts_with("🔧") || trimmed.starts_with("📎") || trimmed.starts_with("===") {
+        if trimmed.starts_with("📂") || trimmed.starts_with("🤖") || trimmed.starts_with("🔧") || trimmed.starts_with("📎") || trimmed.starts_with("🧭") || trimmed.starts_with("===") {
             continue;
         }
         
@@ -150,6 +167,7 @@ fn find_fallback_title(content: &str) -> Option<String> {
             && !trimmed.starts_with("📂")
             && !trimmed.starts_with("🤖")
             && !trimmed.starts_with("🔧")
+            && !trimmed.starts_with("🧭")
             && !trimmed.starts_with('#')
             && !trimmed.starts_with("==")
             && !trimmed.starts_with("--")
@@ -219,6 +237,7 @@ mod tests {
             Some("memory".to_string()),
             Some("language".to_string()),
             None, // include_prompt
+            None, // profile
             &workspace,
         );
         assert!(result.is_some());
@@ -232,7 +251,7 @@ mod tests {
     #[test]
     fn test_combine_project_content_partial() {
         let workspace = std::path::PathBuf::from("/test/workspace");
-        let result = combine_project_content(None, Some("memory".to_string()), None, None, &workspace);
+        let result = combine_project_content(None, Some("memory".to_string()), None, None, None, &workspace);
         assert!(result.is_some());
         let content = result.unwrap();
         assert!(content.contains("📂 Working Directory: /test/workspace"));
@@ -242,7 +261,7 @@ mod tests {
     #[test]
     fn test_combine_project_content_all_none() {
         let workspace = std::path::PathBuf::from("/test/workspace");
-        let result = combine_project_content(None, None, None, None, &workspace);
+        let result = combine_project_content(None, None, None, None, None, &workspace);
         // Now always returns Some because we always include the working directory
         assert!(result.is_some());
         assert!(result.unwrap().contains("📂 Working Directory: /test/workspace"));
@@ -256,6 +275,7 @@ mod tests {
             Some("memory".to_string()),
             Some("language".to_string()),
             Some("include_prompt".to_string()),
+            None, // profile
             &workspace,
         );
         assert!(result.is_some());
@@ -265,13 +285,14 @@ mod tests {
 
     #[test]
     fn test_combine_project_content_order() {
-        // Verify correct ordering: agents < language < include_prompt < memory
+        // Verify correct ordering: agents < language < include_prompt < profile < memory
         let workspace = std::path::PathBuf::from("/test/workspace");
         let result = combine_project_content(
             Some("AGENTS_CONTENT".to_string()),
             Some("MEMORY_CONTENT".to_string()),
             Some("LANGUAGE_CONTENT".to_string()),
             Some("INCLUDE_PROMPT_CONTENT".to_string()),
+            Some("PROFILE_CONTENT".to_string()),
             &workspace,
         );
         let content = result.unwrap();
@@ -280,12 +301,14 @@ mod tests {
         let agents_pos = content.find("AGENTS_CONTENT").expect("agents not found");
         let language_pos = content.find("LANGUAGE_CONTENT").expect("language not found");
         let include_pos = content.find("INCLUDE_PROMPT_CONTENT").expect("include_prompt not found");
+        let profile_pos = content.find("PROFILE_CONTENT").expect("profile not found");
         let memory_pos = content.find("MEMORY_CONTENT").expect("memory not found");
         
-        // Verify order: agents < language < include_prompt < memory
+        // Verify order: agents < language < include_prompt < profile < memory
         assert!(agents_pos < language_pos, "agents should come before language");
         assert!(language_pos < include_pos, "language should come before include_prompt");
-        assert!(include_pos < memory_pos, "include_prompt should come before memory");
+        assert!(include_pos < profile_pos, "include_prompt should come before profile");
+        assert!(profile_pos < memory_pos, "profile should come before memory");
     }
 
     #[test]
@@ -297,6 +320,7 @@ mod tests {
             Some("MEMORY".to_string()),
             Some("LANGUAGE".to_string()),
             None, // no include_prompt
+            None, // profile
             &workspace,
         );
         let content = result.unwrap();
@@ -0,0 +1,399 @@
+//! Release mode for G3 CLI - `g3 release`.
+//!
+//! Walks through a release one step at a time: version bump (done by the
+//! agent), changelog promotion, release commit and tag, and a publish dry-run.
+//! Every step asks for approval first; declining stops the release. `--yes`
+//! skips the prompts, except for commands the `[approval]` policy wouldn't
+//! run unasked.
+
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use g3_core::approval;
+use g3_core::tools::changelog::promote_unreleased;
+use g3_core::ui_writer::UiWriter;
+use g3_core::Agent;
+
+use crate::cli_args::Cli;
+use crate::project_files::{
+    combine_project_content, read_agents_config, read_project_profile, read_workspace_memory,
+};
+use crate::simple_output::SimpleOutput;
+use crate::ui_writer_impl::ConsoleUiWriter;
+use crate::utils::load_config_with_cli_overrides;
+
+/// Package ecosystem being released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ecosystem {
+    Cargo,
+    Npm,
+}
+
+impl Ecosystem {
+    fn detect(workspace: &Path) -> Option<Self> {
+        if workspace.join("Cargo.toml").exists() {
+            Some(Ecosystem::Cargo)
+        } else if workspace.join("package.json").exists() {
+            Some(Ecosystem::Npm)
+        } else {
+            None
+        }
+    }
+
+    fn manifest(&self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "Cargo.toml",
+            Ecosystem::Npm => "package.json",
+        }
+    }
+
+    fn publish_dry_run(&self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "cargo publish --dry-run",
+            Ecosystem::Npm => "npm pack --dry-run",
+        }
+    }
+}
+
+/// Run `g3 release`.
+///
+/// `version` is either an explicit version (`1.4.0`) or a bump level
+/// (`major`, `minor`, `patch`). With `yes`, approval prompts are skipped.
+pub async fn run_release(cli: &Cli, version: &str, yes: bool) -> Result<()> {
+    let output = SimpleOutput::new();
+    let workspace_dir = cli
+        .workspace
+        .clone()
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+    std::env::set_current_dir(&workspace_dir)?;
+
+    let ecosystem = Ecosystem::detect(&workspace_dir)
+        .ok_or_else(|| anyhow!("No Cargo.toml or package.json found in {}", workspace_dir.display()))?;
+    let manifest = std::fs::read_to_string(workspace_dir.join(ecosystem.manifest()))?;
+    let current = read_version(ecosystem, &manifest)
+        .ok_or_else(|| anyhow!("Could not find a version in {}", ecosystem.manifest()))?;
+    let next = next_version(&current, version)?;
+    let tag = format!("v{}", next);
+
+    output.print("g3 programming agent - release mode");
+    output.print(&format!("📁 Using workspace: {}", workspace_dir.display()));
+    output.print(&format!("🏷️  Releasing {} → {} ({})", current, next, tag));
+
+    if git(&["rev-parse", "-q", "--verify", &format!("refs/tags/{}", tag)])
+        .await
+        .is_ok()
+    {
+        return Err(anyhow!("Tag {} already exists", tag));
+    }
+
+    let config = load_config_with_cli_overrides(cli)?;
+    let combined_content = combine_project_content(
+        read_agents_config(&workspace_dir),
+        read_workspace_memory(&workspace_dir),
+        None,
+        None,
+        read_project_profile(&workspace_dir),
+        &workspace_dir,
+    );
+    let mut agent = Agent::builder(config, ConsoleUiWriter::new())
+        .project_context(combined_content)
+        .quiet(cli.quiet)
+        .build()
+        .await?;
+
+    // Only the files the release steps change go into the release commit
+    let repo_root = PathBuf::from(git(&["rev-parse", "--show-toplevel"]).await?.trim());
+    let dirty_before = dirty_files(&repo_root).await;
+    if !dirty_before.is_empty()
+        && !approve(
+            &agent,
+            yes,
+            "⚠️ The working tree has uncommitted changes; files the release doesn't change are left out of the release commit. Continue?",
+            &[],
+        )
+    {
+        return stopped(&output);
+    }
+
+    // Step 1: version bump (manifests and lockfiles vary too much to script)
+    if !approve(
+        &agent,
+        yes,
+        &format!("🚦 Step 1/4: bump the version from {} to {}?", current, next),
+        &[],
+    ) {
+        return stopped(&output);
+    }
+    let bump_prompt = format!(
+        "Release preparation: bump the project version from {} to {}. Update every manifest that declares it ({} and any workspace members or internal dependency requirements that pin it), then refresh the lockfile. Do not commit, tag, or publish anything.",
+        current,
+        next,
+        ecosystem.manifest()
+    );
+    agent
+        .execute_task_with_timing(&bump_prompt, None, false, cli.show_prompt, cli.show_code, true, None)
+        .await?;
+    let bumped = std::fs::read_to_string(workspace_dir.join(ecosystem.manifest()))
+        .ok()
+        .and_then(|m| read_version(ecosystem, &m));
+    if bumped.as_deref() != Some(next.as_str()) {
+        return Err(anyhow!(
+            "{} still declares version {}; expected {}",
+            ecosystem.manifest(),
+            bumped.unwrap_or_default(),
+            next
+        ));
+    }
+    output.print(&format!("✅ Version bumped to {}", next));
+
+    // Step 2: changelog
+    let changelog_path = workspace_dir.join(&agent.get_config().changelog.path);
+    if !approve(
+        &agent,
+        yes,
+        &format!(
+            "🚦 Step 2/4: move the [Unreleased] entries in {} under [{}]?",
+            agent.get_config().changelog.path,
+            next
+        ),
+        &[],
+    ) {
+        return stopped(&output);
+    }
+    release_changelog(&mut agent, cli, &changelog_path, &current, &next).await?;
+    output.print(&format!("✅ Changelog updated for {}", next));
+
+    // Step 3: commit and tag
+    let release_files: Vec<String> = dirty_files(&repo_root)
+        .await
+        .into_iter()
+        .filter(|(path, digest)| dirty_before.get(path) != Some(digest))
+        .map(|(path, _)| path)
+        .collect();
+    if release_files.is_empty() {
+        return Err(anyhow!("The release steps didn't change any files"));
+    }
+    let message = format!("Release {}", tag);
+    let pathspecs: Vec<String> = release_files.iter().map(|f| format!(":(top){}", f)).collect();
+    let commit_command = format!("git commit -m '{}' -- {}", message, release_files.join(" "));
+    let tag_command = format!("git tag -a {} -m '{}'", tag, message);
+    if !approve(
+        &agent,
+        yes,
+        &format!(
+            "🚦 Step 3/4: commit \"{}\" with {} and create tag {}?",
+            message,
+            release_files.join(", "),
+            tag
+        ),
+        &[&commit_command, &tag_command],
+    ) {
+        return stopped(&output);
+    }
+    let mut add = vec!["add", "--"];
+    add.extend(pathspecs.iter().map(String::as_str));
+    git(&add).await?;
+    let mut commit = vec!["commit", "-m", &message, "--"];
+    commit.extend(pathspecs.iter().map(String::as_str));
+    git(&commit).await?;
+    git(&["tag", "-a", &tag, "-m", &message]).await?;
+    output.print(&format!("✅ Created release commit and tag {}", tag));
+
+    // Step 4: publish dry-run
+    if !approve(
+        &agent,
+        yes,
+        &format!("🚦 Step 4/4: run `{}`?", ecosystem.publish_dry_run()),
+        &[ecosystem.publish_dry_run()],
+    ) {
+        return stopped(&output);
+    }
+    let (ok, log) = run_shell(ecosystem.publish_dry_run()).await?;
+    output.print(&log);
+    if !ok {
+        return Err(anyhow!(
+            "Publish dry-run failed. The commit and tag {} are local only; fix the issue and amend, or delete the tag with `git tag -d {}`",
+            tag,
+            tag
+        ));
+    }
+
+    output.print(&format!(
+        "🎉 Release {} is ready. Push it with `git push && git push origin {}`, then publish.",
+        tag, tag
+    ));
+    Ok(())
+}
+
+/// Promote the [Unreleased] changelog section, asking the agent to fill it
+/// from the git history first if it is empty.
+async fn release_changelog(
+    agent: &mut Agent<ConsoleUiWriter>,
+    cli: &Cli,
+    path: &Path,
+    current: &str,
+    next: &str,
+) -> Result<()> {
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let existing = std::fs::read_to_string(path).unwrap_or_default();
+    if let Some(updated) = promote_unreleased(&existing, next, &date) {
+        std::fs::write(path, updated)?;
+        return Ok(());
+    }
+
+    let range = match git(&["describe", "--tags", "--abbrev=0"]).await {
+        Ok(last_tag) => format!("{}..HEAD", last_tag.trim()),
+        Err(_) => "HEAD".to_string(),
+    };
+    let prompt = format!(
+        "The changelog has no unreleased entries for the {} → {} release. Read `git log {}` and record the user-visible changes with the `changelog` tool. Do not edit any other files.",
+        current, next, range
+    );
+    agent
+        .execute_task_with_timing(&prompt, None, false, cli.show_prompt, cli.show_code, true, None)
+        .await?;
+
+    let existing = std::fs::read_to_string(path).unwrap_or_default();
+    let updated = promote_unreleased(&existing, next, &date)
+        .ok_or_else(|| anyhow!("{} still has no [Unreleased] entries", path.display()))?;
+    std::fs::write(path, updated)?;
+    Ok(())
+}
+
+/// Ask for approval of a release step that runs `commands`. With `--yes`
+/// the step is approved without asking if the `[approval]` policy lets all
+/// of its commands run unasked.
+fn approve(agent: &Agent<ConsoleUiWriter>, yes: bool, message: &str, commands: &[&str]) -> bool {
+    let workspace = std::env::current_dir().unwrap_or_default();
+    let level = agent.get_config().approval.auto_approve;
+    let allowed = commands
+        .iter()
+        .filter_map(|command| approval::assess_command(command, &workspace, &workspace))
+        .all(|risk| risk.auto_approved(level));
+    (yes && allowed) || agent.ui_writer().prompt_user_yes_no(message)
+}
+
+/// Files that differ from HEAD, keyed by their path from the repository
+/// root, with a digest of their content (None once deleted).
+async fn dirty_files(repo_root: &Path) -> BTreeMap<String, Option<String>> {
+    let mut files = BTreeMap::new();
+    for path in g3_core::utils::list_changed_files(repo_root.to_str()).await {
+        let digest = std::fs::read(repo_root.join(&path))
+            .ok()
+            .map(|content| format!("{:x}", Sha256::digest(&content)));
+        files.insert(path, digest);
+    }
+    files
+}
+
+fn stopped(output: &SimpleOutput) -> Result<()> {
+    output.print("🛑 Release stopped. Steps already completed are left in place.");
+    Ok(())
+}
+
+/// Run a git command, returning stdout or an error with stderr.
+async fn git(args: &[&str]) -> Result<String> {
+    let out = Command::new("git").args(args).output().await?;
+    if out.status.success() {
+        Ok(String::from_utf8_lossy(&out.stdout).to_string())
+    } else {
+        Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&out.stderr).trim()
+        ))
+    }
+}
+
+/// Run a shell command, returning whether it succeeded and its combined output.
+async fn run_shell(command: &str) -> Result<(bool, String)> {
+    let out = Command::new("sh").arg("-c").arg(command).output().await?;
+    let log = format!(
+        "{}{}",
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr)
+    );
+    Ok((out.status.success(), log))
+}
+
+/// Read the package version from a manifest.
+fn read_version(ecosystem: Ecosystem, manifest: &str) -> Option<String> {
+    match ecosystem {
+        Ecosystem::Npm => serde_json::from_str::<serde_json::Value>(manifest)
+            .ok()?
+            .get("version")?
+            .as_str()
+            .map(String::from),
+        Ecosystem::Cargo => {
+            let mut in_package = false;
+            for line in manifest.lines() {
+                let line = line.trim();
+                if line.starts_with('[') {
+                    in_package = line == "[package]" || line == "[workspace.package]";
+                } else if in_package {
+                    let value = line
+                        .strip_prefix("version")
+                        .and_then(|rest| rest.trim_start().strip_prefix('='));
+                    if let Some(value) = value {
+                        return Some(value.trim().trim_matches('"').to_string());
+                    }
+                }
+            }
+            None
+        }
+    }
+}
+
+/// Resolve the release version from the current one and a bump level or
+/// explicit version.
+fn next_version(current: &str, requested: &str) -> Result<String> {
+    let parse = |v: &str| -> Option<(u64, u64, u64)> {
+        let core = v.trim_start_matches('v').split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+        Some((parts.next()??, parts.next()??, parts.next()??))
+    };
+
+    let (major, minor, patch) =
+        parse(current).ok_or_else(|| anyhow!("Current version {} is not semver", current))?;
+    let next = match requested {
+        "major" => format!("{}.0.0", major + 1),
+        "minor" => format!("{}.{}.0", major, minor + 1),
+        "patch" => format!("{}.{}.{}", major, minor, patch + 1),
+        explicit => {
+            parse(explicit)
+                .ok_or_else(|| anyhow!("'{}' is not a version or major/minor/patch", explicit))?;
+            explicit.trim_start_matches('v').to_string()
+        }
+    };
+    Ok(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_version() {
+        assert_eq!(next_version("0.3.1", "patch").unwrap(), "0.3.2");
+        assert_eq!(next_version("0.3.1", "minor").unwrap(), "0.4.0");
+        assert_eq!(next_version("0.3.1", "major").unwrap(), "1.0.0");
+        assert_eq!(next_version("1.0.0-beta.1", "patch").unwrap(), "1.0.1");
+        assert_eq!(next_version("0.3.1", "v2.0.0").unwrap(), "2.0.0");
+        assert!(next_version("0.3.1", "bogus").is_err());
+    }
+
+    #[test]
+    fn test_read_version() {
+        let cargo = "[workspace]\nmembers = [\"a\"]\n\n[workspace.package]\nversion = \"0.5.0\"\n";
+        assert_eq!(read_version(Ecosystem::Cargo, cargo).as_deref(), Some("0.5.0"));
+
+        let cargo = "[dependencies]\nversion = \"9\"\n\n[package]\nname = \"x\"\nversion.workspace = true\nversion = \"1.2.3\"\n";
+        assert_eq!(read_version(Ecosystem::Cargo, cargo).as_deref(), Some("1.2.3"));
+
+        let npm = r#"{"name": "x", "version": "4.5.6"}"#;
+        assert_eq!(read_version(Ecosystem::Npm, npm).as_deref(), Some("4.5.6"));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::filter_json::{filter_json_tool_calls, reset_json_tool_state, ToolParsingHint};
-use crate::display::{shorten_path, shorten_paths_in_command};
+use crate::display::{colorize_diff, shorten_path, shorten_paths_in_command};
 use crate::streaming_markdown::StreamingMarkdownFormatter;
 use g3_core::ui_writer::UiWriter;
 use std::io::{self, Write};
@@ -689,6 +689,15 @@ impl UiWriter for ConsoleUiWriter {
         true
     }
 
+    fn print_diff(&self, _file_path: &str, diff: &str) {
+        // Clear any streaming hint so the diff starts on its own line
+        self.hint_state.handle_hint(ToolParsingHint::Complete);
+        for line in colorize_diff(diff) {
+            println!("   {}", line);
+        }
+        let _ = io::stdout().flush();
+    }
+
     fn print_tool_timing(&self, duration_str: &str, tokens_delta: u32, context_percentage: f32) {
         let color_code = duration_color(duration_str);
 
@@ -136,6 +136,14 @@ pub fn load_config_with_cli_overrides(cli: &Cli) -> Result<Config> {
         config.agent.auto_compact = false;
     }
 
+    if let Some(level) = cli.auto_approve {
+        config.approval.auto_approve = level;
+    }
+
+    if cli.devcontainer {
+        config.shell.devcontainer = true;
+    }
+
     // Validate provider if specified
     if let Some(ref provider) = cli.provider {
         let valid_providers = ["anthropic", "databricks", "embedded", "gemini", "openai"];
@@ -152,8 +160,9 @@ pub fn load_config_with_cli_overrides(cli: &Cli) -> Result<Config> {
     Ok(config)
 }
 
-/// Initialize logging based on CLI verbosity settings.
-pub fn initialize_logging(verbose: bool) {
+/// Initialize logging based on CLI verbosity settings, to stderr if `to_stderr`.
+pub fn initialize_logging(verbose: bool, to_stderr: bool) {
+    use tracing_subscriber::fmt::writer::BoxMakeWriter;
     use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
     let filter = if verbose {
@@ -174,8 +183,16 @@ pub fn initialize_logging(verbose: bool) {
             .add_directive("llama=off".parse().unwrap())
     };
 
+    // Keep stdout clean when it carries JSON output
+    let writer = if to_stderr {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+    // Spans are exported from here once the configuration enables it
     let _ = tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
+        .with(crate::otel::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(writer))
         .with(filter)
         .try_init();
 }
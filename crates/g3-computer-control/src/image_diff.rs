@@ -0,0 +1,243 @@
+//! Screenshot comparison.
+//!
+//! Two images of the same size are compared pixel by pixel. A pixel counts
+//! as changed when any channel differs, and as perceptibly changed when its
+//! colour difference (in YIQ space, as used by pixelmatch) exceeds the
+//! threshold, so anti-aliasing and compression noise don't fail UI checks.
+//! The diff image shows the first image faded to grey with perceptible
+//! changes in red and sub-threshold changes in yellow.
+
+use anyhow::{bail, Context, Result};
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::types::Rect;
+
+/// Largest possible YIQ colour delta, between black and white.
+const MAX_DELTA: f64 = 35215.0;
+
+const CHANGED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const MINOR: Rgba<u8> = Rgba([255, 255, 0, 255]);
+
+/// How two images are compared.
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// Perceptual threshold from 0 (any change) to 1 (nothing counts)
+    pub threshold: f64,
+    /// Regions left out of the comparison, such as clocks or spinners
+    pub ignore: Vec<Rect>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.1,
+            ignore: Vec::new(),
+        }
+    }
+}
+
+/// The result of comparing two images.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageDiff {
+    pub width: u32,
+    pub height: u32,
+    /// Pixels compared, excluding ignored regions
+    pub compared_pixels: u64,
+    /// Pixels with any channel different
+    pub changed_pixels: u64,
+    /// Pixels whose colour difference exceeds the threshold
+    pub perceptual_pixels: u64,
+    pub pixel_percent: f64,
+    pub perceptual_percent: f64,
+    /// Bounding box of the perceptible changes
+    pub changed_region: Option<Rect>,
+    pub diff_path: Option<PathBuf>,
+}
+
+/// Compare two image files, writing the diff image to `diff_path` if given.
+pub fn diff_files(
+    before: &Path,
+    after: &Path,
+    diff_path: Option<&Path>,
+    options: &DiffOptions,
+) -> Result<ImageDiff> {
+    let before = image::open(before)
+        .with_context(|| format!("Failed to open {}", before.display()))?
+        .to_rgba8();
+    let after = image::open(after)
+        .with_context(|| format!("Failed to open {}", after.display()))?
+        .to_rgba8();
+    let (mut diff, image) = diff_images(&before, &after, options)?;
+    if let Some(path) = diff_path {
+        image
+            .save(path)
+            .with_context(|| format!("Failed to save diff image to {}", path.display()))?;
+        diff.diff_path = Some(path.to_path_buf());
+    }
+    Ok(diff)
+}
+
+/// Compare two images of the same size, returning the counts and the diff image.
+pub fn diff_images(
+    before: &RgbaImage,
+    after: &RgbaImage,
+    options: &DiffOptions,
+) -> Result<(ImageDiff, RgbaImage)> {
+    if before.dimensions() != after.dimensions() {
+        bail!(
+            "Images have different sizes: {}x{} and {}x{}",
+            before.width(),
+            before.height(),
+            after.width(),
+            after.height()
+        );
+    }
+    let (width, height) = before.dimensions();
+    let max_delta = MAX_DELTA * options.threshold * options.threshold;
+
+    let mut image = RgbaImage::new(width, height);
+    let mut compared = 0u64;
+    let mut changed = 0u64;
+    let mut perceptual = 0u64;
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (x, y, a) in before.enumerate_pixels() {
+        let b = after.get_pixel(x, y);
+        let faded = faded(a);
+        if options.ignore.iter().any(|r| contains(r, x, y)) {
+            image.put_pixel(x, y, faded);
+            continue;
+        }
+        compared += 1;
+        if a == b {
+            image.put_pixel(x, y, faded);
+            continue;
+        }
+        changed += 1;
+        if color_delta(a, b) > max_delta {
+            perceptual += 1;
+            bounds = Some(match bounds {
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                None => (x, y, x, y),
+            });
+            image.put_pixel(x, y, CHANGED);
+        } else {
+            image.put_pixel(x, y, MINOR);
+        }
+    }
+
+    let percent = |n: u64| {
+        if compared == 0 {
+            0.0
+        } else {
+            n as f64 * 100.0 / compared as f64
+        }
+    };
+    let diff = ImageDiff {
+        width,
+        height,
+        compared_pixels: compared,
+        changed_pixels: changed,
+        perceptual_pixels: perceptual,
+        pixel_percent: percent(changed),
+        perceptual_percent: percent(perceptual),
+        changed_region: bounds.map(|(x0, y0, x1, y1)| Rect {
+            x: x0 as i32,
+            y: y0 as i32,
+            width: (x1 - x0 + 1) as i32,
+            height: (y1 - y0 + 1) as i32,
+        }),
+        diff_path: None,
+    };
+    Ok((diff, image))
+}
+
+fn contains(rect: &Rect, x: u32, y: u32) -> bool {
+    let (x, y) = (x as i64, y as i64);
+    let (rx, ry) = (rect.x as i64, rect.y as i64);
+    x >= rx && y >= ry && x < rx + rect.width as i64 && y < ry + rect.height as i64
+}
+
+/// Squared YIQ distance between two pixels, each blended onto white.
+fn color_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> f64 {
+    let (r1, g1, b1) = blend_white(a);
+    let (r2, g2, b2) = blend_white(b);
+    let y = rgb_to_y(r1, g1, b1) - rgb_to_y(r2, g2, b2);
+    let i = rgb_to_i(r1, g1, b1) - rgb_to_i(r2, g2, b2);
+    let q = rgb_to_q(r1, g1, b1) - rgb_to_q(r2, g2, b2);
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+fn blend_white(pixel: &Rgba<u8>) -> (f64, f64, f64) {
+    let [r, g, b, a] = pixel.0;
+    let alpha = a as f64 / 255.0;
+    let blend = |c: u8| 255.0 + (c as f64 - 255.0) * alpha;
+    (blend(r), blend(g), blend(b))
+}
+
+fn rgb_to_y(r: f64, g: f64, b: f64) -> f64 {
+    r * 0.29889531 + g * 0.58662247 + b * 0.11448223
+}
+
+fn rgb_to_i(r: f64, g: f64, b: f64) -> f64 {
+    r * 0.59597799 - g * 0.27417610 - b * 0.32180189
+}
+
+fn rgb_to_q(r: f64, g: f64, b: f64) -> f64 {
+    r * 0.21147017 - g * 0.52261711 + b * 0.31114694
+}
+
+/// An unchanged pixel in the diff image: its brightness, faded towards white.
+fn faded(pixel: &Rgba<u8>) -> Rgba<u8> {
+    let (r, g, b) = blend_white(pixel);
+    let gray = rgb_to_y(r, g, b);
+    let value = (255.0 + (gray - 255.0) * 0.1) as u8;
+    Rgba([value, value, value, 255])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_counts_changes_and_ignores_regions() {
+        let before = RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]));
+        let mut after = before.clone();
+        // A perceptible change in a 2x2 block, an imperceptible one elsewhere
+        for (x, y) in [(2, 3), (3, 3), (2, 4), (3, 4)] {
+            after.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+        }
+        after.put_pixel(8, 8, Rgba([254, 255, 255, 255]));
+
+        let (diff, image) = diff_images(&before, &after, &DiffOptions::default()).unwrap();
+        assert_eq!(diff.compared_pixels, 100);
+        assert_eq!(diff.changed_pixels, 5);
+        assert_eq!(diff.perceptual_pixels, 4);
+        assert!((diff.perceptual_percent - 4.0).abs() < 1e-9);
+        let region = diff.changed_region.unwrap();
+        assert_eq!(
+            (region.x, region.y, region.width, region.height),
+            (2, 3, 2, 2)
+        );
+        assert_eq!(*image.get_pixel(2, 3), CHANGED);
+        assert_eq!(*image.get_pixel(8, 8), MINOR);
+
+        let options = DiffOptions {
+            ignore: vec![Rect {
+                x: 0,
+                y: 0,
+                width: 5,
+                height: 5,
+            }],
+            ..DiffOptions::default()
+        };
+        let (diff, _) = diff_images(&before, &after, &options).unwrap();
+        assert_eq!(diff.compared_pixels, 75);
+        assert_eq!(diff.perceptual_pixels, 0);
+        assert!(diff.changed_region.is_none());
+
+        let smaller = RgbaImage::new(5, 5);
+        assert!(diff_images(&before, &smaller, &DiffOptions::default()).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 // Suppress warnings from objc crate macros
 #![allow(unexpected_cfgs)]
 
+pub mod image_diff;
 pub mod platform;
 pub mod types;
 pub mod webdriver;
@@ -0,0 +1,238 @@
+//! Fluent construction of `Agent`.
+//!
+//! `Agent::builder(config, ui_writer)` returns an `AgentBuilder`; every
+//! construction option (mode, project context, system prompt, providers, tool
+//! policy, ...) is a builder method, and `build()` does the async setup.
+//! New options should be added here rather than as new `Agent` constructors.
+
+use anyhow::Result;
+use g3_config::Config;
+use g3_providers::{Message, MessageRole, ProviderRegistry};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::context_window::ContextWindow;
+use crate::prompts::{get_system_prompt_for_native, get_system_prompt_for_non_native};
+use crate::ui_writer::UiWriter;
+use crate::{mcp_client, provider_registration, Agent};
+
+/// Which tools an agent may use.
+///
+/// Disallowed tools are left out of the tool definitions sent to native
+/// tool-calling providers, and calls to them are refused with an error result.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ToolPolicy {
+    /// Every tool enabled by the configuration (the default)
+    #[default]
+    AllowAll,
+    /// Only the named tools
+    AllowOnly(Vec<String>),
+    /// Every tool except the named ones
+    Deny(Vec<String>),
+}
+
+impl ToolPolicy {
+    /// Whether `tool` may be used under this policy.
+    pub fn allows(&self, tool: &str) -> bool {
+        match self {
+            Self::AllowAll => true,
+            Self::AllowOnly(allowed) => allowed.iter().any(|t| t == tool),
+            Self::Deny(denied) => !denied.iter().any(|t| t == tool),
+        }
+    }
+}
+
+/// Builder for `Agent`. Created with `Agent::builder`.
+pub struct AgentBuilder<W: UiWriter> {
+    config: Config,
+    ui_writer: W,
+    autonomous: bool,
+    quiet: bool,
+    project_context: Option<String>,
+    system_prompt: Option<String>,
+    working_dir: Option<String>,
+    session_id: Option<String>,
+    providers: Option<Arc<ProviderRegistry>>,
+    tool_policy: ToolPolicy,
+}
+
+impl<W: UiWriter> AgentBuilder<W> {
+    pub fn new(config: Config, ui_writer: W) -> Self {
+        Self {
+            config,
+            ui_writer,
+            autonomous: false,
+            quiet: false,
+            project_context: None,
+            system_prompt: None,
+            working_dir: None,
+            session_id: None,
+            providers: None,
+            tool_policy: ToolPolicy::default(),
+        }
+    }
+
+    /// Run in autonomous mode (registers the coach and player providers).
+    pub fn autonomous(mut self, autonomous: bool) -> Self {
+        self.autonomous = autonomous;
+        self
+    }
+
+    /// Don't write session logs, context summaries or telemetry to disk.
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Project context (README, AGENTS.md, memory) added as a second system
+    /// message after the system prompt.
+    pub fn project_context(mut self, project_context: Option<String>) -> Self {
+        self.project_context = project_context;
+        self
+    }
+
+    /// Replace the default g3 system prompt entirely (used by agent mode).
+    pub fn system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+
+    /// Directory tools run in, instead of the process's current directory.
+    pub fn working_dir(mut self, working_dir: impl Into<String>) -> Self {
+        self.working_dir = Some(working_dir.into());
+        self
+    }
+
+    /// Use this session ID instead of generating one from the first task.
+    pub fn session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Use these providers instead of registering them from the configuration.
+    /// An `Arc<ProviderRegistry>` is shared with the other agents built from it.
+    pub fn providers(mut self, providers: impl Into<Arc<ProviderRegistry>>) -> Self {
+        self.providers = Some(providers.into());
+        self
+    }
+
+    /// Restrict which tools the agent may use.
+    pub fn tool_policy(mut self, tool_policy: ToolPolicy) -> Self {
+        self.tool_policy = tool_policy;
+        self
+    }
+
+    /// Register providers, size the context window and assemble the agent.
+    pub async fn build(self) -> Result<Agent<W>> {
+        let config = self.config;
+        let ui_writer = self.ui_writer;
+
+        let providers = match self.providers {
+            Some(providers) => providers,
+            None => {
+                let providers_to_register = provider_registration::determine_providers_to_register(
+                    &config,
+                    self.autonomous,
+                );
+                Arc::new(
+                    provider_registration::register_providers(&config, &providers_to_register)
+                        .await?,
+                )
+            }
+        };
+
+        // Determine context window size based on active provider
+        let mut context_warnings = Vec::new();
+        let context_length =
+            Agent::<W>::get_configured_context_length(&config, &providers, &mut context_warnings)?;
+        let mut context_window = ContextWindow::new(context_length);
+
+        // Surface any context warnings to the user via UI
+        for warning in context_warnings {
+            ui_writer.print_context_status(&format!("⚠️ {}", warning));
+        }
+
+        // The system prompt must be the FIRST message (before README)
+        let system_prompt = match self.system_prompt {
+            Some(custom_prompt) => custom_prompt,
+            None if providers.get(None)?.has_native_tool_calling() => {
+                get_system_prompt_for_native()
+            }
+            // Non-native providers (embedded models) need JSON format instructions
+            None => get_system_prompt_for_non_native(),
+        };
+        context_window.add_message(Message::new(MessageRole::System, system_prompt));
+
+        if let Some(context) = self.project_context {
+            context_window.add_message(Message::new(MessageRole::System, context));
+        }
+
+        // NOTE: TODO lists are session-scoped and stored in .g3/sessions/<session_id>/todo.g3.md,
+        // so nothing is loaded here; the agent uses todo_read once a session is established.
+
+        let computer_controller = if config.computer_control.enabled {
+            match g3_computer_control::create_controller() {
+                Ok(controller) => Some(controller),
+                Err(e) => {
+                    warn!("Failed to initialize computer control: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let auto_compact = config.agent.auto_compact;
+        let mut agent = Agent::build_agent(
+            config,
+            ui_writer,
+            providers,
+            context_window,
+            auto_compact,
+            self.autonomous,
+            self.quiet,
+            computer_controller,
+        );
+        agent.working_dir = self.working_dir;
+        agent.tool_policy = self.tool_policy;
+        // Agents limited to a few tools don't start the MCP servers for nothing
+        let wants_mcp = match &agent.tool_policy {
+            ToolPolicy::AllowOnly(allowed) => allowed
+                .iter()
+                .any(|tool| tool.starts_with(mcp_client::TOOL_PREFIX)),
+            _ => true,
+        };
+        if wants_mcp && !agent.config.mcp.servers.is_empty() {
+            let (mcp_servers, warnings) = mcp_client::McpServers::connect(&agent.config.mcp).await;
+            for warning in warnings {
+                agent
+                    .ui_writer
+                    .print_context_status(&format!("⚠️ {}", warning));
+            }
+            agent.mcp_servers = mcp_servers;
+        }
+        if let Some(session_id) = self.session_id {
+            debug!("Using provided session ID: {}", session_id);
+            agent.set_session_id(session_id);
+        }
+        Ok(agent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_policy() {
+        assert!(ToolPolicy::AllowAll.allows("shell"));
+
+        let only = ToolPolicy::AllowOnly(vec!["read_file".to_string(), "grep".to_string()]);
+        assert!(only.allows("grep"));
+        assert!(!only.allows("shell"));
+
+        let deny = ToolPolicy::Deny(vec!["shell".to_string()]);
+        assert!(!deny.allows("shell"));
+        assert!(deny.allows("write_file"));
+    }
+}
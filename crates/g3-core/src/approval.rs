@@ -0,0 +1,671 @@
+//! Approval gating for destructive tool calls.
+//!
+//! Before a tool runs, its call is classified: does it delete files, write
+//! outside the workspace, push to a git remote, change something over the
+//! network, or run code whose effects g3 can't see? MCP tools count as
+//! network changes. A risky call runs only if the configured
+//! `approval.auto_approve` level covers its kind; otherwise the user is asked
+//! through `UiWriter::prompt_user_yes_no`, and in autonomous mode, where
+//! nobody can answer, the call is refused.
+//!
+//! Shell commands are classified by their programs and arguments, which is a
+//! heuristic: it catches the usual `rm -rf`, `git push` and `curl -X POST`,
+//! not a command built to hide what it does. The OS sandbox (see
+//! `g3_execution::sandbox`) is the hard boundary.
+
+use g3_config::ApprovalLevel;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::tools::archive;
+use crate::{mcp_client, ToolCall};
+
+/// What makes a tool call risky.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskKind {
+    /// Deletes files in the workspace
+    Deletion,
+    /// Pushes commits to a git remote
+    GitPush,
+    /// Changes state on another machine: an HTTP POST, a package publish, ...
+    NetworkMutation,
+    /// Writes or deletes outside the workspace
+    WriteOutsideWorkspace,
+    /// Runs arbitrary code or controls processes outside the shell tool:
+    /// `python`, `docker run`, ...
+    Execution,
+}
+
+impl RiskKind {
+    /// The lowest `auto_approve` level that lets this kind run unasked.
+    pub fn approval_level(self) -> ApprovalLevel {
+        match self {
+            RiskKind::Deletion => ApprovalLevel::Workspace,
+            RiskKind::GitPush | RiskKind::NetworkMutation | RiskKind::Execution => {
+                ApprovalLevel::Network
+            }
+            RiskKind::WriteOutsideWorkspace => ApprovalLevel::All,
+        }
+    }
+}
+
+/// Why a tool call needs approval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Risk {
+    pub kind: RiskKind,
+    pub reason: String,
+}
+
+impl Risk {
+    fn new(kind: RiskKind, reason: impl Into<String>) -> Self {
+        Self {
+            kind,
+            reason: reason.into(),
+        }
+    }
+
+    /// Whether `level` lets the call run without asking.
+    pub fn auto_approved(&self, level: ApprovalLevel) -> bool {
+        level >= self.kind.approval_level()
+    }
+}
+
+impl fmt::Display for Risk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+/// Tools that write the file named by an argument.
+const FILE_WRITING_TOOLS: &[(&str, &str)] = &[
+    ("write_file", "file_path"),
+    ("str_replace", "file_path"),
+    ("edit_lines", "file_path"),
+    ("regex_replace", "path"),
+    ("notebook_edit", "path"),
+    ("archive_create", "path"),
+    ("archive_extract", "destination"),
+    ("image_diff", "diff_path"),
+    ("screenshot", "path"),
+];
+
+/// Programs that delete their operands.
+const DELETING_PROGRAMS: &[&str] = &["rm", "rmdir", "unlink", "shred"];
+
+/// Programs that write to their operands.
+const WRITING_PROGRAMS: &[&str] = &[
+    "cp", "mv", "tee", "touch", "mkdir", "ln", "chmod", "chown", "install", "truncate",
+];
+
+/// `program subcommand` pairs that publish or change remote state.
+const PUBLISHING_COMMANDS: &[(&str, &str)] = &[
+    ("cargo", "publish"),
+    ("npm", "publish"),
+    ("npm", "unpublish"),
+    ("yarn", "publish"),
+    ("pnpm", "publish"),
+    ("twine", "upload"),
+    ("gem", "push"),
+    ("docker", "push"),
+    ("podman", "push"),
+    ("kubectl", "apply"),
+    ("kubectl", "delete"),
+    ("kubectl", "create"),
+    ("kubectl", "patch"),
+    ("kubectl", "scale"),
+    ("terraform", "apply"),
+    ("terraform", "destroy"),
+];
+
+/// `gh` actions that change something on GitHub.
+const GH_MUTATING_ACTIONS: &[&str] = &[
+    "create", "merge", "close", "delete", "edit", "comment", "review", "reopen", "upload",
+];
+
+/// The risk of running `tool_call` in `dir`, a directory inside
+/// `workspace`, or `None` when it needs no approval.
+pub fn assess(tool_call: &ToolCall, workspace: &Path, dir: &Path) -> Option<Risk> {
+    let args = &tool_call.args;
+    let arg = |name: &str| args.get(name).and_then(|v| v.as_str());
+    if tool_call.tool.starts_with(mcp_client::TOOL_PREFIX) {
+        return Some(Risk::new(
+            RiskKind::NetworkMutation,
+            "MCP tools run on another server and may change anything",
+        ));
+    }
+    match tool_call.tool.as_str() {
+        "shell" | "background_process" => assess_command(arg("command")?, workspace, dir),
+        "checkpoint_rollback" => Some(Risk::new(
+            RiskKind::Deletion,
+            "`checkpoint_rollback` discards the changes made since the checkpoint",
+        )),
+        "http_request" => {
+            let method = arg("method").unwrap_or("GET").to_ascii_uppercase();
+            (!matches!(method.as_str(), "GET" | "HEAD")).then(|| {
+                Risk::new(
+                    RiskKind::NetworkMutation,
+                    format!("{} request to {}", method, arg("url").unwrap_or("a URL")),
+                )
+            })
+        }
+        "python" | "notebook_run" => Some(Risk::new(
+            RiskKind::Execution,
+            format!(
+                "`{}` runs arbitrary code outside the sandbox",
+                tool_call.tool
+            ),
+        )),
+        // The snippet can't write or start processes; only the network is
+        // left to reach
+        "node_eval" if args.get("allow_network").and_then(|v| v.as_bool()) == Some(true) => {
+            Some(Risk::new(
+                RiskKind::Execution,
+                "`node_eval` runs arbitrary code with network access",
+            ))
+        }
+        "docker" => match arg("operation")? {
+            "run" => Some(Risk::new(
+                RiskKind::Execution,
+                "`docker run` runs a command in a container with the workspace mounted",
+            )),
+            "stop" => Some(Risk::new(
+                RiskKind::Execution,
+                format!(
+                    "`docker stop` stops container {}",
+                    arg("name").unwrap_or("?")
+                ),
+            )),
+            _ => None,
+        },
+        // Without diff_path the diff image is written next to `after`
+        "image_diff" if arg("diff_path").is_none() => {
+            outside_workspace(arg("after")?, workspace, dir)
+        }
+        // Without destination the archive is extracted next to itself
+        "archive_extract" if arg("destination").is_none() => {
+            let archive = Path::new(arg("path")?);
+            let destination = archive.with_file_name(archive::archive_stem(archive));
+            outside_workspace(&destination.to_string_lossy(), workspace, dir)
+        }
+        "multi_edit" => args
+            .get("edits")?
+            .as_array()?
+            .iter()
+            .filter_map(|edit| edit.get("file_path")?.as_str())
+            .find_map(|path| outside_workspace(path, workspace, dir)),
+        tool => {
+            let (_, field) = FILE_WRITING_TOOLS.iter().find(|(name, _)| *name == tool)?;
+            outside_workspace(arg(field)?, workspace, dir)
+        }
+    }
+}
+
+/// The risk of a shell command run in `dir`: the riskiest of its pipeline
+/// stages.
+pub fn assess_command(command: &str, workspace: &Path, dir: &Path) -> Option<Risk> {
+    stages(command)
+        .iter()
+        .filter_map(|stage| assess_stage(stage, workspace, dir))
+        .max_by_key(|risk| risk.kind.approval_level())
+}
+
+fn assess_stage(stage: &[String], workspace: &Path, dir: &Path) -> Option<Risk> {
+    // Redirections write wherever they point
+    let mut words = Vec::new();
+    let mut iter = stage.iter();
+    while let Some(word) = iter.next() {
+        if word == ">" || word == ">>" {
+            if let Some(risk) = iter.next().and_then(|t| outside_workspace(t, workspace, dir)) {
+                return Some(risk);
+            }
+        } else {
+            words.push(word.as_str());
+        }
+    }
+
+    // Skip `sudo`, `env` and variable assignments in front of the program
+    let start = words
+        .iter()
+        .position(|w| !matches!(*w, "sudo" | "env" | "command" | "exec") && !is_assignment(w))?;
+    let program = words[start].rsplit('/').next().unwrap_or(words[start]);
+    let rest = &words[start + 1..];
+    let operands = || rest.iter().copied().filter(|w| !w.starts_with('-'));
+
+    if DELETING_PROGRAMS.contains(&program) {
+        return Some(
+            operands()
+                .find_map(|path| outside_workspace(path, workspace, dir))
+                .unwrap_or_else(|| {
+                    Risk::new(RiskKind::Deletion, format!("`{}` deletes files", program))
+                }),
+        );
+    }
+    if WRITING_PROGRAMS.contains(&program) {
+        let mut targets: Vec<&str> = operands().collect();
+        // These only write their last operand; the others may be read from anywhere
+        if matches!(program, "cp" | "ln" | "install") {
+            targets.drain(..targets.len().saturating_sub(1));
+        }
+        return targets
+            .into_iter()
+            .find_map(|path| outside_workspace(path, workspace, dir));
+    }
+    if program == "find" && rest.contains(&"-delete") {
+        return Some(Risk::new(
+            RiskKind::Deletion,
+            "`find -delete` deletes files",
+        ));
+    }
+
+    let subcommand = operands().next().unwrap_or("");
+    match program {
+        "git" if subcommand == "push" => {
+            return Some(Risk::new(RiskKind::GitPush, "`git push` updates a remote"))
+        }
+        "git" if subcommand == "clean" => {
+            return Some(Risk::new(
+                RiskKind::Deletion,
+                "`git clean` deletes untracked files",
+            ))
+        }
+        "curl" => return curl_risk(rest),
+        "wget"
+            if rest
+                .iter()
+                .any(|w| w.starts_with("--post-") || w.starts_with("--method")) =>
+        {
+            return Some(Risk::new(
+                RiskKind::NetworkMutation,
+                "`wget` sends data to a server",
+            ))
+        }
+        "scp" | "rsync" if operands().any(is_remote_path) => {
+            return Some(Risk::new(
+                RiskKind::NetworkMutation,
+                format!("`{}` copies files to another machine", program),
+            ))
+        }
+        "gh" if rest.iter().any(|w| GH_MUTATING_ACTIONS.contains(w)) => {
+            return Some(Risk::new(
+                RiskKind::NetworkMutation,
+                "`gh` changes something on GitHub",
+            ))
+        }
+        _ => {}
+    }
+    PUBLISHING_COMMANDS
+        .iter()
+        .find(|(p, s)| *p == program && *s == subcommand)
+        .map(|(p, s)| {
+            Risk::new(
+                RiskKind::NetworkMutation,
+                format!("`{} {}` changes remote state", p, s),
+            )
+        })
+}
+
+/// curl with a method other than GET/HEAD, or with data to send.
+fn curl_risk(args: &[&str]) -> Option<Risk> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let method = match *arg {
+            "-X" | "--request" => iter.next().copied(),
+            _ => arg
+                .strip_prefix("--request=")
+                .or_else(|| arg.strip_prefix("-X").filter(|m| !m.is_empty())),
+        };
+        let sends_data = arg.starts_with("--data")
+            || arg.starts_with("--form")
+            || arg.starts_with("--upload-file")
+            || matches!(*arg, "-d" | "-F" | "-T");
+        let mutating =
+            method.is_some_and(|m| !matches!(m.to_ascii_uppercase().as_str(), "GET" | "HEAD"));
+        if mutating || sends_data {
+            return Some(Risk::new(
+                RiskKind::NetworkMutation,
+                "`curl` sends data to a server",
+            ));
+        }
+    }
+    None
+}
+
+/// A `WriteOutsideWorkspace` risk if `path`, relative to `dir`, leaves
+/// `workspace` (the temp directory and `/dev/null` are fine).
+fn outside_workspace(path: &str, workspace: &Path, dir: &Path) -> Option<Risk> {
+    let expanded = shellexpand::tilde(path);
+    let resolved = normalize(&dir.join(expanded.as_ref()));
+    let inside = [
+        normalize(workspace),
+        normalize(&std::env::temp_dir()),
+        PathBuf::from("/tmp"),
+        PathBuf::from("/dev/null"),
+    ]
+    .iter()
+    .any(|allowed| resolved.starts_with(allowed));
+    (!inside).then(|| {
+        Risk::new(
+            RiskKind::WriteOutsideWorkspace,
+            format!("writes outside the workspace ({})", resolved.display()),
+        )
+    })
+}
+
+/// `path` with `.` and `..` resolved lexically (it may not exist yet).
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// `host:path` or `user@host:path`, as scp and rsync take them.
+fn is_remote_path(word: &str) -> bool {
+    word.split_once(':')
+        .is_some_and(|(host, _)| !host.is_empty() && !host.contains('/'))
+}
+
+/// Split a command into the words of its pipeline stages, honouring quotes.
+/// `>` and `>>` are words of their own; `$(...)` and backticks are kept as
+/// text.
+fn stages(command: &str) -> Vec<Vec<String>> {
+    let mut stages = vec![Vec::new()];
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars().peekable();
+
+    fn finish(stages: &mut [Vec<String>], current: &mut String, in_word: &mut bool) {
+        if *in_word {
+            if let Some(stage) = stages.last_mut() {
+                stage.push(std::mem::take(current));
+            }
+            *in_word = false;
+        }
+    }
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            match c {
+                c if c == q => quote = None,
+                '\\' if q == '"' => {
+                    if let Some(next) = chars.next() {
+                        current.push(next);
+                    }
+                }
+                c => current.push(c),
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                    in_word = true;
+                }
+            }
+            ' ' | '\t' => finish(&mut stages, &mut current, &mut in_word),
+            '|' | '&' | ';' | '\n' | '(' | ')' => {
+                finish(&mut stages, &mut current, &mut in_word);
+                if c == '&' && chars.peek() == Some(&'>') {
+                    // `&>file` redirects like `>file`
+                    continue;
+                }
+                if stages.last().is_some_and(|s| !s.is_empty()) {
+                    stages.push(Vec::new());
+                }
+            }
+            '>' => {
+                // `2>` and `1>` redirect a stream, not to a file named `2`
+                if current == "1" || current == "2" {
+                    current.clear();
+                    in_word = false;
+                }
+                finish(&mut stages, &mut current, &mut in_word);
+                let mut redirect = String::from(">");
+                if chars.peek() == Some(&'>') {
+                    chars.next();
+                    redirect.push('>');
+                }
+                if chars.peek() == Some(&'&') {
+                    // `>&2` duplicates a descriptor
+                    chars.next();
+                    while chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        chars.next();
+                    }
+                    continue;
+                }
+                if let Some(stage) = stages.last_mut() {
+                    stage.push(redirect);
+                }
+            }
+            c => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    finish(&mut stages, &mut current, &mut in_word);
+    stages.retain(|s| !s.is_empty());
+    stages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn kind(command: &str) -> Option<RiskKind> {
+        let workspace = Path::new("/work/repo");
+        assess_command(command, workspace, workspace).map(|r| r.kind)
+    }
+
+    #[test]
+    fn test_shell_commands() {
+        assert_eq!(kind("cargo test && git status"), None);
+        assert_eq!(kind("ls > out.txt 2>&1"), None);
+        assert_eq!(kind("rm -rf target"), Some(RiskKind::Deletion));
+        assert_eq!(kind("cd src; sudo rm old.rs"), Some(RiskKind::Deletion));
+        assert_eq!(
+            kind("rm -rf ~/.cache"),
+            Some(RiskKind::WriteOutsideWorkspace)
+        );
+        assert_eq!(
+            kind("echo hi >> ../other/notes.txt"),
+            Some(RiskKind::WriteOutsideWorkspace)
+        );
+        assert_eq!(kind("cp build/app /tmp/app"), None);
+        assert_eq!(kind("cp /etc/hosts fixtures/"), None);
+        assert_eq!(
+            kind("cp build/app /usr/local/bin/"),
+            Some(RiskKind::WriteOutsideWorkspace)
+        );
+        assert_eq!(kind("find . -name '*.o' -delete"), Some(RiskKind::Deletion));
+        assert_eq!(
+            kind("git commit -m x && git push origin main"),
+            Some(RiskKind::GitPush)
+        );
+        assert_eq!(kind("curl -s https://example.com"), None);
+        assert_eq!(
+            kind("curl -X POST -d '{}' https://example.com/api"),
+            Some(RiskKind::NetworkMutation)
+        );
+        assert_eq!(
+            kind("RUST_LOG=info cargo publish --dry-run"),
+            Some(RiskKind::NetworkMutation)
+        );
+        assert_eq!(kind("gh pr view 12"), None);
+        assert_eq!(kind("gh pr create --fill"), Some(RiskKind::NetworkMutation));
+        assert_eq!(
+            kind("rsync -a dist/ host:/srv/www"),
+            Some(RiskKind::NetworkMutation)
+        );
+        // The riskiest stage wins
+        assert_eq!(
+            kind("rm -f a.txt | cat > /etc/hosts"),
+            Some(RiskKind::WriteOutsideWorkspace)
+        );
+    }
+
+    #[test]
+    fn test_tools_and_levels() {
+        let workspace = Path::new("/work/repo");
+        let call = |tool: &str, args: serde_json::Value| ToolCall {
+            tool: tool.to_string(),
+            args,
+        };
+        assert_eq!(
+            assess(
+                &call("write_file", json!({"file_path": "src/lib.rs"})),
+                workspace,
+                workspace
+            ),
+            None
+        );
+        let outside = assess(
+            &call(
+                "write_file",
+                json!({"file_path": "/work/repo/../secrets.txt"}),
+            ),
+            workspace,
+            workspace,
+        )
+        .unwrap();
+        assert_eq!(outside.kind, RiskKind::WriteOutsideWorkspace);
+        assert!(outside.reason.contains("/work/secrets.txt"));
+        assert_eq!(
+            assess(
+                &call(
+                    "multi_edit",
+                    json!({"edits": [{"file_path": "a.rs"}, {"file_path": "/etc/passwd"}]})
+                ),
+                workspace,
+                workspace
+            )
+            .map(|r| r.kind),
+            Some(RiskKind::WriteOutsideWorkspace)
+        );
+        assert_eq!(
+            assess(
+                &call("http_request", json!({"url": "https://x"})),
+                workspace,
+                workspace
+            ),
+            None
+        );
+        let post = assess(
+            &call(
+                "http_request",
+                json!({"url": "https://x", "method": "post"}),
+            ),
+            workspace,
+            workspace,
+        )
+        .unwrap();
+        assert_eq!(post.kind, RiskKind::NetworkMutation);
+        assert_eq!(
+            assess(
+                &call("read_file", json!({"file_path": "/etc/passwd"})),
+                workspace,
+                workspace
+            ),
+            None
+        );
+
+        // Relative paths resolve in the call's directory
+        let packages = Path::new("/work/repo/packages/api");
+        assert_eq!(
+            assess(
+                &call("write_file", json!({"file_path": "../web/a.ts"})),
+                workspace,
+                packages
+            ),
+            None
+        );
+        assert_eq!(
+            assess_command("rm -rf ../../../other", workspace, packages).map(|r| r.kind),
+            Some(RiskKind::WriteOutsideWorkspace)
+        );
+        assert_eq!(
+            assess(&call("checkpoint_rollback", json!({})), workspace, workspace)
+                .map(|r| r.kind),
+            Some(RiskKind::Deletion)
+        );
+        assert_eq!(
+            assess(
+                &call("mcp__github__create_issue", json!({})),
+                workspace,
+                workspace
+            )
+            .map(|r| r.kind),
+            Some(RiskKind::NetworkMutation)
+        );
+
+        let deletion = assess_command("rm a", workspace, workspace).unwrap();
+        assert!(!deletion.auto_approved(ApprovalLevel::None));
+        assert!(deletion.auto_approved(ApprovalLevel::Workspace));
+        assert!(!post.auto_approved(ApprovalLevel::Workspace));
+        assert!(post.auto_approved(ApprovalLevel::Network));
+        assert!(!outside.auto_approved(ApprovalLevel::Network));
+        assert!(outside.auto_approved(ApprovalLevel::All));
+    }
+
+    #[test]
+    fn test_executing_and_image_writing_tools() {
+        let workspace = Path::new("/work/repo");
+        let kind = |tool: &str, args: serde_json::Value| {
+            let call = ToolCall {
+                tool: tool.to_string(),
+                args,
+            };
+            assess(&call, workspace, workspace).map(|r| r.kind)
+        };
+
+        let outside = Some(RiskKind::WriteOutsideWorkspace);
+        let diff = json!({"before": "a.png", "after": "b.png", "diff_path": "/etc/d.png"});
+        assert_eq!(kind("image_diff", diff), outside);
+        let beside_after = json!({"before": "a.png", "after": "/home/me/b.png"});
+        assert_eq!(kind("image_diff", beside_after), outside);
+        let inside = json!({"before": "a.png", "after": "b.png"});
+        assert_eq!(kind("image_diff", inside), None);
+        let screenshot = json!({"path": "/usr/share/s.png"});
+        assert_eq!(kind("screenshot", screenshot), outside);
+        assert_eq!(kind("screenshot", json!({"path": "shots/s.png"})), None);
+        let beside_archive = json!({"path": "../downloads/deps.tar.gz"});
+        assert_eq!(kind("archive_extract", beside_archive), outside);
+        let into = json!({"path": "../downloads/deps.tar.gz", "destination": "vendor"});
+        assert_eq!(kind("archive_extract", into), None);
+        assert_eq!(kind("archive_extract", json!({"path": "deps.zip"})), None);
+
+        let execution = Some(RiskKind::Execution);
+        assert_eq!(kind("python", json!({"code": "print(1)"})), execution);
+        let notebook = json!({"path": "analysis.ipynb"});
+        assert_eq!(kind("notebook_run", notebook), execution);
+        assert_eq!(kind("node_eval", json!({"code": "1"})), None);
+        let networked = json!({"code": "fetch('https://x')", "allow_network": true});
+        assert_eq!(kind("node_eval", networked), execution);
+        let run = json!({"operation": "run", "image": "rust:1.80"});
+        assert_eq!(kind("docker", run), execution);
+        let stop = json!({"operation": "stop", "name": "db"});
+        assert_eq!(kind("docker", stop), execution);
+        assert_eq!(kind("docker", json!({"operation": "ps"})), None);
+        assert!(!Risk::new(RiskKind::Execution, "x").auto_approved(ApprovalLevel::Workspace));
+    }
+}
@@ -0,0 +1,159 @@
+//! Execution audit log.
+//!
+//! Every command the agent runs on the machine (`shell`, `background_process`)
+//! is appended as a JSON line to `.g3/sessions/<id>/audit.jsonl`: the
+//! command, where it ran, how it ended and the start of its output. The file
+//! is only ever appended to and is written apart from the conversation log,
+//! so it still shows what a session did after its context was compacted.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::paths::get_audit_file;
+use crate::utils::truncate_chars;
+
+/// Characters of stdout and of stderr kept in a record.
+const MAX_OUTPUT_CHARS: usize = 2000;
+
+/// One executed command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// When the command started (RFC 3339)
+    pub timestamp: String,
+    pub tool: String,
+    pub command: String,
+    pub working_dir: String,
+    /// Whether it ran in the OS sandbox
+    pub sandboxed: bool,
+    /// The devcontainer it ran in, if it ran in one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// `None` for a background process, or a command that failed to start
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub stdout: String,
+    pub stderr: String,
+    /// Why the command couldn't run, if it couldn't
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    pub fn new(tool: &str, command: &str, working_dir: &Path, sandboxed: bool) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339(),
+            tool: tool.to_string(),
+            command: command.to_string(),
+            working_dir: working_dir.display().to_string(),
+            sandboxed,
+            container: None,
+            exit_code: None,
+            duration_ms: 0,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: None,
+        }
+    }
+
+    /// Record the devcontainer the command ran in, if any.
+    pub fn in_container(mut self, container: Option<&str>) -> Self {
+        self.container = container.map(str::to_string);
+        self
+    }
+
+    /// Record how the command ended, keeping the start of its output.
+    pub fn finished(
+        mut self,
+        exit_code: Option<i32>,
+        duration: Duration,
+        stdout: &str,
+        stderr: &str,
+    ) -> Self {
+        self.exit_code = exit_code;
+        self.duration_ms = duration.as_millis() as u64;
+        self.stdout = truncate(stdout);
+        self.stderr = truncate(stderr);
+        self
+    }
+
+    /// Record that the command couldn't run.
+    pub fn failed(mut self, duration: Duration, error: &str) -> Self {
+        self.duration_ms = duration.as_millis() as u64;
+        self.error = Some(error.to_string());
+        self
+    }
+
+    /// Append this record as a JSON line to `path`.
+    pub fn append_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(file, "{}", serde_json::to_string(self).unwrap_or_default())
+    }
+
+    /// Append this record to the session's audit log. Without a session
+    /// there is nowhere to write it.
+    pub fn log(&self, session_id: Option<&str>) {
+        let Some(session_id) = session_id else {
+            return;
+        };
+        let path = get_audit_file(session_id);
+        if let Err(e) = self.append_to(&path) {
+            warn!("Failed to write audit record to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// The first `MAX_OUTPUT_CHARS` characters of `output`, marked if cut.
+fn truncate(output: &str) -> String {
+    truncate_chars(output, MAX_OUTPUT_CHARS, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_are_appended_with_truncated_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("audit.jsonl");
+        let long = "x".repeat(MAX_OUTPUT_CHARS + 10);
+
+        AuditRecord::new("shell", "cargo test", dir.path(), true)
+            .finished(Some(101), Duration::from_millis(1500), &long, "failed")
+            .append_to(&log)
+            .unwrap();
+        AuditRecord::new("shell", "nope", dir.path(), false)
+            .in_container(Some("g3-app-1234"))
+            .failed(Duration::ZERO, "bash not found")
+            .append_to(&log)
+            .unwrap();
+
+        let records: Vec<AuditRecord> = std::fs::read_to_string(&log)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].command, "cargo test");
+        assert_eq!(records[0].exit_code, Some(101));
+        assert_eq!(records[0].duration_ms, 1500);
+        assert!(records[0].sandboxed);
+        assert!(records[0]
+            .stdout
+            .ends_with(&format!("… [{} chars total]", MAX_OUTPUT_CHARS + 10)));
+        assert_eq!(records[0].stderr, "failed");
+        assert_eq!(records[0].container, None);
+        assert_eq!(records[1].container.as_deref(), Some("g3-app-1234"));
+        assert_eq!(records[1].exit_code, None);
+        assert_eq!(records[1].error.as_deref(), Some("bash not found"));
+    }
+}
@@ -11,6 +11,7 @@
 //! - Stop processes: `kill <pid>` or `pkill -f <name>`
 //! - Check status: `ps aux | grep <name>`
 
+use g3_execution::{EnvPolicy, Shell};
 use std::collections::HashMap;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
@@ -77,6 +78,40 @@ impl BackgroundProcessManager {
         name: &str,
         command: &str,
         working_dir: &PathBuf,
+    ) -> Result<ProcessInfo, String> {
+        self.start_with_env(
+            name,
+            command,
+            working_dir,
+            &EnvPolicy::default(),
+            Shell::default(),
+        )
+    }
+
+    /// Start a new background process running `command` in `shell`, with the
+    /// environment `env` describes
+    pub fn start_with_env(
+        &self,
+        name: &str,
+        command: &str,
+        working_dir: &PathBuf,
+        env: &EnvPolicy,
+        shell: Shell,
+    ) -> Result<ProcessInfo, String> {
+        let mut cmd = Command::new(shell.program());
+        env.apply(&mut cmd);
+        cmd.arg("-c").arg(command).current_dir(working_dir);
+        self.start_command(name, command, working_dir, cmd)
+    }
+
+    /// Start a new background process running `cmd`, a prepared process for
+    /// `command` (e.g. a `docker exec` of it). Its output goes to the log.
+    pub fn start_command(
+        &self,
+        name: &str,
+        command: &str,
+        working_dir: &PathBuf,
+        mut cmd: Command,
     ) -> Result<ProcessInfo, String> {
         // Check if a process with this name already exists
         {
@@ -122,10 +157,7 @@ impl BackgroundProcessManager {
             .map_err(|e| format!("Failed to clone log file handle: {}", e))?;
 
         // Spawn the process
-        let child = Command::new("bash")
-            .arg("-c")
-            .arg(command)
-            .current_dir(working_dir)
+        let child = cmd
             .stdout(Stdio::from(log_handle))
             .stderr(Stdio::from(log_handle_stderr))
             .spawn()
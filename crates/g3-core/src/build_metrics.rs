@@ -0,0 +1,415 @@
+//! Binary size and build-time tracking.
+//!
+//! When `build_metrics.enabled` is set, successful build commands run through
+//! the shell tool are timed and their artifacts measured. The first build of
+//! each command that actually compiled something becomes the workspace
+//! baseline (stored in `.g3/build_baseline.json`); later samples are reported
+//! as deltas against it so size and compile-time constraints can be checked
+//! during a session.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::paths::get_g3_dir;
+
+/// Command prefixes recognised as builds.
+const BUILD_COMMANDS: &[&str] = &[
+    "cargo build",
+    "cargo b",
+    "go build",
+    "npm run build",
+    "yarn build",
+    "yarn run build",
+    "pnpm build",
+    "pnpm run build",
+    "swift build",
+];
+
+/// `make` targets that build; `make` with only flags and variables builds the
+/// default target.
+const MAKE_BUILD_TARGETS: &[&str] = &["all", "build", "release"];
+
+/// Size of a single build artifact.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactSize {
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// Timing and artifact sizes for one build.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildSample {
+    pub command: String,
+    pub duration_ms: u64,
+    pub artifacts: Vec<ArtifactSize>,
+    pub recorded_at: String,
+}
+
+/// A build sample together with the baseline it is compared against.
+#[derive(Debug, Clone)]
+pub struct BuildReport {
+    pub sample: BuildSample,
+    /// None when this sample became the baseline, or when there is no
+    /// baseline yet and this build was a no-op
+    pub baseline: Option<BuildSample>,
+    /// The build had nothing to do, so its time says nothing about the build
+    pub no_op: bool,
+}
+
+impl BuildReport {
+    /// One-line-per-artifact summary of sizes and deltas.
+    pub fn format(&self) -> String {
+        let mut lines = Vec::new();
+        let baseline = self.baseline.as_ref();
+
+        let time_delta = match baseline {
+            _ if self.no_op => " (no-op build)".to_string(),
+            Some(b) => {
+                let delta = self.sample.duration_ms as i64 - b.duration_ms as i64;
+                format!(" ({}{:.1}s)", sign(delta), delta.abs() as f64 / 1000.0)
+            }
+            None => " (baseline)".to_string(),
+        };
+        lines.push(format!(
+            "⏱️ build time {:.1}s{}",
+            self.sample.duration_ms as f64 / 1000.0,
+            time_delta
+        ));
+
+        for artifact in &self.sample.artifacts {
+            let previous =
+                baseline.and_then(|b| b.artifacts.iter().find(|a| a.path == artifact.path));
+            let size_delta = match previous {
+                Some(prev) => {
+                    let delta = artifact.bytes as i64 - prev.bytes as i64;
+                    let pct = if prev.bytes > 0 {
+                        delta as f64 / prev.bytes as f64 * 100.0
+                    } else {
+                        0.0
+                    };
+                    format!(
+                        " ({}{}, {}{:.1}%)",
+                        sign(delta),
+                        format_bytes(delta.unsigned_abs()),
+                        sign(delta),
+                        pct.abs()
+                    )
+                }
+                None if baseline.is_some() => " (new)".to_string(),
+                None => String::new(),
+            };
+            lines.push(format!(
+                "📦 {} {}{}",
+                artifact.path,
+                format_bytes(artifact.bytes),
+                size_delta
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn sign(delta: i64) -> &'static str {
+    if delta < 0 {
+        "-"
+    } else {
+        "+"
+    }
+}
+
+/// Format a byte count as B/KB/MB.
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Whether a shell command is a build we know how to measure.
+pub fn is_build_command(command: &str) -> bool {
+    let normalized = normalize_command(command);
+    if let Some(args) = normalized
+        .strip_prefix("make")
+        .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+    {
+        return is_make_build(args);
+    }
+    BUILD_COMMANDS
+        .iter()
+        .any(|prefix| normalized == *prefix || normalized.starts_with(&format!("{} ", prefix)))
+}
+
+/// Whether `make` with these arguments builds: every target it names is a
+/// build target. Options taking a separate value (`-C dir`) aren't told
+/// apart from targets, so they count as other targets.
+fn is_make_build(args: &str) -> bool {
+    args.split_whitespace()
+        .filter(|arg| !arg.starts_with('-') && !arg.contains('='))
+        .all(|target| MAKE_BUILD_TARGETS.contains(&target))
+}
+
+/// Whether the build had nothing to do, judged by the tool's own output.
+/// Commands we can't judge count as real builds.
+pub fn is_no_op_build(command: &str, output: &str) -> bool {
+    let normalized = normalize_command(command);
+    if normalized.starts_with("cargo ") {
+        !output.contains("Compiling ")
+    } else if normalized == "make" || normalized.starts_with("make ") {
+        output.contains("Nothing to be done for") || output.contains("is up to date.")
+    } else {
+        false
+    }
+}
+
+/// Collapse whitespace so equivalent commands share a baseline.
+fn normalize_command(command: &str) -> String {
+    command.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Measure artifacts for a build: cargo binaries for the built profile plus
+/// any explicitly configured paths.
+pub fn measure_artifacts(
+    command: &str,
+    working_dir: &Path,
+    configured: &[String],
+) -> Vec<ArtifactSize> {
+    let mut artifacts = Vec::new();
+
+    if normalize_command(command).starts_with("cargo") {
+        let profile = cargo_profile(command);
+        let target_dir = std::env::var("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| working_dir.join("target"));
+        artifacts.extend(cargo_binaries(&target_dir.join(profile), working_dir));
+    }
+
+    for path in configured {
+        let full = working_dir.join(path);
+        match std::fs::metadata(&full) {
+            Ok(meta) if meta.is_file() => artifacts.push(ArtifactSize {
+                path: path.clone(),
+                bytes: meta.len(),
+            }),
+            _ => debug!("Configured build artifact not found: {}", full.display()),
+        }
+    }
+
+    artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+    artifacts.dedup_by(|a, b| a.path == b.path);
+    artifacts
+}
+
+/// The target directory a cargo command builds into: `release` for
+/// `--release` or `-r` given as an argument of its own, else `debug`.
+fn cargo_profile(command: &str) -> &'static str {
+    let release = command
+        .split_whitespace()
+        .any(|arg| arg == "--release" || arg == "-r");
+    if release {
+        "release"
+    } else {
+        "debug"
+    }
+}
+
+/// Executables at the top level of a cargo profile directory.
+fn cargo_binaries(profile_dir: &Path, working_dir: &Path) -> Vec<ArtifactSize> {
+    let Ok(entries) = std::fs::read_dir(profile_dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let path = entry.path();
+            if !meta.is_file() || !is_binary_artifact(&path, &meta) {
+                return None;
+            }
+            let display = path.strip_prefix(working_dir).unwrap_or(&path);
+            Some(ArtifactSize {
+                path: display.to_string_lossy().to_string(),
+                bytes: meta.len(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_binary_artifact(path: &Path, meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    let ext = path.extension().and_then(|e| e.to_str());
+    (meta.permissions().mode() & 0o111 != 0 && ext.is_none()) || matches!(ext, Some("wasm"))
+}
+
+#[cfg(not(unix))]
+fn is_binary_artifact(path: &Path, _meta: &std::fs::Metadata) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("exe") | Some("wasm")
+    )
+}
+
+fn baseline_path() -> PathBuf {
+    get_g3_dir().join("build_baseline.json")
+}
+
+fn load_baselines(path: &Path) -> HashMap<String, BuildSample> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Record a successful build and compare it against the workspace baseline.
+///
+/// The first sample for a command that wasn't a no-op (see
+/// `is_no_op_build`, judged from the build's `output`) is saved as its
+/// baseline. Delete `.g3/build_baseline.json` to re-baseline.
+pub fn record_build(
+    command: &str,
+    output: &str,
+    duration: Duration,
+    working_dir: &Path,
+    configured_artifacts: &[String],
+) -> BuildReport {
+    let key = normalize_command(command);
+    let sample = BuildSample {
+        command: key.clone(),
+        duration_ms: duration.as_millis() as u64,
+        artifacts: measure_artifacts(command, working_dir, configured_artifacts),
+        recorded_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let path = baseline_path();
+    let mut baselines = load_baselines(&path);
+    let baseline = baselines.get(&key).cloned();
+    let no_op = is_no_op_build(command, output);
+
+    if baseline.is_none() && !no_op {
+        baselines.insert(key, sample.clone());
+        let write = path
+            .parent()
+            .map(std::fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| {
+                let json = serde_json::to_string_pretty(&baselines).unwrap_or_default();
+                std::fs::write(&path, json)
+            });
+        if let Err(e) = write {
+            warn!("Failed to save build baseline to {}: {}", path.display(), e);
+        }
+    }
+
+    BuildReport {
+        sample,
+        baseline,
+        no_op,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(duration_ms: u64, bytes: u64) -> BuildSample {
+        BuildSample {
+            command: "cargo build --release".to_string(),
+            duration_ms,
+            artifacts: vec![ArtifactSize {
+                path: "target/release/g3".to_string(),
+                bytes,
+            }],
+            recorded_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_is_build_command() {
+        assert!(is_build_command("cargo build --release"));
+        assert!(is_build_command("  cargo   build"));
+        assert!(is_build_command("npm run build"));
+        assert!(is_build_command("make"));
+        assert!(is_build_command("make -j8 all CC=clang"));
+        assert!(is_build_command("make build"));
+        assert!(!is_build_command("make test"));
+        assert!(!is_build_command("make clean all"));
+        assert!(!is_build_command("cargo test"));
+        assert!(!is_build_command("makefile-lint"));
+        assert!(!is_build_command("ls target"));
+    }
+
+    #[test]
+    fn test_is_no_op_build() {
+        let fresh = "   Compiling g3 v0.1.0\n    Finished `release` profile [optimized]";
+        let no_op = "    Finished `release` profile [optimized] target(s) in 0.2s";
+        assert!(!is_no_op_build("cargo build --release", fresh));
+        assert!(is_no_op_build("cargo build --release", no_op));
+        assert!(is_no_op_build("make", "make: Nothing to be done for 'all'."));
+        assert!(!is_no_op_build("make", "cc -o app main.c"));
+        assert!(!is_no_op_build("go build ./...", ""));
+    }
+
+    #[test]
+    fn test_cargo_profile() {
+        assert_eq!(cargo_profile("cargo build -r"), "release");
+        assert_eq!(cargo_profile("cargo build --release --bin g3"), "release");
+        assert_eq!(cargo_profile("cargo build"), "debug");
+        assert_eq!(cargo_profile("cargo build -p g3-runner"), "debug");
+        assert_eq!(cargo_profile("cargo build --target-dir -r-out"), "debug");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(2048), "2.0 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MB");
+    }
+
+    #[test]
+    fn test_report_against_baseline() {
+        let report = BuildReport {
+            sample: sample(40_000, 2 * 1024 * 1024),
+            baseline: Some(sample(42_500, 1024 * 1024)),
+            no_op: false,
+        };
+        let text = report.format();
+        assert!(text.contains("build time 40.0s (-2.5s)"), "{}", text);
+        assert!(
+            text.contains("target/release/g3 2.0 MB (+1.0 MB, +100.0%)"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn test_report_as_baseline() {
+        let report = BuildReport {
+            sample: sample(1_000, 100),
+            baseline: None,
+            no_op: false,
+        };
+        let text = report.format();
+        assert!(text.contains("(baseline)"));
+        assert!(text.contains("target/release/g3 100 B"));
+    }
+
+    #[test]
+    fn test_report_no_op_build() {
+        let report = BuildReport {
+            sample: sample(300, 100),
+            baseline: Some(sample(42_500, 100)),
+            no_op: true,
+        };
+        let text = report.format();
+        assert!(text.contains("build time 0.3s (no-op build)"), "{}", text);
+        assert!(!text.contains("(baseline)"));
+    }
+}
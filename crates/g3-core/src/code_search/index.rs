@@ -0,0 +1,314 @@
+//! Symbol index for `symbol_lookup`.
+//!
+//! Discovery can save the declarations of every supported file to
+//! `.g3/planner/index/symbols.json`, found with the same tree-sitter walk as
+//! `ast_outline`: top-level functions, types, traits, impls and modules, and
+//! the members of classes, impls and traits. "Where is X defined" is then a
+//! lookup in that file instead of grep rounds. Rebuilding only re-parses
+//! files whose size or modification time changed.
+
+use anyhow::Result;
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use super::outline::outline;
+use super::symbols::symbol_name;
+use super::TreeSitterSearcher;
+
+/// Location of the index, relative to the codebase root.
+pub const SYMBOL_INDEX_PATH: &str = ".g3/planner/index/symbols.json";
+
+/// Bump when the indexed content changes, so older indexes are rebuilt.
+const INDEX_VERSION: u32 = 1;
+
+/// Top-level declarations and their members.
+const INDEX_DEPTH: usize = 2;
+
+/// Larger files are generated or vendored more often than not.
+const MAX_FILE_BYTES: u64 = 1_000_000;
+
+/// A declaration in the index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedSymbol {
+    pub name: String,
+    /// "function", "method", "struct", "class", "impl", "trait", ...
+    pub kind: String,
+    pub line: usize,
+    pub end_line: usize,
+    pub signature: String,
+    /// The class, impl or trait a member belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+/// The declarations of one file, with the stamp they were read at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub len: u64,
+    /// Nanoseconds since the Unix epoch, 0 when unknown
+    pub modified: u64,
+    pub language: String,
+    pub symbols: Vec<IndexedSymbol>,
+}
+
+/// Declarations of every supported file under a root, by relative path.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SymbolIndex {
+    pub version: u32,
+    pub created_at: String,
+    pub files: BTreeMap<String, IndexedFile>,
+}
+
+/// A lookup result.
+#[derive(Debug, Clone, Copy)]
+pub struct SymbolMatch<'a> {
+    pub file: &'a str,
+    pub symbol: &'a IndexedSymbol,
+}
+
+/// Path of the symbol index for the codebase at `root`.
+pub fn symbol_index_path(root: &Path) -> PathBuf {
+    root.join(SYMBOL_INDEX_PATH)
+}
+
+impl SymbolIndex {
+    /// Index every supported file under `root`, reusing the entries of
+    /// `previous` for files that haven't changed. Returns the index and the
+    /// number of files parsed.
+    pub fn build(root: &Path, previous: Option<&SymbolIndex>) -> Result<(Self, usize)> {
+        let mut searcher = TreeSitterSearcher::new()?;
+        let mut files = BTreeMap::new();
+        let mut parsed = 0;
+
+        let walker = WalkBuilder::new(root)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build();
+        for entry in walker.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let Some(language) = TreeSitterSearcher::language_for_path(path) else {
+                continue;
+            };
+            let Some((len, modified)) = stamp(path) else {
+                continue;
+            };
+            if len > MAX_FILE_BYTES {
+                continue;
+            }
+            let relative = relative_path(root, path);
+
+            if let Some(old) = previous.and_then(|p| p.files.get(&relative)) {
+                if old.len == len && old.modified == modified {
+                    files.insert(relative, old.clone());
+                    continue;
+                }
+            }
+
+            let Ok(source) = fs::read_to_string(path) else {
+                continue;
+            };
+            let Some(tree) = searcher.parse(language, &source) else {
+                continue;
+            };
+            parsed += 1;
+            files.insert(
+                relative,
+                IndexedFile {
+                    len,
+                    modified,
+                    language: language.to_string(),
+                    symbols: symbols(tree.root_node(), &source),
+                },
+            );
+        }
+
+        let index = Self {
+            version: INDEX_VERSION,
+            created_at: chrono::Local::now().to_rfc3339(),
+            files,
+        };
+        Ok((index, parsed))
+    }
+
+    /// The saved index of `root`, unless missing or from another version.
+    pub fn load(root: &Path) -> Option<Self> {
+        let content = fs::read_to_string(symbol_index_path(root)).ok()?;
+        let index: Self = serde_json::from_str(&content).ok()?;
+        (index.version == INDEX_VERSION).then_some(index)
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = symbol_index_path(root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn symbol_count(&self) -> usize {
+        self.files.values().map(|f| f.symbols.len()).sum()
+    }
+
+    /// Declarations named `query`, best first: for `Type::method` or
+    /// `Type.method`, members of `Type`, then exact names, then names that
+    /// differ in case, then names containing the query.
+    pub fn lookup(&self, query: &str, kind: Option<&str>) -> Vec<SymbolMatch<'_>> {
+        let name = symbol_name(query);
+        if name.is_empty() {
+            return Vec::new();
+        }
+        let qualifier = query
+            .trim()
+            .strip_suffix(name)
+            .map(|q| q.trim_end_matches([':', '.']))
+            .and_then(|q| q.rsplit([':', '.']).next())
+            .filter(|q| !q.is_empty());
+        let lowered = name.to_lowercase();
+
+        let mut matches: Vec<(u8, SymbolMatch<'_>)> = Vec::new();
+        for (file, indexed) in &self.files {
+            for symbol in &indexed.symbols {
+                if kind.is_some_and(|k| !symbol.kind.eq_ignore_ascii_case(k)) {
+                    continue;
+                }
+                let rank = if symbol.name == name {
+                    let in_qualifier = qualifier.is_some_and(|q| {
+                        symbol
+                            .parent
+                            .as_deref()
+                            .is_some_and(|p| p == q || p.ends_with(&format!(" for {}", q)))
+                    });
+                    if in_qualifier {
+                        0
+                    } else {
+                        1
+                    }
+                } else if symbol.name.to_lowercase() == lowered {
+                    2
+                } else if symbol.name.to_lowercase().contains(&lowered) {
+                    3
+                } else {
+                    continue;
+                };
+                matches.push((
+                    rank,
+                    SymbolMatch {
+                        file: file.as_str(),
+                        symbol,
+                    },
+                ));
+            }
+        }
+        // Stable: file and source order within a rank
+        matches.sort_by_key(|(rank, _)| *rank);
+        matches.into_iter().map(|(_, m)| m).collect()
+    }
+
+    /// Whether `file` changed on disk since it was indexed.
+    pub fn is_stale(&self, root: &Path, file: &str) -> bool {
+        let Some(indexed) = self.files.get(file) else {
+            return true;
+        };
+        stamp(&root.join(file)) != Some((indexed.len, indexed.modified))
+    }
+}
+
+/// The outline of a file, flattened, with members pointing at their parent.
+fn symbols(root: tree_sitter::Node<'_>, source: &str) -> Vec<IndexedSymbol> {
+    let mut symbols = Vec::new();
+    let mut parent: Option<String> = None;
+    for item in outline(root, source, INDEX_DEPTH) {
+        if item.depth == 0 {
+            parent = Some(item.name.clone());
+        }
+        symbols.push(IndexedSymbol {
+            parent: if item.depth == 0 {
+                None
+            } else {
+                parent.clone()
+            },
+            name: item.name,
+            kind: item.kind,
+            line: item.start_line,
+            end_line: item.end_line,
+            signature: item.signature,
+        });
+    }
+    symbols
+}
+
+fn stamp(path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos() as u64);
+    Some((metadata.len(), modified))
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_and_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/window.rs"),
+            "pub struct ContextWindow {\n    used: u32,\n}\n\nimpl ContextWindow {\n    pub fn new() -> Self {\n        Self { used: 0 }\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "pub fn new() {}\n\npub struct ContextWindowStats;\n",
+        )
+        .unwrap();
+
+        let (index, parsed) = SymbolIndex::build(root, None).unwrap();
+        assert_eq!(parsed, 2);
+        index.save(root).unwrap();
+        let index = SymbolIndex::load(root).unwrap();
+
+        let found = index.lookup("ContextWindow", None);
+        assert_eq!(found[0].file, "src/window.rs");
+        assert_eq!(found[0].symbol.kind, "struct");
+        assert_eq!(found[0].symbol.line, 1);
+        // The impl, then the case-insensitive substring match
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[2].symbol.name, "ContextWindowStats");
+
+        // The qualifier picks the method over the free function
+        let found = index.lookup("ContextWindow::new", None);
+        assert_eq!(found[0].file, "src/window.rs");
+        assert_eq!(found[0].symbol.parent.as_deref(), Some("ContextWindow"));
+        assert_eq!(found[1].file, "src/lib.rs");
+        assert!(index.lookup("new", Some("struct")).is_empty());
+
+        // Unchanged files are reused; changed ones are re-parsed and stale until then
+        assert!(!index.is_stale(root, "src/lib.rs"));
+        fs::write(root.join("src/lib.rs"), "pub fn renamed() {}\n").unwrap();
+        assert!(index.is_stale(root, "src/lib.rs"));
+        let (index, parsed) = SymbolIndex::build(root, Some(&index)).unwrap();
+        assert_eq!(parsed, 1);
+        assert_eq!(index.lookup("renamed", None)[0].file, "src/lib.rs");
+    }
+}
@@ -3,8 +3,15 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 
+mod index;
+mod outline;
 mod searcher;
+mod symbols;
+pub use index::{
+    symbol_index_path, IndexedFile, IndexedSymbol, SymbolIndex, SymbolMatch, SYMBOL_INDEX_PATH,
+};
 pub use searcher::TreeSitterSearcher;
 
 /// Request for batch code searches
@@ -79,3 +86,119 @@ pub async fn execute_code_search(request: CodeSearchRequest) -> Result<CodeSearc
     let mut searcher = TreeSitterSearcher::new()?;
     searcher.execute_search(request).await
 }
+
+/// Request for `find_definition` / `find_references`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolRequest {
+    /// Symbol to resolve; for qualified names (`module::Name`, `pkg.Name`)
+    /// the last segment is matched
+    pub symbol: String,
+    /// Only search files of this language (default: every supported language)
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Paths to search (default: current directory)
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Lines of context around each location
+    #[serde(default = "default_symbol_context_lines")]
+    pub context_lines: usize,
+    #[serde(default = "default_max_symbol_results")]
+    pub max_results: usize,
+}
+
+fn default_symbol_context_lines() -> usize {
+    1
+}
+
+fn default_max_symbol_results() -> usize {
+    100
+}
+
+/// How a symbol occurs at a location
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    /// The name of a declaration (function, type, variable, ...)
+    Definition,
+    /// Inside an import/use statement
+    Import,
+    /// Any other use
+    Reference,
+}
+
+/// Where a symbol occurs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolLocation {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub kind: SymbolKind,
+    /// Syntax node kind of the declaration (e.g. "function_item"), for definitions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declaration: Option<String>,
+    /// Numbered source lines around the location
+    pub context: String,
+}
+
+/// Response for `find_definition` / `find_references`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SymbolResponse {
+    pub symbol: String,
+    pub locations: Vec<SymbolLocation>,
+    pub files_searched: usize,
+    /// More than `max_results` locations were found
+    pub truncated: bool,
+}
+
+/// Find the definitions of a symbol, those in the most imported modules first.
+pub async fn find_definitions(request: SymbolRequest) -> Result<SymbolResponse> {
+    let mut searcher = TreeSitterSearcher::new()?;
+    symbols::find_definitions(&mut searcher, &request)
+}
+
+/// Find the imports of and references to a symbol, excluding comments,
+/// strings and its definitions. Files that define or import the symbol come
+/// first.
+pub async fn find_references(request: SymbolRequest) -> Result<SymbolResponse> {
+    let mut searcher = TreeSitterSearcher::new()?;
+    symbols::find_references(&mut searcher, &request)
+}
+
+/// A declaration in a file's outline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutlineItem {
+    /// "function", "method", "struct", "class", "impl", "trait", ...
+    pub kind: String,
+    pub name: String,
+    /// The declaration up to its body, on one line
+    pub signature: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Nesting level: 0 for top-level, 1 for members of a class/impl/module, ...
+    pub depth: usize,
+}
+
+/// Response for `ast_outline`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutlineResponse {
+    pub language: String,
+    pub total_lines: usize,
+    pub items: Vec<OutlineItem>,
+}
+
+/// The structural outline of a file: its functions, types, classes and
+/// their members with line ranges, nested at most `max_depth` levels.
+pub async fn outline_file(path: &Path, max_depth: usize) -> Result<OutlineResponse> {
+    let language = TreeSitterSearcher::language_for_path(path)
+        .ok_or_else(|| anyhow::anyhow!("No outline support for {}", path.display()))?;
+    let source = std::fs::read_to_string(path)?;
+    let mut searcher = TreeSitterSearcher::new()?;
+    let tree = searcher
+        .parse(language, &source)
+        .ok_or_else(|| anyhow::anyhow!("Failed to parse {}", path.display()))?;
+    Ok(OutlineResponse {
+        language: language.to_string(),
+        total_lines: source.lines().count(),
+        items: outline::outline(tree.root_node(), &source, max_depth),
+    })
+}
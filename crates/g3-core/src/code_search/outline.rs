@@ -0,0 +1,224 @@
+//! Structural outlines for `ast_outline`.
+//!
+//! A file's syntax tree is walked for declarations (functions, types,
+//! classes, impls, modules, ...). Declarations that contain others, such as
+//! classes, impls, traits and modules, are descended into so their members
+//! are listed beneath them; function bodies are not, which keeps the outline
+//! to the file's API rather than every local.
+
+use tree_sitter::Node;
+
+use super::OutlineItem;
+
+/// Node kinds listed in the outline, across the supported languages.
+const ENTRY_KINDS: &[&str] = &[
+    // Rust
+    "function_item",
+    "function_signature_item",
+    "struct_item",
+    "enum_item",
+    "union_item",
+    "trait_item",
+    "impl_item",
+    "mod_item",
+    "type_item",
+    "const_item",
+    "static_item",
+    "macro_definition",
+    // Python
+    "function_definition",
+    "class_definition",
+    // JavaScript / TypeScript
+    "function_declaration",
+    "generator_function_declaration",
+    "class_declaration",
+    "abstract_class_declaration",
+    "method_definition",
+    "interface_declaration",
+    "type_alias_declaration",
+    "enum_declaration",
+    "internal_module",
+    "module",
+    // Go
+    "method_declaration",
+    "type_spec",
+    // Java
+    "record_declaration",
+    "constructor_declaration",
+    "annotation_type_declaration",
+    // C / C++
+    "struct_specifier",
+    "class_specifier",
+    "enum_specifier",
+    "union_specifier",
+    "namespace_definition",
+];
+
+/// Entry kinds whose members are listed beneath them.
+const CONTAINER_KINDS: &[&str] = &[
+    "trait_item",
+    "impl_item",
+    "mod_item",
+    "class_definition",
+    "class_declaration",
+    "abstract_class_declaration",
+    "interface_declaration",
+    "internal_module",
+    "module",
+    "record_declaration",
+    "enum_declaration",
+    "class_specifier",
+    "struct_specifier",
+    "namespace_definition",
+];
+
+/// Longest signature shown.
+const MAX_SIGNATURE_CHARS: usize = 160;
+
+/// The outline of a parsed file, in source order.
+pub(super) fn outline(root: Node<'_>, source: &str, max_depth: usize) -> Vec<OutlineItem> {
+    let mut items = Vec::new();
+    collect(root, source, 0, max_depth, false, &mut items);
+    items
+}
+
+fn collect(
+    node: Node<'_>,
+    source: &str,
+    depth: usize,
+    max_depth: usize,
+    in_container: bool,
+    items: &mut Vec<OutlineItem>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match entry(child, source, in_container) {
+            Some(mut item) => {
+                item.depth = depth;
+                items.push(item);
+                if CONTAINER_KINDS.contains(&child.kind()) && depth + 1 < max_depth {
+                    collect(child, source, depth + 1, max_depth, true, items);
+                }
+            }
+            // Look through wrappers (export statements, decorators, Go type
+            // declarations, class bodies) but never into function bodies
+            None if !is_function_body(child) => {
+                collect(child, source, depth, max_depth, in_container, items)
+            }
+            None => {}
+        }
+    }
+}
+
+fn is_function_body(node: Node<'_>) -> bool {
+    let kind = node.kind();
+    (kind == "block" || kind == "statement_block" || kind == "compound_statement")
+        && node.parent().is_some_and(|p| {
+            let parent = p.kind();
+            parent.contains("function") || parent.contains("method") || parent.contains("arrow")
+        })
+}
+
+/// The outline entry for `node`, if it is a declaration worth listing.
+fn entry(node: Node<'_>, source: &str, in_container: bool) -> Option<OutlineItem> {
+    let kind = node.kind();
+    let name = if kind == "variable_declarator" {
+        // `const handler = () => {...}` is how JS/TS often declares functions
+        let value = node.child_by_field_name("value")?;
+        if !matches!(
+            value.kind(),
+            "arrow_function" | "function_expression" | "function"
+        ) {
+            return None;
+        }
+        text(node.child_by_field_name("name")?, source)
+    } else if !ENTRY_KINDS.contains(&kind) {
+        return None;
+    } else if kind.ends_with("_specifier") && node.child_by_field_name("body").is_none() {
+        // `struct Foo x;` uses a type rather than declaring one
+        return None;
+    } else if kind == "impl_item" {
+        let target = text(node.child_by_field_name("type")?, source);
+        match node.child_by_field_name("trait") {
+            Some(t) => format!("{} for {}", text(t, source), target),
+            None => target,
+        }
+    } else if kind == "function_definition" && node.child_by_field_name("name").is_none() {
+        // C/C++: the name is inside the declarator chain
+        declarator_name(node, source)?
+    } else {
+        text(node.child_by_field_name("name")?, source)
+    };
+
+    let start = node.start_position().row;
+    let end = node.end_position().row;
+    Some(OutlineItem {
+        kind: label(kind, in_container).to_string(),
+        name,
+        signature: signature(node, source),
+        start_line: start + 1,
+        end_line: end + 1,
+        depth: 0,
+    })
+}
+
+/// The innermost name of a C/C++ function declarator.
+fn declarator_name(node: Node<'_>, source: &str) -> Option<String> {
+    let mut current = node.child_by_field_name("declarator")?;
+    while let Some(inner) = current.child_by_field_name("declarator") {
+        current = inner;
+    }
+    Some(text(current, source))
+}
+
+fn text(node: Node<'_>, source: &str) -> String {
+    source[node.byte_range()].to_string()
+}
+
+/// A readable kind for a node kind.
+fn label(kind: &str, in_container: bool) -> &'static str {
+    // Checked in order: "constructor" contains "struct"
+    const LABELS: &[(&str, &str)] = &[
+        ("constructor", "constructor"),
+        ("impl", "impl"),
+        ("trait", "trait"),
+        ("interface", "interface"),
+        ("class", "class"),
+        ("struct", "struct"),
+        ("enum", "enum"),
+        ("union", "union"),
+        ("record", "record"),
+        ("annotation", "annotation"),
+        ("mod", "module"),
+        ("namespace", "namespace"),
+        ("type", "type"),
+        ("const", "const"),
+        ("static", "static"),
+        ("macro", "macro"),
+        ("method", "method"),
+    ];
+    if let Some(&(_, label)) = LABELS.iter().find(|(part, _)| kind.contains(part)) {
+        return label;
+    }
+    if in_container {
+        "method"
+    } else {
+        "function"
+    }
+}
+
+/// The declaration's header: its text up to the body, on one line.
+fn signature(node: Node<'_>, source: &str) -> String {
+    let end = node
+        .child_by_field_name("body")
+        .map_or(node.end_byte(), |body| body.start_byte());
+    let header = source[node.start_byte()..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let header = header.trim_end_matches(['{', ':', '=']).trim_end();
+    match header.char_indices().nth(MAX_SIGNATURE_CHARS) {
+        Some((cut, _)) => format!("{}…", &header[..cut]),
+        None => header.to_string(),
+    }
+}
@@ -4,9 +4,24 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use streaming_iterator::StreamingIterator;
-use tree_sitter::{Language, Parser, Query, QueryCursor};
+use tree_sitter::{Language, Parser, Query, QueryCursor, Tree};
 use walkdir::WalkDir;
 
+/// Language names in the order they are tried when detecting a file's language.
+const LANGUAGES: &[&str] = &[
+    "rust",
+    "python",
+    "javascript",
+    "typescript",
+    "go",
+    "java",
+    "c",
+    "cpp",
+    "haskell",
+    "scheme",
+    "racket",
+];
+
 pub struct TreeSitterSearcher {
     parsers: HashMap<String, Parser>,
     languages: HashMap<String, Language>,
@@ -324,7 +339,25 @@ impl TreeSitterSearcher {
         })
     }
 
-    fn is_language_file(path: &Path, language: &str) -> bool {
+    /// Whether `language` (or an alias like "js") has a parser.
+    pub(super) fn supports(&self, language: &str) -> bool {
+        self.parsers.contains_key(language)
+    }
+
+    /// Parse `source` as `language`.
+    pub(super) fn parse(&mut self, language: &str, source: &str) -> Option<Tree> {
+        self.parsers.get_mut(language)?.parse(source, None)
+    }
+
+    /// The language of a file, from its extension (`.h` is treated as C).
+    pub(super) fn language_for_path(path: &Path) -> Option<&'static str> {
+        LANGUAGES
+            .iter()
+            .copied()
+            .find(|language| Self::is_language_file(path, language))
+    }
+
+    pub(super) fn is_language_file(path: &Path, language: &str) -> bool {
         let ext = path.extension().and_then(|e| e.to_str());
         match (language, ext) {
             ("rust", Some("rs")) => true,
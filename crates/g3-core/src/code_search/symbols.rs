@@ -0,0 +1,306 @@
+//! Symbol resolution for `find_definition` and `find_references`.
+//!
+//! Every supported file under the search paths is parsed and its syntax tree
+//! walked for identifier nodes whose text is the symbol, so hits in comments
+//! and strings are never reported. An identifier is a definition when it is
+//! the name of a declaration node (`function_item`, `class_definition`,
+//! `type_spec`, ...) and an import when it sits inside an import/use
+//! statement. Imports are then used to rank results: definitions in modules
+//! that other files import the symbol from, and references in files that
+//! define or import the symbol, come first.
+
+use anyhow::Result;
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tracing::debug;
+use tree_sitter::Node;
+
+use super::{SymbolKind, SymbolLocation, SymbolRequest, SymbolResponse, TreeSitterSearcher};
+
+/// Suffixes of declaration node kinds whose `name` (or `declarator`) field
+/// defines a symbol.
+const DECLARATION_SUFFIXES: &[&str] = &[
+    "_item",
+    "_definition",
+    "_declaration",
+    "_declarator",
+    "_spec",
+    "_signature",
+];
+
+/// Declaration node kinds not covered by `DECLARATION_SUFFIXES`.
+const DECLARATION_KINDS: &[&str] = &["enum_variant", "function", "signature", "class"];
+
+/// Node kinds of import statements, or prefixes of them.
+const IMPORT_KINDS: &[&str] = &[
+    "use_declaration",
+    "extern_crate_declaration",
+    "import",
+    "preproc_include",
+];
+
+/// A symbol occurrence found while scanning.
+struct Occurrence {
+    location: SymbolLocation,
+    /// Text of the enclosing import statement, for imports
+    import_text: Option<String>,
+}
+
+/// Find where `request.symbol` is defined.
+pub(super) fn find_definitions(
+    searcher: &mut TreeSitterSearcher,
+    request: &SymbolRequest,
+) -> Result<SymbolResponse> {
+    let (occurrences, files_searched) = scan(searcher, request)?;
+
+    let imports: Vec<String> = occurrences
+        .iter()
+        .filter_map(|o| o.import_text.clone())
+        .collect();
+    let mut definitions: Vec<(usize, SymbolLocation)> = occurrences
+        .into_iter()
+        .filter(|o| o.location.kind == SymbolKind::Definition)
+        .map(|o| {
+            let module = module_name(Path::new(&o.location.file));
+            let importers = imports
+                .iter()
+                .filter(|import| module.as_deref().is_some_and(|m| import.contains(m)))
+                .count();
+            (importers, o.location)
+        })
+        .collect();
+    // Most imported first; the sort is stable so file order is kept otherwise
+    definitions.sort_by_key(|(importers, _)| std::cmp::Reverse(*importers));
+
+    Ok(response(
+        request,
+        definitions.into_iter().map(|(_, l)| l).collect(),
+        files_searched,
+    ))
+}
+
+/// Find the imports of and references to `request.symbol` (not its definitions).
+pub(super) fn find_references(
+    searcher: &mut TreeSitterSearcher,
+    request: &SymbolRequest,
+) -> Result<SymbolResponse> {
+    let (occurrences, files_searched) = scan(searcher, request)?;
+
+    // Files that define or import the symbol are where its uses most likely
+    // resolve to it; list them first
+    let related: HashSet<String> = occurrences
+        .iter()
+        .filter(|o| o.location.kind != SymbolKind::Reference)
+        .map(|o| o.location.file.clone())
+        .collect();
+    let (mut locations, unrelated): (Vec<SymbolLocation>, Vec<SymbolLocation>) = occurrences
+        .into_iter()
+        .map(|o| o.location)
+        .filter(|l| l.kind != SymbolKind::Definition)
+        .partition(|l| related.contains(&l.file));
+    locations.extend(unrelated);
+
+    Ok(response(request, locations, files_searched))
+}
+
+fn response(
+    request: &SymbolRequest,
+    mut locations: Vec<SymbolLocation>,
+    files_searched: usize,
+) -> SymbolResponse {
+    let truncated = locations.len() > request.max_results;
+    locations.truncate(request.max_results);
+    SymbolResponse {
+        symbol: request.symbol.clone(),
+        locations,
+        files_searched,
+        truncated,
+    }
+}
+
+/// Walk the search paths and collect every occurrence of the symbol.
+fn scan(
+    searcher: &mut TreeSitterSearcher,
+    request: &SymbolRequest,
+) -> Result<(Vec<Occurrence>, usize)> {
+    let name = symbol_name(&request.symbol);
+    if name.is_empty() {
+        anyhow::bail!("Empty symbol");
+    }
+    if let Some(language) = &request.language {
+        if !searcher.supports(language) {
+            anyhow::bail!("Unsupported language: {}", language);
+        }
+    }
+
+    let paths = if request.paths.is_empty() {
+        vec![".".to_string()]
+    } else {
+        request.paths.clone()
+    };
+
+    let mut occurrences = Vec::new();
+    let mut files_searched = 0;
+    for search_path in &paths {
+        let walker = WalkBuilder::new(search_path)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build();
+        for entry in walker.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let language = match &request.language {
+                Some(language) if TreeSitterSearcher::is_language_file(path, language) => {
+                    language.as_str()
+                }
+                Some(_) => continue,
+                None => match TreeSitterSearcher::language_for_path(path) {
+                    Some(language) => language,
+                    None => continue,
+                },
+            };
+
+            files_searched += 1;
+            let Ok(source) = fs::read_to_string(path) else {
+                debug!("find symbol: skipping unreadable file {}", path.display());
+                continue;
+            };
+            // Cheap pre-filter before parsing
+            if !source.contains(name) {
+                continue;
+            }
+            let Some(tree) = searcher.parse(language, &source) else {
+                continue;
+            };
+            collect_occurrences(
+                tree.root_node(),
+                &source,
+                name,
+                &path.strip_prefix(".").unwrap_or(path).display().to_string(),
+                request.context_lines,
+                &mut occurrences,
+            );
+        }
+    }
+    Ok((occurrences, files_searched))
+}
+
+/// Depth-first walk collecting identifier leaves whose text is `name`.
+fn collect_occurrences(
+    root: Node<'_>,
+    source: &str,
+    name: &str,
+    file: &str,
+    context_lines: usize,
+    occurrences: &mut Vec<Occurrence>,
+) {
+    let mut cursor = root.walk();
+    loop {
+        let node = cursor.node();
+        if node.child_count() == 0 && is_identifier(&node) && &source[node.byte_range()] == name {
+            let import = enclosing_import(node);
+            let (kind, declaration) = match (import, declaration_kind(node)) {
+                (Some(_), _) => (SymbolKind::Import, None),
+                (None, Some(declaration)) => (SymbolKind::Definition, Some(declaration)),
+                (None, None) => (SymbolKind::Reference, None),
+            };
+            let start = node.start_position();
+            occurrences.push(Occurrence {
+                location: SymbolLocation {
+                    file: file.to_string(),
+                    line: start.row + 1,
+                    column: start.column + 1,
+                    kind,
+                    declaration: declaration.map(str::to_string),
+                    context: numbered_context(source, start.row, context_lines),
+                },
+                import_text: import.map(|n| source[n.byte_range()].to_string()),
+            });
+        }
+
+        if cursor.goto_first_child() || cursor.goto_next_sibling() {
+            continue;
+        }
+        loop {
+            if !cursor.goto_parent() {
+                return;
+            }
+            if cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+}
+
+/// Whether a leaf node is a name rather than part of a comment or literal.
+fn is_identifier(node: &Node<'_>) -> bool {
+    let kind = node.kind();
+    node.is_named()
+        && !["comment", "string", "char", "escape", "doc"]
+            .iter()
+            .any(|k| kind.contains(k))
+}
+
+/// The declaration kind if `node` is the name of a declaration.
+fn declaration_kind(node: Node<'_>) -> Option<&'static str> {
+    let parent = node.parent()?;
+    let kind = parent.kind();
+    let is_declaration = DECLARATION_SUFFIXES.iter().any(|s| kind.ends_with(s))
+        || DECLARATION_KINDS.contains(&kind)
+        // C/C++ `struct Foo { ... }`, but not `struct Foo x;`
+        || (kind.ends_with("_specifier") && parent.child_by_field_name("body").is_some());
+    if !is_declaration {
+        return None;
+    }
+    let is_name = ["name", "declarator"]
+        .iter()
+        .filter_map(|field| parent.child_by_field_name(field))
+        .any(|n| n.id() == node.id());
+    is_name.then_some(kind)
+}
+
+/// The import statement containing `node`, if any.
+fn enclosing_import(node: Node<'_>) -> Option<Node<'_>> {
+    let mut current = node.parent();
+    while let Some(n) = current {
+        if IMPORT_KINDS.iter().any(|k| n.kind().starts_with(k)) {
+            return Some(n);
+        }
+        current = n.parent();
+    }
+    None
+}
+
+/// Last segment of a qualified symbol (`crate::foo::Bar`, `pkg.Bar`).
+pub(super) fn symbol_name(symbol: &str) -> &str {
+    symbol
+        .trim()
+        .rsplit([':', '.'])
+        .next()
+        .unwrap_or("")
+}
+
+/// The name a file is imported by: its stem, or its directory for module
+/// index files (`mod.rs`, `__init__.py`, `index.js`, ...).
+fn module_name(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    if matches!(stem, "mod" | "lib" | "main" | "__init__" | "index") {
+        path.parent()?.file_name()?.to_str().map(|s| s.to_string())
+    } else {
+        Some(stem.to_string())
+    }
+}
+
+/// `context_lines` lines either side of `row` (0-indexed), numbered.
+fn numbered_context(source: &str, row: usize, context_lines: usize) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let start = row.saturating_sub(context_lines);
+    let end = (row + context_lines + 1).min(lines.len());
+    (start..end)
+        .map(|i| format!("{:>6}\t{}", i + 1, lines[i]))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
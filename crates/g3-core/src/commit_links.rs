@@ -0,0 +1,291 @@
+//! Links between sessions and the git commits they produced.
+//!
+//! Both directions are recorded, so any change in history can be traced
+//! back to the transcript of the session that made it:
+//! - the commit message names the session in a `G3-Session: <id>` trailer
+//!   (`git_commit` adds it through `[git] commit_template`)
+//! - the session lists its commits in `.g3/sessions/<id>/commits.jsonl`
+//!
+//! `git_commit` records the agent's own commits. Commits the user makes
+//! after a run are linked with `link_commit` (`g3 link-commit`), which the
+//! hooks written by `install_hooks` run on every commit made while a session
+//! is recent. `find_session` goes from a commit to its session.
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use git2::{Commit, Repository};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::paths::get_commits_file;
+use crate::session_continuation::get_session_dir;
+
+/// Trailer naming the session in a commit message.
+pub const SESSION_TRAILER: &str = "G3-Session";
+
+/// Marks the hooks written by `install_hooks`, which may be overwritten.
+const HOOK_MARKER: &str = "# Installed by g3";
+
+/// A commit linked to a session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommitLink {
+    pub sha: String,
+    /// First line of the commit message
+    pub summary: String,
+    pub branch: Option<String>,
+    /// When it was linked (RFC 3339)
+    pub timestamp: String,
+    /// Whether the agent made the commit (with `git_commit`)
+    pub by_agent: bool,
+}
+
+impl CommitLink {
+    pub fn new(commit: &Commit, branch: Option<String>, by_agent: bool) -> Self {
+        Self {
+            sha: commit.id().to_string(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            branch,
+            timestamp: Utc::now().to_rfc3339(),
+            by_agent,
+        }
+    }
+
+    /// Add this commit to the session's `commits.jsonl`, unless it is
+    /// linked already. Returns whether it was added.
+    pub fn record(&self, session_id: &str) -> Result<bool> {
+        if session_commits(session_id)
+            .iter()
+            .any(|link| link.sha == self.sha)
+        {
+            return Ok(false);
+        }
+        let path = get_commits_file(session_id);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+        debug!("Linked commit {} to session {}", self.sha, session_id);
+        Ok(true)
+    }
+}
+
+/// The commits linked to a session, oldest first.
+pub fn session_commits(session_id: &str) -> Vec<CommitLink> {
+    std::fs::read_to_string(get_commits_file(session_id))
+        .map(|log| {
+            log.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The session named by the last `G3-Session:` trailer of `message`.
+pub fn session_trailer(message: &str) -> Option<String> {
+    let prefix = format!("{}:", SESSION_TRAILER);
+    message
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(&prefix))
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+}
+
+/// The session that produced commit `rev` of the repository at `repo_dir`:
+/// the one its trailer names, or else one that has it in `commits.jsonl`.
+pub fn find_session(repo_dir: &Path, rev: &str) -> Result<Option<String>> {
+    let repo = Repository::discover(repo_dir)?;
+    let commit = resolve(&repo, rev)?;
+    if let Some(session_id) = session_trailer(commit.message().unwrap_or_default()) {
+        return Ok(Some(session_id));
+    }
+    let sha = commit.id().to_string();
+    Ok(crate::sessions::list_sessions()?
+        .into_iter()
+        .find(|session| session_commits(&session.id).iter().any(|l| l.sha == sha))
+        .map(|session| session.id))
+}
+
+/// Link commit `rev` of the repository at `repo_dir` to `session_id`, or
+/// to the session its trailer names, or to the current session
+/// (`.g3/session`). Returns the session and the link.
+pub fn link_commit(
+    repo_dir: &Path,
+    rev: &str,
+    session_id: Option<&str>,
+) -> Result<(String, CommitLink)> {
+    let repo = Repository::discover(repo_dir)?;
+    let commit = resolve(&repo, rev)?;
+    let session_id = match session_id {
+        Some(session_id) => session_id.to_string(),
+        None => match session_trailer(commit.message().unwrap_or_default()) {
+            Some(session_id) => session_id,
+            None => current_session().context("No session given and no current session")?,
+        },
+    };
+    let branch = repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_string));
+    let link = CommitLink::new(&commit, branch, false);
+    link.record(&session_id)?;
+    Ok((session_id, link))
+}
+
+/// Write `prepare-commit-msg` and `post-commit` hooks into the repository
+/// at `repo_dir` that link every commit to the current session, if it was
+/// active in the last 24 hours: the first adds the trailer, the second runs
+/// `g3 link-commit`. A hook g3 didn't write is left alone and reported as
+/// an error. Returns the hooks written.
+pub fn install_hooks(repo_dir: &Path) -> Result<Vec<PathBuf>> {
+    let repo = Repository::discover(repo_dir)?;
+    let hooks_dir = repo.path().join("hooks");
+    std::fs::create_dir_all(&hooks_dir)?;
+
+    let hooks = [
+        (
+            "prepare-commit-msg",
+            format!(
+                r#"#!/bin/sh
+{marker}: names the current g3 session in the commit message
+session=$(readlink .g3/session 2>/dev/null) || exit 0
+[ -n "$(find .g3/session/ -maxdepth 1 -name latest.json -mmin -1440 2>/dev/null)" ] || exit 0
+grep -q '^{trailer}:' "$1" && exit 0
+git interpret-trailers --in-place --trailer "{trailer}: $(basename "$session")" "$1"
+"#,
+                marker = HOOK_MARKER,
+                trailer = SESSION_TRAILER
+            ),
+        ),
+        (
+            "post-commit",
+            format!(
+                r#"#!/bin/sh
+{marker}: records the commit in the g3 session it names
+git log -1 --format=%B | grep -q '^{trailer}:' || exit 0
+command -v g3 >/dev/null 2>&1 && g3 link-commit HEAD >/dev/null 2>&1
+exit 0
+"#,
+                marker = HOOK_MARKER,
+                trailer = SESSION_TRAILER
+            ),
+        ),
+    ];
+
+    let mut written = Vec::new();
+    for (name, script) in hooks {
+        let path = hooks_dir.join(name);
+        if let Ok(existing) = std::fs::read_to_string(&path) {
+            if !existing.contains(HOOK_MARKER) {
+                bail!(
+                    "{} already exists; remove it or link commits with `g3 link-commit`",
+                    path.display()
+                );
+            }
+        }
+        std::fs::write(&path, script)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn resolve<'r>(repo: &'r Repository, rev: &str) -> Result<Commit<'r>> {
+    repo.revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| format!("No commit '{}'", rev))
+}
+
+/// The session `.g3/session` points to.
+fn current_session() -> Option<String> {
+    let target = std::fs::read_link(get_session_dir()).ok()?;
+    Some(target.file_name()?.to_str()?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    fn commit_file(repo: &Repository, name: &str, message: &str) -> git2::Oid {
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join(name), message).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("g3 test", "g3@example.com").unwrap();
+        let parent = repo.head().ok().map(|h| h.peel_to_commit().unwrap());
+        let parents: Vec<&Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_session_trailer() {
+        assert_eq!(
+            session_trailer("Fix parser\n\nG3-Session: fix_parser_1a2b\n"),
+            Some("fix_parser_1a2b".to_string())
+        );
+        assert_eq!(session_trailer("Fix parser\n\nG3-Session: \n"), None);
+        assert_eq!(session_trailer("Fix parser"), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_commits_are_traced_to_sessions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old_dir = std::env::current_dir().unwrap();
+        std::env::set_current_dir(temp_dir.path()).unwrap();
+
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let by_agent = commit_file(&repo, "a.txt", "Add a\n\nG3-Session: agent_run\n");
+        let by_user = commit_file(&repo, "b.txt", "Tidy up b");
+        let untracked = commit_file(&repo, "c.txt", "Unrelated");
+
+        let linked = link_commit(temp_dir.path(), &by_user.to_string(), Some("agent_run"));
+        let relinked = link_commit(temp_dir.path(), "HEAD~1", Some("agent_run"));
+        let found_agent = find_session(temp_dir.path(), &by_agent.to_string());
+        let found_user = find_session(temp_dir.path(), "HEAD~1");
+        let found_none = find_session(temp_dir.path(), &untracked.to_string());
+        // Without a session, a trailer or a current session there is nothing to link to
+        let orphan = link_commit(temp_dir.path(), "HEAD", None);
+        let commits = session_commits("agent_run");
+        let hooks = install_hooks(temp_dir.path());
+        std::env::set_current_dir(old_dir).unwrap();
+
+        let (session_id, link) = linked.unwrap();
+        assert_eq!(session_id, "agent_run");
+        assert_eq!(link.summary, "Tidy up b");
+        assert!(!link.by_agent);
+        relinked.unwrap();
+        assert_eq!(commits.len(), 1, "a commit is linked once");
+        assert_eq!(found_agent.unwrap().as_deref(), Some("agent_run"));
+        assert_eq!(found_user.unwrap().as_deref(), Some("agent_run"));
+        assert_eq!(found_none.unwrap(), None);
+        assert!(orphan.is_err());
+
+        let hooks = hooks.unwrap();
+        assert_eq!(hooks.len(), 2);
+        let prepare = std::fs::read_to_string(&hooks[0]).unwrap();
+        assert!(prepare.contains("G3-Session: $(basename"));
+    }
+}
@@ -8,7 +8,7 @@ use anyhow::Result;
 use g3_providers::{CompletionRequest, Message, MessageRole, ProviderRegistry};
 use tracing::{debug, error, warn};
 
-use crate::context_window::ContextWindow;
+use crate::context_window::{ContextWindow, PreservationPolicy};
 use crate::provider_config;
 use crate::ui_writer::UiWriter;
 
@@ -103,12 +103,16 @@ pub fn should_disable_thinking(
 
 /// Build the summary request messages from conversation history.
 pub fn build_summary_messages(context_window: &ContextWindow) -> Vec<Message> {
-    let summary_prompt = context_window.create_summary_prompt();
-    
-    let conversation_text = context_window
-        .conversation_history
+    summary_messages(
+        context_window.create_summary_prompt(),
+        &context_window.conversation_history,
+    )
+}
+
+fn summary_messages(summary_prompt: String, history: &[Message]) -> Vec<Message> {
+    let conversation_text = history
         .iter()
-        .map(|m| format!("{:?}: {}", m.role, m.content))
+        .map(|m| format!("{:?}: {}", m.role, m.full_content()))
         .collect::<Vec<_>>()
         .join("\n\n");
     
@@ -168,8 +172,10 @@ pub async fn perform_compaction<W: UiWriter>(
         summary_max_tokens, context_window.used_tokens
     );
     
-    // Build summary request
-    let summary_messages = build_summary_messages(context_window);
+    // Build summary request, reading stored bodies without blocking the runtime
+    let mut history = context_window.conversation_history.clone();
+    g3_providers::load_bodies(&mut history).await;
+    let summary_messages = summary_messages(context_window.create_summary_prompt(), &history);
     let provider = providers.get(None)?;
     
     let disable_thinking = should_disable_thinking(config, provider.name(), summary_max_tokens);
@@ -186,6 +192,7 @@ pub async fn perform_compaction<W: UiWriter>(
         stream: false,
         tools: None,
         disable_thinking,
+        cancellation_token: None,
     };
     
     // Execute summary request
@@ -193,9 +200,11 @@ pub async fn perform_compaction<W: UiWriter>(
         Ok(summary_response) => {
             // Note: ACD dehydration now happens at the end of each turn in Agent::dehydrate_context()
             // Compaction just does lossy summarization of the existing stubs + summaries
-            let chars_saved = context_window.reset_with_summary(
+            let chars_saved = context_window.reset_with_summary_preserving(
                 summary_response.content,
                 compaction_config.latest_user_msg,
+                None,
+                &PreservationPolicy::from(&config.summarization),
             );
             Ok(CompactionResult::success(chars_saved))
         }
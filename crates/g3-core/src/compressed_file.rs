@@ -0,0 +1,98 @@
+//! Session files stored compressed with zstd.
+//!
+//! Long autonomous runs write large session logs and offload many large
+//! tool results to `thinned/`, mostly repetitive text that zstd shrinks by
+//! an order of magnitude. With `[session_compression]` enabled these files
+//! are written as `<name>.zst` instead of `<name>`. Readers go through
+//! `read`, given the uncompressed path, which finds either, so sessions
+//! written before (or without) compression still load.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Extension appended to the name of a compressed file.
+pub const EXTENSION: &str = "zst";
+
+/// Where `path` is stored compressed: `path` with `.zst` appended.
+pub fn compressed_path(path: &Path) -> PathBuf {
+    with_suffix(path, EXTENSION)
+}
+
+/// Write `content` to `path`, or compressed at zstd `level` to `path.zst`.
+///
+/// The file is replaced atomically, and the other variant is removed so
+/// readers never find a stale copy. Returns the path written.
+pub fn write(path: &Path, content: &[u8], level: Option<i32>) -> io::Result<PathBuf> {
+    let (target, other) = match level {
+        Some(_) => (compressed_path(path), path.to_path_buf()),
+        None => (path.to_path_buf(), compressed_path(path)),
+    };
+    let tmp = with_suffix(&target, "tmp");
+    match level {
+        Some(level) => std::fs::write(&tmp, zstd::encode_all(content, level)?)?,
+        None => std::fs::write(&tmp, content)?,
+    }
+    std::fs::rename(&tmp, &target)?;
+    let _ = std::fs::remove_file(other);
+    Ok(target)
+}
+
+/// Read the file written for `path`: `path` itself, or else `path.zst`
+/// decompressed. A path ending in `.zst` is decompressed as well.
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+    if path.extension().is_some_and(|e| e == EXTENSION) {
+        return zstd::decode_all(std::fs::File::open(path)?);
+    }
+    match std::fs::read(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            match std::fs::File::open(compressed_path(path)) {
+                Ok(file) => zstd::decode_all(file),
+                Err(_) => Err(e),
+            }
+        }
+        result => result,
+    }
+}
+
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    String::from_utf8(read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Whether a file was written for `path`, compressed or not.
+pub fn exists(path: &Path) -> bool {
+    path.exists() || compressed_path(path).exists()
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_either_variant() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        let content = "{\"role\": \"tool\"}\n".repeat(1000);
+
+        let written = write(&path, content.as_bytes(), Some(3)).unwrap();
+        assert_eq!(written, dir.path().join("session.json.zst"));
+        assert!(!path.exists() && exists(&path));
+        assert!(std::fs::metadata(&written).unwrap().len() < content.len() as u64 / 10);
+        assert_eq!(read_to_string(&path).unwrap(), content);
+        assert_eq!(read_to_string(&written).unwrap(), content);
+
+        // Writing uncompressed replaces the compressed copy
+        assert_eq!(write(&path, b"plain", None).unwrap(), path);
+        assert!(!written.exists());
+        assert_eq!(read_to_string(&path).unwrap(), "plain");
+
+        let missing = read(&dir.path().join("missing.json")).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -6,10 +6,13 @@
 //! - Context thinning (reducing context size by saving large tool results to disk)
 //! - Summarization triggers
 
-use g3_providers::{Message, MessageRole, Usage};
+use g3_providers::{Message, MessageKind, MessageRole, StoredBody, Usage};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use tracing::{debug, warn};
 
+use crate::compressed_file;
 use crate::paths::get_thinned_dir;
 use crate::ToolCall;
 
@@ -17,9 +20,68 @@ use crate::ToolCall;
 // Types
 // ============================================================================
 
+/// Prefix of the summary message added by a compaction reset.
+const SUMMARY_PREFIX: &str = "Previous conversation summary:\n\n";
+
+/// Which messages survive a summary reset verbatim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreservationPolicy {
+    /// Leading system messages to keep. The project context (AGENTS.md,
+    /// memory, ...) that follows the system prompt is kept regardless.
+    pub system_messages: usize,
+    /// Keep the first user message (the original task)
+    pub first_user_message: bool,
+    /// Keep messages marked as pinned
+    pub pinned: bool,
+}
+
+impl Default for PreservationPolicy {
+    fn default() -> Self {
+        Self {
+            system_messages: 1,
+            first_user_message: false,
+            pinned: true,
+        }
+    }
+}
+
+impl From<&g3_config::SummarizationConfig> for PreservationPolicy {
+    fn from(config: &g3_config::SummarizationConfig) -> Self {
+        Self {
+            system_messages: config.preserved_system_messages,
+            first_user_message: config.preserve_first_user_message,
+            pinned: config.preserve_pinned,
+        }
+    }
+}
+
+/// Number of characters shown in a message preview.
+const PREVIEW_CHARS: usize = 120;
+
+/// Structured snapshot of the context window, one entry per message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextReport {
+    pub messages: Vec<MessageReport>,
+    pub used_tokens: u32,
+    pub total_tokens: u32,
+    pub percentage_used: f32,
+}
+
+/// A single message in a [`ContextReport`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageReport {
+    pub id: String,
+    pub role: MessageRole,
+    pub kind: MessageKind,
+    /// Estimated tokens for the message content
+    pub tokens: u32,
+    /// First characters of the content, on a single line
+    pub preview: String,
+}
+
 /// Result of a context thinning operation.
 /// Contains semantic data for the UI layer to format.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ThinResult {
     pub scope: ThinScope,
     pub before_percentage: u32,
@@ -33,7 +95,8 @@ pub struct ThinResult {
 }
 
 /// Scope for context thinning operations
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ThinScope {
     /// Process only the first third of messages (incremental thinning)
     FirstThird,
@@ -80,6 +143,21 @@ pub struct ContextWindow {
     pub conversation_history: Vec<Message>,
     /// Track the last percentage at which we thinned
     pub last_thinning_percentage: u32,
+    /// Where large message bodies are moved out of memory (None = keep all in memory)
+    #[serde(skip)]
+    pub body_store: Option<BodyStore>,
+    /// zstd level session logs and thinned tool results of this window are
+    /// compressed with (None = written uncompressed)
+    #[serde(skip)]
+    pub compression_level: Option<i32>,
+}
+
+/// Directory and size threshold for storing large message bodies on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BodyStore {
+    pub dir: PathBuf,
+    /// Bodies longer than this many bytes are stored
+    pub threshold: usize,
 }
 
 impl ContextWindow {
@@ -90,9 +168,17 @@ impl ContextWindow {
             cumulative_tokens: 0,
             conversation_history: Vec::new(),
             last_thinning_percentage: 0,
+            body_store: None,
+            compression_level: None,
         }
     }
 
+    /// Store bodies of messages added from now on in `dir` when they exceed
+    /// `threshold` bytes. A threshold of 0 disables storing.
+    pub fn set_body_store(&mut self, dir: PathBuf, threshold: usize) {
+        self.body_store = (threshold > 0).then_some(BodyStore { dir, threshold });
+    }
+
     // ========================================================================
     // Message Management
     // ========================================================================
@@ -102,13 +188,26 @@ impl ContextWindow {
     }
 
     /// Add a message with optional token count from the provider
-    pub fn add_message_with_tokens(&mut self, message: Message, tokens: Option<u32>) {
+    pub fn add_message_with_tokens(&mut self, mut message: Message, tokens: Option<u32>) {
         if message.content.trim().is_empty() {
             warn!("Skipping empty message to avoid API error");
             return;
         }
 
-        let token_count = tokens.unwrap_or_else(|| Self::estimate_tokens(&message.content));
+        let token_count = tokens.unwrap_or_else(|| Self::message_tokens(&message));
+
+        // System messages are edited in place and always sent, so they stay in memory
+        if let Some(store) = &self.body_store {
+            if !matches!(message.role, MessageRole::System) {
+                if let Err(e) = message.store_body(&store.dir, store.threshold) {
+                    warn!(
+                        "Failed to store message body in {}: {}",
+                        store.dir.display(),
+                        e
+                    );
+                }
+            }
+        }
         self.used_tokens += token_count;
         self.cumulative_tokens += token_count;
         self.conversation_history.push(message);
@@ -122,6 +221,7 @@ impl ContextWindow {
     /// Clear the conversation history while preserving system messages.
     /// Used by /clear command to start fresh.
     pub fn clear_conversation(&mut self) {
+        let bodies = self.referenced_bodies();
         let system_messages: Vec<Message> = self
             .conversation_history
             .iter()
@@ -133,9 +233,46 @@ impl ContextWindow {
         self.used_tokens = self
             .conversation_history
             .iter()
-            .map(|m| Self::estimate_tokens(&m.content))
+            .map(Self::message_tokens)
             .sum();
         self.last_thinning_percentage = 0;
+        self.remove_dropped_bodies(bodies);
+    }
+
+    /// Stored body files the history refers to, either as a message's stored
+    /// body or by path, as a thinned tool result does.
+    fn referenced_bodies(&self) -> HashSet<PathBuf> {
+        let Some(entries) = self
+            .body_store
+            .as_ref()
+            .and_then(|store| std::fs::read_dir(&store.dir).ok())
+        else {
+            return HashSet::new();
+        };
+
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                let shown = path.display().to_string();
+                self.conversation_history.iter().any(|m| {
+                    m.stored_body.as_ref().is_some_and(|b| &b.path == path)
+                        || m.content.contains(&shown)
+                })
+            })
+            .collect()
+    }
+
+    /// Delete the body files in `before` that the history no longer refers
+    /// to. Bodies of dehydrated messages are never in `before`, so the
+    /// fragments holding them keep them.
+    fn remove_dropped_bodies(&self, before: HashSet<PathBuf>) {
+        let after = self.referenced_bodies();
+        for path in before.difference(&after) {
+            if let Err(e) = std::fs::remove_file(path) {
+                debug!("Failed to remove message body {}: {}", path.display(), e);
+            }
+        }
     }
 
     // ========================================================================
@@ -177,11 +314,20 @@ impl ContextWindow {
         self.used_tokens = self
             .conversation_history
             .iter()
-            .map(|m| Self::estimate_tokens(&m.content))
+            .map(Self::message_tokens)
             .sum();
         debug!("Recalculated tokens after thinning: {} tokens", self.used_tokens);
     }
 
+    /// Token estimate for a message, without loading a stored body from disk.
+    pub(crate) fn message_tokens(message: &Message) -> u32 {
+        match &message.stored_body {
+            // Stored bodies are large tool output; estimate them like code
+            Some(body) => ((body.len as f32 / 3.0).ceil() * 1.1).ceil() as u32,
+            None => Self::estimate_tokens(&message.content),
+        }
+    }
+
     /// More accurate token estimation.
     pub fn estimate_tokens(text: &str) -> u32 {
         // Heuristic:
@@ -200,6 +346,33 @@ impl ContextWindow {
     // Capacity Queries
     // ========================================================================
 
+    /// Per-message role, token estimate, and preview, plus overall usage.
+    pub fn report(&self) -> ContextReport {
+        let messages = self
+            .conversation_history
+            .iter()
+            .map(|message| MessageReport {
+                id: message.id.clone(),
+                role: message.role.clone(),
+                kind: message.kind,
+                tokens: Self::message_tokens(message),
+                preview: message
+                    .content
+                    .chars()
+                    .take(PREVIEW_CHARS)
+                    .collect::<String>()
+                    .replace(['\n', '\r'], " "),
+            })
+            .collect();
+
+        ContextReport {
+            messages,
+            used_tokens: self.used_tokens,
+            total_tokens: self.total_tokens,
+            percentage_used: self.percentage_used(),
+        }
+    }
+
     pub fn percentage_used(&self) -> f32 {
         if self.total_tokens == 0 {
             0.0
@@ -265,85 +438,168 @@ Format this as a detailed but concise summary that can be used to resume the con
         summary: String,
         latest_user_message: Option<String>,
         stub: Option<String>,
+    ) -> usize {
+        self.reset_with_summary_preserving(
+            summary,
+            latest_user_message,
+            stub,
+            &PreservationPolicy::default(),
+        )
+    }
+
+    /// Reset context window with a summary, keeping the messages selected by
+    /// `policy` verbatim ahead of it.
+    pub fn reset_with_summary_preserving(
+        &mut self,
+        summary: String,
+        latest_user_message: Option<String>,
+        stub: Option<String>,
+        policy: &PreservationPolicy,
     ) -> usize {
         let old_chars: usize = self
             .conversation_history
             .iter()
-            .map(|m| m.content.len())
+            .map(|m| m.content_len())
             .sum();
 
+        let bodies = self.referenced_bodies();
+
         // Extract preserved messages before clearing
-        let preserved = self.extract_preserved_messages();
+        let preserved = self.extract_preserved_messages(policy);
 
         // Clear and rebuild
         self.conversation_history.clear();
         self.used_tokens = 0;
 
-        // Re-add preserved messages
-        if let Some(system_prompt) = preserved.system_prompt {
-            self.add_message(system_prompt);
-        }
-        if let Some(project_context) = preserved.project_context {
-            self.add_message(project_context);
-        }
+        // Preserved messages (system prefix, original task, pinned) go first
+        let mut messages = preserved.prefix;
 
         // Add ACD stub if provided (before summary so LLM knows about dehydrated context)
         if let Some(stub_content) = stub {
-            self.add_message(Message::new(MessageRole::System, stub_content));
+            messages.push(Message::new(MessageRole::System, stub_content));
         }
 
         // Add the summary as a USER message (not System) to maintain proper alternation.
         // This allows: [Summary as User] -> [Last Assistant] -> [Latest User]
         // which is valid User/Assistant alternation.
-        self.add_message(Message::new(
+        messages.push(Message::new(
             MessageRole::User,
-            format!("Previous conversation summary:\n\n{}", summary),
+            format!("{}{}", SUMMARY_PREFIX, summary),
         ));
 
         // Add the last assistant message if present (preserves continuity)
         if let Some(assistant_msg) = preserved.last_assistant_message {
-            self.add_message(assistant_msg);
+            messages.push(assistant_msg);
         }
 
         // Add the latest user message if provided
         if let Some(user_msg) = latest_user_message {
-            self.add_message(Message::new(MessageRole::User, user_msg));
+            messages.push(Message::new(MessageRole::User, user_msg));
+        }
+
+        // A preserved user message right before the summary would break the
+        // alternation, so adjacent user turns become one
+        for message in merge_adjacent_user_turns(messages) {
+            self.add_message(message);
         }
 
+        self.remove_dropped_bodies(bodies);
+
         let new_chars: usize = self
             .conversation_history
             .iter()
-            .map(|m| m.content.len())
+            .map(|m| m.content_len())
             .sum();
         old_chars.saturating_sub(new_chars)
     }
 
+    /// Pin the most recent user message so it survives summarization verbatim.
+    /// Returns false if there is no user message to pin.
+    pub fn pin_last_user_message(&mut self) -> bool {
+        match self
+            .conversation_history
+            .iter_mut()
+            .rev()
+            .find(|m| {
+                matches!(m.role, MessageRole::User) && !m.content.starts_with(SUMMARY_PREFIX)
+            })
+        {
+            Some(message) => {
+                message.kind = MessageKind::Pinned;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Extract messages that should be preserved across compaction.
-    fn extract_preserved_messages(&self) -> PreservedMessages {
-        let system_prompt = self.conversation_history.first().cloned();
+    fn extract_preserved_messages(&self, policy: &PreservationPolicy) -> PreservedMessages {
+        let history = &self.conversation_history;
+        let mut keep = vec![false; history.len()];
 
-        // Look for project context (AGENTS.md, memory, etc.) in the second message
-        let project_context = self.conversation_history.get(1).and_then(|msg| {
-            if matches!(msg.role, MessageRole::System)
-                && msg.content.contains("Agent Configuration")
-            {
-                Some(msg.clone())
-            } else {
-                None
+        // Leading system messages, starting with the system prompt
+        for (idx, _) in history
+            .iter()
+            .enumerate()
+            .take_while(|(_, m)| matches!(m.role, MessageRole::System))
+            .take(policy.system_messages)
+        {
+            keep[idx] = true;
+        }
+
+        // Project context (AGENTS.md, memory, etc.) in the second message
+        if history.get(1).is_some_and(|msg| {
+            matches!(msg.role, MessageRole::System) && msg.content.contains("Agent Configuration")
+        }) {
+            keep[1] = true;
+        }
+
+        // The original task. It is re-added as pinned so later compactions keep it too.
+        let first_user = if policy.first_user_message {
+            history.iter().position(|m| {
+                matches!(m.role, MessageRole::User)
+                    && !m.is_dehydrated_stub()
+                    && !m.content.starts_with(SUMMARY_PREFIX)
+            })
+        } else {
+            None
+        };
+        if let Some(idx) = first_user {
+            keep[idx] = true;
+        }
+
+        if policy.pinned {
+            for (idx, message) in history.iter().enumerate() {
+                if message.is_pinned() {
+                    keep[idx] = true;
+                }
             }
-        });
+        }
 
-        // Find the last assistant message in the conversation
-        let last_assistant_message = self
-            .conversation_history
+        let prefix = history
             .iter()
+            .enumerate()
+            .filter(|(idx, _)| keep[*idx])
+            .map(|(idx, m)| {
+                let mut message = without_merged_summary(m);
+                if Some(idx) == first_user {
+                    message.kind = MessageKind::Pinned;
+                }
+                message
+            })
+            .collect();
+
+        // Find the last assistant message in the conversation (unless already kept)
+        let last_assistant_message = history
+            .iter()
+            .enumerate()
             .rev()
-            .find(|m| matches!(m.role, MessageRole::Assistant))
-            .cloned();
+            .find(|(_, m)| matches!(m.role, MessageRole::Assistant))
+            .filter(|(idx, _)| !keep[*idx])
+            .map(|(_, m)| m.clone());
 
         PreservedMessages {
-            system_prompt,
-            project_context,
+            prefix,
             last_assistant_message,
         }
     }
@@ -395,8 +651,10 @@ Format this as a detailed but concise summary that can be used to resume the con
         // Collect and apply modifications
         let modifications =
             self.collect_thin_modifications(end_index, &tmp_dir, scope.file_prefix());
+        let bodies = self.referenced_bodies();
         let (leaned_count, tool_call_leaned_count, chars_saved) =
             self.apply_thin_modifications(&modifications);
+        self.remove_dropped_bodies(bodies);
 
         // Recalculate token usage after thinning
         self.recalculate_tokens();
@@ -449,20 +707,33 @@ Format this as a detailed but concise summary that can be used to resume the con
             if matches!(message.role, MessageRole::User)
                 && message.content.starts_with("Tool result:")
                 && !self.is_todo_tool_result(i)
-                && message.content.len() > 500
+                && message.content_len() > 500
             {
-                if let Some(m) =
-                    Self::create_tool_result_modification(&message.content, i, tmp_dir, file_prefix)
-                {
-                    modifications.push(m);
-                }
+                // A stored body is already on disk; point at it instead of loading it
+                let m = match &message.stored_body {
+                    Some(body) => Some(Self::stored_tool_result_modification(body, i)),
+                    None => self.create_tool_result_modification(
+                        &message.content,
+                        i,
+                        tmp_dir,
+                        file_prefix,
+                    ),
+                };
+                modifications.extend(m);
             }
 
-            // Process Assistant messages that contain tool calls with large arguments
-            if matches!(message.role, MessageRole::Assistant) {
-                if let Some(m) =
-                    Self::create_tool_call_modification(&message.content, i, tmp_dir, file_prefix)
-                {
+            // Process Assistant messages that contain tool calls with large arguments.
+            // Stored bodies are only loaded when their preview shows a tool call.
+            if matches!(message.role, MessageRole::Assistant)
+                && (message.stored_body.is_none()
+                    || Self::find_tool_call(&message.content).is_some())
+            {
+                if let Some(m) = self.create_tool_call_modification(
+                    &message.full_content(),
+                    i,
+                    tmp_dir,
+                    file_prefix,
+                ) {
                     modifications.push(m);
                 }
             }
@@ -493,7 +764,7 @@ Format this as a detailed but concise summary that can be used to resume the con
                 } else {
                     tool_call_leaned_count += 1;
                 }
-                msg.content = new_content.clone();
+                msg.set_content(new_content.clone());
                 chars_saved += saved;
             }
         }
@@ -521,6 +792,7 @@ Format this as a detailed but concise summary that can be used to resume the con
 
     /// Create a modification for thinning a tool result message.
     fn create_tool_result_modification(
+        &self,
         content: &str,
         index: usize,
         tmp_dir: &str,
@@ -533,7 +805,7 @@ Format this as a detailed but concise summary that can be used to resume the con
         let filename = format!("{}_tool_result_{}_{}.txt", file_prefix, timestamp, index);
         let file_path = format!("{}/{}", tmp_dir, filename);
 
-        if let Err(e) = std::fs::write(&file_path, content) {
+        if let Err(e) = self.write_thinned(&file_path, content) {
             warn!("Failed to write thinned content to {}: {}", file_path, e);
             return None;
         }
@@ -554,19 +826,45 @@ Format this as a detailed but concise summary that can be used to resume the con
         })
     }
 
+    /// Create a modification for thinning a tool result whose body is
+    /// already stored on disk, sized by the stored length.
+    fn stored_tool_result_modification(body: &StoredBody, index: usize) -> ThinModification {
+        let new_content = format!("Tool result saved to {}", body.path.display());
+        let chars_saved = body.len.saturating_sub(new_content.len());
+
+        debug!(
+            "Thinned stored tool result {} ({} chars) to {}",
+            index,
+            body.len,
+            body.path.display()
+        );
+
+        ThinModification::ReplaceContent {
+            index,
+            new_content,
+            chars_saved,
+        }
+    }
+
+    /// Start of the first JSON tool call in `content`, if any.
+    fn find_tool_call(content: &str) -> Option<usize> {
+        content
+            .find(r#"{"tool":"#)
+            .or_else(|| content.find(r#"{ "tool":"#))
+            .or_else(|| content.find(r#"{"tool" :"#))
+            .or_else(|| content.find(r#"{ "tool" :"#))
+    }
+
     /// Create a modification for thinning tool calls in an assistant message.
     fn create_tool_call_modification(
+        &self,
         content: &str,
         index: usize,
         tmp_dir: &str,
         file_prefix: &str,
     ) -> Option<ThinModification> {
         // Look for JSON tool call patterns
-        let tool_call_start = content
-            .find(r#"{"tool":"#)
-            .or_else(|| content.find(r#"{ "tool":"#))
-            .or_else(|| content.find(r#"{"tool" :"#))
-            .or_else(|| content.find(r#"{ "tool" :"#))?;
+        let tool_call_start = Self::find_tool_call(content)?;
 
         let json_portion = &content[tool_call_start..];
         let json_end = Self::find_json_end(json_portion)?;
@@ -579,7 +877,7 @@ Format this as a detailed but concise summary that can be used to resume the con
         // Handle write_file tool calls
         if tool_call.tool == "write_file" {
             if let Some((saved, new_args)) =
-                Self::thin_write_file_args(&tool_call.args, index, tmp_dir, file_prefix)
+                self.thin_write_file_args(&tool_call.args, index, tmp_dir, file_prefix)
             {
                 tool_call.args = new_args;
                 modified = true;
@@ -590,7 +888,7 @@ Format this as a detailed but concise summary that can be used to resume the con
         // Handle str_replace tool calls
         if tool_call.tool == "str_replace" {
             if let Some((saved, new_args)) =
-                Self::thin_str_replace_args(&tool_call.args, index, tmp_dir, file_prefix)
+                self.thin_str_replace_args(&tool_call.args, index, tmp_dir, file_prefix)
             {
                 tool_call.args = new_args;
                 modified = true;
@@ -618,6 +916,7 @@ Format this as a detailed but concise summary that can be used to resume the con
     /// Thin write_file args by saving content to file.
     /// Returns (chars_saved, new_args) if thinned.
     fn thin_write_file_args(
+        &self,
         args: &serde_json::Value,
         index: usize,
         tmp_dir: &str,
@@ -640,7 +939,7 @@ Format this as a detailed but concise summary that can be used to resume the con
         );
         let file_path = format!("{}/{}", tmp_dir, filename);
 
-        std::fs::write(&file_path, content_str).ok()?;
+        self.write_thinned(&file_path, content_str).ok()?;
 
         let content_len = content_str.len();
         let mut new_args = args_obj.clone();
@@ -660,6 +959,7 @@ Format this as a detailed but concise summary that can be used to resume the con
     /// Thin str_replace args by saving diff to file.
     /// Returns (chars_saved, new_args) if thinned.
     fn thin_str_replace_args(
+        &self,
         args: &serde_json::Value,
         index: usize,
         tmp_dir: &str,
@@ -682,7 +982,7 @@ Format this as a detailed but concise summary that can be used to resume the con
         );
         let file_path = format!("{}/{}", tmp_dir, filename);
 
-        std::fs::write(&file_path, diff_str).ok()?;
+        self.write_thinned(&file_path, diff_str).ok()?;
 
         let diff_len = diff_str.len();
         let mut new_args = args_obj.clone();
@@ -699,6 +999,17 @@ Format this as a detailed but concise summary that can be used to resume the con
         Some((diff_len, serde_json::Value::Object(new_args)))
     }
 
+    /// Write thinned content to `file_path`, compressed if this window's files
+    /// are. The reference left in the context keeps the plain path, which
+    /// read_file also finds compressed.
+    fn write_thinned(&self, file_path: &str, content: &str) -> std::io::Result<PathBuf> {
+        compressed_file::write(
+            Path::new(file_path),
+            content.as_bytes(),
+            self.compression_level,
+        )
+    }
+
     // ========================================================================
     // JSON Utilities
     // ========================================================================
@@ -737,10 +1048,57 @@ Format this as a detailed but concise summary that can be used to resume the con
 // Helper Types
 // ============================================================================
 
+/// Join each run of consecutive user messages into one. The joined message is
+/// pinned if any part of it was.
+fn merge_adjacent_user_turns(messages: Vec<Message>) -> Vec<Message> {
+    let mut merged: Vec<Message> = Vec::with_capacity(messages.len());
+    for mut message in messages {
+        match merged.last_mut() {
+            Some(previous)
+                if matches!(previous.role, MessageRole::User)
+                    && matches!(message.role, MessageRole::User) =>
+            {
+                let mut earlier =
+                    std::mem::replace(previous, Message::new(MessageRole::User, String::new()));
+                let pinned = earlier.is_pinned() || message.is_pinned();
+                let mut images = std::mem::take(&mut earlier.images);
+                images.append(&mut message.images);
+
+                let mut content = earlier.into_full_content();
+                content.push_str("\n\n");
+                content.push_str(&message.into_full_content());
+                *previous = Message::new(MessageRole::User, content);
+                previous.images = images;
+                if pinned {
+                    previous.kind = MessageKind::Pinned;
+                }
+            }
+            _ => merged.push(message),
+        }
+    }
+    merged
+}
+
+/// A copy of a preserved message without the summary a previous compaction
+/// merged into it.
+fn without_merged_summary(message: &Message) -> Message {
+    let separator = format!("\n\n{}", SUMMARY_PREFIX);
+    let content = message.full_content();
+    match content.find(&separator) {
+        Some(end) => {
+            let mut stripped = Message::new(message.role.clone(), content[..end].to_string());
+            stripped.kind = message.kind;
+            stripped.images = message.images.clone();
+            stripped
+        }
+        None => message.clone(),
+    }
+}
+
 /// Messages preserved across compaction.
 struct PreservedMessages {
-    system_prompt: Option<Message>,
-    project_context: Option<Message>,
+    /// Verbatim messages placed ahead of the summary, in original order
+    prefix: Vec<Message>,
     last_assistant_message: Option<Message>,
 }
 
@@ -776,6 +1134,103 @@ mod tests {
         assert!(cw.conversation_history.is_empty());
     }
 
+    #[test]
+    fn test_report() {
+        let mut cw = ContextWindow::new(10000);
+        cw.add_message(Message::new(MessageRole::System, "System prompt".to_string()));
+        cw.add_message(Message::new(
+            MessageRole::User,
+            format!("line one\nline two {}", "x".repeat(200)),
+        ));
+
+        let report = cw.report();
+        assert_eq!(report.messages.len(), 2);
+        assert_eq!(report.total_tokens, 10000);
+        assert_eq!(report.used_tokens, cw.used_tokens);
+        assert!(matches!(report.messages[0].role, MessageRole::System));
+        assert_eq!(report.messages[0].preview, "System prompt");
+        assert_eq!(report.messages[1].preview.chars().count(), PREVIEW_CHARS);
+        assert!(report.messages[1].preview.starts_with("line one line two"));
+        assert_eq!(
+            report.messages.iter().map(|m| m.tokens).sum::<u32>(),
+            ContextWindow::estimate_tokens("System prompt")
+                + ContextWindow::estimate_tokens(&cw.conversation_history[1].content)
+        );
+    }
+
+    #[test]
+    fn test_large_bodies_are_stored() {
+        let dir = std::env::temp_dir().join(format!("g3-cw-bodies-{}", std::process::id()));
+        let mut cw = ContextWindow::new(100_000);
+        cw.set_body_store(dir.clone(), 1_000);
+
+        let big = format!("Tool result: {}", "y".repeat(4_000));
+        cw.add_message(Message::new(MessageRole::System, "s".repeat(2_000)));
+        cw.add_message(Message::new(MessageRole::User, big.clone()));
+        cw.add_message(Message::new(MessageRole::Assistant, "ok".to_string()));
+
+        let history = &cw.conversation_history;
+        assert!(history[0].stored_body.is_none());
+        assert!(history[1].stored_body.is_some());
+        assert!(history[1].content.len() < big.len());
+        assert_eq!(history[1].full_content(), big);
+        assert!(history[2].stored_body.is_none());
+
+        // Disabled store keeps everything in memory
+        cw.set_body_store(dir.clone(), 0);
+        cw.add_message(Message::new(MessageRole::User, big.clone()));
+        assert!(cw.conversation_history[3].stored_body.is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_thinning_points_at_stored_bodies() {
+        let dir = std::env::temp_dir().join(format!("g3-cw-thin-{}", std::process::id()));
+        let mut cw = ContextWindow::new(100_000);
+        cw.set_body_store(dir.clone(), 1_000);
+        cw.add_message(Message::new(
+            MessageRole::User,
+            format!("Tool result: {}", "y".repeat(4_000)),
+        ));
+        cw.add_message(Message::new(MessageRole::Assistant, "ok".to_string()));
+
+        let body = cw.conversation_history[0].stored_body.clone().unwrap();
+        // Thinning sizes the message from the stored length without reading it
+        std::fs::remove_file(&body.path).unwrap();
+        let result = cw.thin_context_all(None);
+
+        assert_eq!(result.leaned_count, 1);
+        assert_eq!(
+            cw.conversation_history[0].content,
+            format!("Tool result saved to {}", body.path.display())
+        );
+        assert!(result.chars_saved > 3_900);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_dropped_bodies_are_removed() {
+        let dir = std::env::temp_dir().join(format!("g3-cw-drop-{}", std::process::id()));
+        let mut cw = ContextWindow::new(100_000);
+        cw.set_body_store(dir.clone(), 1_000);
+        let big = || format!("Tool result: {}", "y".repeat(4_000));
+        cw.add_message(Message::new(MessageRole::User, big()));
+        cw.add_message(Message::new(MessageRole::Assistant, "ok".to_string()));
+        cw.add_message(Message::new(MessageRole::User, big()));
+
+        // A thinned tool result still names its body, so the file stays
+        let thinned = cw.conversation_history[0].stored_body.clone().unwrap();
+        cw.thin_context_all(None);
+        assert!(thinned.path.is_file());
+
+        cw.clear_conversation();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_percentage_used() {
         let mut cw = ContextWindow::new(100);
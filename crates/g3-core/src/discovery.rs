@@ -0,0 +1,163 @@
+//! Token budget for fast-start discovery results.
+//!
+//! Discovery tool results are added to the context before the task starts.
+//! On large repositories a few broad commands can fill a big part of the
+//! window, so each result is checked against its share of a budget (a
+//! percentage of the context window). Results over their share are written
+//! to `.g3/sessions/<id>/tools/` and replaced by a head/tail preview that
+//! points at the file.
+
+use std::fs;
+use std::path::PathBuf;
+
+use tracing::debug;
+
+use crate::context_window::ContextWindow;
+use crate::paths::{generate_short_id, get_tools_output_dir};
+
+/// Smallest share of the budget a result gets, however little is left.
+const MIN_PREVIEW_TOKENS: u32 = 200;
+
+/// Tracks how much of the discovery budget has been used.
+#[derive(Debug, Clone)]
+pub struct DiscoveryBudget {
+    budget_tokens: u32,
+    used_tokens: u32,
+    spilled: usize,
+}
+
+impl DiscoveryBudget {
+    /// Budget of `budget_percent`% of a context window of `context_tokens`.
+    pub fn new(context_tokens: u32, budget_percent: f32) -> Self {
+        let budget_percent = budget_percent.clamp(0.0, 100.0);
+        Self {
+            budget_tokens: (context_tokens as f32 * budget_percent / 100.0) as u32,
+            used_tokens: 0,
+            spilled: 0,
+        }
+    }
+
+    /// Fit one tool result into the budget.
+    ///
+    /// `results_left` counts this result and the ones still to come; each gets
+    /// an equal share of what's left. A result over its share is saved to a
+    /// file (when there is a session) and replaced by a preview of that size.
+    pub fn fit(
+        &mut self,
+        result: String,
+        results_left: usize,
+        session_id: Option<&str>,
+        tool_name: &str,
+    ) -> String {
+        let remaining = self.budget_tokens.saturating_sub(self.used_tokens);
+        let share = (remaining / results_left.max(1) as u32).max(MIN_PREVIEW_TOKENS);
+        let tokens = ContextWindow::estimate_tokens(&result);
+
+        let fitted = if tokens <= share {
+            result
+        } else {
+            self.spilled += 1;
+            spill(&result, share, tokens, session_id, tool_name)
+        };
+        self.used_tokens += ContextWindow::estimate_tokens(&fitted);
+        fitted
+    }
+
+    /// Estimated tokens of the results as added to the context.
+    pub fn used_tokens(&self) -> u32 {
+        self.used_tokens
+    }
+
+    pub fn budget_tokens(&self) -> u32 {
+        self.budget_tokens
+    }
+
+    /// Number of results that were over their share.
+    pub fn spilled(&self) -> usize {
+        self.spilled
+    }
+}
+
+/// Save `result` to the session's tools directory and return a preview of
+/// roughly `preview_tokens` (the first two thirds from the head, the rest
+/// from the tail) pointing at the file.
+fn spill(
+    result: &str,
+    preview_tokens: u32,
+    total_tokens: u32,
+    session_id: Option<&str>,
+    tool_name: &str,
+) -> String {
+    // Keep the same characters-per-token ratio as the full result
+    let total_chars = result.chars().count();
+    let preview_chars =
+        (total_chars as u64 * preview_tokens as u64 / total_tokens.max(1) as u64) as usize;
+    let head_chars = preview_chars * 2 / 3;
+    let tail_chars = preview_chars - head_chars;
+
+    let head: String = result.chars().take(head_chars).collect();
+    let tail: String = result.chars().skip(total_chars - tail_chars).collect();
+
+    let location = match session_id.and_then(|id| save_full_result(result, id, tool_name)) {
+        Some(path) => format!(
+            "Full output saved to: {}\nUse read_file to see more.",
+            path.display()
+        ),
+        None => "The full output was not saved.".to_string(),
+    };
+
+    format!(
+        "{}\n\n[[ DISCOVERY OUTPUT TRUNCATED (~{} tokens, {} total chars) ]]\n{}\n\n{}",
+        head, total_tokens, total_chars, location, tail
+    )
+}
+
+fn save_full_result(result: &str, session_id: &str, tool_name: &str) -> Option<PathBuf> {
+    let tools_dir = get_tools_output_dir(session_id);
+    if let Err(e) = fs::create_dir_all(&tools_dir) {
+        debug!("Failed to create tools output dir: {}", e);
+        return None;
+    }
+    let path = tools_dir.join(format!(
+        "discovery_{}_{}.txt",
+        tool_name,
+        generate_short_id()
+    ));
+    match fs::write(&path, result) {
+        Ok(()) => Some(path),
+        Err(e) => {
+            debug!("Failed to save discovery output: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_results_are_kept() {
+        let mut budget = DiscoveryBudget::new(100_000, 10.0);
+        assert_eq!(budget.budget_tokens(), 10_000);
+
+        let result = budget.fit("src/\nCargo.toml".to_string(), 2, None, "shell");
+        assert_eq!(result, "src/\nCargo.toml");
+        assert_eq!(budget.spilled(), 0);
+        assert!(budget.used_tokens() > 0);
+    }
+
+    #[test]
+    fn test_oversized_results_are_previewed() {
+        let mut budget = DiscoveryBudget::new(10_000, 10.0);
+        let lines: Vec<String> = (0..2000).map(|i| format!("line {}", i)).collect();
+        let result = budget.fit(lines.join("\n"), 1, None, "shell");
+
+        assert_eq!(budget.spilled(), 1);
+        assert!(result.starts_with("line 0\n"));
+        assert!(result.ends_with("line 1999"));
+        assert!(result.contains("DISCOVERY OUTPUT TRUNCATED"));
+        // Within the 1000-token budget, give or take the notice
+        assert!(budget.used_tokens() < 1100);
+    }
+}
@@ -0,0 +1,506 @@
+//! Read-only allowlist for fast-start discovery commands.
+//!
+//! Discovery commands are proposed by an LLM and run before the task
+//! starts, without anyone looking at them. A hallucinated `rm` or
+//! `git checkout .` must not run, so every command is checked first: each
+//! program of each pipeline stage must be a known read-only one (`ls`,
+//! `cat`, `rg`, `find`, ...), flags that make one of them write (`sed -i`,
+//! `find -delete`, `sort -o`) are refused, output may only be redirected
+//! to `/dev/null`, and command substitution, background jobs and
+//! subshells are not allowed. Anything not understood is rejected.
+
+use crate::ToolCall;
+
+/// Programs that only read.
+const READ_ONLY_PROGRAMS: &[&str] = &[
+    "ls", "cat", "head", "tail", "rg", "grep", "egrep", "fgrep", "find", "fd", "wc", "tree",
+    "file", "stat", "du", "sort", "uniq", "cut", "tr", "sed", "echo", "printf", "pwd", "basename",
+    "dirname", "realpath", "readlink", "diff", "cmp", "comm", "nl", "column", "jq", "true",
+    "xargs", "git",
+];
+
+/// git subcommands that only read.
+const READ_ONLY_GIT: &[&str] = &[
+    "log",
+    "status",
+    "diff",
+    "show",
+    "ls-files",
+    "ls-tree",
+    "grep",
+    "blame",
+    "rev-parse",
+    "shortlog",
+    "describe",
+    "branch",
+    "tag",
+    "remote",
+    "cat-file",
+];
+
+/// Flags that make an otherwise read-only program write or run something.
+const WRITING_FLAGS: &[(&str, &[&str])] = &[
+    (
+        "find",
+        &[
+            "-delete", "-exec", "-execdir", "-ok", "-okdir", "-fprint", "-fprint0", "-fprintf",
+            "-fls",
+        ],
+    ),
+    ("fd", &["-x", "--exec", "-X", "--exec-batch"]),
+    ("sort", &["-o", "--output", "--compress-program"]),
+    ("tree", &["-o"]),
+    ("rg", &["--pre"]),
+    ("git", &["--output", "-O", "--open-files-in-pager"]),
+];
+
+/// Why a discovery tool call may not run, or `None` when it only reads.
+pub fn check_discovery_call(tool_call: &ToolCall) -> Option<String> {
+    if tool_call.tool != "shell" {
+        return Some(format!("'{}' is not a discovery tool", tool_call.tool));
+    }
+    match tool_call.args.get("command").and_then(|c| c.as_str()) {
+        Some(command) => check_read_only(command).err(),
+        None => Some("no command".to_string()),
+    }
+}
+
+/// Check that a shell command only reads.
+pub fn check_read_only(command: &str) -> Result<(), String> {
+    let words = tokenize(command)?;
+    let mut stage: Vec<String> = Vec::new();
+    for word in words {
+        match word {
+            Word::Text(text) => stage.push(text),
+            Word::Separator => {
+                check_stage(&stage)?;
+                stage.clear();
+            }
+        }
+    }
+    check_stage(&stage)
+}
+
+#[derive(Debug, PartialEq)]
+enum Word {
+    Text(String),
+    /// `|`, `||`, `&&`, `;` or a newline between pipeline stages
+    Separator,
+}
+
+/// Split a command into words and stage separators, honouring quotes and
+/// refusing the shell features a read-only command doesn't need.
+fn tokenize(command: &str) -> Result<Vec<Word>, String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    // Whether `current` holds a word, even an empty quoted one
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+
+    let finish = |current: &mut String, in_word: &mut bool, words: &mut Vec<Word>| {
+        if *in_word {
+            words.push(Word::Text(std::mem::take(current)));
+            *in_word = false;
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            if let Some(c) = chars.next() {
+                                current.push(c);
+                            }
+                        }
+                        Some('`') => return Err("command substitution is not allowed".to_string()),
+                        Some('$') if chars.peek() == Some(&'(') => {
+                            return Err("command substitution is not allowed".to_string())
+                        }
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(c) = chars.next() {
+                    current.push(c);
+                }
+            }
+            '`' => return Err("command substitution is not allowed".to_string()),
+            '$' if chars.peek() == Some(&'(') => {
+                return Err("command substitution is not allowed".to_string())
+            }
+            '(' | ')' if !in_word => return Err("subshells are not allowed".to_string()),
+            ' ' | '\t' => finish(&mut current, &mut in_word, &mut words),
+            '\n' | ';' => {
+                finish(&mut current, &mut in_word, &mut words);
+                words.push(Word::Separator);
+            }
+            '|' => {
+                finish(&mut current, &mut in_word, &mut words);
+                if chars.peek() == Some(&'|') {
+                    chars.next();
+                }
+                words.push(Word::Separator);
+            }
+            '&' => {
+                if chars.peek() == Some(&'&') {
+                    chars.next();
+                    finish(&mut current, &mut in_word, &mut words);
+                    words.push(Word::Separator);
+                } else if chars.peek() == Some(&'>') {
+                    // `&>` redirects both streams
+                    chars.next();
+                    finish(&mut current, &mut in_word, &mut words);
+                    check_redirect_target(&mut chars)?;
+                } else {
+                    return Err("background jobs are not allowed".to_string());
+                }
+            }
+            '>' => {
+                // A word of digits right before is the redirected descriptor
+                if in_word && current.chars().all(|c| c.is_ascii_digit()) {
+                    current.clear();
+                    in_word = false;
+                }
+                finish(&mut current, &mut in_word, &mut words);
+                if chars.peek() == Some(&'>') {
+                    chars.next();
+                }
+                check_redirect_target(&mut chars)?;
+            }
+            '<' => {
+                // Reading a file is fine; the file name follows as a word
+                finish(&mut current, &mut in_word, &mut words);
+                if chars.peek() == Some(&'(') {
+                    return Err("process substitution is not allowed".to_string());
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    finish(&mut current, &mut in_word, &mut words);
+    Ok(words)
+}
+
+/// Output may only go to `/dev/null` or another descriptor (`2>&1`).
+fn check_redirect_target(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+) -> Result<(), String> {
+    while chars.peek().is_some_and(|c| *c == ' ' || *c == '\t') {
+        chars.next();
+    }
+    let mut target = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace()
+            || matches!(c, ';' | '|' | '<' | '>')
+            || (c == '&' && !target.is_empty())
+        {
+            break;
+        }
+        target.push(c);
+        chars.next();
+    }
+    let is_descriptor = target
+        .strip_prefix('&')
+        .is_some_and(|fd| !fd.is_empty() && fd.chars().all(|c| c.is_ascii_digit()));
+    if target == "/dev/null" || is_descriptor {
+        Ok(())
+    } else {
+        Err(format!("redirecting output to '{}' is not allowed", target))
+    }
+}
+
+/// Check one pipeline stage: its program and flags.
+fn check_stage(stage: &[String]) -> Result<(), String> {
+    let Some(program) = stage.first() else {
+        return Ok(());
+    };
+    // A leading `VAR=value` could change what the program does (PAGER, GIT_EXTERNAL_DIFF)
+    if program.contains('=') {
+        return Err("environment assignments are not allowed".to_string());
+    }
+    let name = program.rsplit('/').next().unwrap_or(program);
+    if !READ_ONLY_PROGRAMS.contains(&name) {
+        return Err(format!("'{}' is not a read-only command", name));
+    }
+    let args = &stage[1..];
+
+    if let Some((_, flags)) = WRITING_FLAGS.iter().find(|(p, _)| *p == name) {
+        if let Some(flag) = args.iter().find(|a| flags.iter().any(|f| is_flag(a, f))) {
+            return Err(format!("'{} {}' can write or run commands", name, flag));
+        }
+    }
+    match name {
+        "sed" => check_sed(args),
+        "git" => check_git(args),
+        // The command xargs runs must pass the same checks
+        "xargs" => {
+            let command = args
+                .iter()
+                .position(|a| !a.starts_with('-'))
+                .map(|i| &args[i..])
+                .unwrap_or_default();
+            check_stage(command)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether `arg` passes `flag`, also when a short flag is bundled with
+/// others or with its value (`-no`, `-oFILE`) or a long one is abbreviated
+/// (`--out=FILE`).
+fn is_flag(arg: &str, flag: &str) -> bool {
+    if let Some(long) = flag.strip_prefix("--") {
+        let name = arg.split('=').next().unwrap_or(arg);
+        return name
+            .strip_prefix("--")
+            .is_some_and(|name| !name.is_empty() && long.starts_with(name));
+    }
+    match flag.strip_prefix('-') {
+        Some(short) if short.len() == 1 => {
+            arg.starts_with('-') && !arg.starts_with("--") && arg[1..].contains(short)
+        }
+        _ => arg == flag || arg.starts_with(&format!("{}=", flag)),
+    }
+}
+
+/// `sed` without in-place editing or the `w`/`e` commands.
+fn check_sed(args: &[String]) -> Result<(), String> {
+    let mut scripts = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--in-place")
+            || (arg.starts_with('-') && !arg.starts_with("--") && arg.contains('i'))
+        {
+            return Err("'sed -i' edits files in place".to_string());
+        }
+        if arg == "-e" || arg == "--expression" {
+            scripts.extend(args.next().cloned());
+        } else if let Some(script) = arg.strip_prefix("--expression=") {
+            scripts.push(script.to_string());
+        } else if !arg.starts_with('-') && scripts.is_empty() {
+            // Without -e the first operand is the script, the rest are files
+            scripts.push(arg.clone());
+        }
+    }
+    for script in &scripts {
+        if script.split([';', '\n']).any(sed_command_writes) {
+            return Err(format!(
+                "sed script '{}' writes files or runs commands",
+                script
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether one sed command is `w`, `W`, `e`, or an `s` with the `w`/`e` flag.
+fn sed_command_writes(command: &str) -> bool {
+    let mut command = command.trim();
+    // Skip the address: line numbers, `$`, `,`, or a `/regex/`
+    loop {
+        command = command
+            .trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, ',' | '$' | '!' | ' '));
+        match command
+            .strip_prefix('/')
+            .and_then(|rest| rest.find('/').map(|end| &rest[end + 1..]))
+        {
+            Some(rest) => command = rest,
+            None => break,
+        }
+    }
+    let mut chars = command.chars();
+    match chars.next() {
+        Some('w' | 'W' | 'e') => true,
+        Some('s') => {
+            let Some(delimiter) = chars.next() else {
+                return false;
+            };
+            let flags = chars.as_str().splitn(3, delimiter).nth(2).unwrap_or("");
+            flags.contains(['w', 'e'])
+        }
+        _ => false,
+    }
+}
+
+/// `git` with a read-only subcommand; branch/tag/remote only list.
+fn check_git(args: &[String]) -> Result<(), String> {
+    let mut args = args.iter().skip_while(|a| a.starts_with('-'));
+    let Some(subcommand) = args.next() else {
+        return Ok(());
+    };
+    if !READ_ONLY_GIT.contains(&subcommand.as_str()) {
+        return Err(format!(
+            "'git {}' is not a read-only git command",
+            subcommand
+        ));
+    }
+    if matches!(subcommand.as_str(), "branch" | "tag" | "remote") {
+        const LISTING: &[&str] = &[
+            "-a",
+            "-r",
+            "-l",
+            "--list",
+            "-v",
+            "-vv",
+            "--all",
+            "--remotes",
+            "--merged",
+            "--no-merged",
+            "--contains",
+            "--sort",
+            "--show-current",
+            "-n",
+        ];
+        if let Some(arg) = args.find(|a| {
+            !LISTING
+                .iter()
+                .any(|l| a.as_str() == *l || a.starts_with(&format!("{}=", l)))
+        }) {
+            return Err(format!(
+                "'git {} {}' may change the repository; only listing is allowed",
+                subcommand, arg
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_commands_pass() {
+        for command in [
+            "ls -la",
+            "cat Cargo.toml",
+            "rg --no-heading -n -g '*.rs' '^(pub )?(struct|enum) ' . 2>/dev/null | grep -v '/target/' | head -100",
+            "find . -name '*.go' -not -path './vendor/*' | sort | head -n 50",
+            "git log --oneline -n 15",
+            "git branch -a",
+            "rg --files | xargs wc -l | sort -n | tail -20",
+            "sed -n '1,80p' src/main.rs",
+            "head -n 40 README.md && ls crates",
+            "wc -l < src/lib.rs",
+            "cat \"a b.txt\" 2>&1",
+            "sort -n -k2 counts.txt",
+            "tree -L 2 -I target",
+            "find . -name '*.rs' -print0 | xargs -0 wc -l",
+            "rg --pretty TODO",
+            "git grep -n -i TODO -- '*.rs'",
+        ] {
+            assert_eq!(check_read_only(command), Ok(()), "{}", command);
+        }
+    }
+
+    #[test]
+    fn test_mutating_commands_are_rejected() {
+        for (command, reason) in [
+            ("rm -rf target", "'rm' is not a read-only command"),
+            ("ls && rm -rf /", "'rm' is not a read-only command"),
+            ("cat a > b", "redirecting output to 'b' is not allowed"),
+            (
+                "echo x >> notes.txt",
+                "redirecting output to 'notes.txt' is not allowed",
+            ),
+            (
+                "sed -i 's/a/b/' src/lib.rs",
+                "'sed -i' edits files in place",
+            ),
+            ("sed -ni '1p' x", "'sed -i' edits files in place"),
+            (
+                "find . -name '*.tmp' -delete",
+                "'find -delete' can write or run commands",
+            ),
+            (
+                "find . -exec rm {} ;",
+                "'find -exec' can write or run commands",
+            ),
+            (
+                "sort -o out.txt in.txt",
+                "'sort -o' can write or run commands",
+            ),
+            (
+                "sort -oout.txt in.txt",
+                "'sort -oout.txt' can write or run commands",
+            ),
+            (
+                "sort -no out.txt in.txt",
+                "'sort -no' can write or run commands",
+            ),
+            (
+                "sort --out=out.txt in.txt",
+                "'sort --out=out.txt' can write or run commands",
+            ),
+            ("tree -o tree.txt", "'tree -o' can write or run commands"),
+            ("tree -aoTREE", "'tree -aoTREE' can write or run commands"),
+            (
+                "find . -fprint0 files.txt",
+                "'find -fprint0' can write or run commands",
+            ),
+            ("fd -HIx rm", "'fd -HIx' can write or run commands"),
+            ("git grep -Orm foo", "'git -Orm' can write or run commands"),
+            ("git grep -nO rm foo", "'git -nO' can write or run commands"),
+            (
+                "git grep --open-files-in-pager=rm foo",
+                "'git --open-files-in-pager=rm' can write or run commands",
+            ),
+            (
+                "sort --compress-program=sh big.txt",
+                "'sort --compress-program=sh' can write or run commands",
+            ),
+            (
+                "git checkout .",
+                "'git checkout' is not a read-only git command",
+            ),
+            (
+                "git branch -D main",
+                "'git branch -D' may change the repository; only listing is allowed",
+            ),
+            ("rg --files | xargs rm", "'rm' is not a read-only command"),
+            ("cat $(which ls)", "command substitution is not allowed"),
+            ("echo \"`id`\"", "command substitution is not allowed"),
+            (
+                "sed -n 's/a/b/w out.txt' x",
+                "sed script 's/a/b/w out.txt' writes files or runs commands",
+            ),
+            (
+                "sed '1e id' x",
+                "sed script '1e id' writes files or runs commands",
+            ),
+            ("ls &", "background jobs are not allowed"),
+            (
+                "PAGER=rm git log",
+                "environment assignments are not allowed",
+            ),
+            ("(cd /tmp; ls)", "subshells are not allowed"),
+        ] {
+            assert_eq!(
+                check_read_only(command),
+                Err(reason.to_string()),
+                "{}",
+                command
+            );
+        }
+    }
+}
@@ -0,0 +1,301 @@
+//! Dry-run mode.
+//!
+//! With dry run on (`Agent::set_dry_run`, `--dry-run`), tool calls that
+//! would change something are not executed: the model gets a description of
+//! what the call would have done (a diff for file edits, the command line for
+//! shell commands), and the planned action is appended to
+//! `.g3/sessions/<id>/dry_run.jsonl`. Tools that only read run as usual, so
+//! the agent can still explore before it plans its changes.
+
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use similar::TextDiff;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::discovery_allowlist;
+use crate::utils::apply_unified_diff_to_string;
+use crate::ToolCall;
+
+/// Tools that don't change files, processes or remote state, and run
+/// normally in dry-run mode. Tools that write session files (`todo_write`,
+/// `plan_write`), other files as a side effect (`image_diff` saves its diff
+/// image) or start subprocesses that may (`dead_code` runs build scripts,
+/// `research` starts a scout agent) are not listed.
+const READ_ONLY_TOOLS: &[&str] = &[
+    "read_file",
+    "read_many_files",
+    "read_output",
+    "read_image",
+    "list_dir",
+    "grep",
+    "git_status",
+    "git_diff",
+    "notebook_read",
+    "inspect_env",
+    "wait_for",
+    "watch_files",
+    "todo_read",
+    "plan_read",
+    "code_search",
+    "find_definition",
+    "find_references",
+    "symbol_lookup",
+    "ast_outline",
+    "research_status",
+    "rehydrate",
+];
+
+/// Lines of context around each change in a previewed diff.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// An action the agent planned but didn't carry out.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedAction {
+    pub timestamp: String,
+    pub tool: String,
+    pub args: Value,
+    /// What the call would have done, as reported to the model
+    pub preview: String,
+}
+
+impl PlannedAction {
+    pub fn new(tool_call: &ToolCall, preview: &str) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339(),
+            tool: tool_call.tool.clone(),
+            args: tool_call.args.clone(),
+            preview: preview.to_string(),
+        }
+    }
+
+    /// Append this action as a JSON line to `path`.
+    pub fn append_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(file, "{}", serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+/// Whether `tool_call` only reads: a read-only tool, a shell command that
+/// passes the discovery allowlist, or an `http_request` GET.
+pub fn is_read_only(tool_call: &ToolCall) -> bool {
+    let arg = |name: &str| tool_call.args.get(name).and_then(|v| v.as_str());
+    match tool_call.tool.as_str() {
+        "shell" => arg("command").is_some_and(|c| discovery_allowlist::check_read_only(c).is_ok()),
+        "http_request" => {
+            let method = arg("method").unwrap_or("GET");
+            method.eq_ignore_ascii_case("GET") || method.eq_ignore_ascii_case("HEAD")
+        }
+        tool => READ_ONLY_TOOLS.contains(&tool),
+    }
+}
+
+/// What `tool_call` would do, or `None` if it only reads and should run.
+pub fn preview(tool_call: &ToolCall, working_dir: Option<&str>) -> Option<String> {
+    if is_read_only(tool_call) {
+        return None;
+    }
+    let tool = tool_call.tool.as_str();
+    let arg = |name: &str| tool_call.args.get(name).and_then(|v| v.as_str());
+    let mut dir = working_dir
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+    if let Some(call_dir) = arg("working_dir") {
+        dir = resolve(&dir, call_dir);
+    }
+
+    let preview = match tool {
+        "shell" => format!("Would run in {}:\n$ {}", dir.display(), arg("command")?),
+        "background_process" => format!(
+            "Would start background process '{}' in {}:\n$ {}",
+            arg("name").unwrap_or("?"),
+            dir.display(),
+            arg("command").unwrap_or("")
+        ),
+        "http_request" => format!(
+            "Would send {} {}",
+            arg("method").unwrap_or("GET").to_uppercase(),
+            arg("url").unwrap_or("?")
+        ),
+        "write_file" => match (arg("file_path"), arg("content")) {
+            (Some(path), Some(content)) => preview_write(&resolve(&dir, path), content),
+            _ => return None,
+        },
+        "str_replace" => match (arg("file_path"), arg("diff")) {
+            (Some(path), Some(diff)) => {
+                let range = |name: &str| {
+                    tool_call
+                        .args
+                        .get(name)
+                        .and_then(|v| v.as_u64())
+                        .map(|n| n as usize)
+                };
+                preview_edit(&resolve(&dir, path), diff, range("start"), range("end"))
+            }
+            _ => return None,
+        },
+        _ => format!(
+            "Would call {} with {}",
+            tool,
+            serde_json::to_string(&tool_call.args).unwrap_or_default()
+        ),
+    };
+    Some(format!("🧪 Dry run, nothing was changed. {}", preview))
+}
+
+/// `path` relative to the directory the tool would run in, with `~` expanded.
+fn resolve(dir: &Path, path: &str) -> PathBuf {
+    dir.join(shellexpand::tilde(path).as_ref())
+}
+
+fn preview_write(path: &Path, content: &str) -> String {
+    match std::fs::read_to_string(path) {
+        Ok(original) if original == content => {
+            format!("Would leave {} unchanged", path.display())
+        }
+        Ok(original) => format!(
+            "Would overwrite {}:\n{}",
+            path.display(),
+            unified_diff(path, &original, content)
+        ),
+        Err(_) => format!(
+            "Would create {} ({} lines):\n{}",
+            path.display(),
+            content.lines().count(),
+            unified_diff(path, "", content)
+        ),
+    }
+}
+
+fn preview_edit(path: &Path, diff: &str, start: Option<usize>, end: Option<usize>) -> String {
+    let original = match std::fs::read_to_string(path) {
+        Ok(original) => original,
+        Err(e) => return format!("Would fail: can't read {}: {}", path.display(), e),
+    };
+    match apply_unified_diff_to_string(&original, diff, start, end) {
+        Ok(edited) => format!(
+            "Would edit {}:\n{}",
+            path.display(),
+            unified_diff(path, &original, &edited)
+        ),
+        Err(e) => format!("Would fail: {}", e),
+    }
+}
+
+/// The change from `original` to `edited` as a unified diff of `path`.
+pub(crate) fn unified_diff(path: &Path, original: &str, edited: &str) -> String {
+    let name = path.display().to_string();
+    TextDiff::from_lines(original, edited)
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT_LINES)
+        .header(&format!("a/{}", name), &format!("b/{}", name))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(tool: &str, args: Value) -> ToolCall {
+        ToolCall {
+            tool: tool.to_string(),
+            args,
+        }
+    }
+
+    #[test]
+    fn test_previews_leave_files_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_str = dir.path().to_str();
+        std::fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+
+        // Reads run for real
+        assert!(preview(&call("read_file", json!({"file_path": "a.txt"})), dir_str).is_none());
+        assert!(preview(&call("shell", json!({"command": "ls -la"})), dir_str).is_none());
+
+        let write = preview(
+            &call(
+                "write_file",
+                json!({"file_path": "a.txt", "content": "one\n2\nthree\n"}),
+            ),
+            dir_str,
+        )
+        .unwrap();
+        assert!(write.contains("Would overwrite"));
+        assert!(write.contains("-two\n+2\n"));
+
+        let create = preview(
+            &call(
+                "write_file",
+                json!({"file_path": "new.txt", "content": "x\n"}),
+            ),
+            dir_str,
+        )
+        .unwrap();
+        assert!(create.contains("Would create") && create.contains("+x\n"));
+        assert!(!dir.path().join("new.txt").exists());
+
+        let edit = preview(
+            &call(
+                "str_replace",
+                json!({"file_path": "a.txt", "diff": "-three\n+3"}),
+            ),
+            dir_str,
+        )
+        .unwrap();
+        assert!(edit.contains("-three\n+3\n"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\ntwo\nthree\n"
+        );
+
+        let shell = preview(&call("shell", json!({"command": "rm -rf build"})), dir_str).unwrap();
+        assert!(shell.ends_with("$ rm -rf build"));
+        let commit = preview(&call("git_commit", json!({"message": "wip"})), dir_str).unwrap();
+        assert!(commit.contains("Would call git_commit with {\"message\":\"wip\"}"));
+        for tool in [
+            "todo_write",
+            "plan_write",
+            "image_diff",
+            "dead_code",
+            "research",
+        ] {
+            assert!(
+                preview(&call(tool, json!({})), dir_str).is_some(),
+                "{}",
+                tool
+            );
+        }
+    }
+
+    #[test]
+    fn test_planned_actions_are_appended() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("session").join("dry_run.jsonl");
+        let tool_call = call("shell", json!({"command": "make install"}));
+        PlannedAction::new(&tool_call, "Would run make install")
+            .append_to(&log)
+            .unwrap();
+        PlannedAction::new(&tool_call, "again")
+            .append_to(&log)
+            .unwrap();
+
+        let lines: Vec<Value> = std::fs::read_to_string(&log)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["tool"], "shell");
+        assert_eq!(lines[0]["args"]["command"], "make install");
+        assert_eq!(lines[1]["preview"], "again");
+    }
+}
@@ -0,0 +1,623 @@
+//! The agent's event bus.
+//!
+//! Everything the agent and its tools show goes out as an `AgentEvent` on
+//! the agent's `EventBus`. The bus delivers each event to the agent's
+//! `UiWriter` first, on the publishing thread, so terminal output keeps its
+//! order with prompts, then broadcasts it to every subscriber. A new
+//! front-end can subscribe (`Agent::subscribe_events`) instead of
+//! implementing the print methods of `UiWriter`.
+//!
+//! The bus is itself a `UiWriter`: each print method publishes its event, so
+//! code written against `UiWriter` (tools, compaction) publishes too. Prompts
+//! are answered by the writer and then broadcast as `Prompt`, with the
+//! answer. JSON filtering and display settings aren't events; they go
+//! straight to the writer.
+//!
+//! `HeadlessUiWriter` suits front-ends that only follow the events (the
+//! server, `g3 --json` through `write_json_lines`).
+
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::telemetry::TurnTelemetry;
+use crate::ui_writer::UiWriter;
+use crate::ThinResult;
+
+/// Events kept for subscribers that fall behind.
+const EVENT_BUFFER: usize = 1024;
+
+/// Something the agent shows, one variant per print method of `UiWriter`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// Text output, as is
+    Print {
+        text: String,
+    },
+    /// A line of output
+    Println {
+        text: String,
+    },
+    /// Output that stays on the current line (progress indicators)
+    PrintInline {
+        text: String,
+    },
+    SystemPrompt {
+        prompt: String,
+    },
+    /// Context window and other status messages
+    ContextStatus {
+        message: String,
+    },
+    /// A g3 operation started (e.g. compaction)
+    G3Progress {
+        message: String,
+    },
+    /// A g3 operation finished with `status` ("done", "failed", ...)
+    G3Status {
+        message: String,
+        status: String,
+    },
+    ContextThinned {
+        result: ThinResult,
+    },
+    /// A pre-formatted context thinning message (legacy)
+    ContextThinning {
+        message: String,
+    },
+    ToolHeader {
+        tool: String,
+        args: Option<serde_json::Value>,
+    },
+    /// An argument of the running tool, formatted for display
+    ToolArg {
+        key: String,
+        value: String,
+    },
+    ToolOutputHeader,
+    /// A line of tool output replacing the previous one
+    ToolOutputLineUpdate {
+        line: String,
+    },
+    ToolOutputLine {
+        line: String,
+    },
+    /// Tool output lines not shown
+    ToolOutputSummary {
+        hidden_count: usize,
+    },
+    /// A file tool's call as a single line, instead of its output
+    ToolCompact {
+        tool: String,
+        summary: String,
+        duration: String,
+        tokens_delta: u32,
+        context_percentage: f32,
+    },
+    /// A TODO tool's call with the list (`None` when empty)
+    TodoCompact {
+        content: Option<String>,
+        is_write: bool,
+    },
+    ToolTiming {
+        duration: String,
+        tokens_delta: u32,
+        context_percentage: f32,
+    },
+    /// A file edit about to be written, as a unified diff
+    Diff {
+        path: String,
+        diff: String,
+    },
+    /// The full result of a tool, as returned to the model
+    ToolResult {
+        tool: String,
+        output: String,
+        success: bool,
+    },
+    TurnTelemetry {
+        telemetry: TurnTelemetry,
+    },
+    /// The response is about to start
+    AgentPrompt,
+    /// Streamed response text
+    AgentResponse {
+        text: String,
+    },
+    /// The provider sent something (including pings)
+    SseReceived,
+    /// A tool call started streaming from the provider
+    ToolStreamingHint {
+        tool: String,
+    },
+    /// The tool call is still streaming
+    ToolStreamingActive,
+    /// The response finished streaming
+    ResponseFinished,
+    /// The agent asked a question and the writer answered it
+    Prompt {
+        message: String,
+        options: Vec<String>,
+        answer: usize,
+    },
+}
+
+impl AgentEvent {
+    /// Whether the event only drives a terminal display (indicators and
+    /// headers) and carries nothing for other front-ends.
+    pub fn is_display_only(&self) -> bool {
+        matches!(
+            self,
+            AgentEvent::ToolOutputHeader
+                | AgentEvent::AgentPrompt
+                | AgentEvent::SseReceived
+                | AgentEvent::ToolStreamingActive
+        )
+    }
+
+    /// Show the event with `writer`, through the print method it stands for.
+    /// Returns false when the writer declines a compact format (`ToolCompact`
+    /// and `TodoCompact`), which the publisher replaces with the full one.
+    pub fn deliver<W: UiWriter + ?Sized>(&self, writer: &W) -> bool {
+        match self {
+            AgentEvent::Print { text } => writer.print(text),
+            AgentEvent::Println { text } => writer.println(text),
+            AgentEvent::PrintInline { text } => writer.print_inline(text),
+            AgentEvent::SystemPrompt { prompt } => writer.print_system_prompt(prompt),
+            AgentEvent::ContextStatus { message } => writer.print_context_status(message),
+            AgentEvent::G3Progress { message } => writer.print_g3_progress(message),
+            AgentEvent::G3Status { message, status } => writer.print_g3_status(message, status),
+            AgentEvent::ContextThinned { result } => writer.print_thin_result(result),
+            AgentEvent::ContextThinning { message } => writer.print_context_thinning(message),
+            AgentEvent::ToolHeader { tool, args } => writer.print_tool_header(tool, args.as_ref()),
+            AgentEvent::ToolArg { key, value } => writer.print_tool_arg(key, value),
+            AgentEvent::ToolOutputHeader => writer.print_tool_output_header(),
+            AgentEvent::ToolOutputLineUpdate { line } => writer.update_tool_output_line(line),
+            AgentEvent::ToolOutputLine { line } => writer.print_tool_output_line(line),
+            AgentEvent::ToolOutputSummary { hidden_count } => {
+                writer.print_tool_output_summary(*hidden_count)
+            }
+            AgentEvent::ToolCompact {
+                tool,
+                summary,
+                duration,
+                tokens_delta,
+                context_percentage,
+            } => {
+                return writer.print_tool_compact(
+                    tool,
+                    summary,
+                    duration,
+                    *tokens_delta,
+                    *context_percentage,
+                )
+            }
+            AgentEvent::TodoCompact { content, is_write } => {
+                return writer.print_todo_compact(content.as_deref(), *is_write)
+            }
+            AgentEvent::ToolTiming {
+                duration,
+                tokens_delta,
+                context_percentage,
+            } => writer.print_tool_timing(duration, *tokens_delta, *context_percentage),
+            AgentEvent::Diff { path, diff } => writer.print_diff(path, diff),
+            AgentEvent::ToolResult {
+                tool,
+                output,
+                success,
+            } => writer.emit_tool_result(tool, output, *success),
+            AgentEvent::TurnTelemetry { telemetry } => writer.emit_turn_telemetry(telemetry),
+            AgentEvent::AgentPrompt => writer.print_agent_prompt(),
+            AgentEvent::AgentResponse { text } => writer.print_agent_response(text),
+            AgentEvent::SseReceived => writer.notify_sse_received(),
+            AgentEvent::ToolStreamingHint { tool } => writer.print_tool_streaming_hint(tool),
+            AgentEvent::ToolStreamingActive => writer.print_tool_streaming_active(),
+            AgentEvent::ResponseFinished => writer.finish_streaming_markdown(),
+            // The writer gave the answer
+            AgentEvent::Prompt { .. } => {}
+        }
+        true
+    }
+}
+
+/// Publishes `AgentEvent`s to a `UiWriter` and to any number of subscribers.
+///
+/// Subscribers receive the events the writer showed: a compact format the
+/// writer declined is followed by the full one, which is what they get.
+/// A subscriber more than 1024 events behind misses the oldest ones
+/// (`RecvError::Lagged`).
+pub struct EventBus<W> {
+    writer: W,
+    sender: broadcast::Sender<AgentEvent>,
+}
+
+impl<W: UiWriter> EventBus<W> {
+    pub fn new(writer: W) -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self { writer, sender }
+    }
+
+    /// The writer the events are delivered to.
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Receive the events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Deliver `event` to the writer, then to the subscribers. Returns false,
+    /// without broadcasting, if the writer declined it (see
+    /// `AgentEvent::deliver`).
+    pub fn publish(&self, event: AgentEvent) -> bool {
+        if !event.deliver(&self.writer) {
+            return false;
+        }
+        // Fails only when nobody subscribed
+        let _ = self.sender.send(event);
+        true
+    }
+
+    /// Tell the subscribers how the writer answered a prompt.
+    fn broadcast_prompt(&self, message: &str, options: &[&str], answer: usize) {
+        let _ = self.sender.send(AgentEvent::Prompt {
+            message: message.to_string(),
+            options: options.iter().map(|o| o.to_string()).collect(),
+            answer,
+        });
+    }
+}
+
+/// Write the events from `events` to `out`, one JSON object per line, until
+/// the bus is dropped. Display-only events are left out.
+pub async fn write_json_lines(
+    mut events: broadcast::Receiver<AgentEvent>,
+    mut out: impl Write,
+) -> std::io::Result<()> {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("Skipped {} events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+        if event.is_display_only() {
+            continue;
+        }
+        serde_json::to_writer(&mut out, &event)?;
+        writeln!(out)?;
+        out.flush()?;
+    }
+}
+
+impl<W: UiWriter> UiWriter for EventBus<W> {
+    fn print(&self, message: &str) {
+        self.publish(AgentEvent::Print {
+            text: message.to_string(),
+        });
+    }
+
+    fn println(&self, message: &str) {
+        self.publish(AgentEvent::Println {
+            text: message.to_string(),
+        });
+    }
+
+    fn print_inline(&self, message: &str) {
+        self.publish(AgentEvent::PrintInline {
+            text: message.to_string(),
+        });
+    }
+
+    fn print_system_prompt(&self, prompt: &str) {
+        self.publish(AgentEvent::SystemPrompt {
+            prompt: prompt.to_string(),
+        });
+    }
+
+    fn print_context_status(&self, message: &str) {
+        self.publish(AgentEvent::ContextStatus {
+            message: message.to_string(),
+        });
+    }
+
+    fn print_g3_progress(&self, message: &str) {
+        self.publish(AgentEvent::G3Progress {
+            message: message.to_string(),
+        });
+    }
+
+    fn print_g3_status(&self, message: &str, status: &str) {
+        self.publish(AgentEvent::G3Status {
+            message: message.to_string(),
+            status: status.to_string(),
+        });
+    }
+
+    fn print_thin_result(&self, result: &ThinResult) {
+        self.publish(AgentEvent::ContextThinned {
+            result: result.clone(),
+        });
+    }
+
+    fn print_context_thinning(&self, message: &str) {
+        self.publish(AgentEvent::ContextThinning {
+            message: message.to_string(),
+        });
+    }
+
+    fn print_tool_header(&self, tool_name: &str, tool_args: Option<&serde_json::Value>) {
+        self.publish(AgentEvent::ToolHeader {
+            tool: tool_name.to_string(),
+            args: tool_args.cloned(),
+        });
+    }
+
+    fn print_tool_arg(&self, key: &str, value: &str) {
+        self.publish(AgentEvent::ToolArg {
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    fn print_tool_output_header(&self) {
+        self.publish(AgentEvent::ToolOutputHeader);
+    }
+
+    fn update_tool_output_line(&self, line: &str) {
+        self.publish(AgentEvent::ToolOutputLineUpdate {
+            line: line.to_string(),
+        });
+    }
+
+    fn print_tool_output_line(&self, line: &str) {
+        self.publish(AgentEvent::ToolOutputLine {
+            line: line.to_string(),
+        });
+    }
+
+    fn print_tool_output_summary(&self, hidden_count: usize) {
+        self.publish(AgentEvent::ToolOutputSummary { hidden_count });
+    }
+
+    fn print_tool_compact(
+        &self,
+        tool_name: &str,
+        summary: &str,
+        duration_str: &str,
+        tokens_delta: u32,
+        context_percentage: f32,
+    ) -> bool {
+        self.publish(AgentEvent::ToolCompact {
+            tool: tool_name.to_string(),
+            summary: summary.to_string(),
+            duration: duration_str.to_string(),
+            tokens_delta,
+            context_percentage,
+        })
+    }
+
+    fn print_todo_compact(&self, content: Option<&str>, is_write: bool) -> bool {
+        self.publish(AgentEvent::TodoCompact {
+            content: content.map(str::to_string),
+            is_write,
+        })
+    }
+
+    fn print_tool_timing(&self, duration_str: &str, tokens_delta: u32, context_percentage: f32) {
+        self.publish(AgentEvent::ToolTiming {
+            duration: duration_str.to_string(),
+            tokens_delta,
+            context_percentage,
+        });
+    }
+
+    fn print_diff(&self, file_path: &str, diff: &str) {
+        self.publish(AgentEvent::Diff {
+            path: file_path.to_string(),
+            diff: diff.to_string(),
+        });
+    }
+
+    fn emit_tool_result(&self, tool_name: &str, result: &str, success: bool) {
+        self.publish(AgentEvent::ToolResult {
+            tool: tool_name.to_string(),
+            output: result.to_string(),
+            success,
+        });
+    }
+
+    fn emit_turn_telemetry(&self, telemetry: &TurnTelemetry) {
+        self.publish(AgentEvent::TurnTelemetry {
+            telemetry: telemetry.clone(),
+        });
+    }
+
+    fn print_agent_prompt(&self) {
+        self.publish(AgentEvent::AgentPrompt);
+    }
+
+    fn print_agent_response(&self, content: &str) {
+        self.publish(AgentEvent::AgentResponse {
+            text: content.to_string(),
+        });
+    }
+
+    fn notify_sse_received(&self) {
+        self.publish(AgentEvent::SseReceived);
+    }
+
+    fn print_tool_streaming_hint(&self, tool_name: &str) {
+        self.publish(AgentEvent::ToolStreamingHint {
+            tool: tool_name.to_string(),
+        });
+    }
+
+    fn print_tool_streaming_active(&self) {
+        self.publish(AgentEvent::ToolStreamingActive);
+    }
+
+    fn finish_streaming_markdown(&self) {
+        self.publish(AgentEvent::ResponseFinished);
+    }
+
+    fn flush(&self) {
+        self.writer.flush();
+    }
+
+    fn wants_full_output(&self) -> bool {
+        self.writer.wants_full_output()
+    }
+
+    fn prompt_user_yes_no(&self, message: &str) -> bool {
+        let confirmed = self.writer.prompt_user_yes_no(message);
+        self.broadcast_prompt(message, &["yes", "no"], if confirmed { 0 } else { 1 });
+        confirmed
+    }
+
+    fn prompt_user_choice(&self, message: &str, options: &[&str]) -> usize {
+        let answer = self.writer.prompt_user_choice(message, options);
+        self.broadcast_prompt(message, options, answer);
+        answer
+    }
+
+    fn filter_json_tool_calls(&self, content: &str) -> String {
+        self.writer.filter_json_tool_calls(content)
+    }
+
+    fn reset_json_filter(&self) {
+        self.writer.reset_json_filter();
+    }
+
+    fn set_agent_mode(&self, is_agent_mode: bool) {
+        self.writer.set_agent_mode(is_agent_mode);
+    }
+
+    fn set_workspace_path(&self, path: PathBuf) {
+        self.writer.set_workspace_path(path);
+    }
+
+    fn set_project_path(&self, path: PathBuf, name: String) {
+        self.writer.set_project_path(path, name);
+    }
+
+    fn clear_project(&self) {
+        self.writer.clear_project();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_writer::{HeadlessUiWriter, NullUiWriter, ToolObserver};
+    use std::sync::{Arc, Mutex};
+
+    /// Records the tools the writer was told about.
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl ToolObserver for Recorder {
+        fn tool_starting(&self, tool: &str, _args: Option<&serde_json::Value>) {
+            self.0.lock().unwrap().push(tool.to_string());
+        }
+    }
+
+    #[test]
+    fn test_events_reach_the_writer_and_subscribers_in_order() {
+        let recorder = Arc::new(Recorder::default());
+        let bus = EventBus::new(HeadlessUiWriter::new().tool_observer(recorder.clone()));
+        let mut events = bus.subscribe();
+
+        bus.print_agent_response("Reading the manifest");
+        bus.print_tool_header(
+            "read_file",
+            Some(&serde_json::json!({"path": "Cargo.toml"})),
+        );
+        bus.print_tool_output_line("[package]");
+
+        assert_eq!(
+            events.try_recv().unwrap(),
+            AgentEvent::AgentResponse {
+                text: "Reading the manifest".to_string()
+            }
+        );
+        assert_eq!(
+            events.try_recv().unwrap(),
+            AgentEvent::ToolHeader {
+                tool: "read_file".to_string(),
+                args: Some(serde_json::json!({"path": "Cargo.toml"})),
+            }
+        );
+        assert_eq!(
+            events.try_recv().unwrap(),
+            AgentEvent::ToolOutputLine {
+                line: "[package]".to_string()
+            }
+        );
+        assert!(events.try_recv().is_err());
+
+        // The writer was shown them too
+        assert_eq!(*recorder.0.lock().unwrap(), ["read_file"]);
+
+        let json = serde_json::to_value(AgentEvent::ToolOutputSummary { hidden_count: 3 }).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "tool_output_summary", "hidden_count": 3})
+        );
+    }
+
+    #[test]
+    fn test_declined_compact_format_is_not_broadcast() {
+        let bus = EventBus::new(NullUiWriter);
+        let mut events = bus.subscribe();
+
+        // NullUiWriter keeps the default: no compact format
+        assert!(!bus.print_tool_compact("read_file", "Cargo.toml", "12ms", 40, 1.5));
+        assert!(events.try_recv().is_err());
+
+        // Prompts are answered by the writer, then broadcast with the answer
+        assert!(bus.prompt_user_yes_no("Continue?"));
+        assert_eq!(
+            events.try_recv().unwrap(),
+            AgentEvent::Prompt {
+                message: "Continue?".to_string(),
+                options: vec!["yes".to_string(), "no".to_string()],
+                answer: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_events_are_written_as_json_lines() {
+        let bus = EventBus::new(NullUiWriter);
+        let events = bus.subscribe();
+        bus.print_agent_response("Listing files.");
+        bus.print_tool_header("shell", Some(&serde_json::json!({"command": "ls"})));
+        bus.print_tool_output_header();
+        bus.emit_tool_result("shell", "Cargo.toml\nsrc", true);
+        bus.print_tool_timing("1.2s", 40, 12.5);
+        drop(bus);
+
+        let mut out = Vec::new();
+        write_json_lines(events, &mut out).await.unwrap();
+        let events: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let types: Vec<&str> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+        assert_eq!(
+            types,
+            ["agent_response", "tool_header", "tool_result", "tool_timing"]
+        );
+        assert_eq!(events[1]["args"]["command"], "ls");
+        assert_eq!(events[2]["output"], "Cargo.toml\nsrc");
+        assert_eq!(events[3]["tokens_delta"], 40);
+    }
+}
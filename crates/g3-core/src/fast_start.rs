@@ -0,0 +1,121 @@
+//! Fast-start snapshots: discovery results reused across sessions.
+//!
+//! After fast-start discovery is played back, the tool calls that ran and
+//! their raw results are saved to `.g3/planner/fast_start.json` in the
+//! codebase, keyed by the codebase path and its git HEAD. A new session on
+//! the same HEAD loads them instead of asking the planner LLM for a plan and
+//! re-running every command. Uncommitted changes don't invalidate a
+//! snapshot; delete the file to force fresh discovery.
+
+use anyhow::Result;
+use g3_providers::{Message, MessageRole};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Location of the snapshot, relative to the codebase root.
+pub const FAST_START_SNAPSHOT_PATH: &str = ".g3/planner/fast_start.json";
+
+/// One discovery tool call and what it returned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FastStartEntry {
+    /// The tool call as played back (JSON, as in the discovery messages)
+    pub tool_call: String,
+    /// The raw result, before the discovery budget was applied
+    pub result: String,
+}
+
+/// The discovery of one codebase at one commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FastStartSnapshot {
+    pub codebase_path: String,
+    pub head: String,
+    pub created_at: String,
+    pub entries: Vec<FastStartEntry>,
+}
+
+/// Path of the fast-start snapshot for the codebase at `root`.
+pub fn fast_start_snapshot_path(root: &Path) -> PathBuf {
+    root.join(FAST_START_SNAPSHOT_PATH)
+}
+
+/// The commit checked out at `dir`, if it is in a git repository with one.
+pub fn git_head(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--verify", "-q", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    let head = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !head.is_empty()).then_some(head)
+}
+
+impl FastStartSnapshot {
+    pub fn new(codebase_path: &str, head: String, entries: Vec<FastStartEntry>) -> Self {
+        Self {
+            codebase_path: codebase_path.to_string(),
+            head,
+            created_at: chrono::Local::now().to_rfc3339(),
+            entries,
+        }
+    }
+
+    /// The saved snapshot of `codebase_path`, if it was taken at `head`.
+    pub fn load(codebase_path: &str, head: &str) -> Option<Self> {
+        let path = fast_start_snapshot_path(Path::new(codebase_path));
+        let snapshot: Self = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        (snapshot.codebase_path == codebase_path && snapshot.head == head).then_some(snapshot)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = fast_start_snapshot_path(Path::new(&self.codebase_path));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// The tool calls as discovery messages, for `DiscoveryOptions::messages`.
+    pub fn messages(&self) -> Vec<Message> {
+        self.entries
+            .iter()
+            .map(|e| Message::new(MessageRole::Assistant, e.tool_call.clone()))
+            .collect()
+    }
+
+    /// The results, in the order of `messages`, for
+    /// `DiscoveryOptions::cached_results`.
+    pub fn results(&self) -> Vec<String> {
+        self.entries.iter().map(|e| e.result.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_is_keyed_by_path_and_head() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        let entries = vec![FastStartEntry {
+            tool_call: r#"{"tool":"shell","args":{"command":"ls"}}"#.to_string(),
+            result: "Cargo.toml\nsrc".to_string(),
+        }];
+        FastStartSnapshot::new(&root, "abc123".to_string(), entries.clone())
+            .save()
+            .unwrap();
+
+        let snapshot = FastStartSnapshot::load(&root, "abc123").unwrap();
+        assert_eq!(snapshot.entries, entries);
+        assert_eq!(snapshot.messages()[0].content, entries[0].tool_call);
+        assert_eq!(snapshot.results(), ["Cargo.toml\nsrc"]);
+
+        // HEAD moved
+        assert!(FastStartSnapshot::load(&root, "def456").is_none());
+        // Not a git repository
+        assert!(git_head(dir.path()).is_none());
+    }
+}
@@ -8,6 +8,7 @@
 //!
 //! Used by both autonomous mode (g3-cli) and planning mode (g3-planner).
 
+use crate::compressed_file;
 use crate::{Agent, TaskResult};
 use crate::ui_writer::UiWriter;
 use serde_json::Value;
@@ -149,12 +150,12 @@ fn try_extract_last_assistant_message(
     
     // Use .g3/sessions/<session_id>/session.json path
     let log_file_path = crate::get_session_file(session_id);
-    if !log_file_path.exists() {
+    if !compressed_file::exists(&log_file_path) {
         debug!("Session log file not found: {:?}", log_file_path);
         return None;
     }
 
-    let log_content = std::fs::read_to_string(&log_file_path).ok()?;
+    let log_content = compressed_file::read_to_string(&log_file_path).ok()?;
     let log_json: Value = serde_json::from_str(&log_content).ok()?;
 
     // Try to get conversation history from context_window
@@ -207,12 +208,12 @@ fn try_extract_from_session_log(
     
     // Use .g3/sessions/<session_id>/session.json path
     let log_file_path = crate::get_session_file(session_id);
-    if !log_file_path.exists() {
+    if !compressed_file::exists(&log_file_path) {
         debug!("Session log file not found: {:?}", log_file_path);
         return None;
     }
 
-    let log_content = std::fs::read_to_string(&log_file_path).ok()?;
+    let log_content = compressed_file::read_to_string(&log_file_path).ok()?;
     let log_json: Value = serde_json::from_str(&log_content).ok()?;
 
     // Try to get conversation history from context_window
@@ -344,11 +345,11 @@ fn try_extract_from_conversation_history(
     
     // Use .g3/sessions/<session_id>/session.json path
     let log_file_path = crate::get_session_file(session_id);
-    if !log_file_path.exists() {
+    if !compressed_file::exists(&log_file_path) {
         return None;
     }
 
-    let log_content = std::fs::read_to_string(&log_file_path).ok()?;
+    let log_content = compressed_file::read_to_string(&log_file_path).ok()?;
     let log_json: Value = serde_json::from_str(&log_content).ok()?;
 
     // Check for tool_calls array in the log
@@ -0,0 +1,214 @@
+//! Detecting edits made to files behind the agent's back.
+//!
+//! The file tools remember the content of every file the agent reads or
+//! writes. Before `write_file`, `str_replace`, `multi_edit` or `edit_lines`
+//! writes a file, its content on disk is compared with the remembered
+//! version: if someone (usually the user, in their editor) changed it in the
+//! meantime, the edit is refused with a diff of their changes rather than
+//! overwriting them with an edit made against an outdated version. The
+//! refusal makes the version on disk the remembered one, so the agent can
+//! redo the edit on top of it.
+//!
+//! Other tools that may change files (`shell`, `cargo`, `regex_replace`, ...)
+//! don't report what they touched, so after they run every remembered file
+//! is re-read: what the agent's own commands changed is not reported as a
+//! conflict.
+
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Files larger than this (16MB) are not tracked.
+const MAX_TRACKED_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Files up to this size are remembered in full, so a conflict can show a
+/// diff; larger ones only by hash.
+const MAX_KEPT_BYTES: usize = 1024 * 1024;
+
+/// Lines of context around each change in a conflict diff.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Tools that record the files they read and check the files they write.
+pub const TRACKING_TOOLS: &[&str] = &[
+    "read_file",
+    "read_many_files",
+    "write_file",
+    "str_replace",
+    "multi_edit",
+    "edit_lines",
+];
+
+#[derive(Debug)]
+struct Version {
+    hash: [u8; 32],
+    /// The content, if small enough to keep
+    content: Option<String>,
+}
+
+impl Version {
+    fn of(content: &str) -> Self {
+        Self {
+            hash: Sha256::digest(content.as_bytes()).into(),
+            content: (content.len() <= MAX_KEPT_BYTES).then(|| content.to_string()),
+        }
+    }
+}
+
+/// The version of each file the agent last read or wrote.
+#[derive(Debug, Default)]
+pub struct FileVersions {
+    versions: Mutex<HashMap<PathBuf, Version>>,
+}
+
+impl FileVersions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember `content` as the agent's version of `path`.
+    pub fn record(&self, path: &Path, content: &str) {
+        self.versions
+            .lock()
+            .unwrap()
+            .insert(key(path), Version::of(content));
+    }
+
+    /// Remember the content `path` has on disk, e.g. after reading part of
+    /// it. Unreadable and very large files are forgotten.
+    pub fn record_from_disk(&self, path: &Path) {
+        match read_tracked(path) {
+            Some(content) => self.record(path, &content),
+            None => self.forget(path),
+        }
+    }
+
+    pub fn forget(&self, path: &Path) {
+        self.versions.lock().unwrap().remove(&key(path));
+    }
+
+    /// Re-read every remembered file, after a tool that may have changed
+    /// files without saying which.
+    pub fn refresh(&self) {
+        let mut versions = self.versions.lock().unwrap();
+        versions.retain(|path, version| match read_tracked(path) {
+            Some(content) => {
+                *version = Version::of(&content);
+                true
+            }
+            None => false,
+        });
+    }
+
+    /// Check that `path` still has the content the agent last saw, before
+    /// the agent writes it. Files the agent hasn't seen pass.
+    ///
+    /// `Err` holds the message refusing the write, with a diff of the
+    /// changes made on disk. The version on disk then counts as seen, so
+    /// the next write goes through.
+    pub fn check(&self, path: &Path) -> Result<(), String> {
+        let key = key(path);
+        let mut versions = self.versions.lock().unwrap();
+        let Some(seen) = versions.get(&key) else {
+            return Ok(());
+        };
+        let Ok(on_disk) = std::fs::read_to_string(&key) else {
+            versions.remove(&key);
+            return Err(format!(
+                "❌ File deleted on disk since last read: {}\n\
+                It was removed outside the agent after you last read or wrote it, so the \
+                edit was not applied. Check whether it should still exist before writing it again.",
+                path.display()
+            ));
+        };
+        let current = Version::of(&on_disk);
+        if current.hash == seen.hash {
+            return Ok(());
+        }
+
+        let changes = match &seen.content {
+            Some(seen) => format!(
+                "Changes on disk:\n```diff\n{}```",
+                TextDiff::from_lines(seen.as_str(), on_disk.as_str())
+                    .unified_diff()
+                    .context_radius(DIFF_CONTEXT_LINES)
+                    .header("last read", "on disk")
+            ),
+            None => "The file is too large to show the changes; read it again.".to_string(),
+        };
+        versions.insert(key, current);
+        Err(format!(
+            "❌ File changed on disk since last read: {}\n\
+            It was modified outside the agent after you last read or wrote it, so the edit \
+            was not applied (it would have overwritten those changes). {}\n\
+            Redo the edit against the current content; don't undo these changes unless asked to.",
+            path.display(),
+            changes
+        ))
+    }
+}
+
+/// The content of `path`, unless it can't be read or is too large to track.
+fn read_tracked(path: &Path) -> Option<String> {
+    let len = std::fs::metadata(path).ok()?.len();
+    if len > MAX_TRACKED_BYTES {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
+}
+
+/// The key `path` is remembered under: absolute, with the directory's
+/// symlinks resolved, so different spellings of a path (and a deleted file)
+/// still match.
+fn key(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(dir), Some(name)) => dir
+            .canonicalize()
+            .map(|dir| dir.join(name))
+            .unwrap_or(absolute),
+        _ => absolute,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edits_on_disk_are_detected_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        let versions = FileVersions::new();
+
+        // Files never seen can be written
+        assert!(versions.check(&path).is_ok());
+
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        versions.record_from_disk(&path);
+        assert!(versions.check(&path).is_ok());
+
+        std::fs::write(&path, "one\ntwo, edited by the user\n").unwrap();
+        let conflict = versions.check(&dir.path().join("./notes.txt")).unwrap_err();
+        assert!(conflict.starts_with("❌ File changed on disk since last read"));
+        assert!(conflict.contains("-two\n+two, edited by the user\n"));
+        // The refusal showed the changes, so the next write goes through
+        assert!(versions.check(&path).is_ok());
+
+        // The agent's own writes, and files changed by its commands, aren't
+        // conflicts
+        versions.record(&path, "three\n");
+        std::fs::write(&path, "three\n").unwrap();
+        assert!(versions.check(&path).is_ok());
+        std::fs::write(&path, "formatted\n").unwrap();
+        versions.refresh();
+        assert!(versions.check(&path).is_ok());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(versions
+            .check(&path)
+            .unwrap_err()
+            .starts_with("❌ File deleted on disk"));
+    }
+}
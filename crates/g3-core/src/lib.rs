@@ -1,29 +1,61 @@
 pub mod acd;
+pub mod agent_builder;
+pub mod approval;
+pub mod audit;
 pub mod background_process;
+pub mod build_metrics;
 pub mod code_search;
+pub mod commit_links;
 pub mod compaction;
+pub mod compressed_file;
 pub mod context_window;
+pub mod discovery;
+pub mod discovery_allowlist;
+pub mod dry_run;
 pub mod error_handling;
+pub mod events;
+pub mod fast_start;
 pub mod feedback_extraction;
+pub mod file_versions;
+pub mod lsp_client;
+pub mod mcp_client;
+pub mod notebook_kernel;
+pub mod otel;
+pub mod output_compression;
 pub mod paths;
 pub mod pending_research;
+pub mod plan;
+pub mod prefetch;
 pub mod project;
 pub mod provider_config;
 pub mod provider_registration;
+pub mod python_interpreter;
+pub mod requirements;
 pub mod retry;
 pub mod session;
 pub mod session_continuation;
+pub mod session_diff;
+pub mod session_import;
+pub mod session_pool;
+pub mod session_sync;
+pub mod sessions;
 pub mod stats;
 pub mod streaming;
 pub mod streaming_parser;
 pub mod task_result;
+pub mod telemetry;
 pub mod tool_definitions;
 pub mod tool_dispatch;
+pub mod tool_transcript;
+pub mod tool_validation;
 pub mod tools;
+pub mod transcript_export;
 pub mod ui_writer;
 pub mod utils;
+pub mod verification;
 pub mod webdriver_session;
 
+pub use agent_builder::{AgentBuilder, ToolPolicy};
 pub use feedback_extraction::{
     extract_coach_feedback, ExtractedFeedback, FeedbackExtractionConfig, FeedbackSource,
 };
@@ -31,12 +63,16 @@ pub use retry::{execute_with_retry, retry_operation, RetryConfig, RetryResult};
 pub use session_continuation::{
     clear_continuation, find_incomplete_agent_session, format_session_time, get_session_dir,
     has_valid_continuation, list_sessions_for_directory, load_context_from_session_log,
-    load_continuation, save_continuation, SessionContinuation,
+    load_continuation, load_session_continuation, save_continuation, SessionContinuation,
 };
-pub use task_result::TaskResult;
+pub use task_result::{FailureCategory, TaskReport, TaskResult, ToolCallRecord};
+pub use events::{AgentEvent, EventBus};
+pub use telemetry::TurnTelemetry;
 
 // Re-export context window types
-pub use context_window::{ContextWindow, ThinResult, ThinScope};
+pub use context_window::{
+    BodyStore, ContextReport, ContextWindow, MessageReport, PreservationPolicy, ThinResult, ThinScope,
+};
 
 // Re-export pending research types for notification handling
 pub use pending_research::{PendingResearchManager, ResearchCompletionNotification, ResearchStatus};
@@ -58,11 +94,10 @@ mod prompts;
 use anyhow::Result;
 use g3_config::Config;
 use g3_providers::{CacheControl, CompletionRequest, Message, MessageRole, ProviderRegistry};
-use prompts::{get_system_prompt_for_native, get_system_prompt_for_non_native};
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, warn};
+use tracing::{debug, error, warn, Instrument};
 
 // Re-export path utilities
 use paths::get_todo_path;
@@ -72,7 +107,7 @@ pub use paths::{
     get_thinned_dir, G3_WORKSPACE_PATH_ENV,
 };
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ToolCall {
     pub tool: String,
     pub args: serde_json::Value, // Should be a JSON object with tool-specific arguments
@@ -102,6 +137,9 @@ pub use webdriver_session::WebDriverSession;
 pub struct DiscoveryOptions<'a> {
     pub messages: &'a [Message],
     pub fast_start_path: Option<&'a str>,
+    /// Results of `messages` from a fast-start snapshot (see `fast_start`),
+    /// injected instead of running the commands again
+    pub cached_results: Option<&'a [String]>,
 }
 
 #[derive(Debug, Clone)]
@@ -112,11 +150,12 @@ pub enum StreamState {
     Resuming,
 }
 
-// Re-export StreamingToolParser from its own module
-pub use streaming_parser::StreamingToolParser;
+// Re-export the streaming tool parser and its event API
+pub use streaming_parser::{PartialToolCall, StreamingToolParser, ToolParserEvent};
 
 pub struct Agent<W: UiWriter> {
-    providers: ProviderRegistry,
+    /// Shared with the other agents of the process (see `session_pool`)
+    providers: std::sync::Arc<ProviderRegistry>,
     context_window: ContextWindow,
     thinning_events: Vec<usize>,      // chars saved per thinning event
     pending_90_compaction: bool,      // flag to trigger compaction at 90%
@@ -127,8 +166,9 @@ pub struct Agent<W: UiWriter> {
     cache_stats: CacheStats,
     config: Config,
     session_id: Option<String>,
-    tool_call_metrics: Vec<(String, Duration, bool)>, // (tool_name, duration, success)
-    ui_writer: W,
+    tool_call_metrics: Vec<tool_transcript::ToolCallMetric>,
+    /// Everything shown goes through here to the `UiWriter` and subscribers
+    ui_writer: events::EventBus<W>,
     is_autonomous: bool,
     quiet: bool,
     computer_controller: Option<Box<dyn g3_computer_control::ComputerController>>,
@@ -141,6 +181,8 @@ pub struct Agent<W: UiWriter> {
     /// Tool calls made in the current turn (reset after each turn)
     tool_calls_this_turn: Vec<String>,
     requirements_sha: Option<String>,
+    /// IDs of the structured requirements being implemented, if they have any
+    requirement_ids: Vec<String>,
     /// Working directory for tool execution (set by --codebase-fast-start)
     working_dir: Option<String>,
     background_process_manager: std::sync::Arc<background_process::BackgroundProcessManager>,
@@ -154,8 +196,35 @@ pub struct Agent<W: UiWriter> {
     auto_memory: bool,
     /// Whether aggressive context dehydration is enabled (--acd flag)
     acd_enabled: bool,
+    /// Whether changing tool calls are previewed instead of executed (--dry-run flag)
+    dry_run: bool,
     /// Manager for async research tasks
     pending_research_manager: pending_research::PendingResearchManager,
+    /// Build size/time reports recorded this session (when build_metrics is enabled)
+    build_reports: Vec<build_metrics::BuildReport>,
+    /// Background verification runs against worktree snapshots
+    pending_verification_manager: verification::PendingVerificationManager,
+    /// Files read speculatively while a tool call is still streaming
+    prefetch_cache: prefetch::PrefetchCache,
+    /// Jupyter kernels started by notebook_run, one per notebook
+    notebook_kernels: notebook_kernel::NotebookKernelManager,
+    /// Python interpreter kept by the python tool so variables persist
+    python_interpreter: python_interpreter::PythonInterpreter,
+    /// Bash process the shell tool reuses when `shell.persistent` is set
+    persistent_shell: g3_execution::PersistentShell,
+    /// Content of the files the agent last read or wrote, to catch edits
+    /// made on disk in the meantime
+    file_versions: file_versions::FileVersions,
+    /// Debounces writes of the context window summary file
+    context_summary_writer: session::ContextSummaryWriter,
+    /// Checkpoints the context window every few tool calls or minutes
+    autosave: session::Autosave,
+    /// Pushes session files to the `[sync]` remote, if one is set
+    session_sync: Option<std::sync::Arc<session_sync::SessionSync>>,
+    /// Which tools this agent may use (set through `AgentBuilder::tool_policy`)
+    tool_policy: ToolPolicy,
+    /// MCP servers from `[mcp]` and their tools (connected by `AgentBuilder::build`)
+    mcp_servers: mcp_client::McpServers,
 }
 
 impl<W: UiWriter> Agent<W> {
@@ -169,13 +238,26 @@ impl<W: UiWriter> Agent<W> {
     fn build_agent(
         config: Config,
         ui_writer: W,
-        providers: ProviderRegistry,
-        context_window: ContextWindow,
+        providers: std::sync::Arc<ProviderRegistry>,
+        mut context_window: ContextWindow,
         auto_compact: bool,
         is_autonomous: bool,
         quiet: bool,
         computer_controller: Option<Box<dyn g3_computer_control::ComputerController>>,
     ) -> Self {
+        let context_summary_writer = session::ContextSummaryWriter::new(&config.context_summary);
+        let autosave = session::Autosave::new(&config.autosave);
+        context_window.compression_level = config
+            .session_compression
+            .enabled
+            .then_some(config.session_compression.level);
+        let session_sync = match session_sync::SessionSync::from_config(&config.sync) {
+            Ok(sync) => sync.map(std::sync::Arc::new),
+            Err(e) => {
+                warn!("Session sync disabled: {}", e);
+                None
+            }
+        };
         Self {
             providers,
             context_window,
@@ -188,7 +270,7 @@ impl<W: UiWriter> Agent<W> {
             config,
             session_id: None,
             tool_call_metrics: Vec::new(),
-            ui_writer,
+            ui_writer: events::EventBus::new(ui_writer),
             todo_content: std::sync::Arc::new(tokio::sync::RwLock::new(String::new())),
             is_autonomous,
             quiet,
@@ -198,6 +280,7 @@ impl<W: UiWriter> Agent<W> {
             tool_call_count: 0,
             tool_calls_this_turn: Vec::new(),
             requirements_sha: None,
+            requirement_ids: Vec::new(),
             working_dir: None,
             background_process_manager: std::sync::Arc::new(
                 background_process::BackgroundProcessManager::new(
@@ -209,53 +292,26 @@ impl<W: UiWriter> Agent<W> {
             agent_name: None,
             auto_memory: false,
             acd_enabled: false,
+            dry_run: false,
             pending_research_manager: pending_research::PendingResearchManager::new(),
+            build_reports: Vec::new(),
+            pending_verification_manager: verification::PendingVerificationManager::new(),
+            prefetch_cache: prefetch::PrefetchCache::new(),
+            notebook_kernels: notebook_kernel::NotebookKernelManager::new(),
+            python_interpreter: python_interpreter::PythonInterpreter::new(),
+            persistent_shell: g3_execution::PersistentShell::new(),
+            file_versions: file_versions::FileVersions::new(),
+            context_summary_writer,
+            autosave,
+            session_sync,
+            tool_policy: ToolPolicy::default(),
+            mcp_servers: mcp_client::McpServers::default(),
         }
     }
 
-    pub async fn new(config: Config, ui_writer: W) -> Result<Self> {
-        Self::new_with_mode(config, ui_writer, false, false).await
-    }
-
-    pub async fn new_autonomous(config: Config, ui_writer: W) -> Result<Self> {
-        Self::new_with_mode(config, ui_writer, true, false).await
-    }
-
-    pub async fn new_with_project_context_and_quiet(
-        config: Config,
-        ui_writer: W,
-        project_context: Option<String>,
-        quiet: bool,
-    ) -> Result<Self> {
-        Self::new_with_mode_and_project_context(config, ui_writer, false, project_context, quiet, None).await
-    }
-
-    pub async fn new_autonomous_with_project_context_and_quiet(
-        config: Config,
-        ui_writer: W,
-        project_context: Option<String>,
-        quiet: bool,
-    ) -> Result<Self> {
-        Self::new_with_mode_and_project_context(config, ui_writer, true, project_context, quiet, None).await
-    }
-
-    /// Create a new agent with a custom system prompt (for agent mode)
-    /// The custom_system_prompt replaces the default G3 system prompt entirely
-    pub async fn new_with_custom_prompt(
-        config: Config,
-        ui_writer: W,
-        custom_system_prompt: String,
-        project_context: Option<String>,
-    ) -> Result<Self> {
-        Self::new_with_mode_and_project_context(
-            config,
-            ui_writer,
-            false,
-            project_context,
-            false,
-            Some(custom_system_prompt),
-        )
-        .await
+    /// Start building an agent. See `AgentBuilder` for the available options.
+    pub fn builder(config: Config, ui_writer: W) -> AgentBuilder<W> {
+        AgentBuilder::new(config, ui_writer)
     }
 
     /// Create a new agent with a custom provider registry (for testing).
@@ -302,7 +358,7 @@ impl<W: UiWriter> Agent<W> {
         Ok(Self::build_agent(
             config,
             ui_writer,
-            providers,
+            providers.into(),
             context_window,
             false, // auto_compact
             false, // is_autonomous
@@ -311,99 +367,6 @@ impl<W: UiWriter> Agent<W> {
         ))
     }
 
-    async fn new_with_mode(
-        config: Config,
-        ui_writer: W,
-        is_autonomous: bool,
-        quiet: bool,
-    ) -> Result<Self> {
-        Self::new_with_mode_and_project_context(config, ui_writer, is_autonomous, None, quiet, None).await
-    }
-
-    async fn new_with_mode_and_project_context(
-        config: Config,
-        ui_writer: W,
-        is_autonomous: bool,
-        project_context: Option<String>,
-        quiet: bool,
-        custom_system_prompt: Option<String>,
-    ) -> Result<Self> {
-        // Register providers using the extracted module
-        let providers_to_register =
-            provider_registration::determine_providers_to_register(&config, is_autonomous);
-        let providers =
-            provider_registration::register_providers(&config, &providers_to_register).await?;
-
-        // Determine context window size based on active provider
-        let mut context_warnings = Vec::new();
-        let context_length =
-            Self::get_configured_context_length(&config, &providers, &mut context_warnings)?;
-        let mut context_window = ContextWindow::new(context_length);
-
-        // Surface any context warnings to the user via UI
-        for warning in context_warnings {
-            ui_writer.print_context_status(&format!("⚠️ {}", warning));
-        }
-
-        // Add system prompt as the FIRST message (before README)
-        // This ensures the agent always has proper tool usage instructions
-        let provider = providers.get(None)?;
-        let provider_has_native_tool_calling = provider.has_native_tool_calling();
-        let _ = provider; // Drop provider reference to avoid borrowing issues
-
-        let system_prompt = if let Some(custom_prompt) = custom_system_prompt {
-            // Use custom system prompt (for agent mode)
-            custom_prompt
-        } else {
-            // Use default system prompt based on provider capabilities
-            if provider_has_native_tool_calling {
-                // For native tool calling providers, use a more explicit system prompt
-                get_system_prompt_for_native()
-            } else {
-                // For non-native providers (embedded models), use JSON format instructions
-                get_system_prompt_for_non_native()
-            }
-        };
-
-        let system_message = Message::new(MessageRole::System, system_prompt);
-        context_window.add_message(system_message);
-
-        // If project context is provided, add it as a second system message (after the main system prompt)
-        if let Some(context) = project_context {
-            let context_message = Message::new(MessageRole::System, context);
-            context_window.add_message(context_message);
-        }
-
-        // NOTE: TODO lists are now session-scoped and stored in .g3/sessions/<session_id>/todo.g3.md
-        // We don't load any TODO at initialization since we don't have a session_id yet.
-        // The agent will use todo_read to load the TODO once a session is established.
-
-        // Initialize computer controller if enabled
-        let computer_controller = if config.computer_control.enabled {
-            match g3_computer_control::create_controller() {
-                Ok(controller) => Some(controller),
-                Err(e) => {
-                    warn!("Failed to initialize computer control: {}", e);
-                    None
-                }
-            }
-        } else {
-            None
-        };
-
-        let auto_compact = config.agent.auto_compact;
-        Ok(Self::build_agent(
-            config,
-            ui_writer,
-            providers,
-            context_window,
-            auto_compact,
-            is_autonomous,
-            quiet,
-            computer_controller,
-        ))
-    }
-
     // =========================================================================
     // CONFIGURATION & PROVIDER RESOLUTION
     // =========================================================================
@@ -748,6 +711,16 @@ impl<W: UiWriter> Agent<W> {
         self.providers.get(None)
     }
 
+    /// The provider registry, to build other agents that share its providers
+    pub fn get_providers(&self) -> std::sync::Arc<ProviderRegistry> {
+        self.providers.clone()
+    }
+
+    /// The `[sync]` remote sessions are pushed to, if one is configured
+    pub fn session_sync(&self) -> Option<std::sync::Arc<session_sync::SessionSync>> {
+        self.session_sync.clone()
+    }
+
     /// Get the current session ID for this agent
     pub fn get_session_id(&self) -> Option<&str> {
         self.session_id.as_deref()
@@ -837,7 +810,48 @@ impl<W: UiWriter> Agent<W> {
         .await
     }
 
+    /// Run a task in its `agent_task` span, a root span of the session's
+    /// trace (see `otel`).
     async fn execute_single_task(
+        &mut self,
+        description: &str,
+        show_prompt: bool,
+        show_code: bool,
+        show_timing: bool,
+        cancellation_token: CancellationToken,
+        discovery_options: Option<DiscoveryOptions<'_>>,
+    ) -> Result<TaskResult> {
+        // Generate session ID based on the initial prompt if this is a new session
+        if self.session_id.is_none() {
+            self.set_session_id(self.generate_session_id(description));
+        }
+        let session_id = self.session_id.clone().unwrap_or_default();
+        let span = tracing::info_span!(
+            "agent_task",
+            session.id = %session_id,
+            agent = self.agent_name.as_deref().unwrap_or("g3"),
+            otel.status_code = tracing::field::Empty,
+        );
+        otel::join_session_trace(&span, &session_id);
+
+        let result = self
+            .execute_single_task_inner(
+                description,
+                show_prompt,
+                show_code,
+                show_timing,
+                cancellation_token,
+                discovery_options,
+            )
+            .instrument(span.clone())
+            .await;
+        if result.is_err() {
+            span.record("otel.status_code", "ERROR");
+        }
+        result
+    }
+
+    async fn execute_single_task_inner(
         &mut self,
         description: &str,
         _show_prompt: bool,
@@ -853,11 +867,6 @@ impl<W: UiWriter> Agent<W> {
         // Validate that the system prompt is the first message (critical invariant)
         self.validate_system_prompt_is_first();
 
-        // Generate session ID based on the initial prompt if this is a new session
-        if self.session_id.is_none() {
-            self.session_id = Some(self.generate_session_id(description));
-        }
-
         // Add user message to context window
         let mut user_message = {
             let provider = self.providers.get(None)?;
@@ -894,14 +903,48 @@ impl<W: UiWriter> Agent<W> {
             let provider = self.providers.get(None)?;
             let supports_cache = provider.supports_cache_control();
             let message_count = options.messages.len();
+            let tokens_before = self.context_window.used_tokens;
+            let mut budget = discovery::DiscoveryBudget::new(
+                self.context_window.total_tokens,
+                self.config.discovery.budget_percent,
+            );
+            let mut executed = Vec::new();
 
             for (idx, discovery_msg) in options.messages.iter().enumerate() {
                 if let Ok(tool_call) = serde_json::from_str::<ToolCall>(&discovery_msg.content) {
+                    // Discovery commands come from an LLM and run unattended
+                    if let Some(reason) = discovery_allowlist::check_discovery_call(&tool_call) {
+                        let command = tool_call.args.get("command").and_then(|c| c.as_str());
+                        self.ui_writer.print_context_status(&format!(
+                            "⚠️ Skipped discovery command `{}`: {}",
+                            command.unwrap_or(&tool_call.tool),
+                            reason
+                        ));
+                        continue;
+                    }
                     self.add_message_to_context(discovery_msg.clone());
-                    let result = self
-                        .execute_tool_call_in_dir(&tool_call, options.fast_start_path)
-                        .await
-                        .unwrap_or_else(|e| format!("Error: {}", e));
+                    let cached = options.cached_results.and_then(|r| r.get(idx));
+                    let result = match cached {
+                        Some(result) => result.clone(),
+                        None => {
+                            let result = self
+                                .execute_tool_call_in_dir(&tool_call, options.fast_start_path)
+                                .await
+                                .unwrap_or_else(|e| format!("Error: {}", e));
+                            executed.push(fast_start::FastStartEntry {
+                                tool_call: discovery_msg.content.clone(),
+                                result: result.clone(),
+                            });
+                            result
+                        }
+                    };
+                    // Keep oversized results out of the context (saved to a file instead)
+                    let result = budget.fit(
+                        result,
+                        message_count - idx,
+                        self.session_id.as_deref(),
+                        &tool_call.tool,
+                    );
 
                     // Add cache_control to the last user message if provider supports it (anthropic)
                     let is_last = idx == message_count - 1;
@@ -920,6 +963,34 @@ impl<W: UiWriter> Agent<W> {
                     self.add_message_to_context(result_message);
                 }
             }
+
+            let discovery_tokens = self
+                .context_window
+                .used_tokens
+                .saturating_sub(tokens_before);
+            let spilled = match budget.spilled() {
+                0 => String::new(),
+                n => format!(", {} oversized result(s) saved to files", n),
+            };
+            self.ui_writer.println(&format!(
+                "📊 Discovery used {} tokens ({:.1}% of the context window, budget {}%){}",
+                discovery_tokens,
+                discovery_tokens as f32 / self.context_window.total_tokens.max(1) as f32 * 100.0,
+                self.config.discovery.budget_percent,
+                spilled
+            ));
+
+            // Later sessions on the same commit can skip planning and the commands
+            if let Some(path) = options.fast_start_path {
+                if !executed.is_empty() {
+                    if let Some(head) = fast_start::git_head(std::path::Path::new(path)) {
+                        let snapshot = fast_start::FastStartSnapshot::new(path, head, executed);
+                        if let Err(e) = snapshot.save() {
+                            debug!("Could not save fast-start snapshot: {}", e);
+                        }
+                    }
+                }
+            }
         }
 
         // Use the complete conversation history for the request
@@ -933,14 +1004,12 @@ impl<W: UiWriter> Agent<W> {
         // Check if we should exclude the research tool (scout agent to prevent recursion)
         let exclude_research = self.agent_name.as_deref() == Some("scout");
         let tools = if provider.has_native_tool_calling() {
-            let mut tool_config = tool_definitions::ToolConfig::new(
-                self.config.webdriver.enabled,
-                self.config.computer_control.enabled,
-            );
+            let mut tool_config = tool_definitions::ToolConfig::from_config(&self.config)
+                .with_mcp_tools(self.mcp_servers.tools());
             if exclude_research {
                 tool_config = tool_config.with_research_excluded();
             }
-            Some(tool_definitions::create_tool_definitions(tool_config))
+            Some(self.apply_tool_policy(tool_definitions::create_tool_definitions(tool_config)))
         } else {
             None
         };
@@ -962,6 +1031,8 @@ impl<W: UiWriter> Agent<W> {
             stream: true, // Enable streaming
             tools,
             disable_thinking: false,
+            // Lets providers abort the HTTP request itself, not just our consuming loop
+            cancellation_token: Some(cancellation_token.clone()),
         };
 
         // Time the LLM call with cancellation support and streaming
@@ -1044,22 +1115,45 @@ impl<W: UiWriter> Agent<W> {
         session::generate_session_id(description, self.agent_name.as_deref())
     }
 
+    /// Set the session ID and point large message bodies at its directory.
+    fn set_session_id(&mut self, session_id: String) {
+        self.context_window.set_body_store(
+            paths::get_message_bodies_dir(&session_id),
+            self.config.agent.message_body_store_threshold,
+        );
+        // Starting a session is when old ones are pruned
+        if !self.quiet && self.config.retention.auto_gc {
+            if let Err(e) = sessions::gc_sessions(&self.config.retention, Some(&session_id)) {
+                warn!("Failed to prune old sessions: {}", e);
+            }
+        }
+        self.session_id = Some(session_id);
+    }
+
     /// Save the entire context window to a per-session file
     fn save_context_window(&self, status: &str) {
         if self.quiet {
             return;
         }
         session::save_context_window(self.session_id.as_deref(), &self.context_window, status);
+        self.autosave.saved();
+        // Catch up on any summary write that was debounced during the turn
+        if let Some(ref session_id) = self.session_id {
+            self.context_summary_writer.flush(session_id, &self.context_window);
+            if let Some(ref sync) = self.session_sync {
+                sync.push_in_background(session_id);
+            }
+        }
     }
 
-    /// Write context window summary to file
+    /// Write context window summary to file (debounced, see `ContextSummaryWriter`)
     /// Format: date&time, token_count, message_id, role, first_100_chars
     fn write_context_window_summary(&self) {
         if self.quiet {
             return;
         }
         if let Some(ref session_id) = self.session_id {
-            session::write_context_window_summary(session_id, &self.context_window);
+            self.context_summary_writer.write(session_id, &self.context_window);
         }
     }
 
@@ -1069,7 +1163,17 @@ impl<W: UiWriter> Agent<W> {
 
     /// Get a reference to the UI writer.
     pub fn ui_writer(&self) -> &W {
-        &self.ui_writer
+        self.ui_writer.writer()
+    }
+
+    /// Receive the agent's output from now on, as `AgentEvent`s.
+    pub fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<events::AgentEvent> {
+        self.ui_writer.subscribe()
+    }
+
+    /// Pin the most recent user message so it is kept verbatim across compactions.
+    pub fn pin_last_user_message(&mut self) -> bool {
+        self.context_window.pin_last_user_message()
     }
 
     /// Add a message directly to the context window.
@@ -1123,6 +1227,87 @@ impl<W: UiWriter> Agent<W> {
         completed.len()
     }
 
+    /// Inject finished background verification results into the context.
+    ///
+    /// Called at the same break points as `inject_completed_research`.
+    /// Returns the number of results injected.
+    pub fn inject_completed_verification(&mut self) -> usize {
+        let completed = self.pending_verification_manager.take_completed();
+        for run in &completed {
+            self.context_window
+                .add_message(Message::new(MessageRole::User, run.format_result()));
+            debug!("Injected verification result for run {}", run.id);
+        }
+        completed.len()
+    }
+
+    /// Re-run discovery mid-session and inject the results as one compact report
+    /// that supersedes earlier discovery output.
+    ///
+    /// `options.messages` are discovery tool calls, as produced by
+    /// `g3_planner::get_initial_discovery_messages`; they run in
+    /// `options.fast_start_path`. Results are kept within the `[discovery]`
+    /// budget. Returns the estimated tokens the report added.
+    pub async fn rediscover(&mut self, options: DiscoveryOptions<'_>) -> Result<u32> {
+        let tool_calls: Vec<ToolCall> = options
+            .messages
+            .iter()
+            .filter_map(|m| serde_json::from_str::<ToolCall>(&m.content).ok())
+            .filter(|tool_call| match discovery_allowlist::check_discovery_call(tool_call) {
+                Some(reason) => {
+                    let command = tool_call.args.get("command").and_then(|c| c.as_str());
+                    self.ui_writer.print_context_status(&format!(
+                        "⚠️ Skipped discovery command `{}`: {}",
+                        command.unwrap_or(&tool_call.tool),
+                        reason
+                    ));
+                    false
+                }
+                None => true,
+            })
+            .collect();
+        if tool_calls.is_empty() {
+            anyhow::bail!("No discovery commands to run");
+        }
+
+        let mut budget = discovery::DiscoveryBudget::new(
+            self.context_window.total_tokens,
+            self.config.discovery.budget_percent,
+        );
+        let mut report = String::from(
+            "🔄 **Updated discovery report**: the workspace was re-scanned. \
+             These results supersede any earlier discovery output.\n",
+        );
+        let count = tool_calls.len();
+        for (idx, tool_call) in tool_calls.iter().enumerate() {
+            let result = self
+                .execute_tool_call_in_dir(tool_call, options.fast_start_path)
+                .await
+                .unwrap_or_else(|e| format!("Error: {}", e));
+            let result = budget.fit(
+                result,
+                count - idx,
+                self.session_id.as_deref(),
+                &tool_call.tool,
+            );
+            let command = match tool_call.args.get("command").and_then(|c| c.as_str()) {
+                Some(command) => command.to_string(),
+                None => format!("{} {}", tool_call.tool, tool_call.args),
+            };
+            report.push_str(&format!(
+                "\n### `{}`\n```\n{}\n```\n",
+                command,
+                result.trim_end()
+            ));
+        }
+
+        let tokens = ContextWindow::estimate_tokens(&report);
+        self.context_window
+            .add_message(Message::new(MessageRole::User, report));
+        self.save_context_window("rediscovered");
+        Ok(tokens)
+    }
+
     /// Execute a tool call and return the result.
     /// This is a public wrapper around execute_tool for use by external callers
     /// like the planner's fast-discovery feature.
@@ -1151,7 +1336,13 @@ impl<W: UiWriter> Agent<W> {
             return;
         }
         match &self.session_id {
-            Some(id) => session::log_error_to_session(id, error, role, forensic_context),
+            Some(id) => session::log_error_to_session(
+                id,
+                error,
+                role,
+                forensic_context,
+                self.context_window.compression_level,
+            ),
             None => {
                 error!("Cannot log error to session: no session ID");
             }
@@ -1470,12 +1661,20 @@ impl<W: UiWriter> Agent<W> {
             tool_call_metrics: &self.tool_call_metrics,
             provider_info: self.get_provider_info().ok(),
             cache_stats: &self.cache_stats,
+            build_reports: &self.build_reports,
+            plan: self.get_plan(),
         };
 
         snapshot.format()
     }
 
-    pub fn get_tool_call_metrics(&self) -> &Vec<(String, Duration, bool)> {
+    /// The session's working plan, written with `plan_write`.
+    pub fn get_plan(&self) -> Option<plan::Plan> {
+        self.session_id.as_deref().and_then(plan::Plan::load)
+    }
+
+    /// The tool calls of the current session, in the order they were made.
+    pub fn get_tool_transcript(&self) -> &[tool_transcript::ToolCallMetric] {
         &self.tool_call_metrics
     }
 
@@ -1511,6 +1710,15 @@ impl<W: UiWriter> Agent<W> {
         self.requirements_sha = Some(sha);
     }
 
+    /// Record the IDs of the structured requirements (see `requirements`).
+    pub fn set_requirement_ids(&mut self, ids: Vec<String>) {
+        self.requirement_ids = ids;
+    }
+
+    pub fn requirement_ids(&self) -> &[String] {
+        &self.requirement_ids
+    }
+
     /// Save a session continuation artifact
     /// Save session continuation for potential resumption
     pub fn save_session_continuation(&self, summary: Option<String>) {
@@ -1537,9 +1745,12 @@ impl<W: UiWriter> Agent<W> {
         };
 
         // Get working directory
-        let working_directory = std::env::current_dir()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| ".".to_string());
+        let working_directory = match &self.working_dir {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|_| ".".to_string()),
+        };
 
         // Get description from first user message (strip "Task: " prefix if present)
         let description = self
@@ -1569,6 +1780,9 @@ impl<W: UiWriter> Agent<W> {
             error!("Failed to save session continuation: {}", e);
         } else {
             debug!("Saved session continuation artifact");
+            if let Some(ref sync) = self.session_sync {
+                sync.push_in_background(&continuation.session_id);
+            }
         }
     }
 
@@ -1598,6 +1812,17 @@ impl<W: UiWriter> Agent<W> {
         );
     }
 
+    /// Enable or disable dry-run mode: tool calls that would change files,
+    /// run commands or mutate remote state are only previewed (see `dry_run`)
+    pub fn set_dry_run(&mut self, enabled: bool) {
+        self.dry_run = enabled;
+        debug!("Dry run: {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     // =========================================================================
     // STREAMING & LLM INTERACTION
     // =========================================================================
@@ -1607,36 +1832,62 @@ impl<W: UiWriter> Agent<W> {
     /// This is the single canonical path for completing a streaming turn:
     /// 1. Finish streaming markdown
     /// 2. Save context window
-    /// 3. Add timing footer (if requested)
+    /// 3. Record turn telemetry and add the timing footer (if requested)
     /// 4. Dehydrate context (if ACD enabled)
     /// 5. Build TaskResult
     fn finalize_streaming_turn(
         &mut self,
         full_response: String,
         show_timing: bool,
-        stream_start: Instant,
-        first_token_time: Option<Duration>,
-        turn_accumulated_usage: &Option<g3_providers::Usage>,
+        state: &streaming::StreamingState,
     ) -> TaskResult {
         self.ui_writer.finish_streaming_markdown();
         self.save_context_window("completed");
 
+        let telemetry = TurnTelemetry::new(
+            state.stream_start.elapsed(),
+            state.get_ttft(),
+            state.turn_accumulated_usage.as_ref(),
+            state.tool_calls.iter().map(|c| c.tool.clone()).collect(),
+            self.context_window.percentage_used(),
+            &self.config.telemetry,
+        );
+        self.record_turn_telemetry(&telemetry);
+
         let final_response = if show_timing {
-            let ttft = first_token_time.unwrap_or_else(|| stream_start.elapsed());
-            let turn_tokens = turn_accumulated_usage.as_ref().map(|u| u.total_tokens);
-            let timing_footer = streaming::format_timing_footer(
-                stream_start.elapsed(),
-                ttft,
-                turn_tokens,
-                self.context_window.percentage_used(),
-            );
-            format!("{}\n\n{}", full_response, timing_footer)
+            format!("{}\n\n{}", full_response, telemetry.footer())
         } else {
             full_response
         };
 
         self.dehydrate_context();
-        TaskResult::new(final_response, self.context_window.clone())
+        let mut result =
+            TaskResult::new(final_response, self.context_window.clone()).with_telemetry(telemetry);
+        result.tool_calls = state.tool_calls.clone();
+        result.usage = state.turn_accumulated_usage.clone();
+        if state.iteration_count > streaming::MAX_ITERATIONS {
+            result.failure = Some(FailureCategory::MaxIterations);
+        }
+        result
+    }
+
+    /// Emit a turn's telemetry to the UI and append it to the session's telemetry log.
+    fn record_turn_telemetry(&self, telemetry: &TurnTelemetry) {
+        self.ui_writer.emit_turn_telemetry(telemetry);
+
+        if self.quiet || !self.config.telemetry.log {
+            return;
+        }
+        if let Some(session_id) = &self.session_id {
+            let path = paths::get_telemetry_file(session_id);
+            if let Err(e) = telemetry.append_to(&path) {
+                warn!(
+                    "Failed to write turn telemetry to {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
     }
 
     /// Perform ACD dehydration - save current conversation state to a fragment.
@@ -1850,11 +2101,9 @@ Skip if nothing new. Be brief."#;
         let provider = self.providers.get(None)?;
         let provider_name = provider.name().to_string();
         let tools = if provider.has_native_tool_calling() {
-            let tool_config = tool_definitions::ToolConfig::new(
-                self.config.webdriver.enabled,
-                self.config.computer_control.enabled,
-            );
-            Some(tool_definitions::create_tool_definitions(tool_config))
+            let tool_config = tool_definitions::ToolConfig::from_config(&self.config)
+                .with_mcp_tools(self.mcp_servers.tools());
+            Some(self.apply_tool_policy(tool_definitions::create_tool_definitions(tool_config)))
         } else {
             None
         };
@@ -1869,6 +2118,7 @@ Skip if nothing new. Be brief."#;
             stream: true,
             tools,
             disable_thinking: true, // Keep it brief
+            cancellation_token: None,
         };
 
         // Execute the reminder turn (show_timing = false to keep it quiet)
@@ -1882,7 +2132,7 @@ Skip if nothing new. Be brief."#;
     /// which would require an LLM provider.
     pub fn init_session_id_for_test(&mut self, description: &str) {
         if self.session_id.is_none() {
-            self.session_id = Some(self.generate_session_id(description));
+            self.set_session_id(self.generate_session_id(description));
         }
     }
 
@@ -1912,48 +2162,80 @@ Skip if nothing new. Be brief."#;
         let session_log_path = PathBuf::from(&continuation.session_log_path);
 
         // If context < 80%, try to restore full context
-        if continuation.can_restore_full_context() && session_log_path.exists() {
-            // Load the session log
-            let json = std::fs::read_to_string(&session_log_path)?;
-            let session_data: serde_json::Value = serde_json::from_str(&json)?;
-
-            // Extract conversation history
-            if let Some(context_window) = session_data.get("context_window") {
-                if let Some(history) = context_window.get("conversation_history") {
-                    if let Some(messages) = history.as_array() {
-                        // Clear current conversation (keep system messages)
-                        self.context_window.clear_conversation();
-
-                        // Restore messages from session log (skip system messages as they're preserved)
-                        for msg in messages {
-                            let role_str =
-                                msg.get("role").and_then(|r| r.as_str()).unwrap_or("user");
-                            let content = msg.get("content").and_then(|c| c.as_str()).unwrap_or("");
-
-                            let role = match role_str {
-                                "system" => continue, // Skip system messages, already preserved
-                                "assistant" => MessageRole::Assistant,
-                                _ => MessageRole::User,
-                            };
-
-                            self.context_window.add_message(Message {
-                                role,
-                                id: String::new(),
-                                images: Vec::new(),
-                                content: content.to_string(),
-                                kind: g3_providers::MessageKind::Regular,
-                                cache_control: None,
-                            });
-                        }
-
-                        debug!("Restored full context from session log");
-                        return Ok(true);
+        if continuation.can_restore_full_context() && compressed_file::exists(&session_log_path) {
+            // Load and validate the session log before trusting it
+            let json = compressed_file::read_to_string(&session_log_path)?;
+            let validation = serde_json::from_str::<serde_json::Value>(&json)
+                .map_err(|e| anyhow::anyhow!("Session file is not valid JSON: {}", e))
+                .and_then(|data| session::validate_session_log(&data));
+
+            match validation {
+                Ok(validation) if validation.is_intact() || !validation.messages.is_empty() => {
+                    self.report_session_validation(&continuation.session_id, &validation);
+
+                    // Clear current conversation (keep system messages)
+                    self.context_window.clear_conversation();
+                    for message in validation.messages {
+                        self.context_window.add_message(message);
                     }
+                    // Recount rather than trusting the token totals stored in the file
+                    self.context_window.recalculate_tokens();
+
+                    debug!(
+                        "Restored full context from session log (schema v{}, {} tokens recounted)",
+                        validation.schema_version, validation.recounted_tokens
+                    );
+                    return Ok(true);
+                }
+                Ok(validation) => {
+                    warn!("Session log has no recoverable messages: {:?}", validation.issues);
+                    self.ui_writer.print_context_status(
+                        "⚠️ Session file has no recoverable messages; restoring from summary.",
+                    );
+                }
+                Err(e) => {
+                    warn!("Session log failed validation: {}", e);
+                    self.ui_writer.print_context_status(&format!(
+                        "⚠️ Could not restore full session: {}. Restoring from summary.",
+                        e
+                    ));
                 }
             }
         }
 
-        // Fall back to using session summary + TODO
+        self.restore_summary(continuation);
+        Ok(false)
+    }
+
+    /// Tell the user how a session file that is about to be restored fared
+    /// in validation: damage as a warning, schema migration as a note.
+    fn report_session_validation(
+        &self,
+        session_id: &str,
+        validation: &session::SessionValidation,
+    ) {
+        if !validation.is_intact() {
+            warn!(
+                "Session {} restored with issues: {:?}",
+                session_id, validation.issues
+            );
+            self.ui_writer.print_context_status(&format!(
+                "⚠️ Session file was damaged: {}. Recovered {} message(s), dropped {}.",
+                validation.issues.join("; "),
+                validation.messages.len(),
+                validation.dropped
+            ));
+        }
+        for note in &validation.notes {
+            debug!("Session {}: {}", session_id, note);
+            self.ui_writer
+                .print_context_status(&format!("ℹ️ Migrated session: {}", note));
+        }
+    }
+
+    /// Add the summary and TODO list saved with `continuation` to the
+    /// context, when its full conversation can't be restored.
+    fn restore_summary(&mut self, continuation: &crate::session_continuation::SessionContinuation) {
         let mut context_msg = String::new();
         if let Some(ref summary) = continuation.summary {
             context_msg.push_str(&format!("Previous session summary:\n{}\n\n", summary));
@@ -1970,19 +2252,125 @@ Skip if nothing new. Be brief."#;
                 content: format!("[Session Resumed]\n\n{}", context_msg),
                 kind: g3_providers::MessageKind::Regular,
                 cache_control: None,
+                stored_body: None,
             });
         }
 
         debug!("Restored session from summary");
-        Ok(false)
     }
 
-    /// Switch to a different session, saving the current one first.
-    /// This discards the current in-memory state and loads the new session.
-    pub fn switch_to_session(
+    /// Resume the saved session `session_id`: any session under
+    /// `.g3/sessions/`, not only the continuation the last run left. The
+    /// current session is saved first. With `last_turns`, only the last N
+    /// turns of the conversation are restored.
+    ///
+    /// What is restored (messages, tokens, TODO list) is shown through the
+    /// UiWriter and returned.
+    pub fn resume(
         &mut self,
-        continuation: &crate::session_continuation::SessionContinuation,
-    ) -> Result<bool> {
+        session_id: &str,
+        last_turns: Option<usize>,
+    ) -> Result<session::ResumePreview> {
+        let meta = sessions::get_session_meta(session_id)?;
+        let continuation = session_continuation::load_session_continuation(session_id)
+            .unwrap_or_else(|e| {
+                warn!("Ignoring unreadable continuation of {}: {}", session_id, e);
+                None
+            });
+
+        let load = |path: &std::path::Path| {
+            compressed_file::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| {
+                    serde_json::from_str::<serde_json::Value>(&json)
+                        .map_err(|e| anyhow::anyhow!("Session file is not valid JSON: {}", e))
+                })
+                .and_then(|data| session::validate_session_log(&data))
+        };
+        let mut validation = load(&paths::get_session_file(session_id));
+        if !matches!(&validation, Ok(v) if !v.messages.is_empty()) {
+            // session.json was lost or damaged: fall back to the newest
+            // autosave checkpoint
+            let checkpoint = session::list_checkpoints(session_id)
+                .pop()
+                .and_then(|path| load(&path).ok())
+                .filter(|v| !v.messages.is_empty());
+            if let Some(checkpoint) = checkpoint {
+                self.ui_writer.print_context_status(
+                    "⚠️ Session file could not be restored; using the last autosave checkpoint.",
+                );
+                validation = Ok(checkpoint);
+            }
+        }
+        let messages = match validation {
+            Ok(validation) if !validation.messages.is_empty() => {
+                self.report_session_validation(session_id, &validation);
+                Some(validation.messages)
+            }
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Session log of {} failed validation: {}", session_id, e);
+                None
+            }
+        };
+        if messages.is_none() && continuation.is_none() {
+            anyhow::bail!("Session {} has no conversation to restore", session_id);
+        }
+
+        self.begin_session(session_id.to_string());
+        if let Some(ref continuation) = continuation {
+            self.is_agent_mode = continuation.is_agent_mode;
+            self.agent_name = continuation.agent_name.clone();
+        }
+        let todo = continuation
+            .as_ref()
+            .and_then(|c| c.todo_snapshot.clone())
+            .or_else(|| std::fs::read_to_string(paths::get_session_todo_path(session_id)).ok())
+            .filter(|todo| !todo.trim().is_empty());
+        if let Some(ref todo) = todo {
+            if let Ok(mut guard) = self.todo_content.try_write() {
+                *guard = todo.clone();
+            }
+        }
+
+        let mut preview = session::ResumePreview {
+            session_id: session_id.to_string(),
+            description: meta.description,
+            messages: 0,
+            skipped_turns: 0,
+            tokens: 0,
+            todo,
+            full_context: messages.is_some(),
+        };
+        self.context_window.clear_conversation();
+        match messages {
+            Some(messages) => {
+                let (messages, skipped_turns) = match last_turns {
+                    Some(turns) => session::last_turns(messages, turns),
+                    None => (messages, 0),
+                };
+                preview.messages = messages.len();
+                preview.skipped_turns = skipped_turns;
+                for message in messages {
+                    self.context_window.add_message(message);
+                }
+                self.context_window.recalculate_tokens();
+            }
+            None => {
+                if let Some(ref continuation) = continuation {
+                    self.restore_summary(continuation);
+                }
+            }
+        }
+        preview.tokens = self.context_window.used_tokens;
+
+        self.ui_writer.print_context_status(&preview.format());
+        Ok(preview)
+    }
+
+    /// Save the current session and start over as `session_id`, without
+    /// loading anything from it yet.
+    fn begin_session(&mut self, session_id: String) {
         // Save current session first (so it can be resumed later)
         self.save_session_continuation(None);
 
@@ -1995,7 +2383,16 @@ Skip if nothing new. Be brief."#;
         self.pending_90_compaction = false;
 
         // Update session ID to the new session
-        self.session_id = Some(continuation.session_id.clone());
+        self.set_session_id(session_id);
+    }
+
+    /// Switch to a different session, saving the current one first.
+    /// This discards the current in-memory state and loads the new session.
+    pub fn switch_to_session(
+        &mut self,
+        continuation: &crate::session_continuation::SessionContinuation,
+    ) -> Result<bool> {
+        self.begin_session(continuation.session_id.clone());
 
         // Update agent mode info from continuation
         self.is_agent_mode = continuation.is_agent_mode;
@@ -2025,6 +2422,17 @@ Skip if nothing new. Be brief."#;
     /// Create tool definitions for native tool calling providers
 
     /// Helper method to stream with retry logic
+    #[tracing::instrument(
+        name = "provider_request",
+        skip_all,
+        fields(
+            provider = tracing::field::Empty,
+            model = tracing::field::Empty,
+            messages = request.messages.len(),
+            attempts = tracing::field::Empty,
+            otel.status_code = tracing::field::Empty,
+        )
+    )]
     async fn stream_with_retry(
         &self,
         request: &CompletionRequest,
@@ -2032,6 +2440,17 @@ Skip if nothing new. Be brief."#;
     ) -> Result<g3_providers::CompletionStream> {
         use crate::error_handling::{calculate_retry_delay, classify_error, ErrorType};
 
+        let span = tracing::Span::current();
+        {
+            let provider = self.providers.get(None)?;
+            span.record("provider", provider.name());
+            span.record("model", provider.model());
+        }
+
+        // Read stored message bodies once, without blocking the runtime
+        let mut request = request.clone();
+        g3_providers::load_bodies(&mut request.messages).await;
+
         let mut attempt = 0;
         let max_attempts = if self.is_autonomous {
             self.config.agent.autonomous_max_retry_attempts
@@ -2041,6 +2460,7 @@ Skip if nothing new. Be brief."#;
 
         loop {
             attempt += 1;
+            span.record("attempts", attempt);
             let provider = self.providers.get(None)?;
 
             match provider.stream(request.clone()).await {
@@ -2066,11 +2486,13 @@ Skip if nothing new. Be brief."#;
                         );
                         tokio::time::sleep(delay).await;
                     } else {
+                        span.record("otel.status_code", "ERROR");
                         error_context.clone().log_error(&e);
                         return Err(e);
                     }
                 }
                 Err(e) => {
+                    span.record("otel.status_code", "ERROR");
                     error_context.clone().log_error(&e);
                     return Err(e);
                 }
@@ -2078,6 +2500,7 @@ Skip if nothing new. Be brief."#;
         }
     }
 
+    #[tracing::instrument(skip_all, fields(messages = request.messages.len()))]
     async fn stream_completion_with_tools(
         &mut self,
         mut request: CompletionRequest,
@@ -2094,7 +2517,6 @@ Skip if nothing new. Be brief."#;
         // =========================================================================
 
         use crate::error_handling::ErrorContext;
-        use tokio_stream::StreamExt;
 
         debug!("Starting stream_completion_with_tools");
 
@@ -2126,6 +2548,13 @@ Skip if nothing new. Be brief."#;
                 debug!("Injected {} completed research result(s) into context", injected_count);
                 self.ui_writer.println(&format!("📋 {} research result(s) ready and injected into context", injected_count));
             }
+            let verified_count = self.inject_completed_verification();
+            if verified_count > 0 {
+                self.ui_writer.println(&format!(
+                    "🧪 {} verification result(s) ready and injected into context",
+                    verified_count
+                ));
+            }
 
             // Get provider info for logging, then drop it to avoid borrow issues
             let (provider_name, provider_model) = {
@@ -2198,8 +2627,11 @@ Skip if nothing new. Be brief."#;
 
             // Create fresh iteration state for this streaming iteration
             let mut iter = streaming::IterationState::new();
+            let stall_timeout =
+                streaming::stall_timeout(self.config.agent.stream_stall_timeout_secs);
+            let mut stalled = false;
 
-            while let Some(chunk_result) = stream.next().await {
+            while let Some(chunk_result) = streaming::next_chunk(&mut stream, stall_timeout).await {
                 match chunk_result {
                     Ok(chunk) => {
                         // Notify UI about SSE received (including pings)
@@ -2271,6 +2703,13 @@ Skip if nothing new. Be brief."#;
                         // Process chunk with the new parser
                         let completed_tools = iter.parser.process_chunk(&chunk);
 
+                        // Start fetching what a still-streaming tool call will need
+                        if completed_tools.is_empty() {
+                            if let Some(partial) = iter.parser.partial_tool_call() {
+                                self.prefetch_cache.observe(&partial);
+                            }
+                        }
+
                         // Handle completed tool calls - process all if multiple calls enabled
                         // Always process all tool calls - they will be executed after stream ends
 
@@ -2368,6 +2807,7 @@ Skip if nothing new. Be brief."#;
                             // Clone working_dir to avoid borrow checker issues
                             let working_dir = self.working_dir.clone();
                             let exec_start = Instant::now();
+                            let started_at = chrono::Utc::now();
                             // Tool execution timeout: 20 minutes for research, 8 minutes for others
                             let timeout_duration = if tool_call.tool == "research" {
                                 Duration::from_secs(20 * 60) // 20 minutes for research
@@ -2395,11 +2835,22 @@ Skip if nothing new. Be brief."#;
 
                             // Track tool call metrics
                             let tool_success = !tool_result.contains("❌");
-                            self.tool_call_metrics.push((
-                                tool_call.tool.clone(),
+                            let metric = tool_transcript::ToolCallMetric::new(
+                                &tool_call.tool,
+                                &tool_call.args,
+                                started_at,
                                 exec_duration,
                                 tool_success,
-                            ));
+                                tool_result.len(),
+                            );
+                            state.tool_calls.push(ToolCallRecord {
+                                tool: tool_call.tool.clone(),
+                                args: tool_call.args.clone(),
+                                success: tool_success,
+                                duration_ms: exec_duration.as_millis() as u64,
+                            });
+                            self.ui_writer
+                                .emit_tool_result(&tool_call.tool, &tool_result, tool_success);
 
                             // Display tool execution result with proper indentation
                             let compact_summary = {
@@ -2464,7 +2915,17 @@ Skip if nothing new. Be brief."#;
                                 )
                             };
                             let mut result_message = {
-                                let content = format!("Tool result: {}", tool_result);
+                                // One allocation of the final size, written in order, so
+                                // large results (whole files) aren't moved to fit the prefix.
+                                // The result stays a plain String from here on: what keeps
+                                // big ones from being copied with every history clone is
+                                // storing their bodies on disk (`Message::store_body`), not
+                                // a buffer shared with the request.
+                                const PREFIX: &str = "Tool result: ";
+                                let mut content =
+                                    String::with_capacity(PREFIX.len() + tool_result.len());
+                                content.push_str(PREFIX);
+                                content.push_str(&tool_result);
 
                                 // Apply cache control every 10 tool calls (max 4 annotations)
                                 let should_cache = self.tool_call_count > 0
@@ -2505,6 +2966,16 @@ Skip if nothing new. Be brief."#;
                                 .context_window
                                 .used_tokens
                                 .saturating_sub(tokens_before);
+                            let metric = metric.with_tokens_added(tokens_delta);
+                            metric.log(self.session_id.as_deref());
+                            self.tool_call_metrics.push(metric);
+                            // Checkpoint long turns so a crash loses little
+                            if let Some(ref session_id) = self.session_id {
+                                if !self.quiet {
+                                    self.autosave
+                                        .tool_call_finished(session_id, &self.context_window);
+                                }
+                            }
 
                             // TODO tools handle their own output via print_todo_compact, skip timing
                             if !is_todo_tool {
@@ -2533,16 +3004,15 @@ Skip if nothing new. Be brief."#;
                             // Ensure tools are included for native providers in subsequent iterations
                             let provider_for_tools = self.providers.get(None)?;
                             if provider_for_tools.has_native_tool_calling() {
-                                let mut tool_config = tool_definitions::ToolConfig::new(
-                                    self.config.webdriver.enabled,
-                                    self.config.computer_control.enabled,
-                                );
+                                let mut tool_config = tool_definitions::ToolConfig::from_config(&self.config)
+                                    .with_mcp_tools(self.mcp_servers.tools());
                                 // Exclude research tool for scout agent to prevent recursion
                                 if self.agent_name.as_deref() == Some("scout") {
                                     tool_config = tool_config.with_research_excluded();
                                 }
-                                request.tools =
-                                    Some(tool_definitions::create_tool_definitions(tool_config));
+                                request.tools = Some(self.apply_tool_policy(
+                                    tool_definitions::create_tool_definitions(tool_config),
+                                ));
                             }
 
                             // DO NOT add final_display_content to full_response here!
@@ -2713,9 +3183,7 @@ Skip if nothing new. Be brief."#;
                                 return Ok(self.finalize_streaming_turn(
                                     String::new(),
                                     show_timing,
-                                    state.stream_start,
-                                    state.first_token_time,
-                                    &state.turn_accumulated_usage,
+                                    &state,
                                 ));
                             }
                             break; // Tool was executed, break to continue outer loop
@@ -2734,6 +3202,28 @@ Skip if nothing new. Be brief."#;
                         error!("Parser state at error: text_buffer_len={}, has_incomplete={}, message_stopped={}",
                             iter.parser.text_buffer_len(), iter.parser.has_incomplete_tool_call(), iter.parser.is_message_stopped());
 
+                        // A stalled stream is dropped (closing the connection) and resumed
+                        if streaming::is_stall_error(&error_msg)
+                            && state.stall_reconnects < self.config.agent.max_stall_reconnects
+                        {
+                            state.stall_reconnects += 1;
+                            warn!(
+                                "{} at chunk {}, reconnecting ({}/{})",
+                                error_msg,
+                                iter.chunks_received + 1,
+                                state.stall_reconnects,
+                                self.config.agent.max_stall_reconnects
+                            );
+                            self.ui_writer.print_context_status(&format!(
+                                "⚠️ {}, reconnecting ({}/{})",
+                                error_msg,
+                                state.stall_reconnects,
+                                self.config.agent.max_stall_reconnects
+                            ));
+                            stalled = true;
+                            break;
+                        }
+
                         // Check if this is a recoverable connection error
                         let is_connection_error = streaming::is_connection_error(&error_msg);
 
@@ -2768,6 +3258,23 @@ Skip if nothing new. Be brief."#;
                 }
             }
 
+            if stalled {
+                // Keep what was already streamed and ask the model to carry on from there;
+                // with nothing streamed yet, the same request is simply sent again
+                if !iter.current_response.trim().is_empty() {
+                    self.context_window.add_message(Message::new(
+                        MessageRole::Assistant,
+                        iter.current_response.clone(),
+                    ));
+                    self.context_window.add_message(Message::new(
+                        MessageRole::User,
+                        streaming::STALL_RESUME_PROMPT.to_string(),
+                    ));
+                    request.messages = self.context_window.conversation_history.clone();
+                }
+                continue;
+            }
+
             // Update context window with actual usage if available
             if let Some(usage) = iter.accumulated_usage {
                 debug!("Updating context window with actual usage from stream");
@@ -2850,9 +3357,7 @@ Skip if nothing new. Be brief."#;
                 return Ok(self.finalize_streaming_turn(
                     state.full_response.clone(),
                     show_timing,
-                    state.stream_start,
-                    state.first_token_time,
-                    &state.turn_accumulated_usage,
+                    &state,
                 ));
             }
 
@@ -2860,13 +3365,7 @@ Skip if nothing new. Be brief."#;
         }
 
         // --- Phase 4: Post-Loop Finalization ---
-        Ok(self.finalize_streaming_turn(
-            state.full_response.clone(),
-            show_timing,
-            state.stream_start,
-            state.first_token_time,
-            &state.turn_accumulated_usage,
-        ))
+        Ok(self.finalize_streaming_turn(state.full_response.clone(), show_timing, &state))
     }
 
     // =========================================================================
@@ -2878,7 +3377,85 @@ Skip if nothing new. Be brief."#;
         self.execute_tool_in_dir(tool_call, None).await
     }
 
+    /// Drop tool definitions the tool policy doesn't allow, and tell the
+    /// model which shell commands run in when it isn't bash.
+    fn apply_tool_policy(&self, mut tools: Vec<g3_providers::Tool>) -> Vec<g3_providers::Tool> {
+        tools.retain(|tool| self.tool_policy.allows(&tool.name));
+        let shell = self
+            .config
+            .shell
+            .program
+            .as_deref()
+            .and_then(|name| g3_execution::Shell::from_name(name).ok())
+            .filter(|&shell| shell != g3_execution::Shell::Bash);
+        if let Some(shell) = shell {
+            for tool in &mut tools {
+                if tool.name == "shell" || tool.name == "background_process" {
+                    tool.description.push_str(&format!(
+                        " Commands run in {}, not bash: write them in its syntax.",
+                        shell
+                    ));
+                }
+            }
+        }
+        tools
+    }
+
+    /// Ask for approval of a risky tool call (see `approval`). Returns the
+    /// message refusing the call, or `None` when it may run.
+    fn check_approval(&self, tool_call: &ToolCall, working_dir: Option<&str>) -> Option<String> {
+        // Assess the call where it will run; a `working_dir` argument the
+        // executor refuses leaves the workspace itself
+        let workspace = tools::executor::workspace_dir(working_dir);
+        let dir = tool_call
+            .args
+            .get("working_dir")
+            .and_then(|v| v.as_str())
+            .and_then(|dir| tools::executor::resolve_working_dir(&workspace, dir).ok())
+            .unwrap_or_else(|| workspace.clone());
+        let risk = approval::assess(tool_call, &workspace, &dir)?;
+        if risk.auto_approved(self.config.approval.auto_approve) {
+            return None;
+        }
+        if self.is_autonomous {
+            return Some(format!(
+                "❌ This call needs the user's approval ({}), which isn't available in autonomous mode. It would run with --auto-approve {}.",
+                risk,
+                risk.kind.approval_level()
+            ));
+        }
+        if self
+            .ui_writer
+            .prompt_user_yes_no(&format!("Allow {}: {}?", tool_call.tool, risk))
+        {
+            None
+        } else {
+            Some(format!("❌ The user declined this call ({})", risk))
+        }
+    }
+
+    /// Append a tool call previewed in dry-run mode to the session's
+    /// `dry_run.jsonl`.
+    fn record_planned_action(&self, tool_call: &ToolCall, preview: &str) {
+        let Some(session_id) = &self.session_id else {
+            return;
+        };
+        let path = paths::get_dry_run_file(session_id);
+        if let Err(e) = dry_run::PlannedAction::new(tool_call, preview).append_to(&path) {
+            warn!(
+                "Failed to record planned action in {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
     /// Execute a tool with an optional working directory (for discovery commands)
+    #[tracing::instrument(
+        name = "tool",
+        skip_all,
+        fields(tool = %tool_call.tool, otel.status_code = tracing::field::Empty)
+    )]
     pub async fn execute_tool_in_dir(
         &mut self,
         tool_call: &ToolCall,
@@ -2889,6 +3466,14 @@ Skip if nothing new. Be brief."#;
         self.tool_calls_this_turn.push(tool_call.tool.clone());
 
         let result = self.execute_tool_inner_in_dir(tool_call, working_dir).await;
+        // Failed as counted in the tool-call metrics: an error, or a result with ❌
+        let failed = match &result {
+            Ok(s) => s.contains("❌"),
+            Err(_) => true,
+        };
+        if failed {
+            tracing::Span::current().record("otel.status_code", "ERROR");
+        }
         let log_str = match &result {
             Ok(s) => s.clone(),
             Err(e) => format!("ERROR: {}", e),
@@ -2920,6 +3505,48 @@ Skip if nothing new. Be brief."#;
         );
         debug!("======================");
 
+        if !self.tool_policy.allows(&tool_call.tool) {
+            warn!("Refusing tool '{}': not allowed by tool policy", tool_call.tool);
+            return Ok(format!(
+                "❌ Tool '{}' is not available to this agent",
+                tool_call.tool
+            ));
+        }
+
+        // Report malformed arguments with the tool's schema so the model can
+        // correct the whole call at once
+        if let Some(message) = tool_validation::check_tool_args(&tool_call.tool, &tool_call.args) {
+            warn!("Rejecting '{}' call with invalid arguments", tool_call.tool);
+            return Ok(message);
+        }
+
+        if self.dry_run {
+            if let Some(preview) = dry_run::preview(tool_call, working_dir) {
+                self.record_planned_action(tool_call, &preview);
+                return Ok(preview);
+            }
+        }
+
+        if let Some(refusal) = self.check_approval(tool_call, working_dir) {
+            warn!("Refusing '{}' call without approval", tool_call.tool);
+            return Ok(refusal);
+        }
+
+        if self.mcp_servers.has_tool(&tool_call.tool) {
+            return self
+                .mcp_servers
+                .call(&tool_call.tool, &tool_call.args)
+                .await;
+        }
+
+        // Idempotent calls that fail transiently are made again before the
+        // failure reaches the model
+        let max_retries = if retry::is_idempotent(tool_call) {
+            self.config.agent.max_tool_retries
+        } else {
+            0
+        };
+
         // Create tool context for dispatch
         let mut ctx = tools::executor::ToolContext {
             config: &self.config,
@@ -2937,12 +3564,51 @@ Skip if nothing new. Be brief."#;
             context_total_tokens: self.context_window.total_tokens,
             context_used_tokens: self.context_window.used_tokens,
             pending_research_manager: &self.pending_research_manager,
+            build_reports: &mut self.build_reports,
+            pending_verification_manager: &self.pending_verification_manager,
+            prefetch_cache: &self.prefetch_cache,
+            notebook_kernels: &self.notebook_kernels,
+            python_interpreter: &self.python_interpreter,
+            persistent_shell: &self.persistent_shell,
+            file_versions: &self.file_versions,
         };
 
         // Dispatch to the appropriate tool handler
-        let result = tool_dispatch::dispatch_tool(tool_call, &mut ctx).await?;
+        let mut attempt = 0;
+        let result = loop {
+            let result = tool_dispatch::dispatch_tool(tool_call, &mut ctx).await;
+            let Some(reason) = retry::transient_tool_failure(&result) else {
+                break result;
+            };
+            if attempt >= max_retries {
+                break result;
+            }
+            attempt += 1;
+            let delay = error_handling::calculate_retry_delay(attempt, false);
+            warn!(
+                "'{}' failed with a {}, retrying ({}/{})",
+                tool_call.tool, reason, attempt, max_retries
+            );
+            ctx.ui_writer.print_context_status(&format!(
+                "🔁 {} failed with a {}, retrying in {:.1}s ({}/{})",
+                tool_call.tool,
+                reason,
+                delay.as_secs_f64(),
+                attempt,
+                max_retries
+            ));
+            tokio::time::sleep(delay).await;
+        };
 
-        Ok(result)
+        // Files changed by other tools (a formatter run through `shell`, a
+        // `regex_replace`) were changed by the agent itself, so they don't
+        // count as edits made behind its back
+        if !file_versions::TRACKING_TOOLS.contains(&tool_call.tool.as_str())
+            && !dry_run::is_read_only(tool_call)
+        {
+            self.file_versions.refresh();
+        }
+        result
     }
 }
 
@@ -0,0 +1,336 @@
+//! Minimal Language Server Protocol client over stdio.
+//!
+//! Enough of LSP for one-shot requests such as a rename: start the server,
+//! initialize it for a workspace, open documents, wait until it has loaded
+//! the project, send requests and shut it down. Messages are read by a
+//! background task so waiting with a timeout never cuts a message in half.
+//! Requests from the server (`workspace/configuration`, progress tokens,
+//! capability registration) are answered with empty results.
+//!
+//! Readiness is judged from what the server reports: rust-analyzer's
+//! `experimental/serverStatus` when it sends one, otherwise open
+//! work-done progress (indexing) and a short quiet period.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::debug;
+use url::Url;
+
+/// How long the server must stay quiet before it counts as ready when it
+/// doesn't report its status.
+const QUIET_PERIOD: Duration = Duration::from_millis(750);
+
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// LSP error code for a request overtaken by a change in the server's state.
+pub const CONTENT_MODIFIED: i64 = -32801;
+
+/// LSP error code for a request the server cancelled, e.g. while loading.
+pub const SERVER_CANCELLED: i64 = -32802;
+
+/// An error response to a request.
+#[derive(Debug, Clone)]
+pub struct ResponseError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl ResponseError {
+    /// Whether trying again later may succeed.
+    pub fn is_retryable(&self) -> bool {
+        self.code == CONTENT_MODIFIED || self.code == SERVER_CANCELLED
+    }
+}
+
+/// A running language server.
+pub struct LspClient {
+    name: String,
+    /// Killed when the client is dropped
+    _child: Child,
+    stdin: ChildStdin,
+    incoming: mpsc::UnboundedReceiver<Value>,
+    next_id: i64,
+    /// rust-analyzer's `quiescent` flag, once it has reported one
+    quiescent: Option<bool>,
+    /// Work-done progress tokens begun and not yet ended
+    progress: HashSet<String>,
+}
+
+impl LspClient {
+    /// Start `command` in `root` and initialize it with `root` as the
+    /// workspace.
+    pub async fn start(
+        name: &str,
+        command: &str,
+        args: &[String],
+        root: &Path,
+        deadline: Instant,
+    ) -> Result<Self> {
+        let mut child = Command::new(command)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start language server '{}'", command))?;
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("No stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("No stdout"))?;
+        let (sender, incoming) = mpsc::unbounded_channel();
+        tokio::spawn(read_messages(stdout, sender));
+
+        let mut client = Self {
+            name: name.to_string(),
+            _child: child,
+            stdin,
+            incoming,
+            next_id: 0,
+            quiescent: None,
+            progress: HashSet::new(),
+        };
+        let root_uri = file_uri(root)?;
+        let params = json!({
+            "processId": std::process::id(),
+            "rootUri": root_uri,
+            "rootPath": root.display().to_string(),
+            "workspaceFolders": [{
+                "uri": root_uri,
+                "name": root.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+            }],
+            "capabilities": {
+                "general": { "positionEncodings": ["utf-16"] },
+                "workspace": {
+                    "workspaceEdit": {
+                        "documentChanges": true,
+                        "resourceOperations": ["create", "rename", "delete"]
+                    },
+                    "configuration": true,
+                    "workspaceFolders": true
+                },
+                "textDocument": {
+                    "synchronization": {},
+                    "rename": { "prepareSupport": false }
+                },
+                "window": { "workDoneProgress": true },
+                "experimental": { "serverStatusNotification": true }
+            }
+        });
+        client
+            .request("initialize", params, deadline)
+            .await?
+            .map_err(|e| anyhow!("{} failed to initialize: {}", name, e.message))?;
+        client.notify("initialized", json!({})).await?;
+        Ok(client)
+    }
+
+    /// Open a document so the server analyses it.
+    pub async fn open(&mut self, path: &Path, language_id: &str, text: &str) -> Result<()> {
+        let params = json!({
+            "textDocument": {
+                "uri": file_uri(path)?,
+                "languageId": language_id,
+                "version": 1,
+                "text": text,
+            }
+        });
+        self.notify("textDocument/didOpen", params).await
+    }
+
+    /// Wait until the server has loaded the workspace, or `deadline`.
+    pub async fn wait_until_ready(&mut self, deadline: Instant) -> Result<()> {
+        loop {
+            let quiet_until = (Instant::now() + QUIET_PERIOD).min(deadline);
+            match tokio::time::timeout_at(quiet_until, self.incoming.recv()).await {
+                Ok(Some(message)) => self.handle(message).await?,
+                Ok(None) => bail!("{} exited", self.name),
+                Err(_) if Instant::now() >= deadline => return Ok(()),
+                Err(_) => {
+                    let ready = match self.quiescent {
+                        Some(quiescent) => quiescent,
+                        None => self.progress.is_empty(),
+                    };
+                    if ready {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether the server says it is still loading.
+    pub fn is_loading(&self) -> bool {
+        self.quiescent == Some(false) || !self.progress.is_empty()
+    }
+
+    /// Send a request and wait for its response, handling anything the
+    /// server sends meanwhile.
+    pub async fn request(
+        &mut self,
+        method: &str,
+        params: Value,
+        deadline: Instant,
+    ) -> Result<std::result::Result<Value, ResponseError>> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .await?;
+        loop {
+            let message = match tokio::time::timeout_at(deadline, self.incoming.recv()).await {
+                Ok(Some(message)) => message,
+                Ok(None) => bail!("{} exited while handling {}", self.name, method),
+                Err(_) => bail!("{} did not answer {} in time", self.name, method),
+            };
+            let is_response = message.get("method").is_none();
+            if is_response && message.get("id").and_then(|v| v.as_i64()) == Some(id) {
+                if let Some(error) = message.get("error") {
+                    return Ok(Err(ResponseError {
+                        code: error
+                            .get("code")
+                            .and_then(|v| v.as_i64())
+                            .unwrap_or_default(),
+                        message: error
+                            .get("message")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("unknown error")
+                            .to_string(),
+                    }));
+                }
+                return Ok(Ok(message.get("result").cloned().unwrap_or(Value::Null)));
+            }
+            self.handle(message).await?;
+        }
+    }
+
+    pub async fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .await
+    }
+
+    /// Ask the server to shut down and exit. The process is killed when the
+    /// client is dropped if it doesn't.
+    pub async fn shutdown(mut self) {
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        if self
+            .request("shutdown", Value::Null, deadline)
+            .await
+            .is_ok()
+        {
+            let _ = self.notify("exit", Value::Null).await;
+        }
+    }
+
+    /// Track status notifications and answer server requests.
+    async fn handle(&mut self, message: Value) -> Result<()> {
+        let Some(method) = message.get("method").and_then(|v| v.as_str()) else {
+            return Ok(());
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        match method {
+            "experimental/serverStatus" => {
+                self.quiescent = params.get("quiescent").and_then(|v| v.as_bool());
+            }
+            "$/progress" => {
+                let token = params
+                    .get("token")
+                    .map(|t| t.to_string())
+                    .unwrap_or_default();
+                match params.pointer("/value/kind").and_then(|v| v.as_str()) {
+                    Some("begin") => {
+                        self.progress.insert(token);
+                    }
+                    Some("end") => {
+                        self.progress.remove(&token);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        if let Some(id) = message.get("id") {
+            debug!("Answering {} request {}", self.name, method);
+            let result = match method {
+                // One (empty) setting per item asked for
+                "workspace/configuration" => Value::Array(
+                    params
+                        .get("items")
+                        .and_then(|v| v.as_array())
+                        .map(|items| vec![Value::Null; items.len()])
+                        .unwrap_or_default(),
+                ),
+                "workspace/workspaceFolders" => Value::Array(Vec::new()),
+                // Edits are applied by the caller, not on the server's behalf
+                "workspace/applyEdit" => json!({ "applied": false }),
+                _ => Value::Null,
+            };
+            self.send(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn send(&mut self, message: Value) -> Result<()> {
+        let body = message.to_string();
+        let frame = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        self.stdin
+            .write_all(frame.as_bytes())
+            .await
+            .with_context(|| format!("{} closed its input", self.name))?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+}
+
+/// Forward each message the server writes until it closes its output.
+async fn read_messages(stdout: ChildStdout, sender: mpsc::UnboundedSender<Value>) {
+    let mut reader = BufReader::new(stdout);
+    loop {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            match reader.read_line(&mut header).await {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("Content-Length") {
+                    length = value.trim().parse::<usize>().ok();
+                }
+            }
+        }
+        let Some(length) = length else {
+            continue;
+        };
+        let mut body = vec![0; length];
+        if reader.read_exact(&mut body).await.is_err() {
+            return;
+        }
+        match serde_json::from_slice(&body) {
+            Ok(message) => {
+                if sender.send(message).is_err() {
+                    return;
+                }
+            }
+            Err(e) => debug!("Ignoring malformed LSP message: {}", e),
+        }
+    }
+}
+
+/// The `file://` URI of a path.
+pub fn file_uri(path: &Path) -> Result<String> {
+    Url::from_file_path(path)
+        .map(String::from)
+        .map_err(|_| anyhow!("{} is not an absolute path", path.display()))
+}
@@ -0,0 +1,672 @@
+//! Model Context Protocol client: the tools of external MCP servers, offered
+//! to the model next to g3's own.
+//!
+//! Servers from `[mcp.servers]` are reached over stdio (a `command` that
+//! reads and writes newline-delimited JSON-RPC) or streamable HTTP (a `url`
+//! answering POSTs with JSON or an event stream). Each is initialized and
+//! its tools listed once, when the agent is built; a server that can't be
+//! reached is skipped with a warning. Its tools are renamed
+//! `mcp__<server>__<tool>` so they can't clash with g3's tools or each other,
+//! and calls to them are forwarded with `tools/call`.
+//!
+//! Requests from the server are answered: `ping` with an empty result,
+//! anything else (sampling, roots, elicitation) as an unknown method.
+
+use anyhow::{anyhow, bail, Context, Result};
+use g3_config::{McpConfig, McpServerConfig};
+use g3_providers::Tool;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+/// Prefix of the tools of MCP servers.
+pub const TOOL_PREFIX: &str = "mcp__";
+
+/// Protocol revision asked for in `initialize`.
+const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// Longest tool name the providers accept.
+const MAX_TOOL_NAME_LEN: usize = 64;
+
+/// JSON-RPC error code for a method the client doesn't implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// How messages reach the server.
+enum Transport {
+    Stdio {
+        /// Killed when the client is dropped
+        _child: Child,
+        stdin: ChildStdin,
+        incoming: mpsc::UnboundedReceiver<Value>,
+    },
+    Http {
+        client: reqwest::Client,
+        url: String,
+        headers: HashMap<String, String>,
+        /// `Mcp-Session-Id` the server assigned in `initialize`
+        session_id: Option<String>,
+    },
+}
+
+/// A connection to one MCP server.
+pub struct McpClient {
+    name: String,
+    transport: Transport,
+    next_id: i64,
+}
+
+impl McpClient {
+    /// Start or reach the server and initialize it.
+    pub async fn connect(name: &str, config: &McpServerConfig, deadline: Instant) -> Result<Self> {
+        let transport = match (&config.command, &config.url) {
+            (Some(command), None) => {
+                let mut child = Command::new(command)
+                    .args(&config.args)
+                    .envs(&config.env)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .with_context(|| format!("Failed to start MCP server '{}'", command))?;
+                let stdin = child.stdin.take().ok_or_else(|| anyhow!("No stdin"))?;
+                let stdout = child.stdout.take().ok_or_else(|| anyhow!("No stdout"))?;
+                let (sender, incoming) = mpsc::unbounded_channel();
+                tokio::spawn(read_messages(stdout, sender));
+                Transport::Stdio {
+                    _child: child,
+                    stdin,
+                    incoming,
+                }
+            }
+            (None, Some(url)) => Transport::Http {
+                client: reqwest::Client::new(),
+                url: url.clone(),
+                headers: config.headers.clone(),
+                session_id: None,
+            },
+            _ => bail!("Set either `command` or `url` for MCP server '{}'", name),
+        };
+
+        let mut client = Self {
+            name: name.to_string(),
+            transport,
+            next_id: 0,
+        };
+        let params = json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "g3", "version": env!("CARGO_PKG_VERSION") }
+        });
+        client.request("initialize", params, deadline).await?;
+        client
+            .notify("notifications/initialized", json!({}))
+            .await?;
+        Ok(client)
+    }
+
+    /// The server's tools, as it describes them (`name`, `description`,
+    /// `inputSchema`).
+    pub async fn list_tools(&mut self, deadline: Instant) -> Result<Vec<Value>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params, deadline).await?;
+            if let Some(page) = result.get("tools").and_then(|v| v.as_array()) {
+                tools.extend(page.iter().cloned());
+            }
+            cursor = result
+                .get("nextCursor")
+                .and_then(|v| v.as_str())
+                .map(str::to_string);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    /// Call `tool` and return its result as text for the model.
+    pub async fn call_tool(
+        &mut self,
+        tool: &str,
+        arguments: &Value,
+        deadline: Instant,
+    ) -> Result<String> {
+        let arguments = if arguments.is_object() {
+            arguments.clone()
+        } else {
+            json!({})
+        };
+        let result = self
+            .request(
+                "tools/call",
+                json!({ "name": tool, "arguments": arguments }),
+                deadline,
+            )
+            .await?;
+        Ok(format_result(&result))
+    }
+
+    /// Send a request and wait for its result. An error response is an `Err`.
+    async fn request(&mut self, method: &str, params: Value, deadline: Instant) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let response = match self.transport {
+            Transport::Stdio { .. } => {
+                self.send(&message).await?;
+                loop {
+                    let Transport::Stdio { incoming, .. } = &mut self.transport else {
+                        unreachable!();
+                    };
+                    let message = match tokio::time::timeout_at(deadline, incoming.recv()).await {
+                        Ok(Some(message)) => message,
+                        Ok(None) => bail!("{} exited while handling {}", self.name, method),
+                        Err(_) => bail!("{} did not answer {} in time", self.name, method),
+                    };
+                    if is_response_to(&message, id) {
+                        break message;
+                    }
+                    self.handle(message).await?;
+                }
+            }
+            Transport::Http { .. } => {
+                match tokio::time::timeout_at(deadline, self.post_request(&message, id)).await {
+                    Ok(response) => response?
+                        .ok_or_else(|| anyhow!("{} sent no answer to {}", self.name, method))?,
+                    Err(_) => bail!("{} did not answer {} in time", self.name, method),
+                }
+            }
+        };
+
+        if let Some(error) = response.get("error") {
+            bail!(
+                "{} failed {}: {}",
+                self.name,
+                method,
+                error
+                    .get("message")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown error")
+            );
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    async fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        match self.transport {
+            Transport::Stdio { .. } => self.send(&message).await,
+            Transport::Http { .. } => self.post(&message).await.map(|_| ()),
+        }
+    }
+
+    /// Answer a request from the server; notifications need no answer.
+    async fn handle(&mut self, message: Value) -> Result<()> {
+        let (Some(method), Some(id)) = (
+            message.get("method").and_then(|v| v.as_str()),
+            message.get("id"),
+        ) else {
+            return Ok(());
+        };
+        debug!("Answering {} request {}", self.name, method);
+        let answer = server_request_answer(method, id);
+        match self.transport {
+            Transport::Stdio { .. } => self.send(&answer).await,
+            Transport::Http { .. } => self.post(&answer).await.map(|_| ()),
+        }
+    }
+
+    /// Write a message to the server's stdin.
+    async fn send(&mut self, message: &Value) -> Result<()> {
+        let Transport::Stdio { stdin, .. } = &mut self.transport else {
+            bail!("{} is not a stdio server", self.name);
+        };
+        let line = format!("{}\n", message);
+        stdin
+            .write_all(line.as_bytes())
+            .await
+            .with_context(|| format!("{} closed its input", self.name))?;
+        stdin.flush().await?;
+        Ok(())
+    }
+
+    /// POST a request to the server and return its response, whether it
+    /// came as JSON or in an event stream. Requests the server makes in the
+    /// stream meanwhile are answered.
+    async fn post_request(&mut self, message: &Value, id: i64) -> Result<Option<Value>> {
+        let mut response = self.post(message).await?;
+        let is_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if !is_stream {
+            let body: Value = response.json().await?;
+            let found = match body {
+                Value::Array(batch) => batch.into_iter().find(|m| is_response_to(m, id)),
+                message => Some(message),
+            };
+            return Ok(found);
+        }
+
+        let mut events = SseParser::default();
+        let mut answers = Vec::new();
+        let mut found = None;
+        'read: while let Some(chunk) = response.chunk().await? {
+            for data in events.push(&String::from_utf8_lossy(&chunk)) {
+                let Ok(message) = serde_json::from_str::<Value>(&data) else {
+                    continue;
+                };
+                if is_response_to(&message, id) {
+                    found = Some(message);
+                    break 'read;
+                }
+                if let (Some(method), Some(id)) = (
+                    message.get("method").and_then(|v| v.as_str()),
+                    message.get("id"),
+                ) {
+                    answers.push(server_request_answer(method, id));
+                }
+            }
+        }
+        drop(response);
+        for answer in answers {
+            self.post(&answer).await?;
+        }
+        Ok(found)
+    }
+
+    /// POST a message to the server, keeping the session it assigns.
+    async fn post(&mut self, message: &Value) -> Result<reqwest::Response> {
+        let Transport::Http {
+            client,
+            url,
+            headers,
+            session_id,
+        } = &mut self.transport
+        else {
+            bail!("{} is not an HTTP server", self.name);
+        };
+        let mut request = client
+            .post(url.as_str())
+            .header("Accept", "application/json, text/event-stream")
+            .json(message);
+        for (name, value) in headers.iter() {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(session_id) = session_id.as_deref() {
+            request = request.header("Mcp-Session-Id", session_id);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", self.name))?;
+        if let Some(assigned) = response
+            .headers()
+            .get("Mcp-Session-Id")
+            .and_then(|v| v.to_str().ok())
+        {
+            *session_id = Some(assigned.to_string());
+        }
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("{} answered {}: {}", self.name, status, body.trim());
+        }
+        Ok(response)
+    }
+}
+
+/// The MCP servers an agent uses, and their tools.
+#[derive(Default)]
+pub struct McpServers {
+    clients: HashMap<String, McpClient>,
+    /// Tool definitions offered to the model
+    tools: Vec<Tool>,
+    /// Tool name -> the server and its name for the tool
+    routes: HashMap<String, (String, String)>,
+    call_timeout: Duration,
+}
+
+impl McpServers {
+    /// Connect to the enabled servers of `config` and list their tools.
+    /// Servers that fail are skipped; the warnings are returned for the user.
+    pub async fn connect(config: &McpConfig) -> (Self, Vec<String>) {
+        let mut servers = Self {
+            call_timeout: Duration::from_secs(config.call_timeout_secs),
+            ..Self::default()
+        };
+        let mut warnings = Vec::new();
+
+        // In name order, so the tools are listed the same way every time
+        let mut names: Vec<&String> = config
+            .servers
+            .iter()
+            .filter(|(_, server)| server.enabled)
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        let deadline = Instant::now() + Duration::from_secs(config.connect_timeout_secs);
+        let connecting = names.iter().map(|name| async move {
+            let server = &config.servers[*name];
+            let mut client = McpClient::connect(name, server, deadline).await?;
+            let tools = client.list_tools(deadline).await?;
+            Ok::<_, anyhow::Error>((client, tools))
+        });
+        let connected = futures_util::future::join_all(connecting).await;
+
+        for (name, result) in names.into_iter().zip(connected) {
+            match result {
+                Ok((client, tools)) => {
+                    debug!("MCP server {} has {} tools", name, tools.len());
+                    for tool in &tools {
+                        servers.add_tool(name, tool);
+                    }
+                    servers.clients.insert(name.clone(), client);
+                }
+                Err(e) => {
+                    warn!("MCP server {} unavailable: {:#}", name, e);
+                    warnings.push(format!("MCP server '{}' unavailable: {:#}", name, e));
+                }
+            }
+        }
+        (servers, warnings)
+    }
+
+    /// Definitions of the servers' tools.
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    /// Whether `tool_name` is a tool of one of the servers.
+    pub fn has_tool(&self, tool_name: &str) -> bool {
+        self.routes.contains_key(tool_name)
+    }
+
+    /// Call `tool_name` on its server. Failures are reported to the model
+    /// rather than returned as errors, like those of g3's own tools.
+    pub async fn call(&mut self, tool_name: &str, arguments: &Value) -> Result<String> {
+        let (server, tool) = self
+            .routes
+            .get(tool_name)
+            .ok_or_else(|| anyhow!("No MCP tool '{}'", tool_name))?;
+        let client = self
+            .clients
+            .get_mut(server)
+            .ok_or_else(|| anyhow!("MCP server '{}' is not connected", server))?;
+        let deadline = Instant::now() + self.call_timeout;
+        match client.call_tool(tool, arguments, deadline).await {
+            Ok(output) => Ok(output),
+            Err(e) => Ok(format!("❌ {:#}", e)),
+        }
+    }
+
+    fn add_tool(&mut self, server: &str, tool: &Value) {
+        let Some(original) = tool.get("name").and_then(|v| v.as_str()) else {
+            return;
+        };
+        let name = tool_name(server, original);
+        if self.routes.contains_key(&name) {
+            warn!("Skipping MCP tool {}: its name is taken", name);
+            return;
+        }
+        let description = tool
+            .get("description")
+            .and_then(|v| v.as_str())
+            .unwrap_or(original);
+        let input_schema = match tool.get("inputSchema") {
+            Some(schema) if schema.is_object() => schema.clone(),
+            _ => json!({ "type": "object", "properties": {} }),
+        };
+        self.tools.push(Tool {
+            name: name.clone(),
+            description: format!("[MCP server {}] {}", server, description),
+            input_schema,
+        });
+        self.routes
+            .insert(name, (server.to_string(), original.to_string()));
+    }
+}
+
+/// The name a server's tool is offered under: `mcp__<server>__<tool>`, with
+/// characters providers reject replaced and cut to the length they accept.
+pub fn tool_name(server: &str, tool: &str) -> String {
+    let sanitize = |name: &str| -> String {
+        name.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+    let mut name = format!("{}{}__{}", TOOL_PREFIX, sanitize(server), sanitize(tool));
+    name.truncate(MAX_TOOL_NAME_LEN);
+    name
+}
+
+/// The text of a `tools/call` result: its text content, placeholders for
+/// images and other content, and a ❌ if the tool reported an error.
+pub fn format_result(result: &Value) -> String {
+    let mut parts = Vec::new();
+    for item in result
+        .get("content")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let str_field = |name: &str| item.get(name).and_then(|v| v.as_str());
+        let part = match str_field("type") {
+            Some("text") => str_field("text").unwrap_or_default().to_string(),
+            Some("resource") => match item.pointer("/resource/text").and_then(|v| v.as_str()) {
+                Some(text) => text.to_string(),
+                None => format!(
+                    "[resource {}]",
+                    item.pointer("/resource/uri")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("?")
+                ),
+            },
+            Some("resource_link") => format!("[resource {}]", str_field("uri").unwrap_or("?")),
+            Some(kind) => format!("[{} {}]", kind, str_field("mimeType").unwrap_or("content")),
+            None => continue,
+        };
+        parts.push(part);
+    }
+    if parts.is_empty() {
+        if let Some(structured) = result.get("structuredContent") {
+            parts.push(structured.to_string());
+        }
+    }
+
+    let output = parts.join("\n");
+    if result.get("isError").and_then(|v| v.as_bool()) == Some(true) {
+        format!("❌ {}", output)
+    } else {
+        output
+    }
+}
+
+fn is_response_to(message: &Value, id: i64) -> bool {
+    message.get("method").is_none() && message.get("id").and_then(|v| v.as_i64()) == Some(id)
+}
+
+/// The answer to a request the server made of the client.
+fn server_request_answer(method: &str, id: &Value) -> Value {
+    match method {
+        "ping" => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+        _ => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": METHOD_NOT_FOUND, "message": format!("{} is not supported", method) }
+        }),
+    }
+}
+
+/// Splits an event stream into the data of its events.
+#[derive(Default)]
+struct SseParser {
+    buffer: String,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Feed a chunk of the stream; returns the events it completed.
+    fn push(&mut self, chunk: &str) -> Vec<String> {
+        self.buffer.push_str(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.find('\n') {
+            let line: String = self.buffer.drain(..=end).collect();
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(self.data.join("\n"));
+                    self.data.clear();
+                }
+            } else if let Some(data) = line.strip_prefix("data:") {
+                self.data
+                    .push(data.strip_prefix(' ').unwrap_or(data).to_string());
+            }
+        }
+        events
+    }
+}
+
+/// Forward each message the server writes until it closes its output.
+async fn read_messages(stdout: ChildStdout, sender: mpsc::UnboundedSender<Value>) {
+    let mut lines = BufReader::new(stdout).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(message) => {
+                if sender.send(message).is_err() {
+                    return;
+                }
+            }
+            Err(e) => debug!("Ignoring malformed MCP message: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_name() {
+        assert_eq!(
+            tool_name("github", "create_issue"),
+            "mcp__github__create_issue"
+        );
+        assert_eq!(tool_name("my docs", "search.v2"), "mcp__my_docs__search_v2");
+        assert_eq!(tool_name("s", &"x".repeat(100)).len(), MAX_TOOL_NAME_LEN);
+    }
+
+    #[test]
+    fn test_format_result() {
+        let result = json!({
+            "content": [
+                { "type": "text", "text": "3 issues" },
+                { "type": "image", "data": "...", "mimeType": "image/png" },
+                { "type": "resource", "resource": { "uri": "file:///a.txt", "text": "a" } }
+            ]
+        });
+        assert_eq!(format_result(&result), "3 issues\n[image image/png]\na");
+
+        let error =
+            json!({ "content": [{ "type": "text", "text": "Not found" }], "isError": true });
+        assert_eq!(format_result(&error), "❌ Not found");
+    }
+
+    #[test]
+    fn test_sse_parser() {
+        let mut parser = SseParser::default();
+        assert!(parser.push("event: message\ndata: {\"id\"").is_empty());
+        assert_eq!(
+            parser.push(": 1}\r\n\r\ndata: a\ndata: b\n\n"),
+            ["{\"id\": 1}", "a\nb"]
+        );
+    }
+
+    /// A stdio server that answers `initialize`, lists one tool and echoes
+    /// its arguments, pinging the client first.
+    const FAKE_SERVER: &str = r#"
+while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"method":"initialize"'*)
+      echo '{"jsonrpc":"2.0","id":'$id',"result":{"protocolVersion":"2025-03-26","capabilities":{"tools":{}},"serverInfo":{"name":"fake"}}}' ;;
+    *'"method":"tools/list"'*)
+      echo '{"jsonrpc":"2.0","id":'$id',"result":{"tools":[{"name":"echo","description":"Echo the text","inputSchema":{"type":"object","properties":{"text":{"type":"string"}}}}]}}' ;;
+    *'"method":"tools/call"'*)
+      echo '{"jsonrpc":"2.0","id":"srv-1","method":"ping"}'
+      read -r pong
+      case "$pong" in *'"id":"srv-1"'*) ;; *) exit 1 ;; esac
+      text=$(printf '%s' "$line" | sed -n 's/.*"text":"\([^"]*\)".*/\1/p')
+      echo '{"jsonrpc":"2.0","id":'$id',"result":{"content":[{"type":"text","text":"echo: '$text'"}]}}' ;;
+  esac
+done
+"#;
+
+    #[tokio::test]
+    async fn test_stdio_server_tools_are_called() {
+        let mut config = McpConfig {
+            connect_timeout_secs: 10,
+            call_timeout_secs: 10,
+            servers: HashMap::new(),
+        };
+        config.servers.insert(
+            "fake".to_string(),
+            McpServerConfig {
+                command: Some("sh".to_string()),
+                args: vec!["-c".to_string(), FAKE_SERVER.to_string()],
+                env: HashMap::new(),
+                url: None,
+                headers: HashMap::new(),
+                enabled: true,
+            },
+        );
+        config.servers.insert(
+            "missing".to_string(),
+            McpServerConfig {
+                command: Some("g3-no-such-mcp-server".to_string()),
+                args: Vec::new(),
+                env: HashMap::new(),
+                url: None,
+                headers: HashMap::new(),
+                enabled: true,
+            },
+        );
+
+        let (mut servers, warnings) = McpServers::connect(&config).await;
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(warnings[0].contains("missing"));
+
+        let names: Vec<&str> = servers.tools().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["mcp__fake__echo"]);
+        assert!(servers.tools()[0].description.contains("Echo the text"));
+        assert!(servers.has_tool("mcp__fake__echo"));
+        assert!(!servers.has_tool("shell"));
+
+        let output = servers
+            .call("mcp__fake__echo", &json!({ "text": "hi" }))
+            .await
+            .unwrap();
+        assert_eq!(output, "echo: hi");
+    }
+}
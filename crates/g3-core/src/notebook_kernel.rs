@@ -0,0 +1,204 @@
+//! Jupyter kernels for the notebook tools.
+//!
+//! Each notebook gets its own kernel, started on its first `notebook_run`
+//! and kept alive for the rest of the session so variables survive between
+//! runs, like in Jupyter itself. Kernels are driven through a small Python
+//! bridge built on `jupyter_client` that reads one JSON request per line on
+//! stdin and answers with the cell's status and nbformat outputs on stdout.
+//! The bridge is killed when the manager is dropped; ipykernel exits when
+//! its parent goes away.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// Python interpreter that runs the bridge; needs `jupyter_client` and the
+/// notebook's kernel (usually `ipykernel`) installed.
+const PYTHON: &str = "python3";
+
+/// How long a kernel may take to start.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Extra time allowed for the bridge to answer after a cell times out.
+const REPLY_GRACE: Duration = Duration::from_secs(10);
+
+const BRIDGE: &str = r#"
+import json, sys
+from jupyter_client.manager import start_new_kernel
+
+km, kc = start_new_kernel(kernel_name=sys.argv[1], cwd=sys.argv[2])
+print(json.dumps({"status": "ready"}), flush=True)
+
+for line in sys.stdin:
+    request = json.loads(line)
+    outputs = []
+
+    def hook(msg):
+        kind, content = msg["msg_type"], msg["content"]
+        if kind == "stream":
+            if outputs and outputs[-1]["output_type"] == "stream" and outputs[-1]["name"] == content["name"]:
+                outputs[-1]["text"] += content["text"]
+            else:
+                outputs.append({"output_type": "stream", "name": content["name"], "text": content["text"]})
+        elif kind in ("execute_result", "display_data"):
+            output = {"output_type": kind, "data": content["data"], "metadata": content.get("metadata", {})}
+            if kind == "execute_result":
+                output["execution_count"] = content["execution_count"]
+            outputs.append(output)
+        elif kind == "error":
+            outputs.append({"output_type": "error", "ename": content["ename"], "evalue": content["evalue"], "traceback": content["traceback"]})
+
+    try:
+        reply = kc.execute_interactive(request["code"], timeout=request["timeout"], output_hook=hook, allow_stdin=False)
+        status, count = reply["content"]["status"], reply["content"].get("execution_count")
+    except TimeoutError:
+        km.interrupt_kernel()
+        status, count = "timeout", None
+    print(json.dumps({"status": status, "execution_count": count, "outputs": outputs}), flush=True)
+
+kc.stop_channels()
+km.shutdown_kernel(now=True)
+"#;
+
+/// The result of executing one cell.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CellRun {
+    /// "ok", "error", "aborted" or "timeout"
+    pub status: String,
+    pub execution_count: Option<u64>,
+    /// nbformat output objects, ready to store in the notebook
+    #[serde(default)]
+    pub outputs: Vec<serde_json::Value>,
+}
+
+struct Kernel {
+    name: String,
+    /// The bridge; killed when the kernel is dropped
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+impl Kernel {
+    async fn start(name: &str, cwd: &Path) -> Result<Self> {
+        let mut child = Command::new(PYTHON)
+            .args(["-c", BRIDGE, name])
+            .arg(cwd)
+            .current_dir(cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("Failed to run {}: {}", PYTHON, e))?;
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("No stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("No stdout"))?;
+        let stderr = child.stderr.take().ok_or_else(|| anyhow!("No stderr"))?;
+        // Drain stderr for the life of the bridge, remembering the last line
+        // in case startup fails
+        let last_stderr_line = tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            let mut last = String::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if !line.trim().is_empty() {
+                    last = line;
+                }
+            }
+            last
+        });
+        let mut kernel = Self {
+            name: name.to_string(),
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            _child: child,
+        };
+
+        match tokio::time::timeout(STARTUP_TIMEOUT, kernel.stdout.next_line()).await {
+            Ok(Ok(Some(_))) => Ok(kernel),
+            Ok(_) => {
+                // The bridge exited; its stderr says why (usually a missing package)
+                let reason = last_stderr_line.await.unwrap_or_default();
+                bail!(
+                    "Could not start the '{}' kernel: {} (needs `pip install jupyter_client ipykernel`)",
+                    name,
+                    if reason.is_empty() { "the kernel bridge exited" } else { &reason }
+                )
+            }
+            Err(_) => bail!(
+                "The '{}' kernel did not start within {}s",
+                name,
+                STARTUP_TIMEOUT.as_secs()
+            ),
+        }
+    }
+
+    async fn execute(&mut self, code: &str, timeout: Duration) -> Result<CellRun> {
+        let request = serde_json::json!({ "code": code, "timeout": timeout.as_secs() });
+        self.stdin
+            .write_all(format!("{}\n", request).as_bytes())
+            .await?;
+        self.stdin.flush().await?;
+        match tokio::time::timeout(timeout + REPLY_GRACE, self.stdout.next_line()).await {
+            Ok(Ok(Some(line))) => Ok(serde_json::from_str(&line)?),
+            Ok(Ok(None)) => bail!("The kernel bridge exited"),
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => bail!("The kernel stopped responding"),
+        }
+    }
+}
+
+/// Running kernels, one per notebook.
+#[derive(Default)]
+pub struct NotebookKernelManager {
+    kernels: Mutex<HashMap<PathBuf, Kernel>>,
+}
+
+impl NotebookKernelManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Execute `code` in the notebook's kernel, starting a `kernel_name`
+    /// kernel in the notebook's directory if none is running (or the running
+    /// one is a different kernel). A kernel that fails is discarded, so the
+    /// next call starts a fresh one.
+    pub async fn execute(
+        &self,
+        notebook: &Path,
+        kernel_name: &str,
+        code: &str,
+        timeout: Duration,
+    ) -> Result<CellRun> {
+        let mut kernels = self.kernels.lock().await;
+        if kernels.get(notebook).is_some_and(|k| k.name != kernel_name) {
+            kernels.remove(notebook);
+        }
+        if !kernels.contains_key(notebook) {
+            let cwd = notebook.parent().unwrap_or(Path::new("."));
+            debug!("Starting {} kernel for {}", kernel_name, notebook.display());
+            let kernel = Kernel::start(kernel_name, cwd).await?;
+            kernels.insert(notebook.to_path_buf(), kernel);
+        }
+
+        let Some(kernel) = kernels.get_mut(notebook) else {
+            bail!("Kernel for {} is not running", notebook.display());
+        };
+        let result = kernel.execute(code, timeout).await;
+        if result.is_err() {
+            kernels.remove(notebook);
+        }
+        result
+    }
+
+    /// Stop the notebook's kernel, if running. Returns whether one was.
+    pub async fn shutdown(&self, notebook: &Path) -> bool {
+        self.kernels.lock().await.remove(notebook).is_some()
+    }
+}
@@ -0,0 +1,77 @@
+//! OpenTelemetry traces of sessions.
+//!
+//! The agent's spans (`agent_task`, `stream_completion_with_tools`,
+//! `provider_request`, `tool`) are ordinary `tracing` spans, exported when the
+//! binary installs a `tracing-opentelemetry` layer (`[telemetry]
+//! otlp_endpoint` in g3). Each task's span is made a root span of a trace
+//! whose ID is derived from the session ID, so all the tasks of a session,
+//! across runs and resumes, end up in the same trace.
+
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+use opentelemetry::Context;
+use sha2::{Digest, Sha256};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// The trace ID of a session: the first 16 bytes of the SHA-256 of its ID.
+pub fn session_trace_id(session_id: &str) -> TraceId {
+    let digest = Sha256::digest(session_id.as_bytes());
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    TraceId::from_bytes(bytes)
+}
+
+/// Make `span`, which must not have been entered yet, a root span of the
+/// session's trace.
+pub fn join_session_trace(span: &Span, session_id: &str) {
+    // A parent without a span ID lends its trace ID and nothing else
+    let parent = SpanContext::new(
+        session_trace_id(session_id),
+        SpanId::INVALID,
+        TraceFlags::SAMPLED,
+        true,
+        TraceState::default(),
+    );
+    // Fails when no OpenTelemetry layer is installed: nothing is exported then
+    let _ = span.set_parent(Context::new().with_remote_span_context(parent));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_tasks_of_a_session_share_its_trace() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            for task in ["first", "second"] {
+                let span = tracing::info_span!("agent_task", task);
+                join_session_trace(&span, "fix_the_build_1a2b3c4d");
+                span.in_scope(|| tracing::info_span!("tool").in_scope(|| {}));
+            }
+        });
+
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 4);
+        let trace_id = session_trace_id("fix_the_build_1a2b3c4d");
+        assert!(spans.iter().all(|s| s.span_context.trace_id() == trace_id));
+        // The tasks are roots, each with its tool beneath it
+        let tasks: Vec<_> = spans.iter().filter(|s| s.name == "agent_task").collect();
+        assert!(tasks.iter().all(|s| s.parent_span_id == SpanId::INVALID));
+        let tools = spans.iter().filter(|s| s.name == "tool");
+        assert!(tools
+            .zip(&tasks)
+            .all(|(tool, task)| tool.parent_span_id == task.span_context.span_id()));
+
+        assert_ne!(trace_id, session_trace_id("another_session_5e6f7a8b"));
+    }
+}
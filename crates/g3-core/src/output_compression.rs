@@ -0,0 +1,393 @@
+//! Noise reduction for shell output before it enters the context.
+//!
+//! Build tools print a lot of output that costs tokens without telling the
+//! model anything: terminal colour codes, progress bars redrawn with `\r`,
+//! the same line repeated hundreds of times, and long runs of `Compiling ...`
+//! lines. `compress_output` removes or collapses these. The command that
+//! produced the output selects an `OutputProfile` with tool-specific noise
+//! patterns on top of the generic passes. Each pass can be turned off, and
+//! lines matching user-supplied patterns dropped, in `[output_compression]`.
+
+use g3_config::OutputCompressionConfig;
+use regex::Regex;
+use std::borrow::Cow;
+use tracing::{debug, warn};
+
+use crate::tools::research::strip_ansi_codes;
+
+/// Identical consecutive lines beyond this many are collapsed into a marker.
+const MAX_REPEATED_LINES: usize = 2;
+
+/// Runs of profile noise lines longer than this keep only their first and
+/// last line.
+const MAX_NOISE_RUN: usize = 3;
+
+/// Characters used to draw progress bars by tools like pip, tqdm and indicatif.
+const BAR_CHARS: &[char] = &[
+    '█', '▉', '▊', '▋', '▌', '▍', '▎', '▏', '░', '▒', '▓', '■', '━', '─',
+];
+
+/// Tool-specific knowledge about which output lines are noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputProfile {
+    Cargo,
+    Node,
+    Pip,
+    Generic,
+}
+
+impl OutputProfile {
+    /// Pick the profile for a shell command.
+    pub fn for_command(command: &str) -> Self {
+        for word in command.split_whitespace() {
+            match word {
+                "cargo" => return Self::Cargo,
+                "npm" | "npx" | "yarn" | "pnpm" => return Self::Node,
+                "pip" | "pip3" => return Self::Pip,
+                _ => {}
+            }
+        }
+        Self::Generic
+    }
+
+    /// If `line` is noise for this profile, return a label for it.
+    fn noise_label(self, line: &str) -> Option<&'static str> {
+        let line = line.trim();
+        let prefixes: &[&'static str] = match self {
+            Self::Cargo => {
+                if line.starts_with("test ") && line.ends_with(" ... ok") {
+                    return Some("passing test");
+                }
+                &[
+                    "Compiling ",
+                    "Checking ",
+                    "Documenting ",
+                    "Downloaded ",
+                    "Downloading ",
+                    "Fresh ",
+                    "Locking ",
+                    "Adding ",
+                    "Updating ",
+                    "Removing ",
+                ]
+            }
+            Self::Node => &[
+                "npm WARN deprecated",
+                "npm http fetch",
+                "npm timing",
+                "npm verb",
+                "npm sill",
+            ],
+            Self::Pip => &[
+                "Collecting ",
+                "Downloading ",
+                "Using cached ",
+                "Requirement already satisfied",
+            ],
+            Self::Generic => &[],
+        };
+        prefixes
+            .iter()
+            .copied()
+            .find(|prefix| line.starts_with(prefix))
+            .map(str::trim)
+    }
+}
+
+/// Strip colour codes, progress bars, repeated lines and profile noise from
+/// the output of `command`.
+pub fn compress_output(output: &str, command: &str) -> String {
+    compress_output_with(output, command, &OutputCompressionConfig::default())
+}
+
+/// Run the filters `config` turns on over the output of `command`.
+pub fn compress_output_with(
+    output: &str,
+    command: &str,
+    config: &OutputCompressionConfig,
+) -> String {
+    if !config.enabled {
+        return output.to_string();
+    }
+    let profile = OutputProfile::for_command(command);
+    let stripped = if config.strip_ansi {
+        strip_ansi_codes(output)
+    } else {
+        output.to_string()
+    };
+    let drop = drop_patterns(&config.drop_patterns);
+
+    let lines = stripped
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .map(|line| {
+            // Only the last redraw of a `\r`-updated line is visible in a
+            // terminal
+            if config.collapse_progress {
+                line.rsplit('\r').next().unwrap_or(line)
+            } else {
+                line
+            }
+        })
+        .filter(|line| !drop.iter().any(|re| re.is_match(line)));
+
+    let lines = if config.collapse_progress {
+        drop_progress_lines(lines)
+    } else {
+        lines.collect()
+    };
+    let lines = if config.dedupe_lines {
+        collapse_repeats(lines)
+    } else {
+        lines.into_iter().map(Cow::Borrowed).collect()
+    };
+    let lines = if config.collapse_noise {
+        collapse_noise_runs(lines, profile)
+    } else {
+        lines
+    };
+    let result = lines.join("\n");
+
+    if result.len() < output.len() {
+        debug!(
+            "Compressed {:?} output from {} to {} bytes",
+            profile,
+            output.len(),
+            result.len()
+        );
+    }
+    result
+}
+
+/// The compiled `drop_patterns`, skipping (and logging) invalid ones.
+fn drop_patterns(patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|pattern| match Regex::new(pattern) {
+            Ok(re) => Some(re),
+            Err(e) => {
+                warn!("Ignoring invalid output drop pattern {:?}: {}", pattern, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Whether a line is an in-progress progress bar or spinner.
+fn is_progress_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    let Some(first) = trimmed.chars().next() else {
+        return false;
+    };
+    // Braille spinners (⠋⠙⠹...)
+    if ('\u{2800}'..='\u{28FF}').contains(&first) {
+        return true;
+    }
+    if trimmed.chars().filter(|c| BAR_CHARS.contains(c)).count() >= 5 {
+        return true;
+    }
+    // ASCII bars such as `[=====>    ]`. A bar that is all one character is
+    // more likely a separator (e.g. gtest's `[==========]`) and is kept.
+    trimmed.match_indices('[').any(|(start, _)| {
+        let inner = &trimmed[start + 1..];
+        let Some(end) = inner.find(']') else {
+            return false;
+        };
+        let inner = &inner[..end];
+        inner.len() >= 10
+            && inner.chars().all(|c| "=>#-. ".contains(c))
+            && inner.chars().any(|c| "=>#".contains(c))
+            && inner.chars().any(|c| "-. ".contains(c))
+    })
+}
+
+/// Drop progress lines, keeping only the last of each consecutive run so the
+/// final state (e.g. 100%) is still visible.
+fn drop_progress_lines<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut result = Vec::new();
+    let mut pending: Option<&str> = None;
+    for line in lines {
+        if is_progress_line(line) {
+            pending = Some(line);
+            continue;
+        }
+        result.extend(pending.take());
+        result.push(line);
+    }
+    result.extend(pending);
+    result
+}
+
+/// Collapse runs of identical lines into the line plus a repeat marker.
+fn collapse_repeats(lines: Vec<&str>) -> Vec<Cow<'_, str>> {
+    let mut result = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let run = lines[i..].iter().take_while(|l| **l == line).count();
+        if run > MAX_REPEATED_LINES && !line.trim().is_empty() {
+            result.push(Cow::Borrowed(line));
+            result.push(Cow::Owned(format!(
+                "[last line repeated {} times]",
+                run - 1
+            )));
+        } else {
+            result.extend(lines[i..i + run].iter().map(|l| Cow::Borrowed(*l)));
+        }
+        i += run;
+    }
+    result
+}
+
+/// Collapse long runs of profile noise (e.g. `Compiling ...`) to their first
+/// and last line.
+fn collapse_noise_runs(lines: Vec<Cow<'_, str>>, profile: OutputProfile) -> Vec<Cow<'_, str>> {
+    if profile == OutputProfile::Generic {
+        return lines;
+    }
+
+    let mut result = Vec::with_capacity(lines.len());
+    let mut lines = lines.into_iter().peekable();
+    while let Some(line) = lines.next() {
+        let Some(label) = profile.noise_label(&line) else {
+            result.push(line);
+            continue;
+        };
+        let mut run = vec![line];
+        while let Some(next) = lines.next_if(|l| profile.noise_label(l) == Some(label)) {
+            run.push(next);
+        }
+        if run.len() > MAX_NOISE_RUN {
+            let omitted = run.len() - 2;
+            let last = run.pop();
+            result.push(run.swap_remove(0));
+            result.push(Cow::Owned(format!(
+                "[... {} more `{}` lines omitted]",
+                omitted, label
+            )));
+            result.extend(last);
+        } else {
+            result.extend(run);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_for_command() {
+        assert_eq!(
+            OutputProfile::for_command("cargo test --workspace"),
+            OutputProfile::Cargo
+        );
+        assert_eq!(
+            OutputProfile::for_command("cd web && npm install"),
+            OutputProfile::Node
+        );
+        assert_eq!(
+            OutputProfile::for_command("pip install -r req.txt"),
+            OutputProfile::Pip
+        );
+        assert_eq!(OutputProfile::for_command("ls -la"), OutputProfile::Generic);
+    }
+
+    #[test]
+    fn test_strips_ansi_and_carriage_return_redraws() {
+        let output = "\x1b[32mok\x1b[0m\r\n 10%\r 50%\r100% done\nend";
+        assert_eq!(compress_output(output, "ls"), "ok\n100% done\nend");
+    }
+
+    #[test]
+    fn test_collapses_repeated_lines() {
+        let mut output = String::from("start\n");
+        output.push_str(&"warning: retrying\n".repeat(1200));
+        output.push_str("twice\ntwice\nend");
+        assert_eq!(
+            compress_output(&output, "ls"),
+            "start\nwarning: retrying\n[last line repeated 1199 times]\ntwice\ntwice\nend"
+        );
+    }
+
+    #[test]
+    fn test_drops_progress_bars() {
+        let output = "Downloading\n\
+                      [=====>              ] 25%\n\
+                      [==========>         ] 50%\n\
+                      [====================] 100%\n\
+                      ━━━━━━━━━━━━━━━━━━━━━━ 1.2/1.2 MB\n\
+                      [==========] gtest separator\n\
+                      [----------] gtest separator\n\
+                      done";
+        assert_eq!(
+            compress_output(output, "ls"),
+            "Downloading\n\
+             [==========>         ] 50%\n\
+             [====================] 100%\n\
+             ━━━━━━━━━━━━━━━━━━━━━━ 1.2/1.2 MB\n\
+             [==========] gtest separator\n\
+             [----------] gtest separator\n\
+             done"
+        );
+    }
+
+    #[test]
+    fn test_collapses_cargo_noise() {
+        let mut output = String::new();
+        for i in 0..50 {
+            output.push_str(&format!("   Compiling crate{} v0.1.0\n", i));
+        }
+        output.push_str("error[E0425]: cannot find value `x`\n");
+        for i in 0..10 {
+            output.push_str(&format!("test tests::t{} ... ok\n", i));
+        }
+        output.push_str("test tests::broken ... FAILED");
+
+        assert_eq!(
+            compress_output(&output, "cargo test"),
+            "   Compiling crate0 v0.1.0\n\
+             [... 48 more `Compiling` lines omitted]\n   \
+             Compiling crate49 v0.1.0\n\
+             error[E0425]: cannot find value `x`\n\
+             test tests::t0 ... ok\n\
+             [... 8 more `passing test` lines omitted]\n\
+             test tests::t9 ... ok\n\
+             test tests::broken ... FAILED"
+        );
+
+        // Other tools' output is left alone
+        assert_eq!(compress_output(&output, "ls").lines().count(), 62);
+    }
+
+    #[test]
+    fn test_filters_follow_config() {
+        let output = "\x1b[31mwarning: unused\x1b[0m\nsame\nsame\nsame\n 10%\r100%\nnote: kept";
+        let config = OutputCompressionConfig {
+            strip_ansi: false,
+            dedupe_lines: false,
+            drop_patterns: vec!["^note:".to_string(), "(".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            compress_output_with(output, "ls", &config),
+            "\x1b[31mwarning: unused\x1b[0m\nsame\nsame\nsame\n100%"
+        );
+
+        let config = OutputCompressionConfig {
+            collapse_progress: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            compress_output_with(output, "ls", &config),
+            "warning: unused\nsame\n[last line repeated 2 times]\n 10%\r100%\nnote: kept"
+        );
+
+        let config = OutputCompressionConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        assert_eq!(compress_output_with(output, "ls", &config), output);
+    }
+}
@@ -98,6 +98,12 @@ pub fn get_fragments_dir(session_id: &str) -> PathBuf {
     get_session_logs_dir(session_id).join("fragments")
 }
 
+/// Get the directory for message bodies stored outside memory.
+/// Returns .g3/sessions/<session_id>/bodies/
+pub fn get_message_bodies_dir(session_id: &str) -> PathBuf {
+    get_session_logs_dir(session_id).join("bodies")
+}
+
 /// Get the path to the session.json file for a session.
 /// Returns .g3/sessions/<session_id>/session.json
 pub fn get_session_file(session_id: &str) -> PathBuf {
@@ -110,6 +116,54 @@ pub fn get_context_summary_file(session_id: &str) -> PathBuf {
     get_session_logs_dir(session_id).join("context_summary.txt")
 }
 
+/// Get the path to the per-turn telemetry log for a session.
+/// Returns .g3/sessions/<session_id>/telemetry.jsonl
+pub fn get_telemetry_file(session_id: &str) -> PathBuf {
+    get_session_logs_dir(session_id).join("telemetry.jsonl")
+}
+
+/// Get the directory of periodic context window checkpoints for a session.
+/// Returns .g3/sessions/<session_id>/autosave/
+pub fn get_autosave_dir(session_id: &str) -> PathBuf {
+    get_session_logs_dir(session_id).join("autosave")
+}
+
+/// Get the path to the checkpoint log for a session.
+/// Returns .g3/sessions/<session_id>/checkpoints.jsonl
+pub fn get_checkpoints_file(session_id: &str) -> PathBuf {
+    get_session_logs_dir(session_id).join("checkpoints.jsonl")
+}
+
+/// Get the path to the audit log of commands executed in a session.
+/// Returns .g3/sessions/<session_id>/audit.jsonl
+pub fn get_audit_file(session_id: &str) -> PathBuf {
+    get_session_logs_dir(session_id).join("audit.jsonl")
+}
+
+/// Get the path to the transcript of tool calls made in a session.
+/// Returns .g3/sessions/<session_id>/tool_transcript.jsonl
+pub fn get_tool_transcript_file(session_id: &str) -> PathBuf {
+    get_session_logs_dir(session_id).join("tool_transcript.jsonl")
+}
+
+/// Get the path to the log of actions planned in dry-run mode for a session.
+/// Returns .g3/sessions/<session_id>/dry_run.jsonl
+pub fn get_dry_run_file(session_id: &str) -> PathBuf {
+    get_session_logs_dir(session_id).join("dry_run.jsonl")
+}
+
+/// Get the path to the agent's working plan for a session.
+/// Returns .g3/sessions/<session_id>/plan.json
+pub fn get_plan_file(session_id: &str) -> PathBuf {
+    get_session_logs_dir(session_id).join("plan.json")
+}
+
+/// Get the path to the log of git commits linked to a session.
+/// Returns .g3/sessions/<session_id>/commits.jsonl
+pub fn get_commits_file(session_id: &str) -> PathBuf {
+    get_session_logs_dir(session_id).join("commits.jsonl")
+}
+
 /// Get the tools output directory for a session.
 /// Returns .g3/sessions/<session_id>/tools/
 pub fn get_tools_output_dir(session_id: &str) -> PathBuf {
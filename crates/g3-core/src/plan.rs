@@ -0,0 +1,176 @@
+//! The agent's working plan, kept by the `plan_write` and `plan_read` tools.
+//!
+//! Unlike todo.g3.md, which is the user-facing task list, the plan is the
+//! agent's own scratchpad: ordered steps, each with a status and the
+//! rationale for taking it. It is stored as `plan.json` in the session
+//! directory, summarized in `get_stats()`, and shown to the coach in
+//! autonomous mode so the player's approach can be reviewed alongside the
+//! code.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::paths::{ensure_session_dir, get_plan_file};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Done,
+    Blocked,
+    Skipped,
+}
+
+impl StepStatus {
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "pending" => Some(Self::Pending),
+            "in_progress" => Some(Self::InProgress),
+            "done" => Some(Self::Done),
+            "blocked" => Some(Self::Blocked),
+            "skipped" => Some(Self::Skipped),
+            _ => None,
+        }
+    }
+
+    fn marker(self) -> &'static str {
+        match self {
+            Self::Pending => "[ ]",
+            Self::InProgress => "[~]",
+            Self::Done => "[x]",
+            Self::Blocked => "[!]",
+            Self::Skipped => "[-]",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub title: String,
+    #[serde(default)]
+    pub status: StepStatus,
+    /// Why this step, or why it is blocked or skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rationale: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<PlanStep>,
+    /// Incremented on every write
+    #[serde(default)]
+    pub revision: u32,
+    /// RFC 3339 time of the last write
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+impl Plan {
+    /// The session's plan, if one has been written.
+    pub fn load(session_id: &str) -> Option<Self> {
+        let content = std::fs::read_to_string(get_plan_file(session_id)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Save as the session's plan, bumping the revision.
+    pub fn save(&mut self, session_id: &str) -> Result<()> {
+        ensure_session_dir(session_id)?;
+        self.revision += 1;
+        self.updated_at = Some(chrono::Utc::now().to_rfc3339());
+        std::fs::write(
+            get_plan_file(session_id),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    pub fn count(&self, status: StepStatus) -> usize {
+        self.steps.iter().filter(|s| s.status == status).count()
+    }
+
+    /// The step being worked on: the first in progress, else the first pending.
+    pub fn current_step(&self) -> Option<(usize, &PlanStep)> {
+        let find = |status| {
+            self.steps
+                .iter()
+                .enumerate()
+                .find(|(_, s)| s.status == status)
+        };
+        find(StepStatus::InProgress).or_else(|| find(StepStatus::Pending))
+    }
+
+    /// The plan as numbered checklist lines with rationales.
+    pub fn format(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(goal) = &self.goal {
+            lines.push(format!("Goal: {}", goal));
+        }
+        lines.push(format!(
+            "Steps ({}/{} done, revision {}):",
+            self.count(StepStatus::Done),
+            self.steps.len(),
+            self.revision
+        ));
+        for (i, step) in self.steps.iter().enumerate() {
+            lines.push(format!(
+                "{}. {} {}",
+                i + 1,
+                step.status.marker(),
+                step.title
+            ));
+            if let Some(rationale) = &step.rationale {
+                lines.push(format!("     why: {}", rationale));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_format_and_current_step() {
+        let plan = Plan {
+            goal: Some("Add rate limiting".to_string()),
+            steps: vec![
+                PlanStep {
+                    title: "Read the middleware".to_string(),
+                    status: StepStatus::Done,
+                    rationale: None,
+                },
+                PlanStep {
+                    title: "Add a token bucket".to_string(),
+                    status: StepStatus::Pending,
+                    rationale: Some("Simplest limiter that allows bursts".to_string()),
+                },
+                PlanStep {
+                    title: "Load test".to_string(),
+                    status: StepStatus::Blocked,
+                    rationale: None,
+                },
+            ],
+            revision: 2,
+            updated_at: None,
+        };
+        assert_eq!(
+            plan.format(),
+            "Goal: Add rate limiting\n\
+             Steps (1/3 done, revision 2):\n\
+             1. [x] Read the middleware\n\
+             2. [ ] Add a token bucket\n     why: Simplest limiter that allows bursts\n\
+             3. [!] Load test"
+        );
+        assert_eq!(plan.current_step().map(|(i, _)| i), Some(1));
+
+        let json = serde_json::to_string(&plan).unwrap();
+        assert!(json.contains("\"status\":\"blocked\""));
+        assert_eq!(serde_json::from_str::<Plan>(&json).unwrap(), plan);
+    }
+}
@@ -0,0 +1,171 @@
+//! Speculative prefetching while the model is still streaming.
+//!
+//! When the streaming parser sees a tool call whose file path argument has
+//! finished streaming, the file is read in the background so the tool starts
+//! with its contents already in memory. Prefetched contents are only used if
+//! the file's size and modification time still match when the tool runs.
+
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tracing::debug;
+
+use crate::streaming_parser::PartialToolCall;
+use crate::utils::resolve_path_with_unicode_fallback;
+
+/// Files larger than this are not prefetched.
+const MAX_PREFETCH_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Maximum number of prefetched files kept at once.
+const MAX_ENTRIES: usize = 16;
+
+#[derive(Debug)]
+struct Prefetched {
+    len: u64,
+    modified: Option<SystemTime>,
+    /// None while the read is still in flight
+    bytes: Option<Arc<Vec<u8>>>,
+}
+
+/// Thread-safe cache of speculatively read files
+#[derive(Debug, Clone, Default)]
+pub struct PrefetchCache {
+    entries: Arc<Mutex<HashMap<PathBuf, Prefetched>>>,
+}
+
+impl PrefetchCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prefetch whatever the streaming tool call is likely to need.
+    pub fn observe(&self, partial: &PartialToolCall) {
+        if partial.tool != "read_file" {
+            return;
+        }
+        if let Some(file_path) = partial.args.get("file_path").and_then(|v| v.as_str()) {
+            // Resolve the same way read_file does
+            let expanded = shellexpand::tilde(file_path);
+            let resolved = resolve_path_with_unicode_fallback(expanded.as_ref());
+            self.prefetch_file(PathBuf::from(resolved.as_ref()));
+        }
+    }
+
+    /// Start reading `path` in the background unless it is already cached
+    /// or being read.
+    pub fn prefetch_file(&self, path: PathBuf) {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            if entries.contains_key(&path) {
+                return;
+            }
+            if entries.len() >= MAX_ENTRIES {
+                entries.retain(|_, e| e.bytes.is_none());
+            }
+            entries.insert(
+                path.clone(),
+                Prefetched {
+                    len: 0,
+                    modified: None,
+                    bytes: None,
+                },
+            );
+        }
+
+        let entries = self.entries.clone();
+        tokio::task::spawn_blocking(move || {
+            let read = std::fs::metadata(&path)
+                .ok()
+                .filter(|m| m.is_file() && m.len() <= MAX_PREFETCH_BYTES)
+                .and_then(|before| {
+                    let bytes = std::fs::read(&path).ok()?;
+                    // Discard the read if the file changed underneath us
+                    let after = std::fs::metadata(&path).ok()?;
+                    same_version(&before, after.len(), after.modified().ok())
+                        .then_some((after, bytes))
+                });
+
+            let mut entries = entries.lock().unwrap();
+            match read {
+                Some((metadata, bytes)) => {
+                    debug!("Prefetched {} ({} bytes)", path.display(), bytes.len());
+                    entries.insert(
+                        path,
+                        Prefetched {
+                            len: metadata.len(),
+                            modified: metadata.modified().ok(),
+                            bytes: Some(Arc::new(bytes)),
+                        },
+                    );
+                }
+                None => {
+                    entries.remove(&path);
+                }
+            }
+        });
+    }
+
+    /// Take the prefetched contents of `path` if the file still matches
+    /// `metadata`. Each prefetch is used at most once.
+    pub fn take(&self, path: &Path, metadata: &Metadata) -> Option<Arc<Vec<u8>>> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(path)?;
+        entry.bytes.as_ref()?;
+        let entry = entries.remove(path)?;
+        if same_version(metadata, entry.len, entry.modified) {
+            debug!("Using prefetched contents of {}", path.display());
+            entry.bytes
+        } else {
+            None
+        }
+    }
+
+    /// Drop all prefetched contents.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+fn same_version(metadata: &Metadata, len: u64, modified: Option<SystemTime>) -> bool {
+    metadata.len() == len && modified.is_some() && metadata.modified().ok() == modified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_prefetch_and_invalidate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "hello").unwrap();
+
+        let cache = PrefetchCache::new();
+        cache.prefetch_file(path.clone());
+        for _ in 0..100 {
+            if cache.entries.lock().unwrap()[&path].bytes.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert_eq!(cache.take(&path, &metadata).unwrap().as_slice(), b"hello");
+        // Used at most once
+        assert!(cache.take(&path, &metadata).is_none());
+
+        // A changed file is not served from the cache
+        cache.prefetch_file(path.clone());
+        for _ in 0..100 {
+            if cache.entries.lock().unwrap()[&path].bytes.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        std::fs::write(&path, "hello, world").unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert!(cache.take(&path, &metadata).is_none());
+    }
+}
@@ -2,6 +2,23 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
+/// Requirements files looked for in the workspace, in order. YAML and JSON
+/// files hold structured requirements (see `requirements`).
+pub const REQUIREMENTS_FILES: &[&str] = &[
+    "requirements.md",
+    "requirements.yaml",
+    "requirements.yml",
+    "requirements.json",
+];
+
+/// The first of `REQUIREMENTS_FILES` in `dir`.
+pub fn find_requirements_file(dir: &Path) -> Option<PathBuf> {
+    REQUIREMENTS_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+}
+
 /// Represents a G3 project with workspace configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
@@ -48,22 +65,26 @@ impl Project {
         let mut project = Self::new(workspace_dir.clone());
         project.autonomous = true;
 
-        // Look for requirements.md in the workspace directory
-        let requirements_path = workspace_dir.join("requirements.md");
-        if requirements_path.exists() {
-            project.requirements_path = Some(requirements_path);
-        }
+        // Look for requirements.md (or .yaml/.yml/.json) in the workspace directory
+        project.requirements_path = find_requirements_file(&workspace_dir);
 
         Ok(project)
     }
 
-    /// Create a project for autonomous mode with requirements text override
+    /// Create a project for autonomous mode with requirements text override.
+    /// When the text is the path of an existing file (Markdown, YAML, JSON,
+    /// ...), that file is read instead.
     pub fn new_autonomous_with_requirements(
         workspace_dir: PathBuf,
         requirements_text: String,
     ) -> Result<Self> {
         let mut project = Self::new(workspace_dir.clone());
         project.autonomous = true;
+        let as_path = PathBuf::from(requirements_text.trim());
+        if !requirements_text.contains('\n') && as_path.is_file() {
+            project.requirements_path = Some(as_path);
+            return Ok(project);
+        }
         project.requirements_text = Some(requirements_text);
 
         // Don't look for requirements.md file when text is provided
@@ -83,8 +104,7 @@ impl Project {
 
         // Update requirements path if in autonomous mode
         if self.autonomous {
-            let requirements_path = workspace_dir.join("requirements.md");
-            if requirements_path.exists() {
+            if let Some(requirements_path) = find_requirements_file(&workspace_dir) {
                 self.requirements_path = Some(requirements_path);
             }
         }
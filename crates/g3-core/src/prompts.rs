@@ -189,6 +189,8 @@ Short description for providers without native calling specs:
   - Format: {\"tool\": \"shell\", \"args\": {\"command\": \"your_command_here\"}}
   - Example: {\"tool\": \"shell\", \"args\": {\"command\": \"ls ~/Downloads\"}}
   - Always use `rg` (ripgrep) instead of `grep` - it's faster and respects .gitignore
+  - If the OS sandbox blocks a command that must reach outside the workspace, retry it with \"unsandboxed\": true to ask the user
+  - To run in a subdirectory (e.g. a monorepo package), pass \"working_dir\": \"packages/api\" instead of prefixing `cd packages/api &&`
 
 - **background_process**: Launch a long-running process in the background (e.g., game servers, dev servers)
   - Format: {\"tool\": \"background_process\", \"args\": {\"name\": \"unique_name\", \"command\": \"your_command\"}}
@@ -196,15 +198,98 @@ Short description for providers without native calling specs:
   - Returns PID and log file path. Use shell tool to read logs (`tail -100 <logfile>`), check status (`ps -p <pid>`), or stop (`kill <pid>`)
   - Note: Process runs independently; logs are captured to a file for later inspection
 
+- **wait_for**: Wait until a file exists, a URL returns 200, a port opens or a process exits (use instead of `sleep` after starting a server)
+  - Format: {\"tool\": \"wait_for\", \"args\": {\"port\": 3000, \"background_process\": \"name\", \"timeout_secs\": 60}}
+  - Example: {\"tool\": \"wait_for\", \"args\": {\"url\": \"http://localhost:8080/health\", \"background_process\": \"api\"}}
+
+- **watch_files**: Watch a directory and report files created/modified/deleted (confirm that codegen or a build wrote its outputs)
+  - Format: {\"tool\": \"watch_files\", \"args\": {\"path\": \"dir\", \"glob\": [\"*.ts\"], \"expect\": [\"api/*.ts\"], \"timeout_secs\": 30}}
+  - Example: {\"tool\": \"watch_files\", \"args\": {\"path\": \"dist\", \"no_ignore\": true, \"expect\": [\"*.js\"]}}
+
 - **read_file**: Read the contents of a file (supports partial reads via start/end)
   - Format: {\"tool\": \"read_file\", \"args\": {\"file_path\": \"path/to/file\", \"start\": 0, \"end\": 100}}
   - Example: {\"tool\": \"read_file\", \"args\": {\"file_path\": \"src/main.rs\"}}
   - Example (partial): {\"tool\": \"read_file\", \"args\": {\"file_path\": \"large.log\", \"start\": 0, \"end\": 1000}}
 
+- **read_many_files**: Read up to 20 files in one call, concatenated under `===== path =====` separators (per-file and total size caps)
+  - Format: {\"tool\": \"read_many_files\", \"args\": {\"file_paths\": [\"path/a\", \"path/b\"], \"max_bytes_per_file\": 32768, \"max_total_bytes\": 131072}}
+  - Example: {\"tool\": \"read_many_files\", \"args\": {\"file_paths\": [\"Cargo.toml\", \"crates/core/Cargo.toml\", \".github/workflows/ci.yml\"]}}
+
+- **read_output**: Page through large output that was saved and shown as its first and last lines with a handle (shell, read_file, python, node_eval)
+  - Format: {\"tool\": \"read_output\", \"args\": {\"handle\": \"shell_stdout_1a2b3c4d\", \"start\": 1, \"end\": 200}}
+
+- **list_dir**: Show a directory tree with file sizes, respecting .gitignore (use instead of `ls -R`/`find` for project overviews)
+  - Format: {\"tool\": \"list_dir\", \"args\": {\"path\": \"path/to/dir\", \"depth\": 2}}
+  - Example: {\"tool\": \"list_dir\", \"args\": {\"path\": \"src\"}}
+
+- **grep**: Regex search over files (respects .gitignore, results capped)
+  - Format: {\"tool\": \"grep\", \"args\": {\"pattern\": \"regex\", \"path\": \"src\", \"glob\": [\"*.rs\"], \"context_lines\": 2}}
+  - Example: {\"tool\": \"grep\", \"args\": {\"pattern\": \"fn execute_\\\\w+\", \"glob\": [\"*.rs\"]}}
+
+- **regex_replace**: Regex search/replace across files for mechanical renames (preview with dry_run first)
+  - Format: {\"tool\": \"regex_replace\", \"args\": {\"pattern\": \"regex\", \"replacement\": \"text with $1\", \"path\": \"src\", \"glob\": [\"*.rs\"], \"dry_run\": true}}
+  - Example: {\"tool\": \"regex_replace\", \"args\": {\"pattern\": \"\\\\bold_name\\\\b\", \"replacement\": \"new_name\", \"glob\": [\"*.rs\"]}}
+
+- **git_status** / **git_diff**: Inspect the working tree (use instead of running git through the shell)
+  - Format: {\"tool\": \"git_diff\", \"args\": {\"staged\": false, \"path\": \"src\", \"stat\": false}}
+  - Example: {\"tool\": \"git_status\", \"args\": {}}
+
+- **git_commit**: Stage files and commit them on the current branch (no amend, no push)
+  - Format: {\"tool\": \"git_commit\", \"args\": {\"message\": \"summary\", \"files\": [\"path\"], \"all\": false}}
+  - Example: {\"tool\": \"git_commit\", \"args\": {\"message\": \"Fix off-by-one in pager\", \"files\": [\"src/pager.rs\"]}}
+
+- **git_branch**: List, create or switch branches
+  - Format: {\"tool\": \"git_branch\", \"args\": {\"action\": \"list|create|switch\", \"name\": \"branch\"}}
+  - Example: {\"tool\": \"git_branch\", \"args\": {\"action\": \"create\", \"name\": \"fix-pager\"}}
+
+- **checkpoint_create** / **checkpoint_rollback**: Snapshot the working tree before a risky change and restore it in one call if it fails
+  - Format: {\"tool\": \"checkpoint_create\", \"args\": {\"name\": \"before-refactor\", \"message\": \"why\"}}
+  - Example: {\"tool\": \"checkpoint_rollback\", \"args\": {\"name\": \"before-refactor\"}}
+
+- **http_request**: Call an HTTP API (use instead of curl; configured credentials are added by host)
+  - Format: {\"tool\": \"http_request\", \"args\": {\"url\": \"https://...\", \"method\": \"POST\", \"headers\": {}, \"body\": {}}}
+  - Example: {\"tool\": \"http_request\", \"args\": {\"url\": \"http://localhost:8080/api/users\", \"method\": \"POST\", \"body\": {\"name\": \"Ada\"}}}
+
+- **sql_query**: Query a configured database (SQLite/Postgres/MySQL) and get a row-limited table (use instead of psql/sqlite3)
+  - Format: {\"tool\": \"sql_query\", \"args\": {\"connection\": \"name\", \"query\": \"SELECT ...\", \"max_rows\": 100}}
+  - Example: {\"tool\": \"sql_query\", \"args\": {\"connection\": \"app\", \"query\": \"SELECT status, count(*) FROM orders GROUP BY status\"}}
+
+- **k8s**: kubectl get/describe/logs/apply with summarized output; apply shows the diff and asks the user first (use instead of kubectl)
+  - Format: {\"tool\": \"k8s\", \"args\": {\"operation\": \"get\", \"resource\": \"pods\", \"namespace\": \"ns\", \"selector\": \"app=web\"}}
+  - Example: {\"tool\": \"k8s\", \"args\": {\"operation\": \"logs\", \"name\": \"deployment/api\", \"previous\": true}}
+
+- **docker**: Build images and run containers with the workspace mounted at /workspace; images limited to the configured allowlist (use instead of docker in the shell)
+  - Format: {\"tool\": \"docker\", \"args\": {\"operation\": \"run\", \"image\": \"rust:1.80\", \"command\": \"cargo test\"}}
+  - Example: {\"tool\": \"docker\", \"args\": {\"operation\": \"build\", \"dockerfile\": \"ci/Dockerfile\", \"tag\": \"app-ci\"}}
+
+- **notebook_read** / **notebook_edit** / **notebook_run**: Read, change and execute Jupyter notebook cells (the kernel keeps state between runs; outputs are saved to the notebook)
+  - Format: {\"tool\": \"notebook_edit\", \"args\": {\"path\": \"analysis.ipynb\", \"operation\": \"replace\", \"index\": 2, \"source\": \"df.describe()\"}}
+  - Example: {\"tool\": \"notebook_run\", \"args\": {\"path\": \"analysis.ipynb\", \"cells\": [2, 3]}}
+
+- **archive_extract** / **archive_create**: Extract or create zip/tar/tar.gz archives safely (use instead of unzip/tar; nothing is written outside the destination)
+  - Format: {\"tool\": \"archive_extract\", \"args\": {\"path\": \"archive.zip\", \"destination\": \"dir\"}}
+  - Example: {\"tool\": \"archive_create\", \"args\": {\"path\": \"dist/site.tar.gz\", \"paths\": [\"public\"]}}
+
+- **inspect_env**: Listening ports with their processes, matching processes and selected env vars, on any OS (use instead of lsof/ps/netstat)
+  - Format: {\"tool\": \"inspect_env\", \"args\": {\"ports\": [3000], \"process\": \"name\", \"env\": [\"NAME\", \"PREFIX_*\"]}}
+  - Example: {\"tool\": \"inspect_env\", \"args\": {\"ports\": [3000, 5432]}}
+
+- **node_eval**: Run a JavaScript/TypeScript snippet in a sandboxed Deno or Node process (timeout, no network, read-only working dir) and return its output (use for quick data transformations instead of writing a script file)
+  - Format: {\"tool\": \"node_eval\", \"args\": {\"code\": \"console.log(...)\", \"input\": \"optional stdin text, also in INPUT\"}}
+  - Example: {\"tool\": \"node_eval\", \"args\": {\"code\": \"const rows = JSON.parse(INPUT); console.log(rows.filter(r => r.failed).length)\", \"input\": \"[{\\\"failed\\\": true}]\"}}
+
+- **python**: Run Python in a persistent interpreter for this session (variables survive between calls; a final expression's value is returned) - use for iterative data exploration
+  - Format: {\"tool\": \"python\", \"args\": {\"code\": \"python code\", \"timeout_secs\": 30}}
+  - Example: {\"tool\": \"python\", \"args\": {\"code\": \"import csv\\nrows = list(csv.DictReader(open('data.csv')))\\nlen(rows)\"}}
+
 - **read_image**: Read an image file for visual analysis (PNG, JPEG, GIF, WebP)
   - Format: {\"tool\": \"read_image\", \"args\": {\"file_paths\": [\"path/to/image.png\"]}}
   - Example: {\"tool\": \"read_image\", \"args\": {\"file_paths\": [\"sprites/fairy.png\"]}}
 
+- **image_diff**: Compare two same-size screenshots; reports the percentage of pixels that changed perceptibly and saves a diff image (for UI regression checks)
+  - Format: {\"tool\": \"image_diff\", \"args\": {\"before\": \"before.png\", \"after\": \"after.png\", \"max_diff_percent\": 1.0}}
+  - Example: {\"tool\": \"image_diff\", \"args\": {\"before\": \"home_before.png\", \"after\": \"home_after.png\", \"ignore_regions\": [{\"x\": 0, \"y\": 0, \"width\": 200, \"height\": 40}]}}
+
 - **write_file**: Write content to a file (creates or overwrites)
   - Format: {\"tool\": \"write_file\", \"args\": {\"file_path\": \"path/to/file\", \"content\": \"file content\"}}
   - Example: {\"tool\": \"write_file\", \"args\": {\"file_path\": \"src/lib.rs\", \"content\": \"pub fn hello() {}\"}}
@@ -213,6 +298,14 @@ Short description for providers without native calling specs:
   - Format: {\"tool\": \"str_replace\", \"args\": {\"file_path\": \"path/to/file\", \"diff\": \"--- old\\n-old text\\n+++ new\\n+new text\"}}
   - Example: {\"tool\": \"str_replace\", \"args\": {\"file_path\": \"src/main.rs\", \"diff\": \"--- old\\n-old_code();\\n+++ new\\n+new_code();\"}}
 
+- **multi_edit**: Apply several str_replace-style diffs, to one or more files, in one call; if any edit fails nothing is changed
+  - Format: {\"tool\": \"multi_edit\", \"args\": {\"edits\": [{\"file_path\": \"path/to/file\", \"diff\": \"-old text\\n+new text\"}, ...]}}
+  - Example: {\"tool\": \"multi_edit\", \"args\": {\"edits\": [{\"file_path\": \"src/lib.rs\", \"diff\": \"-fn old_name()\\n+fn new_name()\"}, {\"file_path\": \"src/main.rs\", \"diff\": \"-old_name();\\n+new_name();\"}]}}
+
+- **edit_lines**: Replace lines start_line..=end_line (1-indexed), echoing the current first/last lines for validation
+  - Format: {\"tool\": \"edit_lines\", \"args\": {\"file_path\": \"path/to/file\", \"start_line\": 10, \"end_line\": 12, \"expected_first_line\": \"current line 10\", \"expected_last_line\": \"current line 12\", \"content\": \"new lines\"}}
+  - Example: {\"tool\": \"edit_lines\", \"args\": {\"file_path\": \"src/main.rs\", \"start_line\": 3, \"end_line\": 3, \"expected_first_line\": \"    old_code();\", \"expected_last_line\": \"    old_code();\", \"content\": \"    new_code();\"}}
+
 - **todo_read**: Read the current session's TODO list from todo.g3.md (session-scoped)
   - Format: {\"tool\": \"todo_read\", \"args\": {}}
   - Example: {\"tool\": \"todo_read\", \"args\": {}}
@@ -221,6 +314,10 @@ Short description for providers without native calling specs:
   - Format: {\"tool\": \"todo_write\", \"args\": {\"content\": \"- [ ] Task 1\\n- [ ] Task 2\"}}
   - Example: {\"tool\": \"todo_write\", \"args\": {\"content\": \"- [ ] Implement feature\\n  - [ ] Write tests\\n  - [ ] Run tests\"}}
 
+- **plan_read** / **plan_write**: Your own working plan (goal, steps with status and rationale), separate from the TODO list; reviewed by the coach in autonomous mode
+  - Format: {\"tool\": \"plan_write\", \"args\": {\"goal\": \"...\", \"steps\": [{\"title\": \"...\", \"status\": \"pending\", \"rationale\": \"why\"}]}}
+  - Example: {\"tool\": \"plan_write\", \"args\": {\"updates\": [{\"step\": 2, \"status\": \"blocked\", \"rationale\": \"Needs the API key\"}]}}
+
 - **code_search**: Syntax-aware code search using tree-sitter. Supports Rust, Python, JavaScript, TypeScript.
   - Format: {\"tool\": \"code_search\", \"args\": {\"searches\": [{\"name\": \"label\", \"query\": \"tree-sitter query\", \"language\": \"rust|python|javascript|typescript\", \"paths\": [\"src/\"], \"context_lines\": 0}]}}
   - Find functions: {\"tool\": \"code_search\", \"args\": {\"searches\": [{\"name\": \"find_functions\", \"query\": \"(function_item name: (identifier) @name)\", \"language\": \"rust\", \"paths\": [\"src/\"]}]}}
@@ -229,6 +326,30 @@ Short description for providers without native calling specs:
   - Multiple searches: {\"tool\": \"code_search\", \"args\": {\"searches\": [{\"name\": \"funcs\", \"query\": \"(function_item name: (identifier) @name)\", \"language\": \"rust\"}, {\"name\": \"structs\", \"query\": \"(struct_item name: (type_identifier) @name)\", \"language\": \"rust\"}]}}
   - With context lines: {\"tool\": \"code_search\", \"args\": {\"searches\": [{\"name\": \"funcs\", \"query\": \"(function_item name: (identifier) @name)\", \"language\": \"rust\", \"context_lines\": 3}]}}
 
+- **find_definition**: Find where a symbol is defined (syntax-aware: ignores comments and strings), as file:line:column with context
+  - Format: {\"tool\": \"find_definition\", \"args\": {\"symbol\": \"Name\", \"language\": \"rust\", \"paths\": [\"src/\"], \"context_lines\": 1}}
+  - Example: {\"tool\": \"find_definition\", \"args\": {\"symbol\": \"ContextWindow\"}}
+
+- **find_references**: Find the imports of and references to a symbol across the workspace (syntax-aware, definitions excluded)
+  - Format: {\"tool\": \"find_references\", \"args\": {\"symbol\": \"Name\", \"language\": \"rust\", \"paths\": [\"src/\"], \"context_lines\": 1, \"max_results\": 100}}
+  - Example: {\"tool\": \"find_references\", \"args\": {\"symbol\": \"execute_task\", \"language\": \"rust\"}}
+
+- **symbol_lookup**: Where a function, type, class or method is declared, from the symbol index built during discovery (no search)
+  - Format: {\"tool\": \"symbol_lookup\", \"args\": {\"symbol\": \"Type::method\", \"kind\": \"method\", \"max_results\": 20}}
+  - Example: {\"tool\": \"symbol_lookup\", \"args\": {\"symbol\": \"ContextWindow\"}}
+
+- **ast_outline**: Outline of a source file (functions, types, classes, impls with signatures and line ranges); use before reading big files
+  - Format: {\"tool\": \"ast_outline\", \"args\": {\"path\": \"path/to/file\", \"max_depth\": 2}}
+  - Example: {\"tool\": \"ast_outline\", \"args\": {\"path\": \"crates/g3-core/src/lib.rs\"}}
+
+- **rename_symbol**: Rename a symbol and its references through the configured language server (semantic, unlike regex_replace)
+  - Format: {\"tool\": \"rename_symbol\", \"args\": {\"path\": \"src/lib.rs\", \"line\": 42, \"symbol\": \"old_name\", \"new_name\": \"new_name\", \"dry_run\": false}}
+  - Example: {\"tool\": \"rename_symbol\", \"args\": {\"path\": \"app/models.py\", \"line\": 10, \"symbol\": \"User\", \"new_name\": \"Account\"}}
+
+- **cargo**: Run cargo check/clippy/test/build and get parsed diagnostics (file:line:column, level, message) and failed tests
+  - Format: {\"tool\": \"cargo\", \"args\": {\"subcommand\": \"check\", \"package\": \"my-crate\", \"all_targets\": true, \"args\": []}}
+  - Example: {\"tool\": \"cargo\", \"args\": {\"subcommand\": \"test\", \"args\": [\"--\", \"parser::tests\"]}}
+
 - **research**: Perform web-based research and return a structured report
   - Format: {\"tool\": \"research\", \"args\": {\"query\": \"your research question\"}}
   - Example: {\"tool\": \"research\", \"args\": {\"query\": \"Best Rust HTTP client libraries for async/await\"}}
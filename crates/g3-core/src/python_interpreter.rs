@@ -0,0 +1,260 @@
+//! Persistent Python interpreter for the `python` tool.
+//!
+//! One interpreter is started on the first `python` call and kept for the
+//! rest of the session, so variables, imports and loaded data survive
+//! between calls. It runs a small driver that reads one JSON request per
+//! line on stdin, executes the code in a shared namespace with stdout and
+//! stderr captured, and answers on a duplicate of the original stdout; the
+//! process's own fd 1 is pointed at stderr so output written below Python
+//! (C extensions, subprocesses) can't corrupt the protocol. Like the REPL,
+//! the value of a trailing expression is returned.
+//!
+//! Timeouts are enforced inside the driver with `SIGALRM`, which keeps the
+//! interpreter and its state. Code stuck in native code (or on platforms
+//! without `SIGALRM`) is caught by a second, outer timeout that kills the
+//! interpreter; the next call starts a fresh one.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// How long the interpreter may take to start.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Extra time allowed for the driver to answer after its own timeout.
+const REPLY_GRACE: Duration = Duration::from_secs(5);
+
+const DRIVER: &str = r#"
+import ast, io, json, os, signal, sys, traceback
+
+protocol = os.fdopen(os.dup(1), "w")
+os.dup2(2, 1)
+namespace = {"__name__": "__main__", "__builtins__": __builtins__}
+
+class Timeout(BaseException):
+    pass
+
+def on_alarm(signum, frame):
+    raise Timeout()
+
+has_alarm = hasattr(signal, "setitimer")
+if has_alarm:
+    signal.signal(signal.SIGALRM, on_alarm)
+
+def run(code):
+    tree = ast.parse(code, "<python>", "exec")
+    last = None
+    if tree.body and isinstance(tree.body[-1], ast.Expr):
+        last = ast.Expression(tree.body.pop().value)
+    exec(compile(tree, "<python>", "exec"), namespace)
+    if last is not None:
+        value = eval(compile(last, "<python>", "eval"), namespace)
+        if value is not None:
+            namespace["_"] = value
+            return repr(value)
+    return None
+
+print(json.dumps({"status": "ready", "version": sys.version.split()[0]}), file=protocol, flush=True)
+
+for line in sys.stdin:
+    request = json.loads(line)
+    out, err = io.StringIO(), io.StringIO()
+    sys.stdout, sys.stderr = out, err
+    status, result, error = "ok", None, None
+    try:
+        if has_alarm:
+            signal.setitimer(signal.ITIMER_REAL, request["timeout"])
+        try:
+            result = run(request["code"])
+        finally:
+            if has_alarm:
+                signal.setitimer(signal.ITIMER_REAL, 0)
+    except Timeout:
+        status = "timeout"
+    except KeyboardInterrupt:
+        status, error = "error", "KeyboardInterrupt"
+    except BaseException as e:
+        if isinstance(e, SystemExit):
+            status, error = "error", "SystemExit: exit() is ignored; the interpreter keeps running"
+        else:
+            status = "error"
+            frames = traceback.extract_tb(e.__traceback__)
+            frames = [f for f in frames if f.filename == "<python>"]
+            error = "".join(traceback.format_list(frames) + traceback.format_exception_only(type(e), e))
+    finally:
+        sys.stdout, sys.stderr = sys.__stdout__, sys.__stderr__
+    print(json.dumps({
+        "status": status,
+        "stdout": out.getvalue(),
+        "stderr": err.getvalue(),
+        "result": result,
+        "error": error,
+    }), file=protocol, flush=True)
+"#;
+
+/// The result of running one piece of code.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Execution {
+    /// "ok", "error" or "timeout"
+    pub status: String,
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+    /// repr() of a trailing expression's value, when not None
+    pub result: Option<String>,
+    /// The traceback, for errors
+    pub error: Option<String>,
+    /// Number of this execution in the interpreter's life, from 1
+    #[serde(skip)]
+    pub count: u64,
+}
+
+struct Interpreter {
+    program: PathBuf,
+    version: String,
+    executions: u64,
+    /// Killed when the interpreter is dropped
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+impl Interpreter {
+    async fn start(program: &Path, cwd: &Path) -> Result<Self> {
+        let mut child = Command::new(program)
+            .args(["-u", "-c", DRIVER])
+            .current_dir(cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("Failed to run {}: {}", program.display(), e))?;
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("No stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("No stdout"))?;
+        let stderr = child.stderr.take().ok_or_else(|| anyhow!("No stderr"))?;
+        // Drain stderr (where output from below Python ends up), remembering
+        // the last line in case startup fails
+        let last_stderr_line = tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            let mut last = String::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if !line.trim().is_empty() {
+                    last = line;
+                }
+            }
+            last
+        });
+        let mut stdout = BufReader::new(stdout).lines();
+
+        let ready = match tokio::time::timeout(STARTUP_TIMEOUT, stdout.next_line()).await {
+            Ok(Ok(Some(line))) => line,
+            Ok(_) => {
+                let reason = last_stderr_line.await.unwrap_or_default();
+                bail!(
+                    "Could not start {}: {}",
+                    program.display(),
+                    if reason.is_empty() {
+                        "the interpreter exited"
+                    } else {
+                        &reason
+                    }
+                )
+            }
+            Err(_) => bail!(
+                "{} did not start within {}s",
+                program.display(),
+                STARTUP_TIMEOUT.as_secs()
+            ),
+        };
+        let ready: serde_json::Value = serde_json::from_str(&ready)?;
+        Ok(Self {
+            program: program.to_path_buf(),
+            version: ready["version"].as_str().unwrap_or_default().to_string(),
+            executions: 0,
+            _child: child,
+            stdin,
+            stdout,
+        })
+    }
+
+    async fn execute(&mut self, code: &str, timeout: Duration) -> Result<Execution> {
+        let request = serde_json::json!({ "code": code, "timeout": timeout.as_secs_f64() });
+        self.stdin
+            .write_all(format!("{}\n", request).as_bytes())
+            .await?;
+        self.stdin.flush().await?;
+        let mut execution: Execution =
+            match tokio::time::timeout(timeout + REPLY_GRACE, self.stdout.next_line()).await {
+                Ok(Ok(Some(line))) => serde_json::from_str(&line)?,
+                Ok(Ok(None)) => bail!("The Python interpreter exited"),
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => bail!(
+                    "The Python interpreter stopped responding after {}s",
+                    timeout.as_secs()
+                ),
+            };
+        self.executions += 1;
+        execution.count = self.executions;
+        Ok(execution)
+    }
+}
+
+/// The session's Python interpreter, started on first use.
+#[derive(Default)]
+pub struct PythonInterpreter {
+    interpreter: Mutex<Option<Interpreter>>,
+}
+
+impl PythonInterpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Execute `code`, starting `program` in `cwd` if no interpreter is
+    /// running (or the running one is a different program). An interpreter
+    /// that fails is discarded, so the next call starts a fresh one.
+    pub async fn execute(
+        &self,
+        program: &Path,
+        cwd: &Path,
+        code: &str,
+        timeout: Duration,
+    ) -> Result<Execution> {
+        let mut interpreter = self.interpreter.lock().await;
+        if interpreter.as_ref().is_some_and(|i| i.program != program) {
+            *interpreter = None;
+        }
+        if interpreter.is_none() {
+            debug!("Starting Python interpreter {}", program.display());
+            *interpreter = Some(Interpreter::start(program, cwd).await?);
+        }
+
+        let Some(running) = interpreter.as_mut() else {
+            bail!("The Python interpreter is not running");
+        };
+        let result = running.execute(code, timeout).await;
+        if result.is_err() {
+            *interpreter = None;
+        }
+        result
+    }
+
+    /// The running interpreter's Python version, if any.
+    pub async fn version(&self) -> Option<String> {
+        let interpreter = self.interpreter.lock().await;
+        interpreter.as_ref().map(|i| i.version.clone())
+    }
+
+    /// Stop the interpreter, if running. Returns whether one was.
+    pub async fn reset(&self) -> bool {
+        self.interpreter.lock().await.take().is_some()
+    }
+}
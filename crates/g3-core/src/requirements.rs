@@ -0,0 +1,320 @@
+//! Structured requirements: IDs, titles and acceptance criteria.
+//!
+//! Requirements can be free text, or a list in YAML, JSON or Markdown:
+//!
+//! - YAML/JSON: a list of requirements, or an object with a `requirements`
+//!   list. Each has an `id`, a `title` (or `name`/`summary`), an optional
+//!   `description` and `acceptance_criteria` (a list or a single string).
+//!   A bare string is a requirement with that title.
+//! - Markdown: headings or top-level bullets that start with an ID
+//!   (`## REQ-1: Login`, `- **FR-2** Export`). Bullets under an
+//!   "Acceptance criteria" heading or line, or nested under an ID bullet,
+//!   are its acceptance criteria; other text is its description.
+//!
+//! Free text has no structure, and parses to an empty list.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// One requirement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Requirement {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub acceptance_criteria: Vec<String>,
+}
+
+/// Parse requirements into a structured list, by the file extension of
+/// `path` when given, otherwise by their content. Empty for free text.
+pub fn parse_requirements(text: &str, path: Option<&Path>) -> Vec<Requirement> {
+    let extension = path
+        .and_then(|p| p.extension())
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("yaml" | "yml" | "json") => parse_structured(text, true).unwrap_or_default(),
+        Some("md" | "markdown") => parse_markdown(text),
+        _ => parse_structured(text, false).unwrap_or_else(|| parse_markdown(text)),
+    }
+}
+
+/// The requirements as a compact list for prompts.
+pub fn format_requirements(requirements: &[Requirement]) -> String {
+    let mut out = String::new();
+    for requirement in requirements {
+        out.push_str(&format!("- {}: {}\n", requirement.id, requirement.title));
+        for criterion in &requirement.acceptance_criteria {
+            out.push_str(&format!("  - [ ] {}\n", criterion));
+        }
+    }
+    out
+}
+
+/// YAML or JSON (a YAML subset) holding a list of requirements.
+///
+/// A Markdown bullet list is valid YAML too, so unless the file is known to
+/// be YAML or JSON (`explicit`), a list of bare strings doesn't count.
+fn parse_structured(text: &str, explicit: bool) -> Option<Vec<Requirement>> {
+    let value: Value = serde_yaml::from_str(text).ok()?;
+    let items = match &value {
+        Value::Array(items) => items,
+        Value::Object(map) => map.get("requirements")?.as_array()?,
+        _ => return None,
+    };
+    if !explicit && !items.iter().any(Value::is_object) {
+        return None;
+    }
+
+    let mut requirements = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        let requirement = match item {
+            Value::String(title) => Requirement {
+                id: format!("R{}", index + 1),
+                title: title.trim().to_string(),
+                description: String::new(),
+                acceptance_criteria: Vec::new(),
+            },
+            Value::Object(map) => {
+                let field = |keys: &[&str]| {
+                    keys.iter().find_map(|k| match map.get(*k) {
+                        Some(Value::String(s)) => Some(s.trim().to_string()),
+                        Some(Value::Number(n)) => Some(n.to_string()),
+                        _ => None,
+                    })
+                };
+                let criteria = [
+                    "acceptance_criteria",
+                    "acceptanceCriteria",
+                    "acceptance",
+                    "criteria",
+                ]
+                .iter()
+                .find_map(|k| map.get(*k))
+                .map(|v| match v {
+                    Value::Array(items) => items
+                        .iter()
+                        .filter_map(|i| i.as_str())
+                        .map(|s| s.trim().to_string())
+                        .collect(),
+                    Value::String(s) => vec![s.trim().to_string()],
+                    _ => Vec::new(),
+                })
+                .unwrap_or_default();
+                let id = field(&["id", "key"]).unwrap_or_else(|| format!("R{}", index + 1));
+                let description = field(&["description", "details"]).unwrap_or_default();
+                let title = field(&["title", "name", "summary"])
+                    .or_else(|| description.lines().next().map(String::from))
+                    .unwrap_or_else(|| id.clone());
+                Requirement {
+                    id,
+                    title,
+                    description,
+                    acceptance_criteria: criteria,
+                }
+            }
+            _ => return None,
+        };
+        requirements.push(requirement);
+    }
+    (!requirements.is_empty()).then_some(requirements)
+}
+
+/// Markdown with ID-prefixed headings or bullets.
+fn parse_markdown(text: &str) -> Vec<Requirement> {
+    let mut requirements: Vec<Requirement> = Vec::new();
+    // Heading level (or 0 for a bullet) the current requirement started at
+    let mut current_level: Option<usize> = None;
+    let mut in_criteria = false;
+    let mut in_code = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+
+        if let Some(heading) = heading(trimmed) {
+            let (level, title) = heading;
+            if let Some((id, title)) = split_id(title) {
+                requirements.push(new_requirement(id, title));
+                current_level = Some(level);
+                in_criteria = false;
+            } else if current_level.is_some() && is_criteria_label(title) {
+                in_criteria = true;
+            } else if current_level.is_some_and(|l| l == 0 || level <= l) {
+                current_level = None;
+            } else {
+                in_criteria = false;
+            }
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        if let Some(item) = bullet(trimmed) {
+            if indent == 0 {
+                if let Some((id, title)) = split_id(item) {
+                    requirements.push(new_requirement(id, title));
+                    current_level = Some(0);
+                    in_criteria = false;
+                    continue;
+                }
+            }
+            let Some(requirement) = current_level.and(requirements.last_mut()) else {
+                continue;
+            };
+            // Bullets nested under an ID bullet are its criteria
+            if in_criteria || (current_level == Some(0) && indent > 0) {
+                requirement.acceptance_criteria.push(item.to_string());
+                continue;
+            }
+            if current_level == Some(0) {
+                // A top-level bullet without an ID ends a bullet requirement
+                current_level = None;
+                continue;
+            }
+        }
+
+        let Some(requirement) = current_level.and(requirements.last_mut()) else {
+            continue;
+        };
+        if is_criteria_label(trimmed) {
+            in_criteria = true;
+        } else if !trimmed.is_empty() && !in_criteria {
+            if !requirement.description.is_empty() {
+                requirement.description.push('\n');
+            }
+            requirement.description.push_str(trimmed);
+        }
+    }
+    requirements
+}
+
+fn new_requirement(id: String, title: &str) -> Requirement {
+    Requirement {
+        id,
+        title: title.to_string(),
+        description: String::new(),
+        acceptance_criteria: Vec::new(),
+    }
+}
+
+/// `## Title` as (level, title).
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 {
+        return None;
+    }
+    let title = line[level..].strip_prefix(' ')?;
+    Some((level, title.trim()))
+}
+
+/// The text of a `-`, `*`, `+` or `1.` list item, without a checkbox.
+fn bullet(line: &str) -> Option<&str> {
+    let item = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+        .or_else(|| {
+            let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+            (digits > 0)
+                .then(|| line[digits..].strip_prefix(". "))
+                .flatten()
+        })?;
+    let item = ["[ ] ", "[x] ", "[X] "]
+        .iter()
+        .find_map(|checkbox| item.strip_prefix(checkbox))
+        .unwrap_or(item);
+    Some(item.trim())
+}
+
+/// "Acceptance criteria", as a heading or a (bold) label line.
+fn is_criteria_label(text: &str) -> bool {
+    let label = text
+        .trim_matches(|c: char| c == '*' || c == '_' || c == ':' || c.is_whitespace())
+        .to_ascii_lowercase();
+    label == "acceptance criteria" || label == "acceptance" || label == "criteria"
+}
+
+/// Split `REQ-1: Title` (also `[REQ-1] Title`, `**REQ-1** - Title`) into its
+/// ID and title. IDs are uppercase letters followed by digits, optionally
+/// separated by `-`, `_` or `.` (`R1`, `FR-2.3`, `US_12`).
+fn split_id(text: &str) -> Option<(String, &str)> {
+    let text = text.trim_start_matches(['*', '_', '[', '`']);
+    let end = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(text.len());
+    let id = text[..end].trim_end_matches(['.', '-', '_']);
+    let is_id = id.starts_with(|c: char| c.is_ascii_uppercase())
+        && id.chars().any(|c| c.is_ascii_digit())
+        && !id.chars().any(|c| c.is_ascii_lowercase())
+        && id.len() <= 20;
+    if !is_id {
+        return None;
+    }
+    let title = text[id.len()..]
+        .trim_start_matches(|c: char| {
+            matches!(c, '*' | '_' | ']' | '`' | ':' | '-' | '.' | '–' | '—') || c.is_whitespace()
+        })
+        .trim_end_matches(['*', '_']);
+    Some((id.to_string(), title))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_yaml_and_json() {
+        let yaml = "requirements:\n  - id: REQ-1\n    title: Users can log in\n    acceptance_criteria:\n      - Wrong passwords are rejected\n      - Sessions expire after 1h\n  - title: Export to CSV\n    acceptance: One row per record\n  - Dark mode\n";
+        let requirements = parse_requirements(yaml, Some(Path::new("requirements.yaml")));
+        assert_eq!(requirements.len(), 3);
+        assert_eq!(requirements[0].id, "REQ-1");
+        assert_eq!(requirements[0].acceptance_criteria.len(), 2);
+        assert_eq!(requirements[1].id, "R2");
+        assert_eq!(requirements[1].acceptance_criteria, ["One row per record"]);
+        assert_eq!(requirements[2].title, "Dark mode");
+
+        let json = r#"[{"id": 7, "name": "Rate limiting", "description": "Per API key", "criteria": ["429 after 100 req/min"]}]"#;
+        let requirements = parse_requirements(json, None);
+        assert_eq!(requirements[0].id, "7");
+        assert_eq!(requirements[0].title, "Rate limiting");
+        assert_eq!(requirements[0].description, "Per API key");
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        let markdown = "# Requirements\n\nIntro text.\n\n## REQ-1: Users can log in\nWith email and password.\n\n### Acceptance criteria\n- [ ] Wrong passwords are rejected\n- [x] Sessions expire\n\n## [REQ-2] Export\n**Acceptance criteria:**\n1. One row per record\n\n## Notes\n- not a requirement\n\n- **FR-3** - Dark mode\n  - Follows the OS setting\n- unrelated bullet\n";
+        let requirements = parse_requirements(markdown, Some(Path::new("requirements.md")));
+        let ids: Vec<&str> = requirements.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["REQ-1", "REQ-2", "FR-3"]);
+        assert_eq!(requirements[0].title, "Users can log in");
+        assert_eq!(requirements[0].description, "With email and password.");
+        assert_eq!(
+            requirements[0].acceptance_criteria,
+            ["Wrong passwords are rejected", "Sessions expire"]
+        );
+        assert_eq!(requirements[1].title, "Export");
+        assert_eq!(requirements[1].acceptance_criteria, ["One row per record"]);
+        assert_eq!(requirements[2].title, "Dark mode");
+        assert_eq!(
+            requirements[2].acceptance_criteria,
+            ["Follows the OS setting"]
+        );
+    }
+
+    #[test]
+    fn test_free_text_has_no_structure() {
+        assert!(
+            parse_requirements("Build a todo app with a REST API.\n\nUse SQLite.", None).is_empty()
+        );
+        assert!(parse_requirements("# App\n\n- Add login\n- Add export\n", None).is_empty());
+    }
+}
@@ -4,10 +4,14 @@
 //! including error classification, exponential backoff, and configurable retry strategies.
 //!
 //! Used by both autonomous mode (g3-cli) and planning mode (g3-planner).
+//!
+//! It also decides which tool calls can be repeated safely and which of their
+//! failures are worth repeating them for.
 
+use crate::discovery_allowlist::check_read_only;
 use crate::error_handling::{calculate_retry_delay, classify_error, ErrorType, RecoverableError};
 use crate::ui_writer::UiWriter;
-use crate::{Agent, DiscoveryOptions, TaskResult};
+use crate::{Agent, DiscoveryOptions, TaskResult, ToolCall};
 use anyhow::Result;
 use std::time::Instant;
 use tracing::debug;
@@ -71,8 +75,8 @@ impl RetryConfig {
 /// Result of a retry operation
 #[derive(Debug)]
 pub enum RetryResult {
-    /// Task succeeded with result
-    Success(TaskResult),
+    /// Task succeeded with result (boxed, as it holds the whole context window)
+    Success(Box<TaskResult>),
     /// Task failed after max retries (contains last error message)
     MaxRetriesReached(String),
     /// Context length exceeded - should end current turn
@@ -90,7 +94,7 @@ impl RetryResult {
     /// Get the task result if successful
     pub fn into_result(self) -> Option<TaskResult> {
         match self {
-            RetryResult::Success(result) => Some(result),
+            RetryResult::Success(result) => Some(*result),
             _ => None,
         }
     }
@@ -149,7 +153,7 @@ where
                         start_time.elapsed()
                     );
                 }
-                return RetryResult::Success(task_result);
+                return RetryResult::Success(Box::new(task_result));
             }
             Err(e) => {
                 let error_type = classify_error(&e);
@@ -305,6 +309,201 @@ where
     }
 }
 
+/// Tools that give the same result however often they run, so a failed call
+/// can simply be made again.
+const IDEMPOTENT_TOOLS: &[&str] = &[
+    "read_file",
+    "read_many_files",
+    "read_output",
+    "read_image",
+    "list_dir",
+    "grep",
+    "git_status",
+    "git_diff",
+    "notebook_read",
+    "inspect_env",
+    "image_diff",
+    "todo_read",
+    "plan_read",
+    "code_search",
+    "find_definition",
+    "find_references",
+    "symbol_lookup",
+    "ast_outline",
+    "dead_code",
+    "webdriver_get_page_source",
+    "webdriver_get_title",
+    "webdriver_get_url",
+    "webdriver_find_element",
+    "webdriver_find_elements",
+    "webdriver_screenshot",
+];
+
+/// Shell commands that build, test or fetch without changing the sources:
+/// running them twice does no more than running them once.
+const IDEMPOTENT_COMMANDS: &[&[&str]] = &[
+    &["cargo", "build"],
+    &["cargo", "check"],
+    &["cargo", "test"],
+    &["cargo", "clippy"],
+    &["cargo", "doc"],
+    &["cargo", "fetch"],
+    &["cargo", "nextest"],
+    &["go", "build"],
+    &["go", "test"],
+    &["go", "vet"],
+    &["go", "mod", "download"],
+    &["npm", "test"],
+    &["npm", "ci"],
+    &["yarn", "test"],
+    &["pnpm", "test"],
+    &["pytest"],
+    &["python", "-m", "pytest"],
+    &["python3", "-m", "pytest"],
+    &["make", "test"],
+    &["mvn", "test"],
+    &["gradle", "test"],
+    &["./gradlew", "test"],
+    &["git", "fetch"],
+];
+
+/// Output that says a tool failed for a reason that may be gone on the next
+/// attempt, with a name for each.
+const TRANSIENT_FAILURES: &[(&str, &str)] = &[
+    ("connection reset", "network error"),
+    ("connection refused", "network error"),
+    ("connection closed", "network error"),
+    ("broken pipe", "network error"),
+    // Only the network's timeouts: a tool's own timeout would just be hit again
+    ("connection timed out", "network error"),
+    ("connect timed out", "network error"),
+    ("resolving timed out", "network error"),
+    ("could not resolve host", "network error"),
+    ("temporary failure in name resolution", "network error"),
+    ("name or service not known", "network error"),
+    ("spurious network error", "network error"),
+    ("failed to download", "network error"),
+    ("tls handshake", "network error"),
+    ("502 bad gateway", "server error"),
+    ("503 service unavailable", "server error"),
+    ("504 gateway timeout", "server error"),
+    ("internal compiler error", "compiler crash"),
+    ("the compiler unexpectedly panicked", "compiler crash"),
+    ("thread 'rustc' panicked", "compiler crash"),
+];
+
+/// Whether `tool_call` can be repeated without doing anything twice.
+///
+/// Tools that only read are; so are `http_request` GETs and shell commands
+/// that only read or only build, test or download.
+pub fn is_idempotent(tool_call: &ToolCall) -> bool {
+    let arg = |name: &str| tool_call.args.get(name).and_then(|v| v.as_str());
+    match tool_call.tool.as_str() {
+        "http_request" => matches!(
+            arg("method").unwrap_or("GET").to_uppercase().as_str(),
+            "GET" | "HEAD" | "OPTIONS"
+        ),
+        "shell" => arg("command").is_some_and(is_idempotent_command),
+        tool => IDEMPOTENT_TOOLS.contains(&tool),
+    }
+}
+
+/// Whether every `&&`-joined part of a shell command only reads, or builds,
+/// tests or downloads.
+fn is_idempotent_command(command: &str) -> bool {
+    if check_read_only(command).is_ok() {
+        return true;
+    }
+    command.split("&&").all(|part| {
+        let part = part.trim();
+        if part.contains(['|', ';', '>', '`', '\n', '&']) || part.contains("$(") {
+            return false;
+        }
+        let words: Vec<&str> = part.split_whitespace().collect();
+        match words.first() {
+            Some(&"cd") => words.len() <= 2,
+            Some(&"curl") => is_curl_download(&words[1..]),
+            Some(&"wget") => is_wget_download(&words[1..]),
+            Some(_) => {
+                IDEMPOTENT_COMMANDS
+                    .iter()
+                    .any(|prefix| words.starts_with(prefix))
+                    || check_read_only(part).is_ok()
+            }
+            None => false,
+        }
+    })
+}
+
+/// Whether curl's arguments only fetch: no method other than GET or HEAD,
+/// and no body or upload that a repeat would send again.
+fn is_curl_download(args: &[&str]) -> bool {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let method = if let Some(long) = arg.strip_prefix("--") {
+            let name = long.split('=').next().unwrap_or(long);
+            if name.starts_with("data")
+                || name.starts_with("form")
+                || matches!(name, "json" | "upload-file")
+            {
+                return false;
+            }
+            if name != "request" {
+                continue;
+            }
+            match long.split_once('=') {
+                Some((_, method)) => Some(method),
+                None => args.next().copied(),
+            }
+        } else if let Some(short) = arg.strip_prefix('-') {
+            // `-X` takes the rest of the bundle or the next word as its value
+            let (flags, method) = short.split_once('X').unwrap_or((short, ""));
+            if flags.contains(['d', 'F', 'T']) {
+                return false;
+            }
+            match (short.contains('X'), method) {
+                (false, _) => continue,
+                (true, "") => args.next().copied(),
+                (true, method) => Some(method),
+            }
+        } else {
+            continue;
+        };
+        let method = method.unwrap_or("").trim_matches(['\'', '"']);
+        if !matches!(method.to_uppercase().as_str(), "GET" | "HEAD") {
+            return false;
+        }
+    }
+    true
+}
+
+/// Whether wget's arguments only fetch, without a body or another method.
+fn is_wget_download(args: &[&str]) -> bool {
+    !args.iter().any(|arg| {
+        let name = arg.split('=').next().unwrap_or(*arg);
+        matches!(
+            name,
+            "--post-data" | "--post-file" | "--body-data" | "--body-file" | "--method"
+        )
+    })
+}
+
+/// Why a tool call failed, if it failed for a reason that may be gone on the
+/// next attempt: a dropped connection, a server hiccup, a compiler crash.
+/// Tools report most failures as output starting with ❌ rather than as
+/// errors, so both are looked at.
+pub fn transient_tool_failure(result: &Result<String>) -> Option<&'static str> {
+    let text = match result {
+        Ok(output) if output.starts_with("❌") => output.to_lowercase(),
+        Ok(_) => return None,
+        Err(e) => format!("{:#}", e).to_lowercase(),
+    };
+    TRANSIENT_FAILURES
+        .iter()
+        .find(|(pattern, _)| text.contains(pattern))
+        .map(|(_, reason)| *reason)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -343,10 +542,112 @@ mod tests {
     fn test_retry_result_is_success() {
         use crate::ContextWindow;
         let ctx = ContextWindow::new(1000);
-        let result = RetryResult::Success(TaskResult::new("test".to_string(), ctx));
+        let result = RetryResult::Success(Box::new(TaskResult::new("test".to_string(), ctx)));
         assert!(result.is_success());
 
         let failed = RetryResult::MaxRetriesReached("error".to_string());
         assert!(!failed.is_success());
     }
+
+    fn call(tool: &str, args: serde_json::Value) -> ToolCall {
+        ToolCall {
+            tool: tool.to_string(),
+            args,
+        }
+    }
+
+    #[test]
+    fn test_idempotent_tools_and_commands() {
+        use serde_json::json;
+        let shell = |command: &str| call("shell", json!({ "command": command }));
+
+        let file = json!({ "file_path": "a" });
+        assert!(is_idempotent(&call("read_file", file.clone())));
+        assert!(!is_idempotent(&call("write_file", file)));
+        assert!(!is_idempotent(&call("cargo", json!({ "command": "test" }))));
+        assert!(is_idempotent(&call("http_request", json!({ "url": "u" }))));
+        assert!(!is_idempotent(&call(
+            "http_request",
+            json!({ "url": "u", "method": "post" })
+        )));
+
+        assert!(is_idempotent(&shell("cargo test --workspace")));
+        assert!(is_idempotent(&shell("cd crates/app && cargo build")));
+        assert!(is_idempotent(&shell("ls -la")));
+        assert!(!is_idempotent(&shell("cargo test && rm -rf target")));
+        assert!(!is_idempotent(&shell("cargo build > log.txt")));
+        assert!(!is_idempotent(&shell("git push")));
+        assert!(!is_idempotent(&shell("cargo test\ngit push")));
+        assert!(!is_idempotent(&shell("cargo build & git push")));
+        assert!(!is_idempotent(&call("shell", json!({}))));
+    }
+
+    #[test]
+    fn test_only_fetching_curl_and_wget_are_idempotent() {
+        use serde_json::json;
+        let shell = |command: &str| call("shell", json!({ "command": command }));
+
+        for command in [
+            "curl -fsSL https://example.com/a.tar.gz -o a.tar.gz",
+            "curl -X GET https://example.com",
+            "curl -sXGET https://example.com",
+            "curl --request=head https://example.com",
+            "wget -q https://example.com/a.tar.gz",
+        ] {
+            assert!(is_idempotent(&shell(command)), "{}", command);
+        }
+        for command in [
+            "curl -X POST https://example.com/orders",
+            "curl -sXDELETE https://example.com/orders/1",
+            "curl --request PUT https://example.com",
+            "curl -d 'a=1' https://example.com",
+            "curl --data-binary @body https://example.com",
+            "curl -F file=@a.txt https://example.com",
+            "curl -T a.txt https://example.com",
+            "curl --json '{}' https://example.com",
+            "wget --post-data=a=1 https://example.com",
+        ] {
+            assert!(!is_idempotent(&shell(command)), "{}", command);
+        }
+    }
+
+    #[test]
+    fn test_transient_failures() {
+        assert_eq!(
+            transient_tool_failure(&Ok(
+                "❌ Exit code 101: error: failed to download `serde`".to_string()
+            )),
+            Some("network error")
+        );
+        assert_eq!(
+            transient_tool_failure(&Ok(
+                "❌ Exit code 101: error: internal compiler error: unexpected panic".to_string()
+            )),
+            Some("compiler crash")
+        );
+        assert_eq!(
+            transient_tool_failure(&Err(anyhow::anyhow!("Connection reset by peer"))),
+            Some("network error")
+        );
+        assert_eq!(
+            transient_tool_failure(&Ok(
+                "❌ curl: (28) Connection timed out after 10001 milliseconds".to_string()
+            )),
+            Some("network error")
+        );
+        // A build or test run that hits its timeout would hit it again
+        assert_eq!(
+            transient_tool_failure(&Ok("❌ cargo test timed out after 600s".to_string())),
+            None
+        );
+        // Successful output may mention anything
+        assert_eq!(
+            transient_tool_failure(&Ok("test connection refused ... ok".to_string())),
+            None
+        );
+        assert_eq!(
+            transient_tool_failure(&Ok("❌ Exit code 1: assertion failed".to_string())),
+            None
+        );
+    }
 }
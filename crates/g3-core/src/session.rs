@@ -3,16 +3,32 @@
 //! This module handles session ID generation, context window persistence,
 //! and session logging. It extracts the pure utility functions and I/O
 //! operations from the Agent, keeping the Agent as a thin orchestrator.
+//!
+//! Session files carry a schema version and a checksum of the conversation
+//! history so that restores can detect corrupt or outdated files (see
+//! [`validate_session_log`]).
 
+use crate::compressed_file;
 use crate::context_window::ContextWindow;
-use crate::paths::{ensure_session_dir, get_context_summary_file, get_g3_dir, get_session_file};
-use g3_providers::MessageRole;
+use crate::paths::{
+    ensure_session_dir, get_autosave_dir, get_context_summary_file, get_g3_dir, get_session_file,
+};
+use anyhow::{bail, Result};
+use g3_providers::{Message, MessageKind, MessageRole, StoredBody};
+use sha2::{Digest, Sha256};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tracing::{debug, error};
 
+/// Schema version written to `session.json`.
+///
+/// Version 1 files (written before versioning existed) have no
+/// `schema_version` or `checksum` field.
+pub const SESSION_SCHEMA_VERSION: u32 = 2;
+
 /// Format token count in compact form (e.g., 1K, 2M, 100b, 200K)
 /// Clamps to 4 chars right-aligned.
 pub fn format_token_count(tokens: u32) -> String {
@@ -119,32 +135,234 @@ pub fn save_context_window(
         get_session_file(&anonymous_id)
     };
 
+    match session_json(session_id, context_window, status, timestamp) {
+        Ok(json_content) => {
+            let level = context_window.compression_level;
+            if let Err(e) = compressed_file::write(&filename, json_content.as_bytes(), level) {
+                error!("Failed to save context window to {:?}: {}", &filename, e);
+            }
+        }
+        Err(e) => {
+            error!("Failed to serialize context window: {}", e);
+        }
+    }
+}
+
+/// The content of `session.json` for a context window.
+pub(crate) fn session_json(
+    session_id: Option<&str>,
+    context_window: &ContextWindow,
+    status: &str,
+    timestamp: u64,
+) -> serde_json::Result<String> {
+    let history = serde_json::to_value(&context_window.conversation_history)?;
     let context_data = serde_json::json!({
+        "schema_version": SESSION_SCHEMA_VERSION,
         "session_id": session_id,
         "timestamp": timestamp,
         "status": status,
+        "checksum": history_checksum(&history),
         "context_window": {
             "used_tokens": context_window.used_tokens,
             "total_tokens": context_window.total_tokens,
             "percentage_used": context_window.percentage_used(),
-            "conversation_history": context_window.conversation_history
+            "conversation_history": history
         }
     });
+    serde_json::to_string_pretty(&context_data)
+}
 
-    match serde_json::to_string_pretty(&context_data) {
-        Ok(json_content) => {
-            if let Err(e) = std::fs::write(&filename, &json_content) {
-                error!("Failed to save context window to {:?}: {}", &filename, e);
-            }
+/// Checkpoint the context window in the middle of a turn.
+///
+/// The checkpoint replaces `session.json` (with status "in_progress", so the
+/// session can be resumed after a crash) and is kept as
+/// `autosave/checkpoint-<millis>.json`. Both are written atomically, and
+/// only the newest `keep` checkpoints are kept, so a write interrupted by a
+/// crash still leaves an intact earlier one.
+pub fn checkpoint_context_window(
+    session_id: &str,
+    context_window: &ContextWindow,
+    keep: usize,
+) -> Result<PathBuf> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let json = session_json(
+        Some(session_id),
+        context_window,
+        "in_progress",
+        now.as_secs(),
+    )?;
+
+    let dir = get_autosave_dir(session_id);
+    std::fs::create_dir_all(&dir)?;
+    let level = context_window.compression_level;
+    let checkpoint = dir.join(format!("checkpoint-{:013}.json", now.as_millis()));
+    let checkpoint = compressed_file::write(&checkpoint, json.as_bytes(), level)?;
+    compressed_file::write(&get_session_file(session_id), json.as_bytes(), level)?;
+
+    let checkpoints = list_checkpoints(session_id);
+    for old in &checkpoints[..checkpoints.len().saturating_sub(keep.max(1))] {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(checkpoint)
+}
+
+/// The session's autosave checkpoints, oldest first.
+pub fn list_checkpoints(session_id: &str) -> Vec<PathBuf> {
+    let mut checkpoints: Vec<PathBuf> = std::fs::read_dir(get_autosave_dir(session_id))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| {
+                    p.file_name().and_then(|n| n.to_str()).is_some_and(|n| {
+                        n.starts_with("checkpoint-")
+                            && (n.ends_with(".json") || n.ends_with(".json.zst"))
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    checkpoints.sort();
+    checkpoints
+}
+
+/// Periodic checkpoints of the context window during a turn.
+///
+/// The session is saved when a turn completes, fails or is cancelled; a long
+/// autonomous turn could otherwise lose everything to a crash. After every
+/// `every_tool_calls` tool calls, or the first tool call once `interval` has
+/// passed since the last save, the context window is checkpointed with
+/// [`checkpoint_context_window`].
+#[derive(Debug)]
+pub struct Autosave {
+    enabled: bool,
+    every_tool_calls: u32,
+    interval: Option<Duration>,
+    keep: usize,
+    state: Mutex<AutosaveState>,
+}
+
+#[derive(Debug)]
+struct AutosaveState {
+    /// Tool calls since the last save
+    tool_calls: u32,
+    last_save: Instant,
+}
+
+impl Autosave {
+    pub fn new(config: &g3_config::AutosaveConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            every_tool_calls: config.every_tool_calls,
+            interval: (config.interval_secs > 0).then(|| Duration::from_secs(config.interval_secs)),
+            keep: config.keep,
+            state: Mutex::new(AutosaveState {
+                tool_calls: 0,
+                last_save: Instant::now(),
+            }),
         }
-        Err(e) => {
-            error!("Failed to serialize context window: {}", e);
+    }
+
+    /// Count a finished tool call and checkpoint if one is due.
+    pub fn tool_call_finished(&self, session_id: &str, context_window: &ContextWindow) {
+        if !self.enabled {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.tool_calls += 1;
+        let due = (self.every_tool_calls > 0 && state.tool_calls >= self.every_tool_calls)
+            || self
+                .interval
+                .is_some_and(|interval| state.last_save.elapsed() >= interval);
+        if !due {
+            return;
+        }
+        match checkpoint_context_window(session_id, context_window, self.keep) {
+            Ok(path) => debug!("Checkpointed context window to {:?}", path),
+            Err(e) => error!("Failed to checkpoint context window: {}", e),
+        }
+        *state = AutosaveState {
+            tool_calls: 0,
+            last_save: Instant::now(),
+        };
+    }
+
+    /// Restart the interval after the session was saved another way.
+    pub fn saved(&self) {
+        *self.state.lock().unwrap() = AutosaveState {
+            tool_calls: 0,
+            last_save: Instant::now(),
+        };
+    }
+}
+
+/// Debounced writer for the context window summary file.
+///
+/// The summary is refreshed before every LLM call. On large histories
+/// rewriting it each time adds per-turn latency and disk churn, so writes
+/// closer together than the configured interval are deferred until the next
+/// `flush` (at the end of a turn).
+#[derive(Debug)]
+pub struct ContextSummaryWriter {
+    enabled: bool,
+    min_interval: Duration,
+    state: Mutex<SummaryWriteState>,
+}
+
+#[derive(Debug, Default)]
+struct SummaryWriteState {
+    last_write: Option<Instant>,
+    /// A write was skipped since the last one
+    pending: bool,
+}
+
+impl ContextSummaryWriter {
+    pub fn new(config: &g3_config::ContextSummaryConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            min_interval: Duration::from_millis(config.min_interval_ms),
+            state: Mutex::new(SummaryWriteState::default()),
+        }
+    }
+
+    /// Write the summary unless one was written within the minimum interval.
+    pub fn write(&self, session_id: &str, context_window: &ContextWindow) {
+        if !self.enabled {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state
+            .last_write
+            .is_some_and(|last| last.elapsed() < self.min_interval)
+        {
+            state.pending = true;
+            return;
+        }
+        write_context_window_summary(session_id, context_window);
+        state.last_write = Some(Instant::now());
+        state.pending = false;
+    }
+
+    /// Write the summary if a write was deferred since the last one.
+    pub fn flush(&self, session_id: &str, context_window: &ContextWindow) {
+        if !self.enabled {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.pending {
+            write_context_window_summary(session_id, context_window);
+            state.last_write = Some(Instant::now());
+            state.pending = false;
         }
     }
 }
 
 /// Write a human-readable context window summary to file.
 ///
+/// The file is replaced atomically, and the `current_context_window` symlink
+/// is only recreated when it points elsewhere.
+///
 /// Format: message_id, role, token_count, indicator, first_120_chars
 pub fn write_context_window_summary(session_id: &str, context_window: &ContextWindow) {
     // Ensure session directory exists
@@ -157,16 +375,10 @@ pub fn write_context_window_summary(session_id: &str, context_window: &ContextWi
     let symlink_path = get_g3_dir().join("sessions").join("current_context_window");
 
     // Build the summary content
+    let report = context_window.report();
     let mut summary_lines = Vec::new();
 
-    for message in &context_window.conversation_history {
-        // Estimate tokens for this message
-        let message_tokens = ContextWindow::estimate_tokens(&message.content);
-
-        // Format token count and get indicator
-        let token_str = format_token_count(message_tokens);
-        let indicator = token_indicator(message_tokens);
-
+    for message in &report.messages {
         // Get role as string
         let role = match message.role {
             MessageRole::System => "sys",
@@ -174,35 +386,28 @@ pub fn write_context_window_summary(session_id: &str, context_window: &ContextWi
             MessageRole::Assistant => "ass",
         };
 
-        // Get first 120 characters of content, replace newlines
-        let content_preview: String = message
-            .content
-            .chars()
-            .take(120)
-            .collect::<String>()
-            .replace('\n', " ")
-            .replace('\r', " ");
-
         let line = format!(
             "{}, {}, {} {}, {}\n",
-            message.id, role, token_str, indicator, content_preview
+            message.id,
+            role,
+            format_token_count(message.tokens),
+            token_indicator(message.tokens),
+            message.preview
         );
         summary_lines.push(line);
     }
 
     // Add total estimate
-    let total_token_str = format_token_count(context_window.used_tokens);
-    let capacity_str = format_token_count(context_window.total_tokens);
-    let percentage = context_window.percentage_used();
-
     summary_lines.push(format!(
         "\n--- TOTAL: {} / {} ({:.1}%) ---\n",
-        total_token_str, capacity_str, percentage
+        format_token_count(report.used_tokens),
+        format_token_count(report.total_tokens),
+        report.percentage_used
     ));
 
     // Write to file
     let summary_content = summary_lines.join("");
-    if let Err(e) = std::fs::write(&filename, &summary_content) {
+    if let Err(e) = write_atomically(&filename, &summary_content) {
         error!(
             "Failed to write context window summary to {:?}: {}",
             &filename, e
@@ -210,23 +415,10 @@ pub fn write_context_window_summary(session_id: &str, context_window: &ContextWi
         return;
     }
 
-    // Update symlink
-    let _ = std::fs::remove_file(&symlink_path);
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::symlink;
-        let target = format!("context_window_{}.txt", session_id);
-        if let Err(e) = symlink(&target, &symlink_path) {
-            error!("Failed to create symlink {:?}: {}", &symlink_path, e);
-        }
-    }
-
-    #[cfg(windows)]
-    {
-        use std::os::windows::fs::symlink_file;
-        let target = format!("context_window_{}.txt", session_id);
-        if let Err(e) = symlink_file(&target, &symlink_path) {
+    // Point the symlink at this session's summary (relative to the sessions dir)
+    let target = Path::new(session_id).join("context_summary.txt");
+    if std::fs::read_link(&symlink_path).ok().as_deref() != Some(target.as_path()) {
+        if let Err(e) = replace_symlink(&target, &symlink_path) {
             error!("Failed to create symlink {:?}: {}", &symlink_path, e);
         }
     }
@@ -238,14 +430,36 @@ pub fn write_context_window_summary(session_id: &str, context_window: &ContextWi
     );
 }
 
+/// Write `content` to a temporary file next to `path`, then rename it into place.
+fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Create (or replace) a symlink at `link` pointing to `target`.
+fn replace_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    let tmp = link.with_extension("tmp");
+    let _ = std::fs::remove_file(&tmp);
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(target, &tmp)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_file(target, &tmp)?;
+
+    std::fs::rename(&tmp, link)
+}
+
 /// Log an error to the session JSON file.
 ///
-/// Appends an error entry to the conversation history in the session log.
+/// Appends an error entry to the conversation history in the session log,
+/// which is rewritten compressed at `compression_level` if given.
 pub fn log_error_to_session(
     session_id: &str,
     error: &anyhow::Error,
     role: &str,
     forensic_context: Option<String>,
+    compression_level: Option<i32>,
 ) {
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -256,8 +470,8 @@ pub fn log_error_to_session(
     let filename = get_session_file(session_id);
 
     // Read existing session log
-    let mut session_data: serde_json::Value = if filename.exists() {
-        match std::fs::read_to_string(&filename) {
+    let mut session_data: serde_json::Value = if compressed_file::exists(&filename) {
+        match compressed_file::read_to_string(&filename) {
             Ok(content) => serde_json::from_str(&content).unwrap_or_else(|_| serde_json::json!({})),
             Err(_) => serde_json::json!({}),
         }
@@ -281,6 +495,7 @@ pub fn log_error_to_session(
     });
 
     // Append to conversation history
+    let mut new_checksum = None;
     if let Some(history) = session_data
         .get_mut("context_window")
         .and_then(|cw| cw.get_mut("conversation_history"))
@@ -288,23 +503,29 @@ pub fn log_error_to_session(
         if let Some(history_array) = history.as_array_mut() {
             history_array.push(error_entry);
         }
+        new_checksum = Some(history_checksum(history));
+    }
+
+    // Keep the checksum in sync so the appended entry isn't flagged as corruption
+    if let (Some(checksum), Some(obj)) = (new_checksum, session_data.as_object_mut()) {
+        obj.insert("checksum".to_string(), serde_json::Value::String(checksum));
     }
 
     // Write back to file
     if let Ok(json_content) = serde_json::to_string_pretty(&session_data) {
-        let _ = std::fs::write(&filename, json_content);
+        let _ = compressed_file::write(&filename, json_content.as_bytes(), compression_level);
     }
 }
 
 /// Restore conversation history from a session log file.
 ///
 /// Returns the messages to add to the context window, or None if restoration failed.
-pub fn restore_from_session_log(session_log_path: &PathBuf) -> Option<Vec<(MessageRole, String)>> {
-    if !session_log_path.exists() {
+pub fn restore_from_session_log(session_log_path: &Path) -> Option<Vec<(MessageRole, String)>> {
+    if !compressed_file::exists(session_log_path) {
         return None;
     }
 
-    let json = std::fs::read_to_string(session_log_path).ok()?;
+    let json = compressed_file::read_to_string(session_log_path).ok()?;
     let session_data: serde_json::Value = serde_json::from_str(&json).ok()?;
 
     let context_window = session_data.get("context_window")?;
@@ -326,16 +547,315 @@ pub fn restore_from_session_log(session_log_path: &PathBuf) -> Option<Vec<(Messa
             _ => MessageRole::User,
         };
 
-        result.push((role, content.to_string()));
+        // Stored bodies are read back, falling back to the saved preview
+        let content = msg
+            .get("body")
+            .and_then(|b| b.get("path"))
+            .and_then(|p| p.as_str())
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_else(|| content.to_string());
+
+        result.push((role, content));
     }
 
     Some(result)
 }
 
+/// Compute the checksum of a serialized conversation history.
+///
+/// Hashes the canonical JSON form of the value so the result is the same
+/// whether it is computed before writing or after re-reading the file.
+pub fn history_checksum(history: &serde_json::Value) -> String {
+    let digest = Sha256::digest(history.to_string().as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Result of validating a saved session log before restoring it.
+#[derive(Debug, Default)]
+pub struct SessionValidation {
+    /// Schema version of the file (1 for files written before versioning)
+    pub schema_version: u32,
+    /// Restorable non-system messages, in order
+    pub messages: Vec<Message>,
+    /// Number of history entries that were dropped during recovery
+    pub dropped: usize,
+    /// Token count recomputed from the restorable messages
+    pub recounted_tokens: u32,
+    /// Human-readable description of each problem found
+    pub issues: Vec<String>,
+    /// Informational notes that don't indicate damage, such as a file
+    /// migrated from an older schema
+    pub notes: Vec<String>,
+}
+
+impl SessionValidation {
+    /// True if the session file passed every check without recovery.
+    pub fn is_intact(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Validate a parsed `session.json` and extract the messages that can be restored.
+///
+/// Fails outright only when nothing can be recovered: the history is missing
+/// or the file was written by a newer g3. Everything else (checksum mismatch,
+/// misplaced system prompt, malformed entries, orphaned tool results) is
+/// reported in [`SessionValidation::issues`] and the bad entries are dropped.
+/// Files from an older schema are migrated and noted in
+/// [`SessionValidation::notes`].
+pub fn validate_session_log(session_data: &serde_json::Value) -> Result<SessionValidation> {
+    let schema_version = session_data
+        .get("schema_version")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1) as u32;
+    if schema_version > SESSION_SCHEMA_VERSION {
+        bail!(
+            "Session file was written by a newer g3 (schema v{}, this build supports up to v{})",
+            schema_version,
+            SESSION_SCHEMA_VERSION
+        );
+    }
+
+    let history = match session_data
+        .get("context_window")
+        .and_then(|cw| cw.get("conversation_history"))
+    {
+        Some(history) if history.is_array() => history,
+        _ => bail!("Session file is missing its conversation history"),
+    };
+    let entries = history.as_array().map(Vec::as_slice).unwrap_or_default();
+
+    let mut validation = SessionValidation {
+        schema_version,
+        ..Default::default()
+    };
+
+    if schema_version < SESSION_SCHEMA_VERSION {
+        validation.notes.push(format!(
+            "session file is from an older g3 (schema v{}) and has no checksum to verify",
+            schema_version
+        ));
+    } else {
+        match session_data.get("checksum").and_then(|c| c.as_str()) {
+            Some(expected) if expected == history_checksum(history) => {}
+            Some(_) => validation
+                .issues
+                .push("checksum mismatch: history was modified or truncated".to_string()),
+            None => validation.issues.push("checksum is missing".to_string()),
+        }
+    }
+
+    let first_role = entries
+        .first()
+        .and_then(|m| m.get("role"))
+        .and_then(|r| r.as_str());
+    if !entries.is_empty() && first_role != Some("system") {
+        validation
+            .issues
+            .push("system prompt is not the first message".to_string());
+    }
+
+    collect_restorable_messages(entries, &mut validation);
+
+    validation.recounted_tokens = validation
+        .messages
+        .iter()
+        .map(ContextWindow::message_tokens)
+        .sum();
+
+    Ok(validation)
+}
+
+/// Convert history entries into messages, dropping malformed entries and
+/// tool results that no longer follow an assistant tool call.
+fn collect_restorable_messages(entries: &[serde_json::Value], validation: &mut SessionValidation) {
+    let mut malformed = 0;
+    let mut orphaned = 0;
+
+    for entry in entries {
+        let role = match entry.get("role").and_then(|r| r.as_str()) {
+            Some("system") => continue, // System messages are preserved separately
+            Some("assistant") => MessageRole::Assistant,
+            Some("user") => MessageRole::User,
+            _ => {
+                malformed += 1;
+                continue;
+            }
+        };
+        let content = match entry.get("content").and_then(|c| c.as_str()) {
+            Some(content) if !content.trim().is_empty() => content,
+            _ => {
+                malformed += 1;
+                continue;
+            }
+        };
+
+        let follows_assistant = matches!(
+            validation.messages.last().map(|m| &m.role),
+            Some(MessageRole::Assistant)
+        );
+        if matches!(role, MessageRole::User)
+            && content.starts_with("Tool result:")
+            && !follows_assistant
+        {
+            orphaned += 1;
+            continue;
+        }
+
+        // A stored body whose file is gone is restored as its preview
+        let stored_body = entry
+            .get("body")
+            .and_then(|b| serde_json::from_value::<StoredBody>(b.clone()).ok())
+            .filter(|b| b.path.is_file())
+            .map(Arc::new);
+
+        validation.messages.push(Message {
+            role,
+            id: String::new(),
+            images: Vec::new(),
+            content: content.to_string(),
+            kind: MessageKind::Regular,
+            cache_control: None,
+            stored_body,
+        });
+    }
+
+    if malformed > 0 {
+        validation
+            .issues
+            .push(format!("{} malformed message(s) dropped", malformed));
+    }
+    if orphaned > 0 {
+        validation.issues.push(format!(
+            "{} tool result(s) without a matching tool call dropped",
+            orphaned
+        ));
+    }
+    validation.dropped = malformed + orphaned;
+}
+
+/// What `Agent::resume` restored from a saved session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResumePreview {
+    pub session_id: String,
+    pub description: Option<String>,
+    /// Messages restored into the context window
+    pub messages: usize,
+    /// Earlier turns left out because only the last turns were asked for
+    pub skipped_turns: usize,
+    /// Tokens in the context window after restoring
+    pub tokens: u32,
+    /// The session's TODO list, if it has one
+    pub todo: Option<String>,
+    /// False if the conversation couldn't be restored, only its summary
+    pub full_context: bool,
+}
+
+impl ResumePreview {
+    /// A few lines describing what is restored.
+    pub fn format(&self) -> String {
+        let mut text = format!("🔄 Resuming session {}", self.session_id);
+        if let Some(description) = &self.description {
+            text.push_str(&format!(": '{}'", description));
+        }
+        if self.full_context {
+            text.push_str(&format!("\n   {} messages", self.messages));
+            if self.skipped_turns > 0 {
+                text.push_str(&format!(" (skipping {} earlier turns)", self.skipped_turns));
+            }
+        } else {
+            text.push_str("\n   Conversation could not be restored; continuing from its summary");
+        }
+        text.push_str(&format!(", {} tokens in context", self.tokens));
+        if let Some(todo) = &self.todo {
+            let open = todo.matches("- [ ]").count();
+            let done = todo.matches("- [x]").count() + todo.matches("- [X]").count();
+            text.push_str(&format!("\n   TODO: {} open, {} done", open, done));
+        }
+        text
+    }
+}
+
+/// The last `turns` turns of `messages`, and how many turns were left out.
+/// A turn starts at a user message that isn't a tool result.
+pub fn last_turns(mut messages: Vec<Message>, turns: usize) -> (Vec<Message>, usize) {
+    let starts: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| {
+            matches!(m.role, MessageRole::User) && !m.content.starts_with("Tool result:")
+        })
+        .map(|(i, _)| i)
+        .collect();
+    if starts.len() <= turns {
+        return (messages, 0);
+    }
+    let first_kept = if turns == 0 {
+        messages.len()
+    } else {
+        starts[starts.len() - turns]
+    };
+    (messages.split_off(first_kept), starts.len() - turns)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_and_symlink_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("context_summary.txt");
+        write_atomically(&file, "one").unwrap();
+        write_atomically(&file, "two").unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "two");
+        assert!(!file.with_extension("tmp").exists());
+
+        let link = dir.path().join("current_context_window");
+        replace_symlink(Path::new("a.txt"), &link).unwrap();
+        replace_symlink(Path::new("context_summary.txt"), &link).unwrap();
+        assert_eq!(
+            std::fs::read_link(&link).unwrap(),
+            Path::new("context_summary.txt")
+        );
+        assert_eq!(std::fs::read_to_string(&link).unwrap(), "two");
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_checkpoints_rotate() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let old_dir = std::env::current_dir().unwrap();
+        std::env::set_current_dir(temp_dir.path()).unwrap();
+        let mut context = ContextWindow::new(10_000);
+        context.add_message(Message::new(
+            MessageRole::System,
+            "You are g3".to_string(),
+        ));
+        context.add_message(Message::new(
+            MessageRole::User,
+            "Task: long run".to_string(),
+        ));
+
+        for _ in 0..4 {
+            checkpoint_context_window("autosave_test", &context, 2).unwrap();
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        let checkpoints = list_checkpoints("autosave_test");
+        let session: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(get_session_file("autosave_test")).unwrap(),
+        )
+        .unwrap();
+        std::env::set_current_dir(old_dir).unwrap();
+
+        assert_eq!(checkpoints.len(), 2);
+        assert_eq!(session["status"], "in_progress");
+        let validation = validate_session_log(&session).unwrap();
+        assert!(validation.is_intact());
+        assert_eq!(validation.messages.len(), 1);
+    }
+
     #[test]
     fn test_format_token_count_small() {
         assert_eq!(format_token_count(0), "  0K");
@@ -391,4 +911,126 @@ mod tests {
         let id2 = generate_session_id("test", None);
         assert_ne!(id1, id2);
     }
+
+    fn session_json(history: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "schema_version": SESSION_SCHEMA_VERSION,
+            "checksum": history_checksum(&history),
+            "context_window": { "conversation_history": history }
+        })
+    }
+
+    #[test]
+    fn test_validate_intact_session() {
+        let data = session_json(serde_json::json!([
+            {"role": "system", "content": "You are g3"},
+            {"role": "user", "content": "hello"},
+            {"role": "assistant", "content": "{\"tool\": \"shell\", \"args\": {}}"},
+            {"role": "user", "content": "Tool result: ok"}
+        ]));
+
+        let validation = validate_session_log(&data).unwrap();
+        assert!(validation.is_intact(), "issues: {:?}", validation.issues);
+        assert_eq!(validation.messages.len(), 3);
+        assert_eq!(validation.dropped, 0);
+        assert!(validation.recounted_tokens > 0);
+    }
+
+    #[test]
+    fn test_validate_detects_checksum_mismatch() {
+        let mut data = session_json(serde_json::json!([
+            {"role": "system", "content": "You are g3"},
+            {"role": "user", "content": "hello"}
+        ]));
+        data["context_window"]["conversation_history"][1]["content"] =
+            serde_json::json!("tampered");
+
+        let validation = validate_session_log(&data).unwrap();
+        assert!(!validation.is_intact());
+        assert!(validation.issues[0].contains("checksum mismatch"));
+        assert_eq!(validation.messages.len(), 1);
+    }
+
+    #[test]
+    fn test_validate_partial_recovery() {
+        let data = session_json(serde_json::json!([
+            {"role": "user", "content": "Tool result: orphaned"},
+            {"role": "user", "content": "hello"},
+            {"role": "wizard", "content": "??"},
+            {"role": "assistant"}
+        ]));
+
+        let validation = validate_session_log(&data).unwrap();
+        assert_eq!(validation.messages.len(), 1);
+        assert_eq!(validation.dropped, 3);
+        assert!(validation
+            .issues
+            .iter()
+            .any(|i| i.contains("system prompt is not the first message")));
+    }
+
+    #[test]
+    fn test_validate_restores_stored_bodies() {
+        let path = std::env::temp_dir().join(format!("g3-session-body-{}.txt", std::process::id()));
+        std::fs::write(&path, "full body").unwrap();
+        let data = session_json(serde_json::json!([
+            {"role": "system", "content": "You are g3"},
+            {"role": "user", "content": "preview", "body": {"path": path, "len": 9}},
+            {"role": "user", "content": "gone", "body": {"path": "/nonexistent/body.txt", "len": 9}}
+        ]));
+
+        let validation = validate_session_log(&data).unwrap();
+        assert_eq!(validation.messages[1].full_content(), "full body");
+        assert!(validation.messages[2].stored_body.is_none());
+        assert_eq!(validation.messages[2].full_content(), "gone");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_last_turns() {
+        let message = |role, content: &str| Message::new(role, content.to_string());
+        let messages = vec![
+            message(MessageRole::User, "Task: fix the build"),
+            message(MessageRole::Assistant, "{\"tool\": \"shell\"}"),
+            message(MessageRole::User, "Tool result: ok"),
+            message(MessageRole::Assistant, "Fixed."),
+            message(MessageRole::User, "Now add a test"),
+            message(MessageRole::Assistant, "Added."),
+        ];
+
+        let (kept, skipped) = last_turns(messages.clone(), 1);
+        assert_eq!(skipped, 1);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].content, "Now add a test");
+
+        let (kept, skipped) = last_turns(messages.clone(), 5);
+        assert_eq!((kept.len(), skipped), (6, 0));
+        let (kept, skipped) = last_turns(messages, 0);
+        assert_eq!((kept.len(), skipped), (0, 2));
+    }
+
+    #[test]
+    fn test_validate_legacy_and_future_versions() {
+        let legacy = serde_json::json!({
+            "context_window": { "conversation_history": [
+                {"role": "system", "content": "You are g3"},
+                {"role": "user", "content": "hi"}
+            ] }
+        });
+        let validation = validate_session_log(&legacy).unwrap();
+        assert_eq!(validation.schema_version, 1);
+        assert!(validation.is_intact(), "issues: {:?}", validation.issues);
+        assert!(validation.notes.iter().any(|n| n.contains("older g3")));
+        assert_eq!(validation.messages.len(), 1);
+
+        let future = serde_json::json!({
+            "schema_version": SESSION_SCHEMA_VERSION + 1,
+            "context_window": { "conversation_history": [] }
+        });
+        assert!(validate_session_log(&future).is_err());
+
+        let missing = serde_json::json!({ "schema_version": SESSION_SCHEMA_VERSION });
+        assert!(validate_session_log(&missing).is_err());
+    }
 }
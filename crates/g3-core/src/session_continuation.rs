@@ -13,6 +13,8 @@ use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use tracing::{debug, error, warn};
 
+use crate::compressed_file;
+
 /// Version of the session continuation format
 const CONTINUATION_VERSION: &str = "1.0";
 
@@ -221,6 +223,17 @@ pub fn load_continuation() -> Result<Option<SessionContinuation>> {
     Ok(Some(continuation))
 }
 
+/// Load the continuation artifact saved in session `session_id`'s directory,
+/// whether or not it is the current session
+pub fn load_session_continuation(session_id: &str) -> Result<Option<SessionContinuation>> {
+    let latest_path = get_session_path(session_id).join(CONTINUATION_FILENAME);
+    if !latest_path.exists() {
+        return Ok(None);
+    }
+    let json = std::fs::read_to_string(&latest_path)?;
+    Ok(Some(serde_json::from_str(&json)?))
+}
+
 /// Clear the session continuation symlink (for /clear command)
 /// This only removes the symlink, not the actual session data
 pub fn clear_continuation() -> Result<()> {
@@ -253,7 +266,7 @@ pub fn has_valid_continuation() -> bool {
         Ok(Some(continuation)) => {
             // Check if the session log still exists
             let session_log_path = PathBuf::from(&continuation.session_log_path);
-            if !session_log_path.exists() {
+            if !compressed_file::exists(&session_log_path) {
                 warn!("Session log no longer exists: {:?}", session_log_path);
                 return false;
             }
@@ -283,11 +296,11 @@ pub fn has_valid_continuation() -> bool {
 
 /// Load the full context window from a session log file
 pub fn load_context_from_session_log(session_log_path: &Path) -> Result<Option<serde_json::Value>> {
-    if !session_log_path.exists() {
+    if !compressed_file::exists(session_log_path) {
         return Ok(None);
     }
     
-    let json = std::fs::read_to_string(session_log_path)?;
+    let json = compressed_file::read_to_string(session_log_path)?;
     let session_data: serde_json::Value = serde_json::from_str(&json)?;
     
     Ok(Some(session_data))
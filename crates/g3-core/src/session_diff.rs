@@ -0,0 +1,281 @@
+//! What a session did between two of its checkpoints.
+//!
+//! Autosave checkpoints (see `session::checkpoint_context_window`) snapshot
+//! a session's conversation during long turns. `SessionDiff::between`
+//! compares two snapshots of the same session (checkpoints, or a checkpoint
+//! and the final `session.json`): the messages added in between, the files
+//! the tool calls among them modified and how much the context grew. This
+//! is what a reviewer needs to see what an autonomous agent did overnight.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use g3_providers::{Message, MessageRole};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::compressed_file;
+use crate::paths::get_session_file;
+use crate::session::{list_checkpoints, validate_session_log};
+use crate::task_result::files_modified_by;
+use crate::transcript_export::split_tool_call;
+
+/// The changes between two snapshots of a session.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionDiff {
+    pub session_id: Option<String>,
+    /// When the earlier snapshot was taken
+    pub from: Option<DateTime<Utc>>,
+    /// When the later snapshot was taken
+    pub to: Option<DateTime<Utc>>,
+    /// Messages of the later snapshot that follow the earlier one's
+    pub messages_added: Vec<Message>,
+    /// Tool calls among the added messages
+    pub tool_calls: usize,
+    /// Files modified by the added tool calls that succeeded, in
+    /// first-modified order. Changes made through the shell are not included.
+    pub files_modified: Vec<String>,
+    /// Context tokens used in the earlier snapshot
+    pub tokens_before: u32,
+    /// Context tokens used in the later snapshot
+    pub tokens_after: u32,
+}
+
+impl SessionDiff {
+    /// Compare the snapshots at `from` and `to`, which must be of the same
+    /// session.
+    pub fn between(from: &Path, to: &Path) -> Result<Self> {
+        let (from, to) = (Snapshot::load(from)?, Snapshot::load(to)?);
+        if from.session_id != to.session_id {
+            bail!(
+                "Snapshots are of different sessions ({} and {})",
+                from.session_id.as_deref().unwrap_or("none"),
+                to.session_id.as_deref().unwrap_or("none")
+            );
+        }
+
+        let messages_added = to.messages[first_added(&from.messages, &to.messages)..].to_vec();
+        let mut tool_calls = 0;
+        let mut files_modified: Vec<String> = Vec::new();
+        for (i, message) in messages_added.iter().enumerate() {
+            if !matches!(message.role, MessageRole::Assistant) {
+                continue;
+            }
+            let Some((tool, args)) = split_tool_call(&message.content).1 else {
+                continue;
+            };
+            tool_calls += 1;
+            // Calls whose result isn't in the later snapshot yet don't count
+            let succeeded = messages_added.get(i + 1).is_some_and(|m| {
+                m.content.starts_with("Tool result:") && !m.content.contains('❌')
+            });
+            if !succeeded {
+                continue;
+            }
+            for path in files_modified_by(&tool, &args) {
+                if !files_modified.iter().any(|f| f == path) {
+                    files_modified.push(path.to_string());
+                }
+            }
+        }
+
+        Ok(Self {
+            session_id: to.session_id,
+            from: from.taken,
+            to: to.taken,
+            messages_added,
+            tool_calls,
+            files_modified,
+            tokens_before: from.used_tokens,
+            tokens_after: to.used_tokens,
+        })
+    }
+
+    /// Compare checkpoint `index` of session `session_id` (in the order of
+    /// `session::list_checkpoints`, oldest first) with its `session.json`.
+    pub fn since_checkpoint(session_id: &str, index: usize) -> Result<Self> {
+        let checkpoints = list_checkpoints(session_id);
+        let Some(checkpoint) = checkpoints.get(index) else {
+            bail!(
+                "Session {} has {} checkpoints, no checkpoint {}",
+                session_id,
+                checkpoints.len(),
+                index
+            );
+        };
+        Self::between(checkpoint, &get_session_file(session_id))
+    }
+
+    /// How many context tokens the session grew by (negative if compaction
+    /// or thinning shrank it).
+    pub fn token_growth(&self) -> i64 {
+        self.tokens_after as i64 - self.tokens_before as i64
+    }
+
+    /// A short summary for the terminal.
+    pub fn format(&self) -> String {
+        let time = |t: Option<DateTime<Utc>>| {
+            t.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "?".to_string())
+        };
+        let mut out = format!(
+            "Session {}: {} → {}\n",
+            self.session_id.as_deref().unwrap_or("(anonymous)"),
+            time(self.from),
+            time(self.to)
+        );
+        out.push_str(&format!(
+            "  {} messages added, {} tool calls\n",
+            self.messages_added.len(),
+            self.tool_calls
+        ));
+        out.push_str(&format!(
+            "  Context: {} → {} tokens ({:+})\n",
+            self.tokens_before,
+            self.tokens_after,
+            self.token_growth()
+        ));
+        if self.files_modified.is_empty() {
+            out.push_str("  No files modified\n");
+        } else {
+            out.push_str(&format!(
+                "  Files modified ({}):\n",
+                self.files_modified.len()
+            ));
+            for file in &self.files_modified {
+                out.push_str(&format!("    {}\n", file));
+            }
+        }
+        out
+    }
+}
+
+/// A saved session file.
+struct Snapshot {
+    session_id: Option<String>,
+    taken: Option<DateTime<Utc>>,
+    messages: Vec<Message>,
+    used_tokens: u32,
+}
+
+impl Snapshot {
+    fn load(path: &Path) -> Result<Self> {
+        let json = compressed_file::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        let data: serde_json::Value = serde_json::from_str(&json)
+            .with_context(|| format!("{} is not valid JSON", path.display()))?;
+        Ok(Self {
+            session_id: data["session_id"].as_str().map(str::to_string),
+            taken: data["timestamp"]
+                .as_i64()
+                .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+            used_tokens: data["context_window"]["used_tokens"].as_u64().unwrap_or(0) as u32,
+            messages: validate_session_log(&data)?.messages,
+        })
+    }
+}
+
+/// Index in `after` of the first message added since `before`: the one
+/// following the last message of `before` still found in `after`.
+///
+/// Thinning and compaction rewrite older messages (and compaction only
+/// shortens the history), so earlier messages of `before` are tried in turn,
+/// at or before their old position. If none is left, everything is new.
+fn first_added(before: &[Message], after: &[Message]) -> usize {
+    for (i, message) in before.iter().enumerate().rev() {
+        let found = after[..after.len().min(i + 1)].iter().rposition(|m| {
+            std::mem::discriminant(&m.role) == std::mem::discriminant(&message.role)
+                && m.content == message.content
+        });
+        if let Some(at) = found {
+            return at + 1;
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context_window::ContextWindow;
+    use crate::session::checkpoint_context_window;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_diff_between_checkpoints() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old_dir = std::env::current_dir().unwrap();
+        std::env::set_current_dir(temp_dir.path()).unwrap();
+
+        let mut context = ContextWindow::new(200_000);
+        context.add_message(Message::new(MessageRole::System, "You are g3".to_string()));
+        context.add_message(Message::new(
+            MessageRole::User,
+            "Task: Fix the parser overnight".to_string(),
+        ));
+        let before = checkpoint_context_window("night_run", &context, 5).unwrap();
+
+        let calls = [
+            (
+                r#"{"tool": "write_file", "args": {"file_path": "src/parser.rs", "content": "fn parse() {}"}}"#,
+                "✅ Wrote src/parser.rs",
+            ),
+            (
+                r#"{"tool": "str_replace", "args": {"file_path": "src/lib.rs", "diff": "-a\n+b"}}"#,
+                "❌ Pattern not found",
+            ),
+            (
+                r#"{"tool": "shell", "args": {"command": "cargo test"}}"#,
+                "test result: ok",
+            ),
+        ];
+        for (call, result) in calls {
+            context.add_message(Message::new(
+                MessageRole::Assistant,
+                format!("Working on it.\n\n{}", call),
+            ));
+            context.add_message(Message::new(
+                MessageRole::User,
+                format!("Tool result: {}", result),
+            ));
+        }
+        // Checkpoints are named by the millisecond
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        checkpoint_context_window("night_run", &context, 5).unwrap();
+
+        let diff = SessionDiff::between(&before, &get_session_file("night_run"));
+        let since = SessionDiff::since_checkpoint("night_run", 0);
+        let missing = SessionDiff::since_checkpoint("night_run", 9).is_err();
+        std::env::set_current_dir(old_dir).unwrap();
+
+        let diff = diff.unwrap();
+        assert_eq!(diff.session_id.as_deref(), Some("night_run"));
+        assert_eq!(diff.messages_added.len(), 6);
+        assert_eq!(diff.tool_calls, 3);
+        // The failed str_replace and the shell command don't count
+        assert_eq!(diff.files_modified, vec!["src/parser.rs"]);
+        assert!(diff.token_growth() > 0);
+        assert!(diff.format().contains("6 messages added, 3 tool calls"));
+        assert_eq!(since.unwrap().messages_added.len(), 6);
+        assert!(missing);
+    }
+
+    #[test]
+    fn test_first_added_survives_thinning() {
+        let message = |role, text: &str| Message::new(role, text.to_string());
+        let before = vec![
+            message(MessageRole::User, "Task: one"),
+            message(MessageRole::User, "Tool result: a very long result"),
+            message(MessageRole::Assistant, "Done with step one"),
+        ];
+        let after = vec![
+            message(MessageRole::User, "Task: one"),
+            message(MessageRole::User, "Tool result saved to thinned/x.txt"),
+            message(MessageRole::Assistant, "Done with step one"),
+            message(MessageRole::Assistant, "Step two"),
+        ];
+        assert_eq!(first_added(&before, &after), 3);
+        assert_eq!(first_added(&before, &before), 3);
+        assert_eq!(first_added(&[], &after), 0);
+    }
+}
@@ -0,0 +1,583 @@
+//! Importing conversations from other coding agents as g3 sessions.
+//!
+//! Users switching to g3 in the middle of a task can bring the conversation
+//! along instead of starting over. A Claude Code session log (the `.jsonl`
+//! files under `~/.claude/projects/`), an Aider chat history
+//! (`.aider.chat.history.md`) or a Cursor chat exported as Markdown is
+//! converted into g3 messages: prose stays as it is, tool calls become
+//! `{"tool": ..., "args": ...}` calls followed by their `Tool result:`, and
+//! the first user message becomes the task. The tools keep their original
+//! names and arguments, so the model sees what was done even where g3 would
+//! have used a different tool.
+//!
+//! The result is saved like any other session (`session.json` and
+//! `latest.json` under `.g3/sessions/<id>/`) and made the current session,
+//! so `Agent::switch_to_session` resumes it.
+
+use anyhow::{bail, Context, Result};
+use g3_providers::{Message, MessageRole};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::compressed_file;
+use crate::context_window::ContextWindow;
+use crate::paths::{ensure_session_dir, get_session_file};
+use crate::session::{generate_session_id, session_json};
+use crate::session_continuation::{save_continuation, SessionContinuation};
+use crate::transcript_export::split_tool_call;
+use crate::utils::truncate_to_word_boundary;
+
+/// The agents whose transcripts can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// A Claude Code session log: one JSON record per line
+    ClaudeCode,
+    /// An Aider chat history: `#### ` lines are the user's
+    Aider,
+    /// A Cursor chat exported as Markdown, one section per speaker
+    Cursor,
+}
+
+impl ImportFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ClaudeCode => "Claude Code",
+            Self::Aider => "Aider",
+            Self::Cursor => "Cursor",
+        }
+    }
+
+    /// Guess the format of the transcript at `path` with `content`.
+    pub fn detect(path: &Path, content: &str) -> Option<Self> {
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if path.extension().is_some_and(|e| e == "jsonl") {
+            Some(Self::ClaudeCode)
+        } else if file_name.contains(".aider.chat.history")
+            || content
+                .lines()
+                .any(|l| l.starts_with("# aider chat started"))
+        {
+            Some(Self::Aider)
+        } else if content.lines().any(|l| cursor_speaker(l).is_some()) {
+            Some(Self::Cursor)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "claude-code" | "claude" => Ok(Self::ClaudeCode),
+            "aider" => Ok(Self::Aider),
+            "cursor" => Ok(Self::Cursor),
+            _ => bail!(
+                "Unknown transcript format '{}' (expected claude-code, aider or cursor)",
+                s
+            ),
+        }
+    }
+}
+
+/// A transcript saved as a g3 session.
+#[derive(Debug, Clone)]
+pub struct ImportedSession {
+    pub format: ImportFormat,
+    /// Messages in the new session, not counting the system message
+    pub messages: usize,
+    /// The continuation to resume the session from
+    pub continuation: SessionContinuation,
+}
+
+/// Import the transcript at `path` as a new session and make it the current
+/// one. The format is detected unless given. `total_tokens` is the context
+/// window size of the agent that will resume it.
+pub fn import_transcript(
+    path: &Path,
+    format: Option<ImportFormat>,
+    total_tokens: u32,
+) -> Result<ImportedSession> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let Some(format) = format.or_else(|| ImportFormat::detect(path, &content)) else {
+        bail!(
+            "Cannot tell which agent wrote {}; give the format (claude-code, aider or cursor)",
+            path.display()
+        );
+    };
+    let messages = convert(format, &content)?;
+    let Some(task) = messages
+        .iter()
+        .find(|m| matches!(m.role, MessageRole::User))
+        .map(|m| {
+            m.content
+                .strip_prefix("Task: ")
+                .unwrap_or(&m.content)
+                .to_string()
+        })
+    else {
+        bail!("No conversation found in {}", path.display());
+    };
+    let summary = messages
+        .iter()
+        .rev()
+        .find(|m| matches!(m.role, MessageRole::Assistant))
+        .map(|m| split_tool_call(&m.content).0.to_string())
+        .filter(|s| !s.is_empty());
+
+    let session_id = generate_session_id(&task, None);
+    let mut context = ContextWindow::new(total_tokens);
+    context.add_message(Message::new(
+        MessageRole::System,
+        format!(
+            "Conversation imported from {} ({}).",
+            format.name(),
+            path.display()
+        ),
+    ));
+    let count = messages.len();
+    for message in messages {
+        context.add_message(message);
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let json = session_json(Some(&session_id), &context, "imported", timestamp)?;
+    ensure_session_dir(&session_id)?;
+    let session_file = get_session_file(&session_id);
+    compressed_file::write(&session_file, json.as_bytes(), None)
+        .with_context(|| format!("Failed to write {}", session_file.display()))?;
+
+    let working_directory = std::env::current_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| ".".to_string());
+    let continuation = SessionContinuation::new(
+        false,
+        None,
+        session_id,
+        Some(truncate_to_word_boundary(&task, 60)),
+        summary,
+        session_file.to_string_lossy().to_string(),
+        context.percentage_used(),
+        None,
+        working_directory,
+    );
+    save_continuation(&continuation)?;
+
+    Ok(ImportedSession {
+        format,
+        messages: count,
+        continuation,
+    })
+}
+
+/// Convert a transcript in `format` into g3 messages.
+pub fn convert(format: ImportFormat, content: &str) -> Result<Vec<Message>> {
+    let entries = match format {
+        ImportFormat::ClaudeCode => parse_claude_code(content)?,
+        ImportFormat::Aider => parse_aider(content),
+        ImportFormat::Cursor => parse_cursor(content),
+    };
+    Ok(to_messages(entries))
+}
+
+/// A step of a conversation, as the other agents record it.
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    User(String),
+    Assistant(String),
+    ToolCall {
+        id: Option<String>,
+        tool: String,
+        args: serde_json::Value,
+    },
+    ToolResult {
+        id: Option<String>,
+        content: String,
+        is_error: bool,
+    },
+}
+
+/// Claude Code logs every message as a JSON record with the API message in
+/// `message`. Records of sub-agents (`isSidechain`) and injected context
+/// (`isMeta`) are left out, as are thinking blocks and images.
+fn parse_claude_code(content: &str) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: serde_json::Value = serde_json::from_str(line)
+            .with_context(|| format!("Line {} is not a Claude Code log record", n + 1))?;
+        if record["isSidechain"].as_bool() == Some(true) || record["isMeta"].as_bool() == Some(true)
+        {
+            continue;
+        }
+        let is_user = match record["type"].as_str() {
+            Some("user") => true,
+            Some("assistant") => false,
+            _ => continue, // summaries, snapshots and system records
+        };
+        let text = |text: &str| match is_user {
+            true => Entry::User(text.to_string()),
+            false => Entry::Assistant(text.to_string()),
+        };
+
+        match &record["message"]["content"] {
+            serde_json::Value::String(content) => entries.push(text(content)),
+            serde_json::Value::Array(blocks) => {
+                for block in blocks {
+                    match block["type"].as_str() {
+                        Some("text") => entries.push(text(block["text"].as_str().unwrap_or(""))),
+                        Some("tool_use") => entries.push(Entry::ToolCall {
+                            id: block["id"].as_str().map(str::to_string),
+                            tool: block["name"].as_str().unwrap_or("unknown").to_string(),
+                            args: block["input"].clone(),
+                        }),
+                        Some("tool_result") => entries.push(Entry::ToolResult {
+                            id: block["tool_use_id"].as_str().map(str::to_string),
+                            content: block_text(&block["content"]),
+                            is_error: block["is_error"].as_bool().unwrap_or(false),
+                        }),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// The text of a tool result's content, a string or a list of blocks.
+fn block_text(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|b| b["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Aider writes the user's input as `#### ` lines and everything else (the
+/// model's replies and Aider's own `> ` output, such as applied edits and
+/// commits) as it was shown. The banner after each `# aider chat started`
+/// heading is skipped.
+fn parse_aider(content: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let (mut user, mut assistant) = (String::new(), String::new());
+    let mut in_banner = true;
+    let flush = |entries: &mut Vec<Entry>, text: &mut String, user: bool| {
+        let trimmed = text.trim();
+        if !trimmed.is_empty() {
+            entries.push(match user {
+                true => Entry::User(trimmed.to_string()),
+                false => Entry::Assistant(trimmed.to_string()),
+            });
+        }
+        text.clear();
+    };
+
+    for line in content.lines() {
+        if line.starts_with("# aider chat started") {
+            flush(&mut entries, &mut user, true);
+            flush(&mut entries, &mut assistant, false);
+            in_banner = true;
+            continue;
+        }
+        let prompt = line
+            .strip_prefix("####")
+            .filter(|rest| rest.is_empty() || rest.starts_with(' '));
+        if let Some(prompt) = prompt {
+            flush(&mut entries, &mut assistant, false);
+            user.push_str(prompt.trim_start());
+            user.push('\n');
+            in_banner = false;
+        } else if !in_banner {
+            flush(&mut entries, &mut user, true);
+            assistant.push_str(line);
+            assistant.push('\n');
+        }
+    }
+    flush(&mut entries, &mut user, true);
+    flush(&mut entries, &mut assistant, false);
+    entries
+}
+
+/// Whether `line` opens a section of a Cursor export (`**User**`,
+/// `_**Assistant**_`, `**Cursor**`): true for the user's.
+fn cursor_speaker(line: &str) -> Option<bool> {
+    let line = line.trim();
+    if !line.starts_with("**") && !line.starts_with("_**") {
+        return None;
+    }
+    match line.trim_matches(|c| c == '*' || c == '_').trim() {
+        "User" => Some(true),
+        "Assistant" | "Cursor" => Some(false),
+        _ => None,
+    }
+}
+
+/// Cursor exports a chat as Markdown sections headed by the speaker and
+/// separated by `---` rules. Anything before the first section is the title.
+fn parse_cursor(content: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut speaker = None;
+    let mut text = String::new();
+    let flush = |entries: &mut Vec<Entry>, speaker: Option<bool>, text: &mut String| {
+        let trimmed = text.trim();
+        let trimmed = trimmed.strip_suffix("---").unwrap_or(trimmed).trim();
+        match speaker {
+            _ if trimmed.is_empty() => {}
+            Some(true) => entries.push(Entry::User(trimmed.to_string())),
+            Some(false) => entries.push(Entry::Assistant(trimmed.to_string())),
+            None => {}
+        }
+        text.clear();
+    };
+
+    for line in content.lines() {
+        if let Some(user) = cursor_speaker(line) {
+            flush(&mut entries, speaker, &mut text);
+            speaker = Some(user);
+        } else if speaker.is_some() {
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    flush(&mut entries, speaker, &mut text);
+    entries
+}
+
+/// Turn entries into g3 messages: each tool call ends an assistant message
+/// (after the prose that preceded it) and is followed by its result.
+/// Consecutive prose of the same speaker is joined. Calls whose result isn't
+/// in the transcript, because it ended mid-call, are dropped.
+fn to_messages(entries: Vec<Entry>) -> Vec<Message> {
+    let mut messages: Vec<Message> = Vec::new();
+    let mut prose = String::new();
+    let mut calls: Vec<(Option<String>, String, serde_json::Value)> = Vec::new();
+
+    for entry in entries {
+        match entry {
+            Entry::Assistant(text) => join(&mut prose, &text),
+            Entry::ToolCall { id, tool, args } => calls.push((id, tool, args)),
+            Entry::ToolResult {
+                id,
+                content,
+                is_error,
+            } => {
+                let Some(at) = calls.iter().position(|c| id.is_none() || c.0 == id) else {
+                    continue;
+                };
+                let (_, tool, args) = calls.remove(at);
+                let call = format!(
+                    "{{\"tool\": {}, \"args\": {}}}",
+                    serde_json::json!(tool),
+                    args
+                );
+                join(&mut prose, &call);
+                messages.push(Message::new(
+                    MessageRole::Assistant,
+                    std::mem::take(&mut prose),
+                ));
+                let marker = if is_error { "❌ " } else { "" };
+                messages.push(Message::new(
+                    MessageRole::User,
+                    format!("Tool result: {}{}", marker, content.trim()),
+                ));
+            }
+            Entry::User(text) => {
+                if text.trim().is_empty() {
+                    continue;
+                }
+                calls.clear();
+                if !prose.is_empty() {
+                    messages.push(Message::new(
+                        MessageRole::Assistant,
+                        std::mem::take(&mut prose),
+                    ));
+                }
+                match messages.last_mut() {
+                    Some(last)
+                        if matches!(last.role, MessageRole::User)
+                            && !last.content.starts_with("Tool result:") =>
+                    {
+                        join(&mut last.content, &text)
+                    }
+                    Some(_) => messages.push(Message::new(MessageRole::User, text)),
+                    None => {
+                        messages.push(Message::new(MessageRole::User, format!("Task: {}", text)))
+                    }
+                }
+            }
+        }
+    }
+    if !prose.is_empty() {
+        messages.push(Message::new(MessageRole::Assistant, prose));
+    }
+    messages
+}
+
+fn join(text: &mut String, more: &str) {
+    let more = more.trim();
+    if more.is_empty() {
+        return;
+    }
+    if !text.is_empty() {
+        text.push_str("\n\n");
+    }
+    text.push_str(more);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::validate_session_log;
+    use serial_test::serial;
+
+    fn contents(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    #[test]
+    fn test_claude_code_log() {
+        let log = [
+            r#"{"type":"summary","summary":"Fix the parser"}"#,
+            r#"{"type":"user","message":{"role":"user","content":"Fix the parser"}}"#,
+            r#"{"type":"user","isMeta":true,"message":{"role":"user","content":"<command-caveat>"}}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"thinking","thinking":"hmm"},{"type":"text","text":"Let me look."}]}}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"src/parser.rs"}},{"type":"tool_use","id":"t2","name":"Bash","input":{"command":"cargo test"}}]}}"#,
+            r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"fn parse() {}"},{"type":"tool_result","tool_use_id":"t2","content":[{"type":"text","text":"1 failed"}],"is_error":true}]}}"#,
+            r#"{"type":"assistant","isSidechain":true,"message":{"role":"assistant","content":"sub-agent chatter"}}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"The test fails."}]}}"#,
+        ]
+        .join("\n");
+
+        let messages = convert(ImportFormat::ClaudeCode, &log).unwrap();
+        assert_eq!(
+            contents(&messages),
+            vec![
+                "Task: Fix the parser",
+                r#"Let me look.
+
+{"tool": "Read", "args": {"file_path":"src/parser.rs"}}"#,
+                "Tool result: fn parse() {}",
+                r#"{"tool": "Bash", "args": {"command":"cargo test"}}"#,
+                "Tool result: ❌ 1 failed",
+                "The test fails.",
+            ]
+        );
+        assert!(convert(ImportFormat::ClaudeCode, "not json").is_err());
+    }
+
+    #[test]
+    fn test_aider_and_cursor_histories() {
+        let aider = "\
+# aider chat started at 2026-10-01 09:00:00
+
+> Aider v0.80.0
+> Model: sonnet
+
+#### add a --verbose flag
+#### to the cli
+
+I'll add the flag.
+
+> Applied edit to src/cli.py
+> Commit 1a2b3c feat: add --verbose
+
+#### thanks
+";
+        let messages = convert(ImportFormat::Aider, aider).unwrap();
+        assert_eq!(
+            contents(&messages),
+            vec![
+                "Task: add a --verbose flag\nto the cli",
+                "I'll add the flag.\n\n> Applied edit to src/cli.py\n> Commit 1a2b3c feat: add --verbose",
+                "thanks",
+            ]
+        );
+
+        let cursor = "\
+# Refactor the config loader
+
+_**User**_
+
+Split the loader into modules
+
+---
+
+_**Assistant**_
+
+Done. The loader now lives in `config/`.
+
+---
+";
+        let messages = convert(ImportFormat::Cursor, cursor).unwrap();
+        assert_eq!(
+            contents(&messages),
+            vec![
+                "Task: Split the loader into modules",
+                "Done. The loader now lives in `config/`.",
+            ]
+        );
+
+        let detect = |name: &str, content: &str| ImportFormat::detect(Path::new(name), content);
+        assert_eq!(detect("abc.jsonl", ""), Some(ImportFormat::ClaudeCode));
+        assert_eq!(detect("history.md", aider), Some(ImportFormat::Aider));
+        assert_eq!(detect("chat.md", cursor), Some(ImportFormat::Cursor));
+        assert_eq!(detect("notes.md", "# Notes"), None);
+        assert_eq!(
+            "claude".parse::<ImportFormat>().unwrap(),
+            ImportFormat::ClaudeCode
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_import_creates_resumable_session() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old_dir = std::env::current_dir().unwrap();
+        std::env::set_current_dir(temp_dir.path()).unwrap();
+
+        let path = temp_dir.path().join(".aider.chat.history.md");
+        std::fs::write(&path, "#### rename foo to bar\n\nRenamed.\n").unwrap();
+        let imported = import_transcript(&path, None, 200_000);
+        let empty = temp_dir.path().join("empty.jsonl");
+        std::fs::write(&empty, "").unwrap();
+        let nothing = import_transcript(&empty, None, 200_000);
+        let current = crate::session_continuation::load_continuation();
+        std::env::set_current_dir(old_dir).unwrap();
+
+        let imported = imported.unwrap();
+        assert_eq!(imported.format, ImportFormat::Aider);
+        assert_eq!(imported.messages, 2);
+        let continuation = &imported.continuation;
+        assert_eq!(
+            continuation.description.as_deref(),
+            Some("rename foo to bar")
+        );
+        assert_eq!(continuation.summary.as_deref(), Some("Renamed."));
+        assert!(continuation.can_restore_full_context());
+
+        let session_file = temp_dir.path().join(&continuation.session_log_path);
+        let data = serde_json::from_str(&std::fs::read_to_string(session_file).unwrap()).unwrap();
+        let validation = validate_session_log(&data).unwrap();
+        assert!(validation.is_intact(), "{:?}", validation.issues);
+        assert_eq!(validation.messages.len(), 2);
+
+        let current = current.unwrap().unwrap();
+        assert_eq!(current.session_id, continuation.session_id);
+        assert!(nothing.is_err());
+    }
+}
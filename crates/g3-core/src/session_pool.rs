@@ -0,0 +1,101 @@
+//! Several agent sessions running side by side in one process.
+//!
+//! Each `Agent` owns its context window, session ID, working directory and
+//! tool state (background processes, shells, interpreters, remembered file
+//! versions), so concurrent sessions don't see each other's work. What they
+//! share is the `ProviderRegistry`: providers are registered once and every
+//! session sends its requests through the same clients.
+//!
+//! `SessionPool` keeps the running sessions by session ID, for frontends
+//! serving several clients and for sub-agents running next to their parent.
+//! Each session is behind its own lock, so a turn in one doesn't wait for
+//! the others. Session logs of every session go to the `.g3/` of the
+//! process's workspace, one directory per session ID.
+
+use anyhow::Result;
+use g3_config::Config;
+use g3_providers::ProviderRegistry;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+use crate::agent_builder::AgentBuilder;
+use crate::ui_writer::UiWriter;
+use crate::{provider_registration, session, Agent};
+
+/// A session of a pool, locked while one of its turns runs.
+pub type SharedAgent<W> = Arc<tokio::sync::Mutex<Agent<W>>>;
+
+/// The sessions running in this process, sharing one set of providers.
+pub struct SessionPool<W: UiWriter> {
+    providers: Arc<ProviderRegistry>,
+    sessions: Mutex<HashMap<String, SharedAgent<W>>>,
+}
+
+impl<W: UiWriter> SessionPool<W> {
+    pub fn new(providers: Arc<ProviderRegistry>) -> Self {
+        Self {
+            providers,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A pool using the providers `config` enables for interactive use.
+    pub async fn from_config(config: &Config) -> Result<Self> {
+        let providers_to_register =
+            provider_registration::determine_providers_to_register(config, false);
+        let providers =
+            provider_registration::register_providers(config, &providers_to_register).await?;
+        Ok(Self::new(Arc::new(providers)))
+    }
+
+    pub fn providers(&self) -> Arc<ProviderRegistry> {
+        self.providers.clone()
+    }
+
+    /// Build a session from `builder` with the pool's providers and add it.
+    ///
+    /// The session keeps the ID set with `AgentBuilder::session_id`, or is
+    /// given a new one. Returns the session ID.
+    pub async fn create(&self, builder: AgentBuilder<W>) -> Result<String> {
+        let mut agent = builder.providers(self.providers.clone()).build().await?;
+        let session_id = match agent.get_session_id() {
+            Some(session_id) => session_id.to_string(),
+            None => {
+                let session_id = session::generate_session_id("session", None);
+                agent.set_session_id(session_id.clone());
+                session_id
+            }
+        };
+        debug!("Adding session {} to the pool", session_id);
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_id.clone(), Arc::new(tokio::sync::Mutex::new(agent)));
+        Ok(session_id)
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<SharedAgent<W>> {
+        self.sessions.lock().unwrap().get(session_id).cloned()
+    }
+
+    /// Take a session out of the pool. A turn still running in it finishes.
+    pub fn remove(&self, session_id: &str) -> Option<SharedAgent<W>> {
+        self.sessions.lock().unwrap().remove(session_id)
+    }
+
+    /// IDs of the sessions in the pool, sorted.
+    pub fn session_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.sessions.lock().unwrap().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
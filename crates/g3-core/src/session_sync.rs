@@ -0,0 +1,660 @@
+//! Syncing sessions between machines.
+//!
+//! With `[sync] remote` set, a session's directory (`.g3/sessions/<id>/`)
+//! is pushed to the remote after every save, and `SessionSync::pull`
+//! fetches it on another machine, so a session started on a laptop can be
+//! resumed on a workstation. The remote holds one directory per session ID
+//! and a `current_session` file naming the session pushed last, which is
+//! what a pull without a session ID fetches.
+//!
+//! Directories and WebDAV servers are written file by file, skipping files
+//! unchanged since the last push (recorded in the session's
+//! `sync_manifest.json`). S3 and Google Cloud Storage go through the `aws`
+//! and `gsutil` CLIs, whose `sync` commands skip unchanged files themselves
+//! and which use whatever credentials they are configured with.
+
+use anyhow::{bail, Context, Result};
+use g3_config::SyncConfig;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+use crate::paths::{get_session_file, get_session_logs_dir};
+use crate::session_continuation::{load_session_continuation, save_continuation};
+
+/// Size and modification time (ms) of each file last pushed, by path
+/// relative to the session directory.
+const MANIFEST_FILENAME: &str = "sync_manifest.json";
+
+/// Remote file naming the session pushed last.
+const CURRENT_SESSION_FILENAME: &str = "current_session";
+
+type Manifest = BTreeMap<String, (u64, u64)>;
+
+/// Where sessions are synced to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncRemote {
+    Directory(PathBuf),
+    WebDav {
+        /// The collection sessions are stored in, ending in `/`
+        url: url::Url,
+        username: Option<String>,
+        password: Option<String>,
+    },
+    /// `s3://bucket/prefix`
+    S3(String),
+    /// `gs://bucket/prefix`
+    Gcs(String),
+}
+
+impl SyncRemote {
+    /// The remote configured in `[sync]`, if any.
+    pub fn from_config(config: &SyncConfig) -> Result<Option<Self>> {
+        let Some(remote) = config.remote.as_deref().map(str::trim) else {
+            return Ok(None);
+        };
+        let remote = if remote.starts_with("s3://") {
+            Self::S3(remote.trim_end_matches('/').to_string())
+        } else if remote.starts_with("gs://") {
+            Self::Gcs(remote.trim_end_matches('/').to_string())
+        } else if remote.starts_with("http://") || remote.starts_with("https://") {
+            let url = format!("{}/", remote.trim_end_matches('/'));
+            Self::WebDav {
+                url: url::Url::parse(&url)
+                    .with_context(|| format!("Invalid WebDAV URL {}", remote))?,
+                username: config.username.clone(),
+                password: config.password.clone(),
+            }
+        } else if remote.contains("://") {
+            bail!(
+                "Unsupported sync remote {} (expected s3://, gs://, http(s):// or a directory)",
+                remote
+            );
+        } else if remote.is_empty() {
+            return Ok(None);
+        } else {
+            Self::Directory(PathBuf::from(shellexpand::tilde(remote).as_ref()))
+        };
+        Ok(Some(remote))
+    }
+}
+
+/// Pushes and pulls sessions to and from a remote.
+pub struct SessionSync {
+    remote: SyncRemote,
+    auto_push: bool,
+    client: reqwest::Client,
+    /// Held while a push or pull runs, so background pushes don't overlap
+    busy: tokio::sync::Mutex<()>,
+}
+
+impl SessionSync {
+    pub fn new(remote: SyncRemote, auto_push: bool) -> Self {
+        Self {
+            remote,
+            auto_push,
+            client: reqwest::Client::new(),
+            busy: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Sync as configured in `[sync]`; `None` if no remote is set.
+    pub fn from_config(config: &SyncConfig) -> Result<Option<Self>> {
+        Ok(SyncRemote::from_config(config)?.map(|remote| Self::new(remote, config.auto_push)))
+    }
+
+    pub fn remote(&self) -> &SyncRemote {
+        &self.remote
+    }
+
+    /// Push `session_id` without waiting for it, if pushing after every
+    /// save is enabled. Failures are logged.
+    pub fn push_in_background(self: &Arc<Self>, session_id: &str) {
+        if !self.auto_push {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let (sync, session_id) = (self.clone(), session_id.to_string());
+        runtime.spawn(async move {
+            if let Err(e) = sync.push(&session_id).await {
+                warn!("Failed to push session {}: {}", session_id, e);
+            }
+        });
+    }
+
+    /// Copy the files of `session_id` to the remote and make it the remote's
+    /// current session.
+    pub async fn push(&self, session_id: &str) -> Result<()> {
+        check_session_id(session_id)?;
+        let _busy = self.busy.lock().await;
+        let dir = get_session_logs_dir(session_id);
+        if !dir.is_dir() {
+            bail!("Session {} has no saved files", session_id);
+        }
+        let local = dir.to_string_lossy();
+
+        match &self.remote {
+            SyncRemote::S3(remote) => {
+                let target = format!("{}/{}/", remote, session_id);
+                let args = [
+                    "s3",
+                    "sync",
+                    &local,
+                    &target,
+                    "--exclude",
+                    MANIFEST_FILENAME,
+                ];
+                run_cli("aws", &args, None).await?;
+            }
+            SyncRemote::Gcs(remote) => {
+                let target = format!("{}/{}", remote, session_id);
+                let exclude = format!("^{}$", MANIFEST_FILENAME.replace('.', "\\."));
+                let args = ["-m", "rsync", "-r", "-x", &exclude, &local, &target];
+                run_cli("gsutil", &args, None).await?;
+            }
+            SyncRemote::Directory(_) | SyncRemote::WebDav { .. } => {
+                let mut manifest: Manifest = std::fs::read_to_string(dir.join(MANIFEST_FILENAME))
+                    .ok()
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default();
+                let mut collections = HashSet::new();
+                let mut pushed = 0;
+                for (path, stamp) in session_files(&dir)? {
+                    if manifest.get(&path) == Some(&stamp) {
+                        continue;
+                    }
+                    let content = std::fs::read(dir.join(&path))?;
+                    let remote_path = format!("{}/{}", session_id, path);
+                    self.write_remote(&remote_path, content, &mut collections)
+                        .await?;
+                    manifest.insert(path, stamp);
+                    pushed += 1;
+                }
+                std::fs::write(
+                    dir.join(MANIFEST_FILENAME),
+                    serde_json::to_string(&manifest)?,
+                )?;
+                debug!("Pushed {} changed files of session {}", pushed, session_id);
+            }
+        }
+        self.set_current_session(session_id).await
+    }
+
+    /// Fetch `session_id`, or the session pushed last, from the remote into
+    /// `.g3/sessions/` and make it the current session. Returns its ID.
+    ///
+    /// The continuation is pointed at this machine's copy of the session log
+    /// and at the current directory, so the session is listed and resumed
+    /// here like one started here.
+    pub async fn pull(&self, session_id: Option<&str>) -> Result<String> {
+        let _busy = self.busy.lock().await;
+        let session_id = match session_id {
+            Some(session_id) => session_id.to_string(),
+            None => self.current_session().await?,
+        };
+        check_session_id(&session_id)?;
+        let dir = get_session_logs_dir(&session_id);
+        std::fs::create_dir_all(&dir)?;
+        let local = dir.to_string_lossy();
+
+        match &self.remote {
+            SyncRemote::S3(remote) => {
+                let source = format!("{}/{}/", remote, session_id);
+                run_cli("aws", &["s3", "sync", &source, &local], None).await?;
+            }
+            SyncRemote::Gcs(remote) => {
+                let source = format!("{}/{}", remote, session_id);
+                run_cli("gsutil", &["-m", "rsync", "-r", &source, &local], None).await?;
+            }
+            SyncRemote::Directory(root) => {
+                let source = root.join(&session_id);
+                if !source.is_dir() {
+                    bail!("No session {} in {}", session_id, root.display());
+                }
+                for (path, _) in session_files(&source)? {
+                    let target = dir.join(&path);
+                    if let Some(parent) = target.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::copy(source.join(&path), target)?;
+                }
+            }
+            SyncRemote::WebDav { .. } => {
+                let files = self.webdav_list(&format!("{}/", session_id)).await?;
+                if files.is_empty() {
+                    bail!("No session {} on the WebDAV server", session_id);
+                }
+                for (href, path) in files {
+                    let content = self
+                        .webdav_request(reqwest::Method::GET, &format!("{}/{}", session_id, href))
+                        .send()
+                        .await?
+                        .error_for_status()?
+                        .bytes()
+                        .await?;
+                    let target = dir.join(&path);
+                    if let Some(parent) = target.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(target, content)?;
+                }
+            }
+        }
+
+        // What was just pulled doesn't need pushing back
+        let manifest: Manifest = session_files(&dir)?.into_iter().collect();
+        std::fs::write(
+            dir.join(MANIFEST_FILENAME),
+            serde_json::to_string(&manifest)?,
+        )?;
+
+        if let Some(mut continuation) = load_session_continuation(&session_id)? {
+            continuation.session_log_path =
+                get_session_file(&session_id).to_string_lossy().to_string();
+            continuation.working_directory = std::env::current_dir()?.to_string_lossy().to_string();
+            save_continuation(&continuation)?;
+        }
+        Ok(session_id)
+    }
+
+    /// The session pushed to the remote last.
+    async fn current_session(&self) -> Result<String> {
+        let session_id = match &self.remote {
+            SyncRemote::S3(remote) => {
+                let current = format!("{}/{}", remote, CURRENT_SESSION_FILENAME);
+                run_cli("aws", &["s3", "cp", &current, "-"], None).await?
+            }
+            SyncRemote::Gcs(remote) => {
+                let current = format!("{}/{}", remote, CURRENT_SESSION_FILENAME);
+                run_cli("gsutil", &["cat", &current], None).await?
+            }
+            SyncRemote::Directory(root) => {
+                let current = root.join(CURRENT_SESSION_FILENAME);
+                std::fs::read_to_string(&current)
+                    .with_context(|| format!("No session has been pushed to {}", root.display()))?
+            }
+            SyncRemote::WebDav { .. } => {
+                self.webdav_request(reqwest::Method::GET, CURRENT_SESSION_FILENAME)
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .context("No session has been pushed to the WebDAV server")?
+                    .text()
+                    .await?
+            }
+        };
+        Ok(session_id.trim().to_string())
+    }
+
+    async fn set_current_session(&self, session_id: &str) -> Result<()> {
+        match &self.remote {
+            SyncRemote::S3(remote) => {
+                let current = format!("{}/{}", remote, CURRENT_SESSION_FILENAME);
+                run_cli("aws", &["s3", "cp", "-", &current], Some(session_id)).await?;
+            }
+            SyncRemote::Gcs(remote) => {
+                let current = format!("{}/{}", remote, CURRENT_SESSION_FILENAME);
+                run_cli("gsutil", &["cp", "-", &current], Some(session_id)).await?;
+            }
+            SyncRemote::Directory(_) | SyncRemote::WebDav { .. } => {
+                let content = session_id.as_bytes().to_vec();
+                self.write_remote(CURRENT_SESSION_FILENAME, content, &mut HashSet::new())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a file at `path` (`/`-separated) under a directory or WebDAV
+    /// remote. `collections` are the WebDAV collections already created.
+    async fn write_remote(
+        &self,
+        path: &str,
+        content: Vec<u8>,
+        collections: &mut HashSet<String>,
+    ) -> Result<()> {
+        match &self.remote {
+            SyncRemote::Directory(root) => {
+                let target = root.join(path);
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&target, content)
+                    .with_context(|| format!("Failed to write {}", target.display()))?;
+            }
+            SyncRemote::WebDav { .. } => {
+                // PUT doesn't create missing collections
+                let mut collection = String::new();
+                for part in path
+                    .split('/')
+                    .rev()
+                    .skip(1)
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .rev()
+                {
+                    collection.push_str(part);
+                    collection.push('/');
+                    if collections.insert(collection.clone()) {
+                        let mkcol = reqwest::Method::from_bytes(b"MKCOL")?;
+                        let response = self.webdav_request(mkcol, &collection).send().await?;
+                        // 405: the collection exists already
+                        if !response.status().is_success() && response.status().as_u16() != 405 {
+                            bail!(
+                                "Cannot create {} on the WebDAV server: {}",
+                                collection,
+                                response.status()
+                            );
+                        }
+                    }
+                }
+                self.webdav_request(reqwest::Method::PUT, path)
+                    .body(content)
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .with_context(|| format!("Failed to upload {}", path))?;
+            }
+            SyncRemote::S3(_) | SyncRemote::Gcs(_) => {
+                unreachable!("S3 and GCS are written through their CLIs")
+            }
+        }
+        Ok(())
+    }
+
+    /// Files under the WebDAV collection `collection` (ending in `/`), as
+    /// paths relative to it.
+    /// The files under `collection`, as their percent-encoded path relative
+    /// to it and the decoded local path.
+    async fn webdav_list(&self, collection: &str) -> Result<Vec<(String, String)>> {
+        let SyncRemote::WebDav { url, .. } = &self.remote else {
+            return Ok(Vec::new());
+        };
+        let href =
+            regex::Regex::new(r"(?i)<(?:[a-z0-9]+:)?href>\s*([^<]+?)\s*</(?:[a-z0-9]+:)?href>")?;
+        let base = url.join(collection)?;
+        let mut files = Vec::new();
+        let mut pending = vec![base.clone()];
+        while let Some(dir) = pending.pop() {
+            let propfind = reqwest::Method::from_bytes(b"PROPFIND")?;
+            let response = self
+                .webdav_request(propfind, dir.as_str())
+                .header("Depth", "1")
+                .send()
+                .await?;
+            if response.status().as_u16() == 404 {
+                continue;
+            }
+            let body = response.error_for_status()?.text().await?;
+            for captures in href.captures_iter(&body) {
+                let entry = dir.join(&captures[1])?;
+                if entry.path() == dir.path() || !entry.path().starts_with(base.path()) {
+                    continue;
+                }
+                if entry.path().ends_with('/') {
+                    pending.push(entry);
+                } else {
+                    let href = entry.path()[base.path().len()..].to_string();
+                    let path = decode_relative_path(&href).with_context(|| {
+                        format!("Invalid file name '{}' on the WebDAV server", href)
+                    })?;
+                    files.push((href, path));
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// A request for `path`, relative to the WebDAV collection (or absolute).
+    fn webdav_request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let SyncRemote::WebDav {
+            url,
+            username,
+            password,
+        } = &self.remote
+        else {
+            unreachable!("Not a WebDAV remote");
+        };
+        let target = url.join(path).unwrap_or_else(|_| url.clone());
+        let request = self.client.request(method, target);
+        match username {
+            Some(username) => request.basic_auth(username, password.as_ref()),
+            None => request,
+        }
+    }
+}
+
+/// A percent-encoded relative URL path decoded into a local relative path,
+/// or `None` if a segment is empty, `.` or `..`, contains a separator or
+/// isn't UTF-8.
+fn decode_relative_path(href: &str) -> Option<String> {
+    let segments = href
+        .split('/')
+        .map(|segment| {
+            let decoded = percent_decode(segment)?;
+            let safe = !matches!(decoded.as_str(), "" | "." | "..")
+                && !decoded.contains(['/', '\\', '\0']);
+            safe.then_some(decoded)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(segments.join("/"))
+}
+
+/// `%XX` escapes in `segment` replaced by the bytes they stand for.
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn check_session_id(session_id: &str) -> Result<()> {
+    if session_id.is_empty()
+        || session_id == "."
+        || session_id == ".."
+        || session_id.contains(['/', '\\'])
+    {
+        bail!("Invalid session ID '{}'", session_id);
+    }
+    Ok(())
+}
+
+/// The files under `dir` with their size and modification time, by
+/// `/`-separated path relative to `dir`. The manifest and files being
+/// written atomically are left out.
+fn session_files(dir: &Path) -> Result<Vec<(String, (u64, u64))>> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path().strip_prefix(dir)?;
+        let path = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if path == MANIFEST_FILENAME || path.ends_with(".tmp") {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        files.push((path, (metadata.len(), modified)));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Run one of the cloud storage CLIs, returning its output.
+async fn run_cli(program: &str, args: &[&str], stdin: Option<&str>) -> Result<String> {
+    debug!("Running {} {:?}", program, args);
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to run {} (is it installed?)", program))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        bail!(
+            "{} {} failed: {}",
+            program,
+            args.iter().take(2).copied().collect::<Vec<_>>().join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_continuation::SessionContinuation;
+    use serial_test::serial;
+
+    #[test]
+    fn test_webdav_paths_are_decoded() {
+        assert_eq!(
+            decode_relative_path("a%20b.json").as_deref(),
+            Some("a b.json")
+        );
+        assert_eq!(
+            decode_relative_path("tools/r%C3%A9sum%C3%A9.txt").as_deref(),
+            Some("tools/résumé.txt")
+        );
+        for href in ["../secrets", "a/%2E%2E/b", "a%2Fb", "a//b", "bad%zz", "%FF"] {
+            assert_eq!(decode_relative_path(href), None, "{}", href);
+        }
+    }
+
+    #[test]
+    fn test_remote_from_config() {
+        let remote = |remote: &str| {
+            let config = SyncConfig {
+                remote: Some(remote.to_string()),
+                username: Some("me".to_string()),
+                ..Default::default()
+            };
+            SyncRemote::from_config(&config)
+        };
+        assert_eq!(
+            remote("s3://bucket/g3/").unwrap(),
+            Some(SyncRemote::S3("s3://bucket/g3".to_string()))
+        );
+        assert_eq!(
+            remote("gs://bucket").unwrap(),
+            Some(SyncRemote::Gcs("gs://bucket".to_string()))
+        );
+        match remote("https://dav.example.com/g3").unwrap() {
+            Some(SyncRemote::WebDav { url, username, .. }) => {
+                assert_eq!(url.as_str(), "https://dav.example.com/g3/");
+                assert_eq!(username.as_deref(), Some("me"));
+            }
+            other => panic!("Expected a WebDAV remote, got {:?}", other),
+        }
+        assert_eq!(
+            remote("/mnt/share/g3").unwrap(),
+            Some(SyncRemote::Directory(PathBuf::from("/mnt/share/g3")))
+        );
+        assert!(remote("ftp://host/g3").is_err());
+        assert_eq!(
+            SyncRemote::from_config(&SyncConfig::default()).unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_push_and_pull_through_directory() {
+        let (laptop, workstation) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let remote = tempfile::tempdir().unwrap();
+        let old_dir = std::env::current_dir().unwrap();
+        let sync = SessionSync::new(SyncRemote::Directory(remote.path().to_path_buf()), true);
+
+        std::env::set_current_dir(laptop.path()).unwrap();
+        let dir = crate::paths::ensure_session_dir("fix_parser").unwrap();
+        std::fs::write(dir.join("session.json"), "{}").unwrap();
+        std::fs::write(dir.join("thinned").join("result_1.txt"), "big output").unwrap();
+        let continuation = SessionContinuation::new(
+            false,
+            None,
+            "fix_parser".to_string(),
+            Some("Fix the parser".to_string()),
+            None,
+            get_session_file("fix_parser").to_string_lossy().to_string(),
+            10.0,
+            None,
+            laptop.path().to_string_lossy().to_string(),
+        );
+        save_continuation(&continuation).unwrap();
+        let pushed = sync.push("fix_parser").await;
+        // Unchanged files are not copied again
+        std::fs::remove_file(remote.path().join("fix_parser/thinned/result_1.txt")).unwrap();
+        let pushed_again = sync.push("fix_parser").await;
+
+        std::env::set_current_dir(workstation.path()).unwrap();
+        let pulled = sync.pull(None).await;
+        let restored = load_session_continuation("fix_parser");
+        let missing = sync.pull(Some("no_such_session")).await;
+        let workstation_file = get_session_file("fix_parser");
+        std::env::set_current_dir(old_dir).unwrap();
+
+        pushed.unwrap();
+        pushed_again.unwrap();
+        assert!(remote.path().join("fix_parser/session.json").exists());
+        assert!(!remote
+            .path()
+            .join("fix_parser")
+            .join(MANIFEST_FILENAME)
+            .exists());
+        assert!(!remote
+            .path()
+            .join("fix_parser/thinned/result_1.txt")
+            .exists());
+        assert_eq!(
+            std::fs::read_to_string(remote.path().join(CURRENT_SESSION_FILENAME)).unwrap(),
+            "fix_parser"
+        );
+
+        assert_eq!(pulled.unwrap(), "fix_parser");
+        assert!(workstation_file.exists());
+        let restored = restored.unwrap().unwrap();
+        assert_eq!(
+            restored.session_log_path,
+            workstation_file.to_string_lossy()
+        );
+        assert_eq!(restored.description.as_deref(), Some("Fix the parser"));
+        assert!(missing.is_err());
+    }
+}
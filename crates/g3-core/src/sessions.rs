@@ -0,0 +1,389 @@
+//! Listing, inspecting and deleting saved sessions.
+//!
+//! Every session has a directory under `.g3/sessions/<id>/`. This module
+//! reads what a frontend needs to show a session (when it was created and
+//! last active, how many tokens it used, what it was asked to do) from the
+//! files in that directory, so callers don't parse `session.json` and
+//! `latest.json` themselves.
+//!
+//! `gc_sessions` applies the `[retention]` policy: without it `.g3/` grows
+//! with every session, thinned tool result and error log ever written.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use g3_config::RetentionConfig;
+use g3_providers::{Message, MessageRole};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::commit_links::session_commits;
+use crate::compressed_file;
+use crate::paths::{
+    get_background_processes_dir, get_errors_dir, get_g3_dir, get_session_file,
+    get_session_logs_dir, get_telemetry_file,
+};
+use crate::session_continuation::SessionContinuation;
+use crate::utils::truncate_to_word_boundary;
+
+/// Characters of the first user message kept as a session's description.
+const DESCRIPTION_CHARS: usize = 60;
+
+/// What is known about a saved session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionMeta {
+    pub id: String,
+    pub created: Option<DateTime<Utc>>,
+    /// When the conversation was last saved
+    pub last_active: Option<DateTime<Utc>>,
+    /// The task the session was started with, shortened
+    pub description: Option<String>,
+    /// How the last turn ended ("completed", "error", ...)
+    pub status: Option<String>,
+    pub agent_name: Option<String>,
+    pub working_directory: Option<String>,
+    pub message_count: usize,
+    /// Tokens in the context window when it was last saved
+    pub context_tokens: u32,
+    /// Size of the context window
+    pub context_window_size: u32,
+    /// Tokens used over all turns, prompt and completion
+    pub total_tokens: u64,
+    /// Disk space the session's directory takes
+    pub size_bytes: u64,
+    /// SHAs of the git commits linked to the session
+    pub commits: Vec<String>,
+}
+
+/// What `gc_sessions` removed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GcReport {
+    pub removed_sessions: Vec<String>,
+    /// Error and background process logs removed
+    pub removed_logs: usize,
+    pub freed_bytes: u64,
+}
+
+/// The `.g3/sessions/` directory.
+fn sessions_dir() -> PathBuf {
+    get_g3_dir().join("sessions")
+}
+
+/// All saved sessions, most recently active first.
+pub fn list_sessions() -> Result<Vec<SessionMeta>> {
+    let dir = sessions_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut sessions = Vec::new();
+    for entry in
+        std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))?
+    {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if let Some(id) = entry.file_name().to_str() {
+            sessions.push(read_meta(id, &entry.path()));
+        }
+    }
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.last_active));
+    Ok(sessions)
+}
+
+/// The metadata of session `id`.
+pub fn get_session_meta(id: &str) -> Result<SessionMeta> {
+    let dir = session_dir(id)?;
+    Ok(read_meta(id, &dir))
+}
+
+/// Delete session `id` and everything saved for it. If it is the current
+/// session, `.g3/session` is removed too.
+pub fn delete_session(id: &str) -> Result<()> {
+    let dir = session_dir(id)?;
+    let current = crate::session_continuation::get_session_dir();
+    if current.is_symlink() && std::fs::read_link(&current).is_ok_and(|target| target.ends_with(id))
+    {
+        std::fs::remove_file(&current)
+            .with_context(|| format!("Failed to remove {}", current.display()))?;
+    }
+    std::fs::remove_dir_all(&dir).with_context(|| format!("Failed to delete {}", dir.display()))
+}
+
+/// Prune `.g3/` according to `policy`.
+///
+/// Sessions are kept most recently active first while they are within
+/// `max_sessions` and `max_total_mb`, and removed once they are older than
+/// `max_age_days`. Error and background process logs older than that go too.
+/// `keep` (the running session) and the session `.g3/session` points to are
+/// never removed.
+pub fn gc_sessions(policy: &RetentionConfig, keep: Option<&str>) -> Result<GcReport> {
+    let mut report = GcReport::default();
+    let max_age =
+        (policy.max_age_days > 0).then(|| Duration::from_secs(policy.max_age_days * 24 * 60 * 60));
+    let max_bytes = policy.max_total_mb * 1024 * 1024;
+    let current = std::fs::read_link(crate::session_continuation::get_session_dir()).ok();
+    let now = Utc::now();
+
+    // Protected sessions take their place within the limits first
+    let (protected, sessions): (Vec<_>, Vec<_>) =
+        list_sessions()?.into_iter().partition(|session| {
+            keep == Some(session.id.as_str())
+                || current.as_ref().is_some_and(|c| c.ends_with(&session.id))
+        });
+    let mut kept = protected.len();
+    let mut kept_bytes: u64 = protected.iter().map(|s| s.size_bytes).sum();
+    for session in sessions {
+        let expired = max_age.is_some_and(|max_age| {
+            session
+                .last_active
+                .and_then(|t| (now - t).to_std().ok())
+                .is_some_and(|age| age > max_age)
+        });
+        let over_count = policy.max_sessions > 0 && kept >= policy.max_sessions;
+        let over_size = max_bytes > 0 && kept_bytes + session.size_bytes > max_bytes;
+        if !(expired || over_count || over_size) {
+            kept += 1;
+            kept_bytes += session.size_bytes;
+            continue;
+        }
+        match std::fs::remove_dir_all(get_session_logs_dir(&session.id)) {
+            Ok(()) => {
+                report.freed_bytes += session.size_bytes;
+                report.removed_sessions.push(session.id);
+            }
+            Err(e) => warn!("Failed to remove session {}: {}", session.id, e),
+        }
+    }
+
+    if let Some(max_age) = max_age {
+        for dir in [get_errors_dir(), get_background_processes_dir()] {
+            for (path, size) in files_older_than(&dir, max_age) {
+                if std::fs::remove_file(&path).is_ok() {
+                    report.removed_logs += 1;
+                    report.freed_bytes += size;
+                }
+            }
+        }
+    }
+
+    if !report.removed_sessions.is_empty() || report.removed_logs > 0 {
+        debug!(
+            "Session GC removed {} sessions and {} logs ({} bytes)",
+            report.removed_sessions.len(),
+            report.removed_logs,
+            report.freed_bytes
+        );
+    }
+    Ok(report)
+}
+
+/// The files directly in `dir` last modified longer than `age` ago, with
+/// their sizes.
+fn files_older_than(dir: &Path, age: Duration) -> Vec<(PathBuf, u64)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            let old = metadata.is_file() && metadata.modified().ok()?.elapsed().ok()? > age;
+            old.then(|| (e.path(), metadata.len()))
+        })
+        .collect()
+}
+
+/// Total size of the files under `dir`.
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| match e.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&e.path()),
+            Ok(t) if t.is_file() => e.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// The directory of an existing session.
+fn session_dir(id: &str) -> Result<PathBuf> {
+    if id.is_empty() || id == "." || id == ".." || id.contains(['/', '\\']) {
+        bail!("Invalid session ID '{}'", id);
+    }
+    let dir = get_session_logs_dir(id);
+    if !dir.is_dir() {
+        bail!("No session '{}'", id);
+    }
+    Ok(dir)
+}
+
+/// Read what the session's files say about it. Missing or unreadable files
+/// leave their fields empty.
+fn read_meta(id: &str, dir: &Path) -> SessionMeta {
+    let mut meta = SessionMeta {
+        id: id.to_string(),
+        created: std::fs::metadata(dir)
+            .and_then(|m| m.created().or_else(|_| m.modified()))
+            .ok()
+            .map(DateTime::from),
+        last_active: None,
+        description: None,
+        status: None,
+        agent_name: None,
+        working_directory: None,
+        message_count: 0,
+        context_tokens: 0,
+        context_window_size: 0,
+        total_tokens: total_tokens(id),
+        size_bytes: dir_size(dir),
+        commits: session_commits(id).into_iter().map(|c| c.sha).collect(),
+    };
+
+    let session_file = get_session_file(id);
+    if let Some(session) = read_json(&session_file) {
+        let context = &session["context_window"];
+        let history: Vec<Message> =
+            serde_json::from_value(context["conversation_history"].clone()).unwrap_or_default();
+        meta.last_active = session["timestamp"]
+            .as_i64()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0));
+        meta.status = session["status"].as_str().map(str::to_string);
+        meta.message_count = history.len();
+        meta.context_tokens = context["used_tokens"].as_u64().unwrap_or(0) as u32;
+        meta.context_window_size = context["total_tokens"].as_u64().unwrap_or(0) as u32;
+        meta.description = history
+            .iter()
+            .find(|m| matches!(m.role, MessageRole::User))
+            .map(|m| {
+                let task = m.content.strip_prefix("Task: ").unwrap_or(&m.content);
+                truncate_to_word_boundary(task, DESCRIPTION_CHARS)
+            });
+    }
+    if meta.last_active.is_none() {
+        meta.last_active = modified(&session_file).or(meta.created);
+    }
+
+    let continuation = read_json(&dir.join("latest.json"))
+        .and_then(|json| serde_json::from_value::<SessionContinuation>(json).ok());
+    if let Some(continuation) = continuation {
+        meta.agent_name = continuation.agent_name;
+        meta.working_directory = Some(continuation.working_directory);
+        meta.description = continuation.description.or(meta.description);
+    }
+    meta
+}
+
+/// Tokens used by all turns recorded in the session's telemetry log.
+fn total_tokens(id: &str) -> u64 {
+    std::fs::read_to_string(get_telemetry_file(id))
+        .map(|log| {
+            log.lines()
+                .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+                .filter_map(|event| event["total_tokens"].as_u64())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&compressed_file::read_to_string(path).ok()?).ok()
+}
+
+fn modified(path: &Path) -> Option<DateTime<Utc>> {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(DateTime::<Utc>::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context_window::ContextWindow;
+    use crate::session::save_context_window;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_list_inspect_and_delete() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old_dir = std::env::current_dir().unwrap();
+        std::env::set_current_dir(temp_dir.path()).unwrap();
+
+        assert!(list_sessions().unwrap().is_empty());
+
+        let mut context = ContextWindow::new(200_000);
+        context.add_message(Message::new(
+            MessageRole::User,
+            "Task: Add a sessions command to the CLI".to_string(),
+        ));
+        save_context_window(Some("add_sessions_1f2e"), &context, "completed");
+        std::fs::write(
+            get_telemetry_file("add_sessions_1f2e"),
+            "{\"event\":\"turn_telemetry\",\"total_tokens\":1200}\n\
+             {\"event\":\"turn_telemetry\",\"total_tokens\":800}\n",
+        )
+        .unwrap();
+
+        let sessions = list_sessions().unwrap();
+        let meta = get_session_meta("add_sessions_1f2e").unwrap();
+        let invalid = get_session_meta("../escape").is_err();
+        let deleted = delete_session("add_sessions_1f2e");
+        let remaining = list_sessions().unwrap();
+        std::env::set_current_dir(old_dir).unwrap();
+
+        assert_eq!(sessions, vec![meta.clone()]);
+        assert_eq!(
+            meta.description.as_deref(),
+            Some("Add a sessions command to the CLI")
+        );
+        assert_eq!(meta.status.as_deref(), Some("completed"));
+        assert_eq!(meta.message_count, 1);
+        assert_eq!(meta.context_window_size, 200_000);
+        assert_eq!(meta.total_tokens, 2000);
+        assert!(meta.created.is_some() && meta.last_active.is_some());
+        assert!(invalid);
+        assert!(deleted.is_ok());
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn test_gc_applies_retention_policy() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old_dir = std::env::current_dir().unwrap();
+        std::env::set_current_dir(temp_dir.path()).unwrap();
+
+        let now = Utc::now().timestamp();
+        let day = 24 * 60 * 60;
+        for (id, age_days) in [("new", 0), ("recent", 1), ("older", 2), ("ancient", 400)] {
+            let dir = get_session_logs_dir(id);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(
+                dir.join("session.json"),
+                format!("{{\"timestamp\": {}}}", now - age_days * day),
+            )
+            .unwrap();
+        }
+        let policy = RetentionConfig {
+            auto_gc: true,
+            max_sessions: 2,
+            max_total_mb: 0,
+            max_age_days: 90,
+        };
+        let report = gc_sessions(&policy, Some("older")).unwrap();
+        let remaining: Vec<String> = list_sessions().unwrap().into_iter().map(|s| s.id).collect();
+        std::env::set_current_dir(old_dir).unwrap();
+
+        // "older" is the running session, so "recent" goes over the limit
+        assert_eq!(report.removed_sessions, vec!["recent", "ancient"]);
+        assert!(report.freed_bytes > 0);
+        assert_eq!(remaining, vec!["new", "older"]);
+    }
+}
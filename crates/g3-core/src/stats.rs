@@ -6,7 +6,10 @@
 use g3_providers::MessageRole;
 use std::time::Duration;
 
+use crate::build_metrics::BuildReport;
 use crate::context_window::ContextWindow;
+use crate::plan::{Plan, StepStatus};
+use crate::tool_transcript::ToolCallMetric;
 use crate::CacheStats;
 
 /// Data required to format agent statistics.
@@ -16,9 +19,12 @@ pub struct AgentStatsSnapshot<'a> {
     pub thinning_events: &'a [usize],
     pub compaction_events: &'a [usize],
     pub first_token_times: &'a [Duration],
-    pub tool_call_metrics: &'a [(String, Duration, bool)],
+    pub tool_call_metrics: &'a [ToolCallMetric],
     pub provider_info: Option<(String, String)>,
     pub cache_stats: &'a CacheStats,
+    pub build_reports: &'a [BuildReport],
+    /// The session's working plan, if the agent wrote one
+    pub plan: Option<Plan>,
 }
 
 impl<'a> AgentStatsSnapshot<'a> {
@@ -36,6 +42,8 @@ impl<'a> AgentStatsSnapshot<'a> {
         self.format_conversation_history(&mut stats);
         self.format_tool_call_metrics(&mut stats);
         self.format_cache_stats(&mut stats);
+        self.format_build_metrics(&mut stats);
+        self.format_plan(&mut stats);
         self.format_provider_info(&mut stats);
 
         stats.push_str(&"=".repeat(60));
@@ -157,7 +165,7 @@ impl<'a> AgentStatsSnapshot<'a> {
         let successful_calls = self
             .tool_call_metrics
             .iter()
-            .filter(|(_, _, success)| *success)
+            .filter(|metric| metric.success)
             .count();
         let failed_calls = self.tool_call_metrics.len() - successful_calls;
 
@@ -171,7 +179,7 @@ impl<'a> AgentStatsSnapshot<'a> {
             let total_duration: Duration = self
                 .tool_call_metrics
                 .iter()
-                .map(|(_, duration, _)| *duration)
+                .map(ToolCallMetric::duration)
                 .sum();
             let avg_duration = total_duration / self.tool_call_metrics.len() as u32;
 
@@ -187,6 +195,46 @@ impl<'a> AgentStatsSnapshot<'a> {
         stats.push('\n');
     }
 
+    fn format_build_metrics(&self, stats: &mut String) {
+        if self.build_reports.is_empty() {
+            return;
+        }
+
+        stats.push_str("🏗️  Build Metrics (vs. baseline):\n");
+        for report in self.build_reports {
+            stats.push_str(&format!("   • {}\n", report.sample.command));
+            for line in report.format().lines() {
+                stats.push_str(&format!("     {}\n", line));
+            }
+        }
+        stats.push('\n');
+    }
+
+    fn format_plan(&self, stats: &mut String) {
+        let Some(plan) = &self.plan else {
+            return;
+        };
+
+        stats.push_str("🗺️  Plan:\n");
+        if let Some(goal) = &plan.goal {
+            stats.push_str(&format!("   • Goal:              {}\n", goal));
+        }
+        stats.push_str(&format!(
+            "   • Steps Done:        {:>10} / {}\n",
+            plan.count(StepStatus::Done),
+            plan.steps.len()
+        ));
+        let blocked = plan.count(StepStatus::Blocked);
+        if blocked > 0 {
+            stats.push_str(&format!("   • Blocked:           {:>10}\n", blocked));
+        }
+        if let Some((i, step)) = plan.current_step() {
+            stats.push_str(&format!("   • Current Step:      {}. {}\n", i + 1, step.title));
+        }
+        stats.push_str(&format!("   • Revisions:         {:>10}\n", plan.revision));
+        stats.push('\n');
+    }
+
     fn format_cache_stats(&self, stats: &mut String) {
         stats.push_str("💾 Prompt Cache Statistics:\n");
         stats.push_str(&format!(
@@ -260,6 +308,8 @@ mod tests {
             tool_call_metrics: &[],
             provider_info: None,
             cache_stats: &cache_stats,
+            build_reports: &[],
+            plan: None,
         };
 
         let stats = snapshot.format();
@@ -287,10 +337,20 @@ mod tests {
             Duration::from_millis(150),
             Duration::from_millis(120),
         ];
+        let metric = |tool: &str, millis: u64, success: bool| {
+            ToolCallMetric::new(
+                tool,
+                &serde_json::json!({}),
+                chrono::Utc::now(),
+                Duration::from_millis(millis),
+                success,
+                0,
+            )
+        };
         let tool_call_metrics = vec![
-            ("shell".to_string(), Duration::from_millis(500), true),
-            ("read_file".to_string(), Duration::from_millis(100), true),
-            ("write_file".to_string(), Duration::from_millis(200), false),
+            metric("shell", 500, true),
+            metric("read_file", 100, true),
+            metric("write_file", 200, false),
         ];
 
         let snapshot = AgentStatsSnapshot {
@@ -301,6 +361,8 @@ mod tests {
             tool_call_metrics: &tool_call_metrics,
             provider_info: Some(("anthropic".to_string(), "claude-3".to_string())),
             cache_stats: &cache_stats,
+            build_reports: &[],
+            plan: None,
         };
 
         let stats = snapshot.format();
@@ -328,4 +390,41 @@ mod tests {
         assert!(stats.contains("Hit Rate:") && stats.contains("60.0%"));
         assert!(stats.contains("Cache Efficiency:"));
     }
+
+    #[test]
+    fn test_format_stats_with_build_metrics() {
+        use crate::build_metrics::{ArtifactSize, BuildSample};
+
+        let context_window = ContextWindow::new(100000);
+        let cache_stats = CacheStats::default();
+        let build_reports = vec![BuildReport {
+            sample: BuildSample {
+                command: "cargo build --release".to_string(),
+                duration_ms: 30_000,
+                artifacts: vec![ArtifactSize {
+                    path: "target/release/g3".to_string(),
+                    bytes: 2048,
+                }],
+                recorded_at: String::new(),
+            },
+            baseline: None,
+            no_op: false,
+        }];
+        let snapshot = AgentStatsSnapshot {
+            context_window: &context_window,
+            thinning_events: &[],
+            compaction_events: &[],
+            first_token_times: &[],
+            tool_call_metrics: &[],
+            provider_info: None,
+            cache_stats: &cache_stats,
+            build_reports: &build_reports,
+            plan: None,
+        };
+
+        let stats = snapshot.format();
+        assert!(stats.contains("Build Metrics"));
+        assert!(stats.contains("cargo build --release"));
+        assert!(stats.contains("target/release/g3 2.0 KB"));
+    }
 }
@@ -5,9 +5,11 @@
 
 use crate::context_window::ContextWindow;
 use crate::streaming_parser::StreamingToolParser;
+use crate::task_result::ToolCallRecord;
 use crate::ToolCall;
-use g3_providers::{CompletionRequest, MessageRole};
+use g3_providers::{CompletionChunk, CompletionRequest, CompletionStream, MessageRole};
 use std::time::{Duration, Instant};
+use tokio_stream::StreamExt;
 use tracing::{debug, error};
 
 pub const MAX_ITERATIONS: usize = 400;
@@ -23,6 +25,10 @@ pub struct StreamingState {
     pub auto_summary_attempts: usize,
     pub assistant_message_added: bool,
     pub turn_accumulated_usage: Option<g3_providers::Usage>,
+    /// Tool calls executed so far in this turn, in order
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// Stalled streams reconnected so far in this turn
+    pub stall_reconnects: u32,
 }
 
 impl StreamingState {
@@ -37,6 +43,8 @@ impl StreamingState {
             auto_summary_attempts: 0,
             assistant_message_added: false,
             turn_accumulated_usage: None,
+            tool_calls: Vec::new(),
+            stall_reconnects: 0,
         }
     }
 
@@ -614,6 +622,53 @@ pub fn is_connection_error(error_msg: &str) -> bool {
         || error_msg.contains("body error")
 }
 
+// =============================================================================
+// Stall Detection
+// =============================================================================
+
+/// Sent after a stalled response is reconnected. The partial response is
+/// already in the context, so the model only needs to pick up from there.
+pub const STALL_RESUME_PROMPT: &str =
+    "Your previous response was cut off by a stalled connection. \
+     Continue exactly where it stopped, without repeating what you already wrote.";
+
+/// Stall timeout for `agent.stream_stall_timeout_secs` (0 disables the watchdog).
+pub fn stall_timeout(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Wait for the next chunk, yielding a "Stream stalled" error if nothing
+/// arrives within `stall_timeout`. Providers send a keep-alive chunk for every
+/// network frame (pings, thinking deltas, ...); those restart the timer and
+/// are skipped.
+pub async fn next_chunk(
+    stream: &mut CompletionStream,
+    stall_timeout: Option<Duration>,
+) -> Option<anyhow::Result<CompletionChunk>> {
+    loop {
+        let chunk = match stall_timeout {
+            None => stream.next().await,
+            Some(timeout) => match tokio::time::timeout(timeout, stream.next()).await {
+                Ok(chunk) => chunk,
+                Err(_) => {
+                    return Some(Err(anyhow::anyhow!(
+                        "Stream stalled: no data for {}s",
+                        timeout.as_secs()
+                    )))
+                }
+            },
+        };
+        if !matches!(&chunk, Some(Ok(c)) if c.is_keepalive()) {
+            return chunk;
+        }
+    }
+}
+
+/// Check if an error came from the stall watchdog in `next_chunk`
+pub fn is_stall_error(error_msg: &str) -> bool {
+    error_msg.starts_with("Stream stalled")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -625,6 +680,49 @@ mod tests {
         assert_eq!(clean_llm_tokens("[/INST]response"), "response");
     }
 
+    #[tokio::test]
+    async fn test_next_chunk_reports_stall() {
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let mut stream = CompletionStream::new(rx);
+
+        tx.send(Ok(g3_providers::make_text_chunk("hi".to_string())))
+            .await
+            .unwrap();
+        let chunk = next_chunk(&mut stream, stall_timeout(1)).await.unwrap();
+        assert_eq!(chunk.unwrap().content, "hi");
+
+        // The sender is still alive but silent
+        let stalled = next_chunk(&mut stream, Some(Duration::from_millis(10)))
+            .await
+            .unwrap();
+        assert!(is_stall_error(&stalled.unwrap_err().to_string()));
+        assert_eq!(stall_timeout(0), None);
+
+        drop(tx);
+        assert!(next_chunk(&mut stream, None).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_next_chunk_keepalives_restart_the_timer() {
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        let mut stream = CompletionStream::new(rx);
+
+        // Quiet for longer than the timeout in total, but never between frames
+        tokio::spawn(async move {
+            for _ in 0..15 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                let _ = tx.send(Ok(g3_providers::make_keepalive_chunk())).await;
+            }
+            let _ = tx
+                .send(Ok(g3_providers::make_text_chunk("done".to_string())))
+                .await;
+        });
+        let chunk = next_chunk(&mut stream, Some(Duration::from_millis(100)))
+            .await
+            .unwrap();
+        assert_eq!(chunk.unwrap().content, "done");
+    }
+
     #[test]
     fn test_format_duration() {
         assert_eq!(format_duration(Duration::from_millis(500)), "500ms");
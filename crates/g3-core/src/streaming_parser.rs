@@ -9,7 +9,33 @@
 //! To prevent false positives from JSON examples in prose, tool calls are only
 //! recognized when they appear "on their own line" - either at the start of the
 //! buffer or preceded by a newline (with optional whitespace).
+//!
+//! # Event API
+//!
+//! `process_chunk` returns completed tool calls and leaves text handling to
+//! the caller. Programs that want a single ordered stream instead can use
+//! `process_chunk_events`, which splits the response into `ToolParserEvent`s:
+//! prose as `TextDelta`, and each tool call as `ToolStarted` followed by
+//! `ToolCompleted`. The JSON of a tool call is never included in `TextDelta`.
+//!
+//! ```rust,ignore
+//! let mut parser = StreamingToolParser::new();
+//! while let Some(chunk) = stream.next().await {
+//!     for event in parser.process_chunk_events(&chunk?) {
+//!         match event {
+//!             ToolParserEvent::TextDelta(text) => print!("{}", text),
+//!             ToolParserEvent::ToolStarted { tool } => println!("\n⚙️ {}...", tool),
+//!             ToolParserEvent::ToolCompleted(call) => run_tool(call).await,
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Text that could still turn out to be the start of a tool call is held back
+//! until it is resolved, so `TextDelta`s can lag the stream by up to a line.
 
+use regex::Regex;
+use std::sync::OnceLock;
 use tracing::debug;
 
 use crate::ToolCall;
@@ -22,6 +48,37 @@ const TOOL_CALL_PATTERNS: &[&str] = &[
     r#"{ "tool" :"#,
 ];
 
+/// Bytes at the start of a streaming tool call scanned for early arguments.
+const PARTIAL_SCAN_BYTES: usize = 2048;
+
+/// A `"key": "value"` pair whose string value has finished streaming.
+fn complete_string_field_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#""([A-Za-z_][A-Za-z0-9_]*)"\s*:\s*"((?:[^"\\]|\\.)*)""#).unwrap()
+    })
+}
+
+/// A JSON tool call that is still streaming.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialToolCall {
+    pub tool: String,
+    /// String arguments whose values have finished streaming
+    pub args: serde_json::Map<String, serde_json::Value>,
+}
+
+/// An event produced by `StreamingToolParser::process_chunk_events`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolParserEvent {
+    /// Response text outside of tool calls
+    TextDelta(String),
+    /// A tool call began streaming. If the JSON turns out to be malformed, no
+    /// `ToolCompleted` follows and its text is emitted as a `TextDelta`.
+    ToolStarted { tool: String },
+    /// A tool call finished streaming and parsed successfully
+    ToolCompleted(ToolCall),
+}
+
 // ============================================================================
 // Code Fence Tracking
 // ============================================================================
@@ -301,6 +358,13 @@ pub struct StreamingToolParser {
     json_tool_start: Option<usize>,
     // Code fence tracking (to skip JSON examples in ``` blocks)
     fence_tracker: CodeFenceTracker,
+    // Event API state
+    /// Parsed JSON tool calls with their byte range in the buffer
+    json_tool_calls: Vec<(usize, usize, ToolCall)>,
+    /// Buffer position up to which text has been emitted or skipped
+    text_emitted: usize,
+    /// Tool announced by a `ToolStarted` event that hasn't completed yet
+    started_tool: Option<String>,
 }
 
 impl Default for StreamingToolParser {
@@ -318,6 +382,9 @@ impl StreamingToolParser {
             in_json_tool_call: false,
             json_tool_start: None,
             fence_tracker: CodeFenceTracker::new(),
+            json_tool_calls: Vec::new(),
+            text_emitted: 0,
+            started_tool: None,
         }
     }
 
@@ -351,7 +418,10 @@ impl StreamingToolParser {
                         "Found {} JSON tool calls in buffer at stream end",
                         all_tools.len()
                     );
-                    completed_tools.extend(all_tools);
+                    for (start, end, tool_call) in all_tools {
+                        self.record_json_tool_call(start, end, &tool_call);
+                        completed_tools.push(tool_call);
+                    }
                 }
             }
         }
@@ -407,6 +477,7 @@ impl StreamingToolParser {
                     if let Some(tool_call) = self.try_parse_tool_call_json(json_str) {
                         self.in_json_tool_call = false;
                         self.json_tool_start = None;
+                        self.record_json_tool_call(start_pos, start_pos + end_pos + 1, &tool_call);
                         return Some(tool_call);
                     }
 
@@ -427,8 +498,9 @@ impl StreamingToolParser {
         None
     }
 
-    /// Parse all JSON tool calls from the accumulated buffer (used at stream end).
-    fn parse_all_json_tool_calls(&self) -> Vec<ToolCall> {
+    /// Parse all JSON tool calls from the accumulated buffer (used at stream end),
+    /// with the byte range of each.
+    fn parse_all_json_tool_calls(&self) -> Vec<(usize, usize, ToolCall)> {
         let mut tool_calls = Vec::new();
         let mut search_start = 0;
         let fence_ranges = find_code_fence_ranges(&self.text_buffer);
@@ -456,7 +528,7 @@ impl StreamingToolParser {
             let json_str = &json_text[..=end_pos];
             if let Some(tool_call) = self.try_parse_tool_call_json(json_str) {
                 debug!("Found tool call at position {}: {:?}", abs_start, tool_call.tool);
-                tool_calls.push(tool_call);
+                tool_calls.push((abs_start, abs_start + end_pos + 1, tool_call));
             }
 
             search_start = abs_start + end_pos + 1;
@@ -465,6 +537,14 @@ impl StreamingToolParser {
         tool_calls
     }
 
+    /// Remember where a JSON tool call sits in the buffer, for the event API.
+    /// Calls found again by the end-of-stream rescan are ignored.
+    fn record_json_tool_call(&mut self, start: usize, end: usize, tool_call: &ToolCall) {
+        if !self.json_tool_calls.iter().any(|(s, _, _)| *s == start) {
+            self.json_tool_calls.push((start, end, tool_call.clone()));
+        }
+    }
+
     fn try_parse_tool_call_json(&self, json_str: &str) -> Option<ToolCall> {
         let tool_call: ToolCall = serde_json::from_str(json_str).ok()?;
         let args_obj = tool_call.args.as_object()?;
@@ -476,6 +556,112 @@ impl StreamingToolParser {
         Some(tool_call)
     }
 
+    // --- Event API ---
+
+    /// Process a streaming chunk and return what it contributed to the
+    /// response as ordered events. See the module docs.
+    pub fn process_chunk_events(
+        &mut self,
+        chunk: &g3_providers::CompletionChunk,
+    ) -> Vec<ToolParserEvent> {
+        let known_calls = self.json_tool_calls.len();
+        let was_in_json_tool_call = self.in_json_tool_call;
+        self.process_chunk(chunk);
+
+        let mut new_calls = self.json_tool_calls[known_calls..].to_vec();
+        new_calls.sort_by_key(|(start, _, _)| *start);
+        if was_in_json_tool_call && !self.in_json_tool_call && new_calls.is_empty() {
+            // The JSON was invalidated; its text is emitted below
+            self.started_tool = None;
+        }
+
+        let mut events = Vec::new();
+        let mut pos = self.text_emitted;
+        for (start, end, tool_call) in new_calls {
+            if start > pos {
+                self.push_text_event(&mut events, pos, start);
+            }
+            self.push_tool_events(&mut events, tool_call);
+            pos = pos.max(end);
+            // Resume scanning for tool calls after this one
+            self.last_consumed_position = self.last_consumed_position.max(end);
+        }
+        let limit = if self.message_stopped {
+            self.text_buffer.len()
+        } else {
+            self.text_safe_end()
+        };
+        if limit > pos {
+            self.push_text_event(&mut events, pos, limit);
+            pos = limit;
+        }
+        self.text_emitted = pos;
+
+        if self.in_json_tool_call && self.started_tool.is_none() {
+            if let Some(partial) = self.partial_tool_call() {
+                events.push(ToolParserEvent::ToolStarted {
+                    tool: partial.tool.clone(),
+                });
+                self.started_tool = Some(partial.tool);
+            }
+        }
+
+        if let Some(tool) = &chunk.tool_call_streaming {
+            if self.started_tool.is_none() {
+                events.push(ToolParserEvent::ToolStarted { tool: tool.clone() });
+                self.started_tool = Some(tool.clone());
+            }
+        }
+        for tool_call in chunk.tool_calls.iter().flatten() {
+            let tool_call = ToolCall {
+                tool: tool_call.tool.clone(),
+                args: tool_call.args.clone(),
+            };
+            self.push_tool_events(&mut events, tool_call);
+        }
+
+        events
+    }
+
+    /// End of the text that can be emitted without risking emitting part of a
+    /// tool call: the start of a streaming tool call, or the start of the last
+    /// line if it could still become one.
+    fn text_safe_end(&self) -> usize {
+        if self.in_json_tool_call {
+            if let Some(start) = self.json_tool_start {
+                return start;
+            }
+        }
+        let line_start = self.text_buffer.rfind('\n').map_or(0, |i| i + 1);
+        let tail = self.text_buffer[line_start..].trim_start();
+        let could_be_tool_call = !tail.is_empty()
+            && TOOL_CALL_PATTERNS
+                .iter()
+                .any(|pattern| pattern.starts_with(tail) || tail.starts_with(pattern));
+        if could_be_tool_call {
+            line_start
+        } else {
+            self.text_buffer.len()
+        }
+    }
+
+    fn push_text_event(&self, events: &mut Vec<ToolParserEvent>, start: usize, end: usize) {
+        let text = &self.text_buffer[start..end];
+        if !text.is_empty() {
+            events.push(ToolParserEvent::TextDelta(text.to_string()));
+        }
+    }
+
+    /// Push `ToolCompleted`, preceded by `ToolStarted` unless already announced.
+    fn push_tool_events(&mut self, events: &mut Vec<ToolParserEvent>, tool_call: ToolCall) {
+        if self.started_tool.take().as_deref() != Some(tool_call.tool.as_str()) {
+            events.push(ToolParserEvent::ToolStarted {
+                tool: tool_call.tool.clone(),
+            });
+        }
+        events.push(ToolParserEvent::ToolCompleted(tool_call));
+    }
+
     // --- Public Accessors ---
     pub fn get_text_content(&self) -> &str {
         &self.text_buffer
@@ -489,6 +675,37 @@ impl StreamingToolParser {
         }
     }
 
+    /// The JSON tool call currently being streamed: its tool name and the
+    /// string arguments that are already complete.
+    ///
+    /// Lets the agent start work (e.g. prefetching a file) before the call
+    /// finishes streaming. Only the start of the call is scanned.
+    pub fn partial_tool_call(&self) -> Option<PartialToolCall> {
+        if !self.in_json_tool_call {
+            return None;
+        }
+        let start = self.json_tool_start?;
+        let mut end = (start + PARTIAL_SCAN_BYTES).min(self.text_buffer.len());
+        while !self.text_buffer.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let mut tool = None;
+        let mut args = serde_json::Map::new();
+        for caps in complete_string_field_regex().captures_iter(&self.text_buffer[start..end]) {
+            let Ok(value) = serde_json::from_str::<String>(&format!("\"{}\"", &caps[2])) else {
+                continue;
+            };
+            if &caps[1] == "tool" && tool.is_none() {
+                tool = Some(value);
+            } else {
+                args.insert(caps[1].to_string(), serde_json::Value::String(value));
+            }
+        }
+
+        Some(PartialToolCall { tool: tool?, args })
+    }
+
     pub fn is_message_stopped(&self) -> bool {
         self.message_stopped
     }
@@ -546,6 +763,9 @@ impl StreamingToolParser {
         self.in_json_tool_call = false;
         self.json_tool_start = None;
         self.fence_tracker.reset();
+        self.json_tool_calls.clear();
+        self.text_emitted = 0;
+        self.started_tool = None;
     }
 
     // --- Static Methods (for external use) ---
@@ -572,6 +792,116 @@ impl StreamingToolParser {
 mod tests {
     use super::*;
 
+    fn text_chunk(content: &str) -> g3_providers::CompletionChunk {
+        g3_providers::CompletionChunk {
+            content: content.to_string(),
+            finished: false,
+            tool_calls: None,
+            usage: None,
+            stop_reason: None,
+            tool_call_streaming: None,
+        }
+    }
+
+    fn collect_events(parser: &mut StreamingToolParser, chunks: &[&str]) -> Vec<ToolParserEvent> {
+        let mut events: Vec<ToolParserEvent> = chunks
+            .iter()
+            .flat_map(|chunk| parser.process_chunk_events(&text_chunk(chunk)))
+            .collect();
+        let mut finished = text_chunk("");
+        finished.finished = true;
+        events.extend(parser.process_chunk_events(&finished));
+        events
+    }
+
+    #[test]
+    fn test_events_for_json_tool_calls() {
+        let mut parser = StreamingToolParser::new();
+        let events = collect_events(
+            &mut parser,
+            &[
+                "Let me check.\n",
+                r#"{"tool": "shell", "#,
+                r#""args": {"command": "ls"}}"#,
+                "\nNow the file:\n",
+                r#"{"tool": "read_file", "args": {"file_path": "a.rs"}}"#,
+                "\nDone.",
+            ],
+        );
+
+        let shell = ToolCall {
+            tool: "shell".to_string(),
+            args: serde_json::json!({"command": "ls"}),
+        };
+        let read_file = ToolCall {
+            tool: "read_file".to_string(),
+            args: serde_json::json!({"file_path": "a.rs"}),
+        };
+        let text = |s: &str| ToolParserEvent::TextDelta(s.to_string());
+        let started = |tool: &str| ToolParserEvent::ToolStarted {
+            tool: tool.to_string(),
+        };
+        assert_eq!(
+            events,
+            vec![
+                text("Let me check.\n"),
+                started("shell"),
+                ToolParserEvent::ToolCompleted(shell),
+                text("\nNow the file:\n"),
+                started("read_file"),
+                ToolParserEvent::ToolCompleted(read_file),
+                text("\nDone."),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_events_for_native_tool_calls() {
+        let mut parser = StreamingToolParser::new();
+        let mut chunk = text_chunk("Running it.");
+        chunk.tool_calls = Some(vec![g3_providers::ToolCall {
+            id: "call_1".to_string(),
+            tool: "shell".to_string(),
+            args: serde_json::json!({"command": "ls"}),
+        }]);
+
+        assert_eq!(
+            parser.process_chunk_events(&chunk),
+            vec![
+                ToolParserEvent::TextDelta("Running it.".to_string()),
+                ToolParserEvent::ToolStarted {
+                    tool: "shell".to_string()
+                },
+                ToolParserEvent::ToolCompleted(ToolCall {
+                    tool: "shell".to_string(),
+                    args: serde_json::json!({"command": "ls"}),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial_tool_call() {
+        let mut parser = StreamingToolParser::new();
+        parser.process_chunk(&text_chunk(
+            "Let me look.\n{\"tool\": \"read_file\", \"args\": {\"file_pa",
+        ));
+        let partial = parser.partial_tool_call().unwrap();
+        assert_eq!(partial.tool, "read_file");
+        assert!(partial.args.is_empty());
+
+        parser.process_chunk(&text_chunk("th\": \"src/a \\\"b\\\".rs\", \"start\": 1"));
+        let partial = parser.partial_tool_call().unwrap();
+        assert_eq!(partial.tool, "read_file");
+        assert_eq!(partial.args["file_path"], "src/a \"b\".rs");
+        assert!(!partial.args.contains_key("start"));
+
+        // No partial call once the tool call completes
+        let completed = parser.process_chunk(&text_chunk("}}\n"));
+        assert_eq!(completed.len(), 1);
+        assert!(parser.partial_tool_call().is_none());
+    }
+
     #[test]
     fn test_find_complete_json_object_end_simple() {
         let text = r#"{"tool":"shell","args":{"command":"ls"}}"#;
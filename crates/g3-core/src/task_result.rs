@@ -1,5 +1,101 @@
+use g3_providers::Usage;
+use serde::{Deserialize, Serialize};
+
+use crate::error_handling::{classify_error, ErrorType, RecoverableError};
+use crate::telemetry::TurnTelemetry;
 use crate::ContextWindow;
 
+/// Tools whose `file_path` argument names a file they modify.
+const FILE_EDITING_TOOLS: &[&str] = &["write_file", "str_replace", "edit_lines"];
+
+/// Tools whose `edits[].file_path` arguments name the files they modify.
+const MULTI_FILE_EDITING_TOOLS: &[&str] = &["multi_edit"];
+
+/// The files a call to the file editing tool `tool` with `args` modifies
+/// (none for other tools).
+pub(crate) fn files_modified_by<'a>(tool: &str, args: &'a serde_json::Value) -> Vec<&'a str> {
+    if FILE_EDITING_TOOLS.contains(&tool) {
+        args.get("file_path")
+            .or_else(|| args.get("path"))
+            .and_then(|v| v.as_str())
+            .into_iter()
+            .collect()
+    } else if MULTI_FILE_EDITING_TOOLS.contains(&tool) {
+        args.get("edits")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|edit| edit.get("file_path").and_then(|v| v.as_str()))
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// A tool call executed during a task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    pub tool: String,
+    pub args: serde_json::Value,
+    /// False if the tool reported an error (its result contained ❌)
+    pub success: bool,
+    pub duration_ms: u64,
+}
+
+/// Why a task did not complete normally
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// Cancelled through the cancellation token
+    Cancelled,
+    /// The tool loop hit its iteration limit before the model finished
+    MaxIterations,
+    /// The conversation no longer fits in the model's context window
+    ContextLengthExceeded,
+    /// The provider kept rate limiting requests
+    RateLimited,
+    /// Network errors talking to the provider
+    Network,
+    /// Provider-side errors (5xx, overloaded model, timeouts)
+    Provider,
+    /// Anything else
+    Other,
+}
+
+impl FailureCategory {
+    /// Categorize an error returned by `Agent::execute_task*`.
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        if error.to_string().contains("cancelled") {
+            return Self::Cancelled;
+        }
+        match classify_error(error) {
+            ErrorType::Recoverable(RecoverableError::RateLimit) => Self::RateLimited,
+            ErrorType::Recoverable(RecoverableError::NetworkError) => Self::Network,
+            ErrorType::Recoverable(
+                RecoverableError::ServerError
+                | RecoverableError::ModelBusy
+                | RecoverableError::Timeout,
+            ) => Self::Provider,
+            ErrorType::Recoverable(
+                RecoverableError::TokenLimit | RecoverableError::ContextLengthExceeded,
+            ) => Self::ContextLengthExceeded,
+            ErrorType::NonRecoverable => Self::Other,
+        }
+    }
+}
+
+/// Serializable summary of a task, for wrappers and library users
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskReport {
+    /// The final block of the response
+    pub summary: String,
+    pub files_changed: Vec<String>,
+    pub tool_calls: Vec<ToolCallRecord>,
+    pub usage: Option<Usage>,
+    pub telemetry: Option<TurnTelemetry>,
+    pub failure: Option<FailureCategory>,
+}
+
 /// Result of a task execution containing both the response and the context window
 #[derive(Debug, Clone)]
 pub struct TaskResult {
@@ -7,6 +103,14 @@ pub struct TaskResult {
     pub response: String,
     /// The complete context window at the time of completion
     pub context_window: ContextWindow,
+    /// Timing, token and tool statistics for the turn, if it was streamed
+    pub telemetry: Option<TurnTelemetry>,
+    /// Tool calls executed during the task, in order
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// Token usage summed over the task's LLM calls, if the provider reported it
+    pub usage: Option<Usage>,
+    /// Set when the task ended without the model finishing (e.g. iteration limit)
+    pub failure: Option<FailureCategory>,
 }
 
 impl TaskResult {
@@ -14,6 +118,43 @@ impl TaskResult {
         Self {
             response,
             context_window,
+            telemetry: None,
+            tool_calls: Vec::new(),
+            usage: None,
+            failure: None,
+        }
+    }
+
+    /// Attach the turn's telemetry record
+    pub fn with_telemetry(mut self, telemetry: TurnTelemetry) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Files modified by successful file editing tools (write_file,
+    /// str_replace, multi_edit, edit_lines), in first-modified order.
+    /// Changes made through the shell tool are not included.
+    pub fn files_changed(&self) -> Vec<String> {
+        let mut files: Vec<String> = Vec::new();
+        for call in self.tool_calls.iter().filter(|c| c.success) {
+            for path in files_modified_by(&call.tool, &call.args) {
+                if !files.iter().any(|f| f == path) {
+                    files.push(path.to_string());
+                }
+            }
+        }
+        files
+    }
+
+    /// Build the serializable report for this task
+    pub fn report(&self) -> TaskReport {
+        TaskReport {
+            summary: self.extract_last_block(),
+            files_changed: self.files_changed(),
+            tool_calls: self.tool_calls.clone(),
+            usage: self.usage.clone(),
+            telemetry: self.telemetry.clone(),
+            failure: self.failure,
         }
     }
 
@@ -171,4 +312,48 @@ mod tests {
         let result = TaskResult::new(empty_response, context_window);
         assert_eq!(result.extract_final_output(), "");
     }
+
+    #[test]
+    fn test_files_changed_and_report() {
+        let call = |tool: &str, args: serde_json::Value, success: bool| ToolCallRecord {
+            tool: tool.to_string(),
+            args,
+            success,
+            duration_ms: 5,
+        };
+        let mut result = TaskResult::new("Working\n\nDone".to_string(), ContextWindow::new(1000));
+        result.tool_calls = vec![
+            call("read_file", serde_json::json!({"file_path": "a.rs"}), true),
+            call("write_file", serde_json::json!({"file_path": "b.rs"}), true),
+            call(
+                "str_replace",
+                serde_json::json!({"file_path": "c.rs"}),
+                false,
+            ),
+            call("edit_lines", serde_json::json!({"file_path": "b.rs"}), true),
+            call("write_file", serde_json::json!({"path": "d.rs"}), true),
+            call(
+                "multi_edit",
+                serde_json::json!({"edits": [{"file_path": "e.rs"}, {"file_path": "b.rs"}]}),
+                true,
+            ),
+        ];
+        assert_eq!(result.files_changed(), vec!["b.rs", "d.rs", "e.rs"]);
+
+        let report = result.report();
+        assert_eq!(report.summary, "Done");
+        assert_eq!(report.tool_calls.len(), 6);
+        assert_eq!(report.failure, None);
+    }
+
+    #[test]
+    fn test_failure_category_from_error() {
+        let cancelled = anyhow::anyhow!("Operation cancelled by user");
+        assert_eq!(
+            FailureCategory::from_error(&cancelled),
+            FailureCategory::Cancelled
+        );
+        let other = anyhow::anyhow!("invalid configuration");
+        assert_eq!(FailureCategory::from_error(&other), FailureCategory::Other);
+    }
 }
@@ -0,0 +1,173 @@
+//! Per-turn telemetry.
+//!
+//! Each completed turn produces a `TurnTelemetry` record. It is attached to the
+//! `TaskResult`, rendered as the timing footer, passed to
+//! `UiWriter::emit_turn_telemetry`, and appended as a JSON line to
+//! `.g3/sessions/<id>/telemetry.jsonl`, so wrappers can read typed values
+//! instead of parsing the footer.
+
+use g3_config::TelemetryConfig;
+use g3_providers::Usage;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::streaming::format_timing_footer;
+
+/// Timing, token and tool statistics for one turn.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnTelemetry {
+    /// Wall-clock time of the whole turn, including tool execution
+    pub duration_ms: u64,
+    /// Time to the first streamed token
+    pub ttft_ms: u64,
+    /// Token usage summed over every LLM call in the turn, if reported
+    pub prompt_tokens: Option<u32>,
+    pub completion_tokens: Option<u32>,
+    pub total_tokens: Option<u32>,
+    /// Estimated cost, if token prices are configured under `[telemetry]`
+    pub cost_usd: Option<f64>,
+    /// Tools executed during the turn, in order
+    pub tools_run: Vec<String>,
+    /// Context window usage at the end of the turn (0-100)
+    pub context_percentage: f32,
+}
+
+/// Wrapper adding the event name when serialized as a JSON event.
+#[derive(Serialize)]
+struct TelemetryEvent<'a> {
+    event: &'static str,
+    #[serde(flatten)]
+    telemetry: &'a TurnTelemetry,
+}
+
+impl TurnTelemetry {
+    pub fn new(
+        elapsed: Duration,
+        ttft: Duration,
+        usage: Option<&Usage>,
+        tools_run: Vec<String>,
+        context_percentage: f32,
+        config: &TelemetryConfig,
+    ) -> Self {
+        let cost_usd = match (
+            usage,
+            config.input_cost_per_mtok,
+            config.output_cost_per_mtok,
+        ) {
+            (Some(u), Some(input), Some(output)) => Some(
+                (u.prompt_tokens as f64 * input + u.completion_tokens as f64 * output)
+                    / 1_000_000.0,
+            ),
+            _ => None,
+        };
+
+        Self {
+            duration_ms: elapsed.as_millis() as u64,
+            ttft_ms: ttft.as_millis() as u64,
+            prompt_tokens: usage.map(|u| u.prompt_tokens),
+            completion_tokens: usage.map(|u| u.completion_tokens),
+            total_tokens: usage.map(|u| u.total_tokens),
+            cost_usd,
+            tools_run,
+            context_percentage,
+        }
+    }
+
+    /// The human-readable footer shown after the response.
+    pub fn footer(&self) -> String {
+        format_timing_footer(
+            Duration::from_millis(self.duration_ms),
+            Duration::from_millis(self.ttft_ms),
+            self.total_tokens,
+            self.context_percentage,
+        )
+    }
+
+    /// Serialize as a single-line `turn_telemetry` JSON event.
+    pub fn to_json_event(&self) -> String {
+        serde_json::to_string(&TelemetryEvent {
+            event: "turn_telemetry",
+            telemetry: self,
+        })
+        .unwrap_or_default()
+    }
+
+    /// Append this record as a JSON line to `path`.
+    pub fn append_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(file, "{}", self.to_json_event())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage() -> Usage {
+        Usage {
+            prompt_tokens: 1_000,
+            completion_tokens: 200,
+            total_tokens: 1_200,
+            cache_creation_tokens: 0,
+            cache_read_tokens: 0,
+        }
+    }
+
+    #[test]
+    fn test_footer_matches_timing_format() {
+        let telemetry = TurnTelemetry::new(
+            Duration::from_millis(12_300),
+            Duration::from_millis(800),
+            Some(&usage()),
+            vec!["shell".to_string()],
+            42.0,
+            &TelemetryConfig::default(),
+        );
+        assert_eq!(
+            telemetry.footer(),
+            format_timing_footer(
+                Duration::from_millis(12_300),
+                Duration::from_millis(800),
+                Some(1_200),
+                42.0
+            )
+        );
+        assert_eq!(telemetry.cost_usd, None);
+    }
+
+    #[test]
+    fn test_json_event_and_cost() {
+        let config = TelemetryConfig {
+            input_cost_per_mtok: Some(3.0),
+            output_cost_per_mtok: Some(15.0),
+            ..Default::default()
+        };
+        let telemetry = TurnTelemetry::new(
+            Duration::from_secs(2),
+            Duration::from_millis(500),
+            Some(&usage()),
+            vec!["read_file".to_string(), "shell".to_string()],
+            10.0,
+            &config,
+        );
+        assert!((telemetry.cost_usd.unwrap() - 0.006).abs() < 1e-9);
+
+        let event: serde_json::Value = serde_json::from_str(&telemetry.to_json_event()).unwrap();
+        assert_eq!(event["event"], "turn_telemetry");
+        assert_eq!(event["duration_ms"], 2000);
+        assert_eq!(event["ttft_ms"], 500);
+        assert_eq!(event["total_tokens"], 1200);
+        assert_eq!(event["tools_run"][1], "shell");
+
+        let round_trip: TurnTelemetry = serde_json::from_value(event).unwrap();
+        assert_eq!(round_trip, telemetry);
+    }
+}
@@ -9,18 +9,87 @@ use serde_json::json;
 
 /// Configuration for which optional tool sets to enable
 #[derive(Debug, Clone, Copy, Default)]
-pub struct ToolConfig {
+pub struct ToolConfig<'a> {
     pub webdriver: bool,
     pub computer_control: bool,
+    /// sql_query, for databases under `[sql.connections]`
+    pub sql: bool,
+    /// k8s, with `k8s.enabled`
+    pub k8s: bool,
+    /// docker, with `docker.enabled`
+    pub docker: bool,
+    /// rename_symbol, for language servers under `[lsp.servers]`
+    pub lsp: bool,
+    /// notebook_run, with `notebook.enabled`
+    pub notebook: bool,
+    /// cargo, with `cargo.enabled`
+    pub cargo: bool,
+    /// changelog, with `changelog.enabled` or `changelog.auto_update`
+    pub changelog: bool,
+    /// node_eval, with `node_eval.enabled`
+    pub node_eval: bool,
+    /// python, with `python.enabled`
+    pub python: bool,
+    /// i18n_extract, with `i18n.enabled`
+    pub i18n: bool,
     pub exclude_research: bool,
+    /// Tools of the connected MCP servers (see `mcp_client`)
+    pub mcp_tools: &'a [Tool],
 }
 
-impl ToolConfig {
+impl<'a> ToolConfig<'a> {
     pub fn new(webdriver: bool, computer_control: bool) -> Self {
         Self {
             webdriver,
             computer_control,
+            sql: false,
+            k8s: false,
+            docker: false,
+            lsp: false,
+            notebook: false,
+            cargo: false,
+            changelog: false,
+            node_eval: false,
+            python: false,
+            i18n: false,
             exclude_research: false,
+            mcp_tools: &[],
+        }
+    }
+
+    /// Every tool set, e.g. to look up any tool's schema.
+    pub fn all() -> Self {
+        Self {
+            sql: true,
+            k8s: true,
+            docker: true,
+            lsp: true,
+            notebook: true,
+            cargo: true,
+            changelog: true,
+            node_eval: true,
+            python: true,
+            i18n: true,
+            ..Self::new(true, true)
+        }
+    }
+
+    /// Enable the tools whose config sections are set up: webdriver,
+    /// computer control, databases, k8s, docker, language servers, notebook
+    /// kernels, cargo, the changelog, node_eval, python and i18n extraction.
+    pub fn from_config(config: &g3_config::Config) -> Self {
+        Self {
+            sql: !config.sql.connections.is_empty(),
+            k8s: config.k8s.enabled,
+            docker: config.docker.enabled,
+            lsp: !config.lsp.servers.is_empty(),
+            notebook: config.notebook.enabled,
+            cargo: config.cargo.enabled,
+            changelog: config.changelog.tool_enabled(),
+            node_eval: config.node_eval.enabled,
+            python: config.python.enabled,
+            i18n: config.i18n.enabled,
+            ..Self::new(config.webdriver.enabled, config.computer_control.enabled)
         }
     }
 
@@ -30,6 +99,12 @@ impl ToolConfig {
         self.exclude_research = true;
         self
     }
+
+    /// Add the tools of MCP servers after g3's own.
+    pub fn with_mcp_tools(mut self, mcp_tools: &'a [Tool]) -> Self {
+        self.mcp_tools = mcp_tools;
+        self
+    }
 }
 
 /// Create tool definitions for native tool calling providers.
@@ -39,10 +114,43 @@ impl ToolConfig {
 pub fn create_tool_definitions(config: ToolConfig) -> Vec<Tool> {
     let mut tools = create_core_tools(config.exclude_research);
 
+    if config.sql && cfg!(feature = "sql") {
+        tools.extend(create_sql_tools());
+    }
+    if config.k8s {
+        tools.extend(create_k8s_tools());
+    }
+    if config.docker {
+        tools.extend(create_docker_tools());
+    }
+    if config.lsp {
+        tools.extend(create_lsp_tools());
+    }
+    if config.notebook {
+        tools.extend(create_notebook_tools());
+    }
+    if config.cargo {
+        tools.extend(create_cargo_tools());
+    }
+    if config.changelog {
+        tools.extend(create_changelog_tools());
+    }
+    if config.node_eval {
+        tools.extend(create_node_eval_tools());
+    }
+    if config.python {
+        tools.extend(create_python_tools());
+    }
+    if config.i18n {
+        tools.extend(create_i18n_tools());
+    }
+
     if config.webdriver {
         tools.extend(create_webdriver_tools());
     }
 
+    tools.extend_from_slice(config.mcp_tools);
+
     tools
 }
 
@@ -51,13 +159,21 @@ fn create_core_tools(exclude_research: bool) -> Vec<Tool> {
     let mut tools = vec![
         Tool {
             name: "shell".to_string(),
-            description: "Execute shell commands in the current working directory. Do NOT prefix commands with `cd <path> &&` - commands already run in the workspace.".to_string(),
+            description: "Execute shell commands in the current working directory. Do NOT prefix commands with `cd <path> &&` - commands already run in the workspace; to run in a subdirectory, pass working_dir.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "command": {
                         "type": "string",
                         "description": "The shell command to execute"
+                    },
+                    "unsandboxed": {
+                        "type": "boolean",
+                        "description": "Run outside the OS sandbox, after the user approves. Only for a command the sandbox blocked that must reach outside the workspace."
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory to run in, relative to the workspace (e.g. a package of a monorepo). Must be inside the workspace."
                     }
                 },
                 "required": ["command"]
@@ -79,7 +195,7 @@ fn create_core_tools(exclude_research: bool) -> Vec<Tool> {
                     },
                     "working_dir": {
                         "type": "string",
-                        "description": "Optional working directory. Defaults to current directory if not specified."
+                        "description": "Directory to run in, relative to the workspace. Must be inside the workspace. Defaults to the workspace."
                     }
                 },
                 "required": ["name", "command"]
@@ -102,11 +218,68 @@ fn create_core_tools(exclude_research: bool) -> Vec<Tool> {
                     "end": {
                         "type": "integer",
                         "description": "Ending character position (0-indexed, EXCLUSIVE). If omitted, reads to end of file."
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory a relative file_path is resolved against, relative to the workspace. Must be inside the workspace."
                     }
                 },
                 "required": ["file_path"]
             }),
         },
+        Tool {
+            name: "read_many_files".to_string(),
+            description: "Read several files (up to 20) in one call, returned concatenated under `===== path =====` separators. Each file is capped at max_bytes_per_file and all files together at max_total_bytes; truncated files are marked. Prefer this over several read_file calls when you need a handful of small files (configs, manifests, related modules).".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_paths": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "maxItems": 20,
+                        "description": "Paths of the files to read, in order"
+                    },
+                    "max_bytes_per_file": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "default": 32768,
+                        "description": "Bytes read from each file at most"
+                    },
+                    "max_total_bytes": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "default": 131072,
+                        "description": "Bytes read from all files together at most; later files are skipped once it is reached"
+                    }
+                },
+                "required": ["file_paths"]
+            }),
+        },
+        Tool {
+            name: "read_output".to_string(),
+            description: "Page through output too large to return whole (from shell, read_file, python or node_eval), which was saved and shown as its first and last lines with a handle. Returns lines start to end (1-based, inclusive); a page stops at about 8KB and says where to continue.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "handle": {
+                        "type": "string",
+                        "description": "The handle from the truncated output, e.g. \"shell_stdout_1a2b3c4d\""
+                    },
+                    "start": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "default": 1,
+                        "description": "First line to return (1-based)"
+                    },
+                    "end": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Last line to return (inclusive). Defaults to 199 lines after start."
+                    }
+                },
+                "required": ["handle"]
+            }),
+        },
         Tool {
             name: "read_image".to_string(),
             description: "Read one or more image files and send them to the LLM for visual analysis. Supports PNG, JPEG, GIF, and WebP formats. Use this when you need to visually inspect images (e.g., find sprites, analyze UI, read diagrams). The images will be included in your next response for analysis.".to_string(),
@@ -119,208 +292,1502 @@ fn create_core_tools(exclude_research: bool) -> Vec<Tool> {
                         "description": "Array of paths to image files to read"
                     }
                 },
-                "required": ["file_paths"]
+                "required": ["file_paths"]
+            }),
+        },
+        Tool {
+            name: "write_file".to_string(),
+            description: "Write content to a file (creates or overwrites). You MUST provide all arguments".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "The path to the file to write"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The content to write to the file"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory a relative file_path is resolved against, relative to the workspace. Must be inside the workspace."
+                    }
+                },
+                "required": ["file_path", "content"]
+            }),
+        },
+        Tool {
+            name: "str_replace".to_string(),
+            description: "Apply a unified diff to a file. Supports multiple hunks and context lines. Optionally constrain the search to a [start, end) character range (0-indexed; end is EXCLUSIVE). Useful to disambiguate matches or limit scope in large files.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "The path to the file to edit"
+                    },
+                    "diff": {
+                        "type": "string",
+                        "description": "A unified diff showing what to replace. Supports @@ hunk headers, context lines, and multiple hunks (---/+++ headers optional for minimal diffs)."
+                    },
+                    "start": {
+                        "type": "integer",
+                        "description": "Starting character position in the file (0-indexed, inclusive). If omitted, searches from beginning."
+                    },
+                    "end": {
+                        "type": "integer",
+                        "description": "Ending character position in the file (0-indexed, EXCLUSIVE - character at this position is NOT included). If omitted, searches to end of file."
+                    }
+                },
+                "required": ["file_path", "diff"]
+            }),
+        },
+        Tool {
+            name: "multi_edit".to_string(),
+            description: "Apply several unified diffs, to one or more files, in a single call. Edits are applied in order and edits to the same file stack (each sees the result of the previous ones). The call is transactional: if any edit fails to apply, no file is changed. Prefer this over several str_replace calls for small coordinated edits.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "edits": {
+                        "type": "array",
+                        "description": "The edits to apply, in order",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "file_path": {
+                                    "type": "string",
+                                    "description": "The path to the file to edit"
+                                },
+                                "diff": {
+                                    "type": "string",
+                                    "description": "A unified diff showing what to replace, as for str_replace"
+                                },
+                                "start": {
+                                    "type": "integer",
+                                    "description": "Starting character position to search from (0-indexed, inclusive), in the file as left by earlier edits"
+                                },
+                                "end": {
+                                    "type": "integer",
+                                    "description": "Ending character position to search to (0-indexed, EXCLUSIVE), in the file as left by earlier edits"
+                                }
+                            },
+                            "required": ["file_path", "diff"]
+                        }
+                    }
+                },
+                "required": ["edits"]
+            }),
+        },
+        Tool {
+            name: "edit_lines".to_string(),
+            description: "Replace a range of lines in a file (1-indexed, inclusive). You MUST echo the current first and last lines of the range in expected_first_line/expected_last_line; if they don't match the file, nothing is changed and the actual lines are shown. Use an empty content to delete the lines.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "The path to the file to edit"
+                    },
+                    "start_line": {
+                        "type": "integer",
+                        "description": "First line to replace (1-indexed, inclusive)"
+                    },
+                    "end_line": {
+                        "type": "integer",
+                        "description": "Last line to replace (1-indexed, inclusive)"
+                    },
+                    "expected_first_line": {
+                        "type": "string",
+                        "description": "The current content of start_line, exactly as it appears in the file (trailing whitespace is ignored)"
+                    },
+                    "expected_last_line": {
+                        "type": "string",
+                        "description": "The current content of end_line, exactly as it appears in the file (trailing whitespace is ignored)"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The replacement lines. Use an empty string to delete the range."
+                    }
+                },
+                "required": ["file_path", "start_line", "end_line", "expected_first_line", "expected_last_line", "content"]
+            }),
+        },
+        Tool {
+            name: "list_dir".to_string(),
+            description: "List a directory as a depth-limited tree with file sizes and per-directory totals. Respects .gitignore inside git repositories and skips dependency/build directories (node_modules, target, ...) elsewhere. Prefer this over `ls -R` or `find` for getting an overview of a project.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Directory to list (default: current working directory)"
+                    },
+                    "depth": {
+                        "type": "integer",
+                        "description": "How many levels to expand (default: 2, max: 10). Deeper directories are shown collapsed with file counts and sizes."
+                    },
+                    "include_ignored": {
+                        "type": "boolean",
+                        "description": "Include gitignored files and dependency directories (default: false)"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory a relative path is resolved against, relative to the workspace. Must be inside the workspace."
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "grep".to_string(),
+            description: "Search file contents with a regex (ripgrep engine). Respects .gitignore, skips binary files, and caps the number of results. Returns `path:line:text` lines (or JSON with format=\"json\"). Prefer this over running grep/rg through the shell tool.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "Regular expression (Rust regex syntax) to search for"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "File or directory to search (default: current working directory)"
+                    },
+                    "glob": {
+                        "type": ["array", "string"],
+                        "items": { "type": "string" },
+                        "description": "Glob filters like [\"*.rs\", \"!tests/**\"]; a leading ! excludes"
+                    },
+                    "case_insensitive": {
+                        "type": "boolean",
+                        "description": "Case-insensitive matching (default: false)"
+                    },
+                    "fixed_strings": {
+                        "type": "boolean",
+                        "description": "Treat the pattern as a literal string (default: false)"
+                    },
+                    "context_lines": {
+                        "type": "integer",
+                        "description": "Lines of context before and after each match (default: 0, max: 10)"
+                    },
+                    "max_matches": {
+                        "type": "integer",
+                        "description": "Stop after this many matches (default: 100, max: 1000)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["text", "json"],
+                        "description": "Output format (default: text)"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory a relative path is resolved against, relative to the workspace. Must be inside the workspace."
+                    }
+                },
+                "required": ["pattern"]
+            }),
+        },
+        Tool {
+            name: "regex_replace".to_string(),
+            description: "Apply one regex search/replace to every matching file under a path (respects .gitignore). Use for mechanical renames across many files instead of editing each one. Run with dry_run=true first to see per-file counts and a preview of changed lines.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "Regular expression (Rust regex syntax); use (?m) for per-line ^ and $"
+                    },
+                    "replacement": {
+                        "type": "string",
+                        "description": "Replacement text; $1 or ${name} insert capture groups, $$ is a literal $"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "File or directory to process (default: current working directory)"
+                    },
+                    "glob": {
+                        "type": ["array", "string"],
+                        "items": { "type": "string" },
+                        "description": "Glob filters like [\"*.rs\", \"!tests/**\"]; a leading ! excludes"
+                    },
+                    "case_insensitive": {
+                        "type": "boolean",
+                        "description": "Case-insensitive matching (default: false)"
+                    },
+                    "fixed_strings": {
+                        "type": "boolean",
+                        "description": "Treat the pattern and replacement as literal strings (default: false)"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Only report what would change (default: false)"
+                    }
+                },
+                "required": ["pattern", "replacement"]
+            }),
+        },
+        Tool {
+            name: "git_status".to_string(),
+            description: "Show the current branch, ahead/behind counts against its upstream, and staged, unstaged, untracked and conflicted files.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "format": {
+                        "type": "string",
+                        "enum": ["text", "json"],
+                        "description": "Output format (default: text)"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory to work in, relative to the workspace (e.g. a package of a monorepo): its repository is used and relative paths are resolved against it. Must be inside the workspace."
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "git_diff".to_string(),
+            description: "Show unstaged changes (working tree vs index) or, with staged=true, the changes that will be committed. Large diffs are truncated; use path or stat to narrow them.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "staged": {
+                        "type": "boolean",
+                        "description": "Diff the index against HEAD instead of the working tree against the index (default: false)"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Limit the diff to this file or directory"
+                    },
+                    "stat": {
+                        "type": "boolean",
+                        "description": "Only show per-file change counts (default: false)"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory to work in, relative to the workspace (e.g. a package of a monorepo): its repository is used and relative paths are resolved against it. Must be inside the workspace."
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "git_commit".to_string(),
+            description: "Stage files and commit them on the current branch. The message is wrapped in the configured commit template, which records the g3 session ID. Refuses detached HEAD, protected branches, conflicts and empty commits. Cannot amend or push.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "message": {
+                        "type": "string",
+                        "description": "Commit message (summary line, blank line, optional body)"
+                    },
+                    "files": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Files to stage before committing (new, modified or deleted)"
+                    },
+                    "all": {
+                        "type": "boolean",
+                        "description": "Stage all changes to tracked files before committing (default: false)"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory to work in, relative to the workspace (e.g. a package of a monorepo): its repository is used and relative paths are resolved against it. Must be inside the workspace."
+                    }
+                },
+                "required": ["message"]
+            }),
+        },
+        Tool {
+            name: "git_branch".to_string(),
+            description: "List local branches, create a branch at HEAD, or switch to a branch. Switching requires a clean working tree. Branches cannot be deleted.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["list", "create", "switch"],
+                        "description": "What to do (default: list)"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Branch name (required for create and switch)"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory to work in, relative to the workspace (e.g. a package of a monorepo): its repository is used and relative paths are resolved against it. Must be inside the workspace."
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "checkpoint_create".to_string(),
+            description: "Snapshot the whole working tree (tracked and untracked files, not ignored ones) as a checkpoint you can roll back to in one call. Take one before a risky refactor. HEAD, the branch and the staging area are not changed.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Checkpoint name (default: checkpoint-N)"
+                    },
+                    "message": {
+                        "type": "string",
+                        "description": "What the checkpoint is for"
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "checkpoint_rollback".to_string(),
+            description: "Restore the working tree to a checkpoint: files changed or deleted since are restored and files created since are removed. The current state is saved as a pre-rollback checkpoint first, so a rollback can be undone.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Checkpoint to restore (default: the most recent one)"
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "http_request".to_string(),
+            description: "Send an HTTP request and return the status, content type and body (JSON is pretty-printed, long bodies are truncated). Use this instead of curl in the shell for API calls. Credentials configured for the host are added automatically; don't put secrets in headers.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "http or https URL, including any query string"
+                    },
+                    "method": {
+                        "type": "string",
+                        "enum": ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD"],
+                        "description": "HTTP method (default: GET)"
+                    },
+                    "headers": {
+                        "type": "object",
+                        "description": "Request headers, e.g. {\"Accept\": \"application/json\"}"
+                    },
+                    "body": {
+                        "description": "Request body: a string is sent as is, any other JSON value is sent as JSON"
+                    },
+                    "include_headers": {
+                        "type": "boolean",
+                        "description": "Also return the response headers (default: false)"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Timeout in seconds (default: http.timeout_secs, 30)"
+                    }
+                },
+                "required": ["url"]
+            }),
+        },
+        Tool {
+            name: "notebook_read".to_string(),
+            description: "Read a Jupyter notebook (.ipynb) as numbered cells with their type, source and text outputs (images and other rich outputs are shown as placeholders). Use the cell numbers with notebook_edit and notebook_run.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to the .ipynb file"
+                    },
+                    "cells": {
+                        "type": "array",
+                        "items": { "type": "integer" },
+                        "description": "Only these cell numbers (0-based; default: all)"
+                    },
+                    "include_outputs": {
+                        "type": "boolean",
+                        "description": "Include stored outputs (default: true)"
+                    }
+                },
+                "required": ["path"]
+            }),
+        },
+        Tool {
+            name: "notebook_edit".to_string(),
+            description: "Change one cell of a Jupyter notebook: replace its source (clearing its outputs), insert a new cell, or delete it. Inserting into a notebook that doesn't exist creates it. Notebook metadata and cell ids are preserved.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to the .ipynb file"
+                    },
+                    "operation": {
+                        "type": "string",
+                        "enum": ["replace", "insert", "delete"]
+                    },
+                    "index": {
+                        "type": "integer",
+                        "description": "Cell number (0-based). For insert, the new cell goes before this cell (default: at the end)"
+                    },
+                    "source": {
+                        "type": "string",
+                        "description": "The cell's new source (replace and insert)"
+                    },
+                    "cell_type": {
+                        "type": "string",
+                        "enum": ["code", "markdown", "raw"],
+                        "description": "Type of the new cell (insert default: code; replace default: unchanged)"
+                    }
+                },
+                "required": ["path", "operation"]
+            }),
+        },
+        Tool {
+            name: "archive_extract".to_string(),
+            description: "Extract a zip, tar or tar.gz archive. Entries that would land outside the destination (absolute paths, `..`) and links are skipped, existing files are kept unless overwrite is set, and extraction stops (removing what it wrote) when the archive expands beyond the size limit. Use instead of unzip/tar in the shell.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "The archive (.zip, .tar, .tar.gz or .tgz)"
+                    },
+                    "destination": {
+                        "type": "string",
+                        "description": "Directory to extract into, created if missing (default: a directory named after the archive, next to it)"
+                    },
+                    "overwrite": {
+                        "type": "boolean",
+                        "description": "Replace files that already exist (default: false)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["zip", "tar", "tar.gz"],
+                        "description": "Archive format (default: from the file name)"
+                    },
+                    "max_size_mb": {
+                        "type": "integer",
+                        "description": "Stop when the extracted data exceeds this size (default: 500)"
+                    }
+                },
+                "required": ["path"]
+            }),
+        },
+        Tool {
+            name: "archive_create".to_string(),
+            description: "Create a zip, tar or tar.gz archive from files and directories. Directories are walked respecting .gitignore and entries are named relative to the working directory. Use instead of zip/tar in the shell.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "The archive to write (.zip, .tar, .tar.gz or .tgz)"
+                    },
+                    "paths": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Files and directories to include"
+                    },
+                    "overwrite": {
+                        "type": "boolean",
+                        "description": "Replace the archive if it exists (default: false)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["zip", "tar", "tar.gz"],
+                        "description": "Archive format (default: from the file name)"
+                    },
+                    "max_size_mb": {
+                        "type": "integer",
+                        "description": "Refuse when the files add up to more than this (default: 500)"
+                    }
+                },
+                "required": ["path", "paths"]
+            }),
+        },
+        Tool {
+            name: "inspect_env".to_string(),
+            description: "Inspect the local environment in one call, the same on every OS: listening ports with the processes that own them, whether specific ports are free, processes matching a name or command line, and selected environment variables (secret-looking values are hidden). Use instead of chains of lsof/ps/netstat/env shell calls, e.g. to find out why a port is busy.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "ports": {
+                        "type": "array",
+                        "items": { "type": "integer" },
+                        "description": "Report who listens on these ports, or that they are free"
+                    },
+                    "listening": {
+                        "type": "boolean",
+                        "description": "List every listening port (default: true when no other argument is given)"
+                    },
+                    "process": {
+                        "type": "string",
+                        "description": "List processes whose name or command line contains this text (case-insensitive)"
+                    },
+                    "env": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Environment variable names; a trailing * matches a prefix (e.g. \"NODE_*\")"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["text", "json"],
+                        "description": "Output format (default: text)"
+                    }
+                }
+            }),
+        },
+        Tool {
+            name: "wait_for".to_string(),
+            description: "Wait until a condition holds: a file exists, a URL returns the expected status, a TCP port accepts connections, or a process exits. Polls until the timeout and reports how long it took. Use after background_process instead of `sleep` in the shell; pass background_process to stop early (with its log) if the server crashes.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "Wait for this path to exist"
+                    },
+                    "url": {
+                        "type": "string",
+                        "description": "Wait for a GET of this http(s) URL to return `status`"
+                    },
+                    "status": {
+                        "type": "integer",
+                        "description": "Expected status for url (default: any 2xx)"
+                    },
+                    "port": {
+                        "type": "integer",
+                        "description": "Wait for this TCP port to accept connections"
+                    },
+                    "host": {
+                        "type": "string",
+                        "description": "Host for port (default: 127.0.0.1)"
+                    },
+                    "process_exit": {
+                        "type": ["string", "integer"],
+                        "description": "Wait for a background process (by name) or any process (by pid, e.g. \"4242\") to exit"
+                    },
+                    "background_process": {
+                        "type": "string",
+                        "description": "Name of a background process that must stay up; the wait fails as soon as it exits"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Give up after this long (default: 60, max: 600)"
+                    },
+                    "interval_ms": {
+                        "type": "integer",
+                        "description": "Time between checks (default: 500, min: 100)"
+                    }
+                }
+            }),
+        },
+        Tool {
+            name: "watch_files".to_string(),
+            description: "Watch a directory for up to timeout_secs and report the files created, modified and deleted meanwhile (with sizes and when they changed). Use after triggering codegen or a build (e.g. with background_process) to confirm its outputs appeared. Returns once the changes settle, or with `expect`, as soon as a file matching each expected glob was written.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Directory to watch (default: current directory)"
+                    },
+                    "glob": {
+                        "type": ["array", "string"],
+                        "items": { "type": "string" },
+                        "description": "Only watch files matching these globs, e.g. [\"*.ts\", \"!*.d.ts\"]"
+                    },
+                    "expect": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Globs (relative to path) that must each see a created or modified file; the watch ends as soon as they all do"
+                    },
+                    "no_ignore": {
+                        "type": "boolean",
+                        "description": "Also watch .gitignored and hidden files, e.g. build outputs (default: false)"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Watch at most this long (default: 30, max: 600)"
+                    },
+                    "settle_ms": {
+                        "type": "integer",
+                        "description": "Without expect, return once nothing changed for this long after the first change (default: 1000)"
+                    },
+                    "interval_ms": {
+                        "type": "integer",
+                        "description": "Time between scans (default: 250, min: 50)"
+                    }
+                }
+            }),
+        },
+        Tool {
+            name: "screenshot".to_string(),
+            description: "Capture a screenshot of a specific application window. You MUST specify the window_id parameter with the application name (e.g., 'Safari', 'Terminal', 'Google Chrome'). The tool will automatically use the native screencapture command with the application's window ID for a clean capture. Use list_windows first to identify available windows.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Filename for the screenshot (e.g., 'safari.png'). If a relative path is provided, the screenshot will be saved to ~/tmp or $TMPDIR. Use an absolute path to save elsewhere."
+                    },
+                    "window_id": {
+                        "type": "string",
+                        "description": "REQUIRED: Application name to capture (e.g., 'Safari', 'Terminal', 'Google Chrome'). The tool will capture the frontmost window of that application using its native window ID."
+                    },
+                    "region": {
+                        "type": "object",
+                        "properties": {
+                            "x": {"type": "integer"},
+                            "y": {"type": "integer"},
+                            "width": {"type": "integer"},
+                            "height": {"type": "integer"}
+                        }
+                    }
+                },
+                "required": ["path", "window_id"]
+            }),
+        },
+        Tool {
+            name: "image_diff".to_string(),
+            description: "Compare two screenshots of the same size. Reports the percentage of pixels that differ perceptibly (ignoring anti-aliasing and compression noise) and at all, the bounding box of the changes, and saves a diff image with changes highlighted in red. Use for UI regression checks: capture before and after a change and compare, optionally failing above max_diff_percent.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "before": {
+                        "type": "string",
+                        "description": "Path to the reference image"
+                    },
+                    "after": {
+                        "type": "string",
+                        "description": "Path to the image to compare against it"
+                    },
+                    "diff_path": {
+                        "type": "string",
+                        "description": "Where to save the diff image (default: <after>.diff.png next to the after image)"
+                    },
+                    "threshold": {
+                        "type": "number",
+                        "description": "Perceptual threshold from 0 to 1; higher ignores larger colour differences (default: 0.1)"
+                    },
+                    "ignore_regions": {
+                        "type": "array",
+                        "description": "Regions to leave out, such as clocks or spinners",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "x": {"type": "integer"},
+                                "y": {"type": "integer"},
+                                "width": {"type": "integer"},
+                                "height": {"type": "integer"}
+                            }
+                        }
+                    },
+                    "max_diff_percent": {
+                        "type": "number",
+                        "description": "Fail when more than this percentage of pixels differ perceptibly"
+                    }
+                },
+                "required": ["before", "after"]
+            }),
+        },
+        Tool {
+            name: "todo_read".to_string(),
+            description: "Read your current TODO list from todo.g3.md file in the session directory. Shows what tasks are planned and their status. Call this at the start of multi-step tasks to check for existing plans, and during execution to review progress before updating. TODO lists are scoped to the current session.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "todo_write".to_string(),
+            description: "Create or update your TODO list in todo.g3.md file with a complete task plan. Use markdown checkboxes: - [ ] for pending, - [x] for complete. This tool replaces the entire file content, so always call todo_read first to preserve existing content. Essential for multi-step tasks. TODO lists are scoped to the current session.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "content": {
+                        "type": "string",
+                        "description": "The TODO list content to save. Use markdown checkbox format: - [ ] for incomplete tasks, - [x] for completed tasks. Support nested tasks with indentation."
+                    }
+                },
+                "required": ["content"]
+            }),
+        },
+        Tool {
+            name: "plan_read".to_string(),
+            description: "Read your working plan for this session: the goal and numbered steps with their status and rationale. The plan is your own scratchpad, separate from the user-facing TODO list; in autonomous mode the coach reviews it.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
+        Tool {
+            name: "plan_write".to_string(),
+            description: "Write or update your working plan: a goal and ordered steps, each with a status (pending, in_progress, done, blocked, skipped) and the rationale for it. Pass steps to replace the whole list, or updates to change numbered steps (e.g. mark one done) without repeating the rest. Record why you chose an approach, and why a step is blocked or skipped.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "goal": {
+                        "type": "string",
+                        "description": "What the plan achieves"
+                    },
+                    "steps": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "title": { "type": "string" },
+                                "status": { "type": "string", "enum": ["pending", "in_progress", "done", "blocked", "skipped"] },
+                                "rationale": { "type": "string" }
+                            },
+                            "required": ["title"]
+                        },
+                        "description": "The full step list, replacing the current one"
+                    },
+                    "updates": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "step": { "type": "integer", "minimum": 1, "description": "Step number, from 1" },
+                                "status": { "type": "string", "enum": ["pending", "in_progress", "done", "blocked", "skipped"] },
+                                "title": { "type": "string" },
+                                "rationale": { "type": "string" }
+                            },
+                            "required": ["step"]
+                        },
+                        "description": "Changes to existing steps"
+                    }
+                }
+            }),
+        },
+        Tool {
+            name: "coverage".to_string(),
+            description: "Generate a per-file line coverage report using cargo llvm-cov. Runs the whole workspace by default; pass packages (or paths, which select the packages containing them) to run only those tests, which is much faster on large repos. Each file shows its change since the previous coverage run in this session. Requires llvm-tools-preview and cargo-llvm-cov to be installed (they will be auto-installed if missing).".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "packages": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Workspace packages to run and report on (default: the whole workspace)"
+                    },
+                    "paths": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only report files under these paths. Without packages, runs the packages containing them."
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["text", "json"],
+                        "description": "Output format (default: text)"
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "code_search".to_string(),
+            description: "Syntax-aware code search that understands code structure, not just text. Finds actual functions, classes, methods, and other code constructs - ignores matches in comments and strings. Much more accurate than grep for code searches. Supports batch searches (up to 20 parallel) with structured results and context lines. Languages: Rust, Python, JavaScript, TypeScript, Go, Java, C, C++, Racket. Uses tree-sitter query syntax.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "searches": {
+                        "type": "array",
+                        "maxItems": 20,
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "name": { "type": "string", "description": "Label for this search." },
+                                "query": { "type": "string", "description": "tree-sitter query in S-expression format (e.g., \"(function_item name: (identifier) @name)\")" },
+                                "language": { "type": "string", "enum": ["rust", "python", "javascript", "typescript", "go", "java", "c", "cpp", "racket"], "description": "Programming language to search." },
+                                "paths": { "type": "array", "items": { "type": "string" }, "description": "Paths/dirs to search. Defaults to current dir if empty." },
+                                "context_lines": { "type": "integer", "minimum": 0, "maximum": 20, "default": 0, "description": "Lines of context to include around each match." }
+                            },
+                            "required": ["name", "query", "language"]
+                        }
+                    },
+                    "max_concurrency": { "type": "integer", "minimum": 1, "default": 4 },
+                    "max_matches_per_search": { "type": "integer", "minimum": 1, "default": 500 }
+                },
+                "required": ["searches"]
+            }),
+        },
+        Tool {
+            name: "find_definition".to_string(),
+            description: "Find where a symbol (function, type, method, variable, ...) is defined, using the syntax tree rather than text search, so mentions in comments and strings are ignored. Returns file:line:column locations with context. When several definitions share the name, those in modules that other files import the symbol from are listed first.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "symbol": {
+                        "type": "string",
+                        "description": "The symbol name. For qualified names (module::Name, pkg.Name) the last segment is matched."
+                    },
+                    "language": {
+                        "type": "string",
+                        "enum": ["rust", "python", "javascript", "typescript", "go", "java", "c", "cpp", "haskell", "scheme", "racket"],
+                        "description": "Only search files of this language. Defaults to every supported language, detected by file extension."
+                    },
+                    "paths": { "type": "array", "items": { "type": "string" }, "description": "Paths/dirs to search. Defaults to current dir if empty." },
+                    "context_lines": { "type": "integer", "minimum": 0, "maximum": 20, "default": 1, "description": "Lines of context around each location." },
+                    "max_results": { "type": "integer", "minimum": 1, "default": 100 }
+                },
+                "required": ["symbol"]
+            }),
+        },
+        Tool {
+            name: "find_references".to_string(),
+            description: "Find the imports of and references to a symbol across the workspace, using the syntax tree rather than text search, so mentions in comments and strings are ignored. Definitions are not included (use find_definition). Returns file:line:column locations with context; files that define or import the symbol are listed first.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "symbol": {
+                        "type": "string",
+                        "description": "The symbol name. For qualified names (module::Name, pkg.Name) the last segment is matched."
+                    },
+                    "language": {
+                        "type": "string",
+                        "enum": ["rust", "python", "javascript", "typescript", "go", "java", "c", "cpp", "haskell", "scheme", "racket"],
+                        "description": "Only search files of this language. Defaults to every supported language, detected by file extension."
+                    },
+                    "paths": { "type": "array", "items": { "type": "string" }, "description": "Paths/dirs to search. Defaults to current dir if empty." },
+                    "context_lines": { "type": "integer", "minimum": 0, "maximum": 20, "default": 1, "description": "Lines of context around each location." },
+                    "max_results": { "type": "integer", "minimum": 1, "default": 100 }
+                },
+                "required": ["symbol"]
+            }),
+        },
+        Tool {
+            name: "symbol_lookup".to_string(),
+            description: "Look up where a function, type, class, trait or method is declared in the symbol index built during discovery, without searching the tree. Returns file:line ranges with signatures, exact matches first, then case-insensitive and partial ones. Use `Type::method` (or `Type.method`) to prefer a member of Type. Files changed since indexing are flagged; for those, and for locals or files added later, use find_definition.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "symbol": {
+                        "type": "string",
+                        "description": "The name to look up, optionally qualified by its class/impl/trait (Type::method)"
+                    },
+                    "kind": {
+                        "type": "string",
+                        "description": "Only this kind of declaration: function, method, struct, class, enum, trait, interface, impl, type, module, ..."
+                    },
+                    "max_results": { "type": "integer", "minimum": 1, "default": 20 }
+                },
+                "required": ["symbol"]
+            }),
+        },
+        Tool {
+            name: "ast_outline".to_string(),
+            description: "Get the structural outline of a source file from its syntax tree: functions, types, classes, impls, traits and modules with their signatures and line ranges, members nested under their class/impl. Much cheaper than reading a large file; use it to decide which line ranges to read_file.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Source file (Rust, Python, JavaScript, TypeScript, Go, Java, C or C++)"
+                    },
+                    "max_depth": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Nesting levels to list; 1 shows only top-level declarations (default: all)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["text", "json"],
+                        "description": "Output format (default: text)"
+                    }
+                },
+                "required": ["path"]
+            }),
+        },
+        Tool {
+            name: "dead_code".to_string(),
+            description: "Detect dead code and unused dependencies using the project's analyzer (rustc lints + cargo-udeps for Rust, knip for JS/TS, vulture for Python). By default only reports findings in files changed in git, so you can verify a refactor or cleanup didn't leave orphans. Unused dependencies are always reported.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "scope": {
+                        "type": "string",
+                        "enum": ["changes", "all"],
+                        "description": "'changes' (default) reports only findings in changed files; 'all' reports the whole project."
+                    },
+                    "ecosystem": {
+                        "type": "string",
+                        "enum": ["rust", "node", "python"],
+                        "description": "Analyzer to run. Auto-detected from project manifests if omitted."
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory to analyze, relative to the workspace (e.g. a package of a monorepo). Must be inside the workspace."
+                    }
+                },
+                "required": []
+            }),
+        },
+        Tool {
+            name: "verify".to_string(),
+            description: "Run an expensive verification command (test suite, full build, lints) in the BACKGROUND against a snapshot of the current working tree, checked out in a separate git worktree. Returns immediately; the result is injected into the conversation when the run finishes. Keep working meanwhile, but remember later edits are not covered by the run.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "description": "Shell command to run in the snapshot (e.g. 'cargo test --workspace'). Defaults to verification.command from the config."
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory to run in, relative to the workspace (e.g. a package of a monorepo). The same directory is used inside the snapshot."
+                    }
+                },
+                "required": []
+            }),
+        },
+    ];
+
+    // Conditionally add the research tool (excluded for scout agent to prevent recursion)
+    if !exclude_research {
+        tools.push(Tool {
+            name: "research".to_string(),
+            description: "Initiate web-based research on a topic. This tool is ASYNCHRONOUS - it spawns a research agent in the background and returns immediately with a research_id. Results are automatically injected into the conversation when ready. Use this when you need to research APIs, SDKs, libraries, approaches, bugs, or documentation. If you need the results before continuing, say so and yield the turn to the user. Check status with research_status tool.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "The research question or topic to investigate. Be specific about what you need to know."
+                    }
+                },
+                "required": ["query"]
+            }),
+        });
+
+        // research_status tool - check status of pending research
+        tools.push(Tool {
+            name: "research_status".to_string(),
+            description: "Check the status of pending research tasks. Call without arguments to list all pending research, or with a research_id to check a specific task. Use this to see if research has completed before it's automatically injected.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "research_id": {
+                        "type": "string",
+                        "description": "Optional: specific research_id to check. If omitted, lists all pending research tasks."
+                    }
+                },
+                "required": []
+            }),
+        });
+    }
+
+    // Workspace memory tool (memory is auto-loaded at startup, only remember is needed)
+    tools.push(Tool {
+        name: "remember".to_string(),
+        description: "Update the workspace memory with new discoveries. Call this at the END of your turn (before your summary) if you discovered something worth noting. Provide your notes in markdown format - they will be merged with existing memory.".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "notes": {
+                    "type": "string",
+                    "description": "New discoveries to add to memory in markdown format. Use the format:\n### Feature Name\n- `file/path.rs` [start..end] - `function_name()`, `StructName`\n\nDo not include content already in memory."
+                }
+            },
+            "required": ["notes"]
+        }),
+    });
+
+    // ACD rehydration tool
+    tools.push(Tool {
+        name: "rehydrate".to_string(),
+        description: "Restore dehydrated conversation history from a previous context segment. Use this when you see a DEHYDRATED CONTEXT stub and need to recall the full conversation details from that segment.".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "fragment_id": {
+                    "type": "string",
+                    "description": "The fragment ID to restore (from a DEHYDRATED CONTEXT stub message)"
+                }
+            },
+            "required": ["fragment_id"]
+        }),
+    });
+
+    tools
+}
+
+/// Create the database tool (built with the `sql` feature)
+fn create_sql_tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "sql_query".to_string(),
+            description: "Run a SQL query against a database configured under [sql.connections] (SQLite, Postgres or MySQL) and get the rows back as a table, limited to a maximum row count. Send one statement per call; it runs in a read-only transaction unless write=true on a connection with allow_writes. Use instead of psql/mysql/sqlite3 in the shell, and add LIMIT or aggregate for large tables.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "The SQL statement to run (one per call)"
+                    },
+                    "connection": {
+                        "type": "string",
+                        "description": "Name of the configured connection (optional when only one is configured)"
+                    },
+                    "write": {
+                        "type": "boolean",
+                        "description": "Run in a read-write session; only allowed for connections with allow_writes (default: false)"
+                    },
+                    "max_rows": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Most rows returned (default and upper bound: sql.max_rows, 100)"
+                    }
+                },
+                "required": ["query"]
+            }),
+        },
+    ]
+}
+
+/// Create the k8s tool (`k8s.enabled`)
+fn create_k8s_tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "k8s".to_string(),
+            description: "Run common kubectl operations against the configured cluster: `get` returns one summarized row per object (status, ready, restarts, age), `describe` and `logs` return kubectl's text, `apply` shows the diff and asks the user before changing anything. Namespaces are limited to k8s.allowed_namespaces when configured. Use instead of kubectl in the shell.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "operation": {
+                        "type": "string",
+                        "enum": ["get", "describe", "logs", "apply"]
+                    },
+                    "resource": {
+                        "type": "string",
+                        "description": "Resource type for get/describe (e.g. pods, deployments, svc, deployment/web); for logs, the type of `name` (default: pod)"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Object name; for logs a pod or e.g. deployment/web"
+                    },
+                    "namespace": {
+                        "type": "string",
+                        "description": "Namespace (default: k8s.namespace, the first allowed namespace, or kubectl's default)"
+                    },
+                    "all_namespaces": {
+                        "type": "boolean",
+                        "description": "get across all namespaces; unavailable when namespaces are restricted (default: false)"
+                    },
+                    "selector": {
+                        "type": "string",
+                        "description": "Label selector, e.g. app=web (get, describe, logs)"
+                    },
+                    "container": {
+                        "type": "string",
+                        "description": "Container for logs"
+                    },
+                    "tail": {
+                        "type": "integer",
+                        "description": "Log lines to fetch (default: 200)"
+                    },
+                    "since": {
+                        "type": "string",
+                        "description": "Only logs newer than this, e.g. 10m or 1h"
+                    },
+                    "previous": {
+                        "type": "boolean",
+                        "description": "Logs of the previous (crashed) container instance (default: false)"
+                    },
+                    "manifest": {
+                        "type": "string",
+                        "description": "YAML file or directory for apply"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["text", "json"],
+                        "description": "Output format for get (default: text)"
+                    }
+                },
+                "required": ["operation"]
+            }),
+        },
+    ]
+}
+
+/// Create the docker tool (`docker.enabled`)
+fn create_docker_tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "docker".to_string(),
+            description: "Build images and run containers for checks like \"does it build in the CI image\": `build` builds a Dockerfile inside the workspace, `run` runs a command with the workspace mounted at /workspace (removed when done unless detached), `logs` and `stop` work on containers this tool started, `ps` lists them. Images are limited to docker.allowed_images (plus images built with this tool) when configured. Use instead of docker in the shell.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "operation": {
+                        "type": "string",
+                        "enum": ["build", "run", "logs", "stop", "ps"]
+                    },
+                    "image": {
+                        "type": "string",
+                        "description": "Image to run, e.g. rust:1.80"
+                    },
+                    "command": {
+                        "type": ["string", "array"],
+                        "items": { "type": "string" },
+                        "description": "Command for run, executed with sh -c (an array of arguments is also accepted; default: the image's command)"
+                    },
+                    "context": {
+                        "type": "string",
+                        "description": "Build context directory inside the workspace (default: .)"
+                    },
+                    "dockerfile": {
+                        "type": "string",
+                        "description": "Dockerfile path (default: Dockerfile in the context)"
+                    },
+                    "tag": {
+                        "type": "string",
+                        "description": "Tag for the built image (default: g3-<context dir>:latest)"
+                    },
+                    "target": {
+                        "type": "string",
+                        "description": "Build stage to stop at"
+                    },
+                    "build_args": {
+                        "type": "object",
+                        "description": "Build arguments as name/value pairs"
+                    },
+                    "mount_workspace": {
+                        "type": "boolean",
+                        "description": "Mount the workspace at /workspace and start there (default: true)"
+                    },
+                    "mounts": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "source": { "type": "string" },
+                                "target": { "type": "string" },
+                                "read_only": { "type": "boolean" }
+                            },
+                            "required": ["source", "target"]
+                        },
+                        "description": "Extra bind mounts; sources must be inside the workspace"
+                    },
+                    "workdir": {
+                        "type": "string",
+                        "description": "Working directory in the container"
+                    },
+                    "env": {
+                        "type": "object",
+                        "description": "Environment variables for run"
+                    },
+                    "detach": {
+                        "type": "boolean",
+                        "description": "Start in the background and return; read it with logs and end it with stop (default: false)"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Container name for run (default: generated), logs and stop"
+                    },
+                    "tail": {
+                        "type": "integer",
+                        "description": "Log lines to fetch (default: 200)"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Limit for a foreground run (default and maximum: docker.timeout_secs)"
+                    }
+                },
+                "required": ["operation"]
             }),
         },
+    ]
+}
+
+/// Create the rename_symbol tool (language servers under `[lsp.servers]`)
+fn create_lsp_tools() -> Vec<Tool> {
+    vec![
         Tool {
-            name: "write_file".to_string(),
-            description: "Write content to a file (creates or overwrites). You MUST provide all arguments".to_string(),
+            name: "rename_symbol".to_string(),
+            description: "Rename a symbol and all its references through a language server from lsp.servers (e.g. rust-analyzer, pyright), so only real uses change, unlike regex_replace. Point at the symbol by file, line and its current name. Writes every edited file (and renames files, e.g. Rust modules) unless dry_run. Slow on large projects: the server loads the workspace first.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "file_path": {
+                    "path": {
                         "type": "string",
-                        "description": "The path to the file to write"
+                        "description": "File containing a definition or use of the symbol"
                     },
-                    "content": {
+                    "line": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "1-based line of that occurrence"
+                    },
+                    "symbol": {
                         "type": "string",
-                        "description": "The content to write to the file"
+                        "description": "The symbol's current name; its first whole-word occurrence on the line is used"
+                    },
+                    "column": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "1-based column of the symbol, instead of finding it by name"
+                    },
+                    "new_name": {
+                        "type": "string",
+                        "description": "The new name"
+                    },
+                    "server": {
+                        "type": "string",
+                        "description": "Language server from lsp.servers (default: the one handling the file's extension)"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "List the edits without writing anything (default: false)"
                     }
                 },
-                "required": ["file_path", "content"]
+                "required": ["path", "line", "new_name"]
             }),
         },
+    ]
+}
+
+/// Create the notebook_run tool (`notebook.enabled`)
+fn create_notebook_tools() -> Vec<Tool> {
+    vec![
         Tool {
-            name: "str_replace".to_string(),
-            description: "Apply a unified diff to a file. Supports multiple hunks and context lines. Optionally constrain the search to a [start, end) character range (0-indexed; end is EXCLUSIVE). Useful to disambiguate matches or limit scope in large files.".to_string(),
+            name: "notebook_run".to_string(),
+            description: "Execute code cells of a Jupyter notebook in a kernel that stays alive for the session (variables persist between calls, like in Jupyter), save the outputs into the notebook, and return each cell's status and text output. Requires jupyter_client and the notebook's kernel (e.g. ipykernel) in python3.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "file_path": {
+                    "path": {
                         "type": "string",
-                        "description": "The path to the file to edit"
+                        "description": "Path to the .ipynb file"
                     },
-                    "diff": {
-                        "type": "string",
-                        "description": "A unified diff showing what to replace. Supports @@ hunk headers, context lines, and multiple hunks (---/+++ headers optional for minimal diffs)."
+                    "cells": {
+                        "type": "array",
+                        "items": { "type": "integer" },
+                        "description": "Cell numbers to run, in order (default: every code cell)"
                     },
-                    "start": {
-                        "type": "integer",
-                        "description": "Starting character position in the file (0-indexed, inclusive). If omitted, searches from beginning."
+                    "restart": {
+                        "type": "boolean",
+                        "description": "Restart the kernel first, clearing its state (default: false)"
                     },
-                    "end": {
+                    "stop_on_error": {
+                        "type": "boolean",
+                        "description": "Stop at the first cell that fails (default: true)"
+                    },
+                    "timeout_secs": {
                         "type": "integer",
-                        "description": "Ending character position in the file (0-indexed, EXCLUSIVE - character at this position is NOT included). If omitted, searches to end of file."
+                        "description": "Per-cell timeout; the kernel is interrupted when it expires (default: 120)"
                     }
                 },
-                "required": ["file_path", "diff"]
+                "required": ["path"]
             }),
         },
+    ]
+}
+
+/// Create the cargo tool (`cargo.enabled`)
+fn create_cargo_tools() -> Vec<Tool> {
+    vec![
         Tool {
-            name: "screenshot".to_string(),
-            description: "Capture a screenshot of a specific application window. You MUST specify the window_id parameter with the application name (e.g., 'Safari', 'Terminal', 'Google Chrome'). The tool will automatically use the native screencapture command with the application's window ID for a clean capture. Use list_windows first to identify available windows.".to_string(),
+            name: "cargo".to_string(),
+            description: "Run cargo check, clippy, test or build and get the compiler diagnostics as file:line:column: level[code]: message lines (errors first) instead of the full compiler output. For test, failed tests are listed with their panic location and message. Prefer this over running cargo through the shell in Rust projects.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
+                    "subcommand": {
+                        "type": "string",
+                        "enum": ["check", "clippy", "test", "build"],
+                        "description": "The cargo subcommand to run"
+                    },
+                    "package": {
+                        "type": "string",
+                        "description": "Only this workspace package (-p)"
+                    },
+                    "all_targets": {
+                        "type": "boolean",
+                        "description": "Include tests, benches and examples (--all-targets, default: false)"
+                    },
+                    "args": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Extra arguments, e.g. [\"--workspace\"] or [\"--\", \"parser::tests\"] to filter tests"
+                    },
                     "path": {
                         "type": "string",
-                        "description": "Filename for the screenshot (e.g., 'safari.png'). If a relative path is provided, the screenshot will be saved to ~/tmp or $TMPDIR. Use an absolute path to save elsewhere."
+                        "description": "Directory to run in, relative to the workspace (default: the workspace). Must be inside the workspace."
                     },
-                    "window_id": {
+                    "max_diagnostics": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Most diagnostics listed (default: 50)"
+                    },
+                    "format": {
                         "type": "string",
-                        "description": "REQUIRED: Application name to capture (e.g., 'Safari', 'Terminal', 'Google Chrome'). The tool will capture the frontmost window of that application using its native window ID."
+                        "enum": ["text", "json"],
+                        "description": "'text' (default) or 'json' for the parsed diagnostics and test results as JSON"
                     },
-                    "region": {
-                        "type": "object",
-                        "properties": {
-                            "x": {"type": "integer"},
-                            "y": {"type": "integer"},
-                            "width": {"type": "integer"},
-                            "height": {"type": "integer"}
-                        }
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Kill cargo after this many seconds (default: 600)"
                     }
                 },
-                "required": ["path", "window_id"]
-            }),
-        },
-        Tool {
-            name: "todo_read".to_string(),
-            description: "Read your current TODO list from todo.g3.md file in the session directory. Shows what tasks are planned and their status. Call this at the start of multi-step tasks to check for existing plans, and during execution to review progress before updating. TODO lists are scoped to the current session.".to_string(),
-            input_schema: json!({
-                "type": "object",
-                "properties": {},
-                "required": []
+                "required": ["subcommand"]
             }),
         },
+    ]
+}
+
+/// Create the changelog tool (`changelog.enabled` or `changelog.auto_update`)
+fn create_changelog_tools() -> Vec<Tool> {
+    vec![
         Tool {
-            name: "todo_write".to_string(),
-            description: "Create or update your TODO list in todo.g3.md file with a complete task plan. Use markdown checkboxes: - [ ] for pending, - [x] for complete. This tool replaces the entire file content, so always call todo_read first to preserve existing content. Essential for multi-step tasks. TODO lists are scoped to the current session.".to_string(),
+            name: "changelog".to_string(),
+            description: "Record user-visible changes under the [Unreleased] section of the project's Keep a Changelog file (CHANGELOG.md by default), creating the file or category headings if needed. Write one short, past-tense entry per change; entries already listed are skipped.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "content": {
+                    "added": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "New features."
+                    },
+                    "changed": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Changes in existing functionality."
+                    },
+                    "deprecated": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Soon-to-be removed features."
+                    },
+                    "removed": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Removed features."
+                    },
+                    "fixed": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Bug fixes."
+                    },
+                    "security": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Vulnerability fixes."
+                    },
+                    "path": {
                         "type": "string",
-                        "description": "The TODO list content to save. Use markdown checkbox format: - [ ] for incomplete tasks, - [x] for completed tasks. Support nested tasks with indentation."
+                        "description": "Changelog file relative to the workspace. Defaults to the configured path (CHANGELOG.md)."
                     }
                 },
-                "required": ["content"]
-            }),
-        },
-        Tool {
-            name: "coverage".to_string(),
-            description: "Generate a code coverage report for the entire workspace using cargo llvm-cov. This runs all tests with coverage instrumentation and returns a summary of coverage statistics. Requires llvm-tools-preview and cargo-llvm-cov to be installed (they will be auto-installed if missing).".to_string(),
-            input_schema: json!({
-                "type": "object",
-                "properties": {},
                 "required": []
             }),
         },
+    ]
+}
+
+/// Create the node_eval tool (`node_eval.enabled`)
+fn create_node_eval_tools() -> Vec<Tool> {
+    vec![
         Tool {
-            name: "code_search".to_string(),
-            description: "Syntax-aware code search that understands code structure, not just text. Finds actual functions, classes, methods, and other code constructs - ignores matches in comments and strings. Much more accurate than grep for code searches. Supports batch searches (up to 20 parallel) with structured results and context lines. Languages: Rust, Python, JavaScript, TypeScript, Go, Java, C, C++, Racket. Uses tree-sitter query syntax.".to_string(),
+            name: "node_eval".to_string(),
+            description: "Run a JavaScript or TypeScript snippet in a sandboxed Deno (preferred) or Node process and return its output. The process has a hard timeout, a 512 MB heap limit, no environment variables beyond PATH/HOME, read-only access to the working directory and no network unless allow_network is set. Use for quick data transformations instead of writing a script to disk and running it with shell. Print results with console.log; text passed as input is on stdin and in the global INPUT.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "searches": {
-                        "type": "array",
-                        "maxItems": 20,
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "name": { "type": "string", "description": "Label for this search." },
-                                "query": { "type": "string", "description": "tree-sitter query in S-expression format (e.g., \"(function_item name: (identifier) @name)\")" },
-                                "language": { "type": "string", "enum": ["rust", "python", "javascript", "typescript", "go", "java", "c", "cpp", "racket"], "description": "Programming language to search." },
-                                "paths": { "type": "array", "items": { "type": "string" }, "description": "Paths/dirs to search. Defaults to current dir if empty." },
-                                "context_lines": { "type": "integer", "minimum": 0, "maximum": 20, "default": 0, "description": "Lines of context to include around each match." }
-                            },
-                            "required": ["name", "query", "language"]
-                        }
+                    "code": {
+                        "type": "string",
+                        "description": "The snippet, run as an ES module (top-level await, import and require all work)"
                     },
-                    "max_concurrency": { "type": "integer", "minimum": 1, "default": 4 },
-                    "max_matches_per_search": { "type": "integer", "minimum": 1, "default": 500 }
+                    "language": {
+                        "type": "string",
+                        "enum": ["javascript", "typescript"],
+                        "description": "Snippet language (default: javascript; typescript needs Deno or Node 22.6+)"
+                    },
+                    "input": {
+                        "type": "string",
+                        "description": "Text passed on stdin and as the global INPUT"
+                    },
+                    "runtime": {
+                        "type": "string",
+                        "enum": ["deno", "node"],
+                        "description": "Runtime to use (default: deno if installed, else node)"
+                    },
+                    "allow_network": {
+                        "type": "boolean",
+                        "description": "Allow network access (default: false)"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Kill the process after this long (default: 10, max: 120)"
+                    }
                 },
-                "required": ["searches"]
+                "required": ["code"]
             }),
         },
-    ];
+    ]
+}
 
-    // Conditionally add the research tool (excluded for scout agent to prevent recursion)
-    if !exclude_research {
-        tools.push(Tool {
-            name: "research".to_string(),
-            description: "Initiate web-based research on a topic. This tool is ASYNCHRONOUS - it spawns a research agent in the background and returns immediately with a research_id. Results are automatically injected into the conversation when ready. Use this when you need to research APIs, SDKs, libraries, approaches, bugs, or documentation. If you need the results before continuing, say so and yield the turn to the user. Check status with research_status tool.".to_string(),
+/// Create the python tool (`python.enabled`)
+fn create_python_tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "python".to_string(),
+            description: "Run Python code in a persistent interpreter kept for this session: variables, imports and loaded data survive between calls, so data can be loaded once and explored step by step. Returns captured stdout/stderr, the value of a trailing expression (like the Python REPL) and tracebacks. Uses the project's virtualenv (.venv, venv or $VIRTUAL_ENV) when there is one, else python3.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "query": {
+                    "code": {
                         "type": "string",
-                        "description": "The research question or topic to investigate. Be specific about what you need to know."
+                        "description": "Python code to run; the value of a final expression is returned"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Interrupt the code after this long; variables are kept (default: 30, max: 600)"
+                    },
+                    "reset": {
+                        "type": "boolean",
+                        "description": "Discard the interpreter and its variables before running (default: false)"
                     }
                 },
-                "required": ["query"]
+                "required": []
             }),
-        });
+        },
+    ]
+}
 
-        // research_status tool - check status of pending research
-        tools.push(Tool {
-            name: "research_status".to_string(),
-            description: "Check the status of pending research tasks. Call without arguments to list all pending research, or with a research_id to check a specific task. Use this to see if research has completed before it's automatically injected.".to_string(),
+/// Create the i18n_extract tool (`i18n.enabled`)
+fn create_i18n_tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "i18n_extract".to_string(),
+            description: "Scan changed source files for hard-coded user-facing strings and propose keys for the project's i18n resource file (JSON, YAML, Fluent, or Android strings.xml). Defaults to files changed in git. Only proposes entries - apply them with the file editing tools.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "research_id": {
+                    "paths": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Files to scan. If omitted, scans files changed in the git working tree."
+                    },
+                    "resource_file": {
                         "type": "string",
-                        "description": "Optional: specific research_id to check. If omitted, lists all pending research tasks."
+                        "description": "Path to the default-locale resource file. Auto-detected (e.g. locales/en.json) if omitted."
+                    },
+                    "key_prefix": {
+                        "type": "string",
+                        "description": "Namespace for proposed keys. Defaults to the source file name."
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory to scan in, relative to the workspace; paths and resource_file are resolved against it. Must be inside the workspace."
                     }
                 },
                 "required": []
             }),
-        });
-    }
-
-    // Workspace memory tool (memory is auto-loaded at startup, only remember is needed)
-    tools.push(Tool {
-        name: "remember".to_string(),
-        description: "Update the workspace memory with new discoveries. Call this at the END of your turn (before your summary) if you discovered something worth noting. Provide your notes in markdown format - they will be merged with existing memory.".to_string(),
-        input_schema: json!({
-            "type": "object",
-            "properties": {
-                "notes": {
-                    "type": "string",
-                    "description": "New discoveries to add to memory in markdown format. Use the format:\n### Feature Name\n- `file/path.rs` [start..end] - `function_name()`, `StructName`\n\nDo not include content already in memory."
-                }
-            },
-            "required": ["notes"]
-        }),
-    });
-
-    // ACD rehydration tool
-    tools.push(Tool {
-        name: "rehydrate".to_string(),
-        description: "Restore dehydrated conversation history from a previous context segment. Use this when you see a DEHYDRATED CONTEXT stub and need to recall the full conversation details from that segment.".to_string(),
-        input_schema: json!({
-            "type": "object",
-            "properties": {
-                "fragment_id": {
-                    "type": "string",
-                    "description": "The fragment ID to restore (from a DEHYDRATED CONTEXT stub message)"
-                }
-            },
-            "required": ["fragment_id"]
-        }),
-    });
-
-    tools
+        },
+    ]
 }
 
 /// Create WebDriver browser automation tools
@@ -523,11 +1990,17 @@ mod tests {
     #[test]
     fn test_core_tools_count() {
         let tools = create_core_tools(false);
-        // Should have the core tools: shell, background_process, read_file, read_image,
-        // write_file, str_replace, screenshot,
-        // todo_read, todo_write, coverage, code_search, research, research_status, remember
-        // (15 total - memory is auto-loaded, only remember tool needed)
-        assert_eq!(tools.len(), 15);
+        // Should have the core tools: shell, background_process, read_file, read_many_files,
+        // read_output, read_image,
+        // write_file, str_replace, multi_edit, edit_lines, list_dir, grep, regex_replace,
+        // git_status, git_diff,
+        // git_commit, git_branch, checkpoint_create, checkpoint_rollback, http_request,
+        // notebook_read, notebook_edit, archive_extract, archive_create, inspect_env,
+        // wait_for, watch_files, screenshot, image_diff,
+        // todo_read, todo_write, plan_read, plan_write, coverage, code_search, find_definition, find_references,
+        // symbol_lookup, ast_outline, dead_code, verify, research, research_status,
+        // remember, rehydrate (45 total - memory is auto-loaded, only remember tool needed)
+        assert_eq!(tools.len(), 45);
     }
 
     #[test]
@@ -537,19 +2010,102 @@ mod tests {
         assert_eq!(tools.len(), 15);
     }
 
+    #[test]
+    fn test_optional_tools_count() {
+        assert_eq!(create_sql_tools().len(), 1);
+        assert_eq!(create_k8s_tools().len(), 1);
+        assert_eq!(create_docker_tools().len(), 1);
+        assert_eq!(create_lsp_tools().len(), 1);
+        assert_eq!(create_notebook_tools().len(), 1);
+        assert_eq!(create_cargo_tools().len(), 1);
+        assert_eq!(create_changelog_tools().len(), 1);
+        assert_eq!(create_node_eval_tools().len(), 1);
+        assert_eq!(create_python_tools().len(), 1);
+        assert_eq!(create_i18n_tools().len(), 1);
+    }
+
+    /// sql_query, when built with the `sql` feature
+    fn sql_tools() -> usize {
+        usize::from(cfg!(feature = "sql"))
+    }
+
     #[test]
     fn test_create_tool_definitions_core_only() {
         let config = ToolConfig::default();
         let tools = create_tool_definitions(config);
-        assert_eq!(tools.len(), 15);
+        assert_eq!(tools.len(), 45);
     }
 
     #[test]
     fn test_create_tool_definitions_all_enabled() {
-        let config = ToolConfig::new(true, true);
+        let config = ToolConfig::all();
         let tools = create_tool_definitions(config);
-        // 15 core + 15 webdriver = 30
-        assert_eq!(tools.len(), 30);
+        // 45 core + 15 webdriver + k8s, docker, rename_symbol, notebook_run, cargo, changelog,
+        // node_eval, python, i18n_extract and sql
+        assert_eq!(tools.len(), 69 + sql_tools());
+    }
+
+    #[test]
+    fn test_optional_tools_follow_the_config() {
+        let mut config = g3_config::Config::default();
+        let names = |config: &g3_config::Config| -> Vec<String> {
+            create_tool_definitions(ToolConfig::from_config(config))
+                .into_iter()
+                .map(|tool| tool.name)
+                .collect()
+        };
+        let tools = names(&config);
+        let gated = [
+            "sql_query",
+            "k8s",
+            "docker",
+            "rename_symbol",
+            "notebook_run",
+            "cargo",
+            "changelog",
+            "node_eval",
+            "python",
+            "i18n_extract",
+        ];
+        for name in gated {
+            assert!(!tools.iter().any(|tool| tool == name), "{name} offered unconfigured");
+        }
+
+        config.k8s.enabled = true;
+        config.docker.enabled = true;
+        let tools = names(&config);
+        assert!(tools.iter().any(|tool| tool == "k8s"));
+        assert!(tools.iter().any(|tool| tool == "docker"));
+        assert!(!tools.iter().any(|tool| tool == "rename_symbol"));
+
+        config.notebook.enabled = true;
+        config.cargo.enabled = true;
+        let tools = names(&config);
+        assert!(tools.iter().any(|tool| tool == "notebook_run"));
+        assert!(tools.iter().any(|tool| tool == "cargo"));
+        assert!(!tools.iter().any(|tool| tool == "changelog"));
+
+        config.changelog.auto_update = true;
+        config.node_eval.enabled = true;
+        config.python.enabled = true;
+        config.i18n.enabled = true;
+        let tools = names(&config);
+        assert!(tools.iter().any(|tool| tool == "changelog"));
+        assert!(tools.iter().any(|tool| tool == "node_eval"));
+        assert!(tools.iter().any(|tool| tool == "python"));
+        assert!(tools.iter().any(|tool| tool == "i18n_extract"));
+    }
+
+    #[test]
+    fn test_create_tool_definitions_with_mcp_tools() {
+        let mcp_tools = [Tool {
+            name: "mcp__github__create_issue".to_string(),
+            description: "[MCP server github] Create an issue".to_string(),
+            input_schema: json!({ "type": "object", "properties": {} }),
+        }];
+        let tools = create_tool_definitions(ToolConfig::default().with_mcp_tools(&mcp_tools));
+        assert_eq!(tools.len(), 46);
+        assert_eq!(tools.last().unwrap().name, "mcp__github__create_issue");
     }
 
     #[test]
@@ -567,8 +2123,8 @@ mod tests {
         let tools_with_research = create_core_tools(false);
         let tools_without_research = create_core_tools(true);
         
-        assert_eq!(tools_with_research.len(), 15);
-        assert_eq!(tools_without_research.len(), 13);  // research + research_status both excluded
+        assert_eq!(tools_with_research.len(), 51);
+        assert_eq!(tools_without_research.len(), 49);  // research + research_status both excluded
         
         assert!(tools_with_research.iter().any(|t| t.name == "research"));
         assert!(!tools_without_research.iter().any(|t| t.name == "research"));
@@ -7,7 +7,9 @@ use anyhow::Result;
 use tracing::{debug, warn};
 
 use crate::tools::executor::ToolContext;
-use crate::tools::{acd, file_ops, memory, misc, research, shell, todo, webdriver};
+use crate::tools::{acd, archive, cargo, changelog, checkpoint, coverage, dead_code, docker, environment, file_ops, git, grep, http, i18n, k8s, list_dir, lsp, memory, misc, node_eval, notebook, plan, python, read_output, regex_replace, research, shell, todo, verify, wait, watch, webdriver};
+#[cfg(feature = "sql")]
+use crate::tools::sql;
 use crate::ui_writer::UiWriter;
 use crate::ToolCall;
 
@@ -28,18 +30,83 @@ pub async fn dispatch_tool<W: UiWriter>(
 
         // File operations
         "read_file" => file_ops::execute_read_file(tool_call, ctx).await,
+        "read_many_files" => file_ops::execute_read_many_files(tool_call, ctx).await,
+        "read_output" => read_output::execute_read_output(tool_call, ctx).await,
         "read_image" => file_ops::execute_read_image(tool_call, ctx).await,
         "write_file" => file_ops::execute_write_file(tool_call, ctx).await,
         "str_replace" => file_ops::execute_str_replace(tool_call, ctx).await,
+        "multi_edit" => file_ops::execute_multi_edit(tool_call, ctx).await,
+        "edit_lines" => file_ops::execute_edit_lines(tool_call, ctx).await,
+        "list_dir" => list_dir::execute_list_dir(tool_call, ctx).await,
+        "grep" => grep::execute_grep(tool_call, ctx).await,
+        "regex_replace" => regex_replace::execute_regex_replace(tool_call, ctx).await,
+
+        // Git
+        "git_status" => git::execute_git_status(tool_call, ctx).await,
+        "git_diff" => git::execute_git_diff(tool_call, ctx).await,
+        "git_commit" => git::execute_git_commit(tool_call, ctx).await,
+        "git_branch" => git::execute_git_branch(tool_call, ctx).await,
+        "checkpoint_create" => checkpoint::execute_checkpoint_create(tool_call, ctx).await,
+        "checkpoint_rollback" => checkpoint::execute_checkpoint_rollback(tool_call, ctx).await,
+
+        // Network
+        "http_request" => http::execute_http_request(tool_call, ctx).await,
+
+        // Databases
+        #[cfg(feature = "sql")]
+        "sql_query" => sql::execute_sql_query(tool_call, ctx).await,
+
+        // Kubernetes
+        "k8s" => k8s::execute_k8s(tool_call, ctx).await,
+
+        // Docker
+        "docker" => docker::execute_docker(tool_call, ctx).await,
+
+        // Jupyter notebooks
+        "notebook_read" => notebook::execute_notebook_read(tool_call, ctx).await,
+        "notebook_edit" => notebook::execute_notebook_edit(tool_call, ctx).await,
+        "notebook_run" => notebook::execute_notebook_run(tool_call, ctx).await,
+
+        // Archives
+        "archive_extract" => archive::execute_archive_extract(tool_call, ctx).await,
+        "archive_create" => archive::execute_archive_create(tool_call, ctx).await,
+
+        // Environment inspection and coordination
+        "inspect_env" => environment::execute_inspect_env(tool_call, ctx).await,
+        "wait_for" => wait::execute_wait_for(tool_call, ctx).await,
+        "watch_files" => watch::execute_watch_files(tool_call, ctx).await,
+
+        // Script evaluation
+        "node_eval" => node_eval::execute_node_eval(tool_call, ctx).await,
+        "python" => python::execute_python(tool_call, ctx).await,
 
         // TODO management
         "todo_read" => todo::execute_todo_read(tool_call, ctx).await,
         "todo_write" => todo::execute_todo_write(tool_call, ctx).await,
+        "plan_read" => plan::execute_plan_read(tool_call, ctx).await,
+        "plan_write" => plan::execute_plan_write(tool_call, ctx).await,
 
         // Miscellaneous tools
         "screenshot" => misc::execute_take_screenshot(tool_call, ctx).await,
-        "coverage" => misc::execute_code_coverage(tool_call, ctx).await,
+        "image_diff" => misc::execute_image_diff(tool_call, ctx).await,
+        "coverage" => coverage::execute_code_coverage(tool_call, ctx).await,
         "code_search" => misc::execute_code_search(tool_call, ctx).await,
+        "find_definition" => misc::execute_find_definition(tool_call, ctx).await,
+        "find_references" => misc::execute_find_references(tool_call, ctx).await,
+        "symbol_lookup" => misc::execute_symbol_lookup(tool_call, ctx).await,
+        "ast_outline" => misc::execute_ast_outline(tool_call, ctx).await,
+        "rename_symbol" => lsp::execute_rename_symbol(tool_call, ctx).await,
+        "dead_code" => dead_code::execute_dead_code(tool_call, ctx).await,
+        "cargo" => cargo::execute_cargo(tool_call, ctx).await,
+
+        // Localization
+        "i18n_extract" => i18n::execute_i18n_extract(tool_call, ctx).await,
+
+        // Release housekeeping
+        "changelog" => changelog::execute_changelog(tool_call, ctx).await,
+
+        // Background verification
+        "verify" => verify::execute_verify(tool_call, ctx).await,
 
         // Research tool
         "research" => research::execute_research(tool_call, ctx).await,
@@ -0,0 +1,155 @@
+//! Structured transcript of a session's tool calls.
+//!
+//! Every tool call is recorded as a `ToolCallMetric`: the tool, a digest of
+//! its arguments, when it started and finished, whether it succeeded, how
+//! large its result was and how many context tokens the call added.
+//! `Agent::get_tool_transcript` returns the records of the current session,
+//! and each one is appended as a JSON line to
+//! `.g3/sessions/<id>/tool_transcript.jsonl` for analytics tooling.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::paths::get_tool_transcript_file;
+
+/// Hex digits of the arguments' SHA-256 kept in `args_digest`.
+const ARGS_DIGEST_LEN: usize = 16;
+
+/// One tool call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallMetric {
+    pub tool: String,
+    /// Start of the SHA-256 of the arguments' JSON: equal for calls with the
+    /// same arguments, without storing the arguments (file contents, diffs)
+    pub args_digest: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub success: bool,
+    /// Size of the result the tool returned, in bytes
+    pub result_bytes: usize,
+    /// Context tokens the call and its result added
+    pub tokens_added: u32,
+}
+
+impl ToolCallMetric {
+    pub fn new(
+        tool: &str,
+        args: &serde_json::Value,
+        started_at: DateTime<Utc>,
+        duration: Duration,
+        success: bool,
+        result_bytes: usize,
+    ) -> Self {
+        Self {
+            tool: tool.to_string(),
+            args_digest: args_digest(args),
+            started_at,
+            finished_at: started_at
+                + chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero()),
+            duration_ms: duration.as_millis() as u64,
+            success,
+            result_bytes,
+            tokens_added: 0,
+        }
+    }
+
+    /// Record the context tokens the call added.
+    pub fn with_tokens_added(mut self, tokens: u32) -> Self {
+        self.tokens_added = tokens;
+        self
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+
+    /// Append this record as a JSON line to `path`.
+    pub fn append_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(file, "{}", serde_json::to_string(self).unwrap_or_default())
+    }
+
+    /// Append this record to the session's tool transcript. Without a
+    /// session there is nowhere to write it.
+    pub fn log(&self, session_id: Option<&str>) {
+        let Some(session_id) = session_id else {
+            return;
+        };
+        let path = get_tool_transcript_file(session_id);
+        if let Err(e) = self.append_to(&path) {
+            warn!(
+                "Failed to write tool transcript to {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// The `args_digest` of a call with arguments `args`.
+pub fn args_digest(args: &serde_json::Value) -> String {
+    let digest = Sha256::digest(args.to_string().as_bytes());
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    hex[..ARGS_DIGEST_LEN].to_string()
+}
+
+/// Read the tool transcript at `path`, skipping lines that don't parse.
+pub fn load(path: &Path) -> std::io::Result<Vec<ToolCallMetric>> {
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_records_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tool_transcript.jsonl");
+        let started = Utc::now();
+
+        let read = ToolCallMetric::new(
+            "read_file",
+            &json!({"file_path": "src/lib.rs"}),
+            started,
+            Duration::from_millis(1500),
+            true,
+            4096,
+        )
+        .with_tokens_added(1200);
+        let same_args = ToolCallMetric::new(
+            "read_file",
+            &json!({"file_path": "src/lib.rs"}),
+            started,
+            Duration::ZERO,
+            false,
+            0,
+        );
+        assert_eq!(read.args_digest.len(), ARGS_DIGEST_LEN);
+        assert_eq!(read.args_digest, same_args.args_digest);
+        assert_eq!(
+            read.finished_at - read.started_at,
+            chrono::Duration::milliseconds(1500)
+        );
+
+        read.append_to(&path).unwrap();
+        same_args.append_to(&path).unwrap();
+        assert_eq!(load(&path).unwrap(), vec![read, same_args]);
+    }
+}
@@ -0,0 +1,425 @@
+//! Validation of tool call arguments against the tools' JSON schemas.
+//!
+//! Run before a tool is dispatched so that a malformed call (a missing
+//! required field, a string where an integer belongs, a value outside an
+//! enum) comes back to the model as one message naming every bad field and
+//! carrying the tool's schema, instead of the first bare "Missing x
+//! argument" the tool happens to hit. The model can then fix the whole call
+//! in one turn.
+//!
+//! Only the parts of JSON Schema the tool definitions use are checked:
+//! `type` (a name or a list of names), `required`, `properties`, `items`
+//! and `enum`. Unknown properties are allowed, and properties without a
+//! `type` accept anything.
+
+use serde_json::Value;
+use std::sync::OnceLock;
+
+use crate::tool_definitions::{create_tool_definitions, ToolConfig};
+
+/// Tools that accept alternative argument shapes of their own (write_file
+/// takes `path`/`content`, `filename`/`text`, ... and `[path, content]`),
+/// so their schema is not the whole story.
+const SELF_VALIDATING_TOOLS: &[&str] = &["write_file"];
+
+/// One problem with a call's arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgError {
+    /// A required field is absent or null.
+    Missing { field: String },
+    /// A field has the wrong JSON type.
+    WrongType {
+        field: String,
+        expected: String,
+        found: String,
+    },
+    /// A field's value is not one of the allowed values.
+    NotAllowed {
+        field: String,
+        allowed: Vec<String>,
+        found: String,
+    },
+}
+
+impl std::fmt::Display for ArgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArgError::Missing { field } => write!(f, "missing required field `{}`", field),
+            ArgError::WrongType {
+                field,
+                expected,
+                found,
+            } if field.is_empty() => {
+                write!(f, "arguments must be {}, got {}", expected, found)
+            }
+            ArgError::WrongType {
+                field,
+                expected,
+                found,
+            } => write!(f, "`{}` must be {}, got {}", field, expected, found),
+            ArgError::NotAllowed {
+                field,
+                allowed,
+                found,
+            } => write!(
+                f,
+                "`{}` must be one of {}, got {}",
+                field,
+                allowed.join(", "),
+                found
+            ),
+        }
+    }
+}
+
+/// The input schema of every tool, by name.
+fn tool_schema(tool: &str) -> Option<&'static Value> {
+    static TOOLS: OnceLock<Vec<g3_providers::Tool>> = OnceLock::new();
+    TOOLS
+        .get_or_init(|| create_tool_definitions(ToolConfig::all()))
+        .iter()
+        .find(|t| t.name == tool)
+        .map(|t| &t.input_schema)
+}
+
+/// Check a tool call's arguments against the tool's schema.
+///
+/// Returns the message to hand back to the model when they don't match, or
+/// `None` when they do or the tool has no schema to check against.
+pub fn check_tool_args(tool: &str, args: &Value) -> Option<String> {
+    if SELF_VALIDATING_TOOLS.contains(&tool) {
+        return None;
+    }
+    let schema = tool_schema(tool)?;
+    let errors = validate_args(schema, args);
+    if errors.is_empty() {
+        None
+    } else {
+        Some(format_errors(tool, schema, &errors))
+    }
+}
+
+/// Every way `args` fails to match `schema`.
+pub fn validate_args(schema: &Value, args: &Value) -> Vec<ArgError> {
+    let mut errors = Vec::new();
+    validate_value(schema, args, "", &mut errors);
+    errors
+}
+
+/// The message for a call that failed validation: the problems, then the
+/// schema to follow.
+pub fn format_errors(tool: &str, schema: &Value, errors: &[ArgError]) -> String {
+    let mut message = format!("❌ Invalid arguments for {}:\n", tool);
+    for error in errors {
+        message.push_str(&format!("- {}\n", error));
+    }
+    message.push_str(&format!(
+        "\nFix these and call {} again. Its arguments schema is:\n{}",
+        tool,
+        serde_json::to_string_pretty(schema).unwrap_or_default()
+    ));
+    message
+}
+
+fn validate_value(schema: &Value, value: &Value, field: &str, errors: &mut Vec<ArgError>) {
+    let types = schema_types(schema);
+    if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+        errors.push(ArgError::WrongType {
+            field: field.to_string(),
+            expected: describe_types(&types),
+            found: describe_value(value),
+        });
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|v| v.as_array()) {
+        if !allowed.iter().any(|a| enum_matches(a, value)) {
+            errors.push(ArgError::NotAllowed {
+                field: field.to_string(),
+                allowed: allowed.iter().map(|a| a.to_string()).collect(),
+                found: value.to_string(),
+            });
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for required in schema
+            .get("required")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+        {
+            if object.get(required).is_none_or(Value::is_null) {
+                errors.push(ArgError::Missing {
+                    field: join_field(field, required),
+                });
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(|v| v.as_object()) {
+            for (name, property) in properties {
+                match object.get(name) {
+                    // An explicit null is the same as leaving the field out
+                    None | Some(Value::Null) => {}
+                    Some(value) => {
+                        validate_value(property, value, &join_field(field, name), errors)
+                    }
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            validate_value(items, item, &format!("{}[{}]", field, i), errors);
+        }
+    }
+}
+
+/// The type names a schema allows: `"type": "string"` or
+/// `"type": ["string", "array"]`.
+fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn has_type(value: &Value, type_name: &str) -> bool {
+    match type_name {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        // Unknown type names are not ours to enforce
+        _ => true,
+    }
+}
+
+/// Enum strings are compared case-insensitively: tools normalize case
+/// themselves (`"get"` is a fine HTTP method).
+fn enum_matches(allowed: &Value, value: &Value) -> bool {
+    match (allowed.as_str(), value.as_str()) {
+        (Some(allowed), Some(value)) => allowed.eq_ignore_ascii_case(value),
+        _ => allowed == value,
+    }
+}
+
+fn describe_types(types: &[&str]) -> String {
+    let names: Vec<&str> = types
+        .iter()
+        .map(|t| match *t {
+            "string" => "a string",
+            "integer" => "an integer",
+            "number" => "a number",
+            "boolean" => "a boolean",
+            "array" => "an array",
+            "object" => "an object",
+            "null" => "null",
+            other => other,
+        })
+        .collect();
+    names.join(" or ")
+}
+
+/// The JSON type of a value, with the value itself when it is short enough
+/// to help (`string "42"`).
+fn describe_value(value: &Value) -> String {
+    let kind = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    };
+    let text = value.to_string();
+    if matches!(value, Value::Array(_) | Value::Object(_) | Value::Null) || text.len() > 60 {
+        kind.to_string()
+    } else {
+        format!("{} {}", kind, text)
+    }
+}
+
+fn join_field(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn all_tools() -> Vec<g3_providers::Tool> {
+        create_tool_definitions(ToolConfig::all())
+    }
+
+    /// A value of the schema's (first) type, valid for nested schemas too.
+    fn sample(schema: &Value) -> Value {
+        if let Some(first) = schema
+            .get("enum")
+            .and_then(|v| v.as_array())
+            .and_then(|v| v.first())
+        {
+            return first.clone();
+        }
+        match schema_types(schema).first().copied() {
+            Some("integer") | Some("number") => json!(1),
+            Some("boolean") => json!(true),
+            Some("array") => json!([sample(schema.get("items").unwrap_or(&json!({})))]),
+            Some("object") => {
+                let mut object = serde_json::Map::new();
+                for (name, property) in schema
+                    .get("properties")
+                    .and_then(|v| v.as_object())
+                    .into_iter()
+                    .flatten()
+                {
+                    object.insert(name.clone(), sample(property));
+                }
+                Value::Object(object)
+            }
+            _ => json!("x"),
+        }
+    }
+
+    /// A value of none of the schema's types.
+    fn wrong_sample(schema: &Value) -> Value {
+        let types = schema_types(schema);
+        [json!(true), json!("x"), json!(1.5), json!({})]
+            .into_iter()
+            .find(|v| !types.iter().any(|t| has_type(v, t)))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_every_tool_accepts_a_well_formed_call() {
+        for tool in all_tools() {
+            let args = sample(&tool.input_schema);
+            assert_eq!(
+                validate_args(&tool.input_schema, &args),
+                Vec::new(),
+                "{} rejected {}",
+                tool.name,
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_tool_reports_each_missing_required_field() {
+        for tool in all_tools() {
+            let required: Vec<String> = tool
+                .input_schema
+                .get("required")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect();
+            let expected: Vec<ArgError> = required
+                .iter()
+                .map(|field| ArgError::Missing {
+                    field: field.clone(),
+                })
+                .collect();
+            assert_eq!(
+                validate_args(&tool.input_schema, &json!({})),
+                expected,
+                "{}",
+                tool.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_tool_reports_each_ill_typed_field() {
+        for tool in all_tools() {
+            let Some(properties) = tool
+                .input_schema
+                .get("properties")
+                .and_then(|v| v.as_object())
+            else {
+                continue;
+            };
+            for (name, property) in properties {
+                if schema_types(property).is_empty() {
+                    continue;
+                }
+                let mut args = sample(&tool.input_schema);
+                args[name.as_str()] = wrong_sample(property);
+                let errors = validate_args(&tool.input_schema, &args);
+                assert!(
+                    matches!(errors.as_slice(), [ArgError::WrongType { field, .. }] if field == name),
+                    "{}.{}: {:?}",
+                    tool.name,
+                    name,
+                    errors
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_enum_nested_and_multi_type_fields() {
+        let schema = tool_schema("plan_write").unwrap();
+        let errors = validate_args(
+            schema,
+            &json!({ "steps": [{ "title": "Read" }, { "status": "finished" }] }),
+        );
+        assert_eq!(
+            errors,
+            vec![
+                ArgError::Missing {
+                    field: "steps[1].title".to_string()
+                },
+                ArgError::NotAllowed {
+                    field: "steps[1].status".to_string(),
+                    allowed: ["pending", "in_progress", "done", "blocked", "skipped"]
+                        .iter()
+                        .map(|s| format!("\"{}\"", s))
+                        .collect(),
+                    found: "\"finished\"".to_string(),
+                },
+            ]
+        );
+
+        let http = tool_schema("http_request").unwrap();
+        assert!(validate_args(http, &json!({ "url": "http://x", "method": "post" })).is_empty());
+        let grep = tool_schema("grep").unwrap();
+        assert!(validate_args(grep, &json!({ "pattern": "fn", "glob": "*.rs" })).is_empty());
+        assert!(validate_args(grep, &json!({ "pattern": "fn", "glob": ["*.rs"] })).is_empty());
+        assert!(validate_args(grep, &json!({ "pattern": "fn", "glob": null })).is_empty());
+    }
+
+    #[test]
+    fn test_check_tool_args_message() {
+        assert_eq!(check_tool_args("shell", &json!({ "command": "ls" })), None);
+        assert_eq!(check_tool_args("no_such_tool", &json!({})), None);
+        assert_eq!(
+            check_tool_args("write_file", &json!({ "path": "a", "content": "" })),
+            None
+        );
+
+        let message = check_tool_args("shell", &json!({ "cmd": "ls" })).unwrap();
+        assert!(message
+            .starts_with("❌ Invalid arguments for shell:\n- missing required field `command`\n"));
+        assert!(message.contains("\"required\": [\n    \"command\"\n  ]"));
+
+        let message =
+            check_tool_args("read_file", &json!({ "file_path": "a", "start": "10" })).unwrap();
+        assert!(message.contains("- `start` must be an integer, got string \"10\"\n"));
+
+        let message = check_tool_args("shell", &json!("ls")).unwrap();
+        assert!(message.contains("- arguments must be an object, got string \"ls\"\n"));
+    }
+}
@@ -137,6 +137,13 @@ mod tests {
         pending_images: Vec<g3_providers::ImageContent>,
         config: g3_config::Config,
         pending_research_manager: PendingResearchManager,
+        build_reports: Vec<crate::build_metrics::BuildReport>,
+        pending_verification_manager: crate::verification::PendingVerificationManager,
+        prefetch_cache: crate::prefetch::PrefetchCache,
+        notebook_kernels: crate::notebook_kernel::NotebookKernelManager,
+        python_interpreter: crate::python_interpreter::PythonInterpreter,
+        persistent_shell: g3_execution::PersistentShell,
+        file_versions: crate::file_versions::FileVersions,
     }
 
     impl TestContext {
@@ -150,6 +157,13 @@ mod tests {
                 pending_images: Vec::new(),
                 config: g3_config::Config::default(),
                 pending_research_manager: PendingResearchManager::new(),
+                build_reports: Vec::new(),
+                pending_verification_manager: crate::verification::PendingVerificationManager::new(),
+                prefetch_cache: crate::prefetch::PrefetchCache::new(),
+                notebook_kernels: crate::notebook_kernel::NotebookKernelManager::new(),
+                python_interpreter: crate::python_interpreter::PythonInterpreter::new(),
+                persistent_shell: g3_execution::PersistentShell::new(),
+                file_versions: crate::file_versions::FileVersions::new(),
             }
         }
     }
@@ -173,6 +187,13 @@ mod tests {
             context_total_tokens: 100000,
             context_used_tokens: 10000,
             pending_research_manager: &test_ctx.pending_research_manager,
+            build_reports: &mut test_ctx.build_reports,
+            pending_verification_manager: &test_ctx.pending_verification_manager,
+            prefetch_cache: &test_ctx.prefetch_cache,
+            notebook_kernels: &test_ctx.notebook_kernels,
+            python_interpreter: &test_ctx.python_interpreter,
+            persistent_shell: &test_ctx.persistent_shell,
+            file_versions: &test_ctx.file_versions,
         };
 
         let tool_call = ToolCall {
@@ -204,6 +225,13 @@ mod tests {
             context_total_tokens: 100000,
             context_used_tokens: 10000,
             pending_research_manager: &test_ctx.pending_research_manager,
+            build_reports: &mut test_ctx.build_reports,
+            pending_verification_manager: &test_ctx.pending_verification_manager,
+            prefetch_cache: &test_ctx.prefetch_cache,
+            notebook_kernels: &test_ctx.notebook_kernels,
+            python_interpreter: &test_ctx.python_interpreter,
+            persistent_shell: &test_ctx.persistent_shell,
+            file_versions: &test_ctx.file_versions,
         };
 
         let tool_call = ToolCall {
@@ -235,6 +263,13 @@ mod tests {
             context_total_tokens: 100000,
             context_used_tokens: 10000,
             pending_research_manager: &test_ctx.pending_research_manager,
+            build_reports: &mut test_ctx.build_reports,
+            pending_verification_manager: &test_ctx.pending_verification_manager,
+            prefetch_cache: &test_ctx.prefetch_cache,
+            notebook_kernels: &test_ctx.notebook_kernels,
+            python_interpreter: &test_ctx.python_interpreter,
+            persistent_shell: &test_ctx.persistent_shell,
+            file_versions: &test_ctx.file_versions,
         };
 
         let tool_call = ToolCall {
@@ -0,0 +1,662 @@
+//! Archive tools: archive_extract, archive_create.
+//!
+//! Zip, tar and tar.gz handled in-process (zip, tar and flate2 crates)
+//! rather than through unzip/tar commands. Extraction only ever writes
+//! inside the destination: entries with absolute paths or `..` components
+//! and symbolic or hard links are skipped, and a symlink already in the
+//! destination is never written through. Entry count and extracted size
+//! are capped with the size counted from the data actually written, not
+//! the headers, so a zip bomb stops at the limit. Whatever a failed
+//! extraction created is removed again.
+
+use anyhow::{anyhow, bail, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::build_metrics::format_bytes;
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::{bool_arg, search_path, walk_files};
+
+/// Extracted or archived size allowed when `max_size_mb` is omitted.
+const DEFAULT_MAX_SIZE_MB: u64 = 500;
+
+/// Most entries extracted or archived.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Skipped entries listed in the result.
+const MAX_SKIPPED_SHOWN: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// The format implied by the file name, if any.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+
+    fn parse(format: &str) -> Option<Self> {
+        match format {
+            "zip" => Some(Self::Zip),
+            "tar" => Some(Self::Tar),
+            "tar.gz" | "tgz" => Some(Self::TarGz),
+            _ => None,
+        }
+    }
+}
+
+/// Caps on entry count and total uncompressed size.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_bytes: u64,
+    pub max_entries: usize,
+}
+
+/// What an extraction wrote and what it left out.
+#[derive(Debug, Default)]
+pub struct ExtractReport {
+    pub files: usize,
+    pub bytes: u64,
+    /// Entry names with the reason they were skipped
+    pub skipped: Vec<(String, &'static str)>,
+}
+
+/// What an archive was created from.
+#[derive(Debug, Default)]
+pub struct CreateReport {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Execute the `archive_extract` tool.
+pub async fn execute_archive_extract<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing archive_extract tool call");
+
+    let args = &tool_call.args;
+    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing path argument".to_string());
+    };
+    let archive = search_path(ctx, Some(path));
+    if !archive.is_file() {
+        return Ok(format!("❌ Archive not found: {}", archive.display()));
+    }
+    let format = match format_arg(args, &archive) {
+        Ok(format) => format,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    // Default: a directory named after the archive, next to it
+    let destination = match args.get("destination").and_then(|v| v.as_str()) {
+        Some(destination) => search_path(ctx, Some(destination)),
+        None => archive.with_file_name(archive_stem(&archive)),
+    };
+    let overwrite = bool_arg(args, "overwrite");
+    let limits = limits_arg(args);
+
+    let (from, to) = (archive.clone(), destination.clone());
+    let report =
+        match tokio::task::spawn_blocking(move || extract(&from, format, &to, overwrite, limits))
+            .await?
+        {
+            Ok(report) => report,
+            Err(e) => return Ok(format!("❌ Failed to extract {}: {}", path, e)),
+        };
+
+    let mut result = format!(
+        "✅ Extracted {} file(s) ({}) from {} to {}",
+        report.files,
+        format_bytes(report.bytes),
+        path,
+        destination.display()
+    );
+    if !report.skipped.is_empty() {
+        result.push_str(&format!("\n⚠️ Skipped {} entries:", report.skipped.len()));
+        for (name, reason) in report.skipped.iter().take(MAX_SKIPPED_SHOWN) {
+            result.push_str(&format!("\n  {} ({})", name, reason));
+        }
+        if report.skipped.len() > MAX_SKIPPED_SHOWN {
+            result.push_str(&format!(
+                "\n  ... and {} more",
+                report.skipped.len() - MAX_SKIPPED_SHOWN
+            ));
+        }
+    }
+    Ok(result)
+}
+
+/// Execute the `archive_create` tool.
+pub async fn execute_archive_create<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing archive_create tool call");
+
+    let args = &tool_call.args;
+    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing path argument".to_string());
+    };
+    let archive = search_path(ctx, Some(path));
+    let format = match format_arg(args, &archive) {
+        Ok(format) => format,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    let inputs: Vec<PathBuf> = match args.get("paths").and_then(|v| v.as_array()) {
+        Some(paths) => paths
+            .iter()
+            .filter_map(|p| p.as_str())
+            .map(|p| search_path(ctx, Some(p)))
+            .collect(),
+        None => return Ok("❌ Missing paths argument".to_string()),
+    };
+    if inputs.is_empty() {
+        return Ok("❌ paths must list at least one file or directory".to_string());
+    }
+    if let Some(missing) = inputs.iter().find(|p| !p.exists()) {
+        return Ok(format!("❌ Path not found: {}", missing.display()));
+    }
+    if archive.exists() && !bool_arg(args, "overwrite") {
+        return Ok(format!(
+            "❌ {} already exists (pass overwrite: true to replace it)",
+            path
+        ));
+    }
+    let base = search_path(ctx, None);
+    let limits = limits_arg(args);
+
+    let target = archive.clone();
+    let report =
+        match tokio::task::spawn_blocking(move || create(&target, format, &inputs, &base, limits))
+            .await?
+        {
+            Ok(report) => report,
+            Err(e) => return Ok(format!("❌ Failed to create {}: {}", path, e)),
+        };
+
+    let compressed = fs::metadata(&archive).map(|m| m.len()).unwrap_or(0);
+    Ok(format!(
+        "✅ Created {}: {} file(s), {} ({} archived)",
+        path,
+        report.files,
+        format_bytes(report.bytes),
+        format_bytes(compressed)
+    ))
+}
+
+/// The `format` argument, or the format implied by the archive's name.
+fn format_arg(args: &serde_json::Value, archive: &Path) -> Result<ArchiveFormat> {
+    match args.get("format").and_then(|v| v.as_str()) {
+        Some(format) => ArchiveFormat::parse(format)
+            .ok_or_else(|| anyhow!("Unknown format '{}' (zip, tar or tar.gz)", format)),
+        None => ArchiveFormat::from_path(archive).ok_or_else(|| {
+            anyhow!(
+                "Can't tell the format of {}; pass format (zip, tar or tar.gz)",
+                archive.display()
+            )
+        }),
+    }
+}
+
+fn limits_arg(args: &serde_json::Value) -> Limits {
+    let max_size_mb = args
+        .get("max_size_mb")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_MAX_SIZE_MB);
+    Limits {
+        max_bytes: max_size_mb.saturating_mul(1024 * 1024),
+        max_entries: MAX_ENTRIES,
+    }
+}
+
+/// The archive's file name without its archive extension.
+pub(crate) fn archive_stem(archive: &Path) -> String {
+    let name = archive
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let lower = name.to_lowercase();
+    [".tar.gz", ".tgz", ".tar", ".zip"]
+        .iter()
+        .find(|ext| lower.ends_with(*ext) && lower.len() > ext.len())
+        .map_or(name.clone(), |ext| {
+            name[..name.len() - ext.len()].to_string()
+        })
+}
+
+/// `name` as a relative path that stays inside the directory it is joined
+/// to, or None for absolute paths, `..` and empty names.
+fn safe_relative_path(name: &Path) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// Extract `archive` into `destination`, which is created if needed.
+pub fn extract(
+    archive: &Path,
+    format: ArchiveFormat,
+    destination: &Path,
+    overwrite: bool,
+    limits: Limits,
+) -> Result<ExtractReport> {
+    let mut extractor = Extractor::new(destination, overwrite, limits)?;
+    let result = match format {
+        ArchiveFormat::Zip => extract_zip(archive, &mut extractor),
+        ArchiveFormat::Tar => extract_tar(File::open(archive)?, &mut extractor),
+        ArchiveFormat::TarGz => extract_tar(GzDecoder::new(File::open(archive)?), &mut extractor),
+    };
+    match result {
+        Ok(()) => Ok(extractor.report),
+        Err(e) => {
+            extractor.roll_back();
+            Err(e)
+        }
+    }
+}
+
+fn extract_zip(archive: &Path, extractor: &mut Extractor) -> Result<()> {
+    let mut zip = ZipArchive::new(File::open(archive)?)?;
+    if zip.len() > extractor.limits.max_entries {
+        bail!(
+            "archive has {} entries, more than the limit of {}",
+            zip.len(),
+            extractor.limits.max_entries
+        );
+    }
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let name = entry.name().to_string();
+        if entry.is_dir() {
+            extractor.dir(&name)?;
+        } else if entry.is_symlink() {
+            extractor.skip(&name, "symbolic link");
+        } else {
+            let mode = entry.unix_mode();
+            extractor.file(&name, mode, &mut entry)?;
+        }
+    }
+    Ok(())
+}
+
+fn extract_tar(reader: impl Read, extractor: &mut Extractor) -> Result<()> {
+    let mut tar = tar::Archive::new(reader);
+    for (i, entry) in tar.entries()?.enumerate() {
+        if i == extractor.limits.max_entries {
+            bail!(
+                "archive has more than {} entries",
+                extractor.limits.max_entries
+            );
+        }
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let kind = entry.header().entry_type();
+        if kind.is_dir() {
+            extractor.dir(&name)?;
+        } else if kind.is_file() {
+            let mode = entry.header().mode().ok();
+            extractor.file(&name, mode, &mut entry)?;
+        } else if kind.is_symlink() || kind.is_hard_link() {
+            extractor.skip(&name, "link");
+        } else {
+            extractor.skip(&name, "special file");
+        }
+    }
+    Ok(())
+}
+
+/// Writes entries under the destination, remembering what it created.
+struct Extractor {
+    destination: PathBuf,
+    /// The destination with symlinks resolved, for containment checks
+    canonical: PathBuf,
+    overwrite: bool,
+    limits: Limits,
+    report: ExtractReport,
+    created_files: Vec<PathBuf>,
+    created_dirs: Vec<PathBuf>,
+}
+
+impl Extractor {
+    fn new(destination: &Path, overwrite: bool, limits: Limits) -> Result<Self> {
+        let mut extractor = Self {
+            destination: destination.to_path_buf(),
+            canonical: PathBuf::new(),
+            overwrite,
+            limits,
+            report: ExtractReport::default(),
+            created_files: Vec::new(),
+            created_dirs: Vec::new(),
+        };
+        let mut missing = Vec::new();
+        let mut dir = destination;
+        while !dir.exists() {
+            missing.push(dir.to_path_buf());
+            dir = dir.parent().unwrap_or(Path::new("."));
+        }
+        fs::create_dir_all(destination)?;
+        extractor.created_dirs.extend(missing.into_iter().rev());
+        extractor.canonical = destination.canonicalize()?;
+        Ok(extractor)
+    }
+
+    fn skip(&mut self, name: &str, reason: &'static str) {
+        self.report.skipped.push((name.to_string(), reason));
+    }
+
+    /// The path for entry `name`, or None (and a skip) if it would escape.
+    fn target(&mut self, name: &str) -> Option<PathBuf> {
+        match safe_relative_path(Path::new(name)) {
+            Some(path) => Some(self.destination.join(path)),
+            None => {
+                self.skip(name, "path outside the destination");
+                None
+            }
+        }
+    }
+
+    fn dir(&mut self, name: &str) -> Result<()> {
+        if let Some(target) = self.target(name) {
+            self.create_dirs(&target)?;
+        }
+        Ok(())
+    }
+
+    fn file(&mut self, name: &str, mode: Option<u32>, data: &mut dyn Read) -> Result<()> {
+        let Some(target) = self.target(name) else {
+            return Ok(());
+        };
+        if let Some(parent) = target.parent() {
+            self.create_dirs(parent)?;
+        }
+        match fs::symlink_metadata(&target) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                bail!("{} is a symbolic link in the destination", name)
+            }
+            Ok(_) if !self.overwrite => {
+                bail!(
+                    "{} already exists (pass overwrite: true to replace it)",
+                    name
+                )
+            }
+            Ok(_) => {}
+            Err(_) => self.created_files.push(target.clone()),
+        }
+
+        let remaining = self.limits.max_bytes - self.report.bytes;
+        let mut file = File::create(&target)?;
+        let written = io::copy(&mut data.take(remaining + 1), &mut file)?;
+        if written > remaining {
+            bail!(
+                "archive expands to more than {}; raise max_size_mb to extract it",
+                format_bytes(self.limits.max_bytes)
+            );
+        }
+        self.report.bytes += written;
+        self.report.files += 1;
+
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            // Plain permission bits only; no setuid/setgid/sticky
+            fs::set_permissions(&target, fs::Permissions::from_mode(mode & 0o777))?;
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        Ok(())
+    }
+
+    /// Create `dir` and any missing parents, refusing to follow a symlink
+    /// out of the destination.
+    fn create_dirs(&mut self, dir: &Path) -> Result<()> {
+        let relative = dir.strip_prefix(&self.destination).unwrap_or(Path::new(""));
+        let mut current = self.destination.clone();
+        for component in relative.components() {
+            current.push(component);
+            if !current.exists() {
+                fs::create_dir(&current)?;
+                self.created_dirs.push(current.clone());
+            }
+        }
+        if !current.canonicalize()?.starts_with(&self.canonical) {
+            bail!(
+                "{} leads outside the destination through a symbolic link",
+                relative.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Remove the files and directories this extraction created.
+    fn roll_back(&self) {
+        for file in &self.created_files {
+            let _ = fs::remove_file(file);
+        }
+        for dir in self.created_dirs.iter().rev() {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+/// Archive the files under `inputs` into `archive`. Directories are walked
+/// respecting .gitignore; entries are named relative to `base` (or to the
+/// input's parent for inputs outside it).
+pub fn create(
+    archive: &Path,
+    format: ArchiveFormat,
+    inputs: &[PathBuf],
+    base: &Path,
+    limits: Limits,
+) -> Result<CreateReport> {
+    let files = collect_files(archive, inputs, base, limits)?;
+    if files.is_empty() {
+        bail!("no files to archive (directories are walked respecting .gitignore)");
+    }
+    if let Some(parent) = archive.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let result = match format {
+        ArchiveFormat::Zip => write_zip(File::create(archive)?, &files),
+        ArchiveFormat::Tar => write_tar(File::create(archive)?, &files).map(drop),
+        ArchiveFormat::TarGz => write_tar(
+            GzEncoder::new(File::create(archive)?, Compression::default()),
+            &files,
+        )
+        .and_then(|encoder| Ok(encoder.finish().map(drop)?)),
+    };
+    if let Err(e) = result {
+        let _ = fs::remove_file(archive);
+        return Err(e);
+    }
+    Ok(CreateReport {
+        files: files.len(),
+        bytes: files.iter().map(|(_, _, size)| size).sum(),
+    })
+}
+
+/// (path, entry name, size) of every file to archive.
+fn collect_files(
+    archive: &Path,
+    inputs: &[PathBuf],
+    base: &Path,
+    limits: Limits,
+) -> Result<Vec<(PathBuf, String, u64)>> {
+    let mut files = Vec::new();
+    let mut total = 0u64;
+    for input in inputs {
+        let root = if input.starts_with(base) {
+            base
+        } else {
+            input.parent().unwrap_or(Path::new(""))
+        };
+        let (_, walker) = walk_files(input, &[])?;
+        for entry in walker {
+            let entry = entry?;
+            if !entry.file_type().is_some_and(|t| t.is_file()) || entry.path() == archive {
+                continue;
+            }
+            let name = entry
+                .path()
+                .strip_prefix(root)
+                .unwrap_or(entry.path())
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let size = entry.metadata()?.len();
+            total += size;
+            if total > limits.max_bytes {
+                bail!(
+                    "files add up to more than {}; raise max_size_mb to archive them",
+                    format_bytes(limits.max_bytes)
+                );
+            }
+            files.push((entry.path().to_path_buf(), name, size));
+            if files.len() > limits.max_entries {
+                bail!("more than {} files to archive", limits.max_entries);
+            }
+        }
+    }
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+    files.dedup_by(|a, b| a.1 == b.1);
+    Ok(files)
+}
+
+fn write_zip(file: File, files: &[(PathBuf, String, u64)]) -> Result<()> {
+    let mut zip = ZipWriter::new(file);
+    for (path, name, size) in files {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(*size >= u32::MAX as u64);
+        #[cfg(unix)]
+        let options = {
+            use std::os::unix::fs::PermissionsExt;
+            options.unix_permissions(fs::metadata(path)?.permissions().mode())
+        };
+        zip.start_file(name.as_str(), options)?;
+        io::copy(&mut File::open(path)?, &mut zip)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+fn write_tar<W: Write>(writer: W, files: &[(PathBuf, String, u64)]) -> Result<W> {
+    let mut tar = tar::Builder::new(writer);
+    tar.follow_symlinks(false);
+    for (path, name, _) in files {
+        tar.append_path_with_name(path, name)?;
+    }
+    Ok(tar.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: Limits = Limits {
+        max_bytes: 1024 * 1024,
+        max_entries: 100,
+    };
+
+    #[test]
+    fn test_create_and_extract_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.path().join("src/nested/lib.rs"), "pub fn f() {}").unwrap();
+
+        for name in ["out.zip", "out.tar", "out.tar.gz"] {
+            let archive = dir.path().join(name);
+            let format = ArchiveFormat::from_path(&archive).unwrap();
+            let report = create(
+                &archive,
+                format,
+                &[dir.path().join("src")],
+                dir.path(),
+                LIMITS,
+            )
+            .unwrap();
+            assert_eq!(report.files, 2);
+
+            let destination = dir.path().join(format!("{}.d", name));
+            let report = extract(&archive, format, &destination, false, LIMITS).unwrap();
+            assert_eq!(report.files, 2, "{}", name);
+            assert_eq!(
+                fs::read_to_string(destination.join("src/nested/lib.rs")).unwrap(),
+                "pub fn f() {}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_archive_stem_and_safe_paths() {
+        assert_eq!(archive_stem(Path::new("dist/app-1.2.tar.gz")), "app-1.2");
+        assert_eq!(archive_stem(Path::new("data.ZIP")), "data");
+        assert_eq!(
+            safe_relative_path(Path::new("./a/b.txt")),
+            Some(PathBuf::from("a/b.txt"))
+        );
+        assert_eq!(safe_relative_path(Path::new("a/../../b")), None);
+        assert_eq!(safe_relative_path(Path::new("/etc/passwd")), None);
+    }
+
+    #[test]
+    fn test_extract_rejects_traversal_and_oversized_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("evil.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        for name in ["../escape.txt", "/etc/evil", "ok.txt"] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(b"x").unwrap();
+        }
+        zip.start_file("big.bin", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(&[0u8; 4096]).unwrap();
+        zip.finish().unwrap();
+
+        let destination = dir.path().join("out");
+        let small = Limits {
+            max_bytes: 1024,
+            ..LIMITS
+        };
+        assert!(extract(&archive, ArchiveFormat::Zip, &destination, false, small).is_err());
+        // Everything the failed extraction created is gone again
+        assert!(!destination.exists());
+
+        let report = extract(&archive, ArchiveFormat::Zip, &destination, false, LIMITS).unwrap();
+        assert_eq!(report.files, 2);
+        assert_eq!(report.skipped.len(), 2);
+        assert!(!dir.path().join("escape.txt").exists());
+        assert!(destination.join("ok.txt").exists());
+    }
+}
@@ -0,0 +1,491 @@
+//! Cargo tool: cargo.
+//!
+//! Runs `cargo check`, `clippy`, `test` or `build` with
+//! `--message-format=json` and reports the compiler diagnostics as
+//! `file:line:column: level[code]: message` lines, errors first, instead of
+//! the rendered compiler output. For `cargo test`, failed tests are listed
+//! with their panic location and message, parsed from the libtest output
+//! that cargo passes through on stdout.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::{resolve_working_dir, ToolContext};
+use super::grep::bool_arg;
+
+const SUBCOMMANDS: &[&str] = &["check", "clippy", "test", "build"];
+
+/// Diagnostics listed in the text output unless `max_diagnostics` is given.
+const DEFAULT_MAX_DIAGNOSTICS: usize = 50;
+
+/// Help and note lines kept per diagnostic.
+const MAX_HINTS: usize = 2;
+
+/// Lines of cargo's stderr shown when it failed without diagnostics.
+const STDERR_TAIL_LINES: usize = 20;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 600;
+
+/// One compiler diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Relative to the workspace root, as cargo reports it
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// "error" or "warning"
+    pub level: String,
+    /// Error code or lint name, e.g. "E0308" or "clippy::needless_return"
+    pub code: Option<String>,
+    pub message: String,
+    /// Help and note messages attached to the diagnostic
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<String>,
+}
+
+impl Diagnostic {
+    fn is_error(&self) -> bool {
+        self.level.starts_with("error")
+    }
+
+    fn location(&self) -> String {
+        match (&self.file, self.line, self.column) {
+            (Some(file), Some(line), Some(column)) => format!("{}:{}:{}", file, line, column),
+            (Some(file), Some(line), None) => format!("{}:{}", file, line),
+            (Some(file), None, _) => file.clone(),
+            (None, _, _) => "<no location>".to_string(),
+        }
+    }
+}
+
+/// A failed test from the libtest output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TestFailure {
+    pub name: String,
+    /// `file:line:column` of the panic
+    pub location: Option<String>,
+    pub message: Option<String>,
+}
+
+/// Test counts summed over every test binary.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+}
+
+/// Everything parsed from a cargo run's stdout.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CargoReport {
+    pub diagnostics: Vec<Diagnostic>,
+    pub failed_tests: Vec<TestFailure>,
+    /// Present when test results were seen
+    pub tests: Option<TestSummary>,
+}
+
+impl CargoReport {
+    fn count(&self, errors: bool) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.is_error() == errors)
+            .count()
+    }
+}
+
+/// Execute the `cargo` tool.
+pub async fn execute_cargo<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing cargo tool call");
+
+    if !ctx.config.cargo.enabled {
+        return Ok(
+            "❌ The cargo tool is not enabled; set cargo.enabled = true in the config".to_string(),
+        );
+    }
+    let args = &tool_call.args;
+    let Some(subcommand) = args.get("subcommand").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing subcommand argument".to_string());
+    };
+    if !SUBCOMMANDS.contains(&subcommand) {
+        return Ok(format!(
+            "❌ Unsupported subcommand '{}' (expected one of {})",
+            subcommand,
+            SUBCOMMANDS.join(", ")
+        ));
+    }
+    let dir = match args.get("path").and_then(|v| v.as_str()) {
+        Some(path) => match resolve_working_dir(&ctx.workspace_dir(), path) {
+            Ok(dir) => dir,
+            Err(refusal) => return Ok(refusal),
+        },
+        None => ctx.workspace_dir(),
+    };
+
+    let mut cargo_args = vec![subcommand.to_string(), "--message-format=json".to_string()];
+    if let Some(package) = args.get("package").and_then(|v| v.as_str()) {
+        cargo_args.push("--package".to_string());
+        cargo_args.push(package.to_string());
+    }
+    if bool_arg(args, "all_targets") {
+        cargo_args.push("--all-targets".to_string());
+    }
+    if let Some(extra) = args.get("args").and_then(|v| v.as_array()) {
+        cargo_args.extend(extra.iter().filter_map(|a| a.as_str()).map(str::to_string));
+    }
+    let max_diagnostics = args
+        .get("max_diagnostics")
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_MAX_DIAGNOSTICS, |n| n as usize);
+    let timeout = args
+        .get("timeout_secs")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+
+    let command_line = format!("cargo {}", cargo_args.join(" "));
+    ctx.ui_writer
+        .print_context_status(&format!("🦀 Running {}...", command_line));
+    let output = match run_cargo(&cargo_args, &dir, Duration::from_secs(timeout)).await {
+        Ok(Some(output)) => output,
+        Ok(None) => return Ok(format!("❌ {} timed out after {}s", command_line, timeout)),
+        Err(e) => return Ok(format!("❌ Failed to run cargo: {}", e)),
+    };
+
+    let report = parse_cargo_output(&String::from_utf8_lossy(&output.stdout));
+    if args.get("format").and_then(|v| v.as_str()) == Some("json") {
+        return Ok(serde_json::to_string_pretty(&report)?);
+    }
+    Ok(format_report(
+        &command_line,
+        output.status.success(),
+        &report,
+        &String::from_utf8_lossy(&output.stderr),
+        max_diagnostics,
+    ))
+}
+
+/// Run cargo in `dir`. Returns None if it timed out.
+async fn run_cargo(
+    args: &[String],
+    dir: &Path,
+    timeout: Duration,
+) -> std::io::Result<Option<std::process::Output>> {
+    let child = tokio::process::Command::new("cargo")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output.map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parse the stdout of a cargo run with `--message-format=json`.
+pub fn parse_cargo_output(stdout: &str) -> CargoReport {
+    let mut report = CargoReport {
+        diagnostics: parse_diagnostics(stdout),
+        ..Default::default()
+    };
+
+    // Whatever isn't a JSON record is the output of test binaries
+    let mut lines = stdout.lines().filter(|l| !l.starts_with('{')).peekable();
+    while let Some(line) = lines.next() {
+        if let Some(name) = line
+            .strip_prefix("test ")
+            .and_then(|l| l.strip_suffix(" ... FAILED"))
+        {
+            if !report.failed_tests.iter().any(|t| t.name == name) {
+                report.failed_tests.push(TestFailure {
+                    name: name.to_string(),
+                    location: None,
+                    message: None,
+                });
+            }
+        } else if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|l| l.strip_suffix(" stdout ----"))
+        {
+            // The captured output of a failed test, up to the next section
+            let mut section = Vec::new();
+            while let Some(next) = lines.peek() {
+                if next.starts_with("---- ") || *next == "failures:" {
+                    break;
+                }
+                section.push(lines.next().unwrap_or_default());
+            }
+            let (location, message) = parse_panic(&section);
+            if let Some(failure) = report.failed_tests.iter_mut().find(|t| t.name == name) {
+                failure.location = location;
+                failure.message = message;
+            }
+        } else if let Some(result) = line.strip_prefix("test result: ") {
+            let summary = report.tests.get_or_insert_with(TestSummary::default);
+            for part in result.split(';') {
+                let mut words = part.split_whitespace().rev();
+                let (Some(label), Some(count)) = (words.next(), words.next()) else {
+                    continue;
+                };
+                let Ok(count) = count.parse::<usize>() else {
+                    continue;
+                };
+                match label {
+                    "passed" => summary.passed += count,
+                    "failed" => summary.failed += count,
+                    "ignored" => summary.ignored += count,
+                    _ => {}
+                }
+            }
+        }
+    }
+    report
+}
+
+/// The `compiler-message` records in cargo's JSON output, without
+/// duplicates (a warning is reported once per target) or rustc's
+/// "aborting due to" / "N warnings emitted" summaries.
+pub fn parse_diagnostics(stdout: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for line in stdout.lines() {
+        let Ok(record) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if record.get("reason").and_then(|r| r.as_str()) != Some("compiler-message") {
+            continue;
+        }
+        let message = &record["message"];
+        let text = message["message"].as_str().unwrap_or("");
+        let primary_span = message["spans"].as_array().and_then(|spans| {
+            spans
+                .iter()
+                .find(|s| s["is_primary"].as_bool() == Some(true))
+        });
+        if primary_span.is_none()
+            && (text.starts_with("aborting due to") || text.ends_with("emitted"))
+        {
+            continue;
+        }
+
+        let hints = message["children"]
+            .as_array()
+            .map(|children| {
+                children
+                    .iter()
+                    .filter_map(|c| {
+                        let text = c["message"].as_str().filter(|m| !m.is_empty())?;
+                        Some(format!(
+                            "{}: {}",
+                            c["level"].as_str().unwrap_or("note"),
+                            text
+                        ))
+                    })
+                    .take(MAX_HINTS)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let as_usize = |v: &serde_json::Value| v.as_u64().map(|n| n as usize);
+        let diagnostic = Diagnostic {
+            file: primary_span.and_then(|s| s["file_name"].as_str().map(str::to_string)),
+            line: primary_span.and_then(|s| as_usize(&s["line_start"])),
+            column: primary_span.and_then(|s| as_usize(&s["column_start"])),
+            level: message["level"].as_str().unwrap_or("warning").to_string(),
+            code: message["code"]["code"].as_str().map(str::to_string),
+            message: text.to_string(),
+            hints,
+        };
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics
+}
+
+/// Panic location and message from a failed test's captured output.
+///
+/// Handles both `panicked at src/lib.rs:3:5:` followed by the message on
+/// the next line (Rust 1.73+) and the older `panicked at 'msg', src/lib.rs:3:5`.
+fn parse_panic(section: &[&str]) -> (Option<String>, Option<String>) {
+    for (i, line) in section.iter().enumerate() {
+        let Some((_, rest)) = line.split_once("panicked at ") else {
+            continue;
+        };
+        if let Some(location) = rest.strip_suffix(':') {
+            let message = section[i + 1..]
+                .iter()
+                .map(|l| l.trim())
+                .find(|l| !l.is_empty())
+                .map(str::to_string);
+            return (Some(location.to_string()), message);
+        }
+        if let Some((message, location)) = rest.rsplit_once("', ") {
+            return (
+                Some(location.to_string()),
+                Some(message.trim_start_matches('\'').to_string()),
+            );
+        }
+        return (None, Some(rest.to_string()));
+    }
+    (None, None)
+}
+
+fn format_report(
+    command_line: &str,
+    success: bool,
+    report: &CargoReport,
+    stderr: &str,
+    max_diagnostics: usize,
+) -> String {
+    let (errors, warnings) = (report.count(true), report.count(false));
+    let mut lines = vec![format!(
+        "{} {}: {} error(s), {} warning(s)",
+        if success { "✅" } else { "⚠️" },
+        command_line,
+        errors,
+        warnings
+    )];
+    if let Some(tests) = &report.tests {
+        lines[0].push_str(&format!(
+            "; tests: {} passed, {} failed, {} ignored",
+            tests.passed, tests.failed, tests.ignored
+        ));
+    }
+
+    let mut diagnostics: Vec<&Diagnostic> = report.diagnostics.iter().collect();
+    diagnostics.sort_by_key(|d| !d.is_error());
+    for diagnostic in diagnostics.iter().take(max_diagnostics) {
+        let code = diagnostic
+            .code
+            .as_ref()
+            .map(|c| format!("[{}]", c))
+            .unwrap_or_default();
+        lines.push(format!(
+            "{}: {}{}: {}",
+            diagnostic.location(),
+            diagnostic.level,
+            code,
+            diagnostic.message
+        ));
+        for hint in &diagnostic.hints {
+            lines.push(format!("    {}", hint));
+        }
+    }
+    if diagnostics.len() > max_diagnostics {
+        lines.push(format!(
+            "... and {} more diagnostics",
+            diagnostics.len() - max_diagnostics
+        ));
+    }
+
+    if !report.failed_tests.is_empty() {
+        lines.push(String::new());
+        lines.push("Failed tests:".to_string());
+        for test in &report.failed_tests {
+            let mut line = format!("- {}", test.name);
+            if let Some(location) = &test.location {
+                line.push_str(&format!(" (panicked at {})", location));
+            }
+            if let Some(message) = &test.message {
+                line.push_str(&format!(": {}", message));
+            }
+            lines.push(line);
+        }
+    }
+
+    // Manifest, resolution and linker errors only show up on stderr
+    if !success && errors == 0 && report.failed_tests.is_empty() {
+        let stderr: Vec<&str> = stderr.lines().collect();
+        lines.push(String::new());
+        lines.extend(
+            stderr[stderr.len().saturating_sub(STDERR_TAIL_LINES)..]
+                .iter()
+                .map(|l| l.to_string()),
+        );
+    }
+    lines.join("\n").trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_output() {
+        let stdout = r#"{"reason":"compiler-artifact","target":{}}
+{"reason":"compiler-message","message":{"message":"mismatched types","level":"error","code":{"code":"E0308"},"spans":[{"file_name":"src/lib.rs","line_start":3,"column_start":17,"is_primary":true}],"children":[{"message":"expected `u32`, found `&str`","level":"note"}]}}
+{"reason":"compiler-message","message":{"message":"unused variable: `x`","level":"warning","code":{"code":"unused_variables"},"spans":[{"file_name":"src/lib.rs","line_start":8,"column_start":9,"is_primary":true}],"children":[]}}
+{"reason":"compiler-message","message":{"message":"unused variable: `x`","level":"warning","code":{"code":"unused_variables"},"spans":[{"file_name":"src/lib.rs","line_start":8,"column_start":9,"is_primary":true}],"children":[]}}
+{"reason":"compiler-message","message":{"message":"aborting due to 1 previous error","level":"error","code":null,"spans":[],"children":[]}}
+
+running 3 tests
+test tests::adds ... ok
+test tests::parses ... FAILED
+test tests::slow ... ignored
+
+failures:
+
+---- tests::parses stdout ----
+
+thread 'tests::parses' panicked at src/parser.rs:42:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+
+failures:
+    tests::parses
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s
+"#;
+
+        let report = parse_cargo_output(stdout);
+        assert_eq!(report.diagnostics.len(), 2);
+        let error = &report.diagnostics[0];
+        assert_eq!(error.location(), "src/lib.rs:3:17");
+        assert_eq!(error.code.as_deref(), Some("E0308"));
+        assert_eq!(error.hints, ["note: expected `u32`, found `&str`"]);
+        assert_eq!(report.count(true), 1);
+        assert_eq!(report.count(false), 1);
+
+        assert_eq!(
+            report.failed_tests,
+            [TestFailure {
+                name: "tests::parses".to_string(),
+                location: Some("src/parser.rs:42:9".to_string()),
+                message: Some("assertion `left == right` failed".to_string()),
+            }]
+        );
+        assert_eq!(
+            report.tests,
+            Some(TestSummary {
+                passed: 1,
+                failed: 1,
+                ignored: 1
+            })
+        );
+
+        let text = format_report("cargo test", false, &report, "", 50);
+        assert!(text.starts_with("⚠️ cargo test: 1 error(s), 1 warning(s); tests: 1 passed"));
+        assert!(text.contains("src/lib.rs:3:17: error[E0308]: mismatched types"));
+        assert!(text.contains("- tests::parses (panicked at src/parser.rs:42:9): assertion"));
+    }
+
+    #[test]
+    fn test_parse_panic_old_format() {
+        let section = ["thread 'a' panicked at 'boom', src/lib.rs:3:5"];
+        assert_eq!(
+            parse_panic(&section),
+            (Some("src/lib.rs:3:5".to_string()), Some("boom".to_string()))
+        );
+    }
+}
@@ -0,0 +1,439 @@
+//! Changelog tool: changelog.
+//!
+//! Records the changes made in a session under the `[Unreleased]` section of
+//! a Keep a Changelog formatted file (CHANGELOG.md by default), creating the
+//! file, section, or category headings as needed.
+
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Keep a Changelog categories, in the order they appear in a release section.
+const CATEGORIES: &[(&str, &str)] = &[
+    ("added", "Added"),
+    ("changed", "Changed"),
+    ("deprecated", "Deprecated"),
+    ("removed", "Removed"),
+    ("fixed", "Fixed"),
+    ("security", "Security"),
+];
+
+const UNRELEASED_HEADING: &str = "## [Unreleased]";
+
+const CHANGELOG_HEADER: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+";
+
+/// Execute the changelog tool.
+pub async fn execute_changelog<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing changelog tool call");
+
+    if !ctx.config.changelog.tool_enabled() {
+        return Ok(
+            "❌ The changelog tool is not enabled; set changelog.enabled = true in the config"
+                .to_string(),
+        );
+    }
+    let changes: Vec<(&str, Vec<String>)> = CATEGORIES
+        .iter()
+        .map(|(key, heading)| {
+            let entries = tool_call
+                .args
+                .get(*key)
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default();
+            (*heading, entries)
+        })
+        .filter(|(_, entries): &(&str, Vec<String>)| !entries.is_empty())
+        .collect();
+
+    if changes.is_empty() {
+        return Ok(format!(
+            "❌ No changes given. Provide at least one of: {}",
+            CATEGORIES
+                .iter()
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let relative = tool_call
+        .args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .unwrap_or(&ctx.config.changelog.path);
+    let path = match changelog_path(&ctx.workspace_dir(), relative) {
+        Ok(path) => path,
+        Err(message) => return Ok(message),
+    };
+
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+    let (updated, added) = add_unreleased_entries(&existing, &changes);
+
+    if added == 0 {
+        return Ok(format!(
+            "✅ {} already lists these changes under [Unreleased]",
+            relative
+        ));
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, updated)?;
+
+    Ok(format!(
+        "✅ Added {} entr{} to {} under [Unreleased]",
+        added,
+        if added == 1 { "y" } else { "ies" },
+        relative
+    ))
+}
+
+/// `relative` inside `workspace`. Absolute paths, `..` and symlinked
+/// directories that lead out of the workspace are refused; `Err` holds the
+/// message refusing the call.
+fn changelog_path(workspace: &Path, relative: &str) -> Result<PathBuf, String> {
+    let outside = || {
+        format!(
+            "❌ Changelog path '{}' is outside the workspace ({})",
+            relative,
+            workspace.display()
+        )
+    };
+    if Path::new(relative)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(outside());
+    }
+
+    let path = workspace.join(relative);
+    // The nearest existing directory, with symlinks resolved, must still be
+    // in the workspace
+    let root = workspace
+        .canonicalize()
+        .unwrap_or_else(|_| workspace.to_path_buf());
+    let existing = path
+        .ancestors()
+        .skip(1)
+        .find_map(|dir| dir.canonicalize().ok());
+    match existing {
+        Some(dir) if !dir.starts_with(&root) => Err(outside()),
+        _ => Ok(path),
+    }
+}
+
+/// Whether `line` is the Unreleased section heading: `## [Unreleased]`,
+/// optionally linked as `## [Unreleased](url)`.
+fn is_unreleased_heading(line: &str) -> bool {
+    let line = line.trim();
+    match line.get(..UNRELEASED_HEADING.len()) {
+        Some(heading) if heading.eq_ignore_ascii_case(UNRELEASED_HEADING) => {
+            let rest = line[UNRELEASED_HEADING.len()..].trim_start();
+            rest.is_empty() || rest.starts_with('(')
+        }
+        _ => false,
+    }
+}
+
+/// Insert entries under `## [Unreleased]`, grouped by category heading.
+///
+/// Entries already present in the section are skipped. Returns the updated
+/// changelog and the number of entries added.
+pub fn add_unreleased_entries(changelog: &str, changes: &[(&str, Vec<String>)]) -> (String, usize) {
+    let mut lines: Vec<String> = if changelog.trim().is_empty() {
+        CHANGELOG_HEADER.lines().map(String::from).collect()
+    } else {
+        changelog.lines().map(String::from).collect()
+    };
+
+    // Locate the Unreleased section, or the spot above the first release
+    let (start, end) = match lines.iter().position(|l| is_unreleased_heading(l)) {
+        Some(idx) => (idx, section_end(&lines, idx)),
+        None => {
+            let idx = lines
+                .iter()
+                .position(|l| l.starts_with("## "))
+                .unwrap_or(lines.len());
+            (idx, idx)
+        }
+    };
+
+    let body: &[String] = if start < end {
+        &lines[start + 1..end]
+    } else {
+        &[]
+    };
+    let mut section = Section::parse(body);
+    let added = changes
+        .iter()
+        .map(|(heading, entries)| {
+            entries
+                .iter()
+                .filter(|entry| section.add(heading, entry))
+                .count()
+        })
+        .sum();
+
+    if added == 0 {
+        return (changelog.to_string(), 0);
+    }
+
+    // Keep the existing heading, link included
+    let heading = if start < end {
+        lines[start].trim()
+    } else {
+        UNRELEASED_HEADING
+    };
+    let mut rendered = section.render(heading);
+    if start > 0 && !lines[start - 1].trim().is_empty() {
+        rendered.insert(0, String::new());
+    }
+    lines.splice(start..end, rendered);
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    (result, added)
+}
+
+/// Turn the `[Unreleased]` section into a release section for `version`,
+/// leaving an empty `[Unreleased]` section above it.
+///
+/// Returns None if there is no `[Unreleased]` section or it has no entries.
+pub fn promote_unreleased(changelog: &str, version: &str, date: &str) -> Option<String> {
+    let mut lines: Vec<String> = changelog.lines().map(String::from).collect();
+    let start = lines.iter().position(|l| is_unreleased_heading(l))?;
+    let end = section_end(&lines, start);
+    if !lines[start + 1..end].iter().any(|l| l.starts_with("- ")) {
+        return None;
+    }
+
+    lines[start] = format!("## [{}] - {}", version, date);
+    lines.splice(
+        start..start,
+        [UNRELEASED_HEADING.to_string(), String::new()],
+    );
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    Some(result)
+}
+
+/// Index of the first line after the section starting at `start`.
+fn section_end(lines: &[String], start: usize) -> usize {
+    lines[start + 1..]
+        .iter()
+        .position(|l| l.starts_with("## "))
+        .map(|p| start + 1 + p)
+        .unwrap_or(lines.len())
+}
+
+/// Position of a category in the canonical ordering (None for custom headings).
+fn category_rank(heading: &str) -> Option<usize> {
+    CATEGORIES.iter().position(|(_, name)| *name == heading)
+}
+
+/// The body of the Unreleased section, split into category headings.
+struct Section {
+    /// Lines before the first `###` heading
+    preamble: Vec<String>,
+    categories: Vec<(String, Vec<String>)>,
+}
+
+impl Section {
+    fn parse(body: &[String]) -> Self {
+        let mut section = Section {
+            preamble: Vec::new(),
+            categories: Vec::new(),
+        };
+        for line in body {
+            if let Some(heading) = line.strip_prefix("### ") {
+                section
+                    .categories
+                    .push((heading.trim().to_string(), Vec::new()));
+            } else if line.trim().is_empty() {
+                continue;
+            } else if let Some((_, entries)) = section.categories.last_mut() {
+                entries.push(line.clone());
+            } else {
+                section.preamble.push(line.clone());
+            }
+        }
+        section
+    }
+
+    /// Add an entry under `heading`. Returns false if it is already listed.
+    fn add(&mut self, heading: &str, entry: &str) -> bool {
+        let bullet = format!("- {}", entry);
+        if self
+            .categories
+            .iter()
+            .flat_map(|(_, entries)| entries)
+            .any(|l| l.trim() == bullet)
+        {
+            return false;
+        }
+
+        let idx = match self.categories.iter().position(|(h, _)| h == heading) {
+            Some(idx) => idx,
+            None => {
+                let rank = category_rank(heading);
+                let idx = self
+                    .categories
+                    .iter()
+                    .position(|(h, _)| category_rank(h).is_some_and(|r| Some(r) > rank))
+                    .unwrap_or(self.categories.len());
+                self.categories
+                    .insert(idx, (heading.to_string(), Vec::new()));
+                idx
+            }
+        };
+        self.categories[idx].1.push(bullet);
+        true
+    }
+
+    fn render(&self, heading: &str) -> Vec<String> {
+        let mut lines = vec![heading.to_string(), String::new()];
+        if !self.preamble.is_empty() {
+            lines.extend(self.preamble.iter().cloned());
+            lines.push(String::new());
+        }
+        for (heading, entries) in &self.categories {
+            lines.push(format!("### {}", heading));
+            lines.extend(entries.iter().cloned());
+            lines.push(String::new());
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(items: &[(&'static str, &[&str])]) -> Vec<(&'static str, Vec<String>)> {
+        items
+            .iter()
+            .map(|(h, e)| (*h, e.iter().map(|s| s.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_creates_changelog_from_scratch() {
+        let (out, added) = add_unreleased_entries("", &changes(&[("Added", &["New tool"])]));
+        assert_eq!(added, 1);
+        assert!(out.starts_with("# Changelog"));
+        assert!(
+            out.contains("## [Unreleased]\n\n### Added\n- New tool\n"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_inserts_above_existing_release() {
+        let existing = "# Changelog\n\n## [1.0.0] - 2024-01-01\n\n### Added\n- First release\n";
+        let (out, _) =
+            add_unreleased_entries(existing, &changes(&[("Fixed", &["Crash on start"])]));
+        let unreleased = out.find("## [Unreleased]").unwrap();
+        let fixed = out.find("- Crash on start").unwrap();
+        let release = out.find("## [1.0.0]").unwrap();
+        assert!(unreleased < fixed && fixed < release, "{}", out);
+        assert!(out.contains("- Crash on start\n\n## [1.0.0]"), "{}", out);
+    }
+
+    #[test]
+    fn test_appends_to_existing_category_in_order() {
+        let existing = "# Changelog\n\n## [Unreleased]\n\n### Fixed\n- Old fix\n\n## [1.0.0]\n";
+        let (out, added) = add_unreleased_entries(
+            existing,
+            &changes(&[("Added", &["Feature"]), ("Fixed", &["New fix", "Old fix"])]),
+        );
+        assert_eq!(added, 2);
+        assert!(
+            out.contains(
+                "## [Unreleased]\n\n### Added\n- Feature\n\n### Fixed\n- Old fix\n- New fix\n\n## [1.0.0]"
+            ),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_promote_unreleased() {
+        let existing =
+            "# Changelog\n\n## [Unreleased]\n\n### Added\n- Feature\n\n## [1.0.0] - 2024-01-01\n";
+        let out = promote_unreleased(existing, "1.1.0", "2024-02-01").unwrap();
+        assert_eq!(
+            out,
+            "# Changelog\n\n## [Unreleased]\n\n## [1.1.0] - 2024-02-01\n\n### Added\n- Feature\n\n## [1.0.0] - 2024-01-01\n"
+        );
+
+        // Nothing to release
+        assert!(promote_unreleased(&out, "1.2.0", "2024-03-01").is_none());
+        assert!(promote_unreleased("# Changelog\n", "1.2.0", "2024-03-01").is_none());
+    }
+
+    #[test]
+    fn test_duplicate_entries_are_skipped() {
+        let existing = "# Changelog\n\n## [Unreleased]\n\n### Added\n- Feature\n";
+        let (out, added) = add_unreleased_entries(existing, &changes(&[("Added", &["Feature"])]));
+        assert_eq!(added, 0);
+        assert_eq!(out, existing);
+    }
+
+    #[test]
+    fn test_linked_unreleased_heading() {
+        let existing = "# Changelog\n\n## [Unreleased](https://example.com/compare/v1.0.0...HEAD)\n\n### Added\n- Feature\n\n## [1.0.0] - 2024-01-01\n";
+        let (out, added) = add_unreleased_entries(existing, &changes(&[("Fixed", &["Bug"])]));
+        assert_eq!(added, 1);
+        assert!(out.contains("## [Unreleased](https://example.com/compare/v1.0.0...HEAD)\n"));
+        assert_eq!(out.matches("[Unreleased]").count(), 1);
+        assert!(out.contains("- Feature\n\n### Fixed\n- Bug\n\n## [1.0.0]"));
+        assert!(promote_unreleased(existing, "1.1.0", "2024-02-01").is_some());
+        assert!(!is_unreleased_heading("## [Unreleased] notes"));
+    }
+
+    #[test]
+    fn test_changelog_path_stays_in_workspace() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+        assert_eq!(
+            changelog_path(root, "docs/CHANGELOG.md").unwrap(),
+            root.join("docs/CHANGELOG.md")
+        );
+        assert!(changelog_path(root, "../CHANGELOG.md").is_err());
+        assert!(changelog_path(root, "/etc/CHANGELOG.md").is_err());
+
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().unwrap();
+            std::os::unix::fs::symlink(outside.path(), root.join("link")).unwrap();
+            assert!(changelog_path(root, "link/CHANGELOG.md").is_err());
+        }
+    }
+}
@@ -0,0 +1,444 @@
+//! Checkpoint tools: checkpoint_create and checkpoint_rollback.
+//!
+//! A checkpoint is a shadow commit of the whole working tree (tracked and
+//! untracked files, but not ignored files or `.g3/`) kept under
+//! `refs/g3/checkpoints/<name>`. It is built from an in-memory copy of the
+//! index, so `HEAD`, the current branch and the staging area are never
+//! touched. Rolling back rewrites only the files that differ from the
+//! checkpoint, after saving the current state as a `pre-rollback-N`
+//! checkpoint so the rollback itself can be undone. Both operations are
+//! appended to the session's `checkpoints.jsonl`.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use git2::{Delta, FileMode, IndexAddOption, IndexMatchedPath, Oid, Repository, Signature};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tracing::debug;
+
+use crate::paths::get_checkpoints_file;
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::git::{head_commit, open_repo};
+
+/// Namespace of checkpoint references.
+const CHECKPOINT_REFS: &str = "refs/g3/checkpoints/";
+
+/// Name prefix of checkpoints created without a name.
+const AUTO_PREFIX: &str = "checkpoint-";
+
+/// Name prefix of the checkpoints taken before a rollback.
+const PRE_ROLLBACK_PREFIX: &str = "pre-rollback-";
+
+/// g3's own data directory, never snapshotted or restored.
+const G3_DIR: &str = ".g3";
+
+/// A saved checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub name: String,
+    pub commit: Oid,
+    /// Commit time, in seconds since the epoch
+    pub time: i64,
+    pub summary: String,
+}
+
+/// Execute the `checkpoint_create` tool.
+pub async fn execute_checkpoint_create<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing checkpoint_create tool call");
+
+    let name = tool_call.args.get("name").and_then(|v| v.as_str());
+    let message = tool_call.args.get("message").and_then(|v| v.as_str());
+
+    let result = open_repo(&ctx.workspace_dir()).and_then(|repo| {
+        let tree = snapshot_tree(&repo)?;
+        let name = match name {
+            Some(name) => name.trim().to_string(),
+            None => next_name(&repo, AUTO_PREFIX)?,
+        };
+        let checkpoint = create_checkpoint(
+            &repo,
+            &name,
+            tree,
+            message.unwrap_or(&name),
+            ctx.session_id,
+        )?;
+        let changed = changed_files(&repo, tree)?;
+        record(
+            ctx.session_id,
+            serde_json::json!({
+                "action": "create",
+                "name": checkpoint.name,
+                "commit": checkpoint.commit.to_string(),
+                "message": message,
+                "changed_files": changed,
+            }),
+        );
+        Ok(format!(
+            "✅ Created checkpoint {} ({}, {} files differ from HEAD)\nRoll back with checkpoint_rollback {{\"name\": \"{}\"}}",
+            checkpoint.name,
+            short_id(&repo, checkpoint.commit),
+            changed,
+            checkpoint.name
+        ))
+    });
+    Ok(result.unwrap_or_else(|e| format!("❌ {}", e)))
+}
+
+/// Execute the `checkpoint_rollback` tool.
+pub async fn execute_checkpoint_rollback<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing checkpoint_rollback tool call");
+
+    let name = tool_call.args.get("name").and_then(|v| v.as_str());
+
+    let result = open_repo(&ctx.workspace_dir()).and_then(|repo| {
+        let target = find_checkpoint(&repo, name)?;
+        let current = snapshot_tree(&repo)?;
+        if repo.find_commit(target.commit)?.tree_id() == current {
+            return Ok(format!(
+                "Working tree already matches checkpoint {}",
+                target.name
+            ));
+        }
+
+        let saved = create_checkpoint(
+            &repo,
+            &next_name(&repo, PRE_ROLLBACK_PREFIX)?,
+            current,
+            &format!("Before rolling back to {}", target.name),
+            ctx.session_id,
+        )?;
+        let (restored, removed) = rollback(&repo, current, &target)?;
+        record(
+            ctx.session_id,
+            serde_json::json!({
+                "action": "rollback",
+                "name": target.name,
+                "commit": target.commit.to_string(),
+                "saved_as": saved.name,
+                "restored_files": restored,
+                "removed_files": removed,
+            }),
+        );
+
+        let mut output = format!(
+            "✅ Rolled back to checkpoint {} ({}): {} files restored, {} removed\nThe previous state was saved as checkpoint {}",
+            target.name,
+            short_id(&repo, target.commit),
+            restored,
+            removed,
+            saved.name
+        );
+        let head = head_commit(&repo)?.map(|c| c.id());
+        let checkpoint_head = repo.find_commit(target.commit)?.parent_ids().next();
+        if head != checkpoint_head {
+            output.push_str(
+                "\n⚠️ HEAD has moved since the checkpoint; commits made since were kept, so git_diff shows the rolled back changes",
+            );
+        }
+        Ok(output)
+    });
+    Ok(result.unwrap_or_else(|e| format!("❌ {}", e)))
+}
+
+/// Checkpoints in the repository, oldest first.
+pub fn list_checkpoints(repo: &Repository) -> Result<Vec<Checkpoint>> {
+    let mut checkpoints = Vec::new();
+    for reference in repo.references_glob(&format!("{}*", CHECKPOINT_REFS))? {
+        let reference = reference?;
+        let Some(name) = reference
+            .name()
+            .and_then(|n| n.strip_prefix(CHECKPOINT_REFS))
+        else {
+            continue;
+        };
+        let commit = reference.peel_to_commit()?;
+        checkpoints.push(Checkpoint {
+            name: name.to_string(),
+            commit: commit.id(),
+            time: commit.time().seconds(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+        });
+    }
+    checkpoints.sort_by(|a, b| (a.time, &a.name).cmp(&(b.time, &b.name)));
+    Ok(checkpoints)
+}
+
+/// The checkpoint called `name`, or without a name the most recent one not
+/// taken by a rollback.
+fn find_checkpoint(repo: &Repository, name: Option<&str>) -> Result<Checkpoint> {
+    let checkpoints = list_checkpoints(repo)?;
+    let found = match name {
+        Some(name) => checkpoints.iter().find(|c| c.name == name.trim()),
+        None => checkpoints
+            .iter()
+            .rev()
+            .find(|c| !c.name.starts_with(PRE_ROLLBACK_PREFIX)),
+    };
+    if let Some(checkpoint) = found {
+        return Ok(checkpoint.clone());
+    }
+    if checkpoints.is_empty() {
+        bail!("No checkpoints yet; create one with checkpoint_create");
+    }
+    let names: Vec<&str> = checkpoints.iter().map(|c| c.name.as_str()).collect();
+    match name {
+        Some(name) => bail!(
+            "No checkpoint named '{}' (available: {})",
+            name,
+            names.join(", ")
+        ),
+        None => bail!(
+            "Only rollback checkpoints exist; pass one of these names: {}",
+            names.join(", ")
+        ),
+    }
+}
+
+/// Write a tree of the working tree as it is now, without changing the index
+/// on disk. Returns the tree ID.
+fn snapshot_tree(repo: &Repository) -> Result<Oid> {
+    fn skip_g3_dir(path: &Path, _matched: &[u8]) -> i32 {
+        i32::from(path.starts_with(G3_DIR))
+    }
+
+    // Start from the real index so tracked files matching .gitignore are kept
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        bail!("The index has unresolved conflicts");
+    }
+    index.add_all(
+        ["*"],
+        IndexAddOption::DEFAULT,
+        Some(&mut skip_g3_dir as &mut IndexMatchedPath),
+    )?;
+    index.update_all(["*"], Some(&mut skip_g3_dir as &mut IndexMatchedPath))?;
+    index.remove_dir(Path::new(G3_DIR), 0)?;
+    let tree = index.write_tree()?;
+    // Drop the in-memory changes
+    index.read(true)?;
+    Ok(tree)
+}
+
+/// Commit `tree` on top of `HEAD` and point `refs/g3/checkpoints/<name>` at it.
+fn create_checkpoint(
+    repo: &Repository,
+    name: &str,
+    tree: Oid,
+    message: &str,
+    session_id: Option<&str>,
+) -> Result<Checkpoint> {
+    let reference = format!("{}{}", CHECKPOINT_REFS, name);
+    if name.is_empty() || !git2::Reference::is_valid_name(&reference) {
+        bail!("Invalid checkpoint name '{}'", name);
+    }
+    if repo.find_reference(&reference).is_ok() {
+        bail!("Checkpoint '{}' already exists", name);
+    }
+
+    let mut full_message = format!("g3 checkpoint: {}\n", message.trim());
+    if let Some(session_id) = session_id {
+        full_message.push_str(&format!("\nG3-Session: {}\n", session_id));
+    }
+    // Checkpoints shouldn't fail for want of a git identity
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("g3", "g3@localhost"))?;
+    let parent = head_commit(repo)?;
+    let parents: Vec<_> = parent.iter().collect();
+    let commit = repo.commit(
+        None,
+        &signature,
+        &signature,
+        &full_message,
+        &repo.find_tree(tree)?,
+        &parents,
+    )?;
+    repo.reference(&reference, commit, false, "g3 checkpoint")?;
+
+    let commit = repo.find_commit(commit)?;
+    Ok(Checkpoint {
+        name: name.to_string(),
+        commit: commit.id(),
+        time: commit.time().seconds(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+    })
+}
+
+/// `prefix` followed by one more than the highest number used with it.
+fn next_name(repo: &Repository, prefix: &str) -> Result<String> {
+    let highest = list_checkpoints(repo)?
+        .iter()
+        .filter_map(|c| c.name.strip_prefix(prefix)?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    Ok(format!("{}{}", prefix, highest + 1))
+}
+
+/// Make the working tree match `target`, given the `current` snapshot.
+/// Returns the number of files restored and removed.
+fn rollback(repo: &Repository, current: Oid, target: &Checkpoint) -> Result<(usize, usize)> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("Repository has no working directory"))?;
+    let target_tree = repo.find_commit(target.commit)?.tree()?;
+    let current_tree = repo.find_tree(current)?;
+    let diff = repo.diff_tree_to_tree(Some(&target_tree), Some(&current_tree), None)?;
+
+    // Remove files created since the checkpoint first, so a directory that
+    // replaced a file is gone before the file is restored
+    let mut removed = 0;
+    for delta in diff.deltas().filter(|d| d.status() == Delta::Added) {
+        let path = delta
+            .new_file()
+            .path()
+            .ok_or_else(|| anyhow!("Path is not valid UTF-8"))?;
+        let full_path = workdir.join(path);
+        fs::remove_file(&full_path)?;
+        remove_empty_parents(workdir, &full_path);
+        removed += 1;
+    }
+
+    let mut restored = 0;
+    for delta in diff.deltas().filter(|d| d.status() != Delta::Added) {
+        let file = delta.old_file();
+        let path = file
+            .path()
+            .ok_or_else(|| anyhow!("Path is not valid UTF-8"))?;
+        restore_file(repo, &workdir.join(path), file.id(), file.mode())?;
+        restored += 1;
+    }
+    Ok((restored, removed))
+}
+
+/// Write blob `id` to `path` with the given mode.
+fn restore_file(repo: &Repository, path: &Path, id: Oid, mode: FileMode) -> Result<()> {
+    if mode == FileMode::Commit {
+        // Submodules are left alone
+        return Ok(());
+    }
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if metadata.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
+        }
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let blob = repo.find_blob(id)?;
+    #[cfg(unix)]
+    if mode == FileMode::Link {
+        let target = String::from_utf8_lossy(blob.content()).to_string();
+        std::os::unix::fs::symlink(target, path)?;
+        return Ok(());
+    }
+    fs::write(path, blob.content())?;
+    #[cfg(unix)]
+    if mode == FileMode::BlobExecutable {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+/// Remove the empty directories between `path` and `workdir`.
+fn remove_empty_parents(workdir: &Path, path: &Path) {
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        if d == workdir || fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+}
+
+/// Number of files in `tree` that differ from `HEAD`.
+fn changed_files(repo: &Repository, tree: Oid) -> Result<usize> {
+    let head_tree = head_commit(repo)?.map(|c| c.tree()).transpose()?;
+    let diff = repo.diff_tree_to_tree(head_tree.as_ref(), Some(&repo.find_tree(tree)?), None)?;
+    Ok(diff.deltas().len())
+}
+
+fn short_id(repo: &Repository, id: Oid) -> String {
+    repo.find_object(id, None)
+        .and_then(|o| o.short_id())
+        .map(|s| s.as_str().unwrap_or_default().to_string())
+        .unwrap_or_else(|_| id.to_string())
+}
+
+/// Append a checkpoint event to the session's `checkpoints.jsonl`.
+fn record(session_id: Option<&str>, mut event: serde_json::Value) {
+    let Some(session_id) = session_id else {
+        return;
+    };
+    event["timestamp"] = serde_json::json!(Utc::now().to_rfc3339());
+    let path = get_checkpoints_file(session_id);
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::OpenOptions::new().create(true).append(true).open(&path))
+        .and_then(|mut file| writeln!(file, "{}", event));
+    if let Err(e) = result {
+        debug!("Failed to record checkpoint event: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_and_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let repo = Repository::init(root).unwrap();
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(root.join("a.txt"), "one\n").unwrap();
+        fs::write(root.join("c.txt"), "keep\n").unwrap();
+
+        let tree = snapshot_tree(&repo).unwrap();
+        create_checkpoint(&repo, "start", tree, "start", None).unwrap();
+        assert!(create_checkpoint(&repo, "start", tree, "again", None).is_err());
+        // The staging area is untouched
+        assert!(repo.index().unwrap().is_empty());
+
+        fs::write(root.join("a.txt"), "two\n").unwrap();
+        fs::remove_file(root.join("c.txt")).unwrap();
+        fs::create_dir_all(root.join("new/dir")).unwrap();
+        fs::write(root.join("new/dir/b.txt"), "new\n").unwrap();
+        fs::write(root.join("debug.log"), "ignored\n").unwrap();
+        fs::create_dir_all(root.join(".g3")).unwrap();
+        fs::write(root.join(".g3/state"), "g3\n").unwrap();
+
+        let target = find_checkpoint(&repo, None).unwrap();
+        assert_eq!(target.name, "start");
+        let current = snapshot_tree(&repo).unwrap();
+        assert_eq!(rollback(&repo, current, &target).unwrap(), (2, 1));
+
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "one\n");
+        assert_eq!(fs::read_to_string(root.join("c.txt")).unwrap(), "keep\n");
+        assert!(!root.join("new").exists());
+        // Ignored files and g3's own data are left alone
+        assert!(root.join("debug.log").exists());
+        assert!(root.join(".g3/state").exists());
+        assert_eq!(snapshot_tree(&repo).unwrap(), tree);
+
+        assert_eq!(next_name(&repo, AUTO_PREFIX).unwrap(), "checkpoint-1");
+        assert!(find_checkpoint(&repo, Some("missing"))
+            .unwrap_err()
+            .to_string()
+            .contains("available: start"));
+    }
+}
@@ -0,0 +1,456 @@
+//! Code coverage tool: code_coverage.
+//!
+//! Runs `cargo llvm-cov` over the whole workspace, or only over the packages
+//! asked for (or the packages owning the paths asked for), and reports line
+//! coverage per file. Each run's numbers are kept in the session directory
+//! so the next run can show what changed, which makes "did my new tests
+//! cover this?" a quick per-package run instead of a full workspace one.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::paths::get_session_logs_dir;
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::search_path;
+
+/// Maximum number of files listed in the text output.
+const MAX_FILES: usize = 200;
+
+/// File in the session directory holding the last run's per-file coverage.
+const BASELINE_FILE: &str = "coverage.json";
+
+/// Covered and coverable line counts for one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineCoverage {
+    pub covered: u64,
+    pub total: u64,
+}
+
+impl LineCoverage {
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.covered as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+/// One file's coverage in this run, against the last run.
+#[derive(Debug, Serialize)]
+pub struct FileReport {
+    pub path: String,
+    #[serde(flatten)]
+    pub lines: LineCoverage,
+    pub percent: f64,
+    /// Change in percentage points; None when the file wasn't in the last run
+    pub delta: Option<f64>,
+}
+
+/// Execute the `code_coverage` tool.
+pub async fn execute_code_coverage<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing code_coverage tool call");
+
+    let args = &tool_call.args;
+    let dir = search_path(ctx, None);
+    let mut packages = string_list(args, "packages");
+    let paths = string_list(args, "paths");
+
+    // Package directories scope the report: a package's tests also run code
+    // in its workspace dependencies, whose partial numbers would be noise
+    let mut scope_dirs = Vec::new();
+    if !packages.is_empty() || !paths.is_empty() {
+        let members = match workspace_packages(&dir).await {
+            Ok(members) => members,
+            Err(e) => return Ok(format!("❌ {}", e)),
+        };
+        if packages.is_empty() {
+            packages = match packages_for_paths(&members, &dir, &paths) {
+                Ok(packages) => packages,
+                Err(e) => return Ok(format!("❌ {}", e)),
+            };
+        }
+        for package in &packages {
+            match members.get(package) {
+                Some(package_dir) => scope_dirs.push(package_dir.clone()),
+                None => {
+                    return Ok(format!(
+                        "❌ Unknown package '{}' (workspace packages: {})",
+                        package,
+                        members.keys().cloned().collect::<Vec<_>>().join(", ")
+                    ))
+                }
+            }
+        }
+    }
+
+    // Ensure coverage tools are installed
+    match tokio::task::spawn_blocking(g3_execution::ensure_coverage_tools_installed).await? {
+        Ok(already_installed) => {
+            if !already_installed {
+                ctx.ui_writer
+                    .print_context_status("✅ Coverage tools installed successfully");
+            }
+        }
+        Err(e) => {
+            return Ok(format!("❌ Failed to install coverage tools: {}", e));
+        }
+    }
+
+    let mut cargo_args = vec!["llvm-cov", "--json", "--summary-only"];
+    if packages.is_empty() {
+        cargo_args.push("--workspace");
+    } else {
+        for package in &packages {
+            cargo_args.extend(["--package", package.as_str()]);
+        }
+    }
+    let target = if packages.is_empty() {
+        "the workspace".to_string()
+    } else {
+        packages.join(", ")
+    };
+    ctx.ui_writer.print_context_status(&format!(
+        "🔍 Generating code coverage report for {}...",
+        target
+    ));
+
+    let output = tokio::process::Command::new("cargo")
+        .args(&cargo_args)
+        .current_dir(&dir)
+        .output()
+        .await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Ok(format!(
+            "❌ Failed to generate coverage report:\n{}",
+            stderr
+        ));
+    }
+
+    let files = match parse_llvm_cov(&String::from_utf8_lossy(&output.stdout), &dir) {
+        Ok(files) => files,
+        Err(e) => return Ok(format!("❌ Could not read cargo llvm-cov output: {}", e)),
+    };
+    let files: BTreeMap<String, LineCoverage> = files
+        .into_iter()
+        .filter(|(path, _)| scope_dirs.is_empty() || scope_dirs.iter().any(|d| in_dir(path, d)))
+        .filter(|(path, _)| paths.is_empty() || paths.iter().any(|p| in_dir(path, p)))
+        .collect();
+
+    // Compare with, then update, the last run's numbers for these files
+    let baseline_path = ctx
+        .session_id
+        .map(|id| get_session_logs_dir(id).join(BASELINE_FILE));
+    let mut baseline = baseline_path
+        .as_deref()
+        .map(load_baseline)
+        .unwrap_or_default();
+    let reports = compare(&files, &baseline);
+    let previous = previous_total(&files, &baseline);
+    if let Some(path) = &baseline_path {
+        baseline.extend(files.iter().map(|(p, c)| (p.clone(), *c)));
+        if let Err(e) = save_baseline(path, &baseline) {
+            debug!("Failed to save coverage baseline: {}", e);
+        }
+    }
+
+    let total = total(&files);
+    if args.get("format").and_then(|v| v.as_str()) == Some("json") {
+        return Ok(serde_json::to_string_pretty(&serde_json::json!({
+            "packages": packages,
+            "total": total,
+            "percent": total.percent(),
+            "delta": previous.map(|p| total.percent() - p.percent()),
+            "files": reports,
+        }))?);
+    }
+    Ok(format_report(&target, total, previous, &reports))
+}
+
+fn string_list(args: &serde_json::Value, key: &str) -> Vec<String> {
+    args.get(key)
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .map(|s| s.trim_start_matches("./").trim_end_matches('/').to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Workspace member names and their directories relative to `dir`.
+async fn workspace_packages(dir: &Path) -> Result<BTreeMap<String, String>> {
+    let output = tokio::process::Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(dir)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let mut members = BTreeMap::new();
+    for package in metadata["packages"].as_array().into_iter().flatten() {
+        let (Some(name), Some(manifest)) =
+            (package["name"].as_str(), package["manifest_path"].as_str())
+        else {
+            continue;
+        };
+        let package_dir = Path::new(manifest).parent().unwrap_or(Path::new(""));
+        members.insert(name.to_string(), relative(package_dir, dir));
+    }
+    Ok(members)
+}
+
+/// The packages owning `paths`: for each, the member with the deepest
+/// directory containing it.
+fn packages_for_paths(
+    members: &BTreeMap<String, String>,
+    dir: &Path,
+    paths: &[String],
+) -> Result<Vec<String>> {
+    let mut packages = Vec::new();
+    for path in paths {
+        let path = relative(&dir.join(path), dir);
+        let owner = members
+            .iter()
+            .filter(|(_, package_dir)| in_dir(&path, package_dir))
+            .max_by_key(|(_, package_dir)| package_dir.len())
+            .map(|(name, _)| name.clone())
+            .ok_or_else(|| anyhow!("No workspace package contains {}", path))?;
+        if !packages.contains(&owner) {
+            packages.push(owner);
+        }
+    }
+    Ok(packages)
+}
+
+/// Per-file line coverage from `cargo llvm-cov --json` output, keyed by
+/// path relative to `dir`.
+pub fn parse_llvm_cov(output: &str, dir: &Path) -> Result<BTreeMap<String, LineCoverage>> {
+    let export: serde_json::Value = serde_json::from_str(output)?;
+    let data = export["data"]
+        .as_array()
+        .ok_or_else(|| anyhow!("missing coverage data"))?;
+    let mut files = BTreeMap::new();
+    for file in data.iter().filter_map(|d| d["files"].as_array()).flatten() {
+        let Some(filename) = file["filename"].as_str() else {
+            continue;
+        };
+        let lines = &file["summary"]["lines"];
+        files.insert(
+            relative(Path::new(filename), dir),
+            LineCoverage {
+                covered: lines["covered"].as_u64().unwrap_or(0),
+                total: lines["count"].as_u64().unwrap_or(0),
+            },
+        );
+    }
+    Ok(files)
+}
+
+/// `files` in path order, with their change since `baseline`.
+pub fn compare(
+    files: &BTreeMap<String, LineCoverage>,
+    baseline: &BTreeMap<String, LineCoverage>,
+) -> Vec<FileReport> {
+    files
+        .iter()
+        .map(|(path, lines)| FileReport {
+            path: path.clone(),
+            lines: *lines,
+            percent: lines.percent(),
+            delta: baseline
+                .get(path)
+                .map(|previous| lines.percent() - previous.percent()),
+        })
+        .collect()
+}
+
+fn total<'a>(files: impl IntoIterator<Item = (&'a String, &'a LineCoverage)>) -> LineCoverage {
+    files.into_iter().fold(
+        LineCoverage {
+            covered: 0,
+            total: 0,
+        },
+        |sum, (_, lines)| LineCoverage {
+            covered: sum.covered + lines.covered,
+            total: sum.total + lines.total,
+        },
+    )
+}
+
+/// The last run's total over those of `files` it covered, if any.
+fn previous_total(
+    files: &BTreeMap<String, LineCoverage>,
+    baseline: &BTreeMap<String, LineCoverage>,
+) -> Option<LineCoverage> {
+    let previous: Vec<_> = baseline
+        .iter()
+        .filter(|(path, _)| files.contains_key(*path))
+        .collect();
+    (!previous.is_empty()).then(|| total(previous))
+}
+
+fn load_baseline(path: &Path) -> BTreeMap<String, LineCoverage> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_baseline(path: &Path, baseline: &BTreeMap<String, LineCoverage>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(baseline)?)?;
+    Ok(())
+}
+
+fn format_report(
+    target: &str,
+    total: LineCoverage,
+    previous: Option<LineCoverage>,
+    reports: &[FileReport],
+) -> String {
+    let mut result = format!("✅ Code coverage for {}\n\n", target);
+    result.push_str(&format!(
+        "Lines: {}/{} ({:.1}%)",
+        total.covered,
+        total.total,
+        total.percent()
+    ));
+    if let Some(previous) = previous {
+        result.push_str(&format!(
+            ", {} since last run",
+            format_delta(total.percent() - previous.percent())
+        ));
+    }
+    result.push('\n');
+    if reports.is_empty() {
+        result.push_str("\nNo files matched.\n");
+        return result;
+    }
+
+    let changed = reports
+        .iter()
+        .filter(|r| r.delta.is_some_and(|d| d.abs() >= 0.05))
+        .count();
+    if previous.is_some() {
+        result.push_str(&format!("{} file(s) changed since last run\n", changed));
+    }
+
+    let width = reports
+        .iter()
+        .take(MAX_FILES)
+        .map(|r| r.path.len())
+        .max()
+        .unwrap_or(0);
+    result.push('\n');
+    for report in reports.iter().take(MAX_FILES) {
+        let delta = match report.delta {
+            Some(d) if d.abs() >= 0.05 => format!("  {}", format_delta(d)),
+            Some(_) => String::new(),
+            None if previous.is_some() => "  new".to_string(),
+            None => String::new(),
+        };
+        result.push_str(&format!(
+            "{:<width$}  {:>6.1}%  {}/{}{}\n",
+            report.path,
+            report.percent,
+            report.lines.covered,
+            report.lines.total,
+            delta,
+            width = width
+        ));
+    }
+    if reports.len() > MAX_FILES {
+        result.push_str(&format!(
+            "... and {} more files (narrow with paths or use format=json)\n",
+            reports.len() - MAX_FILES
+        ));
+    }
+    result
+}
+
+fn format_delta(delta: f64) -> String {
+    format!("{:+.1} pts", delta)
+}
+
+/// `path` relative to `dir` with forward slashes, or as given when outside it.
+fn relative(path: &Path, dir: &Path) -> String {
+    let path: PathBuf = path.components().collect();
+    // cargo reports canonical paths, which `dir` may not be
+    let canonical = dir.canonicalize().ok();
+    path.strip_prefix(dir)
+        .ok()
+        .or_else(|| path.strip_prefix(canonical.as_deref()?).ok())
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Whether the relative `path` is `dir` or inside it.
+fn in_dir(path: &str, dir: &str) -> bool {
+    dir.is_empty()
+        || path == dir
+        || path
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_compare_against_baseline() {
+        let output = r#"{"type":"llvm.coverage.json.export","data":[{"files":[
+            {"filename":"/ws/crates/a/src/lib.rs","summary":{"lines":{"count":10,"covered":5,"percent":50.0}}},
+            {"filename":"/ws/crates/a/src/new.rs","summary":{"lines":{"count":4,"covered":4,"percent":100.0}}},
+            {"filename":"/ws/crates/b/src/lib.rs","summary":{"lines":{"count":8,"covered":2,"percent":25.0}}}
+        ],"totals":{}}]}"#;
+        let files = parse_llvm_cov(output, Path::new("/ws")).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            files["crates/a/src/lib.rs"],
+            LineCoverage {
+                covered: 5,
+                total: 10
+            }
+        );
+
+        let scoped: BTreeMap<_, _> = files
+            .into_iter()
+            .filter(|(path, _)| in_dir(path, "crates/a"))
+            .collect();
+        let baseline = BTreeMap::from([(
+            "crates/a/src/lib.rs".to_string(),
+            LineCoverage {
+                covered: 3,
+                total: 10,
+            },
+        )]);
+        let reports = compare(&scoped, &baseline);
+        assert_eq!(reports.len(), 2);
+        assert!((reports[0].delta.unwrap() - 20.0).abs() < 1e-9);
+        assert_eq!(reports[1].delta, None);
+
+        let previous = previous_total(&scoped, &baseline).unwrap();
+        assert_eq!(previous.covered, 3);
+        assert!(!in_dir("crates/ab/src/lib.rs", "crates/a"));
+    }
+}
@@ -0,0 +1,473 @@
+//! Dead code and unused dependency detection tool: dead_code.
+//!
+//! Wraps the ecosystem's analyzer (rustc lints + cargo-udeps for Rust, knip
+//! for JavaScript/TypeScript, vulture for Python) and by default reports
+//! only findings in files changed in the git working tree, so refactors can
+//! verify they didn't leave orphans behind.
+
+use anyhow::Result;
+use std::path::Path;
+use std::process::Output;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::utils::list_changed_files;
+use crate::ToolCall;
+
+use super::cargo::parse_diagnostics;
+use super::executor::ToolContext;
+
+/// Maximum number of findings included in the tool output.
+const MAX_FINDINGS: usize = 100;
+
+/// rustc lint codes that indicate dead or unused code.
+const RUST_DEAD_CODE_LINTS: &[&str] = &[
+    "dead_code",
+    "unused_imports",
+    "unused_variables",
+    "unused_mut",
+    "unused_macros",
+    "unreachable_code",
+];
+
+/// Language ecosystem with a supported analyzer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ecosystem {
+    Rust,
+    Node,
+    Python,
+}
+
+impl Ecosystem {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "rust" => Some(Ecosystem::Rust),
+            "node" | "javascript" | "typescript" => Some(Ecosystem::Node),
+            "python" => Some(Ecosystem::Python),
+            _ => None,
+        }
+    }
+
+    /// Detect ecosystems from manifest files in the project root.
+    fn detect(base: &Path) -> Vec<Self> {
+        let mut found = Vec::new();
+        if base.join("Cargo.toml").is_file() {
+            found.push(Ecosystem::Rust);
+        }
+        if base.join("package.json").is_file() {
+            found.push(Ecosystem::Node);
+        }
+        if ["pyproject.toml", "setup.py", "requirements.txt"]
+            .iter()
+            .any(|f| base.join(f).is_file())
+        {
+            found.push(Ecosystem::Python);
+        }
+        found
+    }
+}
+
+/// A single dead code or unused dependency finding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Finding {
+    /// File the finding refers to (manifest path for unused dependencies)
+    file: String,
+    line: Option<usize>,
+    message: String,
+    /// Unused dependency findings are never filtered by scope
+    is_dependency: bool,
+}
+
+/// Execute the `dead_code` tool.
+pub async fn execute_dead_code<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing dead_code tool call");
+
+    let base = match ctx.call_dir(tool_call) {
+        Ok(base) => base,
+        Err(refusal) => return Ok(refusal),
+    };
+
+    let ecosystems = match tool_call.args.get("ecosystem").and_then(|v| v.as_str()) {
+        Some(name) => match Ecosystem::parse(name) {
+            Some(eco) => vec![eco],
+            None => {
+                return Ok(format!(
+                    "❌ Unsupported ecosystem '{}'. Use rust, node, or python.",
+                    name
+                ))
+            }
+        },
+        None => Ecosystem::detect(&base),
+    };
+    if ecosystems.is_empty() {
+        return Ok(
+            "❌ No Cargo.toml, package.json, or Python project files found to analyze".to_string(),
+        );
+    }
+
+    let scope_all = tool_call.args.get("scope").and_then(|v| v.as_str()) == Some("all");
+    let changed = if scope_all {
+        Vec::new()
+    } else {
+        list_changed_files(base.to_str()).await
+    };
+    if !scope_all && changed.is_empty() {
+        return Ok(
+            "✅ No changed files to check (use scope \"all\" for the whole project)".to_string(),
+        );
+    }
+
+    ctx.ui_writer
+        .print_context_status("🔍 Analyzing for dead code and unused dependencies...");
+
+    let mut findings = Vec::new();
+    let mut notes = Vec::new();
+    for eco in ecosystems {
+        let (mut eco_findings, mut eco_notes) = match eco {
+            Ecosystem::Rust => analyze_rust(&base).await,
+            Ecosystem::Node => analyze_node(&base).await,
+            Ecosystem::Python => analyze_python(&base, &changed).await,
+        };
+        findings.append(&mut eco_findings);
+        notes.append(&mut eco_notes);
+    }
+
+    if !scope_all {
+        findings.retain(|f| f.is_dependency || is_in_changed_files(&f.file, &base, &changed));
+    }
+
+    Ok(format_report(&findings, &notes, scope_all))
+}
+
+/// Run a command in `dir`, returning None if it could not be spawned.
+async fn run(program: &str, args: &[&str], dir: &Path) -> Option<Output> {
+    match tokio::process::Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+    {
+        Ok(output) => Some(output),
+        Err(e) => {
+            debug!("Failed to run {} {:?}: {}", program, args, e);
+            None
+        }
+    }
+}
+
+async fn analyze_rust(base: &Path) -> (Vec<Finding>, Vec<String>) {
+    let mut findings = Vec::new();
+    let mut notes = Vec::new();
+
+    match run(
+        "cargo",
+        &[
+            "check",
+            "--workspace",
+            "--all-targets",
+            "--message-format=json",
+        ],
+        base,
+    )
+    .await
+    {
+        Some(output) => findings.extend(parse_cargo_messages(&String::from_utf8_lossy(
+            &output.stdout,
+        ))),
+        None => notes.push("cargo not found; skipped Rust dead code lints".to_string()),
+    }
+
+    // Probe the same toolchain the check runs on: cargo-udeps only works on nightly
+    let udeps_installed = run("cargo", &["+nightly", "udeps", "--version"], base)
+        .await
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if !udeps_installed {
+        notes.push(
+            "cargo-udeps or the nightly toolchain not installed; skipped unused dependency check \
+             (install with `rustup toolchain install nightly` and `cargo install cargo-udeps`)"
+                .to_string(),
+        );
+        return (findings, notes);
+    }
+
+    match run(
+        "cargo",
+        &[
+            "+nightly",
+            "udeps",
+            "--workspace",
+            "--all-targets",
+            "--output",
+            "json",
+        ],
+        base,
+    )
+    .await
+    {
+        Some(output) => findings.extend(parse_udeps_json(&String::from_utf8_lossy(&output.stdout))),
+        None => notes.push("cargo udeps failed to run".to_string()),
+    }
+
+    (findings, notes)
+}
+
+async fn analyze_node(base: &Path) -> (Vec<Finding>, Vec<String>) {
+    match run("npx", &["--no-install", "knip", "--reporter", "json"], base).await {
+        Some(output) if !output.stdout.is_empty() => (
+            parse_knip_json(&String::from_utf8_lossy(&output.stdout)),
+            Vec::new(),
+        ),
+        _ => (
+            Vec::new(),
+            vec!["knip not available; install it with `npm install -D knip`".to_string()],
+        ),
+    }
+}
+
+async fn analyze_python(base: &Path, changed: &[String]) -> (Vec<Finding>, Vec<String>) {
+    // vulture is cheap to scope up front, so pass only changed Python files when we have them
+    let targets: Vec<&str> = if changed.is_empty() {
+        vec!["."]
+    } else {
+        changed
+            .iter()
+            .filter(|f| f.ends_with(".py"))
+            .map(String::as_str)
+            .collect()
+    };
+    if targets.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+
+    match run("vulture", &targets, base).await {
+        Some(output) => (
+            parse_vulture_output(&String::from_utf8_lossy(&output.stdout)),
+            Vec::new(),
+        ),
+        None => (
+            Vec::new(),
+            vec!["vulture not installed; install it with `pip install vulture`".to_string()],
+        ),
+    }
+}
+
+/// Parse `cargo check --message-format=json` output into dead code findings.
+fn parse_cargo_messages(stdout: &str) -> Vec<Finding> {
+    parse_diagnostics(stdout)
+        .into_iter()
+        .filter_map(|diagnostic| {
+            let code = diagnostic.code?;
+            if !RUST_DEAD_CODE_LINTS.contains(&code.as_str()) {
+                return None;
+            }
+            Some(Finding {
+                file: diagnostic.file?,
+                line: diagnostic.line,
+                message: format!("{} ({})", diagnostic.message, code),
+                is_dependency: false,
+            })
+        })
+        .collect()
+}
+
+/// Parse `cargo udeps --output json` into unused dependency findings.
+fn parse_udeps_json(stdout: &str) -> Vec<Finding> {
+    let Ok(report) = serde_json::from_str::<serde_json::Value>(stdout) else {
+        return Vec::new();
+    };
+    let Some(packages) = report["unused_deps"].as_object() else {
+        return Vec::new();
+    };
+
+    let mut findings = Vec::new();
+    for info in packages.values() {
+        let manifest = info["manifest_path"].as_str().unwrap_or("Cargo.toml");
+        for kind in ["normal", "development", "build"] {
+            for dep in info[kind].as_array().into_iter().flatten() {
+                if let Some(name) = dep.as_str() {
+                    findings.push(Finding {
+                        file: manifest.to_string(),
+                        line: None,
+                        message: format!("unused {} dependency `{}`", kind, name),
+                        is_dependency: true,
+                    });
+                }
+            }
+        }
+    }
+    findings
+}
+
+/// Parse `knip --reporter json` output.
+///
+/// Each issue entry has a `file` plus one array per issue type; entries in
+/// those arrays carry a `name` and optionally a `line`.
+fn parse_knip_json(stdout: &str) -> Vec<Finding> {
+    let Ok(report) = serde_json::from_str::<serde_json::Value>(stdout) else {
+        return Vec::new();
+    };
+
+    let mut findings: Vec<Finding> = report["files"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|f| f.as_str())
+        .map(|file| Finding {
+            file: file.to_string(),
+            line: None,
+            message: "unused file".to_string(),
+            is_dependency: false,
+        })
+        .collect();
+
+    for issue in report["issues"].as_array().into_iter().flatten() {
+        let file = issue["file"].as_str().unwrap_or("package.json");
+        let Some(fields) = issue.as_object() else {
+            continue;
+        };
+        for (kind, items) in fields {
+            for item in items.as_array().into_iter().flatten() {
+                let Some(name) = item["name"].as_str() else {
+                    continue;
+                };
+                let is_dependency = kind.to_lowercase().contains("dependencies");
+                findings.push(Finding {
+                    file: file.to_string(),
+                    line: item["line"].as_u64().map(|l| l as usize),
+                    message: format!("unused {} `{}`", kind, name),
+                    is_dependency,
+                });
+            }
+        }
+    }
+    findings
+}
+
+/// Parse vulture's `path:line: message` output.
+fn parse_vulture_output(stdout: &str) -> Vec<Finding> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let file = parts.next()?.trim();
+            let line_no = parts.next()?.trim().parse::<usize>().ok()?;
+            let message = parts.next()?.trim();
+            Some(Finding {
+                file: file.to_string(),
+                line: Some(line_no),
+                message: message.to_string(),
+                is_dependency: false,
+            })
+        })
+        .collect()
+}
+
+/// Whether `file` (absolute or relative to `base`) is one of the changed files.
+fn is_in_changed_files(file: &str, base: &Path, changed: &[String]) -> bool {
+    let path = Path::new(file);
+    let relative = path.strip_prefix(base).unwrap_or(path);
+    let relative = relative.strip_prefix("./").unwrap_or(relative);
+    changed.iter().any(|c| Path::new(c) == relative)
+}
+
+fn format_report(findings: &[Finding], notes: &[String], scope_all: bool) -> String {
+    let scope = if scope_all {
+        "project"
+    } else {
+        "changed files"
+    };
+    let mut output = if findings.is_empty() {
+        format!(
+            "✅ No dead code or unused dependencies found in {}\n",
+            scope
+        )
+    } else {
+        format!(
+            "🧹 {} dead code / unused dependency finding(s) in {}\n\n",
+            findings.len(),
+            scope
+        )
+    };
+
+    for finding in findings.iter().take(MAX_FINDINGS) {
+        match finding.line {
+            Some(line) => {
+                output.push_str(&format!("{}:{}: {}\n", finding.file, line, finding.message))
+            }
+            None => output.push_str(&format!("{}: {}\n", finding.file, finding.message)),
+        }
+    }
+    if findings.len() > MAX_FINDINGS {
+        output.push_str(&format!("... and {} more\n", findings.len() - MAX_FINDINGS));
+    }
+
+    for note in notes {
+        output.push_str(&format!("\nNote: {}", note));
+    }
+
+    output.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_messages() {
+        let stdout = r#"{"reason":"compiler-artifact","target":{}}
+{"reason":"compiler-message","message":{"message":"function `old` is never used","code":{"code":"dead_code"},"spans":[{"file_name":"src/lib.rs","line_start":12,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"message":"function `old` is never used","code":{"code":"dead_code"},"spans":[{"file_name":"src/lib.rs","line_start":12,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"message":"mismatched types","code":{"code":"E0308"},"spans":[{"file_name":"src/lib.rs","line_start":3,"is_primary":true}]}}"#;
+
+        let findings = parse_cargo_messages(stdout);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].file, "src/lib.rs");
+        assert_eq!(findings[0].line, Some(12));
+        assert!(findings[0].message.contains("dead_code"));
+    }
+
+    #[test]
+    fn test_parse_udeps_json() {
+        let stdout = r#"{"success":false,"unused_deps":{"foo 0.1.0":{"manifest_path":"/p/Cargo.toml","normal":["regex"],"development":["tempfile"],"build":[]}}}"#;
+        let findings = parse_udeps_json(stdout);
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(|f| f.is_dependency));
+        assert_eq!(findings[0].message, "unused normal dependency `regex`");
+    }
+
+    #[test]
+    fn test_parse_knip_json() {
+        let stdout = r#"{"files":["src/unused.ts"],"issues":[{"file":"package.json","dependencies":[{"name":"lodash"}],"exports":[]},{"file":"src/a.ts","exports":[{"name":"helper","line":4}]}]}"#;
+        let findings = parse_knip_json(stdout);
+        assert_eq!(findings.len(), 3);
+        assert!(findings[1].is_dependency);
+        assert_eq!(findings[2].file, "src/a.ts");
+        assert_eq!(findings[2].line, Some(4));
+    }
+
+    #[test]
+    fn test_parse_vulture_output() {
+        let stdout = "app/util.py:10: unused function 'helper' (60% confidence)\nnot a finding\n";
+        let findings = parse_vulture_output(stdout);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line, Some(10));
+        assert_eq!(
+            findings[0].message,
+            "unused function 'helper' (60% confidence)"
+        );
+    }
+
+    #[test]
+    fn test_is_in_changed_files() {
+        let base = Path::new("/repo");
+        let changed = vec!["src/lib.rs".to_string()];
+        assert!(is_in_changed_files("src/lib.rs", base, &changed));
+        assert!(is_in_changed_files("/repo/src/lib.rs", base, &changed));
+        assert!(is_in_changed_files("./src/lib.rs", base, &changed));
+        assert!(!is_in_changed_files("src/main.rs", base, &changed));
+    }
+}
@@ -0,0 +1,609 @@
+//! Docker tool: docker.
+//!
+//! Builds images and runs containers for "does it build in the CI image"
+//! checks without raw docker commands: `build`, `run`, `logs`, `stop` and
+//! `ps`. Bind mounts are limited to the workspace, which is mounted at
+//! `/workspace` by default. With `docker.allowed_images` set, `run` only
+//! starts matching images (or ones this tool built) and `build` only accepts
+//! Dockerfiles whose `FROM` images match. Containers are labelled so `logs`
+//! and `stop` can't touch containers the tool didn't start. Image references
+//! and container names are checked against docker's grammar first, so none
+//! can pass for a flag.
+
+use anyhow::Result;
+use g3_config::DockerConfig;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::paths::generate_short_id;
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::{bool_arg, search_path};
+use super::k8s::tail;
+
+const OPERATIONS: &[&str] = &["build", "run", "logs", "stop", "ps"];
+
+/// Label on images built by the tool.
+const BUILT_LABEL: &str = "g3.built";
+
+/// Label on containers started by the tool.
+const MANAGED_LABEL: &str = "g3.managed";
+
+/// Where the workspace is mounted in containers.
+const WORKSPACE_MOUNT: &str = "/workspace";
+
+const DEFAULT_LOG_TAIL: u64 = 200;
+
+/// Longer build, run and log output is cut to its last lines.
+const MAX_OUTPUT_LINES: usize = 300;
+
+/// Time allowed for quick docker commands (inspect, logs, stop, ps).
+const QUICK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Execute the `docker` tool.
+pub async fn execute_docker<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing docker tool call");
+
+    let args = &tool_call.args;
+    let config = &ctx.config.docker;
+    if !config.enabled {
+        return Ok(
+            "❌ The docker tool is not enabled; set docker.enabled = true in the config"
+                .to_string(),
+        );
+    }
+    let operation = args
+        .get("operation")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    if !OPERATIONS.contains(&operation) {
+        return Ok(format!(
+            "❌ Unknown operation '{}' (expected one of {})",
+            operation,
+            OPERATIONS.join(", ")
+        ));
+    }
+    let workspace = search_path(ctx, None);
+    let workspace = workspace.canonicalize().unwrap_or(workspace);
+    let name = args.get("name").and_then(|v| v.as_str());
+    if let Some(name) = name.filter(|name| !is_container_name(name)) {
+        return Ok(format!("❌ '{}' is not a valid container name", name));
+    }
+
+    let result = match operation {
+        "build" => build(args, ctx, config, &workspace).await,
+        "run" => run_container(args, ctx, config, &workspace).await,
+        "ps" => {
+            let format = "{{.Names}}\t{{.Image}}\t{{.Status}}";
+            let filter = format!("label={}", MANAGED_LABEL);
+            docker(
+                config,
+                &["ps", "--all", "--filter", &filter, "--format", format],
+            )
+            .await
+            .map(|out| {
+                if out.trim().is_empty() {
+                    "No containers started by this tool".to_string()
+                } else {
+                    format!("NAME\tIMAGE\tSTATUS\n{}", out.trim_end())
+                }
+            })
+        }
+        _ => {
+            let Some(name) = name else {
+                return Ok(format!("❌ {} needs the container name", operation));
+            };
+            match ensure_managed(config, name).await {
+                Err(e) => Err(e),
+                Ok(()) if operation == "logs" => {
+                    let tail_lines = args
+                        .get("tail")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(DEFAULT_LOG_TAIL)
+                        .to_string();
+                    docker_output(
+                        config,
+                        &["logs", "--tail", &tail_lines, "--", name],
+                        QUICK_TIMEOUT,
+                    )
+                    .await
+                    .map(|output| match combined(&output) {
+                        out if out.trim().is_empty() => "(no log output)".to_string(),
+                        out => tail(&out, MAX_OUTPUT_LINES),
+                    })
+                }
+                Ok(()) => docker(config, &["rm", "--force", "--", name])
+                    .await
+                    .map(|_| format!("✅ Stopped and removed container {}", name)),
+            }
+        }
+    };
+    Ok(result.unwrap_or_else(|e| format!("❌ {}", e)))
+}
+
+/// `build`: check the Dockerfile's base images, then build and label the image.
+async fn build<W: UiWriter>(
+    args: &serde_json::Value,
+    ctx: &ToolContext<'_, W>,
+    config: &DockerConfig,
+    workspace: &Path,
+) -> Result<String, String> {
+    let context = within_workspace(
+        workspace,
+        &search_path(ctx, args.get("context").and_then(|v| v.as_str())),
+    )?;
+    let dockerfile = match args.get("dockerfile").and_then(|v| v.as_str()) {
+        Some(path) => within_workspace(workspace, &search_path(ctx, Some(path)))?,
+        None => context.join("Dockerfile"),
+    };
+    let contents = std::fs::read_to_string(&dockerfile)
+        .map_err(|e| format!("Cannot read {}: {}", dockerfile.display(), e))?;
+    if !config.allowed_images.is_empty() {
+        for image in base_images(&contents)? {
+            if !image_allowed(&config.allowed_images, &image) {
+                return Err(format!(
+                    "Base image '{}' is not allowed (allowed: {})",
+                    image,
+                    config.allowed_images.join(", ")
+                ));
+            }
+        }
+    }
+
+    let tag = match args.get("tag").and_then(|v| v.as_str()) {
+        Some(tag) if !is_image_reference(tag) => {
+            return Err(format!("'{}' is not a valid image tag", tag))
+        }
+        Some(tag) => tag.to_string(),
+        None => format!(
+            "g3-{}:latest",
+            context
+                .file_name()
+                .map(|n| n.to_string_lossy().to_lowercase())
+                .unwrap_or_else(|| "image".to_string())
+        ),
+    };
+    let mut build_args = vec![
+        "build".to_string(),
+        "--progress=plain".to_string(),
+        "--label".to_string(),
+        format!("{}=true", BUILT_LABEL),
+        "--tag".to_string(),
+        tag.clone(),
+        "--file".to_string(),
+        dockerfile.display().to_string(),
+    ];
+    if let Some(target) = args.get("target").and_then(|v| v.as_str()) {
+        build_args.extend(["--target".to_string(), target.to_string()]);
+    }
+    for (key, value) in args
+        .get("build_args")
+        .and_then(|v| v.as_object())
+        .into_iter()
+        .flatten()
+    {
+        let value = value
+            .as_str()
+            .map_or_else(|| value.to_string(), String::from);
+        build_args.extend(["--build-arg".to_string(), format!("{}={}", key, value)]);
+    }
+    build_args.push(context.display().to_string());
+
+    ctx.ui_writer
+        .print_context_status(&format!("🐳 Building {}...", tag));
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let arg_refs: Vec<&str> = build_args.iter().map(String::as_str).collect();
+    let output = docker_output(config, &arg_refs, timeout).await?;
+    let log = tail(&combined(&output), MAX_OUTPUT_LINES);
+    if output.status.success() {
+        Ok(format!("✅ Built {}\n\n{}", tag, log))
+    } else {
+        Err(format!("Build of {} failed\n\n{}", tag, log))
+    }
+}
+
+/// `run`: start a container with the workspace mounted, in the foreground
+/// (removed afterwards) or detached.
+async fn run_container<W: UiWriter>(
+    args: &serde_json::Value,
+    ctx: &ToolContext<'_, W>,
+    config: &DockerConfig,
+    workspace: &Path,
+) -> Result<String, String> {
+    let Some(image) = args.get("image").and_then(|v| v.as_str()) else {
+        return Err("run needs an image".to_string());
+    };
+    if !is_image_reference(image) {
+        return Err(format!("'{}' is not a valid image reference", image));
+    }
+    if !image_allowed(&config.allowed_images, image) && !built_by_tool(config, image).await {
+        return Err(format!(
+            "Image '{}' is not allowed (allowed: {}, or images built with this tool)",
+            image,
+            config.allowed_images.join(", ")
+        ));
+    }
+    let detach = bool_arg(args, "detach");
+    let name = args
+        .get("name")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .unwrap_or_else(|| format!("g3-{}", generate_short_id()));
+
+    let mut run_args = vec![
+        "run".to_string(),
+        "--name".to_string(),
+        name.clone(),
+        "--label".to_string(),
+        format!("{}=true", MANAGED_LABEL),
+    ];
+    run_args.push(if detach { "--detach" } else { "--rm" }.to_string());
+    if let Some(network) = &config.network {
+        run_args.push(format!("--network={}", network));
+    }
+    if args.get("mount_workspace").and_then(|v| v.as_bool()) != Some(false) {
+        run_args.extend([
+            "--volume".to_string(),
+            format!("{}:{}", workspace.display(), WORKSPACE_MOUNT),
+            "--workdir".to_string(),
+            WORKSPACE_MOUNT.to_string(),
+        ]);
+    }
+    for mount in args
+        .get("mounts")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let source = mount.get("source").and_then(|v| v.as_str());
+        let target = mount.get("target").and_then(|v| v.as_str());
+        let (Some(source), Some(target)) = (source, target) else {
+            return Err("Each mount needs a source and a target".to_string());
+        };
+        if !target.starts_with('/') {
+            return Err(format!(
+                "Mount target '{}' must be an absolute path",
+                target
+            ));
+        }
+        let source = within_workspace(workspace, &search_path(ctx, Some(source)))?;
+        let mode = if bool_arg(mount, "read_only") {
+            ":ro"
+        } else {
+            ""
+        };
+        run_args.extend([
+            "--volume".to_string(),
+            format!("{}:{}{}", source.display(), target, mode),
+        ]);
+    }
+    if let Some(workdir) = args.get("workdir").and_then(|v| v.as_str()) {
+        run_args.extend(["--workdir".to_string(), workdir.to_string()]);
+    }
+    for (key, value) in args
+        .get("env")
+        .and_then(|v| v.as_object())
+        .into_iter()
+        .flatten()
+    {
+        let value = value
+            .as_str()
+            .map_or_else(|| value.to_string(), String::from);
+        run_args.extend(["--env".to_string(), format!("{}={}", key, value)]);
+    }
+    run_args.push(image.to_string());
+    match args.get("command") {
+        Some(serde_json::Value::String(command)) => {
+            run_args.extend(["sh".to_string(), "-c".to_string(), command.clone()]);
+        }
+        Some(serde_json::Value::Array(parts)) => {
+            run_args.extend(parts.iter().filter_map(|p| p.as_str()).map(String::from));
+        }
+        _ => {}
+    }
+
+    let timeout = Duration::from_secs(
+        args.get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .map_or(config.timeout_secs, |t| t.min(config.timeout_secs)),
+    );
+    ctx.ui_writer
+        .print_context_status(&format!("🐳 Running {} as {}...", image, name));
+    let arg_refs: Vec<&str> = run_args.iter().map(String::as_str).collect();
+    let output = match docker_output(config, &arg_refs, timeout).await {
+        Ok(output) => output,
+        Err(e) => {
+            // Killing the CLI leaves the container running
+            let _ = docker(config, &["rm", "--force", "--", &name]).await;
+            return Err(e);
+        }
+    };
+    if detach {
+        return if output.status.success() {
+            Ok(format!(
+                "✅ Started container {} from {}; use logs and stop with name \"{}\"",
+                name, image, name
+            ))
+        } else {
+            Err(format!("Could not start {}: {}", image, stderr_of(&output)))
+        };
+    }
+    let log = tail(&combined(&output), MAX_OUTPUT_LINES);
+    match output.status.code() {
+        Some(0) => Ok(format!("✅ {} exited with code 0\n\n{}", image, log)),
+        Some(code) => Err(format!("{} exited with code {}\n\n{}", image, code, log)),
+        None => Err(format!("{} was killed\n\n{}", image, log)),
+    }
+}
+
+/// `path` canonicalized, if it is inside the workspace.
+fn within_workspace(workspace: &Path, path: &Path) -> Result<PathBuf, String> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if canonical.starts_with(workspace) {
+        Ok(canonical)
+    } else {
+        Err(format!(
+            "{} is outside the workspace ({})",
+            path.display(),
+            workspace.display()
+        ))
+    }
+}
+
+/// The external images a Dockerfile's `FROM` lines use, leaving out
+/// references to earlier stages and `scratch`.
+pub fn base_images(dockerfile: &str) -> Result<Vec<String>, String> {
+    let mut stages: Vec<String> = Vec::new();
+    let mut images = Vec::new();
+    for line in dockerfile.lines() {
+        let mut words = line.split_whitespace();
+        if !words.next().is_some_and(|w| w.eq_ignore_ascii_case("FROM")) {
+            continue;
+        }
+        let mut words = words.skip_while(|w| w.starts_with("--"));
+        let Some(image) = words.next() else {
+            continue;
+        };
+        if image.contains('$') {
+            return Err(format!(
+                "Can't check the base image '{}' against docker.allowed_images because it uses a build argument",
+                image
+            ));
+        }
+        if image != "scratch" && !stages.iter().any(|s| s.eq_ignore_ascii_case(image)) {
+            images.push(image.to_string());
+        }
+        if words.next().is_some_and(|w| w.eq_ignore_ascii_case("AS")) {
+            stages.extend(words.next().map(String::from));
+        }
+    }
+    Ok(images)
+}
+
+/// Whether `image` is a well-formed reference (`[registry/]path[:tag][@digest]`),
+/// so it can't be taken for one of docker's flags.
+pub fn is_image_reference(image: &str) -> bool {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        let component = r"[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*";
+        let host = r"[a-zA-Z0-9](?:[a-zA-Z0-9-]*[a-zA-Z0-9])?";
+        Regex::new(&format!(
+            r"^(?:(?:{host}(?:\.{host})*|\[[0-9a-fA-F:]+\])(?::[0-9]+)?/)?{component}(?:/{component})*(?::[A-Za-z0-9_][A-Za-z0-9_.-]{{0,127}})?(?:@[A-Za-z][A-Za-z0-9]*(?:[-_+.][A-Za-z][A-Za-z0-9]*)*:[0-9a-fA-F]{{32,}})?$",
+        ))
+        .unwrap()
+    });
+    image.len() <= 255 && re.is_match(image)
+}
+
+/// Whether `name` is a valid container name (which can't start with `-`).
+fn is_container_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Whether `image` matches one of the `allowed` patterns (or there are none).
+pub fn image_allowed(allowed: &[String], image: &str) -> bool {
+    allowed.is_empty()
+        || allowed.iter().any(|pattern| {
+            glob_match(pattern, image)
+                || (!has_tag(pattern) && glob_match(pattern, repository(image)))
+        })
+}
+
+/// Whether an image reference names a tag or digest.
+fn has_tag(image: &str) -> bool {
+    image.contains('@')
+        || image
+            .rsplit('/')
+            .next()
+            .is_some_and(|last| last.contains(':'))
+}
+
+/// An image reference without its tag or digest.
+fn repository(image: &str) -> &str {
+    let image = image.split('@').next().unwrap_or(image);
+    match image.rfind(':') {
+        Some(colon) if !image[colon..].contains('/') => &image[..colon],
+        _ => image,
+    }
+}
+
+/// Match `text` against `pattern`, where `*` matches any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+async fn built_by_tool(config: &DockerConfig, image: &str) -> bool {
+    let format = format!("{{{{ index .Config.Labels \"{}\" }}}}", BUILT_LABEL);
+    docker(
+        config,
+        &["image", "inspect", "--format", &format, "--", image],
+    )
+    .await
+    .is_ok_and(|out| out.trim() == "true")
+}
+
+/// Fails unless the container was started by this tool.
+async fn ensure_managed(config: &DockerConfig, name: &str) -> Result<(), String> {
+    let format = format!("{{{{ index .Config.Labels \"{}\" }}}}", MANAGED_LABEL);
+    match docker(
+        config,
+        &["container", "inspect", "--format", &format, "--", name],
+    )
+    .await
+    {
+        Ok(out) if out.trim() == "true" => Ok(()),
+        Ok(_) => Err(format!(
+            "Container {} was not started by this tool, so it won't be touched",
+            name
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+/// Run a quick docker command and return its stdout, or stderr as the error.
+async fn docker(config: &DockerConfig, args: &[&str]) -> Result<String, String> {
+    let output = docker_output(config, args, QUICK_TIMEOUT).await?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!("docker {} failed: {}", args[0], stderr_of(&output)))
+    }
+}
+
+async fn docker_output(
+    config: &DockerConfig,
+    args: &[&str],
+    timeout: Duration,
+) -> Result<Output, String> {
+    debug!("Running {} {:?}", config.docker, args);
+    let child = tokio::process::Command::new(&config.docker)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", config.docker, e))?;
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output.map_err(|e| e.to_string()),
+        Err(_) => Err(format!(
+            "docker {} timed out after {}s",
+            args[0],
+            timeout.as_secs()
+        )),
+    }
+}
+
+fn stderr_of(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).trim().to_string()
+}
+
+/// stdout followed by stderr.
+fn combined(output: &Output) -> String {
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_allowlist() {
+        let allowed = vec![
+            "rust".to_string(),
+            "node:20*".to_string(),
+            "ghcr.io/acme/*".to_string(),
+        ];
+        assert!(image_allowed(&allowed, "rust"));
+        assert!(image_allowed(&allowed, "rust:1.80-slim"));
+        assert!(image_allowed(&allowed, "node:20-alpine"));
+        assert!(!image_allowed(&allowed, "node:18"));
+        assert!(!image_allowed(&allowed, "node"));
+        assert!(image_allowed(&allowed, "ghcr.io/acme/ci:2024"));
+        assert!(!image_allowed(&allowed, "ghcr.io/other/ci"));
+        assert!(!image_allowed(&allowed, "rustacean/evil"));
+        assert!(image_allowed(&[], "anything:latest"));
+        assert_eq!(repository("localhost:5000/app:1"), "localhost:5000/app");
+        assert_eq!(repository("localhost:5000/app"), "localhost:5000/app");
+    }
+
+    #[test]
+    fn test_image_references() {
+        for image in [
+            "rust",
+            "rust:1.80-slim",
+            "library/rust",
+            "ghcr.io/acme/ci:2024",
+            "localhost:5000/app",
+            "[::1]:5000/app:v1",
+            "g3-my_project:latest",
+            "alpine@sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+        ] {
+            assert!(is_image_reference(image), "{}", image);
+        }
+        for image in [
+            "--privileged",
+            "-v/:/host",
+            "",
+            "Rust",
+            "rust:",
+            "rust:-x",
+            "rust latest",
+            "ghcr.io/acme/",
+            "alpine@sha256:abc",
+        ] {
+            assert!(!is_image_reference(image), "{}", image);
+        }
+        assert!(is_container_name("g3-1a2b3c4d"));
+        assert!(!is_container_name("--help"));
+        assert!(!is_container_name("-x"));
+        assert!(!is_container_name("web app"));
+    }
+
+    #[test]
+    fn test_base_images_skip_stages() {
+        let dockerfile = "\
+FROM --platform=linux/amd64 rust:1.80 AS builder
+RUN cargo build --release
+FROM builder as test
+FROM gcr.io/distroless/cc
+COPY --from=builder /app /app
+FROM scratch
+";
+        assert_eq!(
+            base_images(dockerfile).unwrap(),
+            vec!["rust:1.80", "gcr.io/distroless/cc"]
+        );
+        assert!(base_images("FROM ${BASE}\n").is_err());
+    }
+}
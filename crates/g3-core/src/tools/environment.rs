@@ -0,0 +1,379 @@
+//! Environment inspection tool: inspect_env.
+//!
+//! Reports listening ports with the processes that own them, processes
+//! matching a pattern, and selected environment variables, the same way on
+//! every OS (sockets via netstat2, processes via sysinfo), instead of
+//! chaining lsof/ps/netstat calls whose flags and output differ per
+//! platform. Values of variables that look like secrets are never shown.
+
+use anyhow::Result;
+use netstat2::{get_sockets_info, AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, TcpState};
+use serde::Serialize;
+use std::collections::HashMap;
+use sysinfo::{Pid, System};
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::utils::truncate_chars;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Most listeners and processes listed.
+const MAX_ENTRIES: usize = 100;
+
+/// Longer command lines are cut.
+const MAX_COMMAND_CHARS: usize = 200;
+
+/// Variable names containing any of these are masked.
+const SECRET_MARKERS: &[&str] = &[
+    "KEY",
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "AUTH",
+    "PRIVATE",
+];
+
+/// A socket accepting connections (TCP) or datagrams (UDP).
+#[derive(Debug, Clone, Serialize)]
+pub struct Listener {
+    pub protocol: &'static str,
+    pub address: String,
+    pub port: u16,
+    pub pids: Vec<u32>,
+    /// Name of the first owning process, when visible to us
+    pub process: Option<String>,
+}
+
+/// A running process.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub name: String,
+    pub command: String,
+    pub memory_mb: u64,
+    pub running_secs: u64,
+    pub cwd: Option<String>,
+}
+
+/// An environment variable; `value` is None when unset.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnvVar {
+    pub name: String,
+    pub value: Option<String>,
+    /// The value looks like a secret and was replaced by its length
+    pub masked: bool,
+}
+
+/// What `inspect_env` found; sections that weren't asked for are None.
+#[derive(Debug, Default, Serialize)]
+pub struct EnvironmentReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listeners: Option<Vec<Listener>>,
+    /// Requested ports that nothing listens on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free_ports: Option<Vec<u16>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub processes: Option<Vec<ProcessEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<Vec<EnvVar>>,
+}
+
+/// Execute the `inspect_env` tool.
+pub async fn execute_inspect_env<W: UiWriter>(
+    tool_call: &ToolCall,
+    _ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing inspect_env tool call");
+
+    let args = &tool_call.args;
+    let ports: Option<Vec<u16>> = args.get("ports").and_then(|v| v.as_array()).map(|ports| {
+        ports
+            .iter()
+            .filter_map(|p| p.as_u64())
+            .filter_map(|p| u16::try_from(p).ok())
+            .collect()
+    });
+    let process = args
+        .get("process")
+        .and_then(|v| v.as_str())
+        .filter(|p| !p.is_empty());
+    let env: Option<Vec<String>> = args.get("env").and_then(|v| v.as_array()).map(|names| {
+        names
+            .iter()
+            .filter_map(|n| n.as_str())
+            .map(str::to_string)
+            .collect()
+    });
+    // Listening ports are the default when nothing specific is asked
+    let listening = args
+        .get("listening")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(ports.is_none() && process.is_none() && env.is_none());
+
+    let mut report = EnvironmentReport::default();
+    if listening || ports.is_some() || process.is_some() {
+        let mut system = System::new();
+        system.refresh_processes();
+
+        if listening || ports.is_some() {
+            let mut listeners = match listeners(&system) {
+                Ok(listeners) => listeners,
+                Err(e) => return Ok(format!("❌ Failed to list sockets: {}", e)),
+            };
+            if let Some(ports) = &ports {
+                report.free_ports = Some(
+                    ports
+                        .iter()
+                        .filter(|p| !listeners.iter().any(|l| l.port == **p))
+                        .copied()
+                        .collect(),
+                );
+                if !listening {
+                    listeners.retain(|l| ports.contains(&l.port));
+                }
+            }
+            report.listeners = Some(listeners);
+        }
+        if let Some(pattern) = process {
+            report.processes = Some(matching_processes(&system, pattern));
+        }
+    }
+    if let Some(names) = &env {
+        report.env = Some(match_env(names, std::env::vars()));
+    }
+
+    if args.get("format").and_then(|v| v.as_str()) == Some("json") {
+        return Ok(serde_json::to_string_pretty(&report)?);
+    }
+    Ok(format_report(&report, process))
+}
+
+/// Listening TCP sockets and bound UDP sockets, by port.
+fn listeners(system: &System) -> Result<Vec<Listener>> {
+    let sockets = get_sockets_info(
+        AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6,
+        ProtocolFlags::TCP | ProtocolFlags::UDP,
+    )?;
+    let mut listeners: Vec<Listener> = sockets
+        .into_iter()
+        .filter_map(|socket| {
+            let (protocol, address, port) = match socket.protocol_socket_info {
+                ProtocolSocketInfo::Tcp(tcp) if tcp.state == TcpState::Listen => {
+                    ("tcp", tcp.local_addr, tcp.local_port)
+                }
+                ProtocolSocketInfo::Udp(udp) => ("udp", udp.local_addr, udp.local_port),
+                ProtocolSocketInfo::Tcp(_) => return None,
+            };
+            let process = socket.associated_pids.iter().find_map(|pid| {
+                system
+                    .process(Pid::from_u32(*pid))
+                    .map(|p| p.name().to_string())
+            });
+            Some(Listener {
+                protocol,
+                address: address.to_string(),
+                port,
+                pids: socket.associated_pids,
+                process,
+            })
+        })
+        .collect();
+    listeners.sort_by(|a, b| (a.port, a.protocol).cmp(&(b.port, b.protocol)));
+    listeners.dedup_by(|a, b| a.port == b.port && a.protocol == b.protocol && a.pids == b.pids);
+    Ok(listeners)
+}
+
+/// Processes whose name or command line contains `pattern`, ignoring case.
+fn matching_processes(system: &System, pattern: &str) -> Vec<ProcessEntry> {
+    let pattern = pattern.to_lowercase();
+    let own_pid = std::process::id();
+    let mut processes: Vec<ProcessEntry> = system
+        .processes()
+        .values()
+        .filter(|p| p.pid().as_u32() != own_pid)
+        .filter_map(|p| {
+            let command = p.cmd().join(" ");
+            let matches = p.name().to_lowercase().contains(&pattern)
+                || command.to_lowercase().contains(&pattern);
+            matches.then(|| ProcessEntry {
+                pid: p.pid().as_u32(),
+                parent: p.parent().map(|pid| pid.as_u32()),
+                name: p.name().to_string(),
+                command: truncate_chars(&command, MAX_COMMAND_CHARS, ""),
+                memory_mb: p.memory() / (1024 * 1024),
+                running_secs: p.run_time(),
+                cwd: p.cwd().map(|cwd| cwd.display().to_string()),
+            })
+        })
+        .collect();
+    processes.sort_by_key(|p| p.pid);
+    processes
+}
+
+/// Look up `names` in `vars`. A name ending in `*` matches every variable
+/// with that prefix; secret-looking values are masked.
+fn match_env(names: &[String], vars: impl Iterator<Item = (String, String)>) -> Vec<EnvVar> {
+    let vars: HashMap<String, String> = vars.collect();
+    let mut found = Vec::new();
+    for name in names {
+        if let Some(prefix) = name.strip_suffix('*') {
+            let mut matching: Vec<&String> =
+                vars.keys().filter(|k| k.starts_with(prefix)).collect();
+            matching.sort();
+            found.extend(matching.into_iter().map(|k| env_var(k, vars.get(k))));
+        } else {
+            found.push(env_var(name, vars.get(name)));
+        }
+    }
+    found
+}
+
+fn env_var(name: &str, value: Option<&String>) -> EnvVar {
+    let upper = name.to_uppercase();
+    let masked = value.is_some() && SECRET_MARKERS.iter().any(|m| upper.contains(m));
+    EnvVar {
+        name: name.to_string(),
+        value: value.map(|v| {
+            if masked {
+                format!("<hidden, {} chars>", v.chars().count())
+            } else {
+                v.clone()
+            }
+        }),
+        masked,
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h{}m", s / 3600, s % 3600 / 60),
+        s => format!("{}d{}h", s / 86400, s % 86400 / 3600),
+    }
+}
+
+fn format_report(report: &EnvironmentReport, process: Option<&str>) -> String {
+    let mut sections = Vec::new();
+
+    if let Some(listeners) = &report.listeners {
+        let mut lines = vec![format!("🔌 Listening ports ({}):", listeners.len())];
+        if listeners.is_empty() {
+            lines.push("(none)".to_string());
+        }
+        for listener in listeners.iter().take(MAX_ENTRIES) {
+            let owner = match (&listener.process, listener.pids.as_slice()) {
+                (_, []) => "owner not visible (another user's process?)".to_string(),
+                (Some(name), pids) => format!("{} (pid {})", name, join(pids)),
+                (None, pids) => format!("pid {}", join(pids)),
+            };
+            lines.push(format!(
+                "{} {}:{} → {}",
+                listener.protocol, listener.address, listener.port, owner
+            ));
+        }
+        if listeners.len() > MAX_ENTRIES {
+            lines.push(format!("... and {} more", listeners.len() - MAX_ENTRIES));
+        }
+        if let Some(free) = &report.free_ports {
+            for port in free {
+                lines.push(format!("port {} is free", port));
+            }
+        }
+        sections.push(lines.join("\n"));
+    }
+
+    if let Some(processes) = &report.processes {
+        let mut lines = vec![format!(
+            "⚙️ Processes matching \"{}\" ({}):",
+            process.unwrap_or_default(),
+            processes.len()
+        )];
+        if processes.is_empty() {
+            lines.push("(none)".to_string());
+        }
+        for p in processes.iter().take(MAX_ENTRIES) {
+            let mut line = format!(
+                "{} {} — {} MB, up {}",
+                p.pid,
+                p.name,
+                p.memory_mb,
+                format_duration(p.running_secs)
+            );
+            if let Some(parent) = p.parent {
+                line.push_str(&format!(", parent {}", parent));
+            }
+            if let Some(cwd) = &p.cwd {
+                line.push_str(&format!(", cwd {}", cwd));
+            }
+            if !p.command.is_empty() {
+                line.push_str(&format!("\n    {}", p.command));
+            }
+            lines.push(line);
+        }
+        if processes.len() > MAX_ENTRIES {
+            lines.push(format!("... and {} more", processes.len() - MAX_ENTRIES));
+        }
+        sections.push(lines.join("\n"));
+    }
+
+    if let Some(env) = &report.env {
+        let mut lines = vec!["🌱 Environment:".to_string()];
+        for var in env {
+            lines.push(format!(
+                "{}={}",
+                var.name,
+                var.value.as_deref().unwrap_or("(unset)")
+            ));
+        }
+        sections.push(lines.join("\n"));
+    }
+
+    sections.join("\n\n")
+}
+
+fn join(pids: &[u32]) -> String {
+    pids.iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_env() {
+        let vars = [
+            ("NODE_ENV", "development"),
+            ("NODE_PATH", "/lib"),
+            ("GITHUB_TOKEN", "ghp_123456"),
+            ("PATH", "/usr/bin"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+
+        let names = ["NODE_*", "GITHUB_TOKEN", "MISSING"].map(String::from);
+        let found = match_env(&names, vars);
+        let shown: Vec<(&str, Option<&str>)> = found
+            .iter()
+            .map(|v| (v.name.as_str(), v.value.as_deref()))
+            .collect();
+        assert_eq!(
+            shown,
+            [
+                ("NODE_ENV", Some("development")),
+                ("NODE_PATH", Some("/lib")),
+                ("GITHUB_TOKEN", Some("<hidden, 10 chars>")),
+                ("MISSING", None),
+            ]
+        );
+        assert!(found[2].masked);
+    }
+}
@@ -1,16 +1,24 @@
 //! Tool executor trait and context for tool execution.
 
 use anyhow::Result;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use crate::background_process::BackgroundProcessManager;
+use crate::build_metrics::BuildReport;
+use crate::file_versions::FileVersions;
+use crate::notebook_kernel::NotebookKernelManager;
 use crate::pending_research::PendingResearchManager;
 use crate::paths::{ensure_session_dir, get_session_todo_path, get_todo_path};
+use crate::prefetch::PrefetchCache;
+use crate::python_interpreter::PythonInterpreter;
 use crate::ui_writer::UiWriter;
+use crate::verification::PendingVerificationManager;
 use crate::webdriver_session::WebDriverSession;
 use crate::ToolCall;
 use g3_config::Config;
+use g3_execution::PersistentShell;
 
 /// Context passed to tool executors containing shared state.
 pub struct ToolContext<'a, W: UiWriter> {
@@ -29,6 +37,15 @@ pub struct ToolContext<'a, W: UiWriter> {
     pub context_total_tokens: u32,
     pub context_used_tokens: u32,
     pub pending_research_manager: &'a PendingResearchManager,
+    pub build_reports: &'a mut Vec<BuildReport>,
+    pub pending_verification_manager: &'a PendingVerificationManager,
+    pub prefetch_cache: &'a PrefetchCache,
+    pub notebook_kernels: &'a NotebookKernelManager,
+    pub python_interpreter: &'a PythonInterpreter,
+    /// Bash process kept across shell calls when `shell.persistent` is set
+    pub persistent_shell: &'a PersistentShell,
+    /// Files the agent has seen, checked before the edit tools write them
+    pub file_versions: &'a FileVersions,
 }
 
 impl<'a, W: UiWriter> ToolContext<'a, W> {
@@ -41,6 +58,58 @@ impl<'a, W: UiWriter> ToolContext<'a, W> {
             get_todo_path()
         }
     }
+
+    /// The workspace: `working_dir`, or the current directory.
+    pub fn workspace_dir(&self) -> PathBuf {
+        workspace_dir(self.working_dir)
+    }
+
+    /// The directory named by the call's `working_dir` argument, if it has
+    /// one (see `resolve_working_dir`). `Err` holds the message refusing the
+    /// call.
+    pub fn call_working_dir(&self, tool_call: &ToolCall) -> Result<Option<PathBuf>, String> {
+        match tool_call.args.get("working_dir").and_then(|v| v.as_str()) {
+            Some(dir) => resolve_working_dir(&self.workspace_dir(), dir).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The directory a call works in: its `working_dir` argument, or the
+    /// workspace. `Err` holds the message refusing the call.
+    pub fn call_dir(&self, tool_call: &ToolCall) -> Result<PathBuf, String> {
+        Ok(self
+            .call_working_dir(tool_call)?
+            .unwrap_or_else(|| self.workspace_dir()))
+    }
+}
+
+/// The workspace tools run in: `working_dir`, or the current directory.
+pub fn workspace_dir(working_dir: Option<&str>) -> PathBuf {
+    working_dir
+        .map(|dir| PathBuf::from(shellexpand::tilde(dir).as_ref()))
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default())
+}
+
+/// `dir`, relative to `workspace` unless absolute, with symlinks resolved.
+/// It must be an existing directory inside the workspace.
+pub fn resolve_working_dir(workspace: &Path, dir: &str) -> Result<PathBuf, String> {
+    let path = workspace.join(shellexpand::tilde(dir).as_ref());
+    let resolved = match path.canonicalize() {
+        Ok(resolved) if resolved.is_dir() => resolved,
+        Ok(_) => return Err(format!("❌ working_dir '{}' is not a directory", dir)),
+        Err(e) => return Err(format!("❌ working_dir '{}': {}", dir, e)),
+    };
+    let root = workspace
+        .canonicalize()
+        .unwrap_or_else(|_| workspace.to_path_buf());
+    if !resolved.starts_with(&root) {
+        return Err(format!(
+            "❌ working_dir '{}' is outside the workspace ({})",
+            dir,
+            root.display()
+        ));
+    }
+    Ok(resolved)
 }
 
 /// Trait for tool executors.
@@ -55,3 +124,36 @@ pub trait ToolExecutor<W: UiWriter> {
     where
         W: 'a;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_working_dir() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(workspace.path().join("packages/api")).unwrap();
+        std::fs::write(workspace.path().join("README.md"), "").unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+
+        assert_eq!(
+            resolve_working_dir(workspace.path(), "packages/api").unwrap(),
+            root.join("packages/api")
+        );
+        assert_eq!(
+            resolve_working_dir(workspace.path(), &root.join("packages").to_string_lossy())
+                .unwrap(),
+            root.join("packages")
+        );
+        assert_eq!(
+            resolve_working_dir(workspace.path(), "packages/..").unwrap(),
+            root
+        );
+
+        let outside = resolve_working_dir(workspace.path(), "..").unwrap_err();
+        assert!(outside.contains("outside the workspace"), "{}", outside);
+        let file = resolve_working_dir(workspace.path(), "README.md").unwrap_err();
+        assert!(file.contains("not a directory"), "{}", file);
+        assert!(resolve_working_dir(workspace.path(), "missing").is_err());
+    }
+}
@@ -1,17 +1,22 @@
-//! File operation tools: read_file, write_file, str_replace, read_image.
+//! File operation tools: read_file, read_many_files, write_file, str_replace, multi_edit,
+//! edit_lines, read_image.
 
 use anyhow::Result;
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
+use std::sync::Arc;
 use tracing::debug;
 
+use crate::compressed_file;
+use crate::dry_run::unified_diff;
 use crate::ui_writer::UiWriter;
 use crate::utils::resolve_path_with_unicode_fallback;
 use crate::utils::apply_unified_diff_to_string;
 use crate::ToolCall;
 
 use super::executor::ToolContext;
+use super::read_output::{save_output, tail_preview};
 
 /// Maximum base64-encoded image size in bytes (5MB) - Anthropic API limit
 const MAX_BASE64_SIZE: usize = 5 * 1024 * 1024;
@@ -34,6 +39,18 @@ const BYTES_PER_TOKEN: f32 = 3.5;
 /// Maximum percentage of context window a single file read can consume
 const MAX_FILE_READ_PERCENT: f32 = 0.20; // 20%
 
+/// Largest truncated `read_file` range saved for `read_output` (16MB)
+const MAX_PAGED_READ_BYTES: usize = 16 * 1024 * 1024;
+
+/// Most files `read_many_files` reads in one call
+const MAX_MANY_FILES: usize = 20;
+
+/// Default per-file cap for `read_many_files` (bytes)
+const DEFAULT_MANY_FILES_PER_FILE_BYTES: usize = 32 * 1024;
+
+/// Default total cap for `read_many_files` (bytes)
+const DEFAULT_MANY_FILES_TOTAL_BYTES: usize = 128 * 1024;
+
 /// Estimate token count from byte size
 fn estimate_tokens_from_bytes(bytes: usize) -> u32 {
     ((bytes as f32 / BYTES_PER_TOKEN) * 1.1).ceil() as u32 // 10% safety buffer
@@ -80,10 +97,13 @@ pub async fn execute_read_file<W: UiWriter>(
         None => return Ok("❌ Missing file_path argument".to_string()),
     };
 
-    // Expand tilde (~) to home directory
-    let expanded_path = shellexpand::tilde(file_path);
+    // Expand tilde (~) to home directory, and resolve against the call's working_dir
+    let expanded_path = match expand_in_call_dir(file_path, tool_call, ctx) {
+        Ok(path) => path,
+        Err(refusal) => return Ok(refusal),
+    };
     // Try to resolve with Unicode space fallback (macOS uses U+202F in screenshot names)
-    let resolved_path = resolve_path_with_unicode_fallback(expanded_path.as_ref());
+    let resolved_path = resolve_path_with_unicode_fallback(&expanded_path);
     let path_str = resolved_path.as_ref();
 
     // Extract optional start and end positions
@@ -105,11 +125,26 @@ pub async fn execute_read_file<W: UiWriter>(
 
     // Get file metadata for size without reading content
     let path = Path::new(path_str);
-    let metadata = match std::fs::metadata(path) {
-        Ok(m) => m,
+    let (total_file_len, prefetched) = match std::fs::metadata(path) {
+        Ok(metadata) => {
+            ctx.file_versions.record_from_disk(path);
+            // Contents read speculatively while the tool call was streaming, if still current
+            let prefetched = ctx.prefetch_cache.take(path, &metadata);
+            (metadata.len() as usize, prefetched)
+        }
+        // Thinned tool results may be stored compressed, as `<path>.zst`
+        Err(_) if compressed_file::exists(path) => {
+            let content = Arc::new(compressed_file::read(path)?);
+            (content.len(), Some(content))
+        }
         Err(e) => return Ok(format!("❌ Failed to read file '{}': {}", path_str, e)),
     };
-    let total_file_len = metadata.len() as usize;
+    let read_range = move |start: usize, end: usize| -> Result<String> {
+        match prefetched {
+            Some(bytes) => Ok(slice_file_range(&bytes, start, end)),
+            None => read_file_range(path, start, end),
+        }
+    };
 
     // Calculate token-aware limit
     let read_limit = calculate_read_limit(
@@ -151,38 +186,197 @@ pub async fn execute_read_file<W: UiWriter>(
     if user_start >= total_file_len {
         // Read last 100 bytes instead
         let fallback_start = total_file_len.saturating_sub(100);
-        let content = read_file_range(path, fallback_start, total_file_len)?;
+        let mut content = read_range(fallback_start, total_file_len)?;
         let line_count = content.lines().count();
-        return Ok(format!(
-            "{}\n🔍 {} lines read (start {} exceeded length {}, showing last {} chars)",
-            content, line_count, user_start, total_file_len, total_file_len - fallback_start
+        content.push_str(&format!(
+            "\n🔍 {} lines read (start {} exceeded length {}, showing last {} chars)",
+            line_count, user_start, total_file_len, total_file_len - fallback_start
         ));
+        return Ok(content);
     }
 
     // Use optimized seek-based reading
-    let content = read_file_range(path, user_start, effective_end)?;
+    let mut content = read_range(user_start, effective_end)?;
     let line_count = content.lines().count();
 
-    // Format output based on whether truncation occurred
-    if was_truncated {
+    // Format output based on whether truncation occurred. The footer is appended
+    // in place so large files aren't copied into a second String.
+    let footer = if was_truncated {
         let context_pct = (ctx.context_used_tokens as f32 / ctx.context_total_tokens as f32 * 100.0) as u32;
-        Ok(format!(
-            "{}\n🔍 {} lines read (truncated, chars {}-{} of {}, context {}%)",
-            content, line_count, user_start, effective_end, total_file_len, context_pct
-        ))
+        let footer = format!(
+            "\n🔍 {} lines read (truncated, chars {}-{} of {}, context {}%)",
+            line_count, user_start, effective_end, total_file_len, context_pct
+        );
+        match page_read_range(path, user_start, user_end, ctx.session_id) {
+            Some(paged) => format!("{}{}", paged, footer),
+            None => footer,
+        }
     } else if end_was_clamped {
-        Ok(format!(
-            "{}\n🔍 {} lines read (chars {}-{}, end clamped from {} to file length {})",
-            content, line_count, user_start, effective_end, end_char.unwrap(), total_file_len
-        ))
+        format!(
+            "\n🔍 {} lines read (chars {}-{}, end clamped from {} to file length {})",
+            line_count, user_start, effective_end, end_char.unwrap(), total_file_len
+        )
     } else if start_char.is_some() || end_char.is_some() {
-        Ok(format!(
-            "{}\n🔍 {} lines read (chars {}-{})",
-            content, line_count, user_start, effective_end
-        ))
+        format!(
+            "\n🔍 {} lines read (chars {}-{})",
+            line_count, user_start, effective_end
+        )
     } else {
-        Ok(format!("{}\n🔍 {} lines read", content, line_count))
+        format!("\n🔍 {} lines read", line_count)
+    };
+    content.push_str(&footer);
+    Ok(content)
+}
+
+/// Save the chars `start..end` of the file at `path` for `read_output`, and
+/// return the range's last lines with its handle, to follow a truncated read.
+/// `None` for ranges too large to copy, or without a session.
+fn page_read_range(
+    path: &Path,
+    start: usize,
+    end: usize,
+    session_id: Option<&str>,
+) -> Option<String> {
+    if end - start > MAX_PAGED_READ_BYTES {
+        return None;
+    }
+    let range = read_file_range(path, start, end).ok()?;
+    let handle = save_output(&range, session_id, "read_file")?;
+    Some(format!(
+        "\n\n[[ TRUNCATED: the requested range has {} lines; the last ones follow. \
+        Page through all of them with read_output(handle: \"{}\", start, end) ]]\n\n{}",
+        range.lines().count(),
+        handle,
+        tail_preview(&range)
+    ))
+}
+
+/// Execute the `read_many_files` tool.
+///
+/// Reads several files in one call, each capped at `max_bytes_per_file` and
+/// all together at `max_total_bytes` (further limited to the share of the
+/// context window a single `read_file` may use).
+pub async fn execute_read_many_files<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing read_many_files tool call");
+
+    let Some(paths) = tool_call.args.get("file_paths").and_then(|v| v.as_array()) else {
+        return Ok("❌ Missing or invalid file_paths argument (expected an array)".to_string());
+    };
+    let paths: Vec<String> = paths
+        .iter()
+        .filter_map(|p| p.as_str())
+        .map(|p| {
+            let expanded = shellexpand::tilde(p);
+            resolve_path_with_unicode_fallback(expanded.as_ref()).into_owned()
+        })
+        .collect();
+    if paths.is_empty() {
+        return Ok("❌ No file paths given".to_string());
+    }
+    if paths.len() > MAX_MANY_FILES {
+        return Ok(format!(
+            "❌ Too many files ({}); read_many_files reads at most {} per call",
+            paths.len(),
+            MAX_MANY_FILES
+        ));
+    }
+
+    let per_file = tool_call
+        .args
+        .get("max_bytes_per_file")
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_MANY_FILES_PER_FILE_BYTES, |n| n as usize);
+    let mut total = tool_call
+        .args
+        .get("max_total_bytes")
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_MANY_FILES_TOTAL_BYTES, |n| n as usize);
+    if let Some(limit) =
+        calculate_read_limit(total, ctx.context_total_tokens, ctx.context_used_tokens)
+    {
+        total = total.min(limit);
+    }
+
+    for path in &paths {
+        ctx.file_versions.record_from_disk(Path::new(path));
     }
+    Ok(read_files_concatenated(&paths, per_file, total))
+}
+
+/// Read `paths` in order and concatenate them under `===== path =====`
+/// separators, each file capped at `per_file` bytes and all at `total` bytes.
+///
+/// Files that can't be read are reported in place (with ⚠️, so one missing
+/// file doesn't fail the call); files past the total cap are listed as skipped.
+pub fn read_files_concatenated(paths: &[String], per_file: usize, total: usize) -> String {
+    let mut output = String::new();
+    let mut remaining = total;
+    let mut read = 0;
+    let mut skipped = Vec::new();
+
+    for path in paths {
+        if remaining == 0 {
+            skipped.push(path.as_str());
+            continue;
+        }
+
+        let len = match std::fs::metadata(path) {
+            Ok(m) if m.is_file() => m.len() as usize,
+            Ok(_) => {
+                output.push_str(&format!("===== {} =====\n⚠️ Not a file\n\n", path));
+                continue;
+            }
+            Err(e) => {
+                output.push_str(&format!(
+                    "===== {} =====\n⚠️ Failed to read: {}\n\n",
+                    path, e
+                ));
+                continue;
+            }
+        };
+        let limit = len.min(per_file).min(remaining);
+        let content = match read_file_range(Path::new(path), 0, limit) {
+            Ok(content) => content,
+            Err(e) => {
+                output.push_str(&format!(
+                    "===== {} =====\n⚠️ Failed to read: {}\n\n",
+                    path, e
+                ));
+                continue;
+            }
+        };
+
+        let header = if limit < len {
+            format!("{} (truncated: first {} of {} bytes)", path, limit, len)
+        } else {
+            format!("{} ({} lines)", path, content.lines().count())
+        };
+        output.push_str(&format!("===== {} =====\n{}", header, content));
+        if !content.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push('\n');
+        remaining -= limit;
+        read += 1;
+    }
+
+    if !skipped.is_empty() {
+        output.push_str(&format!(
+            "⚠️ Total size cap of {} bytes reached; not read: {}\n",
+            total,
+            skipped.join(", ")
+        ));
+    }
+    output.push_str(&format!(
+        "🔍 Read {} of {} files ({} bytes)",
+        read,
+        paths.len(),
+        total - remaining
+    ));
+    output
 }
 
 /// Read a specific byte range from a file using seek (O(1) seek + O(n) read where n = range size).
@@ -197,8 +391,7 @@ fn read_file_range(path: &Path, start: usize, end: usize) -> Result<String> {
     
     // Calculate how far back we might need to look for a char boundary
     let safe_start = start.saturating_sub(3);
-    let extra_at_start = start - safe_start;
-    
+
     // Read a few extra bytes at the end to complete any partial char
     let extra_at_end = 3;
     
@@ -210,15 +403,37 @@ fn read_file_range(path: &Path, start: usize, end: usize) -> Result<String> {
     let mut buffer = vec![0u8; bytes_to_read];
     let bytes_read = reader.read(&mut buffer)?;
     buffer.truncate(bytes_read);
-    
+
+    Ok(decode_range(buffer, start, end))
+}
+
+/// Same as `read_file_range`, on file contents already in memory.
+///
+/// Copies only the requested range (plus the bytes `decode_range` needs to
+/// find character boundaries), not the whole file.
+fn slice_file_range(bytes: &[u8], start: usize, end: usize) -> String {
+    let to = (end + 3).min(bytes.len());
+    let from = start.saturating_sub(3).min(to);
+    decode_range(bytes[from..to].to_vec(), start, end)
+}
+
+/// Decode a buffer read from `start - 3` (or 0) to just past `end`, trimming
+/// it to the requested range on valid UTF-8 boundaries.
+fn decode_range(buffer: Vec<u8>, start: usize, end: usize) -> String {
+    let safe_start = start.saturating_sub(3);
+    let extra_at_start = start - safe_start;
+    let extra_at_end = 3;
+    let bytes_read = buffer.len();
+
     // Convert to string - this should work since we read the whole file originally as UTF-8
     // But we need to find valid boundaries within our extended read
-    let full_str = match std::str::from_utf8(&buffer) {
-        Ok(s) => s.to_string(),
-        Err(_) => {
+    // Takes ownership of the buffer, so valid UTF-8 is not copied
+    let mut full_str = match String::from_utf8(buffer) {
+        Ok(s) => s,
+        Err(e) => {
             // If the whole buffer isn't valid UTF-8, try to find valid boundaries
             // This can happen with binary files or corrupted data
-            return Ok(String::from_utf8_lossy(&buffer).into_owned());
+            return String::from_utf8_lossy(e.as_bytes()).into_owned();
         }
     };
     
@@ -231,7 +446,7 @@ fn read_file_range(path: &Path, start: usize, end: usize) -> Result<String> {
         // Just trim any extra at the end
         let target_len = end - start;
         if full_str.len() <= target_len {
-            return Ok(full_str);
+            return full_str;
         }
         // Find char boundary at target_len
         let end_idx = full_str
@@ -240,7 +455,8 @@ fn read_file_range(path: &Path, start: usize, end: usize) -> Result<String> {
             .last()
             .map(|(i, c)| i + c.len_utf8())
             .unwrap_or(full_str.len());
-        return Ok(full_str[..end_idx].to_string());
+        full_str.truncate(end_idx);
+        return full_str;
     }
     
     // Complex case: we read extra at the start, need to skip those bytes
@@ -260,7 +476,9 @@ fn read_file_range(path: &Path, start: usize, end: usize) -> Result<String> {
         .map(|(i, c)| i + c.len_utf8())
         .unwrap_or(full_str.len());
     
-    Ok(full_str[start_idx..end_idx.min(full_str.len())].to_string())
+    full_str.truncate(end_idx.min(full_str.len()));
+    full_str.drain(..start_idx);
+    full_str
 }
 
 /// Execute the `read_image` tool.
@@ -455,7 +673,7 @@ pub async fn execute_read_image<W: UiWriter>(
 /// Execute the `write_file` tool.
 pub async fn execute_write_file<W: UiWriter>(
     tool_call: &ToolCall,
-    _ctx: &ToolContext<'_, W>,
+    ctx: &ToolContext<'_, W>,
 ) -> Result<String> {
     debug!("Processing write_file tool call");
     debug!("Raw tool_call.args: {:?}", tool_call.args);
@@ -470,12 +688,24 @@ pub async fn execute_write_file<W: UiWriter>(
     );
 
     if let (Some(path), Some(content)) = (path_str, content_str) {
-        // Expand tilde (~) to home directory
-        let expanded_path = shellexpand::tilde(path);
-        let path = expanded_path.as_ref();
+        // Expand tilde (~) to home directory, and resolve against the call's working_dir
+        let expanded_path = match expand_in_call_dir(path, tool_call, ctx) {
+            Ok(path) => path,
+            Err(refusal) => return Ok(refusal),
+        };
+        let path = expanded_path.as_str();
 
         debug!("Writing to file: {}", path);
 
+        if let Err(conflict) = ctx.file_versions.check(Path::new(path)) {
+            return Ok(conflict);
+        }
+
+        let original = std::fs::read_to_string(path).unwrap_or_default();
+        if let Some(refusal) = review_edit(ctx, path, &original, content) {
+            return Ok(refusal);
+        }
+
         // Create parent directories if they don't exist
         if let Some(parent) = std::path::Path::new(path).parent() {
             if let Err(e) = std::fs::create_dir_all(parent) {
@@ -488,6 +718,7 @@ pub async fn execute_write_file<W: UiWriter>(
 
         match std::fs::write(path, content) {
             Ok(()) => {
+                ctx.file_versions.record(Path::new(path), content);
                 let line_count = content.lines().count();
                 let char_count = content.len();
                 let char_display = if char_count >= 1000 {
@@ -520,7 +751,7 @@ pub async fn execute_write_file<W: UiWriter>(
 /// Execute the `str_replace` tool.
 pub async fn execute_str_replace<W: UiWriter>(
     tool_call: &ToolCall,
-    _ctx: &ToolContext<'_, W>,
+    ctx: &ToolContext<'_, W>,
 ) -> Result<String> {
     debug!("Processing str_replace tool call");
 
@@ -557,6 +788,10 @@ pub async fn execute_str_replace<W: UiWriter>(
         file_path, start_char, end_char
     );
 
+    if let Err(conflict) = ctx.file_versions.check(Path::new(&file_path)) {
+        return Ok(conflict);
+    }
+
     // Read the existing file
     let file_content = match std::fs::read_to_string(&file_path) {
         Ok(content) => content,
@@ -569,7 +804,222 @@ pub async fn execute_str_replace<W: UiWriter>(
         Err(e) => return Ok(format!("❌ {}", e)),
     };
 
-    // Count insertions and deletions from the diff
+    let (insertions, deletions) = count_diff_lines(diff);
+
+    if let Some(refusal) = review_edit(ctx, &file_path, &file_content, &result) {
+        return Ok(refusal);
+    }
+
+    // Write the result back to the file
+    match std::fs::write(&file_path, &result) {
+        Ok(()) => {
+            ctx.file_versions.record(Path::new(&file_path), &result);
+            Ok(format!(
+                "✅ +{} insertions | -{} deletions",
+                insertions, deletions
+            ))
+        }
+        Err(e) => Ok(format!("❌ Failed to write to file '{}': {}", file_path, e)),
+    }
+}
+
+/// Show the pending edit of `path` as a diff and, with `[approval]
+/// review_edits`, ask the user whether to apply it. Returns the message
+/// refusing the edit when they decline.
+fn review_edit<W: UiWriter>(
+    ctx: &ToolContext<'_, W>,
+    path: &str,
+    original: &str,
+    edited: &str,
+) -> Option<String> {
+    if original == edited {
+        return None;
+    }
+    ctx.ui_writer
+        .print_diff(path, &unified_diff(Path::new(path), original, edited));
+    // Nobody can answer in autonomous mode
+    if !ctx.config.approval.review_edits || ctx.is_autonomous {
+        return None;
+    }
+    if ctx
+        .ui_writer
+        .prompt_user_yes_no(&format!("Apply this edit to {}?", path))
+    {
+        None
+    } else {
+        Some(format!(
+            "❌ The user declined this edit; {} was not changed",
+            path
+        ))
+    }
+}
+
+/// Execute the `multi_edit` tool.
+///
+/// Applies several `str_replace`-style diffs in one call. Either every edit
+/// applies and all files are written, or nothing is changed.
+pub async fn execute_multi_edit<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing multi_edit tool call");
+
+    let Some(entries) = tool_call.args.get("edits").and_then(|v| v.as_array()) else {
+        return Ok("❌ Missing or invalid edits argument (expected an array)".to_string());
+    };
+    if entries.is_empty() {
+        return Ok("❌ No edits given".to_string());
+    }
+
+    let mut edits = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.iter().enumerate() {
+        let (Some(file_path), Some(diff)) = (
+            entry.get("file_path").and_then(|v| v.as_str()),
+            entry.get("diff").and_then(|v| v.as_str()),
+        ) else {
+            return Ok(format!(
+                "❌ Edit {}: missing or invalid file_path/diff; no files were changed",
+                i + 1
+            ));
+        };
+        edits.push(DiffEdit {
+            file_path: shellexpand::tilde(file_path).into_owned(),
+            diff: diff.to_string(),
+            start: entry
+                .get("start")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize),
+            end: entry
+                .get("end")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize),
+        });
+    }
+
+    for edit in &edits {
+        if let Err(conflict) = ctx.file_versions.check(Path::new(&edit.file_path)) {
+            return Ok(format!("{}\nNo files were changed.", conflict));
+        }
+    }
+    let files = match apply_diff_edits(&edits) {
+        Ok(files) => files,
+        Err(e) => return Ok(format!("❌ {}; no files were changed", e)),
+    };
+    if let Err(e) = write_edited_files(&files) {
+        return Ok(format!("❌ {}", e));
+    }
+    for file in &files {
+        ctx.file_versions
+            .record(Path::new(&file.path), &file.content);
+    }
+
+    let insertions: usize = files.iter().map(|f| f.insertions).sum();
+    let deletions: usize = files.iter().map(|f| f.deletions).sum();
+    let mut summary = format!(
+        "✅ Applied {} edits to {} files | +{} insertions | -{} deletions",
+        edits.len(),
+        files.len(),
+        insertions,
+        deletions
+    );
+    for file in &files {
+        summary.push_str(&format!(
+            "\n  {}: {} edits | +{} | -{}",
+            file.path, file.edits, file.insertions, file.deletions
+        ));
+    }
+    Ok(summary)
+}
+
+/// One entry of a `multi_edit` call.
+pub struct DiffEdit {
+    pub file_path: String,
+    /// Unified diff, as for `str_replace`
+    pub diff: String,
+    /// Optional character range to search in (0-indexed, end exclusive)
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+}
+
+/// A file changed by a `multi_edit` call, before it is written.
+#[derive(Debug, Clone)]
+pub struct EditedFile {
+    pub path: String,
+    pub original: String,
+    pub content: String,
+    /// Number of edits applied to this file
+    pub edits: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Apply `edits` in order, in memory.
+///
+/// Edits to the same file stack: each one sees the result of the previous
+/// ones, so `start`/`end` refer to the partly edited content. Returns the
+/// changed files in the order they were first edited, or the first error.
+pub fn apply_diff_edits(edits: &[DiffEdit]) -> std::result::Result<Vec<EditedFile>, String> {
+    let mut files: Vec<EditedFile> = Vec::new();
+
+    for (i, edit) in edits.iter().enumerate() {
+        let index = match files.iter().position(|f| f.path == edit.file_path) {
+            Some(index) => index,
+            None => {
+                let original = std::fs::read_to_string(&edit.file_path).map_err(|e| {
+                    format!(
+                        "Edit {}: failed to read file '{}': {}",
+                        i + 1,
+                        edit.file_path,
+                        e
+                    )
+                })?;
+                files.push(EditedFile {
+                    path: edit.file_path.clone(),
+                    content: original.clone(),
+                    original,
+                    edits: 0,
+                    insertions: 0,
+                    deletions: 0,
+                });
+                files.len() - 1
+            }
+        };
+
+        let file = &mut files[index];
+        file.content =
+            apply_unified_diff_to_string(&file.content, &edit.diff, edit.start, edit.end)
+                .map_err(|e| format!("Edit {} ({}): {}", i + 1, edit.file_path, e))?;
+        let (insertions, deletions) = count_diff_lines(&edit.diff);
+        file.edits += 1;
+        file.insertions += insertions;
+        file.deletions += deletions;
+    }
+
+    Ok(files)
+}
+
+/// Write the edited files. If a write fails, files already written are
+/// restored to their original content.
+pub fn write_edited_files(files: &[EditedFile]) -> std::result::Result<(), String> {
+    for (i, file) in files.iter().enumerate() {
+        if let Err(e) = std::fs::write(&file.path, &file.content) {
+            let mut message = format!("Failed to write to file '{}': {}", file.path, e);
+            for written in &files[..i] {
+                if let Err(e) = std::fs::write(&written.path, &written.original) {
+                    message.push_str(&format!("\n⚠️ Failed to restore '{}': {}", written.path, e));
+                }
+            }
+            if i > 0 {
+                message.push_str(&format!("\n{} files already written were restored", i));
+            }
+            return Err(message);
+        }
+    }
+    Ok(())
+}
+
+/// Count the added and removed lines of a unified diff.
+fn count_diff_lines(diff: &str) -> (usize, usize) {
     let mut insertions = 0;
     let mut deletions = 0;
     for line in diff.lines() {
@@ -579,14 +1029,159 @@ pub async fn execute_str_replace<W: UiWriter>(
             deletions += 1;
         }
     }
+    (insertions, deletions)
+}
+
+/// Execute the `edit_lines` tool.
+///
+/// Replaces lines `start_line..=end_line` (1-indexed) after checking that the
+/// first and last lines of the range match what the model expects.
+pub async fn execute_edit_lines<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing edit_lines tool call");
+
+    let args = &tool_call.args;
+    let file_path = match args.get("file_path").and_then(|v| v.as_str()) {
+        Some(path) => shellexpand::tilde(path).into_owned(),
+        None => return Ok("❌ Missing or invalid file_path argument".to_string()),
+    };
+    let (Some(start_line), Some(end_line)) = (
+        args.get("start_line").and_then(|v| v.as_u64()),
+        args.get("end_line").and_then(|v| v.as_u64()),
+    ) else {
+        return Ok("❌ Missing or invalid start_line/end_line arguments".to_string());
+    };
+    let (Some(expected_first), Some(expected_last)) = (
+        args.get("expected_first_line").and_then(|v| v.as_str()),
+        args.get("expected_last_line").and_then(|v| v.as_str()),
+    ) else {
+        return Ok(
+            "❌ Missing expected_first_line/expected_last_line arguments (echo the current first and last lines of the range)"
+                .to_string(),
+        );
+    };
+    let Some(content) = args.get("content").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing or invalid content argument".to_string());
+    };
+
+    if let Err(conflict) = ctx.file_versions.check(Path::new(&file_path)) {
+        return Ok(conflict);
+    }
+    let file_content = match std::fs::read_to_string(&file_path) {
+        Ok(content) => content,
+        Err(e) => return Ok(format!("❌ Failed to read file '{}': {}", file_path, e)),
+    };
+
+    let edit = LineEdit {
+        start_line: start_line as usize,
+        end_line: end_line as usize,
+        expected_first_line: expected_first,
+        expected_last_line: expected_last,
+        content,
+    };
+    let (result, removed, inserted) = match replace_line_range(&file_content, &edit) {
+        Ok(r) => r,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
 
-    // Write the result back to the file
     match std::fs::write(&file_path, &result) {
-        Ok(()) => Ok(format!("✅ +{} insertions | -{} deletions", insertions, deletions)),
+        Ok(()) => {
+            ctx.file_versions.record(Path::new(&file_path), &result);
+            Ok(format!(
+                "✅ Replaced lines {}-{} | -{} lines | +{} lines",
+                start_line, end_line, removed, inserted
+            ))
+        }
         Err(e) => Ok(format!("❌ Failed to write to file '{}': {}", file_path, e)),
     }
 }
 
+/// A line-range replacement requested by `edit_lines`.
+pub struct LineEdit<'a> {
+    /// First line to replace (1-indexed)
+    pub start_line: usize,
+    /// Last line to replace (1-indexed, inclusive)
+    pub end_line: usize,
+    /// The current content of `start_line`, as the model expects it
+    pub expected_first_line: &'a str,
+    /// The current content of `end_line`, as the model expects it
+    pub expected_last_line: &'a str,
+    /// Replacement text (empty to delete the lines)
+    pub content: &'a str,
+}
+
+/// Lines shown around the range when the expected lines don't match.
+const EDIT_LINES_CONTEXT: usize = 2;
+
+/// Replace a line range, validating the expected first and last lines.
+///
+/// Comparison ignores trailing whitespace. The file's line ending style and
+/// trailing newline are preserved. Returns the new content and the number of
+/// lines removed and inserted.
+pub fn replace_line_range(
+    file_content: &str,
+    edit: &LineEdit<'_>,
+) -> std::result::Result<(String, usize, usize), String> {
+    let newline = if file_content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let lines: Vec<&str> = file_content.lines().collect();
+
+    if edit.start_line == 0 || edit.end_line < edit.start_line {
+        return Err(format!(
+            "Invalid range {}-{}: lines are 1-indexed and end_line must be >= start_line",
+            edit.start_line, edit.end_line
+        ));
+    }
+    if edit.end_line > lines.len() {
+        return Err(format!(
+            "Range {}-{} is past the end of the file ({} lines)",
+            edit.start_line,
+            edit.end_line,
+            lines.len()
+        ));
+    }
+
+    let first = lines[edit.start_line - 1];
+    let last = lines[edit.end_line - 1];
+    if first.trim_end() != edit.expected_first_line.trim_end()
+        || last.trim_end() != edit.expected_last_line.trim_end()
+    {
+        let from = edit.start_line.saturating_sub(EDIT_LINES_CONTEXT).max(1);
+        let to = (edit.end_line + EDIT_LINES_CONTEXT).min(lines.len());
+        let numbered: Vec<String> = (from..=to)
+            .map(|n| format!("{:>6}\t{}", n, lines[n - 1]))
+            .collect();
+        return Err(format!(
+            "Expected lines don't match the file; nothing was changed. Lines {}-{} are:\n{}",
+            from,
+            to,
+            numbered.join("\n")
+        ));
+    }
+
+    let replacement: Vec<&str> = edit.content.lines().collect();
+    let mut new_lines: Vec<&str> = Vec::with_capacity(lines.len() + replacement.len());
+    new_lines.extend_from_slice(&lines[..edit.start_line - 1]);
+    new_lines.extend_from_slice(&replacement);
+    new_lines.extend_from_slice(&lines[edit.end_line..]);
+
+    let mut result = new_lines.join(newline);
+    if file_content.ends_with('\n') && !result.is_empty() {
+        result.push_str(newline);
+    }
+
+    Ok((
+        result,
+        edit.end_line - edit.start_line + 1,
+        replacement.len(),
+    ))
+}
+
 // Helper functions
 
 /// Known argument key pairs for path and content.
@@ -597,6 +1192,22 @@ const PATH_CONTENT_KEYS: &[(&str, &str)] = &[
     ("file", "data"),          // Alternative naming
 ];
 
+/// `path` with `~` expanded, relative to the call's `working_dir` argument
+/// if it has one. `Err` holds the message refusing a bad `working_dir`.
+fn expand_in_call_dir<W: UiWriter>(
+    path: &str,
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> std::result::Result<String, String> {
+    let expanded = shellexpand::tilde(path);
+    match ctx.call_working_dir(tool_call)? {
+        Some(dir) if Path::new(expanded.as_ref()).is_relative() => {
+            Ok(dir.join(expanded.as_ref()).to_string_lossy().into_owned())
+        }
+        _ => Ok(expanded.into_owned()),
+    }
+}
+
 /// Extract path and content from various argument formats.
 fn extract_path_and_content(args: &serde_json::Value) -> (Option<&str>, Option<&str>) {
     match args {
@@ -863,3 +1474,124 @@ pub fn print_imgcat(
     // Blank line before next image (no │ prefix)
     println!();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit<'a>(
+        start: usize,
+        end: usize,
+        first: &'a str,
+        last: &'a str,
+        content: &'a str,
+    ) -> LineEdit<'a> {
+        LineEdit {
+            start_line: start,
+            end_line: end,
+            expected_first_line: first,
+            expected_last_line: last,
+            content,
+        }
+    }
+
+    #[test]
+    fn test_replace_line_range() {
+        let file = "one\ntwo\nthree\nfour\n";
+        let (out, removed, inserted) =
+            replace_line_range(file, &edit(2, 3, "two", "three  ", "2\n2.5\n3")).unwrap();
+        assert_eq!(out, "one\n2\n2.5\n3\nfour\n");
+        assert_eq!((removed, inserted), (2, 3));
+
+        // Deleting lines, CRLF preserved
+        let (out, _, inserted) =
+            replace_line_range("a\r\nb\r\nc\r\n", &edit(2, 2, "b", "b", "")).unwrap();
+        assert_eq!(out, "a\r\nc\r\n");
+        assert_eq!(inserted, 0);
+    }
+
+    #[test]
+    fn test_replace_line_range_validation() {
+        let file = "one\ntwo\nthree\n";
+        let err = replace_line_range(file, &edit(2, 2, "TWO", "TWO", "x")).unwrap_err();
+        assert!(err.contains("nothing was changed"), "{}", err);
+        assert!(err.contains("     2\ttwo"), "{}", err);
+
+        assert!(replace_line_range(file, &edit(0, 1, "", "", "")).is_err());
+        assert!(replace_line_range(file, &edit(3, 2, "", "", "")).is_err());
+        assert!(replace_line_range(file, &edit(3, 4, "three", "", "")).is_err());
+    }
+
+    fn diff_edit(path: &std::path::Path, diff: &str) -> DiffEdit {
+        DiffEdit {
+            file_path: path.to_string_lossy().into_owned(),
+            diff: diff.to_string(),
+            start: None,
+            end: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_diff_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        std::fs::write(&a, "one\ntwo\nthree\n").unwrap();
+        std::fs::write(&b, "alpha\nbeta\n").unwrap();
+
+        // Edits to the same file stack
+        let files = apply_diff_edits(&[
+            diff_edit(&a, "-two\n+2\n"),
+            diff_edit(&b, "-beta\n+BETA\n+gamma\n"),
+            diff_edit(&a, " 2\n-three\n+3\n"),
+        ])
+        .unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].content, "one\n2\n3\n");
+        assert_eq!(
+            (files[0].edits, files[0].insertions, files[0].deletions),
+            (2, 2, 2)
+        );
+        assert_eq!(files[1].content, "alpha\nBETA\ngamma\n");
+
+        write_edited_files(&files).unwrap();
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "one\n2\n3\n");
+
+        // A failing edit reports its position and nothing is applied
+        let err = apply_diff_edits(&[diff_edit(&a, "-one\n+1\n"), diff_edit(&b, "-missing\n+x\n")])
+            .err()
+            .unwrap();
+        assert!(err.starts_with("Edit 2 ("), "{}", err);
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "one\n2\n3\n");
+    }
+
+    #[test]
+    fn test_read_files_concatenated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+        std::fs::write(path("a.toml"), "a = 1\nb = 2\n").unwrap();
+        std::fs::write(path("b.toml"), "x".repeat(50)).unwrap();
+        std::fs::write(path("c.toml"), "c = 3\n").unwrap();
+
+        let output = read_files_concatenated(
+            &[
+                path("a.toml"),
+                path("missing.toml"),
+                path("b.toml"),
+                path("c.toml"),
+            ],
+            20,
+            30,
+        );
+        assert!(output.contains(&format!(
+            "===== {} (2 lines) =====\na = 1\nb = 2\n",
+            path("a.toml")
+        )));
+        assert!(output.contains("⚠️ Failed to read:"));
+        // 12 bytes used by a.toml, so b.toml gets the remaining 18 of its 20
+        assert!(output.contains("(truncated: first 18 of 50 bytes)"));
+        assert!(output.contains(&format!("not read: {}", path("c.toml"))));
+        assert!(output.ends_with("🔍 Read 2 of 4 files (30 bytes)"));
+        assert!(!output.contains('❌'));
+    }
+}
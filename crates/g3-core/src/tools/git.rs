@@ -0,0 +1,646 @@
+//! Git tools: git_status, git_diff, git_commit, git_branch.
+//!
+//! Built on git2 rather than the shell so the output is structured and the
+//! operations are limited to safe ones: there is no push, no force, no branch
+//! deletion and no history rewriting. Commit messages go through the
+//! `[git] commit_template` so every commit records the g3 session that made it,
+//! and the session records the commit (see `commit_links`).
+
+use anyhow::{anyhow, bail, Result};
+use git2::{
+    BranchType, Commit, DiffFormat, DiffOptions, DiffStatsFormat, ErrorCode, Repository, Status,
+    StatusOptions,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use crate::commit_links::CommitLink;
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Diff output beyond this many bytes is cut.
+const MAX_DIFF_BYTES: usize = 50_000;
+
+/// A changed path and its one-letter change kind (A, M, D, R, T).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatusEntry {
+    pub path: String,
+    pub change: char,
+}
+
+/// Working tree state, as reported by `git_status`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GitStatus {
+    /// Current branch, None if HEAD is detached
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub staged: Vec<StatusEntry>,
+    pub unstaged: Vec<StatusEntry>,
+    pub untracked: Vec<String>,
+    pub conflicted: Vec<String>,
+}
+
+impl GitStatus {
+    pub fn is_clean(&self) -> bool {
+        self.staged.is_empty()
+            && self.unstaged.is_empty()
+            && self.untracked.is_empty()
+            && self.conflicted.is_empty()
+    }
+
+    fn to_text(&self) -> String {
+        let mut lines = vec![match (&self.branch, &self.upstream) {
+            (Some(branch), Some(upstream)) => format!(
+                "On branch {} (ahead {}, behind {} of {})",
+                branch, self.ahead, self.behind, upstream
+            ),
+            (Some(branch), None) => format!("On branch {}", branch),
+            (None, _) => "HEAD detached".to_string(),
+        }];
+        if self.is_clean() {
+            lines.push("Nothing to commit, working tree clean".to_string());
+        }
+        let sections: [(&str, Vec<String>); 4] = [
+            ("Conflicted", self.conflicted.clone()),
+            (
+                "Staged",
+                self.staged
+                    .iter()
+                    .map(|e| format!("{} {}", e.change, e.path))
+                    .collect(),
+            ),
+            (
+                "Unstaged",
+                self.unstaged
+                    .iter()
+                    .map(|e| format!("{} {}", e.change, e.path))
+                    .collect(),
+            ),
+            ("Untracked", self.untracked.clone()),
+        ];
+        for (title, entries) in sections {
+            if !entries.is_empty() {
+                lines.push(format!("{} ({}):", title, entries.len()));
+                lines.extend(entries.iter().map(|e| format!("  {}", e)));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Execute the `git_status` tool.
+pub async fn execute_git_status<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing git_status tool call");
+
+    let base = match ctx.call_dir(tool_call) {
+        Ok(base) => base,
+        Err(refusal) => return Ok(refusal),
+    };
+    let json = tool_call.args.get("format").and_then(|v| v.as_str()) == Some("json");
+    let result = open_repo(&base).and_then(|repo| read_status(&repo));
+    Ok(match result {
+        Ok(status) if json => serde_json::to_string_pretty(&status)?,
+        Ok(status) => status.to_text(),
+        Err(e) => format!("❌ {}", e),
+    })
+}
+
+/// Execute the `git_diff` tool.
+pub async fn execute_git_diff<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing git_diff tool call");
+
+    let args = &tool_call.args;
+    let staged = args
+        .get("staged")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let stat = args.get("stat").and_then(|v| v.as_bool()).unwrap_or(false);
+    let path = args.get("path").and_then(|v| v.as_str());
+
+    let base = match ctx.call_dir(tool_call) {
+        Ok(base) => base,
+        Err(refusal) => return Ok(refusal),
+    };
+    let result = open_repo(&base).and_then(|repo| {
+        let pathspec = path.map(|p| repo_relative(&repo, &base, p)).transpose()?;
+        diff(&repo, staged, pathspec.as_deref(), stat)
+    });
+    Ok(result.unwrap_or_else(|e| format!("❌ {}", e)))
+}
+
+/// Execute the `git_commit` tool.
+pub async fn execute_git_commit<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing git_commit tool call");
+
+    let args = &tool_call.args;
+    let Some(message) = args.get("message").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing message argument".to_string());
+    };
+    if message.trim().is_empty() {
+        return Ok("❌ Commit message is empty".to_string());
+    }
+    let files: Vec<&str> = args
+        .get("files")
+        .and_then(|v| v.as_array())
+        .map(|files| files.iter().filter_map(|f| f.as_str()).collect())
+        .unwrap_or_default();
+    let all = args.get("all").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let git_config = &ctx.config.git;
+    let message = render_commit_message(&git_config.commit_template, message, ctx.session_id);
+
+    let base = match ctx.call_dir(tool_call) {
+        Ok(base) => base,
+        Err(refusal) => return Ok(refusal),
+    };
+    let result = open_repo(&base).and_then(|repo| {
+        let files = files
+            .iter()
+            .map(|f| repo_relative(&repo, &base, f))
+            .collect::<Result<Vec<_>>>()?;
+        let summary = commit(&repo, &message, &files, all, &git_config.protected_branches)?;
+        if let (Some(session_id), Some(head)) = (ctx.session_id, head_commit(&repo)?) {
+            let link = CommitLink::new(&head, current_branch(&repo), true);
+            if let Err(e) = link.record(session_id) {
+                warn!("Failed to link commit to session {}: {}", session_id, e);
+            }
+        }
+        Ok(summary)
+    });
+    Ok(result.unwrap_or_else(|e| format!("❌ {}", e)))
+}
+
+/// Execute the `git_branch` tool.
+pub async fn execute_git_branch<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing git_branch tool call");
+
+    let args = &tool_call.args;
+    let action = args
+        .get("action")
+        .and_then(|v| v.as_str())
+        .unwrap_or("list");
+    let name = args.get("name").and_then(|v| v.as_str());
+
+    let base = match ctx.call_dir(tool_call) {
+        Ok(base) => base,
+        Err(refusal) => return Ok(refusal),
+    };
+    let result = open_repo(&base).and_then(|repo| match (action, name) {
+        ("list", _) => list_branches(&repo),
+        ("create", Some(name)) => create_branch(&repo, name),
+        ("switch", Some(name)) => switch_branch(&repo, name),
+        ("create" | "switch", None) => bail!("Missing name argument for action '{}'", action),
+        _ => bail!(
+            "Unknown action '{}' (expected list, create or switch)",
+            action
+        ),
+    });
+    Ok(result.unwrap_or_else(|e| format!("❌ {}", e)))
+}
+
+/// Fill in a commit message template. `{message}` is replaced by the message
+/// and `{session_id}` by the session ID; without a session, lines mentioning
+/// `{session_id}` are dropped.
+pub fn render_commit_message(template: &str, message: &str, session_id: Option<&str>) -> String {
+    let rendered = template
+        .lines()
+        .filter(|line| session_id.is_some() || !line.contains("{session_id}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .replace("{session_id}", session_id.unwrap_or_default())
+        .replace("{message}", message.trim());
+    format!("{}\n", rendered.trim_end())
+}
+
+/// Find the repository containing `dir`, the directory relative paths in
+/// arguments are resolved against.
+pub(super) fn open_repo(dir: &Path) -> Result<Repository> {
+    let repo = Repository::discover(dir)
+        .map_err(|_| anyhow!("Not a git repository: {}", dir.display()))?;
+    if repo.is_bare() {
+        bail!("Bare repositories are not supported");
+    }
+    Ok(repo)
+}
+
+/// `path` relative to the repository root. The path doesn't have to exist
+/// (e.g. a deleted file), but its parent directory does.
+fn repo_relative(repo: &Repository, base: &Path, path: &str) -> Result<PathBuf> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("Repository has no working directory"))?
+        .canonicalize()?;
+    let joined = base.join(path);
+    let absolute = match joined.canonicalize() {
+        Ok(absolute) => absolute,
+        Err(_) => {
+            let (Some(parent), Some(name)) = (joined.parent(), joined.file_name()) else {
+                bail!("Invalid path: {}", path);
+            };
+            parent
+                .canonicalize()
+                .map_err(|_| anyhow!("No such file or directory: {}", path))?
+                .join(name)
+        }
+    };
+    absolute
+        .strip_prefix(&workdir)
+        .map(Path::to_path_buf)
+        .map_err(|_| anyhow!("{} is outside the repository", path))
+}
+
+/// The checked-out branch, or None if HEAD is detached. Works on a branch
+/// without commits.
+fn current_branch(repo: &Repository) -> Option<String> {
+    let head = repo.find_reference("HEAD").ok()?;
+    head.symbolic_target()
+        .map(|target| target.trim_start_matches("refs/heads/").to_string())
+}
+
+/// The HEAD commit, or None on a branch without commits.
+pub(super) fn head_commit(repo: &Repository) -> Result<Option<Commit<'_>>> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_commit()?)),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn change_letter(status: Status, staged: bool) -> char {
+    let (new, deleted, renamed, typechange) = if staged {
+        (
+            Status::INDEX_NEW,
+            Status::INDEX_DELETED,
+            Status::INDEX_RENAMED,
+            Status::INDEX_TYPECHANGE,
+        )
+    } else {
+        (
+            Status::WT_NEW,
+            Status::WT_DELETED,
+            Status::WT_RENAMED,
+            Status::WT_TYPECHANGE,
+        )
+    };
+    if status.intersects(new) {
+        'A'
+    } else if status.intersects(deleted) {
+        'D'
+    } else if status.intersects(renamed) {
+        'R'
+    } else if status.intersects(typechange) {
+        'T'
+    } else {
+        'M'
+    }
+}
+
+fn read_status(repo: &Repository) -> Result<GitStatus> {
+    let mut status = GitStatus {
+        branch: current_branch(repo),
+        ..Default::default()
+    };
+
+    if let Some(branch) = status.branch.as_deref() {
+        if let Some((upstream, ahead, behind)) = upstream_info(repo, branch) {
+            status.upstream = Some(upstream);
+            status.ahead = ahead;
+            status.behind = behind;
+        }
+    }
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    let staged_flags = Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE;
+    let unstaged_flags =
+        Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE;
+
+    for entry in repo.statuses(Some(&mut options))?.iter() {
+        let flags = entry.status();
+        let path = entry.path().unwrap_or_default().to_string();
+        if flags.contains(Status::CONFLICTED) {
+            status.conflicted.push(path);
+            continue;
+        }
+        if flags.intersects(staged_flags) {
+            status.staged.push(StatusEntry {
+                path: path.clone(),
+                change: change_letter(flags, true),
+            });
+        }
+        if flags.intersects(unstaged_flags) {
+            status.unstaged.push(StatusEntry {
+                path: path.clone(),
+                change: change_letter(flags, false),
+            });
+        }
+        if flags.contains(Status::WT_NEW) {
+            status.untracked.push(path);
+        }
+    }
+    Ok(status)
+}
+
+/// Upstream branch name and the ahead/behind counts against it.
+fn upstream_info(repo: &Repository, branch: &str) -> Option<(String, usize, usize)> {
+    let local = repo.find_branch(branch, BranchType::Local).ok()?;
+    let upstream = local.upstream().ok()?;
+    let name = upstream.name().ok()??.to_string();
+    let (ahead, behind) = repo
+        .graph_ahead_behind(local.get().target()?, upstream.get().target()?)
+        .ok()?;
+    Some((name, ahead, behind))
+}
+
+fn diff(repo: &Repository, staged: bool, pathspec: Option<&Path>, stat: bool) -> Result<String> {
+    let mut options = DiffOptions::new();
+    if let Some(pathspec) = pathspec.filter(|p| !p.as_os_str().is_empty()) {
+        options.pathspec(pathspec);
+    }
+    let diff = if staged {
+        let head_tree = head_commit(repo)?.map(|c| c.tree()).transpose()?;
+        repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut options))?
+    } else {
+        repo.diff_index_to_workdir(None, Some(&mut options))?
+    };
+
+    let stats = diff.stats()?;
+    if stats.files_changed() == 0 {
+        return Ok(format!(
+            "No {} changes",
+            if staged { "staged" } else { "unstaged" }
+        ));
+    }
+    if stat {
+        let buf = stats.to_buf(DiffStatsFormat::FULL, 80)?;
+        return Ok(buf.as_str().unwrap_or_default().trim_end().to_string());
+    }
+
+    let mut patch = String::new();
+    diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin());
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+
+    if patch.len() > MAX_DIFF_BYTES {
+        let mut end = MAX_DIFF_BYTES;
+        while !patch.is_char_boundary(end) {
+            end -= 1;
+        }
+        patch.truncate(end);
+        patch.push_str(&format!(
+            "\n... (diff truncated; {} files changed, +{} -{}; use path or stat to narrow it)",
+            stats.files_changed(),
+            stats.insertions(),
+            stats.deletions()
+        ));
+    }
+    Ok(patch.trim_end().to_string())
+}
+
+fn commit(
+    repo: &Repository,
+    message: &str,
+    files: &[PathBuf],
+    all: bool,
+    protected_branches: &[String],
+) -> Result<String> {
+    let Some(branch) = current_branch(repo) else {
+        bail!("HEAD is detached; switch to a branch with git_branch before committing");
+    };
+    if protected_branches.contains(&branch) {
+        bail!(
+            "Branch '{}' is protected; create another branch with git_branch first",
+            branch
+        );
+    }
+
+    let mut index = repo.index()?;
+    if all {
+        index.update_all(["*"], None)?;
+    }
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("Repository has no working directory"))?;
+    for file in files {
+        if workdir.join(file).exists() {
+            index.add_path(file)?;
+        } else {
+            index.remove_path(file)?;
+        }
+    }
+    index.write()?;
+
+    if index.has_conflicts() {
+        bail!("The index has unresolved conflicts");
+    }
+
+    let parent = head_commit(repo)?;
+    let parent_tree = parent.as_ref().map(|c| c.tree()).transpose()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let unchanged = match &parent_tree {
+        Some(parent_tree) => parent_tree.id() == tree.id(),
+        None => index.is_empty(),
+    };
+    if unchanged {
+        bail!("Nothing to commit; pass files or all=true to stage changes");
+    }
+
+    let signature = repo.signature().map_err(|e| {
+        anyhow!(
+            "No git identity configured (set user.name and user.email): {}",
+            e.message()
+        )
+    })?;
+    let parents: Vec<&Commit> = parent.iter().collect();
+    let oid = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )?;
+
+    let stats = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?
+        .stats()?;
+    let short_id = repo.find_object(oid, None)?.short_id()?;
+    Ok(format!(
+        "✅ Committed {} on {}: {} ({} files changed, +{} -{})",
+        short_id.as_str().unwrap_or_default(),
+        branch,
+        message.lines().next().unwrap_or_default(),
+        stats.files_changed(),
+        stats.insertions(),
+        stats.deletions()
+    ))
+}
+
+fn list_branches(repo: &Repository) -> Result<String> {
+    let mut lines = Vec::new();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let name = branch.name()?.unwrap_or_default().to_string();
+        let commit = branch.get().peel_to_commit()?;
+        let short_id = commit.as_object().short_id()?;
+        lines.push(format!(
+            "{} {} {} {}",
+            if branch.is_head() { "*" } else { " " },
+            name,
+            short_id.as_str().unwrap_or_default(),
+            commit.summary().unwrap_or_default()
+        ));
+    }
+    if lines.is_empty() {
+        return Ok("No branches yet (no commits)".to_string());
+    }
+    Ok(lines.join("\n"))
+}
+
+fn create_branch(repo: &Repository, name: &str) -> Result<String> {
+    if repo.find_branch(name, BranchType::Local).is_ok() {
+        bail!("Branch '{}' already exists", name);
+    }
+    let Some(head) = head_commit(repo)? else {
+        bail!("Cannot create a branch before the first commit");
+    };
+    repo.branch(name, &head, false)?;
+    Ok(format!(
+        "✅ Created branch {} at {}",
+        name,
+        head.as_object().short_id()?.as_str().unwrap_or_default()
+    ))
+}
+
+fn switch_branch(repo: &Repository, name: &str) -> Result<String> {
+    let branch = repo
+        .find_branch(name, BranchType::Local)
+        .map_err(|_| anyhow!("No local branch named '{}'", name))?;
+    if branch.is_head() {
+        return Ok(format!("Already on {}", name));
+    }
+
+    let status = read_status(repo)?;
+    if !status.staged.is_empty() || !status.unstaged.is_empty() || !status.conflicted.is_empty() {
+        bail!("The working tree has uncommitted changes; commit them before switching branches");
+    }
+
+    let reference = branch
+        .get()
+        .name()
+        .ok_or_else(|| anyhow!("Branch name is not valid UTF-8"))?
+        .to_string();
+    let target = branch.get().peel(git2::ObjectType::Commit)?;
+    repo.checkout_tree(&target, Some(git2::build::CheckoutBuilder::new().safe()))?;
+    repo.set_head(&reference)?;
+    Ok(format!("✅ Switched to branch {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init_repo(dir: &Path) -> Repository {
+        let repo = Repository::init(dir).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "g3 test").unwrap();
+        config.set_str("user.email", "g3@example.com").unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_render_commit_message() {
+        let template = "{message}\n\nG3-Session: {session_id}";
+        assert_eq!(
+            render_commit_message(template, "Fix parser\n", Some("abc_123")),
+            "Fix parser\n\nG3-Session: abc_123\n"
+        );
+        assert_eq!(
+            render_commit_message(template, "Fix parser", None),
+            "Fix parser\n"
+        );
+    }
+
+    #[test]
+    fn test_open_repo_from_a_package_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        let package = dir.path().join("packages/api");
+        std::fs::create_dir_all(package.join("src")).unwrap();
+
+        let repo = open_repo(&package).unwrap();
+        assert_eq!(
+            repo_relative(&repo, &package, "src/lib.rs").unwrap(),
+            Path::new("packages/api/src/lib.rs")
+        );
+        assert!(repo_relative(&repo, &package, "../../..").is_err());
+    }
+
+    #[test]
+    fn test_commit_status_and_branches() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(dir.path());
+        std::fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+
+        let status = read_status(&repo).unwrap();
+        assert_eq!(status.untracked, vec!["a.txt"]);
+        assert!(commit(&repo, "Empty\n", &[], true, &[]).is_err());
+
+        let result = commit(&repo, "Add a\n", &[PathBuf::from("a.txt")], false, &[]).unwrap();
+        assert!(result.starts_with("✅ Committed"), "{}", result);
+        assert!(read_status(&repo).unwrap().is_clean());
+
+        std::fs::write(dir.path().join("a.txt"), "two\n").unwrap();
+        let patch = diff(&repo, false, None, false).unwrap();
+        assert!(patch.contains("-one\n+two"), "{}", patch);
+
+        let branch = current_branch(&repo).unwrap();
+        let protected = [branch.clone()];
+        assert!(commit(&repo, "Edit a\n", &[], true, &protected).is_err());
+
+        create_branch(&repo, "feature").unwrap();
+        assert!(
+            switch_branch(&repo, "feature").is_err(),
+            "dirty tree must block switching"
+        );
+        commit(&repo, "Edit a\n", &[], true, &[]).unwrap();
+        switch_branch(&repo, "feature").unwrap();
+        assert_eq!(current_branch(&repo).as_deref(), Some("feature"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\n"
+        );
+
+        let listing = list_branches(&repo).unwrap();
+        assert!(listing.contains("* feature"), "{}", listing);
+        assert!(listing.contains(&format!("  {}", branch)), "{}", listing);
+    }
+}
@@ -0,0 +1,401 @@
+//! Search tool: grep.
+//!
+//! Regex search over files using the same engine as ripgrep (the `grep` and
+//! `ignore` crates), so .gitignore is respected, binary files are skipped and
+//! the number of results is capped before anything reaches the context.
+
+use ::grep::regex::RegexMatcherBuilder;
+use ::grep::searcher::{
+    BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkMatch,
+};
+use anyhow::Result;
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Matches returned when `max_matches` is omitted.
+const DEFAULT_MAX_MATCHES: usize = 100;
+
+/// Largest accepted `max_matches`.
+const MAX_MATCHES_LIMIT: usize = 1000;
+
+/// Largest accepted `context_lines`.
+const MAX_CONTEXT_LINES: usize = 10;
+
+/// Lines longer than this (minified code, data files) are cut.
+const MAX_LINE_CHARS: usize = 300;
+
+/// Search parameters, parsed from the tool arguments.
+#[derive(Debug, Clone)]
+pub struct GrepOptions {
+    pub pattern: String,
+    pub path: PathBuf,
+    pub globs: Vec<String>,
+    pub case_insensitive: bool,
+    pub fixed_strings: bool,
+    pub context_lines: usize,
+    pub max_matches: usize,
+}
+
+/// A matching line with its surrounding context.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GrepMatch {
+    pub path: String,
+    pub line: u64,
+    pub text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+}
+
+/// Results of a search.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GrepResults {
+    pub matches: Vec<GrepMatch>,
+    pub files_searched: usize,
+    pub files_with_matches: usize,
+    /// True if the search stopped at `max_matches`
+    pub truncated: bool,
+}
+
+/// Execute the `grep` tool.
+pub async fn execute_grep<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing grep tool call");
+
+    let args = &tool_call.args;
+    let Some(pattern) = args.get("pattern").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing pattern argument".to_string());
+    };
+
+    let base = match ctx.call_dir(tool_call) {
+        Ok(base) => base,
+        Err(refusal) => return Ok(refusal),
+    };
+    let path = path_in(base, args.get("path").and_then(|v| v.as_str()));
+    if !path.exists() {
+        return Ok(format!("❌ Path not found: {}", path.display()));
+    }
+
+    let options = GrepOptions {
+        pattern: pattern.to_string(),
+        path,
+        globs: glob_args(args),
+        case_insensitive: bool_arg(args, "case_insensitive"),
+        fixed_strings: bool_arg(args, "fixed_strings"),
+        context_lines: args
+            .get("context_lines")
+            .and_then(|v| v.as_u64())
+            .map_or(0, |n| (n as usize).min(MAX_CONTEXT_LINES)),
+        max_matches: args
+            .get("max_matches")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_MAX_MATCHES, |n| {
+                (n as usize).clamp(1, MAX_MATCHES_LIMIT)
+            }),
+    };
+    let json = args.get("format").and_then(|v| v.as_str()) == Some("json");
+
+    let results = match tokio::task::spawn_blocking(move || search(&options)).await? {
+        Ok(results) => results,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+
+    if json {
+        return Ok(serde_json::to_string_pretty(&results)?);
+    }
+    Ok(format_results(&results))
+}
+
+pub(super) fn bool_arg(args: &serde_json::Value, key: &str) -> bool {
+    args.get(key).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// The `path` argument resolved against the workspace (default: the
+/// workspace itself).
+pub(super) fn search_path<W: UiWriter>(ctx: &ToolContext<'_, W>, path: Option<&str>) -> PathBuf {
+    path_in(ctx.workspace_dir(), path)
+}
+
+/// `path` with `~` expanded, relative to `base` unless absolute (default:
+/// `base` itself).
+pub(super) fn path_in(base: PathBuf, path: Option<&str>) -> PathBuf {
+    match path {
+        Some(path) => {
+            let path = PathBuf::from(shellexpand::tilde(path).as_ref());
+            if path.is_absolute() {
+                path
+            } else {
+                base.join(path)
+            }
+        }
+        None => base,
+    }
+}
+
+/// The `glob` argument: a single glob or a list of globs.
+pub(super) fn glob_args(args: &serde_json::Value) -> Vec<String> {
+    match args.get("glob") {
+        Some(serde_json::Value::String(glob)) => vec![glob.clone()],
+        Some(serde_json::Value::Array(globs)) => globs
+            .iter()
+            .filter_map(|g| g.as_str().map(String::from))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Run a search. Errors are invalid patterns or globs.
+pub fn search(options: &GrepOptions) -> Result<GrepResults> {
+    let matcher = RegexMatcherBuilder::new()
+        .case_insensitive(options.case_insensitive)
+        .fixed_strings(options.fixed_strings)
+        .line_terminator(Some(b'\n'))
+        .build(&options.pattern)
+        .map_err(|e| anyhow::anyhow!("Invalid pattern: {}", e))?;
+
+    let mut searcher = SearcherBuilder::new()
+        .line_number(true)
+        .before_context(options.context_lines)
+        .after_context(options.context_lines)
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .build();
+
+    let (root, walker) = walk_files(&options.path, &options.globs)?;
+    let mut results = GrepResults::default();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                debug!("grep: skipping unreadable entry: {}", e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+
+        let display_path = entry
+            .path()
+            .strip_prefix(&root)
+            .unwrap_or(entry.path())
+            .display()
+            .to_string();
+        let before = results.matches.len();
+        let mut sink = Collector {
+            path: &display_path,
+            results: &mut results,
+            max_matches: options.max_matches,
+            pending_before: Vec::new(),
+        };
+        if let Err(e) = searcher.search_path(&matcher, entry.path(), &mut sink) {
+            debug!("grep: failed to search {}: {}", entry.path().display(), e);
+        }
+        results.files_searched += 1;
+        if results.matches.len() > before {
+            results.files_with_matches += 1;
+        }
+        if results.truncated {
+            break;
+        }
+    }
+    Ok(results)
+}
+
+/// A gitignore-aware walk of `path` limited to `globs`, sorted by file name.
+/// Also returns the directory that paths should be shown relative to.
+pub(super) fn walk_files(path: &Path, globs: &[String]) -> Result<(PathBuf, ignore::Walk)> {
+    let root = if path.is_dir() {
+        path.to_path_buf()
+    } else {
+        path.parent().map(Path::to_path_buf).unwrap_or_default()
+    };
+    let mut overrides = OverrideBuilder::new(&root);
+    for glob in globs {
+        overrides
+            .add(glob)
+            .map_err(|e| anyhow::anyhow!("Invalid glob '{}': {}", glob, e))?;
+    }
+    let walker = WalkBuilder::new(path)
+        .overrides(overrides.build()?)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    Ok((root, walker))
+}
+
+/// Collects matches and context lines for one file.
+struct Collector<'a> {
+    path: &'a str,
+    results: &'a mut GrepResults,
+    max_matches: usize,
+    /// Context lines seen since the last match
+    pending_before: Vec<String>,
+}
+
+impl Sink for Collector<'_> {
+    type Error = std::io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        if self.results.matches.len() >= self.max_matches {
+            self.results.truncated = true;
+            return Ok(false);
+        }
+        self.results.matches.push(GrepMatch {
+            path: self.path.to_string(),
+            line: mat.line_number().unwrap_or(0),
+            text: line_text(mat.bytes()),
+            before: std::mem::take(&mut self.pending_before),
+            after: Vec::new(),
+        });
+        Ok(true)
+    }
+
+    fn context(
+        &mut self,
+        _searcher: &Searcher,
+        context: &SinkContext<'_>,
+    ) -> Result<bool, Self::Error> {
+        let text = line_text(context.bytes());
+        match context.kind() {
+            SinkContextKind::Before => self.pending_before.push(text),
+            SinkContextKind::After => {
+                if let Some(last) = self.results.matches.last_mut() {
+                    last.after.push(text);
+                }
+            }
+            SinkContextKind::Other => {}
+        }
+        Ok(true)
+    }
+}
+
+/// Decode a line, dropping the line terminator and cutting very long lines.
+fn line_text(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim_end_matches(['\n', '\r']);
+    if text.chars().count() > MAX_LINE_CHARS {
+        let cut: String = text.chars().take(MAX_LINE_CHARS).collect();
+        format!("{}... [line truncated]", cut)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Render results like ripgrep: `path:line:text` for matches,
+/// `path-line-text` for context, `--` between separate groups.
+fn format_results(results: &GrepResults) -> String {
+    if results.matches.is_empty() {
+        return format!("No matches ({} files searched)", results.files_searched);
+    }
+
+    let with_context = results
+        .matches
+        .iter()
+        .any(|m| !m.before.is_empty() || !m.after.is_empty());
+    let mut lines = Vec::new();
+    let mut previous_end: Option<(&str, u64)> = None;
+    for m in &results.matches {
+        let start = m.line - m.before.len() as u64;
+        if let Some((path, end)) = previous_end {
+            if with_context && (path != m.path || start > end + 1) {
+                lines.push("--".to_string());
+            }
+        }
+        for (i, text) in m.before.iter().enumerate() {
+            lines.push(format!("{}-{}-{}", m.path, start + i as u64, text));
+        }
+        lines.push(format!("{}:{}:{}", m.path, m.line, m.text));
+        for (i, text) in m.after.iter().enumerate() {
+            lines.push(format!("{}-{}-{}", m.path, m.line + 1 + i as u64, text));
+        }
+        previous_end = Some((m.path.as_str(), m.line + m.after.len() as u64));
+    }
+
+    lines.push(String::new());
+    lines.push(format!(
+        "{} matches in {} files ({} searched)",
+        results.matches.len(),
+        results.files_with_matches,
+        results.files_searched
+    ));
+    if results.truncated {
+        lines.push(
+            "Stopped at max_matches; narrow the pattern or path, or add a glob filter".to_string(),
+        );
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(path: &Path, pattern: &str) -> GrepOptions {
+        GrepOptions {
+            pattern: pattern.to_string(),
+            path: path.to_path_buf(),
+            globs: Vec::new(),
+            case_insensitive: false,
+            fixed_strings: false,
+            context_lines: 0,
+            max_matches: DEFAULT_MAX_MATCHES,
+        }
+    }
+
+    #[test]
+    fn test_search_with_context_and_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "one\nfn main() {}\nthree\n").unwrap();
+        std::fs::write(dir.path().join("b.txt"), "fn main() in text\n").unwrap();
+        std::fs::write(dir.path().join("bin.dat"), b"fn main\0binary").unwrap();
+
+        let mut opts = options(dir.path(), r"fn \w+");
+        opts.context_lines = 1;
+        opts.globs = vec!["*.rs".to_string()];
+        let results = search(&opts).unwrap();
+        assert_eq!(
+            results.matches,
+            vec![GrepMatch {
+                path: "a.rs".to_string(),
+                line: 2,
+                text: "fn main() {}".to_string(),
+                before: vec!["one".to_string()],
+                after: vec!["three".to_string()],
+            }]
+        );
+        assert_eq!(
+            format_results(&results).lines().take(3).collect::<Vec<_>>(),
+            vec!["a.rs-1-one", "a.rs:2:fn main() {}", "a.rs-3-three"]
+        );
+
+        // Without globs the text file matches too; the binary file never does
+        let results = search(&options(dir.path(), "fn main")).unwrap();
+        assert_eq!(results.files_with_matches, 2);
+        assert!(results.matches.iter().all(|m| m.path != "bin.dat"));
+    }
+
+    #[test]
+    fn test_search_caps_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("many.txt"), "hit\n".repeat(50)).unwrap();
+
+        let mut opts = options(dir.path(), "HIT");
+        opts.case_insensitive = true;
+        opts.max_matches = 10;
+        let results = search(&opts).unwrap();
+        assert_eq!(results.matches.len(), 10);
+        assert!(results.truncated);
+
+        assert!(search(&options(dir.path(), "(unclosed")).is_err());
+    }
+}
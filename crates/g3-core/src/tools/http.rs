@@ -0,0 +1,380 @@
+//! The http_request tool: HTTP calls without curl one-liners in the shell.
+//!
+//! Credentials come from `[http.auth]` in the config, matched by host, so
+//! tokens never appear in tool calls or the conversation. A request carrying
+//! them only follows redirects on the same host, so they never reach another
+//! server. JSON response bodies are pretty-printed, and bodies are cut at
+//! `max_response_bytes`.
+
+use anyhow::Result;
+use g3_config::HttpAuth;
+use reqwest::header::HeaderMap;
+use reqwest::redirect::Policy;
+use reqwest::{Method, RequestBuilder, StatusCode, Url};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+const METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD"];
+
+/// Redirects followed before giving up, as reqwest does by default.
+const MAX_REDIRECTS: usize = 10;
+
+/// Execute the `http_request` tool.
+pub async fn execute_http_request<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing http_request tool call");
+
+    let args = &tool_call.args;
+    let Some(url) = args.get("url").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing url argument".to_string());
+    };
+    let url = match Url::parse(url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => url,
+        Ok(_) => return Ok("❌ Only http and https URLs are supported".to_string()),
+        Err(e) => return Ok(format!("❌ Invalid URL '{}': {}", url, e)),
+    };
+    let method = args
+        .get("method")
+        .and_then(|v| v.as_str())
+        .unwrap_or("GET")
+        .to_uppercase();
+    if !METHODS.contains(&method.as_str()) {
+        return Ok(format!(
+            "❌ Unsupported method '{}' (expected one of {})",
+            method,
+            METHODS.join(", ")
+        ));
+    }
+    let method = Method::from_bytes(method.as_bytes())?;
+    let headers: Vec<(String, String)> = args
+        .get("headers")
+        .and_then(|v| v.as_object())
+        .map(|headers| {
+            headers
+                .iter()
+                .map(|(name, value)| {
+                    let value = value
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| value.to_string());
+                    (name.clone(), value)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let include_headers = args
+        .get("include_headers")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let config = &ctx.config.http;
+    let timeout = args
+        .get("timeout_secs")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(config.timeout_secs);
+    let auth = auth_for_url(&config.auth, &url);
+    let client = build_client(Duration::from_secs(timeout), auth.is_some().then_some(&url))?;
+
+    let mut request = client.request(method.clone(), url.clone());
+    if let Some((_, auth)) = &auth {
+        request = apply_auth(request, auth, &headers);
+    }
+    for (name, value) in &headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    match args.get("body") {
+        None | Some(serde_json::Value::Null) => {}
+        Some(serde_json::Value::String(body)) => request = request.body(body.clone()),
+        Some(json) => request = request.json(json),
+    }
+
+    let start = Instant::now();
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) if e.is_timeout() => {
+            return Ok(format!(
+                "❌ {} {} timed out after {}s",
+                method, url, timeout
+            ))
+        }
+        Err(e) => return Ok(format!("❌ {} {} failed: {}", method, url, e)),
+    };
+    let status = response.status();
+    let response_headers = response.headers().clone();
+    let body = match response.bytes().await {
+        Ok(body) => body,
+        Err(e) => return Ok(format!("❌ Failed to read the response body: {}", e)),
+    };
+
+    let mut output = format_status(status, &response_headers, body.len(), start.elapsed());
+    if let Some((host, _)) = &auth {
+        output.push_str(&format!("\n🔑 Sent configured credentials for {}", host));
+        if let Some(location) = status
+            .is_redirection()
+            .then(|| response_headers.get(reqwest::header::LOCATION))
+            .flatten()
+        {
+            output.push_str(&format!(
+                "\n↪️ Redirect to {} not followed: the credentials are only sent to {}",
+                location.to_str().unwrap_or("<binary>"),
+                host
+            ));
+        }
+    }
+    if include_headers {
+        for (name, value) in &response_headers {
+            output.push_str(&format!(
+                "\n{}: {}",
+                name,
+                value.to_str().unwrap_or("<binary>")
+            ));
+        }
+    }
+    if method != Method::HEAD && !body.is_empty() {
+        output.push_str("\n\n");
+        output.push_str(&format_body(
+            &body,
+            is_json(&response_headers),
+            config.max_response_bytes,
+        ));
+    }
+    Ok(output)
+}
+
+/// The client for a request. With `credentialed` set, it only follows
+/// redirects that stay on that URL's host and port; others are returned as
+/// the response, so configured credentials never reach another server.
+fn build_client(
+    timeout: Duration,
+    credentialed: Option<&Url>,
+) -> reqwest::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().timeout(timeout);
+    if let Some(origin) = credentialed {
+        let host = origin.host_str().map(str::to_string);
+        let port = origin.port_or_known_default();
+        builder = builder.redirect(Policy::custom(move |attempt| {
+            let url = attempt.url();
+            if url.host_str() != host.as_deref() || url.port_or_known_default() != port {
+                attempt.stop()
+            } else if attempt.previous().len() > MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        }));
+    }
+    builder.build()
+}
+
+/// Credentials configured for the URL's `host:port` or, failing that, host.
+fn auth_for_url<'a>(
+    auth: &'a HashMap<String, HttpAuth>,
+    url: &Url,
+) -> Option<(String, &'a HttpAuth)> {
+    let host = url.host_str()?;
+    let with_port = url.port().map(|port| format!("{}:{}", host, port));
+    with_port
+        .into_iter()
+        .chain(std::iter::once(host.to_string()))
+        .find_map(|key| auth.get(&key).map(|a| (key, a)))
+}
+
+/// Add `auth` to the request. Headers the call sets itself take precedence.
+fn apply_auth(
+    mut request: RequestBuilder,
+    auth: &HttpAuth,
+    explicit_headers: &[(String, String)],
+) -> RequestBuilder {
+    let is_set = |name: &str| {
+        explicit_headers
+            .iter()
+            .any(|(n, _)| n.eq_ignore_ascii_case(name))
+    };
+    if !is_set("authorization") {
+        if let Some(token) = &auth.bearer_token {
+            request = request.bearer_auth(expand_env(token));
+        } else if let Some(username) = &auth.username {
+            request = request.basic_auth(
+                expand_env(username),
+                auth.password.as_deref().map(expand_env),
+            );
+        }
+    }
+    for (name, value) in &auth.headers {
+        if !is_set(name) {
+            request = request.header(name.as_str(), expand_env(value));
+        }
+    }
+    request
+}
+
+/// Expand `$VAR` / `${VAR}`; values referencing unset variables are kept as is.
+fn expand_env(value: &str) -> String {
+    shellexpand::env(value)
+        .map(|v| v.into_owned())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("json"))
+}
+
+/// `HTTP 200 OK (123 ms, 456 bytes, application/json)`, flagged when the
+/// status is an error.
+fn format_status(status: StatusCode, headers: &HeaderMap, len: usize, elapsed: Duration) -> String {
+    let mut details = vec![
+        format!("{} ms", elapsed.as_millis()),
+        format!("{} bytes", len),
+    ];
+    if let Some(content_type) = headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+    {
+        details.push(content_type.to_string());
+    }
+    format!(
+        "{}HTTP {} ({})",
+        if status.is_client_error() || status.is_server_error() {
+            "⚠️ "
+        } else {
+            ""
+        },
+        status,
+        details.join(", ")
+    )
+}
+
+/// The body as text, pretty-printed if it's JSON, cut at `max_bytes`.
+fn format_body(body: &[u8], json: bool, max_bytes: usize) -> String {
+    let Ok(text) = std::str::from_utf8(body) else {
+        return format!("(binary body, {} bytes)", body.len());
+    };
+    let mut text = if json {
+        serde_json::from_str::<serde_json::Value>(text)
+            .ok()
+            .and_then(|v| serde_json::to_string_pretty(&v).ok())
+            .unwrap_or_else(|| text.to_string())
+    } else {
+        text.to_string()
+    };
+
+    if text.len() > max_bytes {
+        let total = text.len();
+        let mut end = max_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str(&format!(
+            "\n... (truncated: showing first {} of {} bytes)",
+            end, total
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_for_url() {
+        let mut auth = HashMap::new();
+        auth.insert("api.example.com".to_string(), HttpAuth::default());
+        auth.insert("localhost:8080".to_string(), HttpAuth::default());
+
+        let host = |url: &str| auth_for_url(&auth, &Url::parse(url).unwrap()).map(|(h, _)| h);
+        assert_eq!(
+            host("https://api.example.com/v1/users").as_deref(),
+            Some("api.example.com")
+        );
+        assert_eq!(
+            host("http://localhost:8080/health").as_deref(),
+            Some("localhost:8080")
+        );
+        assert_eq!(host("http://localhost:9090/health"), None);
+        assert_eq!(host("https://example.com/"), None);
+    }
+
+    /// Serve on a local port, answering the n-th request with a redirect to
+    /// `location(port, n)`. Returns the URL to start at and the requests seen.
+    async fn redirecting_server(
+        location: fn(u16, usize) -> String,
+    ) -> (Url, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let requests = seen.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let count = {
+                    let mut requests = requests.lock().unwrap();
+                    requests.push(String::from_utf8_lossy(&buf[..n]).to_string());
+                    requests.len()
+                };
+                let response = format!(
+                    "HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    location(port, count)
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        (
+            Url::parse(&format!("http://127.0.0.1:{}/start", port)).unwrap(),
+            seen,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_credentials_stay_on_their_host() {
+        // 127.0.0.1 redirects to localhost: the same server, another host
+        let (url, seen) =
+            redirecting_server(|port, _| format!("http://localhost:{}/next", port)).await;
+        let client = build_client(Duration::from_secs(5), Some(&url)).unwrap();
+        let response = client
+            .get(url.clone())
+            .header("PRIVATE-TOKEN", "secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(seen.lock().unwrap().len(), 1);
+
+        // Redirects on the same host are followed, up to the limit
+        let (url, seen) =
+            redirecting_server(|port, n| format!("http://127.0.0.1:{}/{}", port, n)).await;
+        let client = build_client(Duration::from_secs(5), Some(&url)).unwrap();
+        assert!(client.get(url).send().await.is_err());
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), MAX_REDIRECTS + 1);
+        assert!(seen[1].starts_with("GET /1 "));
+    }
+
+    #[test]
+    fn test_format_body() {
+        assert_eq!(format_body(br#"{"id":1}"#, true, 1000), "{\n  \"id\": 1\n}");
+        assert_eq!(format_body(b"plain", false, 1000), "plain");
+        assert_eq!(
+            format_body(&[0xff, 0xfe], false, 1000),
+            "(binary body, 2 bytes)"
+        );
+
+        let truncated = format_body("héllo wörld".as_bytes(), false, 2);
+        assert!(truncated.starts_with("h\n... (truncated: showing first 1 of 13 bytes)"));
+    }
+}
@@ -0,0 +1,645 @@
+//! Internationalization tool: i18n_extract.
+//!
+//! Scans changed (or explicitly listed) source files for hard-coded,
+//! user-facing string literals and proposes keys for the project's i18n
+//! resource file. The tool only proposes entries; the agent applies them
+//! with the regular file editing tools.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::utils::{list_changed_files, repo_root};
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Maximum number of candidate strings reported in a single call.
+const MAX_CANDIDATES: usize = 200;
+
+/// Source file extensions that are scanned for literals.
+const SCANNED_EXTENSIONS: &[&str] = &[
+    "rs", "js", "jsx", "ts", "tsx", "vue", "svelte", "py", "go", "java", "kt", "swift", "dart",
+];
+
+/// Well-known locations of default-locale resource files, checked in order.
+const RESOURCE_CANDIDATES: &[&str] = &[
+    "locales/en.json",
+    "locales/en/translation.json",
+    "public/locales/en/translation.json",
+    "src/locales/en.json",
+    "src/i18n/en.json",
+    "i18n/en.json",
+    "assets/i18n/en.json",
+    "locales/en.yml",
+    "config/locales/en.yml",
+    "locales/en-US/main.ftl",
+    "i18n/en/main.ftl",
+    "app/src/main/res/values/strings.xml",
+    "res/values/strings.xml",
+];
+
+/// Markers indicating that a line already goes through an i18n function.
+const I18N_MARKERS: &[&str] = &[
+    "i18n",
+    "tr!(",
+    "fl!(",
+    "gettext",
+    "_(\"",
+    "_('",
+    "intl.",
+    "NSLocalizedString",
+    "getString(",
+    "R.string.",
+];
+
+/// Markers for lines whose literals are not user-facing (logging, asserts).
+const NON_UI_MARKERS: &[&str] = &[
+    "debug!(",
+    "trace!(",
+    "info!(",
+    "warn!(",
+    "error!(",
+    "log::",
+    "console.log",
+    "console.debug",
+    "logger.",
+    "logging.",
+    "require(",
+    "assert",
+    "expect(",
+];
+
+/// Line prefixes for imports and module declarations.
+const NON_UI_PREFIXES: &[&str] = &["import ", "from ", "use ", "package ", "mod "];
+
+/// Format of the project's i18n resource file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResourceFormat {
+    Json,
+    Yaml,
+    Fluent,
+    AndroidXml,
+}
+
+impl ResourceFormat {
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yml") | Some("yaml") => ResourceFormat::Yaml,
+            Some("ftl") => ResourceFormat::Fluent,
+            Some("xml") => ResourceFormat::AndroidXml,
+            _ => ResourceFormat::Json,
+        }
+    }
+
+    /// Render a single resource entry in this format; `value` is the text
+    /// itself, with the source literal's escapes already resolved.
+    fn render_entry(&self, key: &str, value: &str) -> String {
+        match self {
+            ResourceFormat::Json => format!(
+                "  {}: {}",
+                serde_json::Value::String(key.to_string()),
+                serde_json::Value::String(value.to_string())
+            ),
+            ResourceFormat::Yaml => format!("  {}: {:?}", key, value),
+            ResourceFormat::Fluent => format!("{} = {}", key.replace('.', "-"), value),
+            ResourceFormat::AndroidXml => format!(
+                "    <string name=\"{}\">{}</string>",
+                key.replace('.', "_"),
+                value
+                    .replace('\\', "\\\\")
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;")
+                    .replace('"', "\\\"")
+                    .replace('\'', "\\'")
+            ),
+        }
+    }
+}
+
+/// A hard-coded string found in a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Candidate {
+    file: String,
+    line: usize,
+    text: String,
+    key: String,
+}
+
+/// Execute the `i18n_extract` tool.
+pub async fn execute_i18n_extract<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing i18n_extract tool call");
+
+    if !ctx.config.i18n.enabled {
+        return Ok(
+            "❌ The i18n_extract tool is not enabled; set i18n.enabled = true in the config"
+                .to_string(),
+        );
+    }
+    let base = match ctx.call_dir(tool_call) {
+        Ok(base) => base,
+        Err(refusal) => return Ok(refusal),
+    };
+
+    let explicit_paths: Vec<String> = tool_call
+        .args
+        .get("paths")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Changed files are listed relative to the repository root, explicit
+    // paths relative to the working directory
+    let (files_base, files) = if explicit_paths.is_empty() {
+        let root = repo_root(base.to_str())
+            .await
+            .unwrap_or_else(|| base.clone());
+        (root, list_changed_files(base.to_str()).await)
+    } else {
+        (base.clone(), explicit_paths)
+    };
+
+    let files: Vec<String> = files.into_iter().filter(|f| is_scanned_file(f)).collect();
+    if files.is_empty() {
+        return Ok("✅ No changed source files to scan for hard-coded strings".to_string());
+    }
+
+    let resource_path = match tool_call.args.get("resource_file").and_then(|v| v.as_str()) {
+        Some(path) => Some(base.join(path)),
+        None => detect_resource_file(&base),
+    };
+    let resource_content = resource_path
+        .as_ref()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .unwrap_or_default();
+    let format = resource_path
+        .as_deref()
+        .map(ResourceFormat::from_path)
+        .unwrap_or(ResourceFormat::Json);
+
+    let key_prefix = tool_call.args.get("key_prefix").and_then(|v| v.as_str());
+
+    let mut candidates = Vec::new();
+    for file in &files {
+        let content = match std::fs::read_to_string(files_base.join(file)) {
+            Ok(c) => c,
+            Err(e) => {
+                debug!("Skipping {}: {}", file, e);
+                continue;
+            }
+        };
+        candidates.extend(scan_source(file, &content, key_prefix));
+        if candidates.len() >= MAX_CANDIDATES {
+            candidates.truncate(MAX_CANDIDATES);
+            break;
+        }
+    }
+
+    Ok(format_report(
+        &files,
+        &candidates,
+        resource_path.as_deref(),
+        &resource_content,
+        format,
+    ))
+}
+
+/// Whether a file has an extension we know how to scan.
+fn is_scanned_file(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|ext| SCANNED_EXTENSIONS.contains(&ext))
+        .unwrap_or(false)
+}
+
+/// Find the project's default-locale resource file, if any.
+fn detect_resource_file(base: &Path) -> Option<PathBuf> {
+    RESOURCE_CANDIDATES
+        .iter()
+        .map(|candidate| base.join(candidate))
+        .find(|path| path.is_file())
+}
+
+/// Scan a source file and return user-facing string candidates.
+fn scan_source(file: &str, content: &str, key_prefix: Option<&str>) -> Vec<Candidate> {
+    let allow_single_quotes = !file.ends_with(".rs");
+    let namespace = key_prefix.map(String::from).unwrap_or_else(|| {
+        Path::new(file)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(slugify)
+            .unwrap_or_else(|| "app".to_string())
+    });
+
+    let mut candidates = Vec::new();
+    let mut test_item: Option<TestItem> = None;
+
+    for (idx, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let mut item_text = trimmed;
+        if let Some(rest) = trimmed.strip_prefix("#[cfg(test)]") {
+            test_item = Some(TestItem::default());
+            item_text = rest;
+        }
+        if let Some(item) = &mut test_item {
+            if item.ends_on(item_text) {
+                test_item = None;
+            }
+            continue;
+        }
+        if is_comment_line(trimmed) {
+            continue;
+        }
+        if I18N_MARKERS.iter().any(|m| line.contains(m))
+            || calls_translate_fn(line)
+            || NON_UI_MARKERS.iter().any(|m| trimmed.contains(m))
+            || NON_UI_PREFIXES.iter().any(|p| trimmed.starts_with(p))
+        {
+            continue;
+        }
+
+        for literal in extract_string_literals(line, allow_single_quotes) {
+            if is_user_facing(&literal) {
+                candidates.push(Candidate {
+                    file: file.to_string(),
+                    line: idx + 1,
+                    key: format!("{}.{}", namespace, slugify(&unescape_literal(&literal))),
+                    text: literal,
+                });
+            }
+        }
+    }
+
+    candidates
+}
+
+/// The item under a `#[cfg(test)]`, skipped by `scan_source`: a `use` or
+/// `mod tests;` up to its `;`, a function or module up to its closing brace.
+#[derive(Default)]
+struct TestItem {
+    depth: usize,
+    opened: bool,
+}
+
+impl TestItem {
+    /// Follow the item through `line`; returns whether it ends there.
+    fn ends_on(&mut self, line: &str) -> bool {
+        let mut in_string = false;
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if in_string => {
+                    chars.next();
+                }
+                '"' => in_string = !in_string,
+                _ if in_string => {}
+                '{' => {
+                    self.depth += 1;
+                    self.opened = true;
+                }
+                '}' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.opened && self.depth == 0 {
+                        return true;
+                    }
+                }
+                ';' if !self.opened => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+/// Whether the line calls a bare `t(...)` translate function (i18next, vue-i18n).
+fn calls_translate_fn(line: &str) -> bool {
+    line.match_indices("t(").any(|(idx, _)| {
+        line[..idx]
+            .chars()
+            .next_back()
+            .map(|c| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(true)
+    })
+}
+
+fn is_comment_line(trimmed: &str) -> bool {
+    ["//", "#", "/*", "*", "--", "<!--"]
+        .iter()
+        .any(|prefix| trimmed.starts_with(prefix))
+}
+
+/// Extract the contents of quoted string literals from a single line.
+///
+/// Handles backslash escapes; literals that are not closed on the same line
+/// are ignored.
+fn extract_string_literals(line: &str, allow_single_quotes: bool) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        let quote = match c {
+            '"' => '"',
+            '\'' if allow_single_quotes => '\'',
+            _ => continue,
+        };
+
+        let mut literal = String::new();
+        let mut closed = false;
+        while let Some(inner) = chars.next() {
+            match inner {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        literal.push('\\');
+                        literal.push(escaped);
+                    }
+                }
+                q if q == quote => {
+                    closed = true;
+                    break;
+                }
+                other => literal.push(other),
+            }
+        }
+
+        if closed {
+            literals.push(literal);
+        }
+    }
+
+    literals
+}
+
+/// The text of a literal as `extract_string_literals` returns it, with its
+/// common backslash escapes resolved; unknown escapes (`\u{..}`, `\x41`) are
+/// kept as written.
+fn unescape_literal(literal: &str) -> String {
+    let mut text = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some('0') => text.push('\0'),
+            Some(escaped @ ('\\' | '"' | '\'')) => text.push(escaped),
+            Some(other) => {
+                text.push('\\');
+                text.push(other);
+            }
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+/// Heuristic: does this literal look like text shown to a user?
+///
+/// Accepts multi-word phrases and single capitalised words ("Cancel"), and
+/// rejects identifiers, paths, URLs, format-only strings, and constants.
+fn is_user_facing(text: &str) -> bool {
+    let trimmed = text.trim();
+    let letters = trimmed.chars().filter(|c| c.is_alphabetic()).count();
+    if trimmed.chars().count() < 3 || letters < 2 {
+        return false;
+    }
+    if trimmed.contains("://")
+        || trimmed.starts_with('/')
+        || trimmed.starts_with("./")
+        || trimmed.starts_with('.')
+        || trimmed.starts_with('-')
+        || (trimmed.starts_with('{') && trimmed.ends_with('}'))
+    {
+        return false;
+    }
+
+    let words: Vec<&str> = trimmed.split_whitespace().collect();
+    if words.len() >= 2 {
+        // Multi-word text must contain at least one plain word
+        return words
+            .iter()
+            .any(|w| w.chars().all(|c| c.is_alphabetic() || ",.!?:'".contains(c)));
+    }
+
+    // Single word: must be a capitalised word, not an ALL_CAPS constant or identifier
+    let word = trimmed.trim_end_matches(['.', '!', '?', ':']);
+    let mut word_chars = word.chars();
+    let starts_upper = word_chars.next().map(|c| c.is_uppercase()).unwrap_or(false);
+    starts_upper
+        && word.chars().all(|c| c.is_alphabetic())
+        && word.chars().any(|c| c.is_lowercase())
+}
+
+/// Turn text into a lowercase snake_case key fragment (at most five words).
+fn slugify(text: &str) -> String {
+    let slug = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .take(5)
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    if slug.is_empty() {
+        "text".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Build the tool output: findings per file plus a resource-file snippet.
+fn format_report(
+    files: &[String],
+    candidates: &[Candidate],
+    resource_path: Option<&Path>,
+    resource_content: &str,
+    format: ResourceFormat,
+) -> String {
+    if candidates.is_empty() {
+        return format!(
+            "✅ No hard-coded user-facing strings found in {} file(s)",
+            files.len()
+        );
+    }
+
+    let mut output = format!(
+        "🌐 Found {} hard-coded string(s) in {} file(s)\n\n",
+        candidates.len(),
+        files.len()
+    );
+
+    let mut new_entries = Vec::new();
+    for candidate in candidates {
+        let existing = resource_content.contains(&format!("\"{}\"", candidate.text))
+            || resource_content.contains(&format!("= {}", candidate.text));
+        let status = if existing {
+            " (value already in resources)"
+        } else {
+            ""
+        };
+        output.push_str(&format!(
+            "{}:{} \"{}\" → {}{}\n",
+            candidate.file, candidate.line, candidate.text, candidate.key, status
+        ));
+        if !existing
+            && !new_entries
+                .iter()
+                .any(|(k, _): &(String, String)| k == &candidate.key)
+        {
+            new_entries.push((candidate.key.clone(), unescape_literal(&candidate.text)));
+        }
+    }
+
+    if new_entries.is_empty() {
+        return output;
+    }
+
+    match resource_path {
+        Some(path) => output.push_str(&format!("\nProposed additions to {}:\n", path.display())),
+        None => output.push_str(
+            "\nNo i18n resource file found; proposed entries for a new locales/en.json:\n",
+        ),
+    }
+    for (key, value) in &new_entries {
+        output.push_str(&format.render_entry(key, value));
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_string_literals() {
+        let line = r#"let msg = format!("Hello \"{}\"", name); let x = 'c';"#;
+        assert_eq!(
+            extract_string_literals(line, false),
+            vec![r#"Hello \"{}\""#.to_string()]
+        );
+        assert_eq!(
+            extract_string_literals("label: 'Save changes'", true),
+            vec!["Save changes".to_string()]
+        );
+        assert!(extract_string_literals("\"unterminated", false).is_empty());
+    }
+
+    #[test]
+    fn test_is_user_facing() {
+        assert!(is_user_facing("Save changes"));
+        assert!(is_user_facing("Cancel"));
+        assert!(is_user_facing("Are you sure you want to delete {}?"));
+
+        assert!(!is_user_facing("user_id"));
+        assert!(!is_user_facing("CONTENT_TYPE"));
+        assert!(!is_user_facing("https://example.com/a b"));
+        assert!(!is_user_facing("./src/main.rs"));
+        assert!(!is_user_facing("{}"));
+        assert!(!is_user_facing("ok"));
+    }
+
+    #[test]
+    fn test_scan_source_skips_logging_and_comments() {
+        let source = r#"
+// "Commented out text"
+debug!("Loading settings now");
+let title = "Account settings";
+let label = t("already.translated");
+print("Printed message")
+"#;
+        let candidates = scan_source("src/settings.rs", source, None);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].text, "Account settings");
+        assert_eq!(candidates[0].line, 4);
+        assert_eq!(candidates[0].key, "settings.account_settings");
+        assert_eq!(candidates[1].text, "Printed message");
+    }
+
+    #[test]
+    fn test_scan_source_skips_test_module() {
+        let source = "let a = \"Visible text\";\n#[cfg(test)]\nlet b = \"Test only text\";\n";
+        let candidates = scan_source("lib.rs", source, Some("core"));
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].key, "core.visible_text");
+    }
+
+    #[test]
+    fn test_scan_source_resumes_after_test_items() {
+        let source = r#"#[cfg(test)]
+use crate::fixtures;
+
+fn greeting() -> &'static str {
+    "Welcome back"
+}
+
+#[cfg(test)]
+#[allow(dead_code)]
+fn sample() -> String {
+    String::from("{ Sample text")
+}
+
+fn farewell() -> &'static str {
+    "See you soon"
+}
+
+#[cfg(test)] fn inline() { let _ = "Inline sample"; }
+const TITLE: &str = "Project settings";
+"#;
+        let candidates = scan_source("lib.rs", source, Some("app"));
+        let texts: Vec<&str> = candidates.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["Welcome back", "See you soon", "Project settings"]
+        );
+    }
+
+    #[test]
+    fn test_render_entry_formats() {
+        assert_eq!(
+            ResourceFormat::Json.render_entry("a.b", "Hi \"there\""),
+            r#"  "a.b": "Hi \"there\"""#
+        );
+        assert_eq!(ResourceFormat::Fluent.render_entry("a.b", "Hi"), "a-b = Hi");
+        assert_eq!(
+            ResourceFormat::AndroidXml.render_entry("a.b", "Tom & Jerry"),
+            "    <string name=\"a_b\">Tom &amp; Jerry</string>"
+        );
+        assert_eq!(
+            ResourceFormat::AndroidXml.render_entry("a.b", "Say \"hi\""),
+            r#"    <string name="a_b">Say \"hi\"</string>"#
+        );
+    }
+
+    #[test]
+    fn test_escaped_literals_are_proposed_once_escaped() {
+        let source = r#"let prompt = "Type \"yes\" to continue\n";"#;
+        let candidates = scan_source("src/confirm.rs", source, Some("confirm"));
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(
+            unescape_literal(&candidates[0].text),
+            "Type \"yes\" to continue\n"
+        );
+
+        let files = ["src/confirm.rs".to_string()];
+        let report = format_report(&files, &candidates, None, "", ResourceFormat::Json);
+        assert!(
+            report.contains(r#"  "confirm.type_yes_to_continue": "Type \"yes\" to continue\n""#),
+            "{report}"
+        );
+        assert_eq!(unescape_literal(r"\u{e9} \\ \'"), r"\u{e9} \ '");
+    }
+}
@@ -0,0 +1,730 @@
+//! Kubernetes tool: k8s.
+//!
+//! Wraps the kubectl operations an agent needs most: `get` (summarized from
+//! `-o json` into one row per object: status, readiness, restarts, age),
+//! `describe`, `logs` and `apply`. Every call is confined to the
+//! namespaces in `k8s.allowed_namespaces` when that is set. `apply` shows
+//! the `kubectl diff` first and, with `k8s.confirm_apply`, only proceeds
+//! once the user agrees.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use g3_config::K8sConfig;
+use serde::Serialize;
+use serde_json::Value;
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::{bool_arg, search_path};
+
+const OPERATIONS: &[&str] = &["get", "describe", "logs", "apply"];
+
+/// Log lines fetched unless `tail` is given.
+const DEFAULT_LOG_TAIL: u64 = 200;
+
+/// Longer describe, logs and diff output is cut to its last lines.
+const MAX_OUTPUT_LINES: usize = 300;
+
+/// Arguments passed on to kubectl, which must not be taken for its flags.
+const VALUE_ARGS: &[&str] = &[
+    "namespace",
+    "resource",
+    "name",
+    "selector",
+    "container",
+    "since",
+];
+
+/// Where a call looks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    /// kubectl's default namespace for the context
+    Default,
+    Namespace(String),
+    AllNamespaces,
+}
+
+impl Scope {
+    fn args(&self) -> Vec<String> {
+        match self {
+            Self::Default => Vec::new(),
+            Self::Namespace(ns) => vec!["--namespace".to_string(), ns.clone()],
+            Self::AllNamespaces => vec!["--all-namespaces".to_string()],
+        }
+    }
+}
+
+/// One object from `kubectl get`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResourceRow {
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    pub name: String,
+    pub status: String,
+    /// Ready containers or replicas, e.g. "2/3"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ready: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restarts: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age: Option<String>,
+    /// Kind-specific extras: node, service ports, ...
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+/// Execute the `k8s` tool.
+pub async fn execute_k8s<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing k8s tool call");
+
+    let args = &tool_call.args;
+    let config = &ctx.config.k8s;
+    if !config.enabled {
+        return Ok(
+            "❌ The k8s tool is not enabled; set k8s.enabled = true in the config".to_string(),
+        );
+    }
+    let operation = args
+        .get("operation")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    if !OPERATIONS.contains(&operation) {
+        return Ok(format!(
+            "❌ Unknown operation '{}' (expected one of {})",
+            operation,
+            OPERATIONS.join(", ")
+        ));
+    }
+    if let Err(e) = check_values(args) {
+        return Ok(format!("❌ {}", e));
+    }
+    let all_namespaces = operation == "get" && bool_arg(args, "all_namespaces");
+    let scope = match resolve_scope(
+        config,
+        args.get("namespace").and_then(|v| v.as_str()),
+        all_namespaces,
+    ) {
+        Ok(scope) => scope,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    if operation == "apply" {
+        return apply(tool_call, ctx, &scope).await;
+    }
+    let kubectl_args = match command_args(operation, args) {
+        Ok(kubectl_args) => kubectl_args,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+
+    match operation {
+        "get" => {
+            let output = match run(config, &scope, &kubectl_args).await {
+                Ok(output) => output,
+                Err(e) => return Ok(format!("❌ {}", e)),
+            };
+            let list: Value = match serde_json::from_str(&output) {
+                Ok(list) => list,
+                Err(e) => return Ok(format!("❌ Unexpected kubectl output: {}", e)),
+            };
+            let rows = summarize(&list, Utc::now());
+            if args.get("format").and_then(|v| v.as_str()) == Some("json") {
+                return Ok(serde_json::to_string_pretty(&rows)?);
+            }
+            Ok(format_rows(&rows, scope == Scope::AllNamespaces))
+        }
+        "describe" => match run(config, &scope, &kubectl_args).await {
+            Ok(output) => Ok(tail(&output, MAX_OUTPUT_LINES)),
+            Err(e) => Ok(format!("❌ {}", e)),
+        },
+        _ => match run(config, &scope, &kubectl_args).await {
+            Ok(output) if output.trim().is_empty() => Ok("(no log output)".to_string()),
+            Ok(output) => Ok(tail(&output, MAX_OUTPUT_LINES)),
+            Err(e) => Ok(format!("❌ {}", e)),
+        },
+    }
+}
+
+/// Refuse values kubectl would read as flags, like a name of
+/// `--kubeconfig=/tmp/other`.
+pub fn check_values(args: &Value) -> Result<(), String> {
+    for key in VALUE_ARGS {
+        if let Some(value) = args.get(key).and_then(|v| v.as_str()) {
+            if value.starts_with('-') {
+                return Err(format!("{} '{}' can't start with '-'", key, value));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The kubectl arguments for `get`, `describe` or `logs`: the flags, then
+/// `--` and the resource and name.
+pub fn command_args(operation: &str, args: &Value) -> Result<Vec<String>, String> {
+    let resource = args.get("resource").and_then(|v| v.as_str());
+    let name = args.get("name").and_then(|v| v.as_str());
+    let selector = args.get("selector").and_then(|v| v.as_str());
+
+    let mut kubectl_args = vec![operation.to_string()];
+    let mut positional = Vec::new();
+    match operation {
+        "get" | "describe" => {
+            let Some(resource) = resource else {
+                return Err(if operation == "get" {
+                    "Missing resource argument (e.g. pods, deployments)".to_string()
+                } else {
+                    "Missing resource argument (e.g. pod, deployment/web)".to_string()
+                });
+            };
+            positional.push(resource.to_string());
+            positional.extend(name.map(String::from));
+            kubectl_args.extend(selector_args(selector));
+            if operation == "get" {
+                kubectl_args.extend(["--output".to_string(), "json".to_string()]);
+            }
+        }
+        _ => {
+            match (name, selector) {
+                (Some(name), _) => positional.push(match resource {
+                    Some(resource) if !name.contains('/') => format!("{}/{}", resource, name),
+                    _ => name.to_string(),
+                }),
+                (None, Some(selector)) => {
+                    kubectl_args.extend(selector_args(Some(selector)));
+                    kubectl_args.push("--prefix".to_string());
+                }
+                (None, None) => {
+                    return Err(
+                        "logs needs a name (pod or e.g. deployment/web) or a selector".to_string(),
+                    )
+                }
+            }
+            let tail_lines = args
+                .get("tail")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(DEFAULT_LOG_TAIL);
+            kubectl_args.push(format!("--tail={}", tail_lines));
+            if let Some(container) = args.get("container").and_then(|v| v.as_str()) {
+                kubectl_args.extend(["--container".to_string(), container.to_string()]);
+            }
+            if let Some(since) = args.get("since").and_then(|v| v.as_str()) {
+                kubectl_args.push(format!("--since={}", since));
+            }
+            if bool_arg(args, "previous") {
+                kubectl_args.push("--previous".to_string());
+            }
+        }
+    }
+    if !positional.is_empty() {
+        kubectl_args.push("--".to_string());
+        kubectl_args.extend(positional);
+    }
+    Ok(kubectl_args)
+}
+
+/// `apply`: show the diff, confirm, then apply.
+async fn apply<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+    scope: &Scope,
+) -> Result<String> {
+    let config = &ctx.config.k8s;
+    let Some(manifest) = tool_call.args.get("manifest").and_then(|v| v.as_str()) else {
+        return Ok("❌ apply needs a manifest (file or directory of YAML)".to_string());
+    };
+    let path = search_path(ctx, Some(manifest));
+    if !path.exists() {
+        return Ok(format!("❌ Manifest not found: {}", path.display()));
+    }
+    let file_args = vec!["--filename".to_string(), path.display().to_string()];
+
+    // kubectl diff exits 1 when there are differences
+    let mut diff_args = vec!["diff".to_string()];
+    diff_args.extend(file_args.iter().cloned());
+    let diff = match run_raw(config, scope, &diff_args).await {
+        Ok(output) if output.status.success() => {
+            return Ok(format!(
+                "✅ No changes: the cluster already matches {}",
+                manifest
+            ))
+        }
+        Ok(output) if output.status.code() == Some(1) => {
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+        Ok(output) => return Ok(format!("❌ kubectl diff failed: {}", stderr_of(&output))),
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    let diff = tail(&diff, MAX_OUTPUT_LINES);
+
+    if config.confirm_apply {
+        if ctx.is_autonomous {
+            return Ok(format!(
+                "❌ apply needs the user's confirmation, which isn't available in autonomous mode (set k8s.confirm_apply = false to allow it). Pending changes:\n{}",
+                diff
+            ));
+        }
+        ctx.ui_writer.println(&diff);
+        let target = match scope {
+            Scope::Namespace(ns) => format!("namespace {}", ns),
+            _ => "the default namespace".to_string(),
+        };
+        let context = config.context.as_deref().unwrap_or("the current context");
+        if !ctx
+            .ui_writer
+            .prompt_user_yes_no(&format!("Apply {} to {} in {}?", manifest, target, context))
+        {
+            return Ok(format!("❌ The user declined to apply {}", manifest));
+        }
+    }
+
+    let mut apply_args = vec!["apply".to_string()];
+    apply_args.extend(file_args);
+    match run(config, scope, &apply_args).await {
+        Ok(output) => Ok(format!(
+            "✅ Applied {}\n{}\n\nDiff:\n{}",
+            manifest,
+            output.trim_end(),
+            diff
+        )),
+        Err(e) => Ok(format!("❌ {}", e)),
+    }
+}
+
+/// The scope for a call, enforcing `allowed_namespaces`.
+pub fn resolve_scope(
+    config: &K8sConfig,
+    requested: Option<&str>,
+    all_namespaces: bool,
+) -> Result<Scope, String> {
+    let allowed = &config.allowed_namespaces;
+    if all_namespaces {
+        if !allowed.is_empty() {
+            return Err(format!(
+                "all_namespaces isn't available while k8s.allowed_namespaces is set (allowed: {})",
+                allowed.join(", ")
+            ));
+        }
+        return Ok(Scope::AllNamespaces);
+    }
+    let namespace = requested
+        .map(String::from)
+        .or_else(|| config.namespace.clone())
+        .or_else(|| allowed.first().cloned());
+    match namespace {
+        Some(ns) if !allowed.is_empty() && !allowed.contains(&ns) => Err(format!(
+            "Namespace '{}' is not allowed (allowed: {})",
+            ns,
+            allowed.join(", ")
+        )),
+        Some(ns) => Ok(Scope::Namespace(ns)),
+        None => Ok(Scope::Default),
+    }
+}
+
+fn selector_args(selector: Option<&str>) -> Vec<String> {
+    selector
+        .map(|s| vec!["--selector".to_string(), s.to_string()])
+        .unwrap_or_default()
+}
+
+/// Run kubectl and return its stdout, or its stderr as the error.
+async fn run(config: &K8sConfig, scope: &Scope, args: &[String]) -> Result<String, String> {
+    let output = run_raw(config, scope, args).await?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!(
+            "kubectl {} failed: {}",
+            args[0],
+            stderr_of(&output)
+        ))
+    }
+}
+
+async fn run_raw(config: &K8sConfig, scope: &Scope, args: &[String]) -> Result<Output, String> {
+    let mut command = tokio::process::Command::new(&config.kubectl);
+    if let Some(context) = &config.context {
+        command.args(["--context", context]);
+    }
+    debug!("Running {} {:?} ({:?})", config.kubectl, args, scope);
+    let child = command
+        .args(scope.args())
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", config.kubectl, e))?;
+    match tokio::time::timeout(
+        Duration::from_secs(config.timeout_secs),
+        child.wait_with_output(),
+    )
+    .await
+    {
+        Ok(output) => output.map_err(|e| e.to_string()),
+        Err(_) => Err(format!(
+            "kubectl {} timed out after {}s",
+            args[0], config.timeout_secs
+        )),
+    }
+}
+
+fn stderr_of(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).trim().to_string()
+}
+
+/// The last `max` lines of `text`, noting how many were cut.
+pub(super) fn tail(text: &str, max: usize) -> String {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    if lines.len() <= max {
+        return lines.join("\n");
+    }
+    format!(
+        "... ({} earlier lines not shown)\n{}",
+        lines.len() - max,
+        lines[lines.len() - max..].join("\n")
+    )
+}
+
+/// One row per object in a `kubectl get -o json` result.
+pub fn summarize(list: &Value, now: DateTime<Utc>) -> Vec<ResourceRow> {
+    let items = match list.get("items").and_then(|v| v.as_array()) {
+        Some(items) => items.iter().collect(),
+        None => vec![list],
+    };
+    items
+        .into_iter()
+        .map(|item| summarize_item(item, now))
+        .collect()
+}
+
+fn summarize_item(item: &Value, now: DateTime<Utc>) -> ResourceRow {
+    let str_at = |pointer: &str| item.pointer(pointer).and_then(|v| v.as_str());
+    let u64_at = |pointer: &str| item.pointer(pointer).and_then(|v| v.as_u64());
+    let kind = str_at("/kind").unwrap_or("Object").to_string();
+    let mut row = ResourceRow {
+        kind: kind.clone(),
+        namespace: str_at("/metadata/namespace").map(String::from),
+        name: str_at("/metadata/name").unwrap_or_default().to_string(),
+        status: str_at("/status/phase").unwrap_or("-").to_string(),
+        ready: None,
+        restarts: None,
+        age: str_at("/metadata/creationTimestamp")
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| format_age(now.signed_duration_since(t).num_seconds())),
+        details: None,
+    };
+
+    match kind.as_str() {
+        "Pod" => {
+            let statuses: Vec<&Value> = item
+                .pointer("/status/containerStatuses")
+                .and_then(|v| v.as_array())
+                .map(|s| s.iter().collect())
+                .unwrap_or_default();
+            let ready = statuses
+                .iter()
+                .filter(|s| s.get("ready").and_then(|v| v.as_bool()) == Some(true))
+                .count();
+            row.ready = Some(format!("{}/{}", ready, statuses.len()));
+            row.restarts = Some(
+                statuses
+                    .iter()
+                    .filter_map(|s| s.get("restartCount").and_then(|v| v.as_u64()))
+                    .sum(),
+            );
+            // A waiting or terminated container says more than the phase
+            if let Some(reason) = statuses.iter().find_map(|s| {
+                s.pointer("/state/waiting/reason")
+                    .or_else(|| s.pointer("/state/terminated/reason"))
+                    .and_then(|v| v.as_str())
+            }) {
+                row.status = reason.to_string();
+            }
+            if item.pointer("/metadata/deletionTimestamp").is_some() {
+                row.status = "Terminating".to_string();
+            }
+            row.details = str_at("/spec/nodeName").map(|node| format!("node {}", node));
+        }
+        "Deployment" | "StatefulSet" | "ReplicaSet" | "DaemonSet" => {
+            let (desired, ready) = if kind == "DaemonSet" {
+                (
+                    u64_at("/status/desiredNumberScheduled").unwrap_or(0),
+                    u64_at("/status/numberReady").unwrap_or(0),
+                )
+            } else {
+                (
+                    u64_at("/spec/replicas").unwrap_or(1),
+                    u64_at("/status/readyReplicas").unwrap_or(0),
+                )
+            };
+            row.ready = Some(format!("{}/{}", ready, desired));
+            row.status = if ready >= desired {
+                "Ready"
+            } else {
+                "NotReady"
+            }
+            .to_string();
+            if let Some(updated) = u64_at("/status/updatedReplicas") {
+                row.details = Some(format!("{} up-to-date", updated));
+            }
+        }
+        "Service" => {
+            row.status = str_at("/spec/type").unwrap_or("ClusterIP").to_string();
+            let ports: Vec<String> = item
+                .pointer("/spec/ports")
+                .and_then(|v| v.as_array())
+                .map(|ports| {
+                    ports
+                        .iter()
+                        .map(|p| {
+                            let port = p.get("port").and_then(|v| v.as_u64()).unwrap_or(0);
+                            let protocol =
+                                p.get("protocol").and_then(|v| v.as_str()).unwrap_or("TCP");
+                            match p.get("targetPort") {
+                                Some(target) if target.as_u64() != Some(port) => {
+                                    let target =
+                                        target.as_str().map_or(target.to_string(), String::from);
+                                    format!("{}→{}/{}", port, target, protocol)
+                                }
+                                _ => format!("{}/{}", port, protocol),
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let ip = str_at("/spec/clusterIP").unwrap_or("-");
+            row.details = Some(format!("{} {}", ip, ports.join(",")).trim_end().to_string());
+        }
+        "Job" => {
+            let succeeded = u64_at("/status/succeeded").unwrap_or(0);
+            let completions = u64_at("/spec/completions").unwrap_or(1);
+            row.ready = Some(format!("{}/{}", succeeded, completions));
+            row.status = if u64_at("/status/failed").unwrap_or(0) > 0 {
+                "Failed"
+            } else if succeeded >= completions {
+                "Complete"
+            } else {
+                "Running"
+            }
+            .to_string();
+        }
+        "Node" => {
+            let ready = item
+                .pointer("/status/conditions")
+                .and_then(|v| v.as_array())
+                .and_then(|c| {
+                    c.iter()
+                        .find(|c| c.get("type").and_then(|v| v.as_str()) == Some("Ready"))
+                })
+                .and_then(|c| c.get("status").and_then(|v| v.as_str()));
+            row.status = if ready == Some("True") {
+                "Ready"
+            } else {
+                "NotReady"
+            }
+            .to_string();
+            row.details = str_at("/status/nodeInfo/kubeletVersion").map(String::from);
+        }
+        _ => {}
+    }
+    row
+}
+
+fn format_age(secs: i64) -> String {
+    match secs.max(0) {
+        s if s < 120 => format!("{}s", s),
+        s if s < 7200 => format!("{}m", s / 60),
+        s if s < 172800 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    }
+}
+
+/// Aligned columns, like `kubectl get` itself prints.
+fn format_rows(rows: &[ResourceRow], show_namespace: bool) -> String {
+    if rows.is_empty() {
+        return "No resources found".to_string();
+    }
+    let mut table: Vec<Vec<String>> = vec![[
+        "NAMESPACE",
+        "NAME",
+        "STATUS",
+        "READY",
+        "RESTARTS",
+        "AGE",
+        "DETAILS",
+    ]
+    .iter()
+    .map(|h| h.to_string())
+    .collect()];
+    for row in rows {
+        let name = if rows.iter().any(|r| r.kind != row.kind) {
+            format!("{}/{}", row.kind.to_lowercase(), row.name)
+        } else {
+            row.name.clone()
+        };
+        table.push(vec![
+            row.namespace.clone().unwrap_or_default(),
+            name,
+            row.status.clone(),
+            row.ready.clone().unwrap_or_default(),
+            row.restarts.map(|r| r.to_string()).unwrap_or_default(),
+            row.age.clone().unwrap_or_default(),
+            row.details.clone().unwrap_or_default(),
+        ]);
+    }
+    // Drop the namespace column unless listing across namespaces, and
+    // columns no row fills
+    let keep: Vec<usize> = (0..table[0].len())
+        .filter(|&i| (i != 0 || show_namespace) && table[1..].iter().any(|r| !r[i].is_empty()))
+        .collect();
+    let widths: Vec<usize> = keep
+        .iter()
+        .map(|&i| {
+            table
+                .iter()
+                .map(|r| r[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    table
+        .iter()
+        .map(|r| {
+            keep.iter()
+                .zip(&widths)
+                .map(|(&i, width)| format!("{:<width$}", r[i], width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_scope() {
+        let mut config = K8sConfig::default();
+        assert_eq!(resolve_scope(&config, None, false), Ok(Scope::Default));
+        assert_eq!(resolve_scope(&config, None, true), Ok(Scope::AllNamespaces));
+
+        config.allowed_namespaces = vec!["web".to_string(), "jobs".to_string()];
+        assert_eq!(
+            resolve_scope(&config, None, false),
+            Ok(Scope::Namespace("web".to_string()))
+        );
+        assert_eq!(
+            resolve_scope(&config, Some("jobs"), false),
+            Ok(Scope::Namespace("jobs".to_string()))
+        );
+        assert!(resolve_scope(&config, Some("kube-system"), false).is_err());
+        assert!(resolve_scope(&config, None, true).is_err());
+    }
+
+    #[test]
+    fn test_command_args() {
+        let argv = |operation: &str, args: Value| {
+            command_args(operation, &args).map(|argv| argv.join(" "))
+        };
+        assert_eq!(
+            argv(
+                "get",
+                json!({"resource": "pods", "name": "web-1", "selector": "app=web"})
+            ),
+            Ok("get --selector app=web --output json -- pods web-1".to_string())
+        );
+        assert_eq!(
+            argv("describe", json!({"resource": "deployment/web"})),
+            Ok("describe -- deployment/web".to_string())
+        );
+        assert_eq!(
+            argv(
+                "logs",
+                json!({"resource": "deployment", "name": "web", "container": "app", "tail": 20})
+            ),
+            Ok("logs --tail=20 --container app -- deployment/web".to_string())
+        );
+        assert_eq!(
+            argv("logs", json!({"selector": "app=web"})),
+            Ok("logs --selector app=web --prefix --tail=200".to_string())
+        );
+        assert!(argv("get", json!({})).is_err());
+        assert!(argv("logs", json!({"resource": "pods"})).is_err());
+    }
+
+    #[test]
+    fn test_values_that_look_like_flags_are_rejected() {
+        for key in VALUE_ARGS {
+            let mut args = json!({});
+            args[*key] = json!("--kubeconfig=/tmp/other");
+            assert_eq!(
+                check_values(&args),
+                Err(format!(
+                    "{} '--kubeconfig=/tmp/other' can't start with '-'",
+                    key
+                )),
+            );
+        }
+        assert_eq!(
+            check_values(&json!({"resource": "pods", "name": "web-1", "since": "1h"})),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_summarize_pods() {
+        let list = serde_json::json!({
+            "kind": "List",
+            "items": [
+                {
+                    "kind": "Pod",
+                    "metadata": {"name": "web-1", "namespace": "web", "creationTimestamp": "2024-01-01T00:00:00Z"},
+                    "spec": {"nodeName": "node-a"},
+                    "status": {
+                        "phase": "Running",
+                        "containerStatuses": [
+                            {"ready": true, "restartCount": 0, "state": {"running": {}}},
+                            {"ready": false, "restartCount": 7, "state": {"waiting": {"reason": "CrashLoopBackOff"}}}
+                        ]
+                    }
+                }
+            ]
+        });
+        let now = DateTime::parse_from_rfc3339("2024-01-01T03:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let rows = summarize(&list, now);
+        assert_eq!(
+            rows,
+            [ResourceRow {
+                kind: "Pod".to_string(),
+                namespace: Some("web".to_string()),
+                name: "web-1".to_string(),
+                status: "CrashLoopBackOff".to_string(),
+                ready: Some("1/2".to_string()),
+                restarts: Some(7),
+                age: Some("3h".to_string()),
+                details: Some("node node-a".to_string()),
+            }]
+        );
+        assert_eq!(
+            format_rows(&rows, false),
+            "NAME   STATUS            READY  RESTARTS  AGE  DETAILS\n\
+             web-1  CrashLoopBackOff  1/2    7         3h   node node-a"
+        );
+    }
+}
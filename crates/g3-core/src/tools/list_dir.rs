@@ -0,0 +1,275 @@
+//! Directory listing tool: list_dir.
+//!
+//! Renders a depth-limited tree of a directory with file sizes. Inside a git
+//! repository the file list comes from `git ls-files`, so anything matched by
+//! .gitignore is left out; elsewhere well-known dependency and build
+//! directories (node_modules, target, ...) are skipped.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::debug;
+use walkdir::WalkDir;
+
+use crate::build_metrics::format_bytes;
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::path_in;
+
+/// Depth used when the `depth` argument is omitted.
+const DEFAULT_DEPTH: usize = 2;
+
+/// Largest accepted `depth`.
+const MAX_DEPTH: usize = 10;
+
+/// Maximum number of files collected for the tree.
+const MAX_FILES: usize = 50_000;
+
+/// Maximum number of entries rendered.
+const MAX_LINES: usize = 400;
+
+/// Directories skipped outside git repositories (unless `include_ignored`).
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "__pycache__",
+    ".venv",
+    "venv",
+    ".tox",
+    ".mypy_cache",
+    ".pytest_cache",
+    ".next",
+    ".gradle",
+    ".idea",
+];
+
+/// A directory in the rendered tree.
+#[derive(Debug, Default)]
+struct DirNode {
+    dirs: BTreeMap<String, DirNode>,
+    files: BTreeMap<String, u64>,
+}
+
+impl DirNode {
+    /// Add a file, creating its parent directories.
+    fn insert(&mut self, relative: &Path, size: u64) {
+        let mut node = self;
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            let name = component.as_os_str().to_string_lossy().into_owned();
+            if components.peek().is_none() {
+                node.files.insert(name, size);
+            } else {
+                node = node.dirs.entry(name).or_default();
+            }
+        }
+    }
+
+    /// Number of files and their total size below this directory.
+    fn totals(&self) -> (usize, u64) {
+        self.dirs.values().map(DirNode::totals).fold(
+            (self.files.len(), self.files.values().sum()),
+            |(count, size), (c, s)| (count + c, size + s),
+        )
+    }
+
+    /// Render directories first, then files. Directories at `max_depth` are
+    /// shown collapsed with their totals. Returns false if `MAX_LINES` was hit.
+    fn render(&self, level: usize, max_depth: usize, lines: &mut Vec<String>) -> bool {
+        let indent = "  ".repeat(level - 1);
+        for (name, dir) in &self.dirs {
+            if lines.len() >= MAX_LINES {
+                return false;
+            }
+            let (count, size) = dir.totals();
+            lines.push(format!("{}{}/ ({})", indent, name, describe(count, size)));
+            if level < max_depth && !dir.render(level + 1, max_depth, lines) {
+                return false;
+            }
+        }
+        for (name, size) in &self.files {
+            if lines.len() >= MAX_LINES {
+                return false;
+            }
+            lines.push(format!("{}{} ({})", indent, name, format_bytes(*size)));
+        }
+        true
+    }
+}
+
+/// Execute the `list_dir` tool.
+pub async fn execute_list_dir<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing list_dir tool call");
+
+    let args = &tool_call.args;
+    let base = match ctx.call_dir(tool_call) {
+        Ok(base) => base,
+        Err(refusal) => return Ok(refusal),
+    };
+    let dir = path_in(base, args.get("path").and_then(|v| v.as_str()));
+    let depth = args
+        .get("depth")
+        .and_then(|v| v.as_u64())
+        .map(|d| (d as usize).clamp(1, MAX_DEPTH))
+        .unwrap_or(DEFAULT_DEPTH);
+    let include_ignored = args
+        .get("include_ignored")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    if !dir.is_dir() {
+        return Ok(format!("❌ Not a directory: {}", dir.display()));
+    }
+
+    let git_files = if include_ignored {
+        None
+    } else {
+        git_listed_files(&dir).await
+    };
+    let (files, source) = match git_files {
+        Some(files) => (files, "gitignored files excluded"),
+        None if include_ignored => (walk_files(&dir, true), "including ignored files"),
+        None => (
+            walk_files(&dir, false),
+            "dependency/build directories skipped",
+        ),
+    };
+
+    let mut root = DirNode::default();
+    for (relative, size) in &files {
+        root.insert(relative, *size);
+    }
+    let (count, size) = root.totals();
+
+    let mut lines = vec![format!(
+        "📁 {} ({}; {})",
+        dir.display(),
+        describe(count, size),
+        source
+    )];
+    let complete = root.render(1, depth, &mut lines);
+    if !complete {
+        lines.push(format!(
+            "... (truncated at {} entries; list a subdirectory or use a smaller depth)",
+            MAX_LINES
+        ));
+    }
+    if files.len() >= MAX_FILES {
+        lines.push(format!("... (stopped after {} files)", MAX_FILES));
+    }
+
+    Ok(lines.join("\n"))
+}
+
+/// "N files, SIZE" for a directory summary.
+fn describe(count: usize, size: u64) -> String {
+    let noun = if count == 1 { "file" } else { "files" };
+    format!("{} {}, {}", count, noun, format_bytes(size))
+}
+
+/// Files under `dir` that git tracks or would track, relative to `dir`.
+/// Returns None if `dir` is not inside a git repository.
+async fn git_listed_files(dir: &Path) -> Option<Vec<(PathBuf, u64)>> {
+    let output = Command::new("git")
+        .args([
+            "ls-files",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+            "-z",
+        ])
+        .current_dir(dir)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        debug!("git ls-files failed in {}", dir.display());
+        return None;
+    }
+
+    let files = output
+        .stdout
+        .split(|b| *b == 0)
+        .filter(|path| !path.is_empty())
+        .filter_map(|path| {
+            let relative = PathBuf::from(String::from_utf8_lossy(path).as_ref());
+            // Tracked files deleted from the working tree are still listed
+            let metadata = std::fs::metadata(dir.join(&relative)).ok()?;
+            metadata.is_file().then_some((relative, metadata.len()))
+        })
+        .take(MAX_FILES)
+        .collect();
+    Some(files)
+}
+
+/// Walk `dir` without git, skipping `.git` and (unless `include_ignored`)
+/// the well-known dependency and build directories.
+fn walk_files(dir: &Path, include_ignored: bool) -> Vec<(PathBuf, u64)> {
+    let skip = |entry: &walkdir::DirEntry| {
+        let name = entry.file_name().to_string_lossy();
+        entry.depth() > 0
+            && entry.file_type().is_dir()
+            && (name == ".git" || (!include_ignored && SKIPPED_DIRS.contains(&&*name)))
+    };
+
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| !skip(entry))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let size = entry.metadata().ok()?.len();
+            let relative = entry.path().strip_prefix(dir).ok()?.to_path_buf();
+            Some((relative, size))
+        })
+        .take(MAX_FILES)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_tree() {
+        let mut root = DirNode::default();
+        root.insert(Path::new("Cargo.toml"), 100);
+        root.insert(Path::new("src/lib.rs"), 2048);
+        root.insert(Path::new("src/tools/shell.rs"), 1024);
+
+        let mut lines = Vec::new();
+        assert!(root.render(1, 2, &mut lines));
+        assert_eq!(
+            lines,
+            vec![
+                "src/ (2 files, 3.0 KB)",
+                "  tools/ (1 file, 1.0 KB)",
+                "  lib.rs (2.0 KB)",
+                "Cargo.toml (100 B)",
+            ]
+        );
+
+        let mut lines = Vec::new();
+        assert!(root.render(1, 1, &mut lines));
+        assert_eq!(lines, vec!["src/ (2 files, 3.0 KB)", "Cargo.toml (100 B)"]);
+    }
+
+    #[test]
+    fn test_walk_skips_dependency_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("node_modules/left-pad")).unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("node_modules/left-pad/index.js"), "x").unwrap();
+        std::fs::write(dir.path().join("src/index.js"), "y").unwrap();
+
+        let files = walk_files(dir.path(), false);
+        assert_eq!(files, vec![(PathBuf::from("src/index.js"), 1)]);
+        assert_eq!(walk_files(dir.path(), true).len(), 2);
+    }
+}
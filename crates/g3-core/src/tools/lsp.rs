@@ -0,0 +1,604 @@
+//! Language server tool: rename_symbol.
+//!
+//! Renames a symbol through a language server configured under
+//! `[lsp.servers]` (rust-analyzer, pyright, typescript-language-server...),
+//! so only real references change, unlike a regex rename. The server is
+//! started for the call, waits until it has loaded the workspace, and the
+//! workspace edit it returns is applied here: text edits first, written
+//! all-or-nothing like `regex_replace`, then any file renames. Edits
+//! outside the working directory are refused.
+
+use anyhow::Result;
+use g3_config::{LspConfig, LspServerConfig};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::debug;
+use url::Url;
+
+use crate::lsp_client::{file_uri, LspClient};
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::file_ops::{write_edited_files, EditedFile};
+use super::grep::{bool_arg, search_path};
+
+/// Pause between rename attempts while the server is still loading.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Edited lines listed per file.
+const MAX_LISTED_LINES: usize = 10;
+
+/// A text edit with LSP positions: 0-based lines and UTF-16 columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub start: (u64, u64),
+    pub end: (u64, u64),
+    pub new_text: String,
+}
+
+/// One step of a workspace edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Edit { path: PathBuf, edits: Vec<TextEdit> },
+    Create { path: PathBuf },
+    Rename { from: PathBuf, to: PathBuf },
+    Delete { path: PathBuf },
+}
+
+/// Execute the `rename_symbol` tool.
+pub async fn execute_rename_symbol<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing rename_symbol tool call");
+
+    let args = &tool_call.args;
+    let config = &ctx.config.lsp;
+    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing path argument".to_string());
+    };
+    let Some(new_name) = args.get("new_name").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing new_name argument".to_string());
+    };
+    let Some(line) = args.get("line").and_then(|v| v.as_u64()).filter(|l| *l > 0) else {
+        return Ok("❌ Missing line argument (1-based)".to_string());
+    };
+    let symbol = args.get("symbol").and_then(|v| v.as_str());
+    let column = args
+        .get("column")
+        .and_then(|v| v.as_u64())
+        .filter(|c| *c > 0);
+    let dry_run = bool_arg(args, "dry_run");
+
+    let workspace = search_path(ctx, None);
+    let workspace = workspace.canonicalize().unwrap_or(workspace);
+    let file = match search_path(ctx, Some(path)).canonicalize() {
+        Ok(file) if file.starts_with(&workspace) => file,
+        Ok(file) => {
+            return Ok(format!(
+                "❌ {} is outside the working directory",
+                file.display()
+            ))
+        }
+        Err(e) => return Ok(format!("❌ {}: {}", path, e)),
+    };
+    let text = match std::fs::read_to_string(&file) {
+        Ok(text) => text,
+        Err(e) => return Ok(format!("❌ Failed to read {}: {}", path, e)),
+    };
+    let character = match symbol_position(&text, line, symbol, column) {
+        Ok(character) => character,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    let (server_name, server) =
+        match choose_server(config, args.get("server").and_then(|v| v.as_str()), &file) {
+            Ok(server) => server,
+            Err(e) => return Ok(format!("❌ {}", e)),
+        };
+
+    ctx.ui_writer.print_context_status(&format!(
+        "🔤 Asking {} to rename {} to {}...",
+        server_name,
+        symbol.unwrap_or("the symbol"),
+        new_name
+    ));
+    let position = json!({ "line": line - 1, "character": character });
+    let edit = match rename(
+        config,
+        server_name,
+        server,
+        &workspace,
+        &file,
+        &text,
+        position,
+        new_name,
+    )
+    .await
+    {
+        Ok(edit) => edit,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    let changes = match parse_workspace_edit(&edit) {
+        Ok(changes) if changes.is_empty() => {
+            return Ok(format!("❌ {} found nothing to rename", server_name))
+        }
+        Ok(changes) => changes,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    match apply_changes(&changes, &workspace, dry_run) {
+        Ok(summary) => Ok(format!(
+            "{} `{}` to `{}` with {}: {}",
+            if dry_run {
+                "🔍 Dry run (nothing was written): rename"
+            } else {
+                "✅ Renamed"
+            },
+            symbol.unwrap_or("symbol"),
+            new_name,
+            server_name,
+            summary
+        )),
+        Err(e) => Ok(format!("❌ {}", e)),
+    }
+}
+
+/// Run the rename request against a freshly started server.
+#[allow(clippy::too_many_arguments)]
+async fn rename(
+    config: &LspConfig,
+    server_name: &str,
+    server: &LspServerConfig,
+    workspace: &Path,
+    file: &Path,
+    text: &str,
+    position: Value,
+    new_name: &str,
+) -> Result<Value, String> {
+    let deadline = Instant::now() + Duration::from_secs(config.timeout_secs);
+    let mut client = LspClient::start(
+        server_name,
+        &server.command,
+        &server.args,
+        workspace,
+        deadline,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    let result = async {
+        let extension = file
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        client
+            .open(file, language_id(extension, server_name), text)
+            .await?;
+        client.wait_until_ready(deadline).await?;
+        let params = json!({
+            "textDocument": { "uri": file_uri(file)? },
+            "position": position,
+            "newName": new_name,
+        });
+        loop {
+            let response = client
+                .request("textDocument/rename", params.clone(), deadline)
+                .await?;
+            let retry = match &response {
+                Err(e) => e.is_retryable(),
+                Ok(edit) => edit.is_null() && client.is_loading(),
+            };
+            if retry && Instant::now() + RETRY_DELAY < deadline {
+                debug!("{} is still loading; retrying the rename", server_name);
+                tokio::time::sleep(RETRY_DELAY).await;
+                client.wait_until_ready(deadline).await?;
+                continue;
+            }
+            return Ok::<_, anyhow::Error>(response);
+        }
+    }
+    .await;
+    client.shutdown().await;
+    match result.map_err(|e| e.to_string())? {
+        Ok(Value::Null) => Err(format!(
+            "{} found no symbol to rename at that position",
+            server_name
+        )),
+        Ok(edit) => Ok(edit),
+        Err(e) => Err(format!("{}: {}", server_name, e.message)),
+    }
+}
+
+/// The server named by the call, or the one handling the file's extension.
+fn choose_server<'a>(
+    config: &'a LspConfig,
+    requested: Option<&str>,
+    file: &Path,
+) -> Result<(&'a str, &'a LspServerConfig), String> {
+    if config.servers.is_empty() {
+        return Err(
+            "No language servers are configured; add one under [lsp.servers] in the config"
+                .to_string(),
+        );
+    }
+    if let Some(name) = requested {
+        return config
+            .servers
+            .get_key_value(name)
+            .map(|(name, server)| (name.as_str(), server))
+            .ok_or_else(|| format!("Unknown language server '{}'", name));
+    }
+    let extension = file
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let mut servers: Vec<_> = config.servers.iter().collect();
+    servers.sort_by_key(|(name, _)| name.as_str());
+    servers
+        .into_iter()
+        .find(|(_, server)| server.extensions.iter().any(|e| e == extension))
+        .map(|(name, server)| (name.as_str(), server))
+        .ok_or_else(|| {
+            format!(
+                "No language server handles .{} files (configured: {})",
+                extension,
+                config
+                    .servers
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+/// LSP language identifier for a file extension.
+fn language_id<'a>(extension: &str, server_name: &'a str) -> &'a str {
+    match extension {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "typescriptreact",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "javascriptreact",
+        "go" => "go",
+        "java" => "java",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" => "cpp",
+        _ => server_name,
+    }
+}
+
+/// The UTF-16 column of the symbol on 1-based `line`: at the 1-based
+/// `column` if given, else at the first whole-word occurrence of `symbol`.
+pub fn symbol_position(
+    text: &str,
+    line: u64,
+    symbol: Option<&str>,
+    column: Option<u64>,
+) -> Result<u64, String> {
+    let Some(line_text) = text.lines().nth(line as usize - 1) else {
+        return Err(format!("Line {} is past the end of the file", line));
+    };
+    let byte = match (column, symbol) {
+        (Some(column), _) => line_text
+            .char_indices()
+            .nth(column as usize - 1)
+            .map(|(i, _)| i)
+            .ok_or_else(|| format!("Column {} is past the end of line {}", column, line))?,
+        (None, Some(symbol)) if !symbol.is_empty() => {
+            let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+            line_text
+                .match_indices(symbol)
+                .map(|(i, _)| i)
+                .find(|&i| {
+                    !line_text[..i].chars().next_back().is_some_and(is_word)
+                        && !line_text[i + symbol.len()..]
+                            .chars()
+                            .next()
+                            .is_some_and(is_word)
+                })
+                .ok_or_else(|| format!("'{}' does not appear on line {}", symbol, line))?
+        }
+        _ => return Err("Give the symbol's current name or its column".to_string()),
+    };
+    Ok(line_text[..byte].encode_utf16().count() as u64)
+}
+
+/// The steps of a WorkspaceEdit, from `documentChanges` or else `changes`.
+pub fn parse_workspace_edit(edit: &Value) -> Result<Vec<Change>, String> {
+    let path_of = |uri: Option<&Value>| -> Result<PathBuf, String> {
+        let uri = uri.and_then(|v| v.as_str()).unwrap_or_default();
+        Url::parse(uri)
+            .ok()
+            .and_then(|url| url.to_file_path().ok())
+            .ok_or_else(|| format!("Unsupported document URI '{}'", uri))
+    };
+    let mut changes = Vec::new();
+    if let Some(document_changes) = edit.get("documentChanges").and_then(|v| v.as_array()) {
+        for change in document_changes {
+            let change = match change.get("kind").and_then(|v| v.as_str()) {
+                None => Change::Edit {
+                    path: path_of(change.pointer("/textDocument/uri"))?,
+                    edits: parse_text_edits(change.get("edits"))?,
+                },
+                Some("create") => Change::Create {
+                    path: path_of(change.get("uri"))?,
+                },
+                Some("rename") => Change::Rename {
+                    from: path_of(change.get("oldUri"))?,
+                    to: path_of(change.get("newUri"))?,
+                },
+                Some("delete") => Change::Delete {
+                    path: path_of(change.get("uri"))?,
+                },
+                Some(kind) => return Err(format!("Unsupported workspace edit '{}'", kind)),
+            };
+            changes.push(change);
+        }
+    } else if let Some(map) = edit.get("changes").and_then(|v| v.as_object()) {
+        for (uri, edits) in map {
+            changes.push(Change::Edit {
+                path: path_of(Some(&Value::String(uri.clone())))?,
+                edits: parse_text_edits(Some(edits))?,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+fn parse_text_edits(edits: Option<&Value>) -> Result<Vec<TextEdit>, String> {
+    edits
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .map(|edit| parse_text_edit(edit).ok_or_else(|| format!("Malformed text edit: {}", edit)))
+        .collect()
+}
+
+fn parse_text_edit(edit: &Value) -> Option<TextEdit> {
+    let position = |pointer: &str| -> Option<(u64, u64)> {
+        let position = edit.pointer(pointer)?;
+        Some((
+            position.get("line")?.as_u64()?,
+            position.get("character")?.as_u64()?,
+        ))
+    };
+    Some(TextEdit {
+        start: position("/range/start")?,
+        end: position("/range/end")?,
+        new_text: edit.get("newText")?.as_str()?.to_string(),
+    })
+}
+
+/// Apply edits given against the original `text`. Inserts at the same
+/// position keep their order; overlapping edits are an error.
+pub fn apply_text_edits(text: &str, edits: &[TextEdit]) -> Result<String, String> {
+    let mut ranges: Vec<(usize, usize, &str)> = edits
+        .iter()
+        .map(|e| {
+            (
+                byte_offset(text, e.start),
+                byte_offset(text, e.end),
+                e.new_text.as_str(),
+            )
+        })
+        .collect();
+    // Stable, so same-position inserts stay in order
+    ranges.sort_by_key(|&(start, end, _)| (start, end));
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, end, new_text) in ranges {
+        if start < last || end < start {
+            return Err("The server returned overlapping edits".to_string());
+        }
+        result.push_str(&text[last..start]);
+        result.push_str(new_text);
+        last = end;
+    }
+    result.push_str(&text[last..]);
+    Ok(result)
+}
+
+/// Byte offset of an LSP position, clamped to the end of its line.
+fn byte_offset(text: &str, (line, character): (u64, u64)) -> usize {
+    let mut start = 0;
+    for _ in 0..line {
+        match text[start..].find('\n') {
+            Some(i) => start += i + 1,
+            None => return text.len(),
+        }
+    }
+    let rest = &text[start..];
+    let line_text = rest[..rest.find('\n').unwrap_or(rest.len())].trim_end_matches('\r');
+    let mut units = 0;
+    for (i, c) in line_text.char_indices() {
+        if units >= character {
+            return start + i;
+        }
+        units += c.len_utf16() as u64;
+    }
+    start + line_text.len()
+}
+
+/// Check, apply (unless `dry_run`) and summarize the changes.
+fn apply_changes(changes: &[Change], workspace: &Path, dry_run: bool) -> Result<String, String> {
+    let relative = |path: &Path| {
+        path.strip_prefix(workspace)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    for change in changes {
+        let paths: Vec<&PathBuf> = match change {
+            Change::Edit { path, .. } | Change::Create { path } | Change::Delete { path } => {
+                vec![path]
+            }
+            Change::Rename { from, to } => vec![from, to],
+        };
+        if let Some(outside) = paths.into_iter().find(|p| !p.starts_with(workspace)) {
+            return Err(format!(
+                "The rename would change {}, outside the working directory; nothing was changed",
+                outside.display()
+            ));
+        }
+    }
+
+    // Text edits for the same file are merged
+    let mut edits: BTreeMap<&PathBuf, Vec<TextEdit>> = BTreeMap::new();
+    for change in changes {
+        if let Change::Edit { path, edits: more } = change {
+            edits.entry(path).or_default().extend(more.iter().cloned());
+        }
+    }
+    let mut files = Vec::new();
+    let mut lines = Vec::new();
+    let mut total = 0;
+    for (path, file_edits) in &edits {
+        let original = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let content = apply_text_edits(&original, file_edits)?;
+        let mut edited_lines: Vec<u64> = file_edits.iter().map(|e| e.start.0 + 1).collect();
+        edited_lines.sort_unstable();
+        edited_lines.dedup();
+        let mut listed: Vec<String> = edited_lines
+            .iter()
+            .take(MAX_LISTED_LINES)
+            .map(|l| l.to_string())
+            .collect();
+        if edited_lines.len() > MAX_LISTED_LINES {
+            listed.push("...".to_string());
+        }
+        lines.push(format!(
+            "{}: {} edits (lines {})",
+            relative(path),
+            file_edits.len(),
+            listed.join(", ")
+        ));
+        total += file_edits.len();
+        files.push(EditedFile {
+            path: path.display().to_string(),
+            original,
+            content,
+            edits: file_edits.len(),
+            insertions: 0,
+            deletions: 0,
+        });
+    }
+    for change in changes {
+        match change {
+            Change::Create { path } => lines.push(format!("create {}", relative(path))),
+            Change::Rename { from, to } => {
+                lines.push(format!("rename {} → {}", relative(from), relative(to)))
+            }
+            Change::Delete { path } => lines.push(format!("delete {}", relative(path))),
+            Change::Edit { .. } => {}
+        }
+    }
+
+    if !dry_run {
+        write_edited_files(&files)?;
+        for change in changes {
+            let result = match change {
+                Change::Create { path } if !path.exists() => std::fs::write(path, ""),
+                Change::Rename { from, to } => std::fs::rename(from, to),
+                Change::Delete { path } if path.is_dir() => std::fs::remove_dir_all(path),
+                Change::Delete { path } => std::fs::remove_file(path),
+                _ => Ok(()),
+            };
+            result.map_err(|e| {
+                format!(
+                    "Text edits were written, but a file operation failed: {}",
+                    e
+                )
+            })?;
+        }
+    }
+    Ok(format!(
+        "{} edits in {} files\n{}",
+        total,
+        files.len(),
+        lines.join("\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_position_and_text_edits() {
+        let text = "let ñame = 1;\nlet café_count = café(2);\n";
+        // "é" is one UTF-16 unit, so café starts at column 17 on line 2
+        assert_eq!(symbol_position(text, 2, Some("café"), None), Ok(17));
+        assert_eq!(symbol_position(text, 2, None, Some(5)), Ok(4));
+        assert!(symbol_position(text, 1, Some("name"), None).is_err());
+
+        let edits = vec![
+            TextEdit {
+                start: (1, 17),
+                end: (1, 21),
+                new_text: "coffee".to_string(),
+            },
+            TextEdit {
+                start: (0, 4),
+                end: (0, 4),
+                new_text: "a".to_string(),
+            },
+            TextEdit {
+                start: (0, 4),
+                end: (0, 4),
+                new_text: "b".to_string(),
+            },
+        ];
+        assert_eq!(
+            apply_text_edits(text, &edits).unwrap(),
+            "let abñame = 1;\nlet café_count = coffee(2);\n"
+        );
+        let overlapping = vec![
+            edits[0].clone(),
+            TextEdit {
+                start: (1, 18),
+                ..edits[0].clone()
+            },
+        ];
+        assert!(apply_text_edits(text, &overlapping).is_err());
+    }
+
+    #[test]
+    fn test_parse_workspace_edit() {
+        let edit = json!({
+            "documentChanges": [
+                {
+                    "textDocument": { "uri": "file:///ws/src/old.rs", "version": 1 },
+                    "edits": [{
+                        "range": { "start": { "line": 0, "character": 7 }, "end": { "line": 0, "character": 10 } },
+                        "newText": "new"
+                    }]
+                },
+                { "kind": "rename", "oldUri": "file:///ws/src/old.rs", "newUri": "file:///ws/src/new.rs" }
+            ]
+        });
+        let changes = parse_workspace_edit(&edit).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                Change::Edit {
+                    path: PathBuf::from("/ws/src/old.rs"),
+                    edits: vec![TextEdit {
+                        start: (0, 7),
+                        end: (0, 10),
+                        new_text: "new".to_string(),
+                    }],
+                },
+                Change::Rename {
+                    from: PathBuf::from("/ws/src/old.rs"),
+                    to: PathBuf::from("/ws/src/new.rs"),
+                },
+            ]
+        );
+        assert!(apply_changes(&changes, Path::new("/elsewhere"), true).is_err());
+    }
+}
@@ -6,7 +6,7 @@
 
 use anyhow::Result;
 use chrono::Utc;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::ui_writer::UiWriter;
 use crate::ToolCall;
@@ -15,11 +15,8 @@ use super::executor::ToolContext;
 
 /// Get the path to the memory file.
 /// Memory is stored at `analysis/memory.md` in the working directory (version controlled).
-fn get_memory_path(working_dir: Option<&str>) -> PathBuf {
-    let base = working_dir
-        .map(PathBuf::from)
-        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
-    base.join("analysis").join("memory.md")
+fn get_memory_path(workspace: &Path) -> PathBuf {
+    workspace.join("analysis").join("memory.md")
 }
 
 /// Format the file size in a human-readable way.
@@ -43,7 +40,7 @@ pub async fn execute_remember<W: UiWriter>(
         .and_then(|v| v.as_str())
         .ok_or_else(|| anyhow::anyhow!("Missing required 'notes' parameter"))?;
 
-    let memory_path = get_memory_path(ctx.working_dir);
+    let memory_path = get_memory_path(&ctx.workspace_dir());
 
     // Ensure analysis directory exists
     if let Some(parent) = memory_path.parent() {
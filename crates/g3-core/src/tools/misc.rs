@@ -1,4 +1,5 @@
-//! Miscellaneous tools: take_screenshot, code_coverage, code_search.
+//! Miscellaneous tools: take_screenshot, image_diff, code_search, find_definition,
+//! find_references, symbol_lookup, ast_outline.
 
 use anyhow::Result;
 use tracing::debug;
@@ -7,6 +8,7 @@ use crate::ui_writer::UiWriter;
 use crate::ToolCall;
 
 use super::executor::ToolContext;
+use super::grep::search_path;
 
 /// Execute the `take_screenshot` tool.
 pub async fn execute_take_screenshot<W: UiWriter>(
@@ -67,10 +69,7 @@ pub async fn execute_take_screenshot<W: UiWriter>(
             let actual_path = if path.starts_with('/') {
                 path.to_string()
             } else {
-                let temp_dir = std::env::var("TMPDIR")
-                    .or_else(|_| std::env::var("HOME").map(|h| format!("{}/tmp", h)))
-                    .unwrap_or_else(|_| "/tmp".to_string());
-                format!("{}/{}", temp_dir.trim_end_matches('/'), path)
+                format!("{}/{}", screenshot_dir().trim_end_matches('/'), path)
             };
 
             Ok(format!(
@@ -82,51 +81,100 @@ pub async fn execute_take_screenshot<W: UiWriter>(
     }
 }
 
-/// Execute the `code_coverage` tool.
-pub async fn execute_code_coverage<W: UiWriter>(
+/// Where screenshots with relative paths are saved.
+fn screenshot_dir() -> String {
+    std::env::var("TMPDIR")
+        .or_else(|_| std::env::var("HOME").map(|h| format!("{}/tmp", h)))
+        .unwrap_or_else(|_| "/tmp".to_string())
+}
+
+/// Execute the `image_diff` tool.
+pub async fn execute_image_diff<W: UiWriter>(
     tool_call: &ToolCall,
     ctx: &ToolContext<'_, W>,
 ) -> Result<String> {
-    debug!("Processing code_coverage tool call");
-    let _ = tool_call; // unused
-    
-    ctx.ui_writer
-        .print_context_status("🔍 Generating code coverage report...");
-
-    // Ensure coverage tools are installed
-    match g3_execution::ensure_coverage_tools_installed() {
-        Ok(already_installed) => {
-            if !already_installed {
-                ctx.ui_writer
-                    .print_context_status("✅ Coverage tools installed successfully");
-            }
-        }
-        Err(e) => {
-            return Ok(format!("❌ Failed to install coverage tools: {}", e));
+    debug!("Processing image_diff tool call");
+
+    let args = &tool_call.args;
+    let (Some(before), Some(after)) = (
+        args.get("before").and_then(|v| v.as_str()),
+        args.get("after").and_then(|v| v.as_str()),
+    ) else {
+        return Ok("❌ Missing before or after argument".to_string());
+    };
+    let before = image_path(ctx, before);
+    let after = image_path(ctx, after);
+    let diff_path = match args.get("diff_path").and_then(|v| v.as_str()) {
+        Some(path) => search_path(ctx, Some(path)),
+        None => {
+            let stem = after.file_stem().unwrap_or_default().to_string_lossy();
+            after.with_file_name(format!("{}.diff.png", stem))
         }
-    }
+    };
+    let options = g3_computer_control::image_diff::DiffOptions {
+        threshold: args
+            .get("threshold")
+            .and_then(|v| v.as_f64())
+            .map_or(0.1, |t| t.clamp(0.0, 1.0)),
+        ignore: args
+            .get("ignore_regions")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|region| serde_json::from_value(region.clone()).ok())
+            .collect(),
+    };
+    let max_percent = args.get("max_diff_percent").and_then(|v| v.as_f64());
 
-    // Run cargo llvm-cov --workspace
-    let output = std::process::Command::new("cargo")
-        .args(["llvm-cov", "--workspace"])
-        .current_dir(std::env::current_dir()?)
-        .output()?;
+    let result = tokio::task::spawn_blocking(move || {
+        g3_computer_control::image_diff::diff_files(&before, &after, Some(&diff_path), &options)
+    })
+    .await?;
+    let diff = match result {
+        Ok(diff) => diff,
+        Err(e) => return Ok(format!("❌ {:#}", e)),
+    };
 
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    let passed = !max_percent.is_some_and(|max| diff.perceptual_percent > max);
+    let mut output = format!(
+        "{} {:.2}% of pixels differ perceptibly ({} of {}), {:.2}% differ at all",
+        if passed { "✅" } else { "❌" },
+        diff.perceptual_percent,
+        diff.perceptual_pixels,
+        diff.compared_pixels,
+        diff.pixel_percent
+    );
+    if let Some(max) = max_percent {
+        output.push_str(&format!(
+            "\n{} the {:.2}% limit",
+            if passed { "Within" } else { "Exceeds" },
+            max
+        ));
+    }
+    if let Some(region) = &diff.changed_region {
+        output.push_str(&format!(
+            "\nChanged region: x={} y={} width={} height={}",
+            region.x, region.y, region.width, region.height
+        ));
+    }
+    if let Some(path) = &diff.diff_path {
+        output.push_str(&format!("\nDiff image: {}", path.display()));
+    }
+    Ok(output)
+}
 
-        let mut result = String::from("✅ Code coverage report generated successfully\n\n");
-        result.push_str("## Coverage Summary\n");
-        result.push_str(&stdout);
-        if !stderr.is_empty() {
-            result.push_str("\n## Warnings\n");
-            result.push_str(&stderr);
-        }
-        Ok(result)
+/// An image path: absolute, relative to the working directory, or else a
+/// screenshot saved under the screenshot directory.
+fn image_path<W: UiWriter>(ctx: &ToolContext<'_, W>, path: &str) -> std::path::PathBuf {
+    let resolved = search_path(ctx, Some(path));
+    if resolved.exists() || std::path::Path::new(path).is_absolute() {
+        return resolved;
+    }
+    let screenshot = std::path::Path::new(&screenshot_dir()).join(path);
+    if screenshot.exists() {
+        screenshot
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Ok(format!("❌ Failed to generate coverage report:\n{}", stderr))
+        resolved
     }
 }
 
@@ -158,3 +206,189 @@ pub async fn execute_code_search<W: UiWriter>(
         Err(e) => Ok(format!("❌ Code search failed: {}", e)),
     }
 }
+
+/// Execute the `find_definition` tool.
+pub async fn execute_find_definition<W: UiWriter>(
+    tool_call: &ToolCall,
+    _ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing find_definition tool call");
+
+    let request: crate::code_search::SymbolRequest =
+        match serde_json::from_value(tool_call.args.clone()) {
+            Ok(req) => req,
+            Err(e) => return Ok(format!("❌ Invalid find_definition arguments: {}", e)),
+        };
+
+    match crate::code_search::find_definitions(request).await {
+        Ok(response) => Ok(format_symbol_response(&response, "definitions of")),
+        Err(e) => Ok(format!("❌ find_definition failed: {}", e)),
+    }
+}
+
+/// Execute the `find_references` tool.
+pub async fn execute_find_references<W: UiWriter>(
+    tool_call: &ToolCall,
+    _ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing find_references tool call");
+
+    let request: crate::code_search::SymbolRequest =
+        match serde_json::from_value(tool_call.args.clone()) {
+            Ok(req) => req,
+            Err(e) => return Ok(format!("❌ Invalid find_references arguments: {}", e)),
+        };
+
+    match crate::code_search::find_references(request).await {
+        Ok(response) => Ok(format_symbol_response(&response, "references to")),
+        Err(e) => Ok(format!("❌ find_references failed: {}", e)),
+    }
+}
+/// Execute the `symbol_lookup` tool.
+pub async fn execute_symbol_lookup<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    use crate::code_search::{SymbolIndex, SYMBOL_INDEX_PATH};
+
+    debug!("Processing symbol_lookup tool call");
+
+    let args = &tool_call.args;
+    let Some(symbol) = args.get("symbol").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing symbol argument".to_string());
+    };
+    let kind = args.get("kind").and_then(|v| v.as_str());
+    let max_results = args
+        .get("max_results")
+        .and_then(|v| v.as_u64())
+        .map_or(20, |n| (n as usize).max(1));
+
+    let root = search_path(ctx, None);
+    let Some(index) = SymbolIndex::load(&root) else {
+        return Ok(format!(
+            "❌ No symbol index at {}. It is built during discovery when `symbol_index` is enabled in the [discovery] config; use find_definition instead.",
+            SYMBOL_INDEX_PATH
+        ));
+    };
+
+    let matches = index.lookup(symbol, kind);
+    if matches.is_empty() {
+        return Ok(format!(
+            "✅ No symbol matching `{}` in the index ({} symbols in {} files, built {}). Files added since then aren't indexed; find_definition searches them.",
+            symbol,
+            index.symbol_count(),
+            index.files.len(),
+            index.created_at
+        ));
+    }
+
+    let mut output = format!(
+        "✅ {} symbols matching `{}`\n",
+        matches.len(),
+        symbol
+    );
+    for found in matches.iter().take(max_results) {
+        let label = match &found.symbol.parent {
+            Some(parent) => format!("{} in {}", found.symbol.kind, parent),
+            None => found.symbol.kind.clone(),
+        };
+        output.push_str(&format!(
+            "\n{}:{}-{} [{}] {}",
+            found.file, found.symbol.line, found.symbol.end_line, label, found.symbol.signature
+        ));
+        if index.is_stale(&root, found.file) {
+            output.push_str("  (file changed since indexed; lines may be off)");
+        }
+    }
+    if matches.len() > max_results {
+        output.push_str(&format!(
+            "\n\n({} more not shown; qualify the name, pass `kind` or raise `max_results`)",
+            matches.len() - max_results
+        ));
+    }
+    Ok(output)
+}
+
+/// Execute the `ast_outline` tool.
+pub async fn execute_ast_outline<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing ast_outline tool call");
+
+    let args = &tool_call.args;
+    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing path argument".to_string());
+    };
+    let file = search_path(ctx, Some(path));
+    if !file.is_file() {
+        return Ok(format!("❌ File not found: {}", file.display()));
+    }
+    let max_depth = args
+        .get("max_depth")
+        .and_then(|v| v.as_u64())
+        .map_or(usize::MAX, |d| (d as usize).max(1));
+
+    let response = match crate::code_search::outline_file(&file, max_depth).await {
+        Ok(response) => response,
+        Err(e) => return Ok(format!("❌ ast_outline failed: {}", e)),
+    };
+    if args.get("format").and_then(|v| v.as_str()) == Some("json") {
+        return Ok(serde_json::to_string_pretty(&response)?);
+    }
+
+    let mut output = format!(
+        "✅ {} ({}, {} lines): {} declarations\n",
+        path,
+        response.language,
+        response.total_lines,
+        response.items.len()
+    );
+    for item in &response.items {
+        output.push_str(&format!(
+            "\n{}{:>5}-{:<5} {}",
+            "  ".repeat(item.depth),
+            item.start_line,
+            item.end_line,
+            item.signature
+        ));
+    }
+    Ok(output)
+}
+
+
+/// Format symbol locations as `file:line:column` headers with context.
+fn format_symbol_response(response: &crate::code_search::SymbolResponse, what: &str) -> String {
+    use crate::code_search::SymbolKind;
+
+    if response.locations.is_empty() {
+        return format!(
+            "✅ No {} `{}` found ({} files searched)",
+            what, response.symbol, response.files_searched
+        );
+    }
+
+    let mut output = format!(
+        "✅ {}{} {} `{}` ({} files searched)\n",
+        response.locations.len(),
+        if response.truncated { "+" } else { "" },
+        what,
+        response.symbol,
+        response.files_searched
+    );
+    for location in &response.locations {
+        let label = match (location.kind, &location.declaration) {
+            (SymbolKind::Definition, Some(declaration)) => format!(" [{}]", declaration),
+            (SymbolKind::Import, _) => " [import]".to_string(),
+            _ => String::new(),
+        };
+        output.push_str(&format!(
+            "\n{}:{}:{}{}\n{}\n",
+            location.file, location.line, location.column, label, location.context
+        ));
+    }
+    if response.truncated {
+        output.push_str("\n(more results not shown; narrow `paths` or raise `max_results`)\n");
+    }
+    output
+}
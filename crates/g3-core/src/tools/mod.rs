@@ -4,21 +4,72 @@
 //! Tools are organized by category:
 //! - `shell` - Shell command execution and background processes
 //! - `file_ops` - File reading, writing, and editing
+//! - `read_output` - Paging through large output saved in the session
 //! - `todo` - TODO list management
+//! - `plan` - The agent's structured working plan
 //! - `webdriver` - Browser automation via WebDriver
 //! - `misc` - Other tools (screenshots, code search, etc.)
 //! - `research` - Web research via scout agent
 //! - `memory` - Workspace memory (remember)
 //! - `acd` - Aggressive Context Dehydration (rehydrate)
+//! - `i18n` - Hard-coded string extraction for localization
+//! - `dead_code` - Dead code and unused dependency detection
+//! - `cargo` - Cargo check/clippy/test/build with parsed diagnostics
+//! - `coverage` - cargo llvm-cov coverage per file, with deltas since the last run
+//! - `changelog` - Keep a Changelog maintenance
+//! - `verify` - Background verification against a worktree snapshot
+//! - `list_dir` - Gitignore-aware directory trees
+//! - `grep` - Capped regex search using ripgrep's engine
+//! - `regex_replace` - Regex search and replace across files
+//! - `git` - Git status, diff, commit and branch via git2
+//! - `checkpoint` - Working tree checkpoints and rollback on shadow git refs
+//! - `http` - HTTP requests with configured credentials
+//! - `sql` - Read-only (or gated write) queries against configured databases
+//! - `k8s` - kubectl get/describe/logs/apply within allowed namespaces
+//! - `docker` - Image builds and containers limited to the workspace and allowed images
+//! - `notebook` - Jupyter notebook reading, editing and execution
+//! - `archive` - Zip and tar extraction and creation with path and size checks
+//! - `environment` - Listening ports, processes and environment variables
+//! - `wait` - Polling until a file, URL, port or process condition holds
+//! - `watch` - Reporting files created, modified or deleted under a path
+//! - `node_eval` - Sandboxed JavaScript/TypeScript snippets on Deno or Node
+//! - `python` - Python code in a persistent per-session interpreter
+//! - `lsp` - Semantic renames through a configured language server
 
 pub mod executor;
 pub mod acd;
+pub mod archive;
+pub mod cargo;
+pub mod changelog;
+pub mod checkpoint;
+pub mod coverage;
+pub mod dead_code;
+pub mod docker;
+pub mod environment;
 pub mod file_ops;
+pub mod git;
+pub mod grep;
+pub mod http;
+pub mod i18n;
+pub mod k8s;
+pub mod list_dir;
+pub mod lsp;
 pub mod memory;
 pub mod misc;
+pub mod node_eval;
+pub mod notebook;
+pub mod plan;
+pub mod python;
+pub mod read_output;
+pub mod regex_replace;
 pub mod research;
 pub mod shell;
+#[cfg(feature = "sql")]
+pub mod sql;
 pub mod todo;
+pub mod verify;
+pub mod wait;
+pub mod watch;
 pub mod webdriver;
 
 pub use executor::ToolExecutor;
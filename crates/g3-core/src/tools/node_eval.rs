@@ -0,0 +1,452 @@
+//! Sandboxed JavaScript/TypeScript evaluation tool: node_eval.
+//!
+//! Runs a snippet in a fresh Deno or Node process with a hard timeout, a
+//! heap cap, a cleared environment and no network unless asked for, so quick
+//! data transformations don't need a script written to disk and run through
+//! `shell`. Deno's permission flags make the stronger sandbox and it is
+//! preferred when installed. Under Node, the permission model (Node 20.7+)
+//! limits file reads to the working directory and blocks writes and child
+//! processes, and a preload script blocks the network entry points and `fetch`.
+
+use anyhow::Result;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::{bool_arg, search_path};
+use super::read_output::paginate_output;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const MAX_TIMEOUT_SECS: u64 = 120;
+
+/// V8 heap limit for the snippet process.
+const HEAP_LIMIT_MB: u32 = 512;
+
+/// Environment variables passed through; everything else, including any
+/// credentials, is cleared.
+const PASSED_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "LANG",
+    "SYSTEMROOT",
+];
+
+/// Loaded with `--require` under Node when the network is off. Patching the
+/// CommonJS builtins and syncing them covers `import` of `node:http` etc. too.
+/// This stops accidental network use, not a determined snippet; Deno's
+/// permissions are the real sandbox.
+const NODE_NO_NETWORK: &str = r#"'use strict';
+const blocked = (name) => function () {
+  throw new Error(`network access is disabled (${name}); pass allow_network: true to enable it`);
+};
+// Entry points only: Node's own stdio pipes are net.Sockets
+const entryPoints = {
+  net: ['connect', 'createConnection', 'createServer'],
+  tls: ['connect', 'createServer'],
+  http: ['request', 'get', 'createServer'],
+  https: ['request', 'get', 'createServer'],
+  http2: ['connect', 'createServer', 'createSecureServer'],
+  dgram: ['createSocket'],
+  dns: ['lookup', 'lookupService', 'resolve', 'resolve4', 'resolve6', 'resolveAny', 'reverse'],
+};
+for (const [name, keys] of Object.entries(entryPoints)) {
+  const mod = require(name);
+  for (const key of keys) mod[key] = blocked(`${name}.${key}`);
+}
+require('module').syncBuiltinESMExports();
+globalThis.fetch = blocked('fetch');
+globalThis.WebSocket = blocked('WebSocket');
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Runtime {
+    Node,
+    Deno,
+}
+
+impl Runtime {
+    fn program(self) -> &'static str {
+        match self {
+            Runtime::Node => "node",
+            Runtime::Deno => "deno",
+        }
+    }
+}
+
+/// An installed runtime and its version.
+#[derive(Debug, Clone, Copy)]
+struct Installed {
+    runtime: Runtime,
+    version: (u32, u32),
+}
+
+/// What to run and how much to allow it.
+#[derive(Debug)]
+struct Snippet<'a> {
+    code: &'a str,
+    typescript: bool,
+    has_input: bool,
+    allow_network: bool,
+}
+
+/// Execute the `node_eval` tool.
+pub async fn execute_node_eval<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing node_eval tool call");
+
+    if !ctx.config.node_eval.enabled {
+        return Ok(
+            "❌ The node_eval tool is not enabled; set node_eval.enabled = true in the config"
+                .to_string(),
+        );
+    }
+    let args = &tool_call.args;
+    let Some(code) = args.get("code").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing code argument".to_string());
+    };
+    let typescript = match args.get("language").and_then(|v| v.as_str()) {
+        None | Some("js") | Some("javascript") => false,
+        Some("ts") | Some("typescript") => true,
+        Some(other) => {
+            return Ok(format!(
+                "❌ Unsupported language '{}' (expected javascript or typescript)",
+                other
+            ))
+        }
+    };
+    let candidates = match args.get("runtime").and_then(|v| v.as_str()) {
+        None => vec![Runtime::Deno, Runtime::Node],
+        Some("deno") => vec![Runtime::Deno],
+        Some("node") => vec![Runtime::Node],
+        Some(other) => {
+            return Ok(format!(
+                "❌ Unsupported runtime '{}' (expected node or deno)",
+                other
+            ))
+        }
+    };
+    let input = args.get("input").and_then(|v| v.as_str());
+    let snippet = Snippet {
+        code,
+        typescript,
+        has_input: input.is_some(),
+        allow_network: bool_arg(args, "allow_network"),
+    };
+    let timeout = Duration::from_secs(
+        args.get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_TIMEOUT_SECS, |t| t.clamp(1, MAX_TIMEOUT_SECS)),
+    );
+
+    let Some(installed) = detect(&candidates).await else {
+        let names: Vec<_> = candidates.iter().map(|r| r.program()).collect();
+        return Ok(format!(
+            "❌ No JavaScript runtime found (looked for {})",
+            names.join(", ")
+        ));
+    };
+    if let Err(refusal) = check_runtime(installed, typescript) {
+        return Ok(refusal);
+    }
+
+    // Permission flags compare canonical paths (macOS temp dirs are symlinks)
+    let working_dir = search_path(ctx, None);
+    let working_dir = working_dir.canonicalize().unwrap_or(working_dir);
+    let scratch_dir = tempfile::tempdir()?;
+    let scratch = scratch_dir.path().canonicalize()?;
+    let script = scratch.join(if typescript {
+        "snippet.mts"
+    } else {
+        "snippet.mjs"
+    });
+    std::fs::write(&script, source(installed.runtime, &snippet))?;
+    let preload = scratch.join("no-network.cjs");
+    std::fs::write(&preload, NODE_NO_NETWORK)?;
+
+    let mut command = tokio::process::Command::new(installed.runtime.program());
+    command
+        .args(command_args(
+            installed,
+            &snippet,
+            &working_dir,
+            &scratch,
+            &script,
+            &preload,
+        ))
+        .current_dir(&working_dir)
+        .env_clear()
+        .envs(
+            PASSED_ENV
+                .iter()
+                .filter_map(|k| Some((*k, std::env::var(k).ok()?))),
+        )
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let start = Instant::now();
+    let mut child = command.spawn()?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        let input = input.to_string();
+        // Written from a task so a snippet that never reads can't block us
+        tokio::spawn(async move {
+            let _ = stdin.write_all(input.as_bytes()).await;
+        });
+    }
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output?,
+        Err(_) => {
+            return Ok(format!(
+                "❌ Snippet timed out after {}s and was killed",
+                timeout.as_secs()
+            ))
+        }
+    };
+    let elapsed = start.elapsed();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = filter_warnings(&String::from_utf8_lossy(&output.stderr));
+    let mut result = match output.status.code() {
+        Some(0) => format!(
+            "✅ Ran with {} in {:.2}s",
+            installed.runtime.program(),
+            elapsed.as_secs_f64()
+        ),
+        Some(code) => format!("❌ Exited with code {}", code),
+        None => "❌ Killed by a signal (out of memory?)".to_string(),
+    };
+    if !stdout.is_empty() {
+        result.push_str("\n\n");
+        result.push_str(&paginate_output(
+            &stdout,
+            ctx.session_id,
+            "node_eval",
+            "stdout",
+        ));
+    }
+    if !stderr.trim().is_empty() {
+        result.push_str("\n\nstderr:\n");
+        result.push_str(&paginate_output(
+            &stderr,
+            ctx.session_id,
+            "node_eval",
+            "stderr",
+        ));
+    }
+    Ok(result)
+}
+
+/// The first of `candidates` that runs, with its version.
+async fn detect(candidates: &[Runtime]) -> Option<Installed> {
+    for &runtime in candidates {
+        let Ok(output) = tokio::process::Command::new(runtime.program())
+            .arg("--version")
+            .stdin(Stdio::null())
+            .output()
+            .await
+        else {
+            continue;
+        };
+        if let Some(version) = parse_version(&String::from_utf8_lossy(&output.stdout)) {
+            return Some(Installed { runtime, version });
+        }
+    }
+    None
+}
+
+/// Major and minor version from `v22.6.0` (node) or `deno 1.46.3 (...)`.
+fn parse_version(output: &str) -> Option<(u32, u32)> {
+    let first = output.lines().next()?;
+    let version = first
+        .split_whitespace()
+        .find(|w| {
+            w.trim_start_matches('v')
+                .starts_with(|c: char| c.is_ascii_digit())
+        })?
+        .trim_start_matches('v');
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().and_then(|m| m.parse().ok()).unwrap_or(0);
+    Some((major, minor))
+}
+
+/// Refuse a Node too old to run the snippet sandboxed: without the
+/// permission model (Node 20.7+) it could write files and spawn processes.
+fn check_runtime(installed: Installed, typescript: bool) -> Result<(), String> {
+    if installed.runtime != Runtime::Node {
+        return Ok(());
+    }
+    let (major, minor) = installed.version;
+    if installed.version < (20, 7) {
+        return Err(format!(
+            "❌ node_eval needs Deno or Node 20.7+ for its sandbox (found Node {}.{})",
+            major, minor
+        ));
+    }
+    if typescript && installed.version < (22, 6) {
+        return Err(format!(
+            "❌ TypeScript needs Deno or Node 22.6+ (found Node {}.{})",
+            major, minor
+        ));
+    }
+    Ok(())
+}
+
+/// The script: the snippet, with `require` and `INPUT` defined on its first
+/// line so error line numbers still match.
+fn source(runtime: Runtime, snippet: &Snippet<'_>) -> String {
+    let mut header = String::from(
+        "import{createRequire as __g3CreateRequire}from'node:module';\
+         const require=__g3CreateRequire(import.meta.url);",
+    );
+    if snippet.has_input {
+        header.push_str(match runtime {
+            Runtime::Node => "globalThis.INPUT=require('node:fs').readFileSync(0,'utf8');",
+            Runtime::Deno => "globalThis.INPUT=await new Response(Deno.stdin.readable).text();",
+        });
+    }
+    header + snippet.code
+}
+
+fn command_args(
+    installed: Installed,
+    snippet: &Snippet<'_>,
+    working_dir: &Path,
+    scratch: &Path,
+    script: &Path,
+    preload: &Path,
+) -> Vec<String> {
+    let heap = format!("--max-old-space-size={}", HEAP_LIMIT_MB);
+    let mut args = Vec::new();
+    match installed.runtime {
+        Runtime::Deno => {
+            args.extend(["run", "--quiet", "--no-prompt", "--no-config"].map(String::from));
+            args.push(format!(
+                "--allow-read={},{}",
+                working_dir.display(),
+                scratch.display()
+            ));
+            if snippet.allow_network {
+                args.push("--allow-net".to_string());
+            }
+            args.push(format!("--v8-flags={}", heap));
+        }
+        Runtime::Node => {
+            args.push(heap);
+            let version = installed.version;
+            // Older versions are refused by `check_runtime`
+            let permission = if version >= (23, 5) || (version.0 == 22 && version.1 >= 13) {
+                "--permission"
+            } else {
+                "--experimental-permission"
+            };
+            args.push(permission.to_string());
+            args.push(format!("--allow-fs-read={}", working_dir.display()));
+            args.push(format!("--allow-fs-read={}", scratch.display()));
+            if snippet.typescript && version < (23, 6) {
+                args.push("--experimental-strip-types".to_string());
+            }
+            if !snippet.allow_network {
+                args.push("--require".to_string());
+                args.push(preload.display().to_string());
+            }
+        }
+    }
+    args.push(script.display().to_string());
+    args
+}
+
+/// stderr without Node's experimental feature warnings, which our own flags cause.
+fn filter_warnings(stderr: &str) -> String {
+    stderr
+        .lines()
+        .filter(|line| {
+            !line.contains("ExperimentalWarning") && !line.contains("node --trace-warnings")
+        })
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sandbox_arguments() {
+        assert_eq!(parse_version("v22.6.0\n"), Some((22, 6)));
+        assert_eq!(
+            parse_version("deno 1.46.3 (stable, release, x86_64)\nv8 12.9"),
+            Some((1, 46))
+        );
+
+        let snippet = Snippet {
+            code: "console.log(1)",
+            typescript: true,
+            has_input: false,
+            allow_network: false,
+        };
+        let (dir, scratch) = (Path::new("/work"), Path::new("/tmp/s"));
+        let (script, preload) = (scratch.join("snippet.mts"), scratch.join("no-network.cjs"));
+
+        let node = Installed {
+            runtime: Runtime::Node,
+            version: (22, 8),
+        };
+        let args = command_args(node, &snippet, dir, scratch, &script, &preload);
+        assert!(args.contains(&"--experimental-permission".to_string()));
+        assert!(args.contains(&"--allow-fs-read=/work".to_string()));
+        assert!(args.contains(&"--experimental-strip-types".to_string()));
+        assert!(args.contains(&"--require".to_string()));
+        assert_eq!(args.last().unwrap(), "/tmp/s/snippet.mts");
+
+        let deno = Installed {
+            runtime: Runtime::Deno,
+            version: (2, 0),
+        };
+        let args = command_args(deno, &snippet, dir, scratch, &script, &preload);
+        assert!(args.contains(&"--allow-read=/work,/tmp/s".to_string()));
+        assert!(!args.iter().any(|a| a.starts_with("--allow-net")));
+        let networked = Snippet {
+            allow_network: true,
+            ..snippet
+        };
+        let args = command_args(deno, &networked, dir, scratch, &script, &preload);
+        assert!(args.contains(&"--allow-net".to_string()));
+
+        let old_node = Installed {
+            runtime: Runtime::Node,
+            version: (18, 19),
+        };
+        let refusal = check_runtime(old_node, false).unwrap_err();
+        assert!(refusal.contains("needs Deno or Node 20.7+"), "{}", refusal);
+        assert!(check_runtime(node, true).is_ok());
+        let no_types_node = Installed {
+            runtime: Runtime::Node,
+            version: (22, 2),
+        };
+        assert!(check_runtime(no_types_node, false).is_ok());
+        assert!(check_runtime(no_types_node, true).is_err());
+        assert!(check_runtime(deno, true).is_ok());
+
+        // The header keeps the snippet on its original line numbers
+        let source = source(Runtime::Node, &networked);
+        assert_eq!(source.lines().count(), 1);
+        assert!(source.ends_with("console.log(1)"));
+    }
+}
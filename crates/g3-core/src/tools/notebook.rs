@@ -0,0 +1,606 @@
+//! Jupyter notebook tools: notebook_read, notebook_edit, notebook_run.
+//!
+//! Notebooks are edited as JSON so metadata and fields this module doesn't
+//! know about survive a round trip, and are written back the way Jupyter
+//! writes them (one-space indent, sources as lists of lines). Cells run in a
+//! kernel kept per notebook for the session (see `crate::notebook_kernel`);
+//! their outputs are stored in the notebook and returned as text.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::utils::truncate_chars;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::{bool_arg, search_path};
+use super::research::strip_ansi_codes;
+
+/// Output text kept per cell.
+const MAX_OUTPUT_CHARS: usize = 4_000;
+
+/// Traceback lines kept per error.
+const MAX_TRACEBACK_LINES: usize = 12;
+
+const DEFAULT_CELL_TIMEOUT_SECS: u64 = 120;
+
+/// A parsed .ipynb file.
+#[derive(Debug, Clone)]
+pub struct Notebook {
+    json: Value,
+}
+
+impl Notebook {
+    /// An empty nbformat 4.5 notebook with a Python 3 kernel.
+    pub fn new() -> Self {
+        Self {
+            json: json!({
+                "cells": [],
+                "metadata": {
+                    "kernelspec": {
+                        "display_name": "Python 3",
+                        "language": "python",
+                        "name": "python3"
+                    }
+                },
+                "nbformat": 4,
+                "nbformat_minor": 5
+            }),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let json: Value = serde_json::from_str(content)?;
+        if !json["cells"].is_array() {
+            bail!("not a notebook (no cells array)");
+        }
+        if json["nbformat"].as_u64().is_some_and(|v| v < 4) {
+            bail!("nbformat 3 and older are not supported; convert with `jupyter nbconvert --to notebook`");
+        }
+        Ok(Self { json })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    /// Jupyter's own layout: one-space indent and a trailing newline.
+    pub fn to_json(&self) -> Result<String> {
+        let mut buf = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+        self.json.serialize(&mut serializer)?;
+        Ok(String::from_utf8(buf)? + "\n")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn cells(&self) -> &[Value] {
+        self.json["cells"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn cells_mut(&mut self) -> &mut Vec<Value> {
+        if !self.json["cells"].is_array() {
+            self.json["cells"] = json!([]);
+        }
+        self.json["cells"]
+            .as_array_mut()
+            .expect("cells is an array")
+    }
+
+    /// The kernel named in the notebook's metadata, python3 if none.
+    pub fn kernel_name(&self) -> &str {
+        self.json["metadata"]["kernelspec"]["name"]
+            .as_str()
+            .unwrap_or("python3")
+    }
+
+    fn cell_mut(&mut self, index: usize) -> Result<&mut Value> {
+        let len = self.cells().len();
+        self.cells_mut().get_mut(index).ok_or_else(|| {
+            anyhow!(
+                "Cell {} does not exist (the notebook has {} cells)",
+                index,
+                len
+            )
+        })
+    }
+
+    /// Insert a new cell before `index` (at the end if None).
+    pub fn insert_cell(
+        &mut self,
+        index: Option<usize>,
+        cell_type: &str,
+        source: &str,
+    ) -> Result<usize> {
+        let len = self.cells().len();
+        let index = index.unwrap_or(len);
+        if index > len {
+            bail!(
+                "Cannot insert at {} (the notebook has {} cells)",
+                index,
+                len
+            );
+        }
+        self.cells_mut().insert(index, new_cell(cell_type, source));
+        Ok(index)
+    }
+
+    /// Replace a cell's source (and type, if given), clearing its outputs.
+    pub fn replace_cell(
+        &mut self,
+        index: usize,
+        cell_type: Option<&str>,
+        source: &str,
+    ) -> Result<()> {
+        let cell = self.cell_mut(index)?;
+        let cell_type = cell_type
+            .or_else(|| cell["cell_type"].as_str())
+            .unwrap_or("code")
+            .to_string();
+        let mut replaced = new_cell(&cell_type, source);
+        // Keep the cell's id and metadata (tags, collapsed state, ...)
+        for key in ["id", "metadata"] {
+            if let Some(value) = cell.get(key) {
+                replaced[key] = value.clone();
+            }
+        }
+        *cell = replaced;
+        Ok(())
+    }
+
+    pub fn delete_cell(&mut self, index: usize) -> Result<()> {
+        self.cell_mut(index)?;
+        self.cells_mut().remove(index);
+        Ok(())
+    }
+
+    /// Store the result of running a code cell.
+    fn set_outputs(
+        &mut self,
+        index: usize,
+        execution_count: Option<u64>,
+        outputs: &[Value],
+    ) -> Result<()> {
+        let cell = self.cell_mut(index)?;
+        cell["execution_count"] = json!(execution_count);
+        cell["outputs"] = Value::Array(outputs.to_vec());
+        Ok(())
+    }
+}
+
+impl Default for Notebook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A new cell of `cell_type` ("code", "markdown" or "raw").
+fn new_cell(cell_type: &str, source: &str) -> Value {
+    // nbformat 4.5 cell ids: short, unique within the notebook
+    let id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+    let mut cell = json!({
+        "cell_type": cell_type,
+        "id": id,
+        "metadata": {},
+        "source": source_lines(source),
+    });
+    if cell_type == "code" {
+        cell["execution_count"] = Value::Null;
+        cell["outputs"] = json!([]);
+    }
+    cell
+}
+
+/// Source text as nbformat stores it: lines with their newlines.
+fn source_lines(source: &str) -> Value {
+    Value::Array(
+        source
+            .split_inclusive('\n')
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    )
+}
+
+/// Multiline text, stored either as a string or a list of lines.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(lines) => lines.iter().filter_map(|l| l.as_str()).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Outputs as the model should see them: text, error summaries, and
+/// placeholders for images and other rich data.
+pub fn render_outputs(outputs: &[Value]) -> String {
+    let mut rendered = String::new();
+    for output in outputs {
+        let piece = match output["output_type"].as_str() {
+            Some("stream") => text(&output["text"]),
+            Some("execute_result") | Some("display_data") => {
+                let data = &output["data"];
+                let mut piece = text(&data["text/plain"]);
+                let rich: Vec<&str> = data
+                    .as_object()
+                    .map(|d| {
+                        d.keys()
+                            .map(String::as_str)
+                            .filter(|k| *k != "text/plain")
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if !rich.is_empty() {
+                    if !piece.is_empty() {
+                        piece.push('\n');
+                    }
+                    piece.push_str(&format!("[{} output]", rich.join(", ")));
+                }
+                piece
+            }
+            Some("error") => {
+                let traceback: Vec<String> = output["traceback"]
+                    .as_array()
+                    .map(|lines| {
+                        lines
+                            .iter()
+                            .filter_map(|l| l.as_str())
+                            .map(strip_ansi_codes)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let traceback = traceback.join("\n");
+                let lines: Vec<&str> = traceback.lines().collect();
+                let mut piece = format!(
+                    "{}: {}",
+                    output["ename"].as_str().unwrap_or("Error"),
+                    output["evalue"].as_str().unwrap_or("")
+                );
+                if !lines.is_empty() {
+                    piece.push('\n');
+                    piece.push_str(
+                        &lines[lines.len().saturating_sub(MAX_TRACEBACK_LINES)..].join("\n"),
+                    );
+                }
+                piece
+            }
+            _ => continue,
+        };
+        if !rendered.is_empty() && !rendered.ends_with('\n') {
+            rendered.push('\n');
+        }
+        rendered.push_str(&piece);
+    }
+    truncate_chars(rendered.trim_end(), MAX_OUTPUT_CHARS, "\n")
+}
+
+/// Cell indices given as `cells`, if any.
+fn cell_indices(args: &Value) -> Option<Vec<usize>> {
+    args.get("cells")?.as_array().map(|cells| {
+        cells
+            .iter()
+            .filter_map(|c| c.as_u64())
+            .map(|c| c as usize)
+            .collect()
+    })
+}
+
+/// One cell for `notebook_read`.
+fn format_cell(index: usize, cell: &Value, include_outputs: bool) -> String {
+    let cell_type = cell["cell_type"].as_str().unwrap_or("code");
+    let mut header = format!("[{}] {}", index, cell_type);
+    if let Some(count) = cell["execution_count"].as_u64() {
+        header.push_str(&format!(" (executed {})", count));
+    }
+    let mut lines = vec![header, text(&cell["source"])];
+    if include_outputs {
+        let outputs = render_outputs(
+            cell["outputs"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+        );
+        if !outputs.is_empty() {
+            lines.push("--- output ---".to_string());
+            lines.push(outputs);
+        }
+    }
+    lines.join("\n")
+}
+
+/// Execute the `notebook_read` tool.
+pub async fn execute_notebook_read<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing notebook_read tool call");
+
+    let args = &tool_call.args;
+    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing path argument".to_string());
+    };
+    let path = search_path(ctx, Some(path));
+    let notebook = match Notebook::load(&path) {
+        Ok(notebook) => notebook,
+        Err(e) => return Ok(format!("❌ Failed to read {}: {}", path.display(), e)),
+    };
+    let include_outputs = args
+        .get("include_outputs")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    let selected = cell_indices(args);
+    let mut sections = vec![format!(
+        "📓 {} ({} kernel, {} cells)",
+        path.display(),
+        notebook.kernel_name(),
+        notebook.cells().len()
+    )];
+    for (index, cell) in notebook.cells().iter().enumerate() {
+        if selected.as_ref().is_some_and(|s| !s.contains(&index)) {
+            continue;
+        }
+        sections.push(format_cell(index, cell, include_outputs));
+    }
+    Ok(sections.join("\n\n"))
+}
+
+/// Execute the `notebook_edit` tool.
+pub async fn execute_notebook_edit<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing notebook_edit tool call");
+
+    let args = &tool_call.args;
+    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing path argument".to_string());
+    };
+    let path = search_path(ctx, Some(path));
+    let Some(operation) = args.get("operation").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing operation argument (replace, insert or delete)".to_string());
+    };
+    let index = args
+        .get("index")
+        .and_then(|v| v.as_u64())
+        .map(|i| i as usize);
+    let source = args.get("source").and_then(|v| v.as_str());
+    let cell_type = args.get("cell_type").and_then(|v| v.as_str());
+    if cell_type.is_some_and(|t| !matches!(t, "code" | "markdown" | "raw")) {
+        return Ok("❌ cell_type must be code, markdown or raw".to_string());
+    }
+
+    // Inserting into a notebook that doesn't exist yet creates it
+    let mut notebook = if operation == "insert" && !path.exists() {
+        Notebook::new()
+    } else {
+        match Notebook::load(&path) {
+            Ok(notebook) => notebook,
+            Err(e) => return Ok(format!("❌ Failed to read {}: {}", path.display(), e)),
+        }
+    };
+
+    let result = match (operation, index, source) {
+        ("replace", Some(index), Some(source)) => notebook
+            .replace_cell(index, cell_type, source)
+            .map(|_| format!("Replaced cell {}", index)),
+        ("insert", index, Some(source)) => notebook
+            .insert_cell(index, cell_type.unwrap_or("code"), source)
+            .map(|index| format!("Inserted cell {}", index)),
+        ("delete", Some(index), _) => notebook
+            .delete_cell(index)
+            .map(|_| format!("Deleted cell {}", index)),
+        ("replace" | "delete", None, _) => Err(anyhow!("{} needs an index", operation)),
+        ("replace" | "insert", _, None) => Err(anyhow!("{} needs a source", operation)),
+        _ => Err(anyhow!(
+            "Unknown operation '{}' (expected replace, insert or delete)",
+            operation
+        )),
+    };
+    let message = match result {
+        Ok(message) => message,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    if let Err(e) = notebook.save(&path) {
+        return Ok(format!("❌ Failed to write {}: {}", path.display(), e));
+    }
+    Ok(format!(
+        "✅ {} in {} ({} cells)",
+        message,
+        path.display(),
+        notebook.cells().len()
+    ))
+}
+
+/// Per-cell result of `notebook_run`.
+#[derive(Debug)]
+struct RunReport {
+    index: usize,
+    status: String,
+    output: String,
+}
+
+/// Execute the `notebook_run` tool.
+pub async fn execute_notebook_run<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing notebook_run tool call");
+
+    if !ctx.config.notebook.enabled {
+        return Ok(
+            "❌ The notebook_run tool is not enabled; set notebook.enabled = true in the config"
+                .to_string(),
+        );
+    }
+    let args = &tool_call.args;
+    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing path argument".to_string());
+    };
+    let path = search_path(ctx, Some(path));
+    let mut notebook = match Notebook::load(&path) {
+        Ok(notebook) => notebook,
+        Err(e) => return Ok(format!("❌ Failed to read {}: {}", path.display(), e)),
+    };
+    let timeout = Duration::from_secs(
+        args.get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_CELL_TIMEOUT_SECS),
+    );
+    let stop_on_error = args
+        .get("stop_on_error")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    if bool_arg(args, "restart") && ctx.notebook_kernels.shutdown(&path).await {
+        ctx.ui_writer
+            .print_context_status("🔄 Restarted the kernel");
+    }
+
+    let code_cells: Vec<usize> = notebook
+        .cells()
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| cell["cell_type"] == "code")
+        .map(|(index, _)| index)
+        .collect();
+    let to_run = match cell_indices(args) {
+        Some(selected) => {
+            if let Some(missing) = selected.iter().find(|i| **i >= notebook.cells().len()) {
+                return Ok(format!(
+                    "❌ Cell {} does not exist (the notebook has {} cells)",
+                    missing,
+                    notebook.cells().len()
+                ));
+            }
+            selected
+                .into_iter()
+                .filter(|i| code_cells.contains(i))
+                .collect()
+        }
+        None => code_cells,
+    };
+    if to_run.is_empty() {
+        return Ok("No code cells to run".to_string());
+    }
+
+    let kernel_name = notebook.kernel_name().to_string();
+    let mut reports = Vec::new();
+    for index in to_run {
+        let source = text(&notebook.cells()[index]["source"]);
+        ctx.ui_writer
+            .print_context_status(&format!("📓 Running cell {}...", index));
+        let run = match ctx
+            .notebook_kernels
+            .execute(&path, &kernel_name, &source, timeout)
+            .await
+        {
+            Ok(run) => run,
+            Err(e) => {
+                reports.push(RunReport {
+                    index,
+                    status: "failed".to_string(),
+                    output: e.to_string(),
+                });
+                break;
+            }
+        };
+        notebook.set_outputs(index, run.execution_count, &run.outputs)?;
+        let failed = run.status != "ok";
+        reports.push(RunReport {
+            index,
+            status: run.status,
+            output: render_outputs(&run.outputs),
+        });
+        if failed && stop_on_error {
+            break;
+        }
+    }
+    if let Err(e) = notebook.save(&path) {
+        return Ok(format!(
+            "❌ Failed to write outputs to {}: {}",
+            path.display(),
+            e
+        ));
+    }
+
+    let failures = reports.iter().filter(|r| r.status != "ok").count();
+    let mut sections = vec![format!(
+        "{} Ran {} cells in {} ({} failed); outputs saved to the notebook",
+        if failures == 0 { "✅" } else { "⚠️" },
+        reports.len(),
+        path.display(),
+        failures
+    )];
+    for report in &reports {
+        let mut section = format!("[{}] {}", report.index, report.status);
+        if !report.output.is_empty() {
+            section.push('\n');
+            section.push_str(&report.output);
+        }
+        sections.push(section);
+    }
+    Ok(sections.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notebook_edits_round_trip() {
+        let mut notebook = Notebook::parse(
+            r##"{"cells": [{"cell_type": "markdown", "id": "a1", "metadata": {"tags": ["intro"]}, "source": ["# Title\n", "text"]}],
+                "metadata": {"kernelspec": {"name": "ir"}, "custom": 1}, "nbformat": 4, "nbformat_minor": 5}"##,
+        )
+        .unwrap();
+        assert_eq!(notebook.kernel_name(), "ir");
+
+        notebook.insert_cell(None, "code", "x = 1\nx").unwrap();
+        notebook
+            .set_outputs(
+                1,
+                Some(1),
+                &[json!({"output_type": "execute_result", "data": {"text/plain": "1"}})],
+            )
+            .unwrap();
+        notebook.replace_cell(0, None, "# New title\n").unwrap();
+        assert!(notebook.delete_cell(5).is_err());
+
+        let saved = Notebook::parse(&notebook.to_json().unwrap()).unwrap();
+        let cells = saved.cells();
+        assert_eq!(text(&cells[0]["source"]), "# New title\n");
+        assert_eq!(cells[0]["id"], "a1");
+        assert_eq!(cells[0]["metadata"]["tags"][0], "intro");
+        assert_eq!(cells[1]["source"], json!(["x = 1\n", "x"]));
+        assert_eq!(cells[1]["execution_count"], 1);
+        assert_eq!(saved.json["metadata"]["custom"], 1);
+        assert!(notebook.to_json().unwrap().starts_with("{\n \"cells\""));
+    }
+
+    #[test]
+    fn test_render_outputs() {
+        let outputs = [
+            json!({"output_type": "stream", "name": "stdout", "text": ["hello\n", "world\n"]}),
+            json!({"output_type": "display_data", "data": {"image/png": "iVBOR...", "text/plain": "<Figure>"}}),
+            json!({"output_type": "error", "ename": "NameError", "evalue": "name 'y' is not defined",
+                   "traceback": ["\u{1b}[0;31mNameError\u{1b}[0m: name 'y' is not defined"]}),
+        ];
+        assert_eq!(
+            render_outputs(&outputs),
+            "hello\nworld\n<Figure>\n[image/png output]\nNameError: name 'y' is not defined\nNameError: name 'y' is not defined"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Working plan tools: plan_read, plan_write.
+//!
+//! Read and update the session's structured plan (see `crate::plan`): the
+//! whole step list can be replaced, or single steps updated by number.
+
+use anyhow::Result;
+use serde_json::Value;
+use tracing::debug;
+
+use crate::plan::{Plan, PlanStep, StepStatus};
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Execute the `plan_read` tool.
+pub async fn execute_plan_read<W: UiWriter>(
+    _tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing plan_read tool call");
+
+    let Some(session_id) = ctx.session_id else {
+        return Ok("❌ Plans are stored per session, and there is no session".to_string());
+    };
+    Ok(match Plan::load(session_id) {
+        Some(plan) => format!("🗺️ Plan:\n{}", plan.format()),
+        None => "🗺️ No plan yet; create one with plan_write".to_string(),
+    })
+}
+
+/// Execute the `plan_write` tool.
+pub async fn execute_plan_write<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing plan_write tool call");
+
+    let Some(session_id) = ctx.session_id else {
+        return Ok("❌ Plans are stored per session, and there is no session".to_string());
+    };
+    let mut plan = Plan::load(session_id).unwrap_or_default();
+    if let Err(e) = apply_write(&mut plan, &tool_call.args) {
+        return Ok(format!("❌ {}", e));
+    }
+    if let Err(e) = plan.save(session_id) {
+        return Ok(format!("❌ Failed to save the plan: {}", e));
+    }
+    Ok(format!("✅ Plan saved\n{}", plan.format()))
+}
+
+/// Apply the `goal`, `steps` (replace all) and `updates` (by step number)
+/// arguments to `plan`.
+pub fn apply_write(plan: &mut Plan, args: &Value) -> Result<(), String> {
+    let goal = args.get("goal").and_then(|v| v.as_str());
+    let steps = args.get("steps").and_then(|v| v.as_array());
+    let updates = args.get("updates").and_then(|v| v.as_array());
+    if goal.is_none() && steps.is_none() && updates.is_none() {
+        return Err(
+            "Pass goal, steps (the full list) or updates (changes to numbered steps)".to_string(),
+        );
+    }
+
+    if let Some(goal) = goal {
+        plan.goal = Some(goal.to_string()).filter(|g| !g.trim().is_empty());
+    }
+    if let Some(steps) = steps {
+        plan.steps = steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let title = step
+                    .get("title")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| format!("Step {} has no title", i + 1))?;
+                Ok(PlanStep {
+                    title: title.to_string(),
+                    status: status_arg(step)?.unwrap_or_default(),
+                    rationale: step
+                        .get("rationale")
+                        .and_then(|v| v.as_str())
+                        .map(String::from),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+    }
+    for update in updates.into_iter().flatten() {
+        let number = update.get("step").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        let count = plan.steps.len();
+        let Some(step) = number.checked_sub(1).and_then(|i| plan.steps.get_mut(i)) else {
+            return Err(format!(
+                "No step {} to update (the plan has {} steps, numbered from 1)",
+                number, count
+            ));
+        };
+        if let Some(status) = status_arg(update)? {
+            step.status = status;
+        }
+        if let Some(title) = update.get("title").and_then(|v| v.as_str()) {
+            step.title = title.to_string();
+        }
+        if let Some(rationale) = update.get("rationale").and_then(|v| v.as_str()) {
+            step.rationale = Some(rationale.to_string());
+        }
+    }
+    Ok(())
+}
+
+fn status_arg(value: &Value) -> Result<Option<StepStatus>, String> {
+    match value.get("status").and_then(|v| v.as_str()) {
+        None => Ok(None),
+        Some(status) => StepStatus::parse(status).map(Some).ok_or_else(|| {
+            format!(
+                "Unknown status '{}' (expected pending, in_progress, done, blocked or skipped)",
+                status
+            )
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_write_replaces_and_updates() {
+        let mut plan = Plan::default();
+        apply_write(
+            &mut plan,
+            &json!({
+                "goal": "Fix the flaky test",
+                "steps": [
+                    { "title": "Reproduce", "status": "in_progress" },
+                    { "title": "Fix", "rationale": "Likely a race in setup" }
+                ]
+            }),
+        )
+        .unwrap();
+        assert_eq!(plan.steps[1].status, StepStatus::Pending);
+
+        apply_write(
+            &mut plan,
+            &json!({ "updates": [
+                { "step": 1, "status": "done" },
+                { "step": 2, "status": "in_progress" }
+            ]}),
+        )
+        .unwrap();
+        assert_eq!(plan.goal.as_deref(), Some("Fix the flaky test"));
+        assert_eq!(plan.steps[0].status, StepStatus::Done);
+        assert_eq!(plan.current_step().map(|(i, _)| i), Some(1));
+        assert_eq!(
+            plan.steps[1].rationale.as_deref(),
+            Some("Likely a race in setup")
+        );
+
+        assert!(apply_write(&mut plan, &json!({ "updates": [{ "step": 3 }] })).is_err());
+        assert!(apply_write(
+            &mut plan,
+            &json!({ "updates": [{ "step": 1, "status": "finished" }] })
+        )
+        .is_err());
+        assert!(apply_write(&mut plan, &json!({})).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! Persistent Python execution tool: python.
+//!
+//! Runs code in the session's Python interpreter (see
+//! `crate::python_interpreter`), so data loaded in one call can be explored
+//! in the next. The project's virtualenv is used when there is one.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::debug;
+
+use crate::python_interpreter::Execution;
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::{bool_arg, search_path};
+use super::read_output::paginate_output;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MAX_TIMEOUT_SECS: u64 = 600;
+
+/// Execute the `python` tool.
+pub async fn execute_python<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing python tool call");
+
+    if !ctx.config.python.enabled {
+        return Ok(
+            "❌ The python tool is not enabled; set python.enabled = true in the config"
+                .to_string(),
+        );
+    }
+    let args = &tool_call.args;
+    let reset = bool_arg(args, "reset");
+    if reset {
+        ctx.python_interpreter.reset().await;
+    }
+    let Some(code) = args.get("code").and_then(|v| v.as_str()) else {
+        return Ok(if reset {
+            "✅ Python interpreter reset; the next call starts a fresh one".to_string()
+        } else {
+            "❌ Missing code argument".to_string()
+        });
+    };
+    let timeout = Duration::from_secs(
+        args.get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_TIMEOUT_SECS, |t| t.clamp(1, MAX_TIMEOUT_SECS)),
+    );
+
+    let cwd = search_path(ctx, None);
+    let program = interpreter_program(&cwd);
+    let execution = match ctx
+        .python_interpreter
+        .execute(&program, &cwd, code, timeout)
+        .await
+    {
+        Ok(execution) => execution,
+        Err(e) => {
+            return Ok(format!(
+                "❌ {}\nThe interpreter was stopped; variables from earlier calls are lost and the next call starts a fresh one.",
+                e
+            ))
+        }
+    };
+    let version = ctx.python_interpreter.version().await.unwrap_or_default();
+
+    let report = format_execution(&execution, &version, timeout);
+    Ok(paginate_output(&report, ctx.session_id, "python", "output"))
+}
+
+/// The interpreter to use: the active or project virtualenv's, else python3.
+fn interpreter_program(cwd: &Path) -> PathBuf {
+    let relative = if cfg!(windows) {
+        Path::new("Scripts").join("python.exe")
+    } else {
+        Path::new("bin").join("python")
+    };
+    let venvs = std::env::var_os("VIRTUAL_ENV")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain([cwd.join(".venv"), cwd.join("venv")]);
+    for venv in venvs {
+        let program = venv.join(&relative);
+        if program.is_file() {
+            return program;
+        }
+    }
+    PathBuf::from(if cfg!(windows) { "python" } else { "python3" })
+}
+
+fn format_execution(execution: &Execution, version: &str, timeout: Duration) -> String {
+    let mut report = match execution.status.as_str() {
+        "ok" => format!("✅ [{}] Python {}", execution.count, version),
+        "timeout" => format!(
+            "❌ [{}] Timed out after {}s and was interrupted; earlier variables are kept",
+            execution.count,
+            timeout.as_secs()
+        ),
+        _ => format!("❌ [{}] Error", execution.count),
+    };
+    let mut section = |title: &str, text: &str| {
+        if !text.trim().is_empty() {
+            report.push_str(&format!("\n\n{}:\n{}", title, text.trim_end()));
+        }
+    };
+    section("stdout", &execution.stdout);
+    section("stderr", &execution.stderr);
+    section("result", execution.result.as_deref().unwrap_or_default());
+    section("traceback", execution.error.as_deref().unwrap_or_default());
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_execution() {
+        let execution = Execution {
+            status: "ok".to_string(),
+            stdout: "loaded 3 rows\n".to_string(),
+            stderr: String::new(),
+            result: Some("{'a': 1}".to_string()),
+            error: None,
+            count: 2,
+        };
+        let report = format_execution(&execution, "3.12.1", Duration::from_secs(30));
+        assert_eq!(
+            report,
+            "✅ [2] Python 3.12.1\n\nstdout:\nloaded 3 rows\n\nresult:\n{'a': 1}"
+        );
+
+        let timed_out = Execution {
+            status: "timeout".to_string(),
+            result: None,
+            ..execution
+        };
+        let report = format_execution(&timed_out, "3.12.1", Duration::from_secs(5));
+        assert!(report.starts_with("❌ [2] Timed out after 5s"));
+        assert!(report.contains("loaded 3 rows"));
+    }
+}
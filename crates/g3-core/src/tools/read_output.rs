@@ -0,0 +1,230 @@
+//! Paging through large tool output.
+//!
+//! Output too large for the context (a full test log, a huge file) is saved
+//! under `.g3/sessions/<id>/tools/` and replaced by its first and last lines
+//! plus a handle. The `read_output` tool pages through the saved output by
+//! line number, so the model reads the part it needs instead of the whole
+//! thing.
+
+use anyhow::Result;
+use std::fs;
+use tracing::debug;
+
+use crate::paths::{generate_short_id, get_tools_output_dir};
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Output larger than this (8KB) is saved and paged.
+const OUTPUT_PAGE_THRESHOLD: usize = 8 * 1024;
+
+/// Lines shown from the start and from the end of paged output.
+const PREVIEW_LINES: usize = 20;
+
+/// Characters shown from each end at most, for output with very long lines.
+const PREVIEW_CHARS: usize = 2000;
+
+/// Lines `read_output` returns when no end is given.
+const DEFAULT_PAGE_LINES: usize = 200;
+
+/// Save `output` and return its first and last lines with a handle for
+/// `read_output`, if it is larger than `OUTPUT_PAGE_THRESHOLD`. Smaller
+/// output, and output without a session to save it in, is returned as is.
+pub(super) fn paginate_output(
+    output: &str,
+    session_id: Option<&str>,
+    tool_name: &str,
+    stream_name: &str, // e.g. "stdout", "stderr" or "output"
+) -> String {
+    if output.len() <= OUTPUT_PAGE_THRESHOLD {
+        return output.to_string();
+    }
+    let Some(handle) = save_output(output, session_id, tool_name) else {
+        return output.to_string();
+    };
+
+    let lines: Vec<&str> = output.lines().collect();
+    let (head, tail) = if lines.len() > 2 * PREVIEW_LINES {
+        (
+            &lines[..PREVIEW_LINES],
+            &lines[lines.len() - PREVIEW_LINES..],
+        )
+    } else {
+        // Few but very long lines: split them between the two ends
+        lines.split_at(lines.len() / 2)
+    };
+    let head: String = head.join("\n").chars().take(PREVIEW_CHARS).collect();
+
+    format!(
+        "{}\n\n[[ {} PAGED: {} lines, {} chars. Showing the first and last lines; \
+        read the rest with read_output(handle: \"{}\", start, end) ]]\n\n{}",
+        head,
+        stream_name.to_uppercase(),
+        lines.len(),
+        output.chars().count(),
+        handle,
+        last_chars(&tail.join("\n"))
+    )
+}
+
+/// Save `output` under a new handle for `read_output`. `None` without a
+/// session, or if it couldn't be written.
+pub(super) fn save_output(
+    output: &str,
+    session_id: Option<&str>,
+    tool_name: &str,
+) -> Option<String> {
+    let handle = format!("{}_{}", tool_name, generate_short_id());
+    let tools_dir = get_tools_output_dir(session_id?);
+    match fs::create_dir_all(&tools_dir)
+        .and_then(|_| fs::write(tools_dir.join(format!("{}.txt", handle)), output))
+    {
+        Ok(()) => Some(handle),
+        Err(e) => {
+            debug!("Failed to save large output for paging: {}", e);
+            None
+        }
+    }
+}
+
+/// The last `PREVIEW_LINES` lines of `output`, at most `PREVIEW_CHARS`
+/// characters of them.
+pub(super) fn tail_preview(output: &str) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    last_chars(&lines[lines.len().saturating_sub(PREVIEW_LINES)..].join("\n"))
+}
+
+fn last_chars(text: &str) -> String {
+    let skip = text.chars().count().saturating_sub(PREVIEW_CHARS);
+    text.chars().skip(skip).collect()
+}
+
+/// Execute the `read_output` tool.
+pub async fn execute_read_output<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing read_output tool call");
+
+    let Some(handle) = tool_call.args.get("handle").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing handle argument".to_string());
+    };
+    if handle.is_empty()
+        || !handle
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Ok(format!("❌ Invalid output handle '{}'", handle));
+    }
+    let Some(session_id) = ctx.session_id else {
+        return Ok("❌ No session, so no saved output".to_string());
+    };
+    let path = get_tools_output_dir(session_id).join(format!("{}.txt", handle));
+    let output = match fs::read_to_string(&path) {
+        Ok(output) => output,
+        Err(_) => return Ok(format!("❌ No saved output with handle '{}'", handle)),
+    };
+
+    let line = |name: &str| {
+        tool_call
+            .args
+            .get(name)
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+    };
+    let start = line("start").unwrap_or(1).max(1);
+    let end = line("end").unwrap_or(start + DEFAULT_PAGE_LINES - 1);
+    Ok(page(&output, handle, start, end))
+}
+
+/// Lines `start` to `end` (1-based, inclusive) of `output`, cut short at
+/// `OUTPUT_PAGE_THRESHOLD` bytes with a pointer to where to continue.
+fn page(output: &str, handle: &str, start: usize, end: usize) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    let total = lines.len();
+    if start > total {
+        return format!(
+            "❌ Start line {} is past the end of output '{}' ({} lines)",
+            start, handle, total
+        );
+    }
+    if end < start {
+        return format!("❌ End line {} is before start line {}", end, start);
+    }
+
+    let end = end.min(total);
+    let mut text = String::new();
+    let mut last = start - 1;
+    for line in &lines[start - 1..end] {
+        if !text.is_empty() && text.len() + line.len() > OUTPUT_PAGE_THRESHOLD {
+            break;
+        }
+        text.push_str(line);
+        text.push('\n');
+        last += 1;
+    }
+
+    let mut footer = format!("📄 Lines {}-{} of {} ('{}')", start, last, total, handle);
+    if last < total {
+        footer.push_str(&format!("; continue with start: {}", last + 1));
+    }
+    format!("{}{}", text, footer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_output_is_unchanged() {
+        let output = "x".repeat(OUTPUT_PAGE_THRESHOLD + 1);
+        assert_eq!(paginate_output(&output, None, "shell", "stdout"), output);
+        assert_eq!(
+            paginate_output("small output", Some("test-session"), "shell", "stdout"),
+            "small output"
+        );
+    }
+
+    #[test]
+    fn test_large_output_is_saved_and_paged() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old_dir = std::env::current_dir().unwrap();
+        std::env::set_current_dir(temp_dir.path()).unwrap();
+
+        let output: String = (1..=1000).map(|i| format!("line {}\n", i)).collect();
+        let paged = paginate_output(&output, Some("test-sess"), "shell_stdout", "stdout");
+        let handle = paged
+            .split("handle: \"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap()
+            .to_string();
+        let saved = get_tools_output_dir("test-sess").join(format!("{}.txt", handle));
+        let saved = fs::read_to_string(saved).unwrap();
+        std::env::set_current_dir(old_dir).unwrap();
+
+        assert!(handle.starts_with("shell_stdout_"));
+        assert!(paged.starts_with("line 1\nline 2\n"));
+        assert!(paged.contains("[[ STDOUT PAGED: 1000 lines"));
+        assert!(paged.ends_with("line 999\nline 1000"));
+        assert!(!paged.contains("line 500\n"));
+
+        let page_text = page(&saved, &handle, 500, 502);
+        assert!(page_text.starts_with("line 500\nline 501\nline 502\n📄 Lines 500-502 of 1000"));
+        assert!(page_text.ends_with("continue with start: 503"));
+        assert!(page(&saved, &handle, 999, 5000)
+            .ends_with(&format!("📄 Lines 999-1000 of 1000 ('{}')", handle)));
+        assert!(page(&saved, &handle, 1001, 1002).starts_with("❌"));
+    }
+
+    #[test]
+    fn test_pages_stop_at_the_size_limit() {
+        let output: String = (0..100)
+            .map(|_| format!("{}\n", "y".repeat(1000)))
+            .collect();
+        let text = page(&output, "h", 1, 100);
+        assert!(text.len() < OUTPUT_PAGE_THRESHOLD + 100);
+        assert!(text.ends_with("📄 Lines 1-8 of 100 ('h'); continue with start: 9"));
+    }
+}
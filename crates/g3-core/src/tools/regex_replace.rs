@@ -0,0 +1,344 @@
+//! Search and replace tool: regex_replace.
+//!
+//! Applies one regex replacement to every file under a path that matches the
+//! glob filters, walking files like `grep` does (.gitignore respected). Meant
+//! for mechanical renames that don't need a model-written diff per file. A
+//! dry run reports per-file counts and a preview of the changed lines without
+//! writing anything; a real run writes all files or, if any write fails,
+//! restores the ones already written.
+
+use anyhow::{anyhow, bail, Result};
+use regex::{Regex, RegexBuilder};
+use std::fs;
+use std::path::PathBuf;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::file_ops::{write_edited_files, EditedFile};
+use super::grep::{bool_arg, glob_args, search_path, walk_files};
+
+/// Refuse to change more files than this in one call.
+const MAX_FILES: usize = 500;
+
+/// Files larger than this are skipped.
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Changed lines previewed per file in a dry run.
+const PREVIEW_PER_FILE: usize = 3;
+
+/// Files listed in the output.
+const MAX_LISTED_FILES: usize = 50;
+
+/// Replacement parameters, parsed from the tool arguments.
+#[derive(Debug, Clone)]
+pub struct ReplaceOptions {
+    pub pattern: String,
+    pub replacement: String,
+    pub path: PathBuf,
+    pub globs: Vec<String>,
+    pub case_insensitive: bool,
+    /// Treat the pattern and replacement literally
+    pub fixed_strings: bool,
+}
+
+/// A changed line: line number, before and after.
+pub type PreviewLine = (usize, String, String);
+
+/// The replacements planned for one file.
+#[derive(Debug, Clone)]
+pub struct FileReplacement {
+    /// Path relative to the search root
+    pub display_path: String,
+    pub file: EditedFile,
+    /// The first `PREVIEW_PER_FILE` changed lines
+    pub preview: Vec<PreviewLine>,
+}
+
+/// Planned replacements and how many files were searched.
+#[derive(Debug, Clone, Default)]
+pub struct ReplacePlan {
+    pub files: Vec<FileReplacement>,
+    pub files_searched: usize,
+}
+
+impl ReplacePlan {
+    pub fn replacements(&self) -> usize {
+        self.files.iter().map(|f| f.file.edits).sum()
+    }
+}
+
+/// Execute the `regex_replace` tool.
+pub async fn execute_regex_replace<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing regex_replace tool call");
+
+    let args = &tool_call.args;
+    let Some(pattern) = args.get("pattern").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing pattern argument".to_string());
+    };
+    let Some(replacement) = args.get("replacement").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing replacement argument".to_string());
+    };
+    let path = search_path(ctx, args.get("path").and_then(|v| v.as_str()));
+    if !path.exists() {
+        return Ok(format!("❌ Path not found: {}", path.display()));
+    }
+    let dry_run = bool_arg(args, "dry_run");
+
+    let options = ReplaceOptions {
+        pattern: pattern.to_string(),
+        replacement: replacement.to_string(),
+        path,
+        globs: glob_args(args),
+        case_insensitive: bool_arg(args, "case_insensitive"),
+        fixed_strings: bool_arg(args, "fixed_strings"),
+    };
+    let plan = match tokio::task::spawn_blocking(move || plan_replacements(&options)).await? {
+        Ok(plan) => plan,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+
+    if plan.files.is_empty() {
+        return Ok(format!(
+            "No matches ({} files searched)",
+            plan.files_searched
+        ));
+    }
+    if plan.files.len() > MAX_FILES {
+        return Ok(format!(
+            "❌ The pattern matches in {} files (limit {}); narrow the path or add a glob filter",
+            plan.files.len(),
+            MAX_FILES
+        ));
+    }
+    if !dry_run {
+        let files: Vec<EditedFile> = plan.files.iter().map(|f| f.file.clone()).collect();
+        if let Err(e) = write_edited_files(&files) {
+            return Ok(format!("❌ {}", e));
+        }
+    }
+    Ok(format_plan(&plan, dry_run))
+}
+
+/// Compute the replacements without writing anything. Errors are invalid
+/// patterns or globs.
+pub fn plan_replacements(options: &ReplaceOptions) -> Result<ReplacePlan> {
+    let (pattern, replacement) = if options.fixed_strings {
+        (
+            regex::escape(&options.pattern),
+            options.replacement.replace('$', "$$"),
+        )
+    } else {
+        (options.pattern.clone(), options.replacement.clone())
+    };
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(options.case_insensitive)
+        .build()
+        .map_err(|e| anyhow!("Invalid pattern: {}", e))?;
+    if regex.is_match("") {
+        bail!("The pattern matches the empty string");
+    }
+
+    let (root, walker) = walk_files(&options.path, &options.globs)?;
+    let mut plan = ReplacePlan::default();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                debug!("regex_replace: skipping unreadable entry: {}", e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file())
+            || !entry.metadata().is_ok_and(|m| m.len() <= MAX_FILE_BYTES)
+        {
+            continue;
+        }
+        plan.files_searched += 1;
+
+        // Binary and non-UTF-8 files are skipped
+        let Ok(original) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        if original.contains('\0') {
+            continue;
+        }
+        let Some((content, edits, preview)) = replace_in(&regex, &replacement, &original) else {
+            continue;
+        };
+        let insertions = content
+            .lines()
+            .count()
+            .saturating_sub(original.lines().count());
+        let deletions = original
+            .lines()
+            .count()
+            .saturating_sub(content.lines().count());
+        plan.files.push(FileReplacement {
+            display_path: entry
+                .path()
+                .strip_prefix(&root)
+                .unwrap_or(entry.path())
+                .display()
+                .to_string(),
+            file: EditedFile {
+                path: entry.path().display().to_string(),
+                original,
+                content,
+                edits,
+                insertions,
+                deletions,
+            },
+            preview,
+        });
+    }
+    Ok(plan)
+}
+
+/// Replace every match in `text`. Returns the new text, the number of
+/// replacements and a preview, or None if nothing changed.
+fn replace_in(
+    regex: &Regex,
+    replacement: &str,
+    text: &str,
+) -> Option<(String, usize, Vec<PreviewLine>)> {
+    let mut result = String::with_capacity(text.len());
+    let mut last_end = 0;
+    let mut count = 0;
+    let mut preview = Vec::new();
+    // Line of the current match, counted incrementally
+    let (mut line, mut counted_to) = (1, 0);
+    for captures in regex.captures_iter(text) {
+        let m = captures.get(0)?;
+        let mut replaced = String::new();
+        captures.expand(replacement, &mut replaced);
+        result.push_str(&text[last_end..m.start()]);
+        result.push_str(&replaced);
+        last_end = m.end();
+        count += 1;
+
+        line += text[counted_to..m.start()].matches('\n').count();
+        counted_to = m.start();
+        if preview.len() < PREVIEW_PER_FILE
+            && preview.last().map(|p: &PreviewLine| p.0) != Some(line)
+        {
+            // The whole lines the match spans, with every match in them replaced
+            let line_start = text[..m.start()].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = text[m.end()..]
+                .find('\n')
+                .map_or(text.len(), |i| m.end() + i);
+            let before = &text[line_start..line_end];
+            let after = regex.replace_all(before, replacement);
+            if before != after {
+                preview.push((line, before.to_string(), after.into_owned()));
+            }
+        }
+    }
+    result.push_str(&text[last_end..]);
+    (result != text).then_some((result, count, preview))
+}
+
+fn format_plan(plan: &ReplacePlan, dry_run: bool) -> String {
+    let mut lines = vec![if dry_run {
+        format!(
+            "🔍 Dry run: {} replacements in {} files ({} searched); nothing was written",
+            plan.replacements(),
+            plan.files.len(),
+            plan.files_searched
+        )
+    } else {
+        format!(
+            "✅ Made {} replacements in {} files ({} searched)",
+            plan.replacements(),
+            plan.files.len(),
+            plan.files_searched
+        )
+    }];
+
+    for file in plan.files.iter().take(MAX_LISTED_FILES) {
+        lines.push(format!("{}: {}", file.display_path, file.file.edits));
+        if !dry_run {
+            continue;
+        }
+        for (line, before, after) in &file.preview {
+            lines.push(format!(
+                "  {:>5} - {}",
+                line,
+                before.replace('\n', "\n        - ")
+            ));
+            lines.push(format!("        + {}", after.replace('\n', "\n        + ")));
+        }
+    }
+    if plan.files.len() > MAX_LISTED_FILES {
+        lines.push(format!(
+            "... and {} more files",
+            plan.files.len() - MAX_LISTED_FILES
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(path: &std::path::Path, pattern: &str, replacement: &str) -> ReplaceOptions {
+        ReplaceOptions {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            path: path.to_path_buf(),
+            globs: vec!["*.rs".to_string()],
+            case_insensitive: false,
+            fixed_strings: false,
+        }
+    }
+
+    #[test]
+    fn test_plan_replacements() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/a.rs"),
+            "fn old_name() {}\nfn main() { old_name(); old_name(); }\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/b.rs"), "// nothing here\n").unwrap();
+        fs::write(root.join("notes.md"), "old_name\n").unwrap();
+
+        let plan = plan_replacements(&options(root, r"\bold_(\w+)", "new_$1")).unwrap();
+        assert_eq!(plan.files_searched, 2);
+        assert_eq!(plan.replacements(), 3);
+        let file = &plan.files[0];
+        assert_eq!(file.display_path, "src/a.rs");
+        assert_eq!(
+            file.file.content,
+            "fn new_name() {}\nfn main() { new_name(); new_name(); }\n"
+        );
+        assert_eq!(
+            file.preview[0],
+            (
+                1,
+                "fn old_name() {}".to_string(),
+                "fn new_name() {}".to_string()
+            )
+        );
+        // Nothing is written by planning
+        assert!(fs::read_to_string(root.join("src/a.rs"))
+            .unwrap()
+            .contains("old_name"));
+
+        let mut literal = options(root, "old_name()", "$x");
+        literal.fixed_strings = true;
+        let plan = plan_replacements(&literal).unwrap();
+        assert!(plan.files[0].file.content.starts_with("fn $x {}"));
+
+        assert!(plan_replacements(&options(root, "a*", "b")).is_err());
+        assert!(plan_replacements(&options(root, "(", "b")).is_err());
+    }
+}
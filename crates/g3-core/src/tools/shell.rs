@@ -1,75 +1,36 @@
 //! Shell command execution tools.
 
 use anyhow::Result;
-use std::fs;
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
 use tracing::debug;
 
-use crate::paths::{generate_short_id, get_tools_output_dir};
+use crate::audit::AuditRecord;
+use crate::build_metrics;
+use crate::output_compression::compress_output_with;
 use crate::ui_writer::UiWriter;
 use crate::utils::resolve_paths_in_shell_command;
 use crate::utils::shell_escape_command;
 use crate::ToolCall;
 
 use super::executor::ToolContext;
+use super::read_output::paginate_output;
 
-/// Threshold for truncating output (8KB)
-const OUTPUT_TRUNCATE_THRESHOLD: usize = 8 * 1024;
-
-/// Number of characters to show in truncated output head
-const TRUNCATED_HEAD_SIZE: usize = 500;
-
-/// Truncate output if it exceeds the threshold, saving full content to a file.
-/// 
-/// If the output is larger than OUTPUT_TRUNCATE_THRESHOLD:
-/// 1. Saves the full output to `.g3/sessions/<session_id>/tools/<tool>_<id>_<stream>.txt`
-/// 2. Returns the first TRUNCATED_HEAD_SIZE chars with a message pointing to the file
-///
-/// If session_id is None, returns the original output unchanged.
-fn truncate_large_output(
-    output: &str,
-    session_id: Option<&str>,
-    tool_name: &str,
-    stream_name: &str, // "stdout" or "stderr"
-) -> String {
-    // If output is small enough or no session, return as-is
-    if output.len() <= OUTPUT_TRUNCATE_THRESHOLD || session_id.is_none() {
-        return output.to_string();
-    }
-
-    let session_id = session_id.unwrap();
-    let output_id = generate_short_id();
-    let tools_dir = get_tools_output_dir(session_id);
-    
-    // Create tools directory if needed
-    if let Err(e) = fs::create_dir_all(&tools_dir) {
-        debug!("Failed to create tools output dir: {}", e);
-        return output.to_string();
+/// Apply output compression if it is enabled.
+fn maybe_compress<'a>(
+    output: &'a str,
+    command: &str,
+    config: &g3_config::OutputCompressionConfig,
+) -> Cow<'a, str> {
+    if config.enabled {
+        Cow::Owned(compress_output_with(output, command, config))
+    } else {
+        Cow::Borrowed(output)
     }
-
-    let filename = format!("{}_{}.txt", tool_name, output_id);
-    let file_path = tools_dir.join(&filename);
-
-    // Save full output to file
-    if let Err(e) = fs::write(&file_path, output) {
-        debug!("Failed to save large output to file: {}", e);
-        return output.to_string();
-    }
-
-    // Truncate to first TRUNCATED_HEAD_SIZE chars (UTF-8 safe)
-    let head: String = output.chars().take(TRUNCATED_HEAD_SIZE).collect();
-    let total_chars = output.chars().count();
-    
-    format!(
-        "{}\n\n[[ {} TRUNCATED ({} total chars) ]]\nFull output saved to: {}\nUse read_file to see more.",
-        head,
-        stream_name.to_uppercase(),
-        total_chars,
-        file_path.display()
-    )
 }
 
 /// Execute the `shell` tool.
-pub async fn execute_shell<W: UiWriter>(tool_call: &ToolCall, ctx: &ToolContext<'_, W>) -> Result<String> {
+pub async fn execute_shell<W: UiWriter>(tool_call: &ToolCall, ctx: &mut ToolContext<'_, W>) -> Result<String> {
     debug!("Processing shell tool call");
     
     let command = match tool_call.args.get("command").and_then(|v| v.as_str()) {
@@ -84,9 +45,34 @@ pub async fn execute_shell<W: UiWriter>(tool_call: &ToolCall, ctx: &ToolContext<
     // First resolve any file paths with Unicode space fallback (macOS screenshot names)
     let resolved_command = resolve_paths_in_shell_command(command);
     debug!("Resolved command: {}", resolved_command);
-    let escaped_command = shell_escape_command(&resolved_command);
+    let shell = match shell(&ctx.config.shell) {
+        Ok(shell) => shell,
+        Err(refusal) => return Ok(refusal),
+    };
+    let escaped_command = shell_escape_command(&resolved_command, shell);
+
+    let call_dir = match ctx.call_working_dir(tool_call) {
+        Ok(dir) => dir.map(|dir| dir.to_string_lossy().into_owned()),
+        Err(refusal) => return Ok(refusal),
+    };
 
-    let executor = g3_execution::CodeExecutor::new();
+    let unsandboxed = tool_call
+        .args
+        .get("unsandboxed")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let container = match devcontainer(ctx).await {
+        Ok(container) => container,
+        Err(refusal) => return Ok(refusal),
+    };
+    // The devcontainer takes the place of the sandbox
+    let policy = match &container {
+        Some(_) => None,
+        None => match sandbox_policy(command, unsandboxed, ctx) {
+            Ok(policy) => policy,
+            Err(refusal) => return Ok(refusal),
+        },
+    };
 
     struct ToolOutputReceiver<'a, W: UiWriter> {
         ui_writer: &'a W,
@@ -107,31 +93,91 @@ pub async fn execute_shell<W: UiWriter>(tool_call: &ToolCall, ctx: &ToolContext<
         escaped_command, ctx.working_dir
     );
 
-    match executor
-        .execute_bash_streaming_in_dir(&escaped_command, &receiver, ctx.working_dir)
-        .await
-    {
+    let env = env_policy(&ctx.config.shell);
+    let audit = AuditRecord::new(
+        "shell",
+        command,
+        &call_dir
+            .as_deref()
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|| ctx.workspace_dir()),
+        policy.is_some(),
+    )
+    .in_container(container.as_ref().map(|c| c.name()));
+    let config: &g3_config::Config = ctx.config;
+    let compress = &config.output_compression;
+    let started = Instant::now();
+    // An approved unsandboxed command runs on its own, outside the sandboxed
+    // persistent shell
+    let persistent = ctx.config.shell.persistent
+        && shell.supports_persistence()
+        && !(unsandboxed && ctx.config.sandbox.enabled);
+    let mut executor = g3_execution::CodeExecutor::new()
+        .with_shell(shell)
+        .with_env(env);
+    if let Some(policy) = policy {
+        executor = executor.with_sandbox(policy);
+    }
+    if let Some(container) = container.clone() {
+        executor = executor.with_devcontainer(container);
+    }
+    let execution = if persistent {
+        // A call with its own working_dir runs in a subshell, leaving the
+        // persistent shell's directory and variables as they were
+        let code = match &call_dir {
+            Some(dir) => {
+                let dir = container
+                    .as_ref()
+                    .and_then(|c| c.container_path(std::path::Path::new(dir)))
+                    .unwrap_or_else(|| dir.clone());
+                shell.in_dir(&dir, &escaped_command)
+            }
+            None => escaped_command.clone(),
+        };
+        ctx.persistent_shell
+            .execute(&code, &ctx.workspace_dir(), &executor, &receiver)
+            .await
+    } else {
+        executor
+            .execute_bash_streaming_in_dir(
+                &escaped_command,
+                &receiver,
+                call_dir.as_deref().or(ctx.working_dir),
+            )
+            .await
+    };
+    match &execution {
+        Ok(result) => audit.finished(
+            Some(result.exit_code),
+            started.elapsed(),
+            &result.stdout,
+            &result.stderr,
+        ),
+        Err(e) => audit.failed(started.elapsed(), &e.to_string()),
+    }
+    .log(ctx.session_id);
+    match execution {
         Ok(result) => {
             if result.success {
-                if result.stdout.is_empty() {
-                    Ok("⚡️ ran successfully".to_string())
+                let output = if result.stdout.is_empty() {
+                    "⚡️ ran successfully".to_string()
                 } else {
-                    let stdout = result.stdout.trim();
-                    let truncated = truncate_large_output(
-                        stdout,
-                        ctx.session_id,
-                        "shell_stdout",
-                        "stdout",
-                    );
-                    Ok(truncated)
-                }
+                    let stdout = maybe_compress(&result.stdout, command, compress);
+                    paginate_output(stdout.trim(), ctx.session_id, "shell_stdout", "stdout")
+                };
+                Ok(match record_build_metrics(command, &result, started, ctx) {
+                    Some(report) => format!("{}\n\n{}", output, report),
+                    None => output,
+                })
             } else {
                 // Build error message with available information
-                let stderr = result.stderr.trim();
-                let stdout = result.stdout.trim();
+                let stderr = maybe_compress(&result.stderr, command, compress);
+                let stdout = maybe_compress(&result.stdout, command, compress);
+                let stderr = stderr.trim();
+                let stdout = stdout.trim();
                 
                 if !stderr.is_empty() {
-                    let truncated = truncate_large_output(
+                    let truncated = paginate_output(
                         stderr,
                         ctx.session_id,
                         "shell_stderr",
@@ -140,7 +186,7 @@ pub async fn execute_shell<W: UiWriter>(tool_call: &ToolCall, ctx: &ToolContext<
                     Ok(format!("❌ {}", truncated))
                 } else if !stdout.is_empty() {
                     // Sometimes error info is in stdout
-                    let truncated = truncate_large_output(
+                    let truncated = paginate_output(
                         stdout,
                         ctx.session_id,
                         "shell_stdout",
@@ -156,6 +202,137 @@ pub async fn execute_shell<W: UiWriter>(tool_call: &ToolCall, ctx: &ToolContext<
     }
 }
 
+/// The sandbox policy for a shell command, or `None` to run it unconfined:
+/// with the sandbox off, or for an `unsandboxed` command the user approved.
+/// `Err` holds the message refusing the command.
+fn sandbox_policy<W: UiWriter>(
+    command: &str,
+    unsandboxed: bool,
+    ctx: &ToolContext<'_, W>,
+) -> Result<Option<g3_execution::SandboxPolicy>, String> {
+    let config = &ctx.config.sandbox;
+    if !config.enabled {
+        return Ok(None);
+    }
+    if unsandboxed {
+        if !config.allow_unsandboxed {
+            return Err(
+                "❌ Running commands outside the sandbox is disabled (sandbox.allow_unsandboxed = false)"
+                    .to_string(),
+            );
+        }
+        if ctx.is_autonomous {
+            return Err("❌ Running a command outside the sandbox needs the user's approval, which isn't available in autonomous mode".to_string());
+        }
+        if !ctx
+            .ui_writer
+            .prompt_user_yes_no(&format!("Run outside the sandbox: `{}`?", command))
+        {
+            return Err("❌ The user declined to run the command outside the sandbox".to_string());
+        }
+        return Ok(None);
+    }
+
+    let workspace = ctx
+        .working_dir
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+    let mut policy = g3_execution::SandboxPolicy::new(&workspace);
+    for path in &config.read_only_paths {
+        policy.add_read_only(std::path::Path::new(shellexpand::tilde(path).as_ref()));
+    }
+    for path in &config.writable_paths {
+        policy.add_writable(std::path::Path::new(shellexpand::tilde(path).as_ref()));
+    }
+    policy.allow_network = config.allow_network;
+    Ok(Some(policy))
+}
+
+/// The running devcontainer to run commands in, or `None` with
+/// `shell.devcontainer` off. `Err` holds the message refusing the command.
+async fn devcontainer<W: UiWriter>(
+    ctx: &ToolContext<'_, W>,
+) -> Result<Option<g3_execution::Devcontainer>, String> {
+    if !ctx.config.shell.devcontainer {
+        return Ok(None);
+    }
+    let container =
+        g3_execution::Devcontainer::load(&ctx.workspace_dir(), &ctx.config.docker.docker)
+            .map_err(|e| format!("❌ Can't run in the devcontainer: {:#}", e))?;
+    match container.ensure_running().await {
+        Ok(true) => ctx
+            .ui_writer
+            .print_context_status(&format!("📦 Created devcontainer {}", container.name())),
+        Ok(false) => {}
+        Err(e) => {
+            return Err(format!(
+                "❌ Failed to start devcontainer {}: {:#}",
+                container.name(),
+                e
+            ))
+        }
+    }
+    Ok(Some(container))
+}
+
+/// The environment of commands run by the shell tools: `shell.strip_env`
+/// removed and `shell.env` set, with `~` and `$VAR` expanded in its values.
+fn env_policy(config: &g3_config::ShellConfig) -> g3_execution::EnvPolicy {
+    let set = config
+        .env
+        .iter()
+        .map(|(name, value)| {
+            let value = shellexpand::full(value)
+                .map(|v| v.into_owned())
+                .unwrap_or_else(|_| value.clone());
+            (name.clone(), value)
+        })
+        .collect();
+    g3_execution::EnvPolicy::new(set, config.strip_env.clone())
+}
+
+/// The shell commands run in (`shell.program`). `Err` holds the message
+/// refusing the command.
+fn shell(config: &g3_config::ShellConfig) -> Result<g3_execution::Shell, String> {
+    match &config.program {
+        Some(name) => {
+            g3_execution::Shell::from_name(name).map_err(|e| format!("❌ {} (shell.program)", e))
+        }
+        None => Ok(g3_execution::Shell::default()),
+    }
+}
+
+/// Record size/time metrics if this was a build command and tracking is enabled.
+/// Returns the formatted report to append to the tool output.
+fn record_build_metrics<W: UiWriter>(
+    command: &str,
+    result: &g3_execution::ExecutionResult,
+    started: Instant,
+    ctx: &mut ToolContext<'_, W>,
+) -> Option<String> {
+    let settings = &ctx.config.build_metrics;
+    if !settings.enabled || !build_metrics::is_build_command(command) {
+        return None;
+    }
+
+    let working_dir = ctx
+        .working_dir
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+    // Cargo reports what it compiled on stderr
+    let output = format!("{}{}", result.stdout, result.stderr);
+    let report = build_metrics::record_build(
+        command,
+        &output,
+        started.elapsed(),
+        &working_dir,
+        &settings.artifacts,
+    );
+    let formatted = report.format();
+    ctx.build_reports.push(report);
+    Some(formatted)
+}
+
 /// Execute the `background_process` tool.
 pub async fn execute_background_process<W: UiWriter>(
     tool_call: &ToolCall,
@@ -173,16 +350,62 @@ pub async fn execute_background_process<W: UiWriter>(
         None => return Ok("❌ Missing 'command' argument".to_string()),
     };
 
-    // Use provided working_dir, or fall back to context working_dir, or current dir
-    let work_dir = tool_call
-        .args
-        .get("working_dir")
-        .and_then(|v| v.as_str())
-        .map(|s| std::path::PathBuf::from(shellexpand::tilde(s).as_ref()))
-        .or_else(|| ctx.working_dir.map(std::path::PathBuf::from))
-        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+    // The call's working_dir, or the workspace
+    let work_dir = match ctx.call_dir(tool_call) {
+        Ok(dir) => dir,
+        Err(refusal) => return Ok(refusal),
+    };
 
-    match ctx.background_process_manager.start(name, command, &work_dir) {
+    let shell = match shell(&ctx.config.shell) {
+        Ok(shell) => shell,
+        Err(refusal) => return Ok(refusal),
+    };
+    let container = match devcontainer(ctx).await {
+        Ok(container) => container,
+        Err(refusal) => return Ok(refusal),
+    };
+    // The devcontainer takes the place of the sandbox
+    let policy = match &container {
+        Some(_) => None,
+        None => match sandbox_policy(command, false, ctx) {
+            Ok(policy) => policy,
+            Err(refusal) => return Ok(refusal),
+        },
+    };
+    let env = env_policy(&ctx.config.shell);
+    let audit = AuditRecord::new("background_process", command, &work_dir, policy.is_some())
+        .in_container(container.as_ref().map(|c| c.name()));
+    let manager = &ctx.background_process_manager;
+    let result = match (&container, &policy) {
+        (Some(container), _) => container
+            .command(shell, command, Some(&work_dir), &env, false)
+            .map_err(|e| e.to_string())
+            .and_then(|cmd| manager.start_command(name, command, &work_dir, cmd)),
+        (None, Some(policy)) => g3_execution::sandbox::sandboxed(shell, command, policy)
+            .map_err(|e| e.to_string())
+            .and_then(|cmd| {
+                let mut cmd = cmd.into_std();
+                env.apply(&mut cmd);
+                cmd.current_dir(&work_dir);
+                manager.start_command(name, command, &work_dir, cmd)
+            }),
+        (None, None) => manager.start_with_env(name, command, &work_dir, &env, shell),
+    };
+    match &result {
+        Ok(info) => audit.finished(None, Duration::ZERO, &format!("PID {}", info.pid), ""),
+        Err(e) => audit.failed(Duration::ZERO, e),
+    }
+    .log(ctx.session_id);
+    let container_note = match &container {
+        Some(container) => format!(
+            "\n\nIt runs in devcontainer {}, where the PID and log file above (on the \
+            host) don't exist: read the log with read_file, and stop the process with \
+            `pkill -f` on its command line.",
+            container.name()
+        ),
+        None => String::new(),
+    };
+    match result {
         Ok(info) => Ok(format!(
             "✅ Background process '{}' started\n\n\
             **PID:** {}\n\
@@ -192,7 +415,7 @@ pub async fn execute_background_process<W: UiWriter>(
             - View logs: `tail -100 {}`\n\
             - Follow logs: `tail -f {}` (blocks until Ctrl+C)\n\
             - Check status: `ps -p {}`\n\
-            - Stop process: `kill {}`",
+            - Stop process: `kill {}`{}",
             info.name,
             info.pid,
             info.log_file.display(),
@@ -200,69 +423,9 @@ pub async fn execute_background_process<W: UiWriter>(
             info.log_file.display(),
             info.log_file.display(),
             info.pid,
-            info.pid
+            info.pid,
+            container_note
         )),
         Err(e) => Ok(format!("❌ Failed to start background process: {}", e)),
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_truncate_small_output() {
-        let output = "small output";
-        let result = truncate_large_output(output, Some("test-session"), "shell", "stdout");
-        assert_eq!(result, output);
-    }
-
-    #[test]
-    fn test_truncate_no_session() {
-        let output = "x".repeat(10000);
-        let result = truncate_large_output(&output, None, "shell", "stdout");
-        assert_eq!(result, output);
-    }
-
-    #[test]
-    fn test_truncate_large_output_format() {
-        let large_output = "x".repeat(10000);
-        
-        assert!(large_output.len() > OUTPUT_TRUNCATE_THRESHOLD);
-        
-        // Test UTF-8 safe truncation
-        let head: String = large_output.chars().take(TRUNCATED_HEAD_SIZE).collect();
-        assert_eq!(head.len(), TRUNCATED_HEAD_SIZE);
-    }
-
-    #[test]
-    fn test_truncate_utf8_safe() {
-        // Test with multi-byte characters
-        let emoji_output = "🎉".repeat(5000); // Each emoji is 4 bytes
-        let head: String = emoji_output.chars().take(TRUNCATED_HEAD_SIZE).collect();
-        
-        // Should have exactly TRUNCATED_HEAD_SIZE characters (emojis)
-        assert_eq!(head.chars().count(), TRUNCATED_HEAD_SIZE);
-    }
-
-    #[test]
-    fn test_truncate_saves_to_file() {
-        use tempfile::TempDir;
-        use std::env;
-        
-        // Create a temp directory and set it as the workspace
-        let temp_dir = TempDir::new().unwrap();
-        let old_dir = env::current_dir().unwrap();
-        env::set_current_dir(temp_dir.path()).unwrap();
-        
-        let large_output = "y".repeat(10000);
-        let result = truncate_large_output(&large_output, Some("test-sess"), "shell_stdout", "stdout");
-        
-        // Should be truncated
-        assert!(result.contains("[[ STDOUT TRUNCATED"));
-        assert!(result.contains("Use read_file to see more."));
-        assert!(result.starts_with(&"y".repeat(500)));
-        
-        env::set_current_dir(old_dir).unwrap();
-    }
-}
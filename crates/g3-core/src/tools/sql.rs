@@ -0,0 +1,531 @@
+//! Database tool: sql_query.
+//!
+//! Runs a query against one of the SQLite, Postgres or MySQL connections
+//! configured under `[sql.connections]` and returns the rows as a table,
+//! cut at `sql.max_rows`. The query runs in a read-only transaction that is
+//! rolled back afterwards (`BEGIN READ ONLY` for Postgres and MySQL, on a
+//! connection opened read-only for SQLite) unless the connection sets
+//! `allow_writes` and the call asks to `write`, so the database itself
+//! rejects modifications rather than a guess at what the SQL does. Only one
+//! statement is accepted, so a `COMMIT;` can't end the transaction early.
+//!
+//! The tool is built with the `sql` feature.
+//!
+//! Queries are sent without parameters, which makes Postgres and MySQL
+//! return every value as text; cells are therefore decoded as strings
+//! regardless of the column type.
+
+use anyhow::Result;
+use futures_util::TryStreamExt;
+use sqlx::mysql::{MySqlConnection, MySqlQueryResult};
+use sqlx::postgres::{PgConnection, PgQueryResult};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteQueryResult};
+use sqlx::{Column, ConnectOptions, Connection, Database, Either, Executor, Row};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::{bool_arg, search_path};
+
+/// Longer cell values are cut.
+const MAX_CELL_CHARS: usize = 200;
+
+/// Rows and column names of a query's first result set.
+#[derive(Debug, Default)]
+pub struct QueryOutput {
+    pub columns: Vec<String>,
+    /// Cells as text; None is NULL
+    pub rows: Vec<Vec<Option<String>>>,
+    /// More rows were available than were fetched
+    pub truncated: bool,
+    pub rows_affected: u64,
+}
+
+/// Execute the `sql_query` tool.
+pub async fn execute_sql_query<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing sql_query tool call");
+
+    let args = &tool_call.args;
+    let Some(query) = args.get("query").and_then(|v| v.as_str()) else {
+        return Ok("❌ Missing query argument".to_string());
+    };
+    if let Err(e) = check_single_statement(query) {
+        return Ok(format!("❌ {}", e));
+    }
+    let config = &ctx.config.sql;
+    if config.connections.is_empty() {
+        return Ok(
+            "❌ No databases configured; add one under [sql.connections.<name>] in the config"
+                .to_string(),
+        );
+    }
+    let name = match args.get("connection").and_then(|v| v.as_str()) {
+        Some(name) => name,
+        None if config.connections.len() == 1 => {
+            config.connections.keys().next().map_or("", String::as_str)
+        }
+        None => {
+            return Ok(format!(
+                "❌ Missing connection argument (one of {})",
+                connection_names(ctx)
+            ))
+        }
+    };
+    let Some(connection) = config.connections.get(name) else {
+        return Ok(format!(
+            "❌ Unknown connection '{}' (configured: {})",
+            name,
+            connection_names(ctx)
+        ));
+    };
+    let write = bool_arg(args, "write");
+    if write && !connection.allow_writes {
+        return Ok(format!(
+            "❌ Connection '{}' is read-only; set allow_writes = true under [sql.connections.{}] to allow writes",
+            name, name
+        ));
+    }
+    let max_rows = args
+        .get("max_rows")
+        .and_then(|v| v.as_u64())
+        .map_or(config.max_rows, |n| (n as usize).min(config.max_rows));
+
+    let url = shellexpand::env(&connection.url)
+        .map(|u| u.into_owned())
+        .unwrap_or_else(|_| connection.url.clone());
+    let base = search_path(ctx, None);
+    let start = Instant::now();
+    let output = match tokio::time::timeout(
+        Duration::from_secs(config.timeout_secs),
+        run_query(&url, &base, query, write, max_rows),
+    )
+    .await
+    {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) if !write => {
+            return Ok(format!(
+                "❌ {}: {}\n(The query ran in a read-only session; writes need `write: true` on a connection with allow_writes)",
+                name, e
+            ))
+        }
+        Ok(Err(e)) => return Ok(format!("❌ {}: {}", name, e)),
+        Err(_) => {
+            return Ok(format!(
+                "❌ {}: query timed out after {}s",
+                name, config.timeout_secs
+            ))
+        }
+    };
+
+    Ok(format_output(name, &output, start.elapsed()))
+}
+
+fn connection_names<W: UiWriter>(ctx: &ToolContext<'_, W>) -> String {
+    let mut names: Vec<&str> = ctx
+        .config
+        .sql
+        .connections
+        .keys()
+        .map(String::as_str)
+        .collect();
+    names.sort();
+    names.join(", ")
+}
+
+/// Connect to `url` and run `sql`, in a read-only transaction that is
+/// rolled back unless `write`.
+pub async fn run_query(
+    url: &str,
+    base: &Path,
+    sql: &str,
+    write: bool,
+    max_rows: usize,
+) -> Result<QueryOutput, sqlx::Error> {
+    check_single_statement(sql).map_err(sqlx::Error::Protocol)?;
+    if url.starts_with("sqlite:") {
+        let mut conn = sqlite_options(url, base)?
+            .read_only(!write)
+            .connect()
+            .await?;
+        // SQLite has no read-only transactions; the connection is read-only
+        let begin = (!write).then_some("BEGIN");
+        fetch_in(
+            &mut conn,
+            begin,
+            sql,
+            max_rows,
+            SqliteQueryResult::rows_affected,
+        )
+        .await
+    } else if url.starts_with("postgres:") || url.starts_with("postgresql:") {
+        let mut conn = PgConnection::connect(url).await?;
+        let begin = (!write).then_some("BEGIN READ ONLY");
+        fetch_in(
+            &mut conn,
+            begin,
+            sql,
+            max_rows,
+            PgQueryResult::rows_affected,
+        )
+        .await
+    } else if url.starts_with("mysql:") || url.starts_with("mariadb:") {
+        let mut conn = MySqlConnection::connect(url).await?;
+        let begin = (!write).then_some("START TRANSACTION READ ONLY");
+        fetch_in(
+            &mut conn,
+            begin,
+            sql,
+            max_rows,
+            MySqlQueryResult::rows_affected,
+        )
+        .await
+    } else {
+        Err(sqlx::Error::Configuration(
+            "unsupported database URL (expected sqlite:, postgres: or mysql:)".into(),
+        ))
+    }
+}
+
+/// SQLite options for `url`, with a relative database path resolved
+/// against `base` rather than the process's directory.
+fn sqlite_options(url: &str, base: &Path) -> Result<SqliteConnectOptions, sqlx::Error> {
+    let options = SqliteConnectOptions::from_str(url)?;
+    let path = url
+        .trim_start_matches("sqlite://")
+        .trim_start_matches("sqlite:")
+        .split('?')
+        .next()
+        .unwrap_or_default();
+    if path.is_empty() || path == ":memory:" || Path::new(path).is_absolute() {
+        return Ok(options);
+    }
+    Ok(options.filename(base.join(path)))
+}
+
+/// `fetch` after running `begin`, if given, and roll back afterwards.
+async fn fetch_in<C, DB>(
+    conn: &mut C,
+    begin: Option<&str>,
+    sql: &str,
+    max_rows: usize,
+    rows_affected: fn(&DB::QueryResult) -> u64,
+) -> Result<QueryOutput, sqlx::Error>
+where
+    C: Connection<Database = DB>,
+    DB: Database,
+    for<'e> &'e mut C: Executor<'e, Database = DB>,
+    for<'r> String: sqlx::Decode<'r, DB>,
+    usize: sqlx::ColumnIndex<DB::Row>,
+{
+    let Some(begin) = begin else {
+        return fetch(&mut *conn, sql, max_rows, rows_affected).await;
+    };
+    conn.execute(begin).await?;
+    let output = fetch(&mut *conn, sql, max_rows, rows_affected).await;
+    conn.execute("ROLLBACK").await?;
+    output
+}
+
+/// Refuse SQL with more than one statement. Semicolons in strings, quoted
+/// identifiers and comments don't count, and trailing semicolons and
+/// comments are fine.
+pub fn check_single_statement(sql: &str) -> Result<(), String> {
+    // Databases differ on backslash escapes and dollar quoting, so the SQL
+    // must be one statement whichever way it is read
+    for backslash_escapes in [false, true] {
+        for dollar_quotes in [false, true] {
+            if has_second_statement(sql, backslash_escapes, dollar_quotes) {
+                return Err(
+                    "Only one SQL statement can be run per call; send them separately".to_string(),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether anything but semicolons, whitespace and comments follows the
+/// first statement.
+fn has_second_statement(sql: &str, backslash_escapes: bool, dollar_quotes: bool) -> bool {
+    let mut chars = sql.char_indices().peekable();
+    let mut statement_ended = false;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            ';' => statement_ended = true,
+            c if c.is_whitespace() => {}
+            '-' if chars.peek().map(|(_, c)| *c) == Some('-') => {
+                chars.find(|(_, c)| *c == '\n');
+            }
+            '/' if chars.peek().map(|(_, c)| *c) == Some('*') => {
+                chars.next();
+                let mut previous = ' ';
+                for (_, c) in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ if statement_ended => return true,
+            '\'' | '"' | '`' => {
+                while let Some((_, next)) = chars.next() {
+                    if next == '\\' && backslash_escapes {
+                        chars.next();
+                    } else if next == c {
+                        // A doubled quote is an escaped one
+                        if chars.peek().map(|(_, n)| *n) != Some(c) {
+                            break;
+                        }
+                        chars.next();
+                    }
+                }
+            }
+            '$' if dollar_quotes => {
+                let rest = &sql[i + 1..];
+                let Some(tag) = rest.find('$').map(|end| &rest[..end]) else {
+                    continue;
+                };
+                if !tag.chars().all(|c| c.is_alphanumeric() || c == '_')
+                    || tag.starts_with(|c: char| c.is_ascii_digit())
+                {
+                    continue;
+                }
+                let delimiter = format!("${}$", tag);
+                let body = i + delimiter.len();
+                let close = sql[body..]
+                    .find(&delimiter)
+                    .map_or(sql.len(), |at| body + at + delimiter.len());
+                while chars.peek().is_some_and(|(j, _)| *j < close) {
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Collect the first result set of `sql`, at most `max_rows` rows.
+async fn fetch<'c, DB, E>(
+    executor: E,
+    sql: &'c str,
+    max_rows: usize,
+    rows_affected: fn(&DB::QueryResult) -> u64,
+) -> Result<QueryOutput, sqlx::Error>
+where
+    DB: Database,
+    E: Executor<'c, Database = DB>,
+    for<'r> String: sqlx::Decode<'r, DB>,
+    usize: sqlx::ColumnIndex<DB::Row>,
+{
+    let mut output = QueryOutput::default();
+    let mut first_set_done = false;
+    let mut stream = executor.fetch_many(sql);
+    while let Some(step) = stream.try_next().await? {
+        match step {
+            Either::Left(result) => {
+                output.rows_affected += rows_affected(&result);
+                first_set_done |= !output.columns.is_empty();
+            }
+            Either::Right(_) if first_set_done => {}
+            Either::Right(row) => {
+                if output.rows.len() == max_rows {
+                    output.truncated = true;
+                    break;
+                }
+                if output.columns.is_empty() {
+                    output.columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                }
+                let cells = (0..row.len())
+                    .map(|i| {
+                        row.try_get_unchecked::<Option<String>, _>(i)
+                            .unwrap_or_else(|_| Some("<binary>".to_string()))
+                    })
+                    .collect();
+                output.rows.push(cells);
+            }
+        }
+    }
+    Ok(output)
+}
+
+fn format_output(name: &str, output: &QueryOutput, elapsed: Duration) -> String {
+    if output.columns.is_empty() {
+        return format!(
+            "✅ {}: {} rows affected ({} ms)",
+            name,
+            output.rows_affected,
+            elapsed.as_millis()
+        );
+    }
+
+    let mut lines = vec![format!(
+        "🗄️ {}: {}{} rows ({} ms)",
+        name,
+        if output.truncated { "first " } else { "" },
+        output.rows.len(),
+        elapsed.as_millis()
+    )];
+    lines.push(String::new());
+    lines.extend(format_table(&output.columns, &output.rows));
+    if output.truncated {
+        lines.push(String::new());
+        lines.push("... more rows not shown; add a LIMIT or aggregate to see the rest".to_string());
+    }
+    lines.join("\n")
+}
+
+/// A Markdown table with padded columns.
+fn format_table(columns: &[String], rows: &[Vec<Option<String>>]) -> Vec<String> {
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| format_cell(cell.as_deref()))
+                .collect()
+        })
+        .collect();
+    let columns: Vec<String> = columns.iter().map(|c| format_cell(Some(c))).collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .chain(std::iter::once(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        format!("| {} |", padded.join(" | "))
+    };
+
+    let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    let mut lines = vec![line(&columns), line(&separator)];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines
+}
+
+/// One line, pipes escaped, cut at `MAX_CELL_CHARS`.
+fn format_cell(cell: Option<&str>) -> String {
+    let Some(cell) = cell else {
+        return "NULL".to_string();
+    };
+    let mut cell = cell
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('|', "\\|");
+    if let Some((end, _)) = cell.char_indices().nth(MAX_CELL_CHARS) {
+        cell.truncate(end);
+        cell.push('…');
+    }
+    cell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_query_sqlite() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = "sqlite://app.db?mode=rwc";
+        for sql in [
+            "CREATE TABLE users (id INTEGER, name TEXT)",
+            "INSERT INTO users VALUES (1, 'Ada'), (2, NULL), (3, 'Grace');",
+        ] {
+            run_query(url, dir.path(), sql, true, 10).await.unwrap();
+        }
+        assert!(dir.path().join("app.db").exists());
+
+        let output = run_query(url, dir.path(), "SELECT * FROM users", false, 2)
+            .await
+            .unwrap();
+        assert_eq!(output.columns, ["id", "name"]);
+        assert_eq!(
+            output.rows,
+            [
+                vec![Some("1".to_string()), Some("Ada".to_string())],
+                vec![Some("2".to_string()), None],
+            ]
+        );
+        assert!(output.truncated);
+
+        // Read-only sessions reject writes, and can't be ended early
+        assert!(run_query(url, dir.path(), "DELETE FROM users", false, 10)
+            .await
+            .is_err());
+        assert!(
+            run_query(url, dir.path(), "COMMIT; DELETE FROM users", false, 10)
+                .await
+                .is_err()
+        );
+        let output = run_query(url, dir.path(), "SELECT count(*) FROM users", false, 10)
+            .await
+            .unwrap();
+        assert_eq!(output.rows, [vec![Some("3".to_string())]]);
+    }
+
+    #[test]
+    fn test_check_single_statement() {
+        for sql in [
+            "SELECT 1",
+            "SELECT 1;",
+            "SELECT 1; -- done",
+            "SELECT ';' AS semi, \"a;b\" FROM t /* ; */",
+            "SELECT 'it''s; fine'",
+            "SELECT $body$ a b $body$, $1",
+            "SELECT 1 ;\n  /* trailing */ ;",
+        ] {
+            assert_eq!(check_single_statement(sql), Ok(()), "{}", sql);
+        }
+        for sql in [
+            "COMMIT; DELETE FROM users",
+            "SELECT 1; SELECT 2",
+            "SELECT 'a'';'; DROP TABLE t",
+            "SELECT $$;$$; DROP TABLE t",
+            "SELECT 1 -- note\n; UPDATE t SET a = 1",
+            // One statement to Postgres, three to MySQL (and the reverse)
+            "SELECT $b$ ; DROP TABLE t; $b$",
+            "SELECT 'a\\'; DROP TABLE t; --'",
+            "SELECT 'a\\''; DROP TABLE t",
+        ] {
+            assert!(check_single_statement(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_format_table() {
+        let columns = vec!["id".to_string(), "note".to_string()];
+        let rows = vec![
+            vec![Some("1".to_string()), Some("a|b\nc".to_string())],
+            vec![Some("22".to_string()), None],
+        ];
+        assert_eq!(
+            format_table(&columns, &rows),
+            [
+                "| id  | note    |",
+                "| --- | ------- |",
+                "| 1   | a\\|b\\nc |",
+                "| 22  | NULL    |",
+            ]
+        );
+    }
+}
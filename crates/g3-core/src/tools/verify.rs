@@ -0,0 +1,73 @@
+//! Verification tool: verify.
+//!
+//! Starts a verification command against a git worktree snapshot of the
+//! current working tree and returns immediately. See `crate::verification`.
+
+use anyhow::Result;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::ui_writer::UiWriter;
+use crate::verification::spawn_verification;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+
+/// Execute the verify tool.
+pub async fn execute_verify<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing verify tool call");
+
+    let command = tool_call
+        .args
+        .get("command")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .or_else(|| ctx.config.verification.command.clone());
+    let Some(command) = command.filter(|c| !c.trim().is_empty()) else {
+        return Ok("❌ No command given and verification.command is not configured".to_string());
+    };
+
+    let working_dir = match ctx.call_dir(tool_call) {
+        Ok(dir) => dir,
+        Err(refusal) => return Ok(refusal),
+    };
+    let toplevel = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(&working_dir)
+        .output()
+        .await?;
+    if !toplevel.status.success() {
+        return Ok(format!(
+            "❌ {} is not inside a git repository; verify needs git worktrees",
+            working_dir.display()
+        ));
+    }
+    let repo_dir = PathBuf::from(String::from_utf8_lossy(&toplevel.stdout).trim());
+    // The snapshot is checked out at the worktree root; run where the call
+    // asked to, at the same place inside it.
+    let subdir = working_dir
+        .canonicalize()
+        .ok()
+        .and_then(|dir| dir.strip_prefix(&repo_dir).ok().map(PathBuf::from))
+        .unwrap_or_default();
+
+    let timeout = Duration::from_secs(ctx.config.verification.timeout_secs);
+    let id = spawn_verification(
+        ctx.pending_verification_manager,
+        &command,
+        repo_dir,
+        subdir,
+        timeout,
+    );
+    let running = ctx.pending_verification_manager.running_count();
+
+    Ok(format!(
+        "🧪 Verification started (id: `{}`): `{}`\n\nRunning in the background against a snapshot of the current working tree ({} run(s) in progress). Continue with other work; the result will be provided automatically when it finishes.",
+        id, command, running
+    ))
+}
@@ -0,0 +1,356 @@
+//! Condition polling tool: wait_for.
+//!
+//! Polls until a file exists, a URL answers with the expected status, a TCP
+//! port accepts connections or a process exits, and reports how long it
+//! took, so coordinating with a server started by `background_process`
+//! doesn't need guessed `sleep` calls. While waiting on a server, the wait
+//! can be tied to its background process: if that exits first, the wait
+//! stops right away with the end of its log.
+
+use anyhow::Result;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, System};
+use tracing::debug;
+
+use crate::background_process::BackgroundProcessManager;
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::search_path;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+const MAX_TIMEOUT_SECS: u64 = 600;
+const DEFAULT_INTERVAL_MS: u64 = 500;
+const MIN_INTERVAL_MS: u64 = 100;
+
+/// Time allowed for a single URL or port check.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Log lines shown when a background process exits during a wait.
+const LOG_TAIL_LINES: usize = 20;
+
+/// What to wait for.
+#[derive(Debug, Clone)]
+pub enum Condition {
+    FileExists(PathBuf),
+    /// The URL answers with this status, or any 2xx when None
+    Url {
+        url: String,
+        status: Option<u16>,
+    },
+    PortOpen {
+        host: String,
+        port: u16,
+    },
+    /// A background process (by name) exits
+    BackgroundExit(String),
+    /// Any process exits
+    PidExit(u32),
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FileExists(path) => write!(f, "{} to exist", path.display()),
+            Self::Url {
+                url,
+                status: Some(status),
+            } => write!(f, "{} to return {}", url, status),
+            Self::Url { url, status: None } => write!(f, "{} to return 2xx", url),
+            Self::PortOpen { host, port } => write!(f, "{}:{} to accept connections", host, port),
+            Self::BackgroundExit(name) => write!(f, "background process '{}' to exit", name),
+            Self::PidExit(pid) => write!(f, "process {} to exit", pid),
+        }
+    }
+}
+
+/// How a wait ended.
+#[derive(Debug)]
+pub enum WaitOutcome {
+    Satisfied {
+        elapsed: Duration,
+        checks: u32,
+    },
+    /// Timed out; `last` says what the final check saw
+    TimedOut {
+        last: String,
+    },
+    /// The guarding background process exited first
+    GuardExited,
+}
+
+/// Execute the `wait_for` tool.
+pub async fn execute_wait_for<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing wait_for tool call");
+
+    let args = &tool_call.args;
+    let condition = match parse_condition(args, ctx) {
+        Ok(condition) => condition,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    let manager = ctx.background_process_manager;
+    let guard = args.get("background_process").and_then(|v| v.as_str());
+    if let Some(guard) = guard {
+        if manager.get(guard).is_none() {
+            return Ok(format!("❌ No background process named '{}'", guard));
+        }
+    }
+    let timeout = Duration::from_secs(
+        args.get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_TIMEOUT_SECS, |t| t.min(MAX_TIMEOUT_SECS)),
+    );
+    let interval = Duration::from_millis(
+        args.get("interval_ms")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_INTERVAL_MS, |i| i.max(MIN_INTERVAL_MS)),
+    );
+
+    match wait(&condition, manager, guard, timeout, interval).await {
+        WaitOutcome::Satisfied { elapsed, checks } => Ok(format!(
+            "✅ Done waiting for {} after {:.1}s ({} check{})",
+            condition,
+            elapsed.as_secs_f64(),
+            checks,
+            if checks == 1 { "" } else { "s" }
+        )),
+        WaitOutcome::TimedOut { last } => Ok(format!(
+            "❌ Timed out after {}s waiting for {} (last check: {})",
+            timeout.as_secs(),
+            condition,
+            last
+        )),
+        WaitOutcome::GuardExited => {
+            let name = guard.unwrap_or_default();
+            let mut result = format!(
+                "❌ Background process '{}' exited while waiting for {}",
+                name, condition
+            );
+            if let Some(tail) = manager.get(name).and_then(|info| log_tail(&info.log_file)) {
+                result.push_str(&format!("\n\nLast lines of its log:\n{}", tail));
+            }
+            Ok(result)
+        }
+    }
+}
+
+/// The condition named by the arguments; exactly one must be given.
+fn parse_condition<W: UiWriter>(
+    args: &serde_json::Value,
+    ctx: &ToolContext<'_, W>,
+) -> Result<Condition, String> {
+    let mut conditions = Vec::new();
+    if let Some(path) = args.get("file").and_then(|v| v.as_str()) {
+        conditions.push(Condition::FileExists(search_path(ctx, Some(path))));
+    }
+    if let Some(url) = args.get("url").and_then(|v| v.as_str()) {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(format!("Only http and https URLs are supported: {}", url));
+        }
+        let status = args
+            .get("status")
+            .and_then(|v| v.as_u64())
+            .and_then(|s| u16::try_from(s).ok());
+        conditions.push(Condition::Url {
+            url: url.to_string(),
+            status,
+        });
+    }
+    if let Some(port) = args.get("port").and_then(|v| v.as_u64()) {
+        let port = u16::try_from(port).map_err(|_| format!("Invalid port {}", port))?;
+        let host = args
+            .get("host")
+            .and_then(|v| v.as_str())
+            .unwrap_or("127.0.0.1");
+        conditions.push(Condition::PortOpen {
+            host: host.to_string(),
+            port,
+        });
+    }
+    if let Some(process) = args.get("process_exit") {
+        let manager = ctx.background_process_manager;
+        let pid = match process {
+            serde_json::Value::Number(pid) => pid.as_u64(),
+            serde_json::Value::String(name) if manager.get(name).is_none() => name.parse().ok(),
+            _ => None,
+        };
+        match (process, pid) {
+            (_, Some(pid)) => {
+                let pid = u32::try_from(pid).map_err(|_| format!("Invalid pid {}", pid))?;
+                // Our own children must be reaped through the manager, or
+                // they linger as zombies that never look exited
+                let name = manager
+                    .list()
+                    .into_iter()
+                    .find(|info| info.pid == pid)
+                    .map(|info| info.name);
+                conditions.push(name.map_or(Condition::PidExit(pid), Condition::BackgroundExit));
+            }
+            (serde_json::Value::String(name), None) if manager.get(name).is_some() => {
+                conditions.push(Condition::BackgroundExit(name.clone()));
+            }
+            _ => {
+                return Err(format!(
+                    "process_exit must name a background process or be a pid, got {}",
+                    process
+                ))
+            }
+        }
+    }
+
+    match conditions.len() {
+        1 => Ok(conditions.remove(0)),
+        0 => Err("Nothing to wait for: pass one of file, url, port or process_exit".to_string()),
+        _ => Err("Pass only one of file, url, port or process_exit".to_string()),
+    }
+}
+
+/// Poll `condition` every `interval` until it holds, `timeout` passes, or
+/// the `guard` background process exits.
+pub async fn wait(
+    condition: &Condition,
+    manager: &BackgroundProcessManager,
+    guard: Option<&str>,
+    timeout: Duration,
+    interval: Duration,
+) -> WaitOutcome {
+    let start = Instant::now();
+    let client = reqwest::Client::builder()
+        .timeout(CHECK_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    let mut checks = 0;
+    loop {
+        checks += 1;
+        let last = match check(condition, manager, &client).await {
+            Ok(()) => {
+                return WaitOutcome::Satisfied {
+                    elapsed: start.elapsed(),
+                    checks,
+                }
+            }
+            Err(last) => last,
+        };
+        if guard.is_some_and(|name| !manager.is_running(name)) {
+            return WaitOutcome::GuardExited;
+        }
+        if start.elapsed() + interval > timeout {
+            return WaitOutcome::TimedOut { last };
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Ok when the condition holds, otherwise what was seen instead.
+async fn check(
+    condition: &Condition,
+    manager: &BackgroundProcessManager,
+    client: &reqwest::Client,
+) -> Result<(), String> {
+    match condition {
+        Condition::FileExists(path) => {
+            if path.exists() {
+                Ok(())
+            } else {
+                Err("file not found".to_string())
+            }
+        }
+        Condition::Url { url, status } => match client.get(url).send().await {
+            Ok(response) => {
+                let actual = response.status();
+                let ok = match status {
+                    Some(status) => actual.as_u16() == *status,
+                    None => actual.is_success(),
+                };
+                if ok {
+                    Ok(())
+                } else {
+                    Err(format!("status {}", actual))
+                }
+            }
+            Err(e) if e.is_connect() => Err("connection refused".to_string()),
+            Err(e) if e.is_timeout() => Err("request timed out".to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+        Condition::PortOpen { host, port } => {
+            let connect = tokio::net::TcpStream::connect((host.as_str(), *port));
+            match tokio::time::timeout(CHECK_TIMEOUT, connect).await {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("connection timed out".to_string()),
+            }
+        }
+        Condition::BackgroundExit(name) => {
+            if manager.is_running(name) {
+                Err("still running".to_string())
+            } else {
+                Ok(())
+            }
+        }
+        Condition::PidExit(pid) => {
+            let mut system = System::new();
+            if system.refresh_process(Pid::from_u32(*pid)) {
+                Err("still running".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The last lines of a background process's log.
+fn log_tail(path: &std::path::Path) -> Option<String> {
+    let log = std::fs::read_to_string(path).ok()?;
+    let lines: Vec<&str> = log.lines().collect();
+    let start = lines.len().saturating_sub(LOG_TAIL_LINES);
+    Some(lines[start..].join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for_file_and_port() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = BackgroundProcessManager::new(dir.path().join("logs"));
+        let interval = Duration::from_millis(20);
+
+        let path = dir.path().join("ready");
+        let writer = {
+            let path = path.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                std::fs::write(path, "").unwrap();
+            })
+        };
+        let outcome = wait(
+            &Condition::FileExists(path),
+            &manager,
+            None,
+            Duration::from_secs(5),
+            interval,
+        )
+        .await;
+        assert!(matches!(outcome, WaitOutcome::Satisfied { checks, .. } if checks > 1));
+        writer.await.unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let open = Condition::PortOpen {
+            host: "127.0.0.1".to_string(),
+            port,
+        };
+        let outcome = wait(&open, &manager, None, Duration::from_secs(5), interval).await;
+        assert!(matches!(outcome, WaitOutcome::Satisfied { .. }));
+
+        drop(listener);
+        let outcome = wait(&open, &manager, None, Duration::from_millis(50), interval).await;
+        assert!(matches!(outcome, WaitOutcome::TimedOut { .. }));
+    }
+}
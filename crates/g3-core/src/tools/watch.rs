@@ -0,0 +1,395 @@
+//! File watch tool: watch_files.
+//!
+//! Polls a directory (optionally narrowed by globs) for up to a timeout and
+//! reports the files created, modified and deleted meanwhile, so the agent
+//! can confirm that codegen or a build step produced its outputs. Without
+//! `expect`, the watch ends once changes have been seen and the tree has
+//! been quiet for the settle time; with `expect`, it ends as soon as every
+//! expected pattern has a created or modified file.
+
+use anyhow::Result;
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::WalkBuilder;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::debug;
+
+use crate::build_metrics::format_bytes;
+use crate::ui_writer::UiWriter;
+use crate::ToolCall;
+
+use super::executor::ToolContext;
+use super::grep::{bool_arg, glob_args, search_path};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MAX_TIMEOUT_SECS: u64 = 600;
+const DEFAULT_INTERVAL_MS: u64 = 250;
+const MIN_INTERVAL_MS: u64 = 50;
+const DEFAULT_SETTLE_MS: u64 = 1000;
+
+/// Files tracked per watch; larger trees must be narrowed.
+const MAX_FILES: usize = 20_000;
+
+/// Changed files listed in the report.
+const MAX_LISTED: usize = 100;
+
+/// Size and modification time of a file.
+pub type Stamp = (u64, Option<SystemTime>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// A file that changed during the watch.
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub kind: ChangeKind,
+    /// When the change was first seen, from the start of the watch
+    pub at: Duration,
+    /// Size after the change
+    pub size: Option<u64>,
+}
+
+/// Execute the `watch_files` tool.
+pub async fn execute_watch_files<W: UiWriter>(
+    tool_call: &ToolCall,
+    ctx: &ToolContext<'_, W>,
+) -> Result<String> {
+    debug!("Processing watch_files tool call");
+
+    let args = &tool_call.args;
+    let root = search_path(ctx, args.get("path").and_then(|v| v.as_str()));
+    if !root.is_dir() {
+        return Ok(format!("❌ Not a directory: {}", root.display()));
+    }
+    let globs = glob_args(args);
+    let no_ignore = bool_arg(args, "no_ignore");
+    let expect: Vec<String> = args
+        .get("expect")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str().map(String::from))
+        .collect();
+    let timeout = Duration::from_secs(
+        args.get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_TIMEOUT_SECS, |t| t.min(MAX_TIMEOUT_SECS)),
+    );
+    let interval = Duration::from_millis(
+        args.get("interval_ms")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_INTERVAL_MS, |i| i.max(MIN_INTERVAL_MS)),
+    );
+    let settle = Duration::from_millis(
+        args.get("settle_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_SETTLE_MS),
+    );
+
+    let filter = match glob_filter(&root, &globs) {
+        Ok(filter) => filter,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    let expected = match expect
+        .iter()
+        .map(|pattern| {
+            glob_filter(&root, std::slice::from_ref(pattern)).map(|m| (pattern.clone(), m))
+        })
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(expected) => expected,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+
+    let snap = {
+        let (root, filter) = (root.clone(), filter.clone());
+        move || snapshot(&root, &filter, no_ignore)
+    };
+    let mut previous = match tokio::task::spawn_blocking(snap.clone()).await? {
+        Ok(files) => files,
+        Err(e) => return Ok(format!("❌ {}", e)),
+    };
+    ctx.ui_writer.print_context_status(&format!(
+        "👀 Watching {} files under {} for up to {}s...",
+        previous.len(),
+        root.display(),
+        timeout.as_secs()
+    ));
+
+    let start = Instant::now();
+    let mut changes: BTreeMap<PathBuf, FileChange> = BTreeMap::new();
+    let mut last_change: Option<Instant> = None;
+    let satisfied = loop {
+        if !expected.is_empty() && missing(&expected, &changes).is_empty() {
+            break true;
+        }
+        if expected.is_empty() && last_change.is_some_and(|t| t.elapsed() >= settle) {
+            break true;
+        }
+        if start.elapsed() + interval > timeout {
+            break false;
+        }
+        tokio::time::sleep(interval).await;
+        let current = match tokio::task::spawn_blocking(snap.clone()).await? {
+            Ok(files) => files,
+            Err(e) => return Ok(format!("❌ {}", e)),
+        };
+        if record_changes(&previous, &current, start.elapsed(), &mut changes) {
+            last_change = Some(Instant::now());
+        }
+        previous = current;
+    };
+
+    Ok(format_report(
+        &root,
+        &changes,
+        satisfied,
+        &missing(&expected, &changes),
+        start.elapsed(),
+    ))
+}
+
+/// Include-globs as an override matcher (every path matches when empty).
+fn glob_filter(root: &Path, globs: &[String]) -> Result<Override, String> {
+    let mut builder = OverrideBuilder::new(root);
+    for glob in globs {
+        builder
+            .add(glob)
+            .map_err(|e| format!("Invalid glob '{}': {}", glob, e))?;
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Stamps of the files under `root` passing `filter`, keyed by path
+/// relative to `root`.
+fn snapshot(
+    root: &Path,
+    filter: &Override,
+    no_ignore: bool,
+) -> Result<HashMap<PathBuf, Stamp>, String> {
+    let walker = WalkBuilder::new(root)
+        .standard_filters(!no_ignore)
+        .overrides(filter.clone())
+        .build();
+    let mut files = HashMap::new();
+    for entry in walker.flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let path = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        files.insert(
+            path.to_path_buf(),
+            (metadata.len(), metadata.modified().ok()),
+        );
+        if files.len() > MAX_FILES {
+            return Err(format!(
+                "More than {} files under {}; narrow the path or add a glob",
+                MAX_FILES,
+                root.display()
+            ));
+        }
+    }
+    Ok(files)
+}
+
+/// Add the differences between two snapshots to `changes`. A file created
+/// and then modified stays created; one created and deleted again is
+/// dropped. Returns whether anything changed.
+pub fn record_changes(
+    previous: &HashMap<PathBuf, Stamp>,
+    current: &HashMap<PathBuf, Stamp>,
+    at: Duration,
+    changes: &mut BTreeMap<PathBuf, FileChange>,
+) -> bool {
+    let mut changed = false;
+    for (path, stamp) in current {
+        let kind = match previous.get(path) {
+            None => ChangeKind::Created,
+            Some(old) if old != stamp => ChangeKind::Modified,
+            Some(_) => continue,
+        };
+        changed = true;
+        let size = Some(stamp.0);
+        changes
+            .entry(path.clone())
+            .and_modify(|change| {
+                change.size = size;
+                if change.kind == ChangeKind::Deleted {
+                    change.kind = ChangeKind::Modified;
+                }
+            })
+            .or_insert(FileChange { kind, at, size });
+    }
+    for path in previous.keys().filter(|p| !current.contains_key(*p)) {
+        changed = true;
+        match changes.get(path).map(|c| c.kind) {
+            Some(ChangeKind::Created) => {
+                changes.remove(path);
+            }
+            _ => {
+                changes.insert(
+                    path.clone(),
+                    FileChange {
+                        kind: ChangeKind::Deleted,
+                        at,
+                        size: None,
+                    },
+                );
+            }
+        }
+    }
+    changed
+}
+
+/// Expected patterns that no created or modified file matches yet.
+fn missing(
+    expected: &[(String, Override)],
+    changes: &BTreeMap<PathBuf, FileChange>,
+) -> Vec<String> {
+    expected
+        .iter()
+        .filter(|(_, matcher)| {
+            !changes.iter().any(|(path, change)| {
+                change.kind != ChangeKind::Deleted && matcher.matched(path, false).is_whitelist()
+            })
+        })
+        .map(|(pattern, _)| pattern.clone())
+        .collect()
+}
+
+fn format_report(
+    root: &Path,
+    changes: &BTreeMap<PathBuf, FileChange>,
+    satisfied: bool,
+    missing: &[String],
+    elapsed: Duration,
+) -> String {
+    let mut lines = Vec::new();
+    let count = |kind| changes.values().filter(|c| c.kind == kind).count();
+    let summary = format!(
+        "{} created, {} modified, {} deleted under {} in {:.1}s",
+        count(ChangeKind::Created),
+        count(ChangeKind::Modified),
+        count(ChangeKind::Deleted),
+        root.display(),
+        elapsed.as_secs_f64()
+    );
+    lines.push(match (satisfied, changes.is_empty()) {
+        (true, _) => format!("✅ {}", summary),
+        (false, true) => format!(
+            "❌ No changes under {} in {:.1}s",
+            root.display(),
+            elapsed.as_secs_f64()
+        ),
+        (false, false) => format!("❌ Timed out: {}", summary),
+    });
+    if !missing.is_empty() {
+        lines.push(format!("Still missing: {}", missing.join(", ")));
+    }
+
+    let mut sorted: Vec<_> = changes.iter().collect();
+    sorted.sort_by_key(|(path, change)| (change.at, (*path).clone()));
+    for (path, change) in sorted.iter().take(MAX_LISTED) {
+        let kind = match change.kind {
+            ChangeKind::Created => "created ",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Deleted => "deleted ",
+        };
+        let size = change
+            .size
+            .map(|s| format!("{}, ", format_bytes(s)))
+            .unwrap_or_default();
+        lines.push(format!(
+            "{} {} ({}+{:.1}s)",
+            kind,
+            path.display(),
+            size,
+            change.at.as_secs_f64()
+        ));
+    }
+    if changes.len() > MAX_LISTED {
+        lines.push(format!("... and {} more", changes.len() - MAX_LISTED));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_changes() {
+        let stamp = |len| (len, None);
+        let previous: HashMap<PathBuf, Stamp> = [
+            (PathBuf::from("src/lib.rs"), stamp(10)),
+            (PathBuf::from("gen/old.rs"), stamp(5)),
+        ]
+        .into();
+        let current: HashMap<PathBuf, Stamp> = [
+            (PathBuf::from("src/lib.rs"), stamp(12)),
+            (PathBuf::from("gen/api.rs"), stamp(300)),
+        ]
+        .into();
+        let mut changes = BTreeMap::new();
+        assert!(record_changes(
+            &previous,
+            &current,
+            Duration::from_secs(1),
+            &mut changes
+        ));
+        let kinds: Vec<_> = changes
+            .iter()
+            .map(|(p, c)| (p.to_str().unwrap(), c.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("gen/api.rs", ChangeKind::Created),
+                ("gen/old.rs", ChangeKind::Deleted),
+                ("src/lib.rs", ChangeKind::Modified),
+            ]
+        );
+
+        // Growing a created file keeps it created; deleting it forgets it
+        let mut grown = current.clone();
+        grown.insert(PathBuf::from("gen/api.rs"), stamp(400));
+        assert!(record_changes(
+            &current,
+            &grown,
+            Duration::from_secs(2),
+            &mut changes
+        ));
+        assert_eq!(changes[Path::new("gen/api.rs")].kind, ChangeKind::Created);
+        assert_eq!(changes[Path::new("gen/api.rs")].size, Some(400));
+        assert!(!record_changes(
+            &grown,
+            &grown,
+            Duration::from_secs(3),
+            &mut changes
+        ));
+
+        let expected = vec![(
+            "gen/*.rs".to_string(),
+            glob_filter(Path::new("/ws"), &["gen/*.rs".to_string()]).unwrap(),
+        )];
+        assert!(missing(&expected, &changes).is_empty());
+        grown.remove(Path::new("gen/api.rs"));
+        assert!(record_changes(
+            &current,
+            &grown,
+            Duration::from_secs(4),
+            &mut changes
+        ));
+        assert!(!changes.contains_key(Path::new("gen/api.rs")));
+        // The deleted gen/old.rs doesn't satisfy the expectation
+        assert_eq!(missing(&expected, &changes), vec!["gen/*.rs"]);
+    }
+}
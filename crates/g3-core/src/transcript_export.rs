@@ -0,0 +1,493 @@
+//! Exporting a session as a readable transcript.
+//!
+//! A saved session (`.g3/sessions/<id>/`) is turned into a Markdown or HTML
+//! document that can be pasted into a PR or a design doc: the user and
+//! assistant turns in order, each tool call as a collapsed `<details>` block
+//! with its arguments, result and timing, and the session's token and cost
+//! totals at the top. Timing comes from the tool transcript and totals from
+//! the telemetry log, so sessions without them export without those parts.
+
+use anyhow::{Context, Result};
+use g3_providers::{Message, MessageRole};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::compressed_file;
+use crate::paths::{get_session_file, get_telemetry_file, get_tool_transcript_file};
+use crate::streaming::format_duration;
+use crate::telemetry::TurnTelemetry;
+use crate::tool_transcript::{self, args_digest, ToolCallMetric};
+use crate::utils::truncate_chars;
+
+/// Characters of a tool result kept in the transcript.
+const MAX_RESULT_CHARS: usize = 10_000;
+
+/// The format of an exported transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+}
+
+impl ExportFormat {
+    /// The format for a file at `path`: HTML for `.html` and `.htm`,
+    /// Markdown otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm") => {
+                Self::Html
+            }
+            _ => Self::Markdown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    User(String),
+    Assistant(String),
+    ToolCall {
+        tool: String,
+        args: serde_json::Value,
+        result: Option<String>,
+        metric: Option<ToolCallMetric>,
+    },
+}
+
+/// Token, cost and time totals over a session's turns.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Totals {
+    turns: usize,
+    duration_ms: u64,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    total_tokens: u64,
+    cost_usd: Option<f64>,
+}
+
+/// A session, ready to render.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transcript {
+    session_id: String,
+    title: String,
+    entries: Vec<Entry>,
+    totals: Totals,
+}
+
+impl Transcript {
+    /// Read session `session_id` from `.g3/sessions/`.
+    pub fn load(session_id: &str) -> Result<Self> {
+        let path = get_session_file(session_id);
+        let json = compressed_file::read_to_string(&path)
+            .with_context(|| format!("No saved conversation for session {}", session_id))?;
+        let data: serde_json::Value = serde_json::from_str(&json)
+            .with_context(|| format!("{} is not valid JSON", path.display()))?;
+        let messages = crate::session::validate_session_log(&data)?.messages;
+
+        let tool_calls =
+            tool_transcript::load(&get_tool_transcript_file(session_id)).unwrap_or_default();
+        let turns: Vec<TurnTelemetry> = std::fs::read_to_string(get_telemetry_file(session_id))
+            .map(|log| {
+                log.lines()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let title = crate::sessions::get_session_meta(session_id)
+            .ok()
+            .and_then(|meta| meta.description)
+            .unwrap_or_else(|| session_id.to_string());
+
+        Ok(Self::new(session_id, &title, &messages, tool_calls, &turns))
+    }
+
+    /// Build a transcript from a conversation, the session's tool transcript
+    /// and its per-turn telemetry.
+    pub fn new(
+        session_id: &str,
+        title: &str,
+        messages: &[Message],
+        mut tool_calls: Vec<ToolCallMetric>,
+        turns: &[TurnTelemetry],
+    ) -> Self {
+        let mut entries = Vec::new();
+        for message in messages {
+            let content = message.full_content();
+            let content: &str = &content;
+            match message.role {
+                MessageRole::System => {}
+                MessageRole::User => match content.strip_prefix("Tool result:") {
+                    Some(result) => match entries.last_mut() {
+                        Some(Entry::ToolCall { result: slot, .. }) if slot.is_none() => {
+                            *slot = Some(result.trim().to_string());
+                        }
+                        _ => entries.push(Entry::User(content.to_string())),
+                    },
+                    None => {
+                        let text = content.strip_prefix("Task: ").unwrap_or(content);
+                        entries.push(Entry::User(text.trim().to_string()));
+                    }
+                },
+                MessageRole::Assistant => {
+                    let (text, call) = split_tool_call(content);
+                    if !text.is_empty() {
+                        entries.push(Entry::Assistant(text.to_string()));
+                    }
+                    if let Some((tool, args)) = call {
+                        // The first unused record of the same call: calls are
+                        // recorded in order, including ones compacted away
+                        let digest = args_digest(&args);
+                        let metric = tool_calls
+                            .iter()
+                            .position(|m| m.tool == tool && m.args_digest == digest)
+                            .map(|i| tool_calls.remove(i));
+                        entries.push(Entry::ToolCall {
+                            tool,
+                            args,
+                            result: None,
+                            metric,
+                        });
+                    }
+                }
+            }
+        }
+
+        let cost_usd = turns
+            .iter()
+            .filter_map(|t| t.cost_usd)
+            .fold(None, |total: Option<f64>, cost| {
+                Some(total.unwrap_or(0.0) + cost)
+            });
+        let totals = Totals {
+            turns: turns.len(),
+            duration_ms: turns.iter().map(|t| t.duration_ms).sum(),
+            prompt_tokens: turns
+                .iter()
+                .filter_map(|t| t.prompt_tokens)
+                .map(u64::from)
+                .sum(),
+            completion_tokens: turns
+                .iter()
+                .filter_map(|t| t.completion_tokens)
+                .map(u64::from)
+                .sum(),
+            total_tokens: turns
+                .iter()
+                .filter_map(|t| t.total_tokens)
+                .map(u64::from)
+                .sum(),
+            cost_usd,
+        };
+
+        Self {
+            session_id: session_id.to_string(),
+            title: title.to_string(),
+            entries,
+            totals,
+        }
+    }
+
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Markdown => self.to_markdown(),
+            ExportFormat::Html => self.to_html(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n{}\n", self.title, self.summary_line());
+        let mut last_speaker = None;
+        for entry in &self.entries {
+            let speaker = speaker(entry);
+            if last_speaker != Some(speaker) {
+                out.push_str(&format!("\n---\n\n### {}\n", speaker));
+                last_speaker = Some(speaker);
+            }
+            match entry {
+                Entry::User(text) | Entry::Assistant(text) => {
+                    out.push_str(&format!("\n{}\n", text));
+                }
+                Entry::ToolCall {
+                    tool,
+                    args,
+                    result,
+                    metric,
+                } => {
+                    out.push_str(&format!(
+                        "\n<details>\n<summary>{}</summary>\n\n**Arguments**\n\n{}\n",
+                        tool_summary(tool, result.as_deref(), metric.as_ref(), |s| {
+                            format!("<code>{}</code>", escape_html(s))
+                        }),
+                        fenced("json", &pretty_args(args))
+                    ));
+                    if let Some(result) = result {
+                        out.push_str(&format!(
+                            "\n**Result**\n\n{}\n",
+                            fenced("", &truncate(result))
+                        ));
+                    }
+                    out.push_str("\n</details>\n");
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut body = format!(
+            "<h1>{}</h1>\n<p class=\"meta\">{}</p>\n",
+            escape_html(&self.title),
+            escape_html(&self.summary_line())
+        );
+        for entry in &self.entries {
+            match entry {
+                Entry::User(text) | Entry::Assistant(text) => {
+                    let class = if matches!(entry, Entry::User(_)) {
+                        "user"
+                    } else {
+                        "assistant"
+                    };
+                    body.push_str(&format!(
+                        "<section class=\"{}\">\n<h3>{}</h3>\n<div class=\"text\">{}</div>\n</section>\n",
+                        class,
+                        speaker(entry),
+                        escape_html(text)
+                    ));
+                }
+                Entry::ToolCall {
+                    tool,
+                    args,
+                    result,
+                    metric,
+                } => {
+                    body.push_str(&format!(
+                        "<details class=\"tool\">\n<summary>{}</summary>\n<h4>Arguments</h4>\n<pre>{}</pre>\n",
+                        tool_summary(tool, result.as_deref(), metric.as_ref(), |s| {
+                            format!("<code>{}</code>", escape_html(s))
+                        }),
+                        escape_html(&pretty_args(args))
+                    ));
+                    if let Some(result) = result {
+                        body.push_str(&format!(
+                            "<h4>Result</h4>\n<pre>{}</pre>\n",
+                            escape_html(&truncate(result))
+                        ));
+                    }
+                    body.push_str("</details>\n");
+                }
+            }
+        }
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+            escape_html(&self.title),
+            HTML_STYLE,
+            body
+        )
+    }
+
+    /// The session ID and totals, as one line.
+    fn summary_line(&self) -> String {
+        let tool_calls = self
+            .entries
+            .iter()
+            .filter(|e| matches!(e, Entry::ToolCall { .. }))
+            .count();
+        let mut parts = vec![format!("Session {}", self.session_id)];
+        if self.totals.turns > 0 {
+            parts.push(count(self.totals.turns, "turn"));
+        }
+        parts.push(count(tool_calls, "tool call"));
+        if self.totals.duration_ms > 0 {
+            parts.push(format_duration(Duration::from_millis(
+                self.totals.duration_ms,
+            )));
+        }
+        if self.totals.total_tokens > 0 {
+            parts.push(format!(
+                "{} tokens ({} prompt, {} completion)",
+                self.totals.total_tokens, self.totals.prompt_tokens, self.totals.completion_tokens
+            ));
+        }
+        if let Some(cost) = self.totals.cost_usd {
+            parts.push(format!("${:.4}", cost));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// Export session `session_id` to `path`, as HTML if it ends in `.html` and
+/// as Markdown otherwise.
+pub fn export_session(session_id: &str, path: &Path) -> Result<ExportFormat> {
+    let format = ExportFormat::from_path(path);
+    let transcript = Transcript::load(session_id)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, transcript.render(format))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(format)
+}
+
+const HTML_STYLE: &str = "body { font-family: -apple-system, sans-serif; max-width: 56rem; margin: 2rem auto; padding: 0 1rem; }
+.meta { color: #666; }
+section { margin: 1.5rem 0; }
+.text { white-space: pre-wrap; }
+.user h3 { color: #0a5; }
+.assistant h3 { color: #06c; }
+details.tool { margin: 0.5rem 0; border: 1px solid #ddd; border-radius: 4px; padding: 0.25rem 0.75rem; }
+pre { background: #f6f8fa; padding: 0.75rem; overflow-x: auto; }
+";
+
+fn speaker(entry: &Entry) -> &'static str {
+    match entry {
+        Entry::User(_) => "👤 User",
+        Entry::Assistant(_) | Entry::ToolCall { .. } => "🤖 Assistant",
+    }
+}
+
+/// The one-line summary of a tool call: its name, whether it succeeded and
+/// how long it took. `code` formats the tool name.
+fn tool_summary(
+    tool: &str,
+    result: Option<&str>,
+    metric: Option<&ToolCallMetric>,
+    code: impl Fn(&str) -> String,
+) -> String {
+    let success = match (metric, result) {
+        (Some(metric), _) => Some(metric.success),
+        (None, Some(result)) => Some(!result.contains("❌")),
+        (None, None) => None,
+    };
+    let mut summary = format!("🔧 {}", code(tool));
+    match success {
+        Some(true) => summary.push_str(" ✅"),
+        Some(false) => summary.push_str(" ❌"),
+        None => {}
+    }
+    if let Some(metric) = metric {
+        summary.push_str(&format!(" {}", format_duration(metric.duration())));
+    }
+    summary
+}
+
+/// The prose of an assistant message and the tool call it ends with, if any.
+pub(crate) fn split_tool_call(content: &str) -> (&str, Option<(String, serde_json::Value)>) {
+    if let Some(start) = content.rfind("{\"tool\"") {
+        if let Ok(call) = serde_json::from_str::<serde_json::Value>(&content[start..]) {
+            if let Some(tool) = call.get("tool").and_then(|t| t.as_str()) {
+                let args = call.get("args").cloned().unwrap_or(serde_json::Value::Null);
+                return (content[..start].trim(), Some((tool.to_string(), args)));
+            }
+        }
+    }
+    (content.trim(), None)
+}
+
+fn count(n: usize, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+fn pretty_args(args: &serde_json::Value) -> String {
+    serde_json::to_string_pretty(args).unwrap_or_else(|_| args.to_string())
+}
+
+/// The first `MAX_RESULT_CHARS` characters of `result`, marked if cut.
+fn truncate(result: &str) -> String {
+    truncate_chars(result, MAX_RESULT_CHARS, "\n")
+}
+
+/// `text` in a Markdown code block whose fence is longer than any run of
+/// backticks inside it.
+fn fenced(lang: &str, text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, lang, text, fence)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn transcript() -> Transcript {
+        let args = json!({"command": "cargo test"});
+        let messages = vec![
+            Message::new(MessageRole::System, "You are g3".to_string()),
+            Message::new(
+                MessageRole::User,
+                "Task: Fix the <failing> test".to_string(),
+            ),
+            Message::new(
+                MessageRole::Assistant,
+                format!(
+                    "Running the tests first.\n\n{{\"tool\": \"shell\", \"args\": {}}}",
+                    args
+                ),
+            ),
+            Message::new(
+                MessageRole::User,
+                "Tool result: ```\ntest result: FAILED\n```".to_string(),
+            ),
+            Message::new(MessageRole::Assistant, "The test is fixed.".to_string()),
+        ];
+        let metric = ToolCallMetric::new(
+            "shell",
+            &args,
+            chrono::Utc::now(),
+            Duration::from_millis(1500),
+            true,
+            40,
+        );
+        let turn: TurnTelemetry = serde_json::from_value(json!({
+            "duration_ms": 12000, "ttft_ms": 800, "prompt_tokens": 1000,
+            "completion_tokens": 200, "total_tokens": 1200, "cost_usd": 0.0045,
+            "tools_run": ["shell"], "context_percentage": 2.0
+        }))
+        .unwrap();
+        Transcript::new(
+            "fix_test_ab12",
+            "Fix the test",
+            &messages,
+            vec![metric],
+            &[turn],
+        )
+    }
+
+    #[test]
+    fn test_markdown_transcript() {
+        let markdown = transcript().to_markdown();
+        assert!(markdown.starts_with("# Fix the test\n\nSession fix_test_ab12 · 1 turn · 1 tool call · 12.0s · 1200 tokens (1000 prompt, 200 completion) · $0.0045\n"));
+        assert!(markdown.contains("### 👤 User\n\nFix the <failing> test\n"));
+        assert!(markdown.contains("Running the tests first.\n\n<details>\n<summary>🔧 <code>shell</code> ✅ 1.5s</summary>"));
+        assert!(markdown.contains("\"command\": \"cargo test\""));
+        // The result's own code fence doesn't end the block
+        assert!(markdown.contains("````\n```\ntest result: FAILED\n```\n````"));
+        // Consecutive assistant output shares one heading
+        assert_eq!(markdown.matches("### 🤖 Assistant").count(), 1);
+        assert!(markdown.trim_end().ends_with("The test is fixed."));
+    }
+
+    #[test]
+    fn test_html_transcript_is_escaped() {
+        let html = transcript().to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Fix the &lt;failing&gt; test"));
+        assert!(html.contains("<details class=\"tool\">"));
+        assert!(!html.contains("<failing>"));
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out/report.HTML")),
+            ExportFormat::Html
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("notes.md")),
+            ExportFormat::Markdown
+        );
+    }
+}
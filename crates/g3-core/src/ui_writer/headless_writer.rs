@@ -0,0 +1,177 @@
+//! A `UiWriter` for front-ends that follow the agent through its events.
+
+use std::fmt;
+use std::sync::Arc;
+
+use super::UiWriter;
+use crate::ThinResult;
+
+/// Answers the agent's questions for a `HeadlessUiWriter`, e.g. by asking a
+/// remote user. The agent waits for the answer.
+pub trait PromptHandler: Send + Sync {
+    /// The index of the chosen option; `default` is what the writer would
+    /// answer on its own.
+    fn choose(&self, message: &str, options: &[&str], default: usize) -> usize;
+}
+
+/// Told by a `HeadlessUiWriter` that a tool is about to run, on the agent's
+/// thread and before `AgentEvent::ToolHeader` is broadcast, e.g. to snapshot
+/// the files it will change. The tool runs once this returns.
+pub trait ToolObserver: Send + Sync {
+    fn tool_starting(&self, tool: &str, args: Option<&serde_json::Value>);
+}
+
+/// Shows nothing itself: the front-end reads the agent's `AgentEvent`s from
+/// `Agent::subscribe_events` instead.
+///
+/// Unless a `PromptHandler` is set, yes/no prompts get `default_confirm` and
+/// choices pick the first option; subscribers see the answers as
+/// `AgentEvent::Prompt`.
+#[derive(Clone)]
+pub struct HeadlessUiWriter {
+    full_output: bool,
+    default_confirm: bool,
+    prompt_handler: Option<Arc<dyn PromptHandler>>,
+    tool_observer: Option<Arc<dyn ToolObserver>>,
+}
+
+impl fmt::Debug for HeadlessUiWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeadlessUiWriter")
+            .field("full_output", &self.full_output)
+            .field("default_confirm", &self.default_confirm)
+            .field("prompt_handler", &self.prompt_handler.is_some())
+            .field("tool_observer", &self.tool_observer.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl HeadlessUiWriter {
+    pub fn new() -> Self {
+        Self {
+            full_output: true,
+            default_confirm: true,
+            prompt_handler: None,
+            tool_observer: None,
+        }
+    }
+
+    /// Whether tools should send untruncated output (default: true).
+    pub fn full_output(mut self, full_output: bool) -> Self {
+        self.full_output = full_output;
+        self
+    }
+
+    /// Answer for yes/no prompts (default: yes).
+    pub fn default_confirm(mut self, confirm: bool) -> Self {
+        self.default_confirm = confirm;
+        self
+    }
+
+    /// Answer prompts with `handler` instead of the defaults.
+    pub fn prompt_handler(mut self, handler: Arc<dyn PromptHandler>) -> Self {
+        self.prompt_handler = Some(handler);
+        self
+    }
+
+    /// Tell `observer` about every tool before it runs.
+    pub fn tool_observer(mut self, observer: Arc<dyn ToolObserver>) -> Self {
+        self.tool_observer = Some(observer);
+        self
+    }
+
+    fn answer(&self, message: &str, options: &[&str], default: usize) -> usize {
+        match &self.prompt_handler {
+            Some(handler) => handler.choose(message, options, default),
+            None => default,
+        }
+    }
+}
+
+impl Default for HeadlessUiWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UiWriter for HeadlessUiWriter {
+    fn print(&self, _message: &str) {}
+    fn println(&self, _message: &str) {}
+    fn print_inline(&self, _message: &str) {}
+    fn print_system_prompt(&self, _prompt: &str) {}
+    fn print_context_status(&self, _message: &str) {}
+    fn print_g3_progress(&self, _message: &str) {}
+    fn print_g3_status(&self, _message: &str, _status: &str) {}
+    fn print_thin_result(&self, _result: &ThinResult) {}
+
+    fn print_tool_header(&self, tool_name: &str, tool_args: Option<&serde_json::Value>) {
+        if let Some(observer) = &self.tool_observer {
+            observer.tool_starting(tool_name, tool_args);
+        }
+    }
+
+    fn print_tool_arg(&self, _key: &str, _value: &str) {}
+    fn print_tool_output_header(&self) {}
+    fn update_tool_output_line(&self, _line: &str) {}
+    fn print_tool_output_line(&self, _line: &str) {}
+    fn print_tool_output_summary(&self, _hidden_count: usize) {}
+    fn print_tool_timing(&self, _duration_str: &str, _tokens_delta: u32, _context_percentage: f32) {}
+    fn print_agent_prompt(&self) {}
+    fn print_agent_response(&self, _content: &str) {}
+    fn notify_sse_received(&self) {}
+    fn print_tool_streaming_hint(&self, _tool_name: &str) {}
+    fn print_tool_streaming_active(&self) {}
+    fn flush(&self) {}
+
+    fn wants_full_output(&self) -> bool {
+        self.full_output
+    }
+
+    fn prompt_user_yes_no(&self, message: &str) -> bool {
+        let default = if self.default_confirm { 0 } else { 1 };
+        self.answer(message, &["yes", "no"], default) == 0
+    }
+
+    fn prompt_user_choice(&self, message: &str, options: &[&str]) -> usize {
+        self.answer(message, options, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompts_get_the_defaults_or_the_handler() {
+        struct PickLast;
+        impl PromptHandler for PickLast {
+            fn choose(&self, _message: &str, options: &[&str], _default: usize) -> usize {
+                options.len() - 1
+            }
+        }
+
+        let writer = HeadlessUiWriter::new();
+        assert!(writer.prompt_user_yes_no("Continue?"));
+        assert!(!writer.clone().default_confirm(false).prompt_user_yes_no("Continue?"));
+        assert_eq!(writer.prompt_user_choice("Next?", &["a", "b", "c"]), 0);
+
+        let writer = writer.prompt_handler(Arc::new(PickLast));
+        assert!(!writer.prompt_user_yes_no("Delete build/?"));
+        assert_eq!(writer.prompt_user_choice("Next?", &["a", "b", "c"]), 2);
+    }
+
+    #[test]
+    fn test_tool_observer_is_told_about_tools() {
+        struct Recorder(std::sync::Mutex<Vec<String>>);
+        impl ToolObserver for Recorder {
+            fn tool_starting(&self, tool: &str, _args: Option<&serde_json::Value>) {
+                self.0.lock().unwrap().push(tool.to_string());
+            }
+        }
+
+        let recorder = Arc::new(Recorder(Default::default()));
+        let writer = HeadlessUiWriter::new().tool_observer(recorder.clone());
+        writer.print_tool_header("write_file", None);
+        assert_eq!(*recorder.0.lock().unwrap(), ["write_file"]);
+    }
+}
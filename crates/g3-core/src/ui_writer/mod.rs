@@ -1,3 +1,18 @@
+//! The `UiWriter` trait and the implementations shipped with g3-core.
+//!
+//! Frontends that embed g3-core can use one of these instead of implementing
+//! the trait themselves:
+//! - `NullUiWriter` - discards all output
+//! - `HeadlessUiWriter` - shows nothing, answers prompts, for front-ends that
+//!   read `AgentEvent`s from `Agent::subscribe_events` (GUIs, servers, JSONL)
+//! - `TracingUiWriter` - logs output through `tracing` (background services)
+
+mod headless_writer;
+mod tracing_writer;
+
+pub use headless_writer::{HeadlessUiWriter, PromptHandler, ToolObserver};
+pub use tracing_writer::TracingUiWriter;
+
 /// Interface for UI output operations
 /// This trait abstracts all UI operations to allow different implementations
 /// (console, TUI, web, etc.) without coupling the core logic to specific output methods.
@@ -71,6 +86,19 @@ pub trait UiWriter: Send + Sync {
     /// Print tool execution timing
     fn print_tool_timing(&self, duration_str: &str, tokens_delta: u32, context_percentage: f32);
 
+    /// Show a file edit about to be written, as a unified diff (`---`/`+++`
+    /// header, then `@@` hunks)
+    /// Default: ignore (the tool's summary follows)
+    fn print_diff(&self, _file_path: &str, _diff: &str) {}
+
+    /// Receive the full result of a tool call, as returned to the model
+    /// Default: ignore (the output lines are displayed as they come)
+    fn emit_tool_result(&self, _tool_name: &str, _result: &str, _success: bool) {}
+
+    /// Receive the telemetry record for a completed turn
+    /// Default: ignore (the footer is already part of the response)
+    fn emit_turn_telemetry(&self, _telemetry: &crate::telemetry::TurnTelemetry) {}
+
     /// Print the agent prompt indicator
     fn print_agent_prompt(&self);
 
@@ -142,7 +170,9 @@ pub trait UiWriter: Send + Sync {
     fn clear_project(&self) {}
 }
 
-/// A no-op implementation for when UI output is not needed
+/// A no-op implementation for when UI output is not needed.
+/// Confirmation prompts are answered "yes" and choices pick the first option.
+#[derive(Debug, Clone, Copy, Default)]
 pub struct NullUiWriter;
 
 impl UiWriter for NullUiWriter {
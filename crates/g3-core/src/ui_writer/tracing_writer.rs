@@ -0,0 +1,164 @@
+//! A `UiWriter` that logs UI output through `tracing`.
+
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use super::UiWriter;
+use crate::telemetry::TurnTelemetry;
+use crate::ThinResult;
+
+/// Target used for every event, so UI output can be filtered separately
+/// (e.g. `RUST_LOG=g3::ui=info`).
+const TARGET: &str = "g3::ui";
+
+/// Logs UI output as `tracing` events under the `g3::ui` target.
+///
+/// Streamed response text is buffered and logged as one event per line.
+/// Tool output lines are logged at debug level; everything else at info.
+/// Prompts can't be answered, so they are logged as warnings and answered
+/// "yes" / first option.
+#[derive(Debug, Default)]
+pub struct TracingUiWriter {
+    response_buffer: Mutex<String>,
+}
+
+impl TracingUiWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Log the complete lines in the response buffer, and the rest if `all`.
+    fn flush_response(&self, all: bool) {
+        let mut buffer = self.response_buffer.lock().unwrap();
+        let end = if all {
+            buffer.len()
+        } else {
+            buffer.rfind('\n').map_or(0, |i| i + 1)
+        };
+        for line in buffer[..end].lines() {
+            if !line.trim().is_empty() {
+                info!(target: TARGET, "{}", line);
+            }
+        }
+        buffer.drain(..end);
+    }
+}
+
+impl UiWriter for TracingUiWriter {
+    fn print(&self, message: &str) {
+        info!(target: TARGET, "{}", message);
+    }
+
+    fn println(&self, message: &str) {
+        info!(target: TARGET, "{}", message);
+    }
+
+    fn print_inline(&self, message: &str) {
+        info!(target: TARGET, "{}", message);
+    }
+
+    fn print_system_prompt(&self, prompt: &str) {
+        debug!(target: TARGET, "System prompt: {}", prompt);
+    }
+
+    fn print_context_status(&self, message: &str) {
+        info!(target: TARGET, "{}", message);
+    }
+
+    fn print_g3_progress(&self, message: &str) {
+        info!(target: TARGET, "g3: {} ...", message);
+    }
+
+    fn print_g3_status(&self, message: &str, status: &str) {
+        info!(target: TARGET, "g3: {} ... [{}]", message, status);
+    }
+
+    fn print_thin_result(&self, result: &ThinResult) {
+        if result.had_changes {
+            info!(
+                target: TARGET,
+                "Context thinned: {}% -> {}% ({} chars saved)",
+                result.before_percentage,
+                result.after_percentage,
+                result.chars_saved
+            );
+        }
+    }
+
+    fn print_tool_header(&self, tool_name: &str, tool_args: Option<&serde_json::Value>) {
+        self.flush_response(true);
+        match tool_args {
+            Some(args) => info!(target: TARGET, tool = tool_name, %args, "Running tool"),
+            None => info!(target: TARGET, tool = tool_name, "Running tool"),
+        }
+    }
+
+    fn print_tool_arg(&self, _key: &str, _value: &str) {}
+
+    fn print_tool_output_header(&self) {}
+
+    fn update_tool_output_line(&self, line: &str) {
+        debug!(target: TARGET, "{}", line);
+    }
+
+    fn print_tool_output_line(&self, line: &str) {
+        debug!(target: TARGET, "{}", line);
+    }
+
+    fn print_tool_output_summary(&self, hidden_count: usize) {
+        debug!(target: TARGET, "({} more lines)", hidden_count);
+    }
+
+    fn print_diff(&self, file_path: &str, diff: &str) {
+        debug!(target: TARGET, "Edit of {}:\n{}", file_path, diff);
+    }
+
+    fn print_tool_timing(&self, duration_str: &str, tokens_delta: u32, context_percentage: f32) {
+        info!(
+            target: TARGET,
+            "Tool finished in {} (+{} tokens, context {:.0}%)",
+            duration_str,
+            tokens_delta,
+            context_percentage
+        );
+    }
+
+    fn emit_turn_telemetry(&self, telemetry: &TurnTelemetry) {
+        info!(target: TARGET, "{}", telemetry.to_json_event());
+    }
+
+    fn print_agent_prompt(&self) {}
+
+    fn print_agent_response(&self, content: &str) {
+        self.response_buffer.lock().unwrap().push_str(content);
+        self.flush_response(false);
+    }
+
+    fn notify_sse_received(&self) {}
+
+    fn print_tool_streaming_hint(&self, _tool_name: &str) {}
+
+    fn print_tool_streaming_active(&self) {}
+
+    fn flush(&self) {}
+
+    fn prompt_user_yes_no(&self, message: &str) -> bool {
+        warn!(target: TARGET, "{} (answering yes: no interactive user)", message);
+        true
+    }
+
+    fn prompt_user_choice(&self, message: &str, options: &[&str]) -> usize {
+        warn!(
+            target: TARGET,
+            "{} {:?} (choosing {:?}: no interactive user)",
+            message,
+            options,
+            options.first()
+        );
+        0
+    }
+
+    fn finish_streaming_markdown(&self) {
+        self.flush_response(true);
+    }
+}
@@ -5,8 +5,10 @@
 //! - Applying unified diffs to strings
 //! - Shell command escaping
 //! - JSON quote fixing
+//! - Listing files changed in the working tree
 
 use anyhow::Result;
+use g3_execution::Shell;
 use tracing::debug;
 
 /// Truncate a string to approximately max_len characters, ending at a word boundary.
@@ -45,6 +47,15 @@ pub fn truncate_to_word_boundary(s: &str, max_len: usize) -> String {
     format!("{}...", truncated)
 }
 
+/// The first `max` characters of `s`. A cut string is followed by `sep` and
+/// a `… [<n> chars total]` marker.
+pub fn truncate_chars(s: &str, max: usize, sep: &str) -> String {
+    match s.char_indices().nth(max) {
+        Some((at, _)) => format!("{}{}… [{} chars total]", &s[..at], sep, s.chars().count()),
+        None => s.to_string(),
+    }
+}
+
 /// Normalize Unicode space characters in a file path to regular ASCII spaces.
 ///
 /// macOS uses special Unicode space characters in certain filenames:
@@ -362,11 +373,13 @@ pub fn parse_unified_diff_hunks(diff: &str) -> Vec<(String, String)> {
 }
 
 /// Helper function to properly escape shell commands.
-/// Handles file paths with spaces and other special characters.
+/// Handles file paths with spaces and other special characters, quoting them
+/// for `shell`. Commands for shells without POSIX syntax (fish, nushell) are
+/// left as written.
 #[allow(dead_code)]
-pub fn shell_escape_command(command: &str) -> String {
+pub fn shell_escape_command(command: &str, shell: Shell) -> String {
     let parts: Vec<&str> = command.split_whitespace().collect();
-    if parts.is_empty() {
+    if parts.is_empty() || !shell.is_posix() {
         return command.to_string();
     }
 
@@ -422,7 +435,7 @@ pub fn shell_escape_command(command: &str) -> String {
             // If this word looks like a file path (contains / or ~) and has spaces, quote it
             if word.contains('/') || word.starts_with('~') {
                 if word.contains(' ') && !word.starts_with('"') && !word.starts_with('\'') {
-                    escaped_command.push_str(&format!("\"{}\"", word));
+                    escaped_command.push_str(&shell.quote(word));
                 } else {
                     escaped_command.push_str(word);
                 }
@@ -545,6 +558,57 @@ pub fn fix_mixed_quotes_in_json(json_str: &str) -> String {
     result
 }
 
+/// The top-level directory of the git repository containing `working_dir`.
+///
+/// Returns None if `git` is unavailable or the directory is not a repository.
+pub async fn repo_root(working_dir: Option<&str>) -> Option<std::path::PathBuf> {
+    let output = tokio::process::Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(working_dir.unwrap_or("."))
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.status.success())?;
+    let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!root.is_empty()).then(|| std::path::PathBuf::from(root))
+}
+
+/// List files changed in the git working tree (modified, staged, and untracked).
+///
+/// Paths are relative to the repository root and deduplicated. Returns an empty
+/// list if `git` is unavailable or the directory is not a repository.
+pub async fn list_changed_files(working_dir: Option<&str>) -> Vec<String> {
+    let dir = working_dir.unwrap_or(".");
+    let mut files: Vec<String> = Vec::new();
+
+    for args in [
+        &["diff", "--name-only", "HEAD"][..],
+        &["ls-files", "--others", "--exclude-standard", "--full-name"][..],
+    ] {
+        let output = match tokio::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .await
+        {
+            Ok(output) if output.status.success() => output,
+            Ok(_) | Err(_) => {
+                debug!("git {:?} failed in {}", args, dir);
+                continue;
+            }
+        };
+
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let line = line.trim();
+            if !line.is_empty() && !files.iter().any(|f| f == line) {
+                files.push(line.to_string());
+            }
+        }
+    }
+
+    files
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -591,8 +655,15 @@ mod tests {
 
     #[test]
     fn shell_escape_preserves_simple_commands() {
-        assert_eq!(shell_escape_command("ls -la"), "ls -la");
-        assert_eq!(shell_escape_command("echo hello"), "echo hello");
+        assert_eq!(shell_escape_command("ls -la", Shell::Bash), "ls -la");
+        assert_eq!(
+            shell_escape_command("echo hello", Shell::Bash),
+            "echo hello"
+        );
+        assert_eq!(
+            shell_escape_command("cat (string split , a,b)", Shell::Fish),
+            "cat (string split , a,b)"
+        );
     }
 
     #[test]
@@ -665,6 +736,13 @@ mod tests {
         assert_eq!(result, "a verylong...");
     }
 
+    #[test]
+    fn truncate_chars_marks_the_cut() {
+        assert_eq!(truncate_chars("hello", 5, "\n"), "hello");
+        assert_eq!(truncate_chars("héllo wörld", 5, ""), "héllo… [11 chars total]");
+        assert_eq!(truncate_chars("héllo wörld", 5, "\n"), "héllo\n… [11 chars total]");
+    }
+
     #[test]
     fn truncate_to_word_boundary_handles_unicode() {
         // Should handle unicode characters correctly
@@ -0,0 +1,393 @@
+//! Background verification against worktree snapshots.
+//!
+//! The `verify` tool snapshots the current working tree (tracked changes and
+//! untracked, non-ignored files) into a detached commit, checks it out in a
+//! temporary git worktree, and runs the verification command there in the
+//! background. The agent keeps working in the real tree; results are injected
+//! into the conversation when the run finishes, like research results.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// Maximum characters of command output kept for injection (tail of the log).
+const MAX_OUTPUT_CHARS: usize = 8_000;
+
+/// Unique identifier for a verification run
+pub type VerificationId = String;
+
+/// Status of a verification run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationStatus {
+    /// Command is still running
+    Running,
+    /// Command exited successfully
+    Passed,
+    /// Command failed (or the snapshot could not be created)
+    Failed,
+}
+
+impl std::fmt::Display for VerificationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerificationStatus::Running => write!(f, "running"),
+            VerificationStatus::Passed => write!(f, "passed"),
+            VerificationStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// A verification run being tracked by the manager
+#[derive(Debug, Clone)]
+pub struct VerificationRun {
+    pub id: VerificationId,
+    pub command: String,
+    /// Snapshot commit the command ran against (once created)
+    pub snapshot: Option<String>,
+    pub status: VerificationStatus,
+    /// Tail of the command output, or the error that stopped the run
+    pub output: Option<String>,
+    pub started_at: Instant,
+    pub finished_in: Option<Duration>,
+    /// Whether this result has been injected into the conversation
+    pub injected: bool,
+}
+
+/// Thread-safe registry of background verification runs
+#[derive(Debug, Clone, Default)]
+pub struct PendingVerificationManager {
+    runs: Arc<Mutex<HashMap<VerificationId, VerificationRun>>>,
+}
+
+impl PendingVerificationManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new run and return its ID
+    pub fn register(&self, command: &str) -> VerificationId {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+        let id = format!(
+            "verify_{:x}_{:04x}",
+            chrono::Utc::now().timestamp_millis(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let run = VerificationRun {
+            id: id.clone(),
+            command: command.to_string(),
+            snapshot: None,
+            status: VerificationStatus::Running,
+            output: None,
+            started_at: Instant::now(),
+            finished_in: None,
+            injected: false,
+        };
+        self.runs.lock().unwrap().insert(id.clone(), run);
+        debug!("Registered verification run {}: {}", id, command);
+        id
+    }
+
+    fn set_snapshot(&self, id: &VerificationId, snapshot: &str) {
+        if let Some(run) = self.runs.lock().unwrap().get_mut(id) {
+            run.snapshot = Some(snapshot.to_string());
+        }
+    }
+
+    /// Record the outcome of a run
+    pub fn finish(&self, id: &VerificationId, passed: bool, output: String) {
+        if let Some(run) = self.runs.lock().unwrap().get_mut(id) {
+            run.status = if passed {
+                VerificationStatus::Passed
+            } else {
+                VerificationStatus::Failed
+            };
+            run.output = Some(output);
+            run.finished_in = Some(run.started_at.elapsed());
+        }
+    }
+
+    /// Number of runs still in progress
+    pub fn running_count(&self) -> usize {
+        self.runs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|r| r.status == VerificationStatus::Running)
+            .count()
+    }
+
+    /// Take all finished runs that haven't been injected yet.
+    ///
+    /// Marks them as injected so they won't be returned again.
+    pub fn take_completed(&self) -> Vec<VerificationRun> {
+        let mut runs = self.runs.lock().unwrap();
+        let mut completed: Vec<VerificationRun> = runs
+            .values_mut()
+            .filter(|r| !r.injected && r.status != VerificationStatus::Running)
+            .map(|r| {
+                r.injected = true;
+                r.clone()
+            })
+            .collect();
+        completed.sort_by_key(|r| r.started_at);
+        completed
+    }
+}
+
+impl VerificationRun {
+    /// Message injected into the conversation when the run finishes.
+    pub fn format_result(&self) -> String {
+        let snapshot = self
+            .snapshot
+            .as_deref()
+            .map(|s| format!(" on snapshot `{}`", &s[..s.len().min(10)]))
+            .unwrap_or_default();
+        let elapsed = self
+            .finished_in
+            .map(|d| format!(" in {}s", d.as_secs()))
+            .unwrap_or_default();
+        let headline = match self.status {
+            VerificationStatus::Passed => "✅ **Verification passed**",
+            _ => "❌ **Verification failed**",
+        };
+        format!(
+            "{} (id: `{}`): `{}`{}{}\n\nResults reflect the working tree when the run started; later edits are not included.\n\n```\n{}\n```",
+            headline,
+            self.id,
+            self.command,
+            snapshot,
+            elapsed,
+            self.output.as_deref().unwrap_or("").trim_end()
+        )
+    }
+}
+
+/// Start a verification run in the background and return its ID. The
+/// command runs in `subdir` of the snapshot, relative to its root.
+pub fn spawn_verification(
+    manager: &PendingVerificationManager,
+    command: &str,
+    repo_dir: PathBuf,
+    subdir: PathBuf,
+    timeout: Duration,
+) -> VerificationId {
+    let id = manager.register(command);
+    let manager = manager.clone();
+    let run_id = id.clone();
+    let command = command.to_string();
+
+    tokio::spawn(async move {
+        let (passed, output) =
+            match run_in_snapshot(&manager, &run_id, &command, &repo_dir, &subdir, timeout)
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => (false, format!("Verification could not run: {}", e)),
+            };
+        debug!("Verification {} finished (passed: {})", run_id, passed);
+        manager.finish(&run_id, passed, output);
+    });
+
+    id
+}
+
+async fn run_in_snapshot(
+    manager: &PendingVerificationManager,
+    id: &VerificationId,
+    command: &str,
+    repo_dir: &Path,
+    subdir: &Path,
+    timeout: Duration,
+) -> Result<(bool, String)> {
+    let scratch = scratch_dir(repo_dir);
+    std::fs::create_dir_all(&scratch)?;
+
+    let snapshot = snapshot_commit(repo_dir, &scratch.join(format!("{}.index", id))).await?;
+    manager.set_snapshot(id, &snapshot);
+
+    let worktree = scratch.join(id);
+    git(
+        repo_dir,
+        &[
+            "worktree",
+            "add",
+            "--detach",
+            &worktree.to_string_lossy(),
+            &snapshot,
+        ],
+        &[],
+    )
+    .await?;
+
+    let result = run_command(
+        command,
+        &worktree.join(subdir),
+        &scratch.join("target"),
+        timeout,
+    )
+    .await;
+
+    if let Err(e) = git(
+        repo_dir,
+        &["worktree", "remove", "--force", &worktree.to_string_lossy()],
+        &[],
+    )
+    .await
+    {
+        warn!(
+            "Failed to remove verification worktree {}: {}",
+            worktree.display(),
+            e
+        );
+    }
+
+    result
+}
+
+/// Per-repository scratch space for worktrees, temporary indexes, and the
+/// shared cargo target dir. Kept outside the repository so snapshots never
+/// pick up earlier worktrees.
+fn scratch_dir(repo_dir: &Path) -> PathBuf {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    repo_dir
+        .canonicalize()
+        .unwrap_or_else(|_| repo_dir.to_path_buf())
+        .hash(&mut hasher);
+    std::env::temp_dir()
+        .join("g3-verify")
+        .join(format!("{:016x}", hasher.finish()))
+}
+
+/// Commit the current working tree (including untracked, non-ignored files)
+/// without touching the real index, HEAD, or any branch.
+async fn snapshot_commit(repo_dir: &Path, index: &Path) -> Result<String> {
+    let index_str = index.to_string_lossy().to_string();
+    let env = [("GIT_INDEX_FILE", index_str.as_str())];
+
+    let result = async {
+        // Seed the temporary index from HEAD so unchanged files stay cheap to add
+        let head = git(repo_dir, &["rev-parse", "--verify", "-q", "HEAD"], &[])
+            .await
+            .ok();
+        if head.is_some() {
+            git(repo_dir, &["read-tree", "HEAD"], &env).await?;
+        }
+        git(repo_dir, &["add", "-A"], &env).await?;
+        let tree = git(repo_dir, &["write-tree"], &env).await?;
+
+        let mut args = vec!["commit-tree", tree.trim(), "-m", "g3 verification snapshot"];
+        if let Some(head) = head.as_deref() {
+            args.extend(["-p", head.trim()]);
+        }
+        git(repo_dir, &args, &[]).await
+    }
+    .await;
+
+    let _ = std::fs::remove_file(index);
+    Ok(result?.trim().to_string())
+}
+
+/// Run the verification command in the worktree, returning success and the
+/// tail of its combined output.
+async fn run_command(
+    command: &str,
+    dir: &Path,
+    target_dir: &Path,
+    timeout: Duration,
+) -> Result<(bool, String)> {
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c")
+        .arg(command)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    // Share build artifacts between runs instead of compiling each snapshot from scratch
+    if std::env::var_os("CARGO_TARGET_DIR").is_none() {
+        cmd.env("CARGO_TARGET_DIR", target_dir);
+    }
+
+    let output = match tokio::time::timeout(timeout, cmd.output()).await {
+        Ok(output) => output?,
+        Err(_) => return Ok((false, format!("Timed out after {}s", timeout.as_secs()))),
+    };
+
+    let combined = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok((
+        output.status.success(),
+        tail_chars(&combined, MAX_OUTPUT_CHARS),
+    ))
+}
+
+/// Keep the last `max` characters of `text` (failures are usually at the end).
+fn tail_chars(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    if count <= max {
+        return text.to_string();
+    }
+    let tail: String = text.chars().skip(count - max).collect();
+    format!("... ({} earlier characters omitted)\n{}", count - max, tail)
+}
+
+async fn git(dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args).current_dir(dir);
+    for (key, value) in env {
+        cmd.env(key, value);
+    }
+    let out = cmd.output().await?;
+    if out.status.success() {
+        Ok(String::from_utf8_lossy(&out.stdout).to_string())
+    } else {
+        Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&out.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_completed_only_once() {
+        let manager = PendingVerificationManager::new();
+        let running = manager.register("cargo test");
+        let done = manager.register("cargo clippy");
+        manager.finish(&done, false, "warning: unused".to_string());
+
+        assert_eq!(manager.running_count(), 1);
+        let completed = manager.take_completed();
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].id, done);
+        assert!(completed[0].format_result().contains("Verification failed"));
+        assert!(manager.take_completed().is_empty());
+
+        manager.finish(&running, true, "ok".to_string());
+        assert_eq!(
+            manager.take_completed()[0].status,
+            VerificationStatus::Passed
+        );
+    }
+
+    #[test]
+    fn test_tail_chars() {
+        assert_eq!(tail_chars("short", 10), "short");
+        let out = tail_chars("0123456789", 4);
+        assert!(out.ends_with("\n6789"));
+        assert!(out.contains("6 earlier characters omitted"));
+    }
+}
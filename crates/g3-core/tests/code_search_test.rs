@@ -1,6 +1,9 @@
 //! Integration tests for tree-sitter code search
 
-use g3_core::code_search::{execute_code_search, CodeSearchRequest, SearchSpec};
+use g3_core::code_search::{
+    execute_code_search, find_definitions, find_references, outline_file, CodeSearchRequest,
+    SearchSpec, SymbolKind, SymbolRequest,
+};
 use std::fs;
 
 #[tokio::test]
@@ -726,3 +729,179 @@ async fn test_racket_contracts() {
     assert!(names.contains(&"safe-divide"), "Should find 'safe-divide', found: {:?}", names);
     assert!(names.contains(&"non-negative-add"), "Should find 'non-negative-add', found: {:?}", names);
 }
+
+fn write_symbol_fixture() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("src");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("a_legacy.rs"), "pub struct Config;\n").unwrap();
+    fs::write(
+        src.join("config.rs"),
+        "/// Config is loaded from disk\npub struct Config {\n    pub name: String,\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        src.join("main.rs"),
+        r#"use crate::config::Config;
+
+fn main() {
+    // Config mentioned in a comment
+    let c = Config { name: "Config".to_string() };
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        src.join("other.rs"),
+        "fn build() -> Config {\n    todo!()\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn symbol_request(symbol: &str, dir: &tempfile::TempDir) -> SymbolRequest {
+    SymbolRequest {
+        symbol: symbol.to_string(),
+        language: Some("rust".to_string()),
+        paths: vec![dir.path().to_string_lossy().to_string()],
+        context_lines: 0,
+        max_results: 100,
+    }
+}
+
+#[tokio::test]
+async fn test_find_definitions_ranks_imported_module_first() {
+    let dir = write_symbol_fixture();
+    let response = find_definitions(symbol_request("crate::config::Config", &dir))
+        .await
+        .unwrap();
+
+    assert_eq!(response.files_searched, 4);
+    let files: Vec<&str> = response.locations.iter().map(|l| l.file.as_str()).collect();
+    assert_eq!(files.len(), 2, "{:?}", files);
+    // config.rs is imported by main.rs, so it comes before a_legacy.rs
+    assert!(files[0].ends_with("config.rs"), "{:?}", files);
+    assert!(files[1].ends_with("a_legacy.rs"), "{:?}", files);
+
+    let first = &response.locations[0];
+    assert_eq!((first.line, first.column), (2, 12));
+    assert_eq!(first.declaration.as_deref(), Some("struct_item"));
+    assert_eq!(first.context, "     2\tpub struct Config {");
+}
+
+#[tokio::test]
+async fn test_find_references_skips_comments_and_strings() {
+    let dir = write_symbol_fixture();
+    let response = find_references(symbol_request("Config", &dir))
+        .await
+        .unwrap();
+
+    let found: Vec<(String, usize, SymbolKind)> = response
+        .locations
+        .iter()
+        .map(|l| {
+            let file = std::path::Path::new(&l.file).file_name().unwrap();
+            (file.to_string_lossy().to_string(), l.line, l.kind)
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("main.rs".to_string(), 1, SymbolKind::Import),
+            ("main.rs".to_string(), 5, SymbolKind::Reference),
+            ("other.rs".to_string(), 1, SymbolKind::Reference),
+        ]
+    );
+    assert!(!response.truncated);
+}
+
+#[tokio::test]
+async fn test_outline_nests_impl_members_and_skips_function_bodies() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("agent.rs");
+    fs::write(
+        &file,
+        r#"/// An agent
+pub struct Agent {
+    name: String,
+}
+
+impl Agent {
+    pub fn new(name: &str) -> Self {
+        fn helper() {}
+        Self { name: name.to_string() }
+    }
+}
+
+impl Drop for Agent {
+    fn drop(&mut self) {}
+}
+"#,
+    )
+    .unwrap();
+
+    let response = outline_file(&file, usize::MAX).await.unwrap();
+    assert_eq!(response.language, "rust");
+    assert_eq!(response.total_lines, 15);
+    let items: Vec<(&str, &str, usize, usize, usize, &str)> = response
+        .items
+        .iter()
+        .map(|i| {
+            (
+                i.kind.as_str(),
+                i.name.as_str(),
+                i.start_line,
+                i.end_line,
+                i.depth,
+                i.signature.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            ("struct", "Agent", 2, 4, 0, "pub struct Agent"),
+            ("impl", "Agent", 6, 11, 0, "impl Agent"),
+            ("method", "new", 7, 10, 1, "pub fn new(name: &str) -> Self"),
+            ("impl", "Drop for Agent", 13, 15, 0, "impl Drop for Agent"),
+            ("method", "drop", 14, 14, 1, "fn drop(&mut self)"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_outline_python_classes_and_max_depth() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("store.py");
+    fs::write(
+        &file,
+        "import os\n\nclass Store(Base):\n    def get(self, key):\n        return key\n\n@cache\ndef load():\n    pass\n",
+    )
+    .unwrap();
+
+    let response = outline_file(&file, usize::MAX).await.unwrap();
+    let items: Vec<(&str, &str, usize, &str)> = response
+        .items
+        .iter()
+        .map(|i| {
+            (
+                i.kind.as_str(),
+                i.name.as_str(),
+                i.depth,
+                i.signature.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            ("class", "Store", 0, "class Store(Base)"),
+            ("method", "get", 1, "def get(self, key)"),
+            ("function", "load", 0, "def load()"),
+        ]
+    );
+
+    let top_level = outline_file(&file, 1).await.unwrap();
+    let names: Vec<&str> = top_level.items.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, vec!["Store", "load"]);
+}
@@ -4,7 +4,7 @@
 //! stream_completion_with_tools without needing a real LLM.
 
 use g3_core::ui_writer::NullUiWriter;
-use g3_core::Agent;
+use g3_core::{Agent, AgentEvent};
 use g3_providers::mock::{MockChunk, MockProvider, MockResponse};
 use g3_providers::{Message, MessageRole, ProviderRegistry};
 use tempfile::TempDir;
@@ -822,3 +822,37 @@ async fn test_llm_repeats_text_before_each_tool_call() {
         preamble_count
     );
 }
+
+/// Test: A subscriber receives the turn's output as events, in order
+#[tokio::test]
+async fn test_agent_events_reach_subscribers() {
+    let provider = MockProvider::new()
+        .with_response(MockResponse::text("Hello! I'm here to help."));
+
+    let (mut agent, _temp_dir) = create_agent_with_mock(provider).await;
+    let mut events = agent.subscribe_events();
+
+    let result = agent.execute_task("Hello", None, false).await;
+    assert!(result.is_ok(), "Task should succeed: {:?}", result.err());
+
+    let mut received = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        received.push(event);
+    }
+    let position = |wanted: fn(&AgentEvent) -> bool| received.iter().position(wanted);
+
+    let response: String = received
+        .iter()
+        .filter_map(|event| match event {
+            AgentEvent::AgentResponse { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert!(response.contains("Hello! I'm here to help."), "Got: {:?}", received);
+
+    let prompt = position(|e| matches!(e, AgentEvent::AgentPrompt)).expect("no agent prompt");
+    let first_text = position(|e| matches!(e, AgentEvent::AgentResponse { .. })).unwrap();
+    let finished = position(|e| matches!(e, AgentEvent::ResponseFinished)).expect("no end of response");
+    let telemetry = position(|e| matches!(e, AgentEvent::TurnTelemetry { .. })).expect("no telemetry");
+    assert!(prompt < first_text && first_text < finished && finished < telemetry);
+}
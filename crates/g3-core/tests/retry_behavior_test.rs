@@ -99,7 +99,7 @@ mod retry_result_states {
     #[test]
     fn test_success_is_success() {
         let ctx = ContextWindow::new(1000);
-        let result = RetryResult::Success(TaskResult::new("done".to_string(), ctx));
+        let result = RetryResult::Success(Box::new(TaskResult::new("done".to_string(), ctx)));
         
         assert!(result.is_success());
     }
@@ -132,7 +132,7 @@ mod retry_result_states {
     #[test]
     fn test_into_result_success() {
         let ctx = ContextWindow::new(1000);
-        let result = RetryResult::Success(TaskResult::new("done".to_string(), ctx));
+        let result = RetryResult::Success(Box::new(TaskResult::new("done".to_string(), ctx)));
         
         let task_result = result.into_result();
         assert!(task_result.is_some());
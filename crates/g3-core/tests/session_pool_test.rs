@@ -0,0 +1,68 @@
+//! Tests for running several sessions in one process through a SessionPool
+
+use g3_config::Config;
+use g3_core::session_pool::SessionPool;
+use g3_core::ui_writer::NullUiWriter;
+use g3_core::Agent;
+use g3_providers::mock::{MockProvider, MockResponse};
+use g3_providers::ProviderRegistry;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_sessions_run_concurrently_with_isolated_state() {
+    let mut registry = ProviderRegistry::new();
+    registry.register(MockProvider::new().with_default_response(MockResponse::text("Done.")));
+    let pool = SessionPool::new(Arc::new(registry));
+
+    let (dir_a, dir_b) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    let a = pool
+        .create(
+            Agent::builder(Config::default(), NullUiWriter)
+                .quiet(true)
+                .working_dir(dir_a.path().to_string_lossy()),
+        )
+        .await
+        .unwrap();
+    let b = pool
+        .create(
+            Agent::builder(Config::default(), NullUiWriter)
+                .quiet(true)
+                .session_id("second_session")
+                .working_dir(dir_b.path().to_string_lossy()),
+        )
+        .await
+        .unwrap();
+    assert_ne!(a, b);
+    assert_eq!(b, "second_session");
+    assert_eq!(pool.len(), 2);
+
+    let (agent_a, agent_b) = (pool.get(&a).unwrap(), pool.get(&b).unwrap());
+    let (mut agent_a, mut agent_b) = (agent_a.lock().await, agent_b.lock().await);
+    let (result_a, result_b) = tokio::join!(
+        agent_a.execute_task("Task for the first session", None, false),
+        agent_b.execute_task("Task for the second session", None, false),
+    );
+    assert!(result_a.is_ok() && result_b.is_ok());
+
+    // Each session only sees its own conversation, under its own ID
+    let mentions = |agent: &Agent<NullUiWriter>, text: &str| {
+        agent
+            .get_context_window()
+            .conversation_history
+            .iter()
+            .any(|m| m.content.contains(text))
+    };
+    assert!(mentions(&agent_a, "first session") && !mentions(&agent_a, "second session"));
+    assert!(mentions(&agent_b, "second session") && !mentions(&agent_b, "first session"));
+    assert_eq!(agent_a.get_session_id(), Some(a.as_str()));
+    assert_eq!(agent_b.get_session_id(), Some("second_session"));
+
+    // ...and they share the pool's providers
+    assert!(Arc::ptr_eq(&agent_a.get_providers(), &pool.providers()));
+    assert!(Arc::ptr_eq(&agent_b.get_providers(), &pool.providers()));
+    drop((agent_a, agent_b));
+
+    assert!(pool.remove(&a).is_some());
+    assert_eq!(pool.session_ids(), vec!["second_session".to_string()]);
+}
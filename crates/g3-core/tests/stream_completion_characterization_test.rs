@@ -509,7 +509,7 @@ mod tool_execution_integration {
         std::env::set_current_dir(temp_dir.path()).unwrap();
         let config = g3_config::Config::default();
         let ui_writer = NullUiWriter;
-        Agent::new(config, ui_writer).await.unwrap()
+        Agent::builder(config, ui_writer).build().await.unwrap()
     }
 
     /// CHARACTERIZATION: Tool execution adds result to context
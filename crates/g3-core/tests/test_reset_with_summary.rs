@@ -1,6 +1,6 @@
 //! Tests for reset_with_summary to ensure system prompt is preserved after compaction
 
-use g3_core::ContextWindow;
+use g3_core::{ContextWindow, PreservationPolicy};
 use g3_providers::{Message, MessageRole};
 
 /// Test that reset_with_summary preserves the original system prompt
@@ -157,3 +157,121 @@ fn test_reset_with_summary_preserves_agent_configuration() {
         "Second message should be the Agent Configuration"
     );
 }
+
+/// Test that a policy can keep more system messages and the original task verbatim
+#[test]
+fn test_reset_with_summary_preserving_configured_prefix() {
+    let mut context = ContextWindow::new(10000);
+
+    context.add_message(Message::new(MessageRole::System, "You are G3".to_string()));
+    context.add_message(Message::new(MessageRole::System, "# Agent Configuration".to_string()));
+    context.add_message(Message::new(MessageRole::System, "=== Workspace Memory ===".to_string()));
+    context.add_message(Message::new(MessageRole::User, "Task: Port the parser".to_string()));
+    context.add_message(Message::new(MessageRole::Assistant, "Working on it.".to_string()));
+
+    let policy = PreservationPolicy {
+        system_messages: 3,
+        first_user_message: true,
+        pinned: true,
+    };
+    context.reset_with_summary_preserving("Ported half.".to_string(), None, None, &policy);
+
+    let contents: Vec<&str> = context
+        .conversation_history
+        .iter()
+        .map(|m| m.content.as_str())
+        .collect();
+    assert_eq!(contents[0], "You are G3");
+    assert_eq!(contents[1], "# Agent Configuration");
+    assert_eq!(contents[2], "=== Workspace Memory ===");
+    // The task and the summary are one user turn
+    assert_eq!(
+        contents[3],
+        "Task: Port the parser\n\nPrevious conversation summary:\n\nPorted half."
+    );
+    assert_eq!(contents[4], "Working on it.");
+    assert_eq!(contents.len(), 5);
+
+    // The original task is pinned, so a second compaction keeps it too,
+    // with the new summary in place of the old one
+    assert!(context.conversation_history[3].is_pinned());
+    context.reset_with_summary_preserving("Ported all.".to_string(), None, None, &policy);
+    assert_eq!(
+        context.conversation_history[3].content,
+        "Task: Port the parser\n\nPrevious conversation summary:\n\nPorted all."
+    );
+    assert_eq!(
+        context
+            .conversation_history
+            .iter()
+            .filter(|m| m.content.contains("Previous conversation summary"))
+            .count(),
+        1
+    );
+}
+
+/// Test that pinned messages survive compaction in their original order
+#[test]
+fn test_reset_with_summary_keeps_pinned_messages() {
+    let mut context = ContextWindow::new(10000);
+
+    context.add_message(Message::new(MessageRole::System, "You are G3".to_string()));
+    context.add_message(Message::new(MessageRole::User, "Never touch the vendor/ directory".to_string()));
+    assert!(context.pin_last_user_message());
+    context.add_message(Message::new(MessageRole::Assistant, "Understood.".to_string()));
+    context.add_message(Message::new(MessageRole::User, "Refactor the CLI".to_string()));
+    context.add_message(Message::new(MessageRole::Assistant, "Done.".to_string()));
+
+    context.reset_with_summary("Refactored the CLI.".to_string(), None);
+
+    assert_eq!(context.conversation_history[0].content, "You are G3");
+    let pinned = &context.conversation_history[1];
+    assert!(pinned.content.starts_with("Never touch the vendor/ directory\n\n"));
+    assert!(pinned.content.contains("Previous conversation summary"));
+    assert!(matches!(
+        context.conversation_history[2].role,
+        MessageRole::Assistant
+    ));
+    assert!(!context
+        .conversation_history
+        .iter()
+        .any(|m| m.content == "Refactor the CLI"));
+
+    // Pinned messages are summarized like any other when the policy disables them
+    let mut context = ContextWindow::new(10000);
+    context.add_message(Message::new(MessageRole::System, "You are G3".to_string()));
+    context.add_message(Message::new(MessageRole::User, "Never touch vendor/".to_string()));
+    context.pin_last_user_message();
+    let policy = PreservationPolicy {
+        pinned: false,
+        ..PreservationPolicy::default()
+    };
+    context.reset_with_summary_preserving("Summary.".to_string(), None, None, &policy);
+    assert!(!context
+        .conversation_history
+        .iter()
+        .any(|m| m.content == "Never touch vendor/"));
+}
+
+/// Test that the default keeps only the system prompt and project context
+#[test]
+fn test_reset_with_summary_default_system_prefix() {
+    let mut context = ContextWindow::new(10000);
+
+    context.add_message(Message::new(MessageRole::System, "You are G3".to_string()));
+    context.add_message(Message::new(MessageRole::System, "Scratch notes".to_string()));
+    context.add_message(Message::new(MessageRole::User, "Task".to_string()));
+    context.add_message(Message::new(MessageRole::Assistant, "Done.".to_string()));
+
+    context.reset_with_summary("Summary.".to_string(), None);
+
+    assert!(!context
+        .conversation_history
+        .iter()
+        .any(|m| m.content == "Scratch notes"));
+    let roles: Vec<&MessageRole> = context.conversation_history.iter().map(|m| &m.role).collect();
+    assert!(matches!(
+        roles[..],
+        [MessageRole::System, MessageRole::User, MessageRole::Assistant]
+    ));
+}
@@ -37,7 +37,7 @@ fn test_readme_is_second_message_after_system_prompt() {
     let system_prompt = "You are G3, an AI programming agent of the same skill level...";
     context.add_message(Message::new(MessageRole::System, system_prompt.to_string()));
 
-    // Add README as second system message (simulating what Agent::new_with_readme does)
+    // Add README as second system message (simulating what AgentBuilder::project_context does)
     let readme_content = "📚 Project README (from README.md):\n\n# My Project\n\nThis is a test project.";
     context.add_message(Message::new(MessageRole::System, readme_content.to_string()));
 
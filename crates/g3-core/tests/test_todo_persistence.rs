@@ -14,7 +14,7 @@ async fn create_test_agent_in_dir(temp_dir: &TempDir) -> Agent<NullUiWriter> {
     let config = g3_config::Config::default();
     let ui_writer = NullUiWriter;
 
-    Agent::new(config, ui_writer).await.unwrap()
+    Agent::builder(config, ui_writer).build().await.unwrap()
 }
 
 /// Helper to get todo.g3.md path in temp directory
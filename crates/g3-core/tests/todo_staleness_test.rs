@@ -108,7 +108,11 @@ async fn test_todo_staleness_check_matching_sha() {
     config.agent.check_todo_staleness = true;
 
     let ui_writer = MockUiWriter::new();
-    let mut agent = Agent::new_autonomous(config, ui_writer).await.unwrap();
+    let mut agent = Agent::builder(config, ui_writer)
+        .autonomous(true)
+        .build()
+        .await
+        .unwrap();
     agent.set_requirements_sha(sha.to_string());
 
     let tool_call = ToolCall {
@@ -142,7 +146,11 @@ async fn test_todo_staleness_check_mismatch_sha_ignore() {
     let ui_writer = MockUiWriter::new();
     ui_writer.set_choice_response(0); // Ignore
 
-    let mut agent = Agent::new_autonomous(config, ui_writer).await.unwrap();
+    let mut agent = Agent::builder(config, ui_writer)
+        .autonomous(true)
+        .build()
+        .await
+        .unwrap();
     agent.set_requirements_sha(sha_req.to_string());
 
     let tool_call = ToolCall {
@@ -175,7 +183,11 @@ async fn test_todo_staleness_check_mismatch_sha_mark_stale() {
     let ui_writer = MockUiWriter::new();
     ui_writer.set_choice_response(1); // Mark as Stale
 
-    let mut agent = Agent::new_autonomous(config, ui_writer).await.unwrap();
+    let mut agent = Agent::builder(config, ui_writer)
+        .autonomous(true)
+        .build()
+        .await
+        .unwrap();
     agent.set_requirements_sha(sha_req.to_string());
 
     let tool_call = ToolCall {
@@ -210,7 +222,11 @@ async fn test_todo_staleness_check_disabled() {
     config.agent.check_todo_staleness = false;
 
     let ui_writer = MockUiWriter::new();
-    let mut agent = Agent::new_autonomous(config, ui_writer).await.unwrap();
+    let mut agent = Agent::builder(config, ui_writer)
+        .autonomous(true)
+        .build()
+        .await
+        .unwrap();
     agent.set_requirements_sha(sha_req.to_string());
 
     let tool_call = ToolCall {
@@ -12,10 +12,10 @@
 //! What these tests intentionally do NOT assert:
 //! - Internal implementation details of tools
 //! - Specific formatting of success messages (only key content)
-//! - UI writer behavior (uses NullUiWriter)
+//! - UI writer behavior (uses NullUiWriter, except to review an edit)
 
-use g3_core::ui_writer::NullUiWriter;
-use g3_core::{Agent, ToolCall};
+use g3_core::ui_writer::{HeadlessUiWriter, NullUiWriter};
+use g3_core::{Agent, AgentEvent, ToolCall};
 use serial_test::serial;
 use std::fs;
 use tempfile::TempDir;
@@ -29,7 +29,7 @@ async fn create_test_agent(temp_dir: &TempDir) -> Agent<NullUiWriter> {
     std::env::set_current_dir(temp_dir.path()).unwrap();
     let config = g3_config::Config::default();
     let ui_writer = NullUiWriter;
-    Agent::new(config, ui_writer).await.unwrap()
+    Agent::builder(config, ui_writer).build().await.unwrap()
 }
 
 /// Create a ToolCall with the given tool name and arguments
@@ -387,6 +387,42 @@ mod str_replace_execution {
             "Should indicate pattern not found: {:?}", result
         );
     }
+
+    /// Test that the edit is shown as a diff, and not applied when review declines it
+    #[tokio::test]
+    #[serial]
+    async fn test_str_replace_review_declined() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.txt");
+        fs::write(&test_file, "line 1\nold line\n").unwrap();
+
+        std::env::set_current_dir(temp_dir.path()).unwrap();
+        let mut config = g3_config::Config::default();
+        config.approval.review_edits = true;
+        let ui_writer = HeadlessUiWriter::new().default_confirm(false);
+        let mut agent = Agent::builder(config, ui_writer).build().await.unwrap();
+        let mut events = agent.subscribe_events();
+
+        let tool_call = make_tool_call(
+            "str_replace",
+            serde_json::json!({
+                "file_path": test_file.to_string_lossy(),
+                "diff": "@@ -1,2 +1,2 @@\n line 1\n-old line\n+new line\n"
+            }),
+        );
+        let result = agent.execute_tool(&tool_call).await.unwrap();
+
+        assert!(result.contains("declined"), "Should report the refusal: {}", result);
+        assert_eq!(fs::read_to_string(&test_file).unwrap(), "line 1\nold line\n");
+        let mut diff = None;
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::Diff { diff: text, .. } = event {
+                diff = Some(text);
+            }
+        }
+        let diff = diff.expect("Should show the diff");
+        assert!(diff.contains("-old line\n+new line"), "Unexpected diff: {}", diff);
+    }
 }
 
 // =============================================================================
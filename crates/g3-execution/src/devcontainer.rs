@@ -0,0 +1,600 @@
+//! Running commands inside a project's devcontainer.
+//!
+//! A project with `.devcontainer/devcontainer.json` (or `.devcontainer.json`)
+//! describes the container its toolchain lives in. In devcontainer mode,
+//! commands run there through `docker exec` instead of on the host, which
+//! keeps the host clean and gives builds the same toolchain as CI. The
+//! workspace is bind-mounted at the container's `workspaceFolder`, so files
+//! edited on the host are the files the container sees.
+//!
+//! The container is created on first use, labelled with a hash of the
+//! configuration, and kept running for later commands and sessions. It is
+//! recreated when `devcontainer.json` changes. Image- and Dockerfile-based
+//! configurations are supported; Docker Compose ones are not.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::env::EnvPolicy;
+use crate::Shell;
+
+/// Where a devcontainer configuration may live, relative to the workspace.
+const CONFIG_LOCATIONS: &[&str] = &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+
+/// Label holding the hash of the configuration a container was created from.
+const CONFIG_LABEL: &str = "g3.devcontainer.config";
+
+/// Label holding the host workspace a container was created for.
+const WORKSPACE_LABEL: &str = "g3.devcontainer.workspace";
+
+/// The path of the devcontainer configuration in `workspace`, if it has one.
+pub fn find(workspace: &Path) -> Option<PathBuf> {
+    CONFIG_LOCATIONS
+        .iter()
+        .map(|location| workspace.join(location))
+        .find(|path| path.is_file())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Image {
+    /// An image to pull
+    Pull(String),
+    /// An image to build from a Dockerfile
+    Build {
+        dockerfile: PathBuf,
+        context: PathBuf,
+    },
+}
+
+/// A workspace's devcontainer, as described by its `devcontainer.json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Devcontainer {
+    /// docker (or compatible) binary to run
+    docker: String,
+    /// The host workspace, mounted into the container
+    workspace: PathBuf,
+    /// Container name, and the tag of a built image
+    name: String,
+    config_hash: String,
+    image: Image,
+    /// Where the workspace is mounted in the container
+    workspace_folder: String,
+    /// User commands run as (`remoteUser`)
+    remote_user: Option<String>,
+    /// User the container runs as (`containerUser`)
+    container_user: Option<String>,
+    container_env: BTreeMap<String, String>,
+    remote_env: BTreeMap<String, String>,
+    /// Extra `docker run` arguments (`runArgs`)
+    run_args: Vec<String>,
+    /// Commands run once after the container is created
+    post_create: Vec<String>,
+}
+
+impl Devcontainer {
+    /// Load the devcontainer of `workspace`, run with the `docker` binary.
+    pub fn load(workspace: &Path, docker: &str) -> Result<Self> {
+        let workspace = workspace
+            .canonicalize()
+            .with_context(|| format!("Can't resolve workspace {}", workspace.display()))?;
+        let path = find(&workspace).ok_or_else(|| {
+            anyhow!(
+                "No .devcontainer/devcontainer.json in {}",
+                workspace.display()
+            )
+        })?;
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Can't read {}", path.display()))?;
+        Self::parse(&workspace, &path, &text, docker)
+            .with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    fn parse(workspace: &Path, path: &Path, text: &str, docker: &str) -> Result<Self> {
+        let json: Value = serde_json::from_str(&strip_trailing_commas(&strip_comments(text)))?;
+        let basename = workspace
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "workspace".to_string());
+        let vars = Variables {
+            workspace,
+            basename: &basename,
+            workspace_folder: None,
+        };
+        let string = |key: &str| {
+            json.get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| vars.expand(s))
+        };
+
+        if json.get("dockerComposeFile").is_some() {
+            bail!("Docker Compose devcontainers are not supported");
+        }
+        let config_dir = path.parent().unwrap_or(workspace);
+        let build = json.get("build");
+        let dockerfile = build
+            .and_then(|b| b.get("dockerfile"))
+            .and_then(|v| v.as_str())
+            .or_else(|| json.get("dockerFile").and_then(|v| v.as_str()));
+        let image = match (dockerfile, string("image")) {
+            (Some(dockerfile), _) => {
+                let context = build
+                    .and_then(|b| b.get("context"))
+                    .and_then(|v| v.as_str())
+                    .or_else(|| json.get("context").and_then(|v| v.as_str()))
+                    .unwrap_or(".");
+                Image::Build {
+                    dockerfile: config_dir.join(vars.expand(dockerfile)),
+                    context: config_dir.join(vars.expand(context)),
+                }
+            }
+            (None, Some(image)) => Image::Pull(image),
+            (None, None) => bail!("Neither \"image\" nor \"build.dockerfile\" is set"),
+        };
+
+        let workspace_folder =
+            string("workspaceFolder").unwrap_or_else(|| format!("/workspaces/{}", basename));
+        let vars = Variables {
+            workspace_folder: Some(&workspace_folder),
+            ..vars
+        };
+        let env = |key: &str| -> BTreeMap<String, String> {
+            json.get(key)
+                .and_then(|v| v.as_object())
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .filter_map(|(k, v)| Some((k.clone(), vars.expand(v.as_str()?))))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let run_args = json
+            .get("runArgs")
+            .and_then(|v| v.as_array())
+            .map(|args| {
+                args.iter()
+                    .filter_map(|a| a.as_str().map(|s| vars.expand(s)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let post_create = json
+            .get("postCreateCommand")
+            .map(|command| lifecycle_commands(command, &vars))
+            .unwrap_or_default();
+        let container_env = env("containerEnv");
+        let remote_env = env("remoteEnv");
+
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let config_hash = format!("{:016x}", hasher.finish());
+        let mut hasher = DefaultHasher::new();
+        workspace.hash(&mut hasher);
+        let slug: String = basename
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let name = format!("g3-{}-{:08x}", slug, hasher.finish() as u32);
+
+        Ok(Self {
+            docker: docker.to_string(),
+            workspace: workspace.to_path_buf(),
+            name,
+            config_hash,
+            image,
+            workspace_folder,
+            remote_user: string("remoteUser"),
+            container_user: string("containerUser"),
+            container_env,
+            remote_env,
+            run_args,
+            post_create,
+        })
+    }
+
+    /// Name of the container.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Where the workspace is mounted in the container.
+    pub fn workspace_folder(&self) -> &str {
+        &self.workspace_folder
+    }
+
+    /// The container path of the host path `path`, which must be in the
+    /// workspace.
+    pub fn container_path(&self, path: &Path) -> Option<String> {
+        if let Ok(relative) = path.strip_prefix(&self.workspace) {
+            return Some(join_container_path(&self.workspace_folder, relative));
+        }
+        // The workspace is canonical; `path` may not be
+        let canonical = path.canonicalize().ok()?;
+        let relative = canonical.strip_prefix(&self.workspace).ok()?;
+        Some(join_container_path(&self.workspace_folder, relative))
+    }
+
+    /// `docker exec` running `<shell> -c <code>` in the container, in the
+    /// container path of `dir` (the workspace folder without one), with the
+    /// variables `env` sets. Host variables aren't passed into the container,
+    /// so there is nothing to strip. `interactive` keeps stdin open.
+    pub fn command(
+        &self,
+        shell: Shell,
+        code: &str,
+        dir: Option<&Path>,
+        env: &EnvPolicy,
+        interactive: bool,
+    ) -> Result<std::process::Command> {
+        let workdir = match dir {
+            Some(dir) => self.container_path(dir).ok_or_else(|| {
+                anyhow!(
+                    "{} is outside the workspace mounted in the devcontainer",
+                    dir.display()
+                )
+            })?,
+            None => self.workspace_folder.clone(),
+        };
+        let mut cmd = std::process::Command::new(&self.docker);
+        cmd.arg("exec");
+        if interactive {
+            cmd.arg("-i");
+        }
+        if let Some(user) = &self.remote_user {
+            cmd.args(["-u", user]);
+        }
+        cmd.args(["-w", &workdir]);
+        // Later -e flags win, so the configured variables override remoteEnv
+        for (name, value) in self.remote_env.iter().chain(&env.set) {
+            cmd.arg("-e").arg(format!("{}={}", name, value));
+        }
+        cmd.arg(&self.name).args(shell.argv(code));
+        Ok(cmd)
+    }
+
+    /// Make sure the container is running: start it if it's stopped, and
+    /// create it (building its image first if needed) if it doesn't exist or
+    /// was created from an older configuration. Returns whether it was
+    /// created.
+    pub async fn ensure_running(&self) -> Result<bool> {
+        let format = format!(
+            "{{{{.State.Running}}}} {{{{index .Config.Labels \"{}\"}}}}",
+            CONFIG_LABEL
+        );
+        if let Ok(state) = self.docker(&["inspect", "-f", &format, &self.name]).await {
+            let (running, hash) = state.trim().split_once(' ').unwrap_or((state.trim(), ""));
+            if hash == self.config_hash {
+                if running != "true" {
+                    debug!("Starting devcontainer {}", self.name);
+                    self.docker(&["start", &self.name]).await?;
+                }
+                return Ok(false);
+            }
+            debug!("devcontainer.json changed, recreating {}", self.name);
+            self.docker(&["rm", "-f", &self.name]).await?;
+        }
+        self.create().await?;
+        Ok(true)
+    }
+
+    async fn create(&self) -> Result<()> {
+        let image = match &self.image {
+            Image::Pull(image) => image.clone(),
+            Image::Build {
+                dockerfile,
+                context,
+            } => {
+                debug!("Building devcontainer image {}", self.name);
+                let dockerfile = dockerfile.to_string_lossy();
+                let context = context.to_string_lossy();
+                self.docker(&["build", "-t", &self.name, "-f", &dockerfile, &context])
+                    .await?;
+                self.name.clone()
+            }
+        };
+
+        debug!("Creating devcontainer {} from {}", self.name, image);
+        let mount = format!("{}:{}", self.workspace.display(), self.workspace_folder);
+        let config_label = format!("{}={}", CONFIG_LABEL, self.config_hash);
+        let workspace_label = format!("{}={}", WORKSPACE_LABEL, self.workspace.display());
+        let mut args = vec![
+            "run".to_string(),
+            "-d".to_string(),
+            "--name".to_string(),
+            self.name.clone(),
+            "--label".to_string(),
+            config_label,
+            "--label".to_string(),
+            workspace_label,
+            "-v".to_string(),
+            mount,
+            "-w".to_string(),
+            self.workspace_folder.clone(),
+        ];
+        if let Some(user) = &self.container_user {
+            args.extend(["-u".to_string(), user.clone()]);
+        }
+        for (name, value) in &self.container_env {
+            args.extend(["-e".to_string(), format!("{}={}", name, value)]);
+        }
+        args.extend(self.run_args.iter().cloned());
+        // Keep the container alive whatever the image's own entrypoint does
+        args.extend([
+            "--entrypoint".to_string(),
+            "sleep".to_string(),
+            image,
+            "infinity".to_string(),
+        ]);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.docker(&args).await?;
+
+        for command in &self.post_create {
+            debug!("Running postCreateCommand: {}", command);
+            let output = tokio::process::Command::from(self.command(
+                Shell::Bash,
+                command,
+                None,
+                &EnvPolicy::default(),
+                false,
+            )?)
+            .output()
+            .await?;
+            if !output.status.success() {
+                bail!(
+                    "postCreateCommand `{}` failed: {}",
+                    command,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Run docker with `args`, returning its stdout.
+    async fn docker(&self, args: &[&str]) -> Result<String> {
+        let output = tokio::process::Command::new(&self.docker)
+            .args(args)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run {}", self.docker))?;
+        if !output.status.success() {
+            bail!(
+                "`{} {}` failed: {}",
+                self.docker,
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// The values of the `${...}` variables devcontainer.json strings may use.
+#[derive(Clone, Copy)]
+struct Variables<'a> {
+    workspace: &'a Path,
+    basename: &'a str,
+    workspace_folder: Option<&'a str>,
+}
+
+impl Variables<'_> {
+    fn expand(&self, s: &str) -> String {
+        let mut out = String::new();
+        let mut rest = s;
+        while let Some(start) = rest.find("${") {
+            out.push_str(&rest[..start]);
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let name = &rest[start + 2..start + len];
+            let value = match name {
+                "localWorkspaceFolder" => Some(self.workspace.display().to_string()),
+                "localWorkspaceFolderBasename" => Some(self.basename.to_string()),
+                "containerWorkspaceFolder" => self.workspace_folder.map(str::to_string),
+                _ => name
+                    .strip_prefix("localEnv:")
+                    .map(|var| std::env::var(var).unwrap_or_default()),
+            };
+            match value {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[start..start + len + 1]),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// The shell commands of a lifecycle command, which is a string, an array
+/// (one command, not run through a shell) or an object of either.
+fn lifecycle_commands(command: &Value, vars: &Variables) -> Vec<String> {
+    match command {
+        Value::String(s) => vec![vars.expand(s)],
+        Value::Array(words) => vec![words
+            .iter()
+            .filter_map(|w| w.as_str())
+            .map(|w| single_quote(&vars.expand(w)))
+            .collect::<Vec<_>>()
+            .join(" ")],
+        Value::Object(commands) => commands
+            .values()
+            .flat_map(|c| lifecycle_commands(c, vars))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn join_container_path(folder: &str, relative: &Path) -> String {
+    let relative = relative.to_string_lossy();
+    if relative.is_empty() {
+        folder.to_string()
+    } else {
+        format!("{}/{}", folder.trim_end_matches('/'), relative)
+    }
+}
+
+/// `s` as a single-quoted bash word.
+fn single_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// `text` with JSONC `//` and `/* */` comments removed.
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push(c);
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `text` without commas directly before a closing `}` or `]`, which JSONC
+/// allows.
+fn strip_trailing_commas(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = text[i + 1..].trim_start().chars().next();
+            if matches!(next, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_jsonc_and_maps_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().canonicalize().unwrap().join("My App");
+        std::fs::create_dir_all(workspace.join(".devcontainer")).unwrap();
+        std::fs::create_dir_all(workspace.join("src")).unwrap();
+        let text = r#"{
+            // The CI toolchain
+            "name": "app // not a comment",
+            "build": { "dockerfile": "Dockerfile", "context": "..", },
+            /* mounted elsewhere */
+            "workspaceFolder": "/work/${localWorkspaceFolderBasename}",
+            "remoteUser": "dev",
+            "remoteEnv": { "CARGO_HOME": "${containerWorkspaceFolder}/.cargo" },
+            "postCreateCommand": ["npm", "install", "it's"],
+        }"#;
+        std::fs::write(workspace.join(".devcontainer/devcontainer.json"), text).unwrap();
+
+        let container = Devcontainer::load(&workspace, "docker").unwrap();
+        assert_eq!(container.workspace_folder(), "/work/My App");
+        assert!(container.name().starts_with("g3-my-app-"));
+        assert_eq!(
+            container.image,
+            Image::Build {
+                dockerfile: workspace.join(".devcontainer/Dockerfile"),
+                context: workspace.join(".devcontainer/.."),
+            }
+        );
+        assert_eq!(container.remote_env["CARGO_HOME"], "/work/My App/.cargo");
+        assert_eq!(container.post_create, vec!["'npm' 'install' 'it'\\''s'"]);
+        assert_eq!(
+            container.container_path(&workspace.join("src")).as_deref(),
+            Some("/work/My App/src")
+        );
+        assert_eq!(container.container_path(dir.path()), None);
+
+        let env = EnvPolicy::new(
+            BTreeMap::from([("CARGO_HOME".to_string(), "/tmp".to_string())]),
+            vec!["*".to_string()],
+        );
+        let cmd = container
+            .command(
+                Shell::Bash,
+                "cargo test",
+                Some(&workspace.join("src")),
+                &env,
+                false,
+            )
+            .unwrap();
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy()).collect();
+        assert_eq!(
+            args,
+            [
+                "exec",
+                "-u",
+                "dev",
+                "-w",
+                "/work/My App/src",
+                "-e",
+                "CARGO_HOME=/work/My App/.cargo",
+                "-e",
+                "CARGO_HOME=/tmp",
+                container.name(),
+                "bash",
+                "-c",
+                "cargo test"
+            ]
+        );
+        assert!(container
+            .command(Shell::Bash, "ls", Some(dir.path()), &env, false)
+            .is_err());
+    }
+
+    #[test]
+    fn test_image_is_required() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".devcontainer.json");
+        let parse = |text| Devcontainer::parse(dir.path(), &path, text, "docker");
+        assert!(parse(r#"{"image": "rust:1"}"#).is_ok());
+        assert!(parse(r#"{"name": "x"}"#).is_err());
+        assert!(parse(r#"{"dockerComposeFile": "compose.yml", "service": "app"}"#).is_err());
+    }
+}
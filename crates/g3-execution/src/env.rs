@@ -0,0 +1,96 @@
+//! The environment agent-run commands get.
+//!
+//! By default a command inherits the whole environment of g3, including
+//! whatever credentials happen to be exported in the user's shell. An
+//! `EnvPolicy` strips host variables matching its patterns (`AWS_*`,
+//! `*_TOKEN`) and sets its own variables on top, which win over stripping.
+
+use std::collections::BTreeMap;
+use std::process::Command;
+
+/// Variables to strip from and add to a command's environment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvPolicy {
+    /// Set on every command
+    pub set: BTreeMap<String, String>,
+    /// Names of host variables to remove; `*` matches any run of characters
+    pub strip: Vec<String>,
+}
+
+impl EnvPolicy {
+    pub fn new(set: BTreeMap<String, String>, strip: Vec<String>) -> Self {
+        Self { set, strip }
+    }
+
+    /// Whether the host variable `name` is removed.
+    pub fn strips(&self, name: &str) -> bool {
+        !self.set.contains_key(name) && self.strip.iter().any(|p| wildcard_match(p, name))
+    }
+
+    /// Apply the policy to `cmd`'s environment.
+    pub fn apply(&self, cmd: &mut Command) {
+        if !self.strip.is_empty() {
+            for (name, _) in std::env::vars_os() {
+                if let Some(name) = name.to_str() {
+                    if self.strips(name) {
+                        cmd.env_remove(name);
+                    }
+                }
+            }
+        }
+        cmd.envs(&self.set);
+    }
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any run of
+/// characters (including none).
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*`: the whole name must match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcards_and_apply() {
+        assert!(wildcard_match("AWS_*", "AWS_SECRET_ACCESS_KEY"));
+        assert!(wildcard_match("*_TOKEN", "GITHUB_TOKEN"));
+        assert!(wildcard_match("*SECRET*", "MY_SECRET_VALUE"));
+        assert!(wildcard_match("HOME", "HOME"));
+        assert!(!wildcard_match("HOME", "HOMEBREW"));
+        assert!(!wildcard_match("*_TOKEN", "TOKEN"));
+        assert!(!wildcard_match("A*B*C", "AXC"));
+
+        let mut policy = EnvPolicy::new(
+            BTreeMap::from([("G3_ENV_TEST_KEEP".to_string(), "set".to_string())]),
+            vec!["G3_ENV_TEST_*".to_string()],
+        );
+        assert!(policy.strips("G3_ENV_TEST_SECRET"));
+        // Set variables win over stripping
+        assert!(!policy.strips("G3_ENV_TEST_KEEP"));
+
+        policy.strip.push("PA*H".to_string());
+        let mut cmd = Command::new("sh");
+        policy.apply(&mut cmd);
+        let envs: Vec<_> = cmd.get_envs().collect();
+        assert!(envs.contains(&("PATH".as_ref(), None)));
+        assert!(envs.contains(&("G3_ENV_TEST_KEEP".as_ref(), Some("set".as_ref()))));
+    }
+}
@@ -5,6 +5,18 @@ use std::process::Command;
 use tempfile::NamedTempFile;
 use tracing::{debug, error};
 
+pub mod devcontainer;
+pub mod env;
+pub mod persistent_shell;
+pub mod sandbox;
+pub mod shell;
+
+pub use devcontainer::Devcontainer;
+pub use env::EnvPolicy;
+pub use persistent_shell::PersistentShell;
+pub use sandbox::SandboxPolicy;
+pub use shell::Shell;
+
 /// Expand tilde (~) in a path to the user's home directory
 fn expand_tilde(path: &str) -> String {
     if path.starts_with("~") {
@@ -16,8 +28,17 @@ fn expand_tilde(path: &str) -> String {
     path.to_string()
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct CodeExecutor {
-    // Future: add configuration for execution limits, sandboxing, etc.
+    /// Runs streamed commands (see `shell`)
+    shell: Shell,
+    /// Confines streamed commands when set (see `sandbox`)
+    sandbox: Option<SandboxPolicy>,
+    /// Environment of streamed commands (see `env`)
+    env: EnvPolicy,
+    /// Runs streamed commands in this container when set, instead of on the
+    /// host (see `devcontainer`)
+    container: Option<Devcontainer>,
 }
 
 #[derive(Debug, Clone)]
@@ -30,7 +51,72 @@ pub struct ExecutionResult {
 
 impl CodeExecutor {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            shell: Shell::default(),
+            sandbox: None,
+            env: EnvPolicy::default(),
+            container: None,
+        }
+    }
+
+    /// Run streamed commands with `shell` instead of bash.
+    pub fn with_shell(mut self, shell: Shell) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// The shell streamed commands run in.
+    pub fn shell(&self) -> Shell {
+        self.shell
+    }
+
+    /// Run streamed commands confined by `policy`.
+    pub fn with_sandbox(mut self, policy: SandboxPolicy) -> Self {
+        self.sandbox = Some(policy);
+        self
+    }
+
+    /// Give streamed commands the environment `env` describes.
+    pub fn with_env(mut self, env: EnvPolicy) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Run streamed commands in `container`, which must be running.
+    /// The container takes the place of the sandbox.
+    pub fn with_devcontainer(mut self, container: Devcontainer) -> Self {
+        self.container = Some(container);
+        self
+    }
+
+    /// `<shell> -c <code>` in `working_dir`: in the devcontainer if one is
+    /// set, sandboxed if a policy is. `interactive` keeps a container
+    /// command's stdin open.
+    fn shell_command(
+        &self,
+        code: &str,
+        working_dir: Option<&str>,
+        interactive: bool,
+    ) -> Result<tokio::process::Command> {
+        let working_dir = working_dir.map(expand_tilde);
+        if let Some(container) = &self.container {
+            let dir = working_dir.as_deref().map(std::path::Path::new);
+            let cmd = container.command(self.shell, code, dir, &self.env, interactive)?;
+            return Ok(tokio::process::Command::from(cmd));
+        }
+        let mut cmd = match &self.sandbox {
+            Some(policy) => sandbox::sandboxed(self.shell, code, policy)?,
+            None => {
+                let mut cmd = tokio::process::Command::new(self.shell.program());
+                cmd.arg("-c").arg(code);
+                cmd
+            }
+        };
+        self.env.apply(cmd.as_std_mut());
+        if let Some(dir) = working_dir {
+            cmd.current_dir(dir);
+        }
+        Ok(cmd)
     }
 
     /// Extract code blocks from LLM response and execute them
@@ -269,7 +355,9 @@ impl CodeExecutor {
             .await
     }
 
-    /// Execute bash command with streaming output in a specific directory
+    /// Execute bash command with streaming output in a specific directory.
+    /// The command runs in the executor's shell, bash unless `with_shell`
+    /// chose another.
     pub async fn execute_bash_streaming_in_dir<R: OutputReceiver>(
         &self,
         code: &str,
@@ -278,7 +366,6 @@ impl CodeExecutor {
     ) -> Result<ExecutionResult> {
         use std::process::Stdio;
         use tokio::io::{AsyncBufReadExt, BufReader};
-        use tokio::process::Command as TokioCommand;
 
         // CRITICAL DEBUG: Print to stderr so it's always visible
         debug!("========== execute_bash_streaming_in_dir START ==========");
@@ -313,15 +400,7 @@ impl CodeExecutor {
 
         if is_detached {
             // For detached commands, just spawn and return immediately
-            let mut cmd = TokioCommand::new("bash");
-            cmd.arg("-c").arg(code);
-
-            // Set working directory if provided
-            if let Some(dir) = working_dir {
-                let expanded_dir = expand_tilde(dir);
-                cmd.current_dir(&expanded_dir);
-            }
-
+            let mut cmd = self.shell_command(code, working_dir, false)?;
             cmd.spawn()?;
 
             // Don't wait for the process - it's meant to run independently
@@ -333,27 +412,8 @@ impl CodeExecutor {
             });
         }
 
-        let mut cmd = TokioCommand::new("bash");
-        cmd.arg("-c")
-            .arg(code)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        // Set working directory if provided
-        if let Some(dir) = working_dir {
-            debug!("Setting current_dir on command to: {}", dir);
-            let expanded_dir = expand_tilde(dir);
-            debug!("Expanded working dir: {}", expanded_dir);
-            debug!(
-                "Expanded dir exists: {}",
-                std::path::Path::new(&expanded_dir).exists()
-            );
-            debug!(
-                "Expanded dir is_dir: {}",
-                std::path::Path::new(&expanded_dir).is_dir()
-            );
-            cmd.current_dir(&expanded_dir);
-        }
+        let mut cmd = self.shell_command(code, working_dir, false)?;
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
 
         debug!("About to spawn command...");
         let spawn_result = cmd.spawn();
@@ -0,0 +1,279 @@
+//! A shell process kept across commands.
+//!
+//! One-shot `bash -c` invocations lose everything a command sets up: a `cd`,
+//! an `export`, a `source .venv/bin/activate`. A `PersistentShell` starts one
+//! shell (the executor's: bash, zsh, sh or fish) on the first command and
+//! feeds it every later one, so that state survives. Each command is passed to `eval` (a syntax error fails the
+//! command, not the shell) with stdin from `/dev/null`, and followed by a
+//! marker line on stdout carrying its exit status and one on stderr, which is
+//! how the end of its output is found.
+//!
+//! The process is killed when the `PersistentShell` is dropped, when a
+//! command is abandoned halfway (its future dropped, e.g. on a timeout), or
+//! when the command ends the shell itself (`exit`, `set -e`); the next
+//! command then starts a fresh shell.
+
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{CodeExecutor, ExecutionResult, OutputReceiver};
+
+struct ShellProcess {
+    /// Directory the shell was started in
+    cwd: PathBuf,
+    /// Sandbox, environment and container the shell was started with
+    executor: CodeExecutor,
+    /// Ends each command's output; random so output can't fake it
+    marker: String,
+    /// Set when the command ended the shell
+    exited: bool,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    stderr: Lines<BufReader<ChildStderr>>,
+}
+
+impl ShellProcess {
+    fn start(cwd: &Path, executor: &CodeExecutor) -> Result<Self> {
+        let shell = executor.shell().interactive()?;
+        let mut cmd = executor.shell_command(shell, cwd.to_str(), true)?;
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("Failed to start {}: {}", executor.shell(), e))?;
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("No stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("No stdout"))?;
+        let stderr = child.stderr.take().ok_or_else(|| anyhow!("No stderr"))?;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        Ok(Self {
+            cwd: cwd.to_path_buf(),
+            executor: executor.clone(),
+            marker: format!("__G3_DONE_{}_{:x}__", std::process::id(), nanos),
+            exited: false,
+            child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            stderr: BufReader::new(stderr).lines(),
+        })
+    }
+
+    async fn execute<R: OutputReceiver>(
+        &mut self,
+        code: &str,
+        receiver: &R,
+    ) -> Result<ExecutionResult> {
+        let script = self.executor.shell().persistent_script(code, &self.marker);
+        self.stdin.write_all(script.as_bytes()).await?;
+        self.stdin.flush().await?;
+
+        let mut stdout_output = Vec::new();
+        let mut stderr_output = Vec::new();
+        let mut exit_code = None;
+        let mut stderr_done = false;
+        while exit_code.is_none() || !stderr_done {
+            tokio::select! {
+                line = self.stdout.next_line(), if exit_code.is_none() => {
+                    let Some(line) = line? else {
+                        self.exited = true;
+                        break;
+                    };
+                    let (output, status) = split_marker(&line, &self.marker);
+                    if !output.is_empty() {
+                        receiver.on_output_line(output);
+                        stdout_output.push(output.to_string());
+                    }
+                    if let Some(status) = status {
+                        exit_code = Some(status.trim().parse().unwrap_or(-1));
+                    }
+                }
+                line = self.stderr.next_line(), if !stderr_done => {
+                    let Some(line) = line? else {
+                        self.exited = true;
+                        break;
+                    };
+                    let (output, status) = split_marker(&line, &self.marker);
+                    if !output.is_empty() {
+                        receiver.on_output_line(output);
+                        stderr_output.push(output.to_string());
+                    }
+                    stderr_done = status.is_some();
+                }
+            }
+        }
+
+        if self.exited {
+            // The command ended the shell: keep what it still printed and
+            // report the shell's exit status
+            while let Ok(Some(line)) = self.stdout.next_line().await {
+                receiver.on_output_line(&line);
+                stdout_output.push(line);
+            }
+            while let Ok(Some(line)) = self.stderr.next_line().await {
+                receiver.on_output_line(&line);
+                stderr_output.push(line);
+            }
+            exit_code = Some(self.child.wait().await?.code().unwrap_or(-1));
+        }
+
+        let exit_code = exit_code.unwrap_or(-1);
+        Ok(ExecutionResult {
+            stdout: stdout_output.join("\n"),
+            stderr: stderr_output.join("\n"),
+            exit_code,
+            success: exit_code == 0,
+        })
+    }
+}
+
+/// A shell process shared by the commands of one session, started on first
+/// use.
+#[derive(Default)]
+pub struct PersistentShell {
+    process: Mutex<Option<ShellProcess>>,
+}
+
+impl PersistentShell {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `code` in the shell, streaming its output lines to `receiver`.
+    ///
+    /// A shell is started in `cwd` the way `executor` runs commands (its
+    /// shell, sandbox, environment and devcontainer), if none is running or
+    /// the running one was started with a different directory or executor.
+    /// Fails for a shell that can't be kept running (nushell).
+    pub async fn execute<R: OutputReceiver>(
+        &self,
+        code: &str,
+        cwd: &Path,
+        executor: &CodeExecutor,
+        receiver: &R,
+    ) -> Result<ExecutionResult> {
+        let mut process = self.process.lock().await;
+        if process
+            .as_ref()
+            .is_some_and(|p| p.cwd != cwd || p.executor != *executor)
+        {
+            debug!("Restarting persistent shell for a new directory, sandbox or environment");
+            *process = None;
+        }
+        let mut running = match process.take() {
+            Some(running) => running,
+            None => {
+                debug!("Starting persistent shell in {}", cwd.display());
+                ShellProcess::start(cwd, executor)?
+            }
+        };
+
+        // Until the command finishes, the shell is owned by this future, so
+        // abandoning the command kills it
+        let result = running.execute(code, receiver).await;
+        if result.is_ok() && !running.exited {
+            *process = Some(running);
+        }
+        result
+    }
+
+    /// Whether a shell is running.
+    pub async fn is_running(&self) -> bool {
+        self.process.lock().await.is_some()
+    }
+
+    /// Stop the shell, if running, discarding its state. Returns whether one
+    /// was running.
+    pub async fn reset(&self) -> bool {
+        self.process.lock().await.take().is_some()
+    }
+}
+
+/// `line` split at the marker into the output before it and the text after
+/// it, or the whole line and `None` without a marker.
+fn split_marker<'a>(line: &'a str, marker: &str) -> (&'a str, Option<&'a str>) {
+    match line.find(marker) {
+        Some(at) => (&line[..at], Some(&line[at + marker.len()..])),
+        None => (line, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Shell;
+    use std::sync::Mutex as StdMutex;
+
+    #[derive(Default)]
+    struct Lines(StdMutex<Vec<String>>);
+
+    impl OutputReceiver for Lines {
+        fn on_output_line(&self, line: &str) {
+            self.0.lock().unwrap().push(line.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_state_survives_between_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let shell = PersistentShell::new();
+        let lines = Lines::default();
+        let executor = CodeExecutor::new();
+        let run = |code: &'static str| shell.execute(code, dir.path(), &executor, &lines);
+
+        let result = run("cd sub && export GREETING='it'\"'\"'s me'")
+            .await
+            .unwrap();
+        assert!(result.success);
+        let result = run("basename \"$PWD\"; echo \"$GREETING\"; printf partial")
+            .await
+            .unwrap();
+        assert_eq!(result.stdout, "sub\nit's me\npartial");
+
+        let result = run("echo oops >&2; false").await.unwrap();
+        assert_eq!((result.exit_code, result.stderr.as_str()), (1, "oops"));
+        // A syntax error fails the command, not the shell
+        let result = run("if then").await.unwrap();
+        assert_eq!(result.exit_code, 2);
+        assert_eq!(run("echo $GREETING").await.unwrap().stdout, "it's me");
+
+        // `exit` ends the shell; the next command gets a fresh one
+        let result = run("echo bye; exit 3").await.unwrap();
+        assert_eq!((result.exit_code, result.stdout.as_str()), (3, "bye"));
+        assert!(!shell.is_running().await);
+        assert_eq!(run("echo \"[$GREETING]\"").await.unwrap().stdout, "[]");
+        assert!(lines.0.lock().unwrap().contains(&"sub".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_other_shells() {
+        let dir = tempfile::tempdir().unwrap();
+        let shell = PersistentShell::new();
+        let lines = Lines::default();
+        let sh = CodeExecutor::new().with_shell(Shell::Sh);
+
+        shell
+            .execute("GREETING=hi", dir.path(), &sh, &lines)
+            .await
+            .unwrap();
+        let result = shell
+            .execute("echo \"$GREETING\"", dir.path(), &sh, &lines)
+            .await
+            .unwrap();
+        assert_eq!(result.stdout, "hi");
+
+        let nu = CodeExecutor::new().with_shell(Shell::Nushell);
+        assert!(shell.execute("ls", dir.path(), &nu, &lines).await.is_err());
+    }
+}
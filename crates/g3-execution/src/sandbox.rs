@@ -0,0 +1,307 @@
+//! OS-level sandboxing of shell commands.
+//!
+//! A sandboxed command can write only to the workspace, the temp directory
+//! and a few devices, read only those plus system directories and the
+//! configured read-only paths, and optionally has no network access.
+//!
+//! * macOS: the command runs under `sandbox-exec` with a profile generated
+//!   from the policy (see `SandboxPolicy::macos_profile`).
+//! * Linux: the shell restricts itself with Landlock before `exec`, and
+//!   without network a seccomp filter refuses IPv4/IPv6 sockets. A kernel
+//!   without Landlock (before 5.13) fails the command rather than running it
+//!   unrestricted.
+//!
+//! Other platforms refuse to run sandboxed commands.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::Shell;
+
+/// System directories every sandboxed command may read.
+#[cfg(target_os = "macos")]
+const SYSTEM_READ_ONLY_PATHS: &[&str] = &[
+    "/bin",
+    "/sbin",
+    "/usr",
+    "/etc",
+    "/opt",
+    "/System",
+    "/Library",
+    "/Applications",
+    "/private/etc",
+    "/private/var/db",
+    "/dev",
+];
+#[cfg(not(target_os = "macos"))]
+const SYSTEM_READ_ONLY_PATHS: &[&str] = &[
+    "/bin", "/sbin", "/usr", "/lib", "/lib32", "/lib64", "/etc", "/opt", "/proc", "/sys", "/dev",
+    "/run", "/nix", "/snap",
+];
+
+/// Devices every sandboxed command may write to.
+const WRITABLE_DEVICES: &[&str] = &["/dev/null", "/dev/zero", "/dev/tty", "/dev/urandom"];
+
+/// What a sandboxed command may touch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxPolicy {
+    /// Readable and writable, with everything under them
+    pub writable: Vec<PathBuf>,
+    /// Readable, with everything under them
+    pub read_only: Vec<PathBuf>,
+    pub allow_network: bool,
+}
+
+impl SandboxPolicy {
+    /// A policy confining commands to `workspace`: it and the temp directory
+    /// are writable, system directories readable, and the network allowed.
+    pub fn new(workspace: &Path) -> Self {
+        let mut policy = Self {
+            writable: Vec::new(),
+            read_only: Vec::new(),
+            allow_network: true,
+        };
+        policy.add_writable(workspace);
+        policy.add_writable(&std::env::temp_dir());
+        for device in WRITABLE_DEVICES {
+            policy.add_writable(Path::new(device));
+        }
+        for dir in SYSTEM_READ_ONLY_PATHS {
+            policy.add_read_only(Path::new(dir));
+        }
+        policy
+    }
+
+    /// Allow writing under `path`. Missing paths are ignored.
+    pub fn add_writable(&mut self, path: &Path) {
+        if let Some(path) = resolve(path) {
+            if !self.writable.contains(&path) {
+                self.writable.push(path);
+            }
+        }
+    }
+
+    /// Allow reading under `path`. Missing paths are ignored.
+    pub fn add_read_only(&mut self, path: &Path) {
+        if let Some(path) = resolve(path) {
+            if !self.read_only.contains(&path) {
+                self.read_only.push(path);
+            }
+        }
+    }
+
+    /// The policy as a `sandbox-exec` profile.
+    pub fn macos_profile(&self) -> String {
+        let mut profile = String::from(
+            "(version 1)\n\
+             (deny default)\n\
+             (allow process-exec process-fork signal)\n\
+             (allow sysctl-read mach-lookup ipc-posix-shm pseudo-tty file-ioctl)\n\
+             (allow file-read-metadata)\n\
+             (allow file-read* (literal \"/\"))\n",
+        );
+        for path in &self.read_only {
+            profile.push_str(&format!("(allow file-read* {})\n", sbpl_path(path)));
+        }
+        for path in &self.writable {
+            profile.push_str(&format!(
+                "(allow file-read* file-write* {})\n",
+                sbpl_path(path)
+            ));
+        }
+        if self.allow_network {
+            profile.push_str("(allow network*)\n");
+        }
+        profile
+    }
+}
+
+/// `<shell> -c <code>` confined by `policy`.
+pub fn sandboxed(shell: Shell, code: &str, policy: &SandboxPolicy) -> Result<Command> {
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = Command::new("/usr/bin/sandbox-exec");
+        cmd.arg("-p")
+            .arg(policy.macos_profile())
+            .args(shell.argv(code));
+        Ok(cmd)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let mut cmd = Command::new(shell.program());
+        cmd.arg("-c").arg(code);
+        linux::restrict(&mut cmd, policy)?;
+        Ok(cmd)
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        let _ = (shell, code, policy);
+        anyhow::bail!("Sandboxed commands are only supported on macOS and Linux")
+    }
+}
+
+/// `path` made absolute with symlinks resolved (sandbox-exec matches real
+/// paths, `/tmp` is `/private/tmp` on macOS), or `None` if it doesn't exist.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    std::fs::canonicalize(path).ok()
+}
+
+/// A `subpath` filter for the profile, quoted as an SBPL string.
+fn sbpl_path(path: &Path) -> String {
+    let path = path
+        .to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    format!("(subpath \"{}\")", path)
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::SandboxPolicy;
+    use anyhow::Result;
+    use landlock::{
+        path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr,
+        RulesetStatus, ABI,
+    };
+    use seccompiler::{
+        BpfProgram, SeccompAction, SeccompCmpArgLen, SeccompCmpOp, SeccompCondition, SeccompFilter,
+        SeccompRule,
+    };
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::sync::Mutex;
+    use tokio::process::Command;
+
+    /// Have `cmd` restrict itself to `policy` between `fork` and `exec`.
+    ///
+    /// The ruleset's file descriptors and the seccomp program are prepared
+    /// here, so the child only makes the restricting system calls.
+    pub fn restrict(cmd: &mut Command, policy: &SandboxPolicy) -> Result<()> {
+        let abi = ABI::V2;
+        let ruleset = Ruleset::default()
+            .handle_access(AccessFs::from_all(abi))?
+            .create()?
+            .add_rules(path_beneath_rules(
+                &policy.read_only,
+                AccessFs::from_read(abi),
+            ))?
+            .add_rules(path_beneath_rules(
+                &policy.writable,
+                AccessFs::from_all(abi),
+            ))?;
+        let network_filter = if policy.allow_network {
+            None
+        } else {
+            Some(network_filter()?)
+        };
+
+        let ruleset = Mutex::new(Some(ruleset));
+        // SAFETY: the closure only takes a lock no other thread of the child
+        // can hold and makes the prctl/landlock/seccomp system calls.
+        unsafe {
+            cmd.pre_exec(move || {
+                // Never run unconfined, even if the ruleset was used up
+                let ruleset = ruleset
+                    .lock()
+                    .map_err(|_| io::Error::other("sandbox ruleset unavailable"))?
+                    .take()
+                    .ok_or_else(|| io::Error::other("sandbox ruleset already used"))?;
+                let status = ruleset.restrict_self().map_err(io::Error::other)?;
+                if status.ruleset == RulesetStatus::NotEnforced {
+                    return Err(io::Error::other(
+                        "this kernel doesn't support Landlock, so the sandbox can't be enforced",
+                    ));
+                }
+                // Landlock has set no_new_privs, which seccomp requires
+                if let Some(filter) = &network_filter {
+                    seccompiler::apply_filter(filter).map_err(io::Error::other)?;
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    /// A seccomp program refusing IPv4 and IPv6 sockets with EPERM. Unix
+    /// sockets keep working.
+    fn network_filter() -> Result<BpfProgram> {
+        let domain_rule = |domain: i32| -> Result<SeccompRule> {
+            Ok(SeccompRule::new(vec![SeccompCondition::new(
+                0,
+                SeccompCmpArgLen::Dword,
+                SeccompCmpOp::Eq,
+                domain as u64,
+            )?])?)
+        };
+        let rules = BTreeMap::from([(
+            libc::SYS_socket,
+            vec![domain_rule(libc::AF_INET)?, domain_rule(libc::AF_INET6)?],
+        )]);
+        let filter = SeccompFilter::new(
+            rules,
+            SeccompAction::Allow,
+            SeccompAction::Errno(libc::EPERM as u32),
+            std::env::consts::ARCH.try_into()?,
+        )?;
+        Ok(filter.try_into()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_and_macos_profile() {
+        let workspace = tempfile::tempdir().unwrap();
+        let mut policy = SandboxPolicy::new(workspace.path());
+        let workspace = std::fs::canonicalize(workspace.path()).unwrap();
+        assert_eq!(policy.writable[0], workspace);
+        assert!(policy.read_only.contains(&PathBuf::from("/usr")));
+
+        // Missing paths and duplicates are skipped
+        policy.add_read_only(Path::new("/no/such/dir"));
+        policy.add_writable(&workspace);
+        assert!(!policy.read_only.iter().any(|p| p.ends_with("such/dir")));
+        assert_eq!(
+            policy.writable.iter().filter(|p| **p == workspace).count(),
+            1
+        );
+
+        let profile = policy.macos_profile();
+        assert!(profile.starts_with("(version 1)\n(deny default)\n"));
+        assert!(profile.contains(&format!(
+            "(allow file-read* file-write* (subpath \"{}\"))",
+            workspace.display()
+        )));
+        assert!(profile.contains("(allow network*)"));
+        policy.allow_network = false;
+        assert!(!policy.macos_profile().contains("network"));
+
+        assert_eq!(
+            sbpl_path(Path::new("/a \"b\"")),
+            "(subpath \"/a \\\"b\\\"\")"
+        );
+    }
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_a_command_spawned_twice_stays_confined() {
+        let workspace = tempfile::tempdir().unwrap();
+        let policy = SandboxPolicy::new(workspace.path());
+        let outside = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let mut cmd = sandboxed(Shell::default(), &format!("cat {}", outside), &policy).unwrap();
+        for run in 0..2 {
+            // Refusing to spawn (no Landlock, ruleset used up) confines it too
+            if let Ok(output) = cmd.output().await {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                assert!(
+                    !stdout.contains("[package]"),
+                    "run {} read {}",
+                    run,
+                    outside
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! The shell agent-run commands are written for.
+//!
+//! Commands run as `<shell> -c <command>`. bash is the default; zsh and sh
+//! share its quoting and syntax. fish and nushell quote strings and scope a
+//! `cd` their own way, so everything g3 wraps around a command (quoting a
+//! path, running it in another directory, the markers of the persistent
+//! shell) is written for the shell it runs in.
+
+use anyhow::{bail, Result};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Shell {
+    #[default]
+    Bash,
+    Zsh,
+    Sh,
+    Fish,
+    Nushell,
+}
+
+impl Shell {
+    /// The shell called `name`: "bash", "zsh", "sh", "fish" or "nu"
+    /// ("nushell"), or a path ending in one of them.
+    pub fn from_name(name: &str) -> Result<Self> {
+        let program = name.trim().rsplit('/').next().unwrap_or_default();
+        Ok(match program {
+            "bash" => Self::Bash,
+            "zsh" => Self::Zsh,
+            "sh" | "dash" => Self::Sh,
+            "fish" => Self::Fish,
+            "nu" | "nushell" => Self::Nushell,
+            _ => bail!(
+                "Unknown shell '{}': expected bash, zsh, sh, fish or nu",
+                name
+            ),
+        })
+    }
+
+    /// The executable, looked up on `PATH`.
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Sh => "sh",
+            Self::Fish => "fish",
+            Self::Nushell => "nu",
+        }
+    }
+
+    /// The command line running `code`: `<shell> -c <code>`.
+    pub fn argv(self, code: &str) -> [&str; 3] {
+        [self.program(), "-c", code]
+    }
+
+    /// Whether the shell has POSIX quoting and subshells.
+    pub fn is_posix(self) -> bool {
+        matches!(self, Self::Bash | Self::Zsh | Self::Sh)
+    }
+
+    /// `s` as a single word of this shell, taken literally.
+    pub fn quote(self, s: &str) -> String {
+        match self {
+            Self::Bash | Self::Zsh | Self::Sh => format!("'{}'", s.replace('\'', "'\\''")),
+            // Inside fish's single quotes only \' and \\ are escapes
+            Self::Fish => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+            // Nushell's single quotes have no escapes; a raw string takes
+            // anything its closing `'#...` doesn't appear in
+            Self::Nushell if !s.contains('\'') => format!("'{}'", s),
+            Self::Nushell => {
+                let mut hashes = "#".to_string();
+                while s.contains(&format!("'{}", hashes)) {
+                    hashes.push('#');
+                }
+                format!("r{}'{}'{}", hashes, s, hashes)
+            }
+        }
+    }
+
+    /// `code` run in `dir` without changing the directory or variables of
+    /// the shell running it.
+    pub fn in_dir(self, dir: &str, code: &str) -> String {
+        let dir = self.quote(dir);
+        match self {
+            Self::Bash | Self::Zsh | Self::Sh => format!("(cd -- {} || exit\n{}\n)", dir, code),
+            // fish has no subshells: run a child fish
+            Self::Fish => format!(
+                "fish --no-config -c {}",
+                self.quote(&format!("cd {}; or exit 1\n{}", dir, code))
+            ),
+            // A `do` block's environment, directory included, is its own
+            Self::Nushell => format!("do {{\ncd {}\n{}\n}}", dir, code),
+        }
+    }
+
+    /// Whether the shell can be kept running as a `PersistentShell`.
+    pub fn supports_persistence(self) -> bool {
+        self.is_posix() || self == Self::Fish
+    }
+
+    /// The command that replaces a started shell with one reading commands
+    /// from stdin, without the user's startup files.
+    pub(crate) fn interactive(self) -> Result<&'static str> {
+        Ok(match self {
+            Self::Bash => "exec bash --noprofile --norc",
+            Self::Zsh => "exec zsh -f",
+            Self::Sh => "exec sh",
+            Self::Fish => "exec fish --no-config",
+            Self::Nushell => bail!("A nushell can't be kept running between commands"),
+        })
+    }
+
+    /// The script a persistent shell runs for `code`: the command, then
+    /// `marker` and its exit status on stdout and `marker` on stderr.
+    pub(crate) fn persistent_script(self, code: &str, marker: &str) -> String {
+        let status = if self == Self::Fish {
+            "$status"
+        } else {
+            "\"$?\""
+        };
+        format!(
+            "eval {} < /dev/null\nprintf '%s %d\\n' '{marker}' {status}\nprintf '%s\\n' '{marker}' >&2\n",
+            self.quote(code),
+        )
+    }
+}
+
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.program())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_and_quoting() {
+        assert_eq!(
+            Shell::from_name("/opt/homebrew/bin/fish").unwrap(),
+            Shell::Fish
+        );
+        assert_eq!(Shell::from_name("nushell").unwrap(), Shell::Nushell);
+        assert!(Shell::from_name("powershell").is_err());
+
+        let tricky = r"it's a \ path";
+        assert_eq!(Shell::Bash.quote(tricky), r"'it'\''s a \ path'");
+        assert_eq!(Shell::Fish.quote(tricky), r"'it\'s a \\ path'");
+        assert_eq!(Shell::Nushell.quote("plain"), "'plain'");
+        assert_eq!(Shell::Nushell.quote(tricky), r"r#'it's a \ path'#");
+        assert_eq!(Shell::Nushell.quote("'#"), "r##''#'##");
+
+        // Quoted words come out of the shell unchanged
+        let output = std::process::Command::new("sh")
+            .args(["-c", &format!("printf %s {}", Shell::Sh.quote(tricky))])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), tricky);
+
+        assert_eq!(Shell::Nushell.in_dir("src", "ls"), "do {\ncd 'src'\nls\n}");
+        assert!(Shell::Fish
+            .in_dir("a b", "make")
+            .starts_with("fish --no-config -c 'cd \\'a b\\'; or exit 1"));
+    }
+}
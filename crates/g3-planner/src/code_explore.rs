@@ -7,82 +7,159 @@
 use std::path::Path;
 use std::process::Command;
 
+use crate::dep_graph::DependencyGraph;
+use crate::ecosystems::detect_ecosystems;
+
+/// What `explore_codebase` looks at and how much of it it reports.
+///
+/// Files are listed with ripgrep, so `.gitignore`, `.ignore` and
+/// `.rgignore` are honored (also outside a git repository), as are the
+/// `ignore` globs. Symbol listings are capped separately, at 100 lines each.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExploreOptions {
+    /// Extra globs to leave out, e.g. `third_party/**` or `*.generated.ts`
+    pub ignore: Vec<String>,
+    /// How many directories deep to descend (unlimited by default)
+    pub max_depth: Option<usize>,
+    /// Files listed per language
+    pub max_files: usize,
+    /// Lines shown of a manifest or README
+    pub preview_lines: usize,
+}
+
+impl Default for ExploreOptions {
+    fn default() -> Self {
+        Self {
+            ignore: Vec::new(),
+            max_depth: None,
+            max_files: 100,
+            preview_lines: 50,
+        }
+    }
+}
+
+impl From<&g3_config::DiscoveryConfig> for ExploreOptions {
+    fn from(config: &g3_config::DiscoveryConfig) -> Self {
+        Self {
+            ignore: config.ignore.clone(),
+            max_depth: config.max_depth,
+            max_files: config.max_files,
+            preview_lines: config.preview_lines,
+        }
+    }
+}
+
+impl ExploreOptions {
+    /// Fill the `{rg}`, `{max_files}` and `{preview_lines}` placeholders of
+    /// an exploration command.
+    fn expand(&self, cmd: &str) -> String {
+        let mut rg = String::from("rg --no-require-git");
+        if let Some(depth) = self.max_depth {
+            rg.push_str(&format!(" --max-depth {}", depth));
+        }
+        for pattern in &self.ignore {
+            rg.push_str(&format!(" -g '!{}'", pattern.replace('\'', "'\\''")));
+        }
+        cmd.replace("{rg}", &rg)
+            .replace("{max_files}", &self.max_files.to_string())
+            .replace("{preview_lines}", &self.preview_lines.to_string())
+    }
+}
+
 /// Main entry point for exploring a codebase at the given path.
 /// Detects which languages are present and generates a comprehensive report.
+/// Ecosystems with a manifest at the root (Cargo, npm, Go, Python) get their
+/// own sections first (see `ecosystems`), and a condensed graph of which
+/// modules import which comes last (see `dep_graph`).
 pub fn explore_codebase(path: &str) -> String {
+    explore_codebase_with(path, &ExploreOptions::default())
+}
+
+/// `explore_codebase` with explicit limits and ignore patterns.
+pub fn explore_codebase_with(path: &str, options: &ExploreOptions) -> String {
     let path = expand_tilde(path);
     let mut report = String::new();
     let mut languages_found = Vec::new();
 
+    let ecosystems = detect_ecosystems(Path::new(&path));
+    for ecosystem in &ecosystems {
+        report.push_str(&ecosystem.format());
+    }
+
     // Check for each language and add to report if found
-    if has_rust_files(&path) {
+    if has_rust_files(&path, options) {
         languages_found.push("Rust".to_string());
-        report.push_str(&explore_rust(&path));
+        report.push_str(&explore_rust(&path, options));
     }
-    if has_java_files(&path) {
+    if has_java_files(&path, options) {
         languages_found.push("Java".to_string());
-        report.push_str(&explore_java(&path));
+        report.push_str(&explore_java(&path, options));
     }
-    if has_kotlin_files(&path) {
+    if has_kotlin_files(&path, options) {
         languages_found.push("Kotlin".to_string());
-        report.push_str(&explore_kotlin(&path));
+        report.push_str(&explore_kotlin(&path, options));
     }
-    if has_swift_files(&path) {
+    if has_swift_files(&path, options) {
         languages_found.push("Swift".to_string());
-        report.push_str(&explore_swift(&path));
+        report.push_str(&explore_swift(&path, options));
     }
-    if has_go_files(&path) {
+    if has_go_files(&path, options) {
         languages_found.push("Go".to_string());
-        report.push_str(&explore_go(&path));
+        report.push_str(&explore_go(&path, options));
     }
-    if has_python_files(&path) {
+    if has_python_files(&path, options) {
         languages_found.push("Python".to_string());
-        report.push_str(&explore_python(&path));
+        report.push_str(&explore_python(&path, options));
     }
-    if has_typescript_files(&path) {
+    if has_typescript_files(&path, options) {
         languages_found.push("TypeScript".to_string());
-        report.push_str(&explore_typescript(&path));
+        report.push_str(&explore_typescript(&path, options));
     }
-    if has_javascript_files(&path) {
+    if has_javascript_files(&path, options) {
         languages_found.push("JavaScript".to_string());
-        report.push_str(&explore_javascript(&path));
+        report.push_str(&explore_javascript(&path, options));
     }
-    if has_cpp_files(&path) {
+    if has_cpp_files(&path, options) {
         languages_found.push("C/C++".to_string());
-        report.push_str(&explore_cpp(&path));
+        report.push_str(&explore_cpp(&path, options));
     }
-    if has_markdown_files(&path) {
+    if has_markdown_files(&path, options) {
         languages_found.push("Markdown".to_string());
-        report.push_str(&explore_markdown(&path));
+        report.push_str(&explore_markdown(&path, options));
     }
-    if has_yaml_files(&path) {
+    if has_yaml_files(&path, options) {
         languages_found.push("YAML".to_string());
-        report.push_str(&explore_yaml(&path));
+        report.push_str(&explore_yaml(&path, options));
     }
-    if has_sql_files(&path) {
+    if has_sql_files(&path, options) {
         languages_found.push("SQL".to_string());
-        report.push_str(&explore_sql(&path));
+        report.push_str(&explore_sql(&path, options));
     }
-    if has_ruby_files(&path) {
+    if has_ruby_files(&path, options) {
         languages_found.push("Ruby".to_string());
-        report.push_str(&explore_ruby(&path));
+        report.push_str(&explore_ruby(&path, options));
     }
+    report.push_str(&DependencyGraph::build(Path::new(&path)).format_condensed());
 
     if languages_found.is_empty() {
         report.push_str("No recognized programming languages found in the codebase.\n");
     } else {
-        let header = format!(
-            "=== CODEBASE ANALYSIS ===\nLanguages detected: {}\n\n",
+        let mut header = format!(
+            "=== CODEBASE ANALYSIS ===\nLanguages detected: {}\n",
             languages_found.join(", ")
         );
-        report = header + &report;
+        if !ecosystems.is_empty() {
+            let names: Vec<&str> = ecosystems.iter().map(|e| e.name.as_str()).collect();
+            header.push_str(&format!("Ecosystems detected: {}\n", names.join(", ")));
+        }
+        report = header + "\n" + &report;
     }
 
     report
 }
 
 /// Expand tilde to home directory
-fn expand_tilde(path: &str) -> String {
+pub(crate) fn expand_tilde(path: &str) -> String {
     if path.starts_with("~/") {
         if let Some(home) = std::env::var_os("HOME") {
             return path.replacen("~", &home.to_string_lossy(), 1);
@@ -115,115 +192,106 @@ fn run_command(cmd: &str, working_dir: &str) -> String {
     }
 }
 
-/// Check if files with given extension exist
-fn has_files_with_extension(path: &str, extension: &str) -> bool {
+/// Check if files with given extension exist, outside ignored paths
+fn has_files_with_extension(path: &str, options: &ExploreOptions, extension: &str) -> bool {
     let cmd = format!(
-        "find . -name '.git' -prune -o -type f -name '*.{}' -print | head -1",
+        "{{rg}} --files -g '*.{}' . 2>/dev/null | head -1",
         extension
     );
-    !run_command(&cmd, path).trim().is_empty()
+    !run_command(&options.expand(&cmd), path).trim().is_empty()
 }
 
 // Language detection functions
-fn has_rust_files(path: &str) -> bool {
-    has_files_with_extension(path, "rs") || Path::new(path).join("Cargo.toml").exists()
+fn has_rust_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "rs") || Path::new(path).join("Cargo.toml").exists()
 }
 
-fn has_java_files(path: &str) -> bool {
-    has_files_with_extension(path, "java")
+fn has_java_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "java")
 }
 
-fn has_kotlin_files(path: &str) -> bool {
-    has_files_with_extension(path, "kt") || has_files_with_extension(path, "kts")
+fn has_kotlin_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "kt") || has_files_with_extension(path, options, "kts")
 }
 
-fn has_swift_files(path: &str) -> bool {
-    has_files_with_extension(path, "swift")
+fn has_swift_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "swift")
 }
 
-fn has_go_files(path: &str) -> bool {
-    has_files_with_extension(path, "go")
+fn has_go_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "go")
 }
 
-fn has_python_files(path: &str) -> bool {
-    has_files_with_extension(path, "py")
+fn has_python_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "py")
 }
 
-fn has_typescript_files(path: &str) -> bool {
-    has_files_with_extension(path, "ts") || has_files_with_extension(path, "tsx")
+fn has_typescript_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "ts") || has_files_with_extension(path, options, "tsx")
 }
 
-fn has_javascript_files(path: &str) -> bool {
-    has_files_with_extension(path, "js") || has_files_with_extension(path, "jsx")
+fn has_javascript_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "js") || has_files_with_extension(path, options, "jsx")
 }
 
-fn has_cpp_files(path: &str) -> bool {
-    has_files_with_extension(path, "cpp")
-        || has_files_with_extension(path, "cc")
-        || has_files_with_extension(path, "c")
-        || has_files_with_extension(path, "h")
-        || has_files_with_extension(path, "hpp")
+fn has_cpp_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "cpp")
+        || has_files_with_extension(path, options, "cc")
+        || has_files_with_extension(path, options, "c")
+        || has_files_with_extension(path, options, "h")
+        || has_files_with_extension(path, options, "hpp")
 }
 
-fn has_markdown_files(path: &str) -> bool {
-    has_files_with_extension(path, "md")
+fn has_markdown_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "md")
 }
 
-fn has_yaml_files(path: &str) -> bool {
-    has_files_with_extension(path, "yaml") || has_files_with_extension(path, "yml")
+fn has_yaml_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "yaml")
+        || has_files_with_extension(path, options, "yml")
 }
 
-fn has_sql_files(path: &str) -> bool {
-    has_files_with_extension(path, "sql")
+fn has_sql_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "sql")
 }
 
-fn has_ruby_files(path: &str) -> bool {
-    has_files_with_extension(path, "rb")
+fn has_ruby_files(path: &str, options: &ExploreOptions) -> bool {
+    has_files_with_extension(path, options, "rb")
 }
 
 /// Explore Rust codebase
-pub fn explore_rust(path: &str) -> String {
+pub fn explore_rust(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== RUST ===\n\n");
 
     // File structure
     report.push_str("--- File Structure ---\n");
-    let files = run_command(
-        "rg --files -g '*.rs' . 2>/dev/null | grep -v '/target/' | sort | head -100",
-        path,
-    );
+    let files =
+        run("{rg} --files -g '*.rs' . 2>/dev/null | grep -v '/target/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
-    // Dependencies (Cargo.toml)
-    report.push_str("--- Dependencies (Cargo.toml) ---\n");
-    let cargo = run_command("cat Cargo.toml 2>/dev/null | head -50", path);
-    report.push_str(&cargo);
-    report.push('\n');
-
     // Data structures
     report.push_str("--- Data Structures (Structs, Enums, Types) ---\n");
-    let structs = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.rs' '^(pub )?(struct|enum|type|union) ' . 2>/dev/null | grep -v '/target/' | head -100"#,
-        path,
+    let structs = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.rs' '^(pub )?(struct|enum|type|union) ' . 2>/dev/null | grep -v '/target/' | head -100"#,
     );
     report.push_str(&structs);
     report.push('\n');
 
     // Traits and implementations
     report.push_str("--- Traits & Implementations ---\n");
-    let traits = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.rs' '^(pub )?trait |^impl ' . 2>/dev/null | grep -v '/target/' | head -100"#,
-        path,
+    let traits = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.rs' '^(pub )?trait |^impl ' . 2>/dev/null | grep -v '/target/' | head -100"#,
     );
     report.push_str(&traits);
     report.push('\n');
 
     // Public functions
     report.push_str("--- Public Functions ---\n");
-    let funcs = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.rs' '^pub (async )?fn ' . 2>/dev/null | grep -v '/target/' | head -100"#,
-        path,
+    let funcs = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.rs' '^pub (async )?fn ' . 2>/dev/null | grep -v '/target/' | head -100"#,
     );
     report.push_str(&funcs);
     report.push('\n');
@@ -232,42 +300,35 @@ pub fn explore_rust(path: &str) -> String {
 }
 
 /// Explore Java codebase
-pub fn explore_java(path: &str) -> String {
+pub fn explore_java(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== JAVA ===\n\n");
 
     // File structure
     report.push_str("--- File Structure ---\n");
-    let files = run_command(
-        "rg --files -g '*.java' . 2>/dev/null | grep -v '/build/' | grep -v '/target/' | sort | head -100",
-        path,
-    );
+    let files = run("{rg} --files -g '*.java' . 2>/dev/null | grep -v '/build/' | grep -v '/target/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
     // Build files
     report.push_str("--- Build Configuration ---\n");
-    let build = run_command(
-        "cat pom.xml 2>/dev/null | head -50 || cat build.gradle 2>/dev/null | head -50",
-        path,
-    );
+    let build = run("cat pom.xml 2>/dev/null | head -{preview_lines} || cat build.gradle 2>/dev/null | head -{preview_lines}");
     report.push_str(&build);
     report.push('\n');
 
     // Classes and interfaces
     report.push_str("--- Classes & Interfaces ---\n");
-    let classes = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.java' '^(public |private |protected )?(abstract )?(class|interface|enum|record) ' . 2>/dev/null | grep -v '/build/' | head -100"#,
-        path,
+    let classes = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.java' '^(public |private |protected )?(abstract )?(class|interface|enum|record) ' . 2>/dev/null | grep -v '/build/' | head -100"#,
     );
     report.push_str(&classes);
     report.push('\n');
 
     // Public methods
     report.push_str("--- Public Methods ---\n");
-    let methods = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.java' '^\s+public .+\(' . 2>/dev/null | grep -v '/build/' | head -100"#,
-        path,
+    let methods = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.java' '^\s+public .+\(' . 2>/dev/null | grep -v '/build/' | head -100"#,
     );
     report.push_str(&methods);
     report.push('\n');
@@ -276,42 +337,35 @@ pub fn explore_java(path: &str) -> String {
 }
 
 /// Explore Kotlin codebase
-pub fn explore_kotlin(path: &str) -> String {
+pub fn explore_kotlin(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== KOTLIN ===\n\n");
 
     // File structure
     report.push_str("--- File Structure ---\n");
-    let files = run_command(
-        "rg --files -g '*.kt' -g '*.kts' . 2>/dev/null | grep -v '/build/' | sort | head -100",
-        path,
-    );
+    let files = run("{rg} --files -g '*.kt' -g '*.kts' . 2>/dev/null | grep -v '/build/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
     // Build files
     report.push_str("--- Build Configuration ---\n");
-    let build = run_command(
-        "cat build.gradle.kts 2>/dev/null | head -50 || cat build.gradle 2>/dev/null | head -50",
-        path,
-    );
+    let build = run("cat build.gradle.kts 2>/dev/null | head -{preview_lines} || cat build.gradle 2>/dev/null | head -{preview_lines}");
     report.push_str(&build);
     report.push('\n');
 
     // Classes, objects, interfaces
     report.push_str("--- Classes, Objects & Interfaces ---\n");
-    let classes = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.kt' '^(data |sealed |open |abstract )?(class|interface|object|enum class) ' . 2>/dev/null | grep -v '/build/' | head -100"#,
-        path,
+    let classes = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.kt' '^(data |sealed |open |abstract )?(class|interface|object|enum class) ' . 2>/dev/null | grep -v '/build/' | head -100"#,
     );
     report.push_str(&classes);
     report.push('\n');
 
     // Functions
     report.push_str("--- Functions ---\n");
-    let funcs = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.kt' '^(suspend |private |internal |public )?fun ' . 2>/dev/null | grep -v '/build/' | head -100"#,
-        path,
+    let funcs = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.kt' '^(suspend |private |internal |public )?fun ' . 2>/dev/null | grep -v '/build/' | head -100"#,
     );
     report.push_str(&funcs);
     report.push('\n');
@@ -320,39 +374,37 @@ pub fn explore_kotlin(path: &str) -> String {
 }
 
 /// Explore Swift codebase
-pub fn explore_swift(path: &str) -> String {
+pub fn explore_swift(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== SWIFT ===\n\n");
 
     // File structure
     report.push_str("--- File Structure ---\n");
-    let files = run_command(
-        "rg --files -g '*.swift' . 2>/dev/null | grep -v '/.build/' | sort | head -100",
-        path,
+    let files = run(
+        "{rg} --files -g '*.swift' . 2>/dev/null | grep -v '/.build/' | sort | head -{max_files}",
     );
     report.push_str(&files);
     report.push('\n');
 
     // Package.swift
     report.push_str("--- Package Configuration ---\n");
-    let pkg = run_command("cat Package.swift 2>/dev/null | head -50", path);
+    let pkg = run("cat Package.swift 2>/dev/null | head -{preview_lines}");
     report.push_str(&pkg);
     report.push('\n');
 
     // Classes, structs, protocols
     report.push_str("--- Types (Classes, Structs, Protocols, Enums) ---\n");
-    let types = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.swift' '^(public |private |internal |open |final )?(class|struct|protocol|enum|actor) ' . 2>/dev/null | grep -v '/.build/' | head -100"#,
-        path,
+    let types = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.swift' '^(public |private |internal |open |final )?(class|struct|protocol|enum|actor) ' . 2>/dev/null | grep -v '/.build/' | head -100"#,
     );
     report.push_str(&types);
     report.push('\n');
 
     // Functions
     report.push_str("--- Functions ---\n");
-    let funcs = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.swift' '^\s*(public |private |internal |open )?func ' . 2>/dev/null | grep -v '/.build/' | head -100"#,
-        path,
+    let funcs = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.swift' '^\s*(public |private |internal |open )?func ' . 2>/dev/null | grep -v '/.build/' | head -100"#,
     );
     report.push_str(&funcs);
     report.push('\n');
@@ -361,39 +413,30 @@ pub fn explore_swift(path: &str) -> String {
 }
 
 /// Explore Go codebase
-pub fn explore_go(path: &str) -> String {
+pub fn explore_go(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== GO ===\n\n");
 
     // File structure
     report.push_str("--- File Structure ---\n");
-    let files = run_command(
-        "rg --files -g '*.go' . 2>/dev/null | grep -v '/vendor/' | sort | head -100",
-        path,
-    );
+    let files =
+        run("{rg} --files -g '*.go' . 2>/dev/null | grep -v '/vendor/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
-    // go.mod
-    report.push_str("--- Module Configuration ---\n");
-    let gomod = run_command("cat go.mod 2>/dev/null | head -50", path);
-    report.push_str(&gomod);
-    report.push('\n');
-
     // Types (structs, interfaces)
     report.push_str("--- Types (Structs & Interfaces) ---\n");
-    let types = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.go' '^type .+ (struct|interface)' . 2>/dev/null | grep -v '/vendor/' | head -100"#,
-        path,
+    let types = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.go' '^type .+ (struct|interface)' . 2>/dev/null | grep -v '/vendor/' | head -100"#,
     );
     report.push_str(&types);
     report.push('\n');
 
     // Functions
     report.push_str("--- Functions ---\n");
-    let funcs = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.go' '^func ' . 2>/dev/null | grep -v '/vendor/' | head -100"#,
-        path,
+    let funcs = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.go' '^func ' . 2>/dev/null | grep -v '/vendor/' | head -100"#,
     );
     report.push_str(&funcs);
     report.push('\n');
@@ -402,42 +445,35 @@ pub fn explore_go(path: &str) -> String {
 }
 
 /// Explore Python codebase
-pub fn explore_python(path: &str) -> String {
+pub fn explore_python(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== PYTHON ===\n\n");
 
     // File structure
     report.push_str("--- File Structure ---\n");
-    let files = run_command(
-        "rg --files -g '*.py' . 2>/dev/null | grep -v '/__pycache__/' | grep -v '/venv/' | grep -v '/.venv/' | sort | head -100",
-        path,
-    );
+    let files = run("{rg} --files -g '*.py' . 2>/dev/null | grep -v '/__pycache__/' | grep -v '/venv/' | grep -v '/.venv/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
-    // Requirements/setup
+    // Requirements (pyproject.toml is covered by the Python ecosystem section)
     report.push_str("--- Dependencies ---\n");
-    let deps = run_command(
-        "cat requirements.txt 2>/dev/null | head -30 || cat pyproject.toml 2>/dev/null | head -50 || cat setup.py 2>/dev/null | head -30",
-        path,
-    );
+    let deps = run("cat requirements.txt 2>/dev/null | head -{preview_lines}");
     report.push_str(&deps);
     report.push('\n');
 
     // Classes
     report.push_str("--- Classes ---\n");
-    let classes = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.py' '^class ' . 2>/dev/null | grep -v '/__pycache__/' | grep -v '/venv/' | head -100"#,
-        path,
+    let classes = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.py' '^class ' . 2>/dev/null | grep -v '/__pycache__/' | grep -v '/venv/' | head -100"#,
     );
     report.push_str(&classes);
     report.push('\n');
 
     // Functions
     report.push_str("--- Functions ---\n");
-    let funcs = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.py' '^def |^async def ' . 2>/dev/null | grep -v '/__pycache__/' | grep -v '/venv/' | head -100"#,
-        path,
+    let funcs = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.py' '^def |^async def ' . 2>/dev/null | grep -v '/__pycache__/' | grep -v '/venv/' | head -100"#,
     );
     report.push_str(&funcs);
     report.push('\n');
@@ -446,39 +482,29 @@ pub fn explore_python(path: &str) -> String {
 }
 
 /// Explore TypeScript codebase
-pub fn explore_typescript(path: &str) -> String {
+pub fn explore_typescript(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== TYPESCRIPT ===\n\n");
 
     // File structure
     report.push_str("--- File Structure ---\n");
-    let files = run_command(
-        "rg --files -g '*.ts' -g '*.tsx' . 2>/dev/null | grep -v '/node_modules/' | grep -v '/dist/' | sort | head -100",
-        path,
-    );
+    let files = run("{rg} --files -g '*.ts' -g '*.tsx' . 2>/dev/null | grep -v '/node_modules/' | grep -v '/dist/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
-    // package.json
-    report.push_str("--- Package Configuration ---\n");
-    let pkg = run_command("cat package.json 2>/dev/null | head -50", path);
-    report.push_str(&pkg);
-    report.push('\n');
-
     // Types, interfaces, classes
     report.push_str("--- Types, Interfaces & Classes ---\n");
-    let types = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.ts' -g '*.tsx' '^export (type|interface|class|enum|abstract class) ' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
-        path,
+    let types = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.ts' -g '*.tsx' '^export (type|interface|class|enum|abstract class) ' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
     );
     report.push_str(&types);
     report.push('\n');
 
     // Functions
     report.push_str("--- Exported Functions ---\n");
-    let funcs = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.ts' -g '*.tsx' '^export (async )?function |^export const .+ = (async )?\(' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
-        path,
+    let funcs = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.ts' -g '*.tsx' '^export (async )?function |^export const .+ = (async )?\(' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
     );
     report.push_str(&funcs);
     report.push('\n');
@@ -487,39 +513,29 @@ pub fn explore_typescript(path: &str) -> String {
 }
 
 /// Explore JavaScript codebase
-pub fn explore_javascript(path: &str) -> String {
+pub fn explore_javascript(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== JAVASCRIPT ===\n\n");
 
     // File structure
     report.push_str("--- File Structure ---\n");
-    let files = run_command(
-        "rg --files -g '*.js' -g '*.jsx' . 2>/dev/null | grep -v '/node_modules/' | grep -v '/dist/' | sort | head -100",
-        path,
-    );
+    let files = run("{rg} --files -g '*.js' -g '*.jsx' . 2>/dev/null | grep -v '/node_modules/' | grep -v '/dist/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
-    // package.json
-    report.push_str("--- Package Configuration ---\n");
-    let pkg = run_command("cat package.json 2>/dev/null | head -50", path);
-    report.push_str(&pkg);
-    report.push('\n');
-
     // Classes
     report.push_str("--- Classes ---\n");
-    let classes = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.js' -g '*.jsx' '^(export )?(default )?(class ) ' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
-        path,
+    let classes = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.js' -g '*.jsx' '^(export )?(default )?(class ) ' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
     );
     report.push_str(&classes);
     report.push('\n');
 
     // Functions
     report.push_str("--- Exported Functions ---\n");
-    let funcs = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.js' -g '*.jsx' '^(export )?(async )?function |^module\.exports' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
-        path,
+    let funcs = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.js' -g '*.jsx' '^(export )?(async )?function |^module\.exports' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
     );
     report.push_str(&funcs);
     report.push('\n');
@@ -528,42 +544,35 @@ pub fn explore_javascript(path: &str) -> String {
 }
 
 /// Explore C/C++ codebase
-pub fn explore_cpp(path: &str) -> String {
+pub fn explore_cpp(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== C/C++ ===\n\n");
 
     // File structure
     report.push_str("--- File Structure ---\n");
-    let files = run_command(
-        "rg --files -g '*.c' -g '*.cpp' -g '*.cc' -g '*.h' -g '*.hpp' . 2>/dev/null | grep -v '/build/' | sort | head -100",
-        path,
-    );
+    let files = run("{rg} --files -g '*.c' -g '*.cpp' -g '*.cc' -g '*.h' -g '*.hpp' . 2>/dev/null | grep -v '/build/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
     // Build files
     report.push_str("--- Build Configuration ---\n");
-    let build = run_command(
-        "cat CMakeLists.txt 2>/dev/null | head -50 || cat Makefile 2>/dev/null | head -50",
-        path,
-    );
+    let build = run("cat CMakeLists.txt 2>/dev/null | head -{preview_lines} || cat Makefile 2>/dev/null | head -{preview_lines}");
     report.push_str(&build);
     report.push('\n');
 
     // Classes and structs
     report.push_str("--- Classes & Structs ---\n");
-    let classes = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.cpp' -g '*.cc' -g '*.h' -g '*.hpp' '^(class|struct|enum|union|typedef) ' . 2>/dev/null | grep -v '/build/' | head -100"#,
-        path,
+    let classes = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.cpp' -g '*.cc' -g '*.h' -g '*.hpp' '^(class|struct|enum|union|typedef) ' . 2>/dev/null | grep -v '/build/' | head -100"#,
     );
     report.push_str(&classes);
     report.push('\n');
 
     // Functions (simplified pattern)
     report.push_str("--- Function Declarations ---\n");
-    let funcs = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.h' -g '*.hpp' '^[a-zA-Z_][a-zA-Z0-9_<>: ]*\s+[a-zA-Z_][a-zA-Z0-9_]*\s*\(' . 2>/dev/null | grep -v '/build/' | head -100"#,
-        path,
+    let funcs = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.h' -g '*.hpp' '^[a-zA-Z_][a-zA-Z0-9_<>: ]*\s+[a-zA-Z_][a-zA-Z0-9_]*\s*\(' . 2>/dev/null | grep -v '/build/' | head -100"#,
     );
     report.push_str(&funcs);
     report.push('\n');
@@ -572,33 +581,27 @@ pub fn explore_cpp(path: &str) -> String {
 }
 
 /// Explore Markdown documentation
-pub fn explore_markdown(path: &str) -> String {
+pub fn explore_markdown(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== MARKDOWN DOCUMENTATION ===\n\n");
 
     // File structure
     report.push_str("--- Documentation Files ---\n");
-    let files = run_command(
-        "rg --files -g '*.md' . 2>/dev/null | grep -v '/node_modules/' | grep -v '/vendor/' | sort | head -50",
-        path,
-    );
+    let files = run("{rg} --files -g '*.md' . 2>/dev/null | grep -v '/node_modules/' | grep -v '/vendor/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
     // README content
     report.push_str("--- README Overview ---\n");
-    let readme = run_command(
-        "cat README.md 2>/dev/null | head -100 || cat readme.md 2>/dev/null | head -100",
-        path,
-    );
+    let readme = run("cat README.md 2>/dev/null | head -{preview_lines} || cat readme.md 2>/dev/null | head -{preview_lines}");
     report.push_str(&readme);
     report.push('\n');
 
     // Headers from all markdown files
     report.push_str("--- Document Headers ---\n");
-    let headers = run_command(
-        r#"rg --no-heading --line-number --with-filename -g '*.md' '^#{1,3} ' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
-        path,
+    let headers = run(
+        r#"{rg} --no-heading --line-number --with-filename -g '*.md' '^#{1,3} ' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
     );
     report.push_str(&headers);
     report.push('\n');
@@ -607,24 +610,21 @@ pub fn explore_markdown(path: &str) -> String {
 }
 
 /// Explore YAML configuration files
-pub fn explore_yaml(path: &str) -> String {
+pub fn explore_yaml(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== YAML CONFIGURATION ===\n\n");
 
     // File structure
     report.push_str("--- YAML Files ---\n");
-    let files = run_command(
-        "rg --files -g '*.yaml' -g '*.yml' . 2>/dev/null | grep -v '/node_modules/' | grep -v '/vendor/' | sort | head -50",
-        path,
-    );
+    let files = run("{rg} --files -g '*.yaml' -g '*.yml' . 2>/dev/null | grep -v '/node_modules/' | grep -v '/vendor/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
     // Top-level keys from YAML files
     report.push_str("--- Top-Level Keys ---\n");
-    let keys = run_command(
-        r#"rg --no-heading --line-number --with-filename -g '*.yaml' -g '*.yml' '^[a-zA-Z_][a-zA-Z0-9_-]*:' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
-        path,
+    let keys = run(
+        r#"{rg} --no-heading --line-number --with-filename -g '*.yaml' -g '*.yml' '^[a-zA-Z_][a-zA-Z0-9_-]*:' . 2>/dev/null | grep -v '/node_modules/' | head -100"#,
     );
     report.push_str(&keys);
     report.push('\n');
@@ -633,33 +633,29 @@ pub fn explore_yaml(path: &str) -> String {
 }
 
 /// Explore SQL files
-pub fn explore_sql(path: &str) -> String {
+pub fn explore_sql(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== SQL ===\n\n");
 
     // File structure
     report.push_str("--- SQL Files ---\n");
-    let files = run_command(
-        "rg --files -g '*.sql' . 2>/dev/null | sort | head -50",
-        path,
-    );
+    let files = run("{rg} --files -g '*.sql' . 2>/dev/null | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
     // Tables
     report.push_str("--- Table Definitions ---\n");
-    let tables = run_command(
-        r#"rg --no-heading --line-number --with-filename -i -g '*.sql' 'CREATE TABLE' . 2>/dev/null | head -100"#,
-        path,
+    let tables = run(
+        r#"{rg} --no-heading --line-number --with-filename -i -g '*.sql' 'CREATE TABLE' . 2>/dev/null | head -100"#,
     );
     report.push_str(&tables);
     report.push('\n');
 
     // Views and procedures
     report.push_str("--- Views & Procedures ---\n");
-    let views = run_command(
-        r#"rg --no-heading --line-number --with-filename -i -g '*.sql' 'CREATE (VIEW|PROCEDURE|FUNCTION)' . 2>/dev/null | head -100"#,
-        path,
+    let views = run(
+        r#"{rg} --no-heading --line-number --with-filename -i -g '*.sql' 'CREATE (VIEW|PROCEDURE|FUNCTION)' . 2>/dev/null | head -100"#,
     );
     report.push_str(&views);
     report.push('\n');
@@ -668,39 +664,36 @@ pub fn explore_sql(path: &str) -> String {
 }
 
 /// Explore Ruby codebase
-pub fn explore_ruby(path: &str) -> String {
+pub fn explore_ruby(path: &str, options: &ExploreOptions) -> String {
+    let run = |cmd: &str| run_command(&options.expand(cmd), path);
     let mut report = String::new();
     report.push_str("\n=== RUBY ===\n\n");
 
     // File structure
     report.push_str("--- File Structure ---\n");
-    let files = run_command(
-        "rg --files -g '*.rb' . 2>/dev/null | grep -v '/vendor/' | sort | head -100",
-        path,
-    );
+    let files =
+        run("{rg} --files -g '*.rb' . 2>/dev/null | grep -v '/vendor/' | sort | head -{max_files}");
     report.push_str(&files);
     report.push('\n');
 
     // Gemfile
     report.push_str("--- Dependencies (Gemfile) ---\n");
-    let gemfile = run_command("cat Gemfile 2>/dev/null | head -50", path);
+    let gemfile = run("cat Gemfile 2>/dev/null | head -{preview_lines}");
     report.push_str(&gemfile);
     report.push('\n');
 
     // Classes and modules
     report.push_str("--- Classes & Modules ---\n");
-    let classes = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.rb' '^(class|module) ' . 2>/dev/null | grep -v '/vendor/' | head -100"#,
-        path,
+    let classes = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.rb' '^(class|module) ' . 2>/dev/null | grep -v '/vendor/' | head -100"#,
     );
     report.push_str(&classes);
     report.push('\n');
 
     // Methods
     report.push_str("--- Methods ---\n");
-    let methods = run_command(
-        r#"rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.rb' '^\s*def ' . 2>/dev/null | grep -v '/vendor/' | head -100"#,
-        path,
+    let methods = run(
+        r#"{rg} --no-heading --line-number --with-filename --max-filesize 500K -g '*.rb' '^\s*def ' . 2>/dev/null | grep -v '/vendor/' | head -100"#,
     );
     report.push_str(&methods);
     report.push('\n');
@@ -724,4 +717,47 @@ mod tests {
         let result = explore_codebase(".");
         assert!(!result.is_empty());
     }
+
+    #[test]
+    fn test_explore_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/deep/er")).unwrap();
+        std::fs::create_dir_all(root.join("vendor")).unwrap();
+        std::fs::write(root.join("src/main.py"), "def main():\n    pass\n").unwrap();
+        std::fs::write(root.join("src/deep/er/util.py"), "def util():\n    pass\n").unwrap();
+        std::fs::write(root.join("vendor/dep.py"), "def dep():\n    pass\n").unwrap();
+        std::fs::write(root.join("build.py"), "def build():\n    pass\n").unwrap();
+        std::fs::write(root.join(".gitignore"), "build.py\n").unwrap();
+        std::fs::write(root.join("requirements.txt"), "a\nb\nc\n").unwrap();
+        let path = root.to_str().unwrap();
+
+        let options = ExploreOptions {
+            ignore: vec!["vendor/**".to_string()],
+            max_depth: Some(2),
+            max_files: 10,
+            preview_lines: 2,
+        };
+        assert_eq!(
+            options.expand("{rg} --files . | head -{max_files}"),
+            "rg --no-require-git --max-depth 2 -g '!vendor/**' --files . | head -10"
+        );
+
+        let report = explore_python(path, &options);
+        assert!(report.contains("src/main.py"));
+        // Ignored by the option, by .gitignore and by depth
+        assert!(!report.contains("dep.py"));
+        assert!(!report.contains("build.py"));
+        assert!(!report.contains("util.py"));
+        // Only two lines of requirements.txt
+        assert!(report.contains("a\nb\n"));
+        assert!(!report.contains("a\nb\nc"));
+
+        let no_python = ExploreOptions {
+            ignore: vec!["*.py".to_string()],
+            ..ExploreOptions::default()
+        };
+        assert!(has_python_files(path, &ExploreOptions::default()));
+        assert!(!has_python_files(path, &no_python));
+    }
 }
@@ -0,0 +1,729 @@
+//! Coarse dependency graph between the modules of a codebase.
+//!
+//! Scans the import statements of Rust, Go, Python and JavaScript/TypeScript
+//! files and resolves the ones that point inside the codebase to modules:
+//! workspace members (crates, packages) when there are any, otherwise the
+//! top-level directories and modules of the source root (`src/tools`,
+//! `internal/auth`, ...). External dependencies are left out. The condensed
+//! graph in the codebase report tells the discovery LLM which subsystems
+//! lean on which, so its commands target the ones that matter for the task.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::ecosystems::{expand_members, npm_workspaces, read_json, read_toml, toml_strings};
+use crate::heuristics::SKIPPED_DIRS;
+
+/// Most source files scanned.
+const MAX_FILES: usize = 5000;
+
+/// Larger files (generated code, bundles) are skipped.
+const MAX_FILE_BYTES: u64 = 256 * 1024;
+
+/// Most modules listed in the condensed graph.
+const MAX_MODULES: usize = 40;
+
+/// Most dependencies listed per module.
+const MAX_DEPENDENCIES: usize = 8;
+
+/// Directories whose subdirectories are the modules.
+const SOURCE_ROOTS: &[&str] = &["src", "lib", "pkg", "internal", "app"];
+
+/// File stems that stand for their directory.
+const ENTRY_STEMS: &[&str] = &["main", "lib", "mod", "index", "__init__", "__main__"];
+
+/// A package of the workspace and the names it is imported by.
+#[derive(Debug, Clone)]
+struct Package {
+    /// Directory relative to the root; empty for the root package
+    dir: String,
+    names: Vec<String>,
+}
+
+/// Modules and how often each imports the others.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DependencyGraph {
+    /// Every module with a scanned source file
+    pub modules: BTreeSet<String>,
+    /// Importing module -> imported module -> number of imports
+    pub edges: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+impl DependencyGraph {
+    /// Build the graph of the codebase at `root`.
+    pub fn build(root: &Path) -> Self {
+        let packages = workspace_packages(root);
+        let mut files = Vec::new();
+        collect_source_files(root, root, &mut files);
+
+        let mut graph = Self::default();
+        for file in files {
+            let Ok(content) = fs::read_to_string(root.join(&file)) else {
+                continue;
+            };
+            let from = module_of(&components(&file), &packages);
+            graph.modules.insert(from.clone());
+            for to in imported_modules(root, &file, &content, &packages) {
+                if to != from {
+                    *graph
+                        .edges
+                        .entry(from.clone())
+                        .or_default()
+                        .entry(to)
+                        .or_default() += 1;
+                }
+            }
+        }
+        graph
+    }
+
+    /// How many modules import `module`.
+    pub fn dependents(&self, module: &str) -> usize {
+        self.edges
+            .values()
+            .filter(|deps| deps.contains_key(module))
+            .count()
+    }
+
+    /// The graph as one line per module, most-connected modules first, with
+    /// the modules most depended on at the end. Empty without edges.
+    pub fn format_condensed(&self) -> String {
+        if self.edges.is_empty() {
+            return String::new();
+        }
+        let mut text = String::from("\n=== MODULE DEPENDENCIES ===\n\n");
+        text.push_str("--- Imports (module -> modules it imports, by number of imports) ---\n");
+
+        let total = |deps: &BTreeMap<String, usize>| deps.values().sum::<usize>();
+        let mut modules: Vec<(&String, &BTreeMap<String, usize>)> = self.edges.iter().collect();
+        modules.sort_by(|a, b| total(b.1).cmp(&total(a.1)).then(a.0.cmp(b.0)));
+        for (module, deps) in modules.iter().take(MAX_MODULES) {
+            let mut deps: Vec<(&String, &usize)> = deps.iter().collect();
+            deps.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            let mut listed: Vec<String> = deps
+                .iter()
+                .take(MAX_DEPENDENCIES)
+                .map(|(dep, count)| format!("{} ({})", dep, count))
+                .collect();
+            if deps.len() > MAX_DEPENDENCIES {
+                listed.push(format!("+{} more", deps.len() - MAX_DEPENDENCIES));
+            }
+            text.push_str(&format!("{} -> {}\n", module, listed.join(", ")));
+        }
+        if modules.len() > MAX_MODULES {
+            text.push_str(&format!(
+                "... ({} more modules)\n",
+                modules.len() - MAX_MODULES
+            ));
+        }
+
+        let mut depended_on: Vec<(&String, usize)> = self
+            .modules
+            .iter()
+            .map(|m| (m, self.dependents(m)))
+            .filter(|(_, n)| *n > 0)
+            .collect();
+        depended_on.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let listed: Vec<String> = depended_on
+            .iter()
+            .take(MAX_DEPENDENCIES)
+            .map(|(m, n)| format!("{} ({})", m, n))
+            .collect();
+        text.push_str(&format!(
+            "\n--- Most depended on (by number of importing modules) ---\n{}\n",
+            listed.join(", ")
+        ));
+        text
+    }
+}
+
+/// Workspace members of every ecosystem, plus the root package, with the
+/// names code imports them by.
+fn workspace_packages(root: &Path) -> Vec<Package> {
+    let mut packages = Vec::new();
+    let rust_name = |dir: &Path| -> Option<String> {
+        let manifest = read_toml(&dir.join("Cargo.toml"))?;
+        let name = manifest
+            .get("lib")
+            .and_then(|l| l.get("name"))
+            .or_else(|| manifest.get("package")?.get("name"))?
+            .as_str()?;
+        Some(name.replace('-', "_"))
+    };
+    let go_module = |dir: &Path| -> Option<String> {
+        let go_mod = fs::read_to_string(dir.join("go.mod")).ok()?;
+        go_mod
+            .lines()
+            .find_map(|l| l.trim().strip_prefix("module "))
+            .map(|m| m.trim().to_string())
+    };
+    let python_name = |dir: &Path| -> Option<String> {
+        let manifest = read_toml(&dir.join("pyproject.toml"))?;
+        let name = manifest.get("project")?.get("name")?.as_str()?;
+        Some(name.replace('-', "_"))
+    };
+
+    if let Some(manifest) = read_toml(&root.join("Cargo.toml")) {
+        let patterns = toml_strings(manifest.get("workspace").and_then(|w| w.get("members")));
+        for dir in expand_members(root, &patterns, "Cargo.toml") {
+            let names = rust_name(&root.join(&dir)).into_iter().collect();
+            packages.push(Package { dir, names });
+        }
+    }
+    if let Some(manifest) = read_json(&root.join("package.json")) {
+        for dir in expand_members(root, &npm_workspaces(&manifest), "package.json") {
+            let names = read_json(&root.join(&dir).join("package.json"))
+                .and_then(|m| m.get("name")?.as_str().map(String::from))
+                .into_iter()
+                .collect();
+            packages.push(Package { dir, names });
+        }
+    }
+    if let Ok(work) = fs::read_to_string(root.join("go.work")) {
+        for line in work.lines() {
+            let dir = line.trim().trim_start_matches("use").trim();
+            let Some(dir) = dir.strip_prefix("./") else {
+                continue;
+            };
+            let names = go_module(&root.join(dir)).into_iter().collect();
+            packages.push(Package {
+                dir: dir.trim_end_matches('/').to_string(),
+                names,
+            });
+        }
+    }
+    if let Some(manifest) = read_toml(&root.join("pyproject.toml")) {
+        let patterns = toml_strings(
+            manifest
+                .get("tool")
+                .and_then(|t| t.get("uv"))
+                .and_then(|u| u.get("workspace"))
+                .and_then(|w| w.get("members")),
+        );
+        for dir in expand_members(root, &patterns, "pyproject.toml") {
+            let names = python_name(&root.join(&dir)).into_iter().collect();
+            packages.push(Package { dir, names });
+        }
+    }
+
+    let root_names: Vec<String> = [rust_name(root), go_module(root), python_name(root)]
+        .into_iter()
+        .flatten()
+        .collect();
+    packages.push(Package {
+        dir: String::new(),
+        names: root_names,
+    });
+    packages
+}
+
+fn collect_source_files(root: &Path, dir: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        if files.len() >= MAX_FILES {
+            return;
+        }
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_ref()) && name != "__pycache__" {
+                collect_source_files(root, &path, files);
+            }
+        } else if language(&name).is_some() && metadata.len() <= MAX_FILE_BYTES {
+            if let Ok(relative) = path.strip_prefix(root) {
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Language {
+    Rust,
+    Go,
+    Python,
+    JavaScript,
+}
+
+fn language(file: &str) -> Option<Language> {
+    let extension = file.rsplit_once('.')?.1;
+    match extension {
+        "rs" => Some(Language::Rust),
+        "go" => Some(Language::Go),
+        "py" => Some(Language::Python),
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => Some(Language::JavaScript),
+        _ => None,
+    }
+}
+
+fn components(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|c| !c.is_empty() && *c != ".")
+        .map(String::from)
+        .collect()
+}
+
+/// The module a path belongs to: the workspace member containing it, or
+/// else its top-level directory (two levels under a source root such as
+/// `src/`). The last component may be a file or module name; entry files
+/// (`mod.rs`, `index.ts`, `__init__.py`, ...) stand for their directory.
+fn module_of(path: &[String], packages: &[Package]) -> String {
+    for package in packages.iter().filter(|p| !p.dir.is_empty()) {
+        let dir = components(&package.dir);
+        if path.starts_with(&dir) {
+            return package.dir.clone();
+        }
+    }
+    let mut path: Vec<String> = path.to_vec();
+    if let Some(last) = path.last_mut() {
+        if let Some((stem, extension)) = last.rsplit_once('.') {
+            if language(&format!(".{}", extension)).is_some() {
+                *last = stem.to_string();
+            }
+        }
+        if ENTRY_STEMS.contains(&last.as_str()) {
+            path.pop();
+        }
+    }
+    let depth = match path.first() {
+        Some(first) if SOURCE_ROOTS.contains(&first.as_str()) => 2,
+        _ => 1,
+    };
+    path.truncate(depth);
+    if path.is_empty() {
+        "(root)".to_string()
+    } else {
+        path.join("/")
+    }
+}
+
+/// The modules a file's import statements point at, one per import.
+fn imported_modules(root: &Path, file: &str, content: &str, packages: &[Package]) -> Vec<String> {
+    let file_path = components(file);
+    let dir = &file_path[..file_path.len().saturating_sub(1)];
+    let package = packages
+        .iter()
+        .filter(|p| p.dir.is_empty() || file_path.starts_with(&components(&p.dir)))
+        .max_by_key(|p| p.dir.len());
+    let package_of = |name: &str| packages.iter().find(|p| p.names.iter().any(|n| n == name));
+    let in_package = |package: &Package, rest: &[&str]| {
+        let mut path = components(&package.dir);
+        path.extend(rest.iter().map(|s| s.to_string()));
+        module_of(&path, packages)
+    };
+
+    let mut modules = Vec::new();
+    match language(file) {
+        Some(Language::Rust) => {
+            for segments in rust_imports(content) {
+                let (first, second) = (segments[0].as_str(), segments.get(1).map(String::as_str));
+                let target = match first {
+                    "crate" => package,
+                    "self" | "super" | "std" | "core" | "alloc" => None,
+                    name => package_of(name),
+                };
+                if let Some(target) = target {
+                    let rest: Vec<&str> = ["src"].into_iter().chain(second).collect();
+                    modules.push(in_package(target, &rest));
+                }
+            }
+        }
+        Some(Language::Go) => {
+            for import in go_imports(content) {
+                // The longest module path wins: modules can nest
+                let target = packages
+                    .iter()
+                    .flat_map(|p| p.names.iter().map(move |n| (p, n)))
+                    .filter(|(_, name)| {
+                        import
+                            .strip_prefix(name.as_str())
+                            .is_some_and(|r| r.is_empty() || r.starts_with('/'))
+                    })
+                    .max_by_key(|(_, name)| name.len());
+                if let Some((package, name)) = target {
+                    let rest: Vec<&str> = import[name.len()..]
+                        .split('/')
+                        .filter(|s| !s.is_empty())
+                        .collect();
+                    modules.push(in_package(package, &rest));
+                }
+            }
+        }
+        Some(Language::Python) => {
+            for (level, name) in python_imports(content) {
+                let segments: Vec<&str> = name.split('.').filter(|s| !s.is_empty()).collect();
+                if level > 0 {
+                    let Some(base) = dir.len().checked_sub(level - 1) else {
+                        continue;
+                    };
+                    let mut path = dir[..base].to_vec();
+                    path.extend(segments.iter().map(|s| s.to_string()));
+                    modules.push(module_of(&path, packages));
+                } else if let Some(target) = segments.first().and_then(|s| package_of(s)) {
+                    modules.push(in_package(target, &segments[1..]));
+                } else if let Some(first) = segments.first() {
+                    // A top-level package of the codebase, at the root or under src/
+                    for base in ["", "src/"] {
+                        let candidate = format!("{}{}", base, first);
+                        if root.join(&candidate).is_dir()
+                            || root.join(format!("{}.py", candidate)).is_file()
+                        {
+                            let mut path = components(&candidate);
+                            path.extend(segments[1..].iter().map(|s| s.to_string()));
+                            modules.push(module_of(&path, packages));
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        Some(Language::JavaScript) => {
+            for specifier in js_imports(content) {
+                if specifier.starts_with("./") || specifier.starts_with("../") {
+                    let mut path = dir.to_vec();
+                    for part in specifier.split('/') {
+                        match part {
+                            "." | "" => {}
+                            ".." => {
+                                path.pop();
+                            }
+                            part => path.push(part.to_string()),
+                        }
+                    }
+                    modules.push(module_of(&path, packages));
+                } else {
+                    // `@scope/name/sub` or `name/sub`
+                    let parts: Vec<&str> = specifier.split('/').collect();
+                    let name_len = if specifier.starts_with('@') { 2 } else { 1 };
+                    let name = parts[..name_len.min(parts.len())].join("/");
+                    if let Some(target) = package_of(&name).filter(|p| !p.dir.is_empty()) {
+                        modules.push(in_package(target, &[]));
+                    }
+                }
+            }
+        }
+        None => {}
+    }
+    modules
+}
+
+/// The first two path segments of every `use` (and `extern crate`)
+/// statement, e.g. `use crate::{tools::x, y}` gives `crate::tools` and
+/// `crate::y`.
+fn rust_imports(content: &str) -> Vec<Vec<String>> {
+    let mut imports = Vec::new();
+    let mut statement: Option<String> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        let text = match statement.take() {
+            Some(partial) => format!("{} {}", partial, line),
+            None => {
+                let line = line
+                    .strip_prefix("pub(crate) ")
+                    .or_else(|| line.strip_prefix("pub(super) "))
+                    .or_else(|| line.strip_prefix("pub "))
+                    .unwrap_or(line);
+                match line
+                    .strip_prefix("use ")
+                    .or_else(|| line.strip_prefix("extern crate "))
+                {
+                    Some(tree) => tree.to_string(),
+                    None => continue,
+                }
+            }
+        };
+        match text.split_once(';') {
+            Some((tree, _)) => use_tree_paths(tree, &mut imports),
+            None => statement = Some(text),
+        }
+    }
+    imports
+}
+
+fn use_tree_paths(tree: &str, paths: &mut Vec<Vec<String>>) {
+    let tree = tree.trim().trim_start_matches("::").trim_start();
+    if let Some(group) = tree.strip_prefix('{') {
+        for item in split_group(group) {
+            use_tree_paths(item, paths);
+        }
+        return;
+    }
+    let first: String = tree
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    if first.is_empty() {
+        return;
+    }
+    let rest = tree[first.len()..].trim_start();
+    let Some(rest) = rest.strip_prefix("::").map(str::trim_start) else {
+        paths.push(vec![first]);
+        return;
+    };
+    let seconds: Vec<String> = match rest.strip_prefix('{') {
+        Some(group) => split_group(group)
+            .into_iter()
+            .map(|item| {
+                item.trim()
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .collect()
+            })
+            .collect(),
+        None => vec![rest
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect()],
+    };
+    for second in seconds {
+        if second.is_empty() || second == "self" {
+            paths.push(vec![first.clone()]);
+        } else {
+            paths.push(vec![first.clone(), second]);
+        }
+    }
+}
+
+/// The comma-separated items of a `{...}` group whose opening brace has
+/// been consumed, ignoring commas in nested groups.
+fn split_group(group: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in group.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => {
+                items.push(&group[start..i]);
+                return items.into_iter().filter(|i| !i.trim().is_empty()).collect();
+            }
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(&group[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&group[start..]);
+    items.into_iter().filter(|i| !i.trim().is_empty()).collect()
+}
+
+/// Import paths of `import "x"` and `import ( ... )` blocks.
+fn go_imports(content: &str) -> Vec<String> {
+    let mut imports = Vec::new();
+    let mut in_block = false;
+    for line in content.lines() {
+        let line = line.trim();
+        let spec = if in_block {
+            if line.starts_with(')') {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if line == "import (" {
+            in_block = true;
+            continue;
+        } else if let Some(spec) = line.strip_prefix("import ") {
+            spec
+        } else {
+            continue;
+        };
+        if let Some(path) = spec.split('"').nth(1) {
+            imports.push(path.to_string());
+        }
+    }
+    imports
+}
+
+/// `(level, module)` of `import a.b` and `from ..a import b` statements;
+/// level counts the leading dots of relative imports.
+fn python_imports(content: &str) -> Vec<(usize, String)> {
+    let mut imports = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(modules) = line.strip_prefix("import ") {
+            for module in modules.split(',') {
+                if let Some(name) = module.split_whitespace().next() {
+                    imports.push((0, name.to_string()));
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("from ") {
+            let Some(module) = rest.split_whitespace().next() else {
+                continue;
+            };
+            let name = module.trim_start_matches('.');
+            imports.push((module.len() - name.len(), name.to_string()));
+        }
+    }
+    imports
+}
+
+/// Module specifiers of `import`/`export ... from`, `require()` and
+/// dynamic `import()`.
+fn js_imports(content: &str) -> Vec<String> {
+    let mut imports = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let is_import = line.starts_with("import ")
+            || line.starts_with("import'")
+            || line.starts_with("import\"");
+        let mut found = false;
+        for marker in ["require(", "import("] {
+            if let Some(i) = line.find(marker) {
+                if let Some(spec) = quoted(&line[i + marker.len()..]) {
+                    imports.push(spec);
+                    found = true;
+                }
+            }
+        }
+        if found {
+            continue;
+        }
+        if let Some(i) = line.rfind(" from ") {
+            if is_import || line.starts_with("export ") || line.starts_with('}') {
+                if let Some(spec) = quoted(&line[i + 6..]) {
+                    imports.push(spec);
+                }
+            }
+        } else if is_import {
+            if let Some(spec) = quoted(&line["import".len()..]) {
+                imports.push(spec);
+            }
+        }
+    }
+    imports
+}
+
+/// The contents of the string literal at the start of `text`.
+fn quoted(text: &str) -> Option<String> {
+    let text = text.trim_start();
+    let quote = text
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '"' | '\'' | '`'))?;
+    let rest = &text[1..];
+    rest.find(quote).map(|end| rest[..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_imports() {
+        let imports = rust_imports(
+            "use std::fs;\npub use crate::tools::{self, shell};\nuse crate::{\n    plan::Plan,\n    paths,\n};\nuse g3_core::Agent;\nuse super::x;\n",
+        );
+        let paths: Vec<String> = imports.iter().map(|p| p.join("::")).collect();
+        assert_eq!(
+            paths,
+            [
+                "std::fs",
+                "crate::tools",
+                "crate::plan",
+                "crate::paths",
+                "g3_core::Agent",
+                "super::x"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cargo_workspace_graph() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        for (name, lib) in [
+            (
+                "app-cli",
+                "use app_core::run;\nuse app_config::Config;\nuse crate::args;\n",
+            ),
+            (
+                "app-core",
+                "use app_config::Config;\nuse serde::Serialize;\n",
+            ),
+            ("app-config", "use std::path::Path;\n"),
+        ] {
+            fs::create_dir_all(root.join("crates").join(name).join("src")).unwrap();
+            fs::write(
+                root.join("crates").join(name).join("Cargo.toml"),
+                format!("[package]\nname = \"{}\"\n", name),
+            )
+            .unwrap();
+            fs::write(root.join("crates").join(name).join("src/lib.rs"), lib).unwrap();
+        }
+
+        let graph = DependencyGraph::build(root);
+        assert_eq!(graph.modules.len(), 3);
+        assert_eq!(
+            graph.edges["crates/app-cli"].keys().collect::<Vec<_>>(),
+            ["crates/app-config", "crates/app-core"]
+        );
+        assert_eq!(graph.dependents("crates/app-config"), 2);
+
+        let text = graph.format_condensed();
+        assert!(text.contains("crates/app-cli -> crates/app-config (1), crates/app-core (1)\n"));
+        assert!(text.contains("crates/app-core -> crates/app-config (1)\n"));
+        assert!(text.contains("crates/app-config (2), crates/app-core (1)"));
+    }
+
+    #[test]
+    fn test_single_package_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/components")).unwrap();
+        fs::create_dir_all(root.join("src/utils")).unwrap();
+        fs::write(
+            root.join("src/components/Button.tsx"),
+            "import React from 'react';\nimport { fmt } from '../utils/format';\nimport { api } from \"../api\";\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/utils/format.ts"), "export const fmt = 1;\n").unwrap();
+        fs::write(
+            root.join("src/index.ts"),
+            "export { Button } from './components/Button';\nconst u = require('./utils');\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("service")).unwrap();
+        fs::write(root.join("service/__init__.py"), "").unwrap();
+        fs::write(
+            root.join("service/handlers.py"),
+            "import os\nfrom service.db import session\nfrom .models import User\n",
+        )
+        .unwrap();
+        fs::write(root.join("main.py"), "from service import handlers\n").unwrap();
+
+        let graph = DependencyGraph::build(root);
+        assert_eq!(
+            graph.edges["src/components"].keys().collect::<Vec<_>>(),
+            ["src/api", "src/utils"]
+        );
+        assert_eq!(
+            graph.edges["src"].keys().collect::<Vec<_>>(),
+            ["src/components", "src/utils"]
+        );
+        assert_eq!(
+            graph.edges["(root)"].keys().collect::<Vec<_>>(),
+            ["service"]
+        );
+        // Imports within a module are not edges
+        assert!(!graph.edges.contains_key("service"));
+    }
+}
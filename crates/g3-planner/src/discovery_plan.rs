@@ -0,0 +1,309 @@
+//! The discovery plan an LLM returns: exploration commands with the reason
+//! for each and what it should reveal.
+//!
+//! The discovery prompt asks for a JSON object (see
+//! `prompts::DISCOVERY_REQUIREMENTS_PROMPT`), which is parsed with serde.
+//! Models sometimes wrap it in a code fence or add a sentence around it, so
+//! the outermost JSON object in the response is used. Responses without
+//! valid JSON fall back to the older text format: a
+//! `{{CODE EXPLORATION COMMANDS}}` section, or any fenced shell block.
+//!
+//! With structured requirements, each command names the requirement ID it
+//! serves; requirements the plan leaves without a command get a keyword
+//! search (see `requirement_search_command`).
+
+use g3_core::requirements::Requirement;
+use serde::{Deserialize, Serialize};
+
+use crate::{extract_shell_commands, extract_summary};
+
+/// One exploration command of a discovery plan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "CommandSpec")]
+pub struct PlannedCommand {
+    pub command: String,
+    pub rationale: Option<String>,
+    pub expected_signal: Option<String>,
+    /// ID of the requirement the command serves, with structured requirements
+    pub requirement: Option<String>,
+}
+
+/// Commands are objects, but a bare string is accepted too.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CommandSpec {
+    Command(String),
+    Detailed {
+        command: String,
+        #[serde(default)]
+        rationale: Option<String>,
+        #[serde(default, alias = "expected")]
+        expected_signal: Option<String>,
+        #[serde(default, alias = "requirement_id")]
+        requirement: Option<String>,
+    },
+}
+
+impl From<CommandSpec> for PlannedCommand {
+    fn from(spec: CommandSpec) -> Self {
+        match spec {
+            CommandSpec::Command(command) => Self {
+                command,
+                rationale: None,
+                expected_signal: None,
+                requirement: None,
+            },
+            CommandSpec::Detailed {
+                command,
+                rationale,
+                expected_signal,
+                requirement,
+            } => Self {
+                command,
+                rationale,
+                expected_signal,
+                requirement,
+            },
+        }
+    }
+}
+
+/// A parsed discovery plan.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveryPlan {
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub commands: Vec<PlannedCommand>,
+}
+
+/// How a plan was read from the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanSource {
+    Json,
+    /// The response wasn't a JSON plan; commands were extracted from text
+    TextFallback,
+}
+
+impl DiscoveryPlan {
+    /// The commands, without blank entries and comments.
+    pub fn command_lines(&self) -> Vec<String> {
+        self.commands
+            .iter()
+            .map(|c| c.command.trim())
+            .filter(|c| !c.is_empty() && !c.starts_with('#'))
+            .map(String::from)
+            .collect()
+    }
+}
+
+/// Parse an LLM response into a discovery plan, falling back to extracting
+/// commands from text when it holds no JSON plan.
+pub fn parse_discovery_plan(response: &str) -> (DiscoveryPlan, PlanSource) {
+    if let Some(plan) = parse_json_plan(response) {
+        return (plan, PlanSource::Json);
+    }
+
+    let mut commands = extract_shell_commands(response);
+    if commands.is_empty() {
+        commands = fenced_shell_commands(response);
+    }
+    let plan = DiscoveryPlan {
+        summary: extract_summary(response),
+        commands: commands
+            .into_iter()
+            .map(|command| PlannedCommand {
+                command,
+                rationale: None,
+                expected_signal: None,
+                requirement: None,
+            })
+            .collect(),
+    };
+    (plan, PlanSource::TextFallback)
+}
+
+/// Words too common in requirements to locate code by.
+const STOPWORDS: &[&str] = &[
+    "able",
+    "after",
+    "also",
+    "when",
+    "with",
+    "that",
+    "this",
+    "from",
+    "into",
+    "have",
+    "should",
+    "must",
+    "will",
+    "each",
+    "every",
+    "user",
+    "users",
+    "support",
+    "allow",
+    "allows",
+    "make",
+    "sure",
+    "them",
+    "their",
+    "there",
+    "which",
+    "where",
+    "what",
+    "only",
+    "than",
+    "then",
+    "more",
+    "less",
+    "some",
+    "such",
+    "same",
+    "other",
+    "page",
+    "data",
+    "feature",
+    "requirement",
+];
+
+/// A keyword search for code related to a requirement: up to three
+/// distinctive words of its title, or of its description when the title has
+/// none. `None` when no word qualifies.
+pub fn requirement_search_command(requirement: &Requirement) -> Option<String> {
+    let keywords = |text: &str| {
+        let mut words: Vec<String> = Vec::new();
+        for word in text.split(|c: char| !c.is_ascii_alphanumeric()) {
+            let word = word.to_ascii_lowercase();
+            if word.len() >= 4 && !STOPWORDS.contains(&word.as_str()) && !words.contains(&word) {
+                words.push(word);
+            }
+        }
+        words.truncate(3);
+        words
+    };
+    let mut words = keywords(&requirement.title);
+    if words.is_empty() {
+        words = keywords(&requirement.description);
+    }
+    if words.is_empty() {
+        return None;
+    }
+    let patterns: Vec<String> = words.iter().map(|w| format!("-e {}", w)).collect();
+    Some(format!(
+        "rg -n -i -m 3 {} . | head -n 40",
+        patterns.join(" ")
+    ))
+}
+
+/// The outermost `{...}` of the response as a plan with at least one command.
+fn parse_json_plan(response: &str) -> Option<DiscoveryPlan> {
+    let start = response.find('{')?;
+    let end = response.rfind('}')?;
+    if end < start {
+        return None;
+    }
+    let plan: DiscoveryPlan = serde_json::from_str(&response[start..=end]).ok()?;
+    (!plan.commands.is_empty()).then_some(plan)
+}
+
+/// Lines of fenced code blocks other than JSON ones, skipping comments.
+fn fenced_shell_commands(response: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut in_block = false;
+    let mut is_json = false;
+    for line in response.lines() {
+        let trimmed = line.trim();
+        if let Some(language) = trimmed.strip_prefix("```") {
+            if !in_block {
+                is_json = language.trim().eq_ignore_ascii_case("json");
+            }
+            in_block = !in_block;
+        } else if in_block && !is_json && !trimmed.is_empty() && !trimmed.starts_with('#') {
+            commands.push(trimmed.to_string());
+        }
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json_plan() {
+        let response = r#"Here is the plan:
+```json
+{
+  "summary": "A Rust workspace with a CLI and a core crate.",
+  "commands": [
+    {
+      "command": "rg -n 'fn main' crates",
+      "rationale": "Find the binaries",
+      "expected_signal": "Entry points of each crate"
+    },
+    "ls crates",
+    { "command": "  " }
+  ]
+}
+```"#;
+        let (plan, source) = parse_discovery_plan(response);
+        assert_eq!(source, PlanSource::Json);
+        assert_eq!(
+            plan.summary.as_deref(),
+            Some("A Rust workspace with a CLI and a core crate.")
+        );
+        assert_eq!(
+            plan.commands[0].expected_signal.as_deref(),
+            Some("Entry points of each crate")
+        );
+        assert_eq!(
+            plan.command_lines(),
+            ["rg -n 'fn main' crates", "ls crates"]
+        );
+    }
+
+    #[test]
+    fn test_text_fallback() {
+        let legacy = "{{SUMMARY BASED ON INITIAL INFO}}\nSmall app.\n\n{{CODE EXPLORATION COMMANDS}}\n```\nls -la\ncat README.md\n```\n";
+        let (plan, source) = parse_discovery_plan(legacy);
+        assert_eq!(source, PlanSource::TextFallback);
+        assert_eq!(plan.summary.as_deref(), Some("Small app."));
+        assert_eq!(plan.command_lines(), ["ls -la", "cat README.md"]);
+
+        // No marker and no JSON: fenced shell blocks still count
+        let loose = "I would run:\n```bash\n# layout\nls src\n```\nand\n```sh\nrg -n Config\n```\n";
+        let (plan, source) = parse_discovery_plan(loose);
+        assert_eq!(source, PlanSource::TextFallback);
+        assert_eq!(plan.command_lines(), ["ls src", "rg -n Config"]);
+
+        // Malformed JSON isn't mistaken for commands
+        let (plan, _) = parse_discovery_plan("```json\n{\"commands\": [\n```");
+        assert!(plan.commands.is_empty());
+    }
+
+    #[test]
+    fn test_requirement_commands() {
+        let response = r#"{"commands": [{"command": "rg -n login src", "requirement": "REQ-1"}, {"command": "ls", "requirement_id": "REQ-2"}]}"#;
+        let (plan, _) = parse_discovery_plan(response);
+        assert_eq!(plan.commands[0].requirement.as_deref(), Some("REQ-1"));
+        assert_eq!(plan.commands[1].requirement.as_deref(), Some("REQ-2"));
+
+        let requirement = Requirement {
+            id: "REQ-3".to_string(),
+            title: "Users should export invoices to CSV with totals".to_string(),
+            description: String::new(),
+            acceptance_criteria: Vec::new(),
+        };
+        assert_eq!(
+            requirement_search_command(&requirement).as_deref(),
+            Some("rg -n -i -m 3 -e export -e invoices -e totals . | head -n 40")
+        );
+        let vague = Requirement {
+            title: "Make it so".to_string(),
+            ..requirement
+        };
+        assert_eq!(requirement_search_command(&vague), None);
+    }
+}
@@ -0,0 +1,205 @@
+//! Snapshot of the workspace at the last discovery, for incremental
+//! re-discovery.
+//!
+//! Full discovery is saved as a snapshot of every file's size and
+//! modification time (`.g3/planner/discovery_snapshot.json`). A follow-up
+//! task in the same session diffs the workspace against it and only looks
+//! at what changed since (see `refresh_discovery`), instead of asking for
+//! and running a whole new plan.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::heuristics::shell_quote;
+use crate::report_cache::{tree_stamps, FileStamp};
+
+/// Location of the snapshot, relative to the codebase root.
+pub const DISCOVERY_SNAPSHOT_PATH: &str = ".g3/planner/discovery_snapshot.json";
+
+/// Above this many changed files a follow-up isn't "minimal" anymore and
+/// full discovery is cheaper to read.
+pub const MAX_INCREMENTAL_CHANGES: usize = 40;
+
+/// Files whose content is shown; the rest are only listed by `git status`.
+const MAX_FILES_SHOWN: usize = 12;
+
+/// Lines of a new file, or of a diff, shown per file.
+const PREVIEW_LINES: usize = 120;
+
+/// The files of the workspace when discovery last ran.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiscoverySnapshot {
+    pub created_at: String,
+    pub files: BTreeMap<String, FileStamp>,
+}
+
+/// Files changed since a snapshot, by relative path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceChanges {
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl WorkspaceChanges {
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.added.len() + self.modified.len() + self.removed.len()
+    }
+}
+
+impl DiscoverySnapshot {
+    /// Snapshot of the workspace at `root` as it is now.
+    pub fn capture(root: &Path) -> Self {
+        Self {
+            created_at: chrono::Local::now().to_rfc3339(),
+            files: tree_stamps(root),
+        }
+    }
+
+    /// What changed between this snapshot and `current`.
+    pub fn diff(&self, current: &DiscoverySnapshot) -> WorkspaceChanges {
+        let mut changes = WorkspaceChanges::default();
+        for (path, stamp) in &current.files {
+            match self.files.get(path) {
+                None => changes.added.push(path.clone()),
+                Some(old) if old != stamp => changes.modified.push(path.clone()),
+                Some(_) => {}
+            }
+        }
+        changes.removed = self
+            .files
+            .keys()
+            .filter(|path| !current.files.contains_key(*path))
+            .cloned()
+            .collect();
+        changes
+    }
+}
+
+/// Path of the discovery snapshot for the codebase at `root`.
+pub fn discovery_snapshot_path(root: &Path) -> PathBuf {
+    root.join(DISCOVERY_SNAPSHOT_PATH)
+}
+
+/// The last saved snapshot, if any.
+pub fn load_discovery_snapshot(root: &Path) -> Option<DiscoverySnapshot> {
+    let content = fs::read_to_string(discovery_snapshot_path(root)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn save_discovery_snapshot(root: &Path, snapshot: &DiscoverySnapshot) -> Result<()> {
+    let path = discovery_snapshot_path(root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string(snapshot)?)?;
+    Ok(())
+}
+
+/// The fewest read-only commands that show what changed: `git status` for
+/// the overview, the diff of each modified file (or its head outside git),
+/// the head of each added file and the listing of directories that lost
+/// files. Never empty for a non-empty `changes`.
+pub fn follow_up_commands(root: &Path, changes: &WorkspaceChanges) -> Vec<String> {
+    let is_git = root.join(".git").exists();
+    let mut commands = Vec::new();
+    if is_git {
+        commands.push("git status --short".to_string());
+    }
+
+    let mut shown = 0;
+    for path in &changes.modified {
+        if shown == MAX_FILES_SHOWN {
+            break;
+        }
+        commands.push(if is_git {
+            format!(
+                "git diff -- {} | head -n {}",
+                shell_quote(path),
+                PREVIEW_LINES
+            )
+        } else {
+            format!("head -n {} {}", PREVIEW_LINES, shell_quote(path))
+        });
+        shown += 1;
+    }
+    for path in &changes.added {
+        if shown == MAX_FILES_SHOWN {
+            break;
+        }
+        commands.push(format!("head -n {} {}", PREVIEW_LINES, shell_quote(path)));
+        shown += 1;
+    }
+
+    // The closest directory that still exists above each removed file
+    let mut dirs: Vec<&str> = changes
+        .removed
+        .iter()
+        .map(|path| {
+            let mut dir = path.as_str();
+            loop {
+                dir = dir.rsplit_once('/').map_or(".", |(parent, _)| parent);
+                if dir == "." || root.join(dir).is_dir() {
+                    break dir;
+                }
+            }
+        })
+        .collect();
+    dirs.sort_unstable();
+    dirs.dedup();
+    commands.extend(
+        dirs.into_iter()
+            .map(|dir| format!("ls -la {}", shell_quote(dir))),
+    );
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_since_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/old")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("src/old/gone.rs"), "\n").unwrap();
+        fs::write(root.join("src/old/kept.rs"), "\n").unwrap();
+        fs::write(root.join("README.md"), "# app\n").unwrap();
+
+        let snapshot = DiscoverySnapshot::capture(root);
+        save_discovery_snapshot(root, &snapshot).unwrap();
+        let saved = load_discovery_snapshot(root).unwrap();
+        assert!(saved.diff(&DiscoverySnapshot::capture(root)).is_empty());
+
+        fs::write(root.join("src/main.rs"), "fn main() { run() }\n").unwrap();
+        fs::write(root.join("src/run.rs"), "pub fn run() {}\n").unwrap();
+        fs::remove_file(root.join("src/old/gone.rs")).unwrap();
+
+        let changes = saved.diff(&DiscoverySnapshot::capture(root));
+        assert_eq!(
+            changes,
+            WorkspaceChanges {
+                added: vec!["src/run.rs".to_string()],
+                modified: vec!["src/main.rs".to_string()],
+                removed: vec!["src/old/gone.rs".to_string()],
+            }
+        );
+        assert_eq!(
+            follow_up_commands(root, &changes),
+            [
+                "head -n 120 src/main.rs",
+                "head -n 120 src/run.rs",
+                "ls -la src/old",
+            ]
+        );
+    }
+}
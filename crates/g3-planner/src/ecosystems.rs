@@ -0,0 +1,581 @@
+//! Ecosystem-aware sections of the codebase report.
+//!
+//! A generic report shows the first lines of a manifest and a file listing.
+//! For the common ecosystems (Cargo, npm, Go modules, Python projects) the
+//! manifest says much more once parsed: which packages make up the
+//! workspace, which scripts build and test it, where execution starts and
+//! which dependencies shape the code. Each detected ecosystem contributes
+//! those facts as named sections.
+
+use std::fs;
+use std::path::Path;
+
+use crate::heuristics::SKIPPED_DIRS;
+
+/// Most items listed per section, so monorepos don't flood the report.
+const MAX_SECTION_ITEMS: usize = 40;
+
+/// What one ecosystem's manifests say about the codebase.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EcosystemReport {
+    /// Name of the ecosystem, e.g. "Cargo"
+    pub name: String,
+    /// Section titles and their lines, in report order
+    pub sections: Vec<(String, Vec<String>)>,
+}
+
+impl EcosystemReport {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            sections: Vec::new(),
+        }
+    }
+
+    /// Add a section unless it has nothing to say.
+    fn section(&mut self, title: &str, lines: Vec<String>) {
+        if !lines.is_empty() {
+            self.sections.push((title.to_string(), lines));
+        }
+    }
+
+    /// The report's text, in the format of the other report sections.
+    pub fn format(&self) -> String {
+        let mut text = format!("\n=== {} ECOSYSTEM ===\n\n", self.name.to_uppercase());
+        for (title, lines) in &self.sections {
+            text.push_str(&format!("--- {} ---\n", title));
+            for line in lines.iter().take(MAX_SECTION_ITEMS) {
+                text.push_str(line);
+                text.push('\n');
+            }
+            if lines.len() > MAX_SECTION_ITEMS {
+                text.push_str(&format!("... ({} more)\n", lines.len() - MAX_SECTION_ITEMS));
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Reports for every ecosystem with a manifest at `root`: Cargo.toml,
+/// package.json, go.mod and pyproject.toml.
+pub fn detect_ecosystems(root: &Path) -> Vec<EcosystemReport> {
+    [
+        cargo_report(root),
+        npm_report(root),
+        go_report(root),
+        python_report(root),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+pub(crate) fn read_toml(path: &Path) -> Option<toml::Value> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+pub(crate) fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// A manifest that exists but doesn't parse is shown as is, so the report
+/// still has what a generic listing would.
+fn unparsed(name: &str, path: &Path) -> Option<EcosystemReport> {
+    let content = fs::read_to_string(path).ok()?;
+    let mut report = EcosystemReport::new(name);
+    let title = format!(
+        "{} (could not be parsed)",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+    report.section(&title, content.lines().take(50).map(String::from).collect());
+    Some(report)
+}
+
+pub(crate) fn toml_strings(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|i| i.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The workspace patterns of a package.json: a list, or yarn's
+/// `{ "packages": [...] }`.
+pub(crate) fn npm_workspaces(manifest: &serde_json::Value) -> Vec<String> {
+    let workspaces = manifest.get("workspaces");
+    [workspaces, workspaces.and_then(|w| w.get("packages"))]
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_array())
+        .flatten()
+        .filter_map(|v| v.as_str().map(String::from))
+        .collect()
+}
+
+/// Directories matching workspace member patterns: exact paths, or paths
+/// ending in `*`/`**`, whose subdirectories are members when they hold
+/// `manifest`.
+pub(crate) fn expand_members(root: &Path, patterns: &[String], manifest: &str) -> Vec<String> {
+    let mut members = Vec::new();
+    for pattern in patterns {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        let Some(parent) = pattern
+            .strip_suffix("/**")
+            .or_else(|| pattern.strip_suffix("/*"))
+        else {
+            if root.join(pattern).join(manifest).is_file() {
+                members.push(pattern.to_string());
+            }
+            continue;
+        };
+        let Ok(entries) = fs::read_dir(root.join(parent)) else {
+            continue;
+        };
+        let mut found: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().join(manifest).is_file())
+            .filter_map(|e| e.file_name().to_str().map(|n| format!("{}/{}", parent, n)))
+            .filter(|m| !SKIPPED_DIRS.iter().any(|s| m.split('/').any(|p| p == *s)))
+            .collect();
+        found.sort();
+        members.extend(found);
+    }
+    members.dedup();
+    members
+}
+
+fn cargo_report(root: &Path) -> Option<EcosystemReport> {
+    let path = root.join("Cargo.toml");
+    let Some(manifest) = read_toml(&path) else {
+        return unparsed("Cargo", &path);
+    };
+    let mut report = EcosystemReport::new("Cargo");
+
+    let mut packages = vec![String::new()];
+    if let Some(workspace) = manifest.get("workspace") {
+        let members = expand_members(root, &toml_strings(workspace.get("members")), "Cargo.toml");
+        report.section(
+            "Workspace Members",
+            members
+                .iter()
+                .map(|member| {
+                    let name = read_toml(&root.join(member).join("Cargo.toml"))
+                        .and_then(|m| m.get("package")?.get("name")?.as_str().map(String::from));
+                    match name {
+                        Some(name) if !member.ends_with(&name) => format!("{} ({})", member, name),
+                        _ => member.clone(),
+                    }
+                })
+                .collect(),
+        );
+        packages.extend(members);
+    }
+
+    let mut entry_points = Vec::new();
+    let mut dependencies = Vec::new();
+    for package in &packages {
+        let dir = root.join(package);
+        let prefix = if package.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", package)
+        };
+        let Some(package_manifest) = read_toml(&dir.join("Cargo.toml")) else {
+            continue;
+        };
+        for target in ["src/main.rs", "src/lib.rs"] {
+            if dir.join(target).is_file() {
+                entry_points.push(format!("{}{}", prefix, target));
+            }
+        }
+        if let Ok(bins) = fs::read_dir(dir.join("src/bin")) {
+            let mut bins: Vec<String> = bins
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    e.file_name()
+                        .to_str()
+                        .map(|n| format!("{}src/bin/{}", prefix, n))
+                })
+                .collect();
+            bins.sort();
+            entry_points.extend(bins);
+        }
+        for bin in package_manifest
+            .get("bin")
+            .and_then(|b| b.as_array())
+            .into_iter()
+            .flatten()
+        {
+            if let Some(path) = bin.get("path").and_then(|p| p.as_str()) {
+                let path = format!("{}{}", prefix, path);
+                if !entry_points.contains(&path) {
+                    entry_points.push(path);
+                }
+            }
+        }
+        if package.is_empty() {
+            // Shared versions say the most about a workspace's stack
+            dependencies.extend(toml_table_entries(
+                package_manifest
+                    .get("workspace")
+                    .and_then(|w| w.get("dependencies")),
+            ));
+            dependencies.extend(toml_table_entries(package_manifest.get("dependencies")));
+        }
+    }
+    report.section("Entry Points", entry_points);
+    report.section("Dependency Highlights", dependencies);
+    Some(report)
+}
+
+/// `name version` lines for a TOML dependency table.
+fn toml_table_entries(table: Option<&toml::Value>) -> Vec<String> {
+    let Some(table) = table.and_then(|t| t.as_table()) else {
+        return Vec::new();
+    };
+    table
+        .iter()
+        .map(|(name, spec)| {
+            let version = spec
+                .as_str()
+                .or_else(|| spec.get("version").and_then(|v| v.as_str()));
+            let source = if spec.get("path").is_some() {
+                Some("path")
+            } else if spec.get("git").is_some() {
+                Some("git")
+            } else if spec.get("workspace").is_some() {
+                Some("workspace")
+            } else {
+                None
+            };
+            match (version, source) {
+                (Some(version), _) => format!("{} {}", name, version),
+                (None, Some(source)) => format!("{} ({})", name, source),
+                (None, None) => name.clone(),
+            }
+        })
+        .collect()
+}
+
+fn npm_report(root: &Path) -> Option<EcosystemReport> {
+    let path = root.join("package.json");
+    let Some(manifest) = read_json(&path) else {
+        return unparsed("npm", &path);
+    };
+    let mut report = EcosystemReport::new("npm");
+
+    let members = expand_members(root, &npm_workspaces(&manifest), "package.json");
+    report.section(
+        "Workspace Members",
+        members
+            .iter()
+            .map(|member| {
+                let name = read_json(&root.join(member).join("package.json"))
+                    .and_then(|m| m.get("name")?.as_str().map(String::from));
+                match name {
+                    Some(name) => format!("{} ({})", member, name),
+                    None => member.clone(),
+                }
+            })
+            .collect(),
+    );
+
+    report.section(
+        "Scripts",
+        manifest
+            .get("scripts")
+            .and_then(|s| s.as_object())
+            .map(|scripts| {
+                scripts
+                    .iter()
+                    .map(|(name, command)| {
+                        format!("{}: {}", name, command.as_str().unwrap_or_default())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    );
+
+    let mut entry_points = Vec::new();
+    for field in ["main", "module", "types"] {
+        if let Some(path) = manifest.get(field).and_then(|v| v.as_str()) {
+            entry_points.push(format!("{}: {}", field, path));
+        }
+    }
+    match manifest.get("bin") {
+        Some(serde_json::Value::String(path)) => entry_points.push(format!("bin: {}", path)),
+        Some(serde_json::Value::Object(bins)) => {
+            for (name, path) in bins {
+                entry_points.push(format!(
+                    "bin {}: {}",
+                    name,
+                    path.as_str().unwrap_or_default()
+                ));
+            }
+        }
+        _ => {}
+    }
+    report.section("Entry Points", entry_points);
+
+    let mut dependencies = Vec::new();
+    for (field, suffix) in [("dependencies", ""), ("devDependencies", " (dev)")] {
+        if let Some(deps) = manifest.get(field).and_then(|d| d.as_object()) {
+            for (name, version) in deps {
+                dependencies.push(format!(
+                    "{} {}{}",
+                    name,
+                    version.as_str().unwrap_or_default(),
+                    suffix
+                ));
+            }
+        }
+    }
+    report.section("Dependency Highlights", dependencies);
+    Some(report)
+}
+
+fn go_report(root: &Path) -> Option<EcosystemReport> {
+    let go_mod = fs::read_to_string(root.join("go.mod")).ok()?;
+    let mut report = EcosystemReport::new("Go");
+
+    let mut module = Vec::new();
+    let mut requires = Vec::new();
+    let mut in_require = false;
+    for line in go_mod.lines() {
+        let line = line.trim();
+        if in_require {
+            if line == ")" {
+                in_require = false;
+            } else if !line.is_empty() && !line.contains("// indirect") {
+                requires.push(line.to_string());
+            }
+        } else if let Some(path) = line.strip_prefix("module ") {
+            module.push(format!("module {}", path.trim()));
+        } else if let Some(version) = line.strip_prefix("go ") {
+            module.push(format!("go {}", version.trim()));
+        } else if line == "require (" {
+            in_require = true;
+        } else if let Some(require) = line.strip_prefix("require ") {
+            if !require.contains("// indirect") {
+                requires.push(require.trim().to_string());
+            }
+        }
+    }
+    report.section("Module", module);
+
+    // go.work lists the modules of a multi-module workspace
+    let members: Vec<String> = fs::read_to_string(root.join("go.work"))
+        .map(|work| {
+            work.lines()
+                .map(|l| l.trim().trim_start_matches("use").trim())
+                .filter(|l| l.starts_with("./") || *l == ".")
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    report.section("Workspace Members", members);
+
+    let mut entry_points = Vec::new();
+    if root.join("main.go").is_file() {
+        entry_points.push("main.go".to_string());
+    }
+    if let Ok(commands) = fs::read_dir(root.join("cmd")) {
+        let mut commands: Vec<String> = commands
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().join("main.go").is_file())
+            .filter_map(|e| e.file_name().to_str().map(|n| format!("cmd/{}/main.go", n)))
+            .collect();
+        commands.sort();
+        entry_points.extend(commands);
+    }
+    report.section("Entry Points", entry_points);
+    report.section("Dependency Highlights", requires);
+    Some(report)
+}
+
+fn python_report(root: &Path) -> Option<EcosystemReport> {
+    let path = root.join("pyproject.toml");
+    let Some(manifest) = read_toml(&path) else {
+        return unparsed("Python", &path);
+    };
+    let mut report = EcosystemReport::new("Python");
+    let project = manifest.get("project");
+    let poetry = manifest.get("tool").and_then(|t| t.get("poetry"));
+
+    let mut about = Vec::new();
+    for key in ["name", "requires-python"] {
+        if let Some(value) = project.and_then(|p| p.get(key)).and_then(|v| v.as_str()) {
+            about.push(format!("{} {}", key, value));
+        }
+    }
+    if let Some(backend) = manifest
+        .get("build-system")
+        .and_then(|b| b.get("build-backend"))
+        .and_then(|v| v.as_str())
+    {
+        about.push(format!("build-backend {}", backend));
+    }
+    report.section("Project", about);
+
+    let members = expand_members(
+        root,
+        &toml_strings(
+            manifest
+                .get("tool")
+                .and_then(|t| t.get("uv"))
+                .and_then(|u| u.get("workspace"))
+                .and_then(|w| w.get("members")),
+        ),
+        "pyproject.toml",
+    );
+    report.section("Workspace Members", members);
+
+    let mut entry_points = Vec::new();
+    for scripts in [
+        project.and_then(|p| p.get("scripts")),
+        poetry.and_then(|p| p.get("scripts")),
+    ] {
+        if let Some(scripts) = scripts.and_then(|s| s.as_table()) {
+            for (name, target) in scripts {
+                entry_points.push(format!("{}: {}", name, target.as_str().unwrap_or_default()));
+            }
+        }
+    }
+    for file in ["__main__.py", "main.py", "app.py", "manage.py"] {
+        if root.join(file).is_file() {
+            entry_points.push(file.to_string());
+        }
+    }
+    report.section("Entry Points", entry_points);
+
+    let mut dependencies = toml_strings(project.and_then(|p| p.get("dependencies")));
+    dependencies.extend(
+        toml_table_entries(poetry.and_then(|p| p.get("dependencies")))
+            .into_iter()
+            .filter(|d| !d.starts_with("python ")),
+    );
+    report.section("Dependency Highlights", dependencies);
+    Some(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cargo_and_npm_workspaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.dependencies]\ntokio = { version = \"1\", features = [\"full\"] }\nserde = \"1.0\"\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("crates/core/src")).unwrap();
+        fs::write(
+            root.join("crates/core/Cargo.toml"),
+            "[package]\nname = \"demo-core\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("crates/core/src/lib.rs"), "").unwrap();
+        fs::create_dir_all(root.join("crates/cli/src/bin")).unwrap();
+        fs::write(
+            root.join("crates/cli/Cargo.toml"),
+            "[package]\nname = \"cli\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("crates/cli/src/main.rs"), "").unwrap();
+        fs::write(root.join("crates/cli/src/bin/tool.rs"), "").unwrap();
+        fs::create_dir_all(root.join("crates/notes")).unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"scripts": {"test": "vitest"}, "bin": {"demo": "bin/demo.js"}, "devDependencies": {"vitest": "^1.0.0"}}"#,
+        )
+        .unwrap();
+
+        let reports = detect_ecosystems(root);
+        assert_eq!(reports.len(), 2);
+
+        let cargo = &reports[0];
+        assert_eq!(
+            cargo.sections,
+            vec![
+                (
+                    "Workspace Members".to_string(),
+                    vec![
+                        "crates/cli".to_string(),
+                        "crates/core (demo-core)".to_string()
+                    ]
+                ),
+                (
+                    "Entry Points".to_string(),
+                    vec![
+                        "crates/cli/src/main.rs".to_string(),
+                        "crates/cli/src/bin/tool.rs".to_string(),
+                        "crates/core/src/lib.rs".to_string(),
+                    ]
+                ),
+                (
+                    "Dependency Highlights".to_string(),
+                    vec!["serde 1.0".to_string(), "tokio 1".to_string()]
+                ),
+            ]
+        );
+
+        let npm = reports[1].format();
+        assert!(npm.starts_with("\n=== NPM ECOSYSTEM ===\n"));
+        assert!(npm.contains("--- Scripts ---\ntest: vitest\n"));
+        assert!(npm.contains("--- Entry Points ---\nbin demo: bin/demo.js\n"));
+        assert!(npm.contains("vitest ^1.0.0 (dev)"));
+        assert!(!npm.contains("Workspace Members"));
+    }
+
+    #[test]
+    fn test_go_and_python_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("go.mod"),
+            "module example.com/demo\n\ngo 1.22\n\nrequire (\n\tgithub.com/spf13/cobra v1.8.0\n\tgolang.org/x/sys v0.1.0 // indirect\n)\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("cmd/demo")).unwrap();
+        fs::write(root.join("cmd/demo/main.go"), "package main\n").unwrap();
+        fs::write(
+            root.join("pyproject.toml"),
+            "[project]\nname = \"demo\"\ndependencies = [\"httpx>=0.27\"]\n\n[project.scripts]\ndemo = \"demo.cli:main\"\n",
+        )
+        .unwrap();
+
+        let reports = detect_ecosystems(root);
+        let go = &reports[0];
+        assert_eq!(go.name, "Go");
+        assert_eq!(
+            go.sections,
+            vec![
+                (
+                    "Module".to_string(),
+                    vec!["module example.com/demo".to_string(), "go 1.22".to_string()]
+                ),
+                (
+                    "Entry Points".to_string(),
+                    vec!["cmd/demo/main.go".to_string()]
+                ),
+                (
+                    "Dependency Highlights".to_string(),
+                    vec!["github.com/spf13/cobra v1.8.0".to_string()]
+                ),
+            ]
+        );
+
+        let python = reports[1].format();
+        assert!(python.contains("--- Project ---\nname demo\n"));
+        assert!(python.contains("--- Entry Points ---\ndemo: demo.cli:main\n"));
+        assert!(python.contains("--- Dependency Highlights ---\nhttpx>=0.27\n"));
+    }
+}
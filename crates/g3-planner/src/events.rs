@@ -0,0 +1,248 @@
+//! Progress events of discovery.
+//!
+//! Discovery reports its progress as typed events, so a UI can render real
+//! progress (a spinner per LLM call, a count of commands, a link to the
+//! report) instead of parsing status lines. `status_messages` adapts a plain
+//! string callback: each event is passed on as the line it displays as.
+
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Receives discovery's progress events.
+pub type StatusCallback = Box<dyn Fn(&PlannerEvent) + Send + Sync>;
+
+/// Something discovery did or ran into.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PlannerEvent {
+    /// LLM discovery started
+    ExplorationStarted,
+    /// A git URL is being shallow-cloned
+    CloneStarted {
+        url: String,
+    },
+    Cloned {
+        path: String,
+    },
+    /// An earlier clone was moved to the remote HEAD
+    CloneUpdated {
+        path: String,
+    },
+    /// The codebase report was reused because no file changed
+    ReportReused,
+    ReportWritten {
+        path: PathBuf,
+    },
+    /// Discovery plan `plan` of `plans` was requested from the LLM
+    LlmRequested {
+        plan: usize,
+        plans: usize,
+    },
+    /// The plan wasn't JSON; its commands were extracted from text
+    PlanNotJson {
+        commands: usize,
+    },
+    /// One of several candidate plans failed
+    PlanFailed {
+        plan: usize,
+        error: String,
+    },
+    PlansMerged {
+        proposed: usize,
+        plans: usize,
+        merged: usize,
+    },
+    /// A command failed the read-only allowlist and won't run
+    CommandRejected {
+        command: String,
+        reason: String,
+    },
+    /// Requirements without a planned command got a keyword search
+    RequirementSearchesAdded {
+        count: usize,
+    },
+    /// The final discovery commands, from an LLM plan or from heuristics
+    CommandsExtracted {
+        count: usize,
+        heuristic: bool,
+    },
+    ProfileUpdated {
+        path: String,
+    },
+    SymbolIndexUpdated {
+        symbols: usize,
+        files: usize,
+        parsed: usize,
+    },
+    WorkspaceDetected {
+        packages: usize,
+    },
+    /// Discovery was scoped to one package of a monorepo
+    TargetSelected {
+        name: String,
+        dir: String,
+    },
+    /// A refresh found no snapshot and runs full discovery
+    NoSnapshot,
+    /// A refresh found nothing changed since the last discovery
+    NoChanges,
+    /// A refresh looks at only these changes
+    ChangesDetected {
+        added: usize,
+        modified: usize,
+        removed: usize,
+    },
+    /// A refresh found too many changes and runs full discovery
+    TooManyChanges {
+        changed: usize,
+    },
+    /// Something failed without failing discovery
+    Warning {
+        message: String,
+    },
+}
+
+impl PlannerEvent {
+    pub(crate) fn warning(message: impl Into<String>) -> Self {
+        Self::Warning {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for PlannerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExplorationStarted => write!(f, "🔍 Starting code discovery..."),
+            Self::CloneStarted { url } => write!(f, "📥 Cloning {} (shallow)...", url),
+            Self::Cloned { path } => write!(f, "📁 Cloned into {}", path),
+            Self::CloneUpdated { path } => write!(f, "🔄 Updated existing clone at {}", path),
+            Self::ReportReused => {
+                write!(f, "♻️ Reusing cached codebase report (no files changed)")
+            }
+            Self::ReportWritten { path } => {
+                write!(f, "📝 Wrote codebase report to {}", path.display())
+            }
+            Self::LlmRequested { plans: 1, .. } => {
+                write!(f, "🤖 Calling LLM for discovery commands...")
+            }
+            Self::LlmRequested { plan, plans } => {
+                write!(f, "🤖 Calling LLM for discovery plan {}/{}...", plan, plans)
+            }
+            Self::PlanNotJson { commands } => write!(
+                f,
+                "⚠️ Discovery plan was not valid JSON; extracted {} commands from text",
+                commands
+            ),
+            Self::PlanFailed { plan, error } => {
+                write!(f, "⚠️ Discovery plan {} failed: {}", plan, error)
+            }
+            Self::PlansMerged {
+                proposed,
+                plans,
+                merged,
+            } => write!(
+                f,
+                "🔀 Merged {} commands from {} plans into {}",
+                proposed, plans, merged
+            ),
+            Self::CommandRejected { command, reason } => {
+                write!(f, "🚫 Rejected discovery command `{}`: {}", command, reason)
+            }
+            Self::RequirementSearchesAdded { count } => write!(
+                f,
+                "🎯 Added keyword searches for {} requirements the plan didn't cover",
+                count
+            ),
+            Self::CommandsExtracted {
+                count,
+                heuristic: false,
+            } => write!(f, "📋 Extracted {} discovery commands", count),
+            Self::CommandsExtracted {
+                count,
+                heuristic: true,
+            } => write!(f, "📋 Generated {} heuristic discovery commands", count),
+            Self::ProfileUpdated { path } => write!(f, "🧭 Updated project profile ({})", path),
+            Self::SymbolIndexUpdated {
+                symbols,
+                files,
+                parsed,
+            } => write!(
+                f,
+                "🗂️ Symbol index: {} symbols in {} files ({} parsed)",
+                symbols, files, parsed
+            ),
+            Self::WorkspaceDetected { packages } => {
+                write!(f, "📦 Workspace with {} packages", packages)
+            }
+            Self::TargetSelected { name, dir } => {
+                write!(f, "🎯 Scoping discovery to {} ({})", name, dir)
+            }
+            Self::NoSnapshot => write!(f, "🔍 No discovery snapshot yet, running full discovery"),
+            Self::NoChanges => write!(f, "✅ No files changed since the last discovery"),
+            Self::ChangesDetected {
+                added,
+                modified,
+                removed,
+            } => write!(
+                f,
+                "🔄 {} added, {} modified, {} removed since the last discovery",
+                added, modified, removed
+            ),
+            Self::TooManyChanges { changed } => write!(
+                f,
+                "🔍 {} files changed since the last discovery, running full discovery",
+                changed
+            ),
+            Self::Warning { message } => write!(f, "⚠️ {}", message),
+        }
+    }
+}
+
+/// A `StatusCallback` for a consumer of status lines: each event is passed
+/// on as it displays.
+pub fn status_messages(callback: impl Fn(&str) + Send + Sync + 'static) -> StatusCallback {
+    Box::new(move |event: &PlannerEvent| callback(&event.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_events_as_status_lines() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        let callback = status_messages(move |line| sink.lock().unwrap().push(line.to_string()));
+
+        callback(&PlannerEvent::LlmRequested { plan: 1, plans: 1 });
+        callback(&PlannerEvent::LlmRequested { plan: 2, plans: 3 });
+        callback(&PlannerEvent::CommandsExtracted {
+            count: 7,
+            heuristic: false,
+        });
+        callback(&PlannerEvent::warning(
+            "Could not save discovery snapshot: denied",
+        ));
+        assert_eq!(
+            *lines.lock().unwrap(),
+            [
+                "🤖 Calling LLM for discovery commands...",
+                "🤖 Calling LLM for discovery plan 2/3...",
+                "📋 Extracted 7 discovery commands",
+                "⚠️ Could not save discovery snapshot: denied",
+            ]
+        );
+
+        let json = serde_json::to_value(PlannerEvent::ReportWritten {
+            path: PathBuf::from("/tmp/report.log"),
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"event": "report_written", "path": "/tmp/report.log"})
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Offline discovery: discovery commands chosen by heuristics instead of an LLM.
+//!
+//! Looks at the codebase on disk and proposes commands that are useful for
+//! almost any project: the top-level layout, the README, build manifests,
+//! likely entry points and recent git history. Used when discovery is
+//! configured with `mode = "heuristic"`, or when LLM discovery fails.
+
+use std::path::Path;
+
+/// Build manifests read in full when small enough.
+const MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "CMakeLists.txt",
+    "Makefile",
+    "Gemfile",
+    "mix.exs",
+    "stack.yaml",
+];
+
+const READMES: &[&str] = &["README.md", "README.rst", "README.txt", "README"];
+
+/// Entry point files, relative to the root or to a workspace member.
+const ENTRY_POINTS: &[&str] = &[
+    "src/main.rs",
+    "src/lib.rs",
+    "main.go",
+    "main.py",
+    "__main__.py",
+    "app.py",
+    "manage.py",
+    "src/index.ts",
+    "src/index.js",
+    "src/main.ts",
+    "index.ts",
+    "index.js",
+    "src/main/java",
+    "app/Main.hs",
+];
+
+/// Directories holding workspace members (crates, packages, commands, ...).
+pub(crate) const MEMBER_DIRS: &[&str] = &["crates", "packages", "apps", "libs", "cmd", "services"];
+
+/// Directories never worth listing.
+pub(crate) const SKIPPED_DIRS: &[&str] =
+    &["target", "node_modules", "dist", "build", "vendor", "venv"];
+
+/// Manifests larger than this are only previewed.
+const MAX_MANIFEST_BYTES: u64 = 16 * 1024;
+
+/// Most entry points read, so large workspaces don't flood the context.
+const MAX_ENTRY_POINTS: usize = 8;
+
+/// Lines read from each entry point.
+const ENTRY_POINT_LINES: usize = 120;
+
+/// Shell commands to discover the codebase at `codebase_path`, in the order
+/// they should run. Paths are relative to `codebase_path`.
+pub fn heuristic_discovery_commands(codebase_path: &Path) -> Vec<String> {
+    let mut commands = vec!["ls -la".to_string(), tree_command()];
+
+    if let Some(readme) = READMES.iter().find(|r| codebase_path.join(r).is_file()) {
+        commands.push(format!("head -n 80 {}", shell_quote(readme)));
+    }
+
+    for manifest in MANIFESTS {
+        let Ok(metadata) = codebase_path.join(manifest).metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        if metadata.len() <= MAX_MANIFEST_BYTES {
+            commands.push(format!("cat {}", shell_quote(manifest)));
+        } else {
+            commands.push(format!("head -n 100 {}", shell_quote(manifest)));
+        }
+    }
+
+    for entry_point in entry_points(codebase_path) {
+        if codebase_path.join(&entry_point).is_dir() {
+            commands.push(format!(
+                "find {} -name '*.java' | head -n 50",
+                shell_quote(&entry_point)
+            ));
+        } else {
+            commands.push(format!(
+                "head -n {} {}",
+                ENTRY_POINT_LINES,
+                shell_quote(&entry_point)
+            ));
+        }
+    }
+
+    if codebase_path.join(".git").exists() {
+        commands.push("git log --oneline -n 15".to_string());
+        commands.push("git log -n 5 --stat --format='--- %h %ar: %s'".to_string());
+        commands.push("git status --short".to_string());
+    }
+
+    commands
+}
+
+/// A listing of the top two directory levels, without hidden and build dirs.
+fn tree_command() -> String {
+    let mut command = "find . -maxdepth 2 -not -path '*/.*'".to_string();
+    for dir in SKIPPED_DIRS {
+        command.push_str(&format!(" -not -path './{}*'", dir));
+    }
+    command.push_str(" | sort | head -n 200");
+    command
+}
+
+/// Entry points at the root and in workspace members, at most `MAX_ENTRY_POINTS`.
+pub(crate) fn entry_points(codebase_path: &Path) -> Vec<String> {
+    let mut roots = vec![String::new()];
+    for member_dir in MEMBER_DIRS {
+        let Ok(entries) = std::fs::read_dir(codebase_path.join(member_dir)) else {
+            continue;
+        };
+        let mut members: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| {
+                e.file_name()
+                    .to_str()
+                    .map(|n| format!("{}/{}/", member_dir, n))
+            })
+            .collect();
+        members.sort();
+        roots.extend(members);
+    }
+
+    let mut found = Vec::new();
+    for root in roots {
+        for entry_point in ENTRY_POINTS {
+            let path = format!("{}{}", root, entry_point);
+            if codebase_path.join(&path).exists() {
+                found.push(path);
+                if found.len() == MAX_ENTRY_POINTS {
+                    return found;
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Quote `s` for the shell if it contains anything but safe characters.
+pub(crate) fn shell_quote(s: &str) -> String {
+    if s.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/._-".contains(c))
+    {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_heuristic_discovery_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Cargo.toml"), "[workspace]\n").unwrap();
+        fs::write(root.join("README.md"), "# Demo\n").unwrap();
+        fs::create_dir_all(root.join("crates/core/src")).unwrap();
+        fs::write(root.join("crates/core/src/lib.rs"), "pub fn run() {}\n").unwrap();
+        fs::create_dir_all(root.join("crates/my cli/src")).unwrap();
+        fs::write(root.join("crates/my cli/src/main.rs"), "fn main() {}\n").unwrap();
+
+        let commands = heuristic_discovery_commands(root);
+        assert_eq!(commands[0], "ls -la");
+        assert!(commands[1].starts_with("find . -maxdepth 2"));
+        assert_eq!(
+            commands[2..],
+            [
+                "head -n 80 README.md",
+                "cat Cargo.toml",
+                "head -n 120 crates/core/src/lib.rs",
+                "head -n 120 'crates/my cli/src/main.rs'",
+            ]
+        );
+        // Not a git repository
+        assert!(!commands.iter().any(|c| c.starts_with("git ")));
+    }
+}
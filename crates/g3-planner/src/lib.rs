@@ -8,42 +8,76 @@
 //! - Fast-discovery functionality for codebase exploration
 
 mod code_explore;
+pub mod dep_graph;
+pub mod discovery_plan;
+pub mod discovery_snapshot;
+pub mod ecosystems;
+pub mod events;
 pub mod git;
+pub mod heuristics;
 pub mod history;
 pub mod llm;
 pub mod planner;
+pub mod profile;
 pub mod prompts;
+pub mod remote;
+pub mod report_cache;
 pub mod state;
-
-pub use code_explore::explore_codebase;
+pub mod workspace;
+
+pub use code_explore::{explore_codebase, explore_codebase_with, ExploreOptions};
+pub use discovery_plan::{
+    parse_discovery_plan, requirement_search_command, DiscoveryPlan, PlanSource, PlannedCommand,
+};
+pub use discovery_snapshot::{DiscoverySnapshot, WorkspaceChanges};
+pub use events::{status_messages, PlannerEvent, StatusCallback};
+pub use heuristics::heuristic_discovery_commands;
 pub use planner::{expand_codepath, PlannerConfig, PlannerResult};
+pub use profile::{refresh_profile, ProjectProfile};
+pub use remote::resolve_codebase;
+pub use report_cache::explore_codebase_cached;
 pub use state::{PlannerState, RecoveryInfo};
+pub use workspace::{detect_workspace_packages, select_target, WorkspacePackage};
 pub use planner::run_planning_mode;
 
 use anyhow::Result;
 use chrono::Local;
+use g3_config::DiscoveryMode;
+use g3_core::code_search::SymbolIndex;
+use g3_core::discovery_allowlist::check_read_only;
+use g3_core::requirements::{format_requirements, parse_requirements};
+use g3_core::ui_writer::UiWriter;
+use g3_core::{Agent, DiscoveryOptions};
 use g3_providers::{CompletionRequest, LLMProvider, Message, MessageRole};
-use prompts::{DISCOVERY_REQUIREMENTS_PROMPT, DISCOVERY_SYSTEM_PROMPT};
+use prompts::{
+    DISCOVERY_CANDIDATE_NOTE, DISCOVERY_PER_REQUIREMENT_NOTE, DISCOVERY_REQUIREMENTS_PROMPT,
+    DISCOVERY_SYSTEM_PROMPT,
+};
+use std::collections::HashSet;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 
-/// Type alias for a status callback function
-pub type StatusCallback = Box<dyn Fn(&str) + Send + Sync>;
-
 /// Generates initial discovery messages for fast codebase exploration.
 ///
 /// This function:
-/// 1. Runs explore_codebase to get a codebase report
-/// 2. Sends the report to the LLM with DISCOVERY_SYSTEM_PROMPT
-/// 3. Extracts shell commands from the LLM response
+/// 1. Runs explore_codebase to get a codebase report, reusing the cached one
+///    when the tree hasn't changed (see `report_cache`)
+/// 2. Sends the report to the LLM with DISCOVERY_SYSTEM_PROMPT, once per candidate plan
+/// 3. Parses the JSON plan in each LLM response (see `discovery_plan`), falling
+///    back to extracting commands from text, and merges and ranks the commands
+///    when there are several candidate plans (see `rank_candidate_commands`)
 /// 4. Returns Assistant messages with tool calls for each command
 ///
 /// # Arguments
 ///
-/// * `codebase_path` - The path to the codebase to explore
-/// * `provider` - An LLM provider to query for exploration commands
+/// * `codebase_path` - The path to the codebase to explore, or a git URL to
+///   shallow-clone and explore (see `remote::resolve_codebase`); the commands
+///   then run in the clone, so pass its path as `fast_start_path`
 /// * `requirements_text` - Optional requirements text to include in the discovery prompt
-/// * `status_callback` - Optional callback for status updates
+/// * `candidate_plans` - Number of candidate plans to request (1 for a single plan)
+/// * `explore_options` - Ignore patterns and limits of the codebase report
+/// * `provider` - An LLM provider to query for exploration commands
+/// * `status_callback` - Optional callback for progress events (see `events`)
 ///
 /// # Returns
 ///
@@ -51,25 +85,36 @@ pub type StatusCallback = Box<dyn Fn(&str) + Send + Sync>;
 pub async fn get_initial_discovery_messages(
     codebase_path: &str,
     requirements_text: Option<&str>,
+    candidate_plans: usize,
+    explore_options: &ExploreOptions,
     provider: &dyn LLMProvider,
     status_callback: Option<&StatusCallback>,
 ) -> Result<Vec<Message>> {
-    // Helper to call status callback if provided
-    let status = |msg: &str| {
+    let emit = |event: PlannerEvent| {
         if let Some(cb) = status_callback {
-            cb(msg);
+            cb(&event);
         }
     };
 
-    status("🔍 Starting code discovery...");
+    emit(PlannerEvent::ExplorationStarted);
+
+    // A git URL is explored in a shallow clone
+    let codebase_path = &resolve_codebase(codebase_path, status_callback)?;
 
     // Step 1: Run explore_codebase to get the codebase report
-    let codebase_report = explore_codebase(codebase_path);
+    let (codebase_report, cached) = explore_codebase_cached(codebase_path, explore_options);
+    if cached {
+        emit(PlannerEvent::ReportReused);
+    }
 
     // Write the codebase report to discovery directory
-    write_code_report(&codebase_report)?;
+    let report_path = write_code_report(&codebase_report)?;
+    emit(PlannerEvent::ReportWritten { path: report_path });
 
     // Step 2: Build the prompt with the codebase report appended
+    let structured = requirements_text
+        .map(|text| parse_requirements(text, None))
+        .unwrap_or_default();
     let user_prompt = if let Some(requirements) = requirements_text {
         format!(
             "{}\n\n
@@ -84,36 +129,133 @@ pub async fn get_initial_discovery_messages(
         )
     };
 
-    // Step 3: Create messages for the LLM
-    let messages = vec![
-        Message::new(MessageRole::System, DISCOVERY_SYSTEM_PROMPT.to_string()),
-        Message::new(MessageRole::User, user_prompt),
-    ];
-
-    // Step 4: Send to LLM
-    let request = CompletionRequest {
-        messages,
-        max_tokens: Some(provider.max_tokens()),
-        temperature: Some(provider.temperature()),
-        stream: false,
-        tools: None,
-        disable_thinking: false,
+    // Structured requirements get commands of their own
+    let user_prompt = if structured.is_empty() {
+        user_prompt
+    } else {
+        let note = DISCOVERY_PER_REQUIREMENT_NOTE
+            .replace("{requirements}", &format_requirements(&structured));
+        format!("{}\n\n{}", user_prompt, note)
     };
 
-    status("🤖 Calling LLM for discovery commands...");
+    // Steps 3-5: Ask the LLM for each candidate plan and extract its commands
+    let candidate_plans = candidate_plans.max(1);
+    let mut candidates = Vec::with_capacity(candidate_plans);
+    let mut planned: Vec<PlannedCommand> = Vec::new();
+    for index in 1..=candidate_plans {
+        emit(PlannerEvent::LlmRequested {
+            plan: index,
+            plans: candidate_plans,
+        });
+        let prompt = if candidate_plans == 1 {
+            user_prompt.clone()
+        } else {
+            let note = DISCOVERY_CANDIDATE_NOTE
+                .replace("{index}", &index.to_string())
+                .replace("{count}", &candidate_plans.to_string());
+            format!("{}\n\n{}", user_prompt, note)
+        };
+
+        let request = CompletionRequest {
+            messages: vec![
+                Message::new(MessageRole::System, DISCOVERY_SYSTEM_PROMPT.to_string()),
+                Message::new(MessageRole::User, prompt),
+            ],
+            max_tokens: Some(provider.max_tokens()),
+            temperature: Some(provider.temperature()),
+            stream: false,
+            tools: None,
+            disable_thinking: false,
+            cancellation_token: None,
+        };
+
+        match provider.complete(request).await {
+            Ok(response) => {
+                let (plan, source) = parse_discovery_plan(&response.content);
+                if source == PlanSource::TextFallback {
+                    emit(PlannerEvent::PlanNotJson {
+                        commands: plan.commands.len(),
+                    });
+                }
+                candidates.push(plan.command_lines());
+                planned.extend(plan.commands);
+            }
+            // A single failed candidate doesn't fail discovery if others succeed
+            Err(e) if candidate_plans > 1 => emit(PlannerEvent::PlanFailed {
+                plan: index,
+                error: e.to_string(),
+            }),
+            Err(e) => return Err(e),
+        }
+    }
+    if candidates.is_empty() {
+        anyhow::bail!("All {} discovery plans failed", candidate_plans);
+    }
+
+    let shell_commands = if candidates.len() == 1 {
+        candidates.remove(0)
+    } else {
+        let proposed: usize = candidates.iter().map(Vec::len).sum();
+        let limit = candidates.iter().map(Vec::len).max().unwrap_or(0);
+        let merged = rank_candidate_commands(&candidates, limit);
+        emit(PlannerEvent::PlansMerged {
+            proposed,
+            plans: candidates.len(),
+            merged: merged.len(),
+        });
+        merged
+    };
 
-    let response = provider.complete(request).await?;
+    // Only read-only commands may run unattended; drop anything else up front
+    let (mut shell_commands, rejected): (Vec<String>, Vec<String>) = shell_commands
+        .into_iter()
+        .partition(|cmd| check_read_only(cmd).is_ok());
+    for cmd in &rejected {
+        if let Err(reason) = check_read_only(cmd) {
+            emit(PlannerEvent::CommandRejected {
+                command: cmd.clone(),
+                reason,
+            });
+        }
+    }
 
-    // Step 5: Extract shell commands from the response
-    let shell_commands = extract_shell_commands(&response.content);
+    // Requirements the plan left without a command get a keyword search
+    let mut searches = 0;
+    for requirement in &structured {
+        let covered = planned.iter().any(|p| {
+            p.requirement.as_deref() == Some(requirement.id.as_str())
+                && shell_commands.iter().any(|c| c == p.command.trim())
+        });
+        if covered {
+            continue;
+        }
+        if let Some(command) = requirement_search_command(requirement) {
+            planned.push(PlannedCommand {
+                command: command.clone(),
+                rationale: Some(format!(
+                    "Keyword search for {}: {}",
+                    requirement.id, requirement.title
+                )),
+                expected_signal: None,
+                requirement: Some(requirement.id.clone()),
+            });
+            shell_commands.push(command);
+            searches += 1;
+        }
+    }
+    if searches > 0 {
+        emit(PlannerEvent::RequirementSearchesAdded { count: searches });
+    }
 
-    status(&format!(
-        "📋 Extracted {} discovery commands",
-        shell_commands.len()
-    ));
+    emit(PlannerEvent::CommandsExtracted {
+        count: shell_commands.len(),
+        heuristic: false,
+    });
 
-    // Write the discovery commands to discovery directory
-    write_discovery_commands(&shell_commands)?;
+    // Write the discovery commands, with why each was planned, to discovery directory
+    write_discovery_commands(&shell_commands, &planned)?;
+    save_project_profile(codebase_path, &emit);
+    save_snapshot(codebase_path, &emit);
 
     // Step 6: Format as tool messages
     let tool_messages = shell_commands
@@ -124,6 +266,266 @@ pub async fn get_initial_discovery_messages(
     Ok(tool_messages)
 }
 
+/// Generates discovery messages from heuristics alone, without calling an LLM.
+///
+/// Reads manifests, lists the top-level layout, previews likely entry points
+/// and shows recent git changes (see `heuristics`). Works offline and avoids
+/// the LLM round trip on cold start. Like `get_initial_discovery_messages`,
+/// accepts a git URL.
+pub fn get_heuristic_discovery_messages(
+    codebase_path: &str,
+    status_callback: Option<&StatusCallback>,
+) -> Result<Vec<Message>> {
+    let emit = |event: PlannerEvent| {
+        if let Some(cb) = status_callback {
+            cb(&event);
+        }
+    };
+
+    let codebase_path = &resolve_codebase(codebase_path, status_callback)?;
+    let shell_commands = heuristic_discovery_commands(std::path::Path::new(codebase_path));
+
+    emit(PlannerEvent::CommandsExtracted {
+        count: shell_commands.len(),
+        heuristic: true,
+    });
+
+    write_discovery_commands(&shell_commands, &[])?;
+    save_project_profile(codebase_path, &emit);
+    save_snapshot(codebase_path, &emit);
+
+    Ok(shell_commands
+        .into_iter()
+        .map(|cmd| create_tool_message("shell", &cmd))
+        .collect())
+}
+
+/// Refresh the project profile (`.g3/profile.json`) after discovery.
+///
+/// Failing to save it doesn't fail discovery.
+fn save_project_profile(codebase_path: &str, emit: &dyn Fn(PlannerEvent)) {
+    match refresh_profile(std::path::Path::new(codebase_path)) {
+        Ok((_, true)) => emit(PlannerEvent::ProfileUpdated {
+            path: profile::PROFILE_PATH.to_string(),
+        }),
+        Ok((_, false)) => {}
+        Err(e) => emit(PlannerEvent::warning(format!(
+            "Could not save project profile: {}",
+            e
+        ))),
+    }
+}
+
+/// Remember the files discovery saw, for `refresh_discovery`.
+///
+/// Failing to save it only makes the next refresh a full one.
+fn save_snapshot(codebase_path: &str, emit: &dyn Fn(PlannerEvent)) {
+    let root = std::path::Path::new(codebase_path);
+    let snapshot = DiscoverySnapshot::capture(root);
+    if let Err(e) = discovery_snapshot::save_discovery_snapshot(root, &snapshot) {
+        emit(PlannerEvent::warning(format!(
+            "Could not save discovery snapshot: {}",
+            e
+        )));
+    }
+}
+
+/// Builds or updates the symbol index of `codebase_path` for the
+/// `symbol_lookup` tool (see `g3_core::code_search::SymbolIndex`).
+///
+/// Only files changed since the last build are parsed. Failing to build it
+/// doesn't fail discovery.
+pub fn update_symbol_index(codebase_path: &str, status_callback: Option<&StatusCallback>) {
+    let emit = |event: PlannerEvent| {
+        if let Some(cb) = status_callback {
+            cb(&event);
+        }
+    };
+
+    let root = std::path::Path::new(codebase_path);
+    let previous = SymbolIndex::load(root);
+    let result = SymbolIndex::build(root, previous.as_ref())
+        .and_then(|(index, parsed)| index.save(root).map(|_| (index, parsed)));
+    match result {
+        Ok((index, parsed)) => emit(PlannerEvent::SymbolIndexUpdated {
+            symbols: index.symbol_count(),
+            files: index.files.len(),
+            parsed,
+        }),
+        Err(e) => emit(PlannerEvent::warning(format!(
+            "Could not build symbol index: {}",
+            e
+        ))),
+    }
+}
+
+/// The directory discovery should explore in `codebase_path`: the monorepo
+/// package picked by `configured_target` (`[discovery] target`) or the
+/// requirements (see `workspace::select_target`), otherwise the codebase
+/// itself. The agent's tools should then work in the same directory.
+pub fn discovery_root(
+    codebase_path: &str,
+    requirements_text: Option<&str>,
+    configured_target: Option<&str>,
+    status_callback: Option<&StatusCallback>,
+) -> String {
+    let emit = |event: PlannerEvent| {
+        if let Some(cb) = status_callback {
+            cb(&event);
+        }
+    };
+
+    let root = std::path::Path::new(codebase_path);
+    let packages = detect_workspace_packages(root);
+    if !packages.is_empty() {
+        emit(PlannerEvent::WorkspaceDetected {
+            packages: packages.len(),
+        });
+    }
+    match select_target(root, &packages, requirements_text, configured_target) {
+        Ok(Some(target)) => {
+            let path = root.join(&target.dir).to_string_lossy().to_string();
+            emit(PlannerEvent::TargetSelected {
+                name: target.name,
+                dir: target.dir,
+            });
+            path
+        }
+        Ok(None) => codebase_path.to_string(),
+        Err(e) => {
+            emit(PlannerEvent::warning(format!(
+                "Discovery {}, exploring the whole codebase",
+                e
+            )));
+            codebase_path.to_string()
+        }
+    }
+}
+
+/// What `refresh_discovery` did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshOutcome {
+    /// No file changed since the last discovery; nothing was injected
+    Unchanged,
+    /// Follow-up commands for the changed files were run and injected
+    Incremental { changed_files: usize, tokens: u32 },
+    /// There was no snapshot, or too much changed: full discovery ran again
+    Full { tokens: u32 },
+}
+
+/// Brings the agent's discovery up to date for a follow-up task.
+///
+/// Diffs `codebase_path` against the snapshot saved by the last discovery
+/// and injects a report of only what changed since: `git status`, the diff
+/// or head of each changed file and listings of directories that lost
+/// files (see `discovery_snapshot::follow_up_commands`). Falls back to a full
+/// `rediscover` without a snapshot or when more than
+/// `MAX_INCREMENTAL_CHANGES` files changed.
+pub async fn refresh_discovery<W: UiWriter>(
+    agent: &mut Agent<W>,
+    codebase_path: &str,
+    requirements_text: Option<&str>,
+    status_callback: Option<&StatusCallback>,
+) -> Result<RefreshOutcome> {
+    let emit = |event: PlannerEvent| {
+        if let Some(cb) = status_callback {
+            cb(&event);
+        }
+    };
+
+    let root = std::path::Path::new(codebase_path);
+    let current = DiscoverySnapshot::capture(root);
+    let changes = match discovery_snapshot::load_discovery_snapshot(root) {
+        Some(previous) => previous.diff(&current),
+        None => {
+            emit(PlannerEvent::NoSnapshot);
+            let tokens =
+                rediscover(agent, codebase_path, requirements_text, status_callback).await?;
+            return Ok(RefreshOutcome::Full { tokens });
+        }
+    };
+
+    if changes.is_empty() {
+        emit(PlannerEvent::NoChanges);
+        return Ok(RefreshOutcome::Unchanged);
+    }
+    if changes.len() > discovery_snapshot::MAX_INCREMENTAL_CHANGES {
+        emit(PlannerEvent::TooManyChanges {
+            changed: changes.len(),
+        });
+        let tokens = rediscover(agent, codebase_path, requirements_text, status_callback).await?;
+        return Ok(RefreshOutcome::Full { tokens });
+    }
+
+    emit(PlannerEvent::ChangesDetected {
+        added: changes.added.len(),
+        modified: changes.modified.len(),
+        removed: changes.removed.len(),
+    });
+    let messages: Vec<Message> = discovery_snapshot::follow_up_commands(root, &changes)
+        .iter()
+        .map(|cmd| create_tool_message("shell", cmd))
+        .collect();
+    let tokens = agent
+        .rediscover(DiscoveryOptions {
+            messages: &messages,
+            fast_start_path: Some(codebase_path),
+            cached_results: None,
+        })
+        .await?;
+    save_snapshot(codebase_path, &emit);
+    if agent.get_config().discovery.symbol_index {
+        update_symbol_index(codebase_path, status_callback);
+    }
+
+    Ok(RefreshOutcome::Incremental {
+        changed_files: changes.len(),
+        tokens,
+    })
+}
+
+/// Re-runs discovery against the current state of `codebase_path` and injects
+/// an updated report into the agent's context (see `Agent::rediscover`).
+///
+/// Useful mid-session, after large refactors or when resuming a session
+/// against a repository that has changed since. Returns the estimated tokens
+/// the report added.
+pub async fn rediscover<W: UiWriter>(
+    agent: &mut Agent<W>,
+    codebase_path: &str,
+    requirements_text: Option<&str>,
+    status_callback: Option<&StatusCallback>,
+) -> Result<u32> {
+    let discovery = &agent.get_config().discovery;
+    if discovery.symbol_index {
+        update_symbol_index(codebase_path, status_callback);
+    }
+    let messages = match discovery.mode {
+        DiscoveryMode::Heuristic => {
+            get_heuristic_discovery_messages(codebase_path, status_callback)?
+        }
+        DiscoveryMode::Llm => {
+            get_initial_discovery_messages(
+                codebase_path,
+                requirements_text,
+                discovery.candidate_plans,
+                &ExploreOptions::from(discovery),
+                agent.get_provider()?,
+                status_callback,
+            )
+            .await?
+        }
+    };
+
+    agent
+        .rediscover(DiscoveryOptions {
+            messages: &messages,
+            fast_start_path: Some(codebase_path),
+            cached_results: None,
+        })
+        .await
+}
+
 /// Creates an Assistant message with a tool call in g3's JSON format.
 pub fn create_tool_message(tool: &str, command: &str) -> Message {
     let tool_call = serde_json::json!({
@@ -175,6 +577,69 @@ pub fn extract_shell_commands(response: &str) -> Vec<String> {
     commands
 }
 
+/// Merge the commands of several candidate discovery plans, best first.
+///
+/// Commands are deduplicated (ignoring whitespace differences) and picked
+/// greedily by expected information gain: how many plans proposed the command
+/// (agreement), scaled by how much of it (paths, patterns, ...) isn't covered by
+/// the commands already picked. At most `limit` commands are returned, so
+/// several plans don't add proportionally more discovery output.
+pub fn rank_candidate_commands(candidates: &[Vec<String>], limit: usize) -> Vec<String> {
+    // Unique commands in first-proposed order, with the number of plans proposing each
+    let mut commands: Vec<(String, usize)> = Vec::new();
+    for plan in candidates {
+        let mut seen_in_plan = HashSet::new();
+        for command in plan {
+            let normalized = command.split_whitespace().collect::<Vec<_>>().join(" ");
+            if normalized.is_empty() || !seen_in_plan.insert(normalized.clone()) {
+                continue;
+            }
+            match commands.iter_mut().find(|(c, _)| *c == normalized) {
+                Some((_, votes)) => *votes += 1,
+                None => commands.push((normalized, 1)),
+            }
+        }
+    }
+
+    let plans = candidates.len().max(1) as f32;
+    let mut covered: HashSet<String> = HashSet::new();
+    let mut ranked = Vec::new();
+    while ranked.len() < limit && !commands.is_empty() {
+        let gain = |(command, votes): &(String, usize)| {
+            let terms = command_terms(command);
+            let novelty = if terms.is_empty() {
+                1.0
+            } else {
+                terms.iter().filter(|t| !covered.contains(*t)).count() as f32 / terms.len() as f32
+            };
+            // Well-supported commands keep some value even when fully covered
+            (*votes as f32 / plans) * (0.25 + 0.75 * novelty)
+        };
+        let mut best = 0;
+        for i in 1..commands.len() {
+            if gain(&commands[i]) > gain(&commands[best]) {
+                best = i;
+            }
+        }
+        let (command, _) = commands.remove(best);
+        covered.extend(command_terms(&command));
+        ranked.push(command);
+    }
+    ranked
+}
+
+/// The parts of a command that say what it looks at: its arguments other than
+/// the program name and flags.
+fn command_terms(command: &str) -> Vec<String> {
+    command
+        .split_whitespace()
+        .skip(1)
+        .filter(|t| !t.starts_with('-') && !matches!(*t, "|" | "&&" | ";"))
+        .map(|t| t.trim_matches(|c| c == '\'' || c == '"').to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
 /// Extract the summary section from the LLM response
 pub fn extract_summary(response: &str) -> Option<String> {
     let section_marker = "{{SUMMARY BASED ON INITIAL INFO}}";
@@ -194,8 +659,8 @@ pub fn extract_summary(response: &str) -> Option<String> {
     }
 }
 
-/// Write the codebase report to discovery directory
-fn write_code_report(report: &str) -> Result<()> {
+/// Write the codebase report to discovery directory, returning its path
+fn write_code_report(report: &str) -> Result<std::path::PathBuf> {
     // Get discovery directory from workspace path or current dir
     let discovery_dir = if let Ok(workspace_path) = std::env::var("G3_WORKSPACE_PATH") {
         std::path::PathBuf::from(workspace_path).join(".g3").join("discovery")
@@ -220,11 +685,12 @@ fn write_code_report(report: &str) -> Result<()> {
     file.write_all(report.as_bytes())?;
     file.flush()?;
 
-    Ok(())
+    Ok(filename)
 }
 
-/// Write the discovery commands to discovery directory
-fn write_discovery_commands(commands: &[String]) -> Result<()> {
+/// Write the discovery commands to discovery directory, each preceded by the
+/// rationale and expected signal it was planned with, if any
+fn write_discovery_commands(commands: &[String], planned: &[PlannedCommand]) -> Result<()> {
     // Get discovery directory from workspace path or current dir
     let discovery_dir = if let Ok(workspace_path) = std::env::var("G3_WORKSPACE_PATH") {
         std::path::PathBuf::from(workspace_path).join(".g3").join("discovery")
@@ -252,6 +718,20 @@ fn write_discovery_commands(commands: &[String]) -> Result<()> {
 
     // Write each command on a separate line
     for cmd in commands {
+        let normalized = cmd.split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some(plan) = planned.iter().find(|p| {
+            p.command.split_whitespace().collect::<Vec<_>>().join(" ") == normalized
+        }) {
+            if let Some(rationale) = &plan.rationale {
+                writeln!(file, "# Why: {}", rationale)?;
+            }
+            if let Some(expected) = &plan.expected_signal {
+                writeln!(file, "# Expect: {}", expected)?;
+            }
+            if let Some(requirement) = &plan.requirement {
+                writeln!(file, "# For: {}", requirement)?;
+            }
+        }
         file.write_all(cmd.as_bytes())?;
         file.write_all(b"\n")?;
     }
@@ -324,6 +804,46 @@ cat file.txt
         assert!(commands.is_empty());
     }
 
+    #[test]
+    fn test_rank_candidate_commands() {
+        let plans = vec![
+            vec![
+                "ls crates".to_string(),
+                "rg -n 'fn main' src".to_string(),
+                "cat Cargo.toml".to_string(),
+            ],
+            vec![
+                "rg  -n 'fn main'  src".to_string(),
+                "cat README.md".to_string(),
+                "ls crates".to_string(),
+            ],
+            vec!["rg -n 'fn main' src".to_string(), "ls tests".to_string()],
+        ];
+        let ranked = rank_candidate_commands(&plans, 10);
+
+        // Deduplicated, whitespace-insensitive, most agreed-on first
+        assert_eq!(ranked.len(), 5);
+        assert_eq!(ranked[0], "rg -n 'fn main' src");
+        assert_eq!(ranked[1], "ls crates");
+
+        // Capped at the limit
+        assert_eq!(rank_candidate_commands(&plans, 3).len(), 3);
+    }
+
+    #[test]
+    fn test_rank_candidate_commands_prefers_new_terms() {
+        let plans = vec![
+            vec!["cat src/lib.rs".to_string(), "head src/lib.rs".to_string()],
+            vec!["cat src/lib.rs".to_string(), "cat src/main.rs".to_string()],
+        ];
+        let ranked = rank_candidate_commands(&plans, 3);
+        // `head src/lib.rs` adds nothing new once `cat src/lib.rs` is picked
+        assert_eq!(
+            ranked,
+            vec!["cat src/lib.rs", "cat src/main.rs", "head src/lib.rs"]
+        );
+    }
+
     #[test]
     fn test_extract_summary() {
         let response = r#"
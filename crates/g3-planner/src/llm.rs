@@ -125,6 +125,7 @@ pub async fn generate_requirements_summary(
         stream: false,
         tools: None,
         disable_thinking: false,
+        cancellation_token: None,
     };
 
     let response = provider
@@ -175,6 +176,7 @@ pub async fn generate_commit_message(
         stream: false,
         tools: None,
         disable_thinking: false,
+        cancellation_token: None,
     };
 
     let response = provider
@@ -357,13 +359,10 @@ pub async fn call_refinement_llm_with_tools(
     project.enter_workspace()?;
 
     // Create agent - not autonomous mode, just regular agent with tools
-    let mut agent = Agent::new_with_project_context_and_quiet(
-        planner_config,
-        ui_writer,
-        Some(system_prompt),
-        false, // not quiet
-    )
-    .await?;
+    let mut agent = Agent::builder(planner_config, ui_writer)
+        .project_context(Some(system_prompt))
+        .build()
+        .await?;
     
     // Execute the refinement task
     // The agent will have access to tools and execute them
@@ -608,12 +608,11 @@ pub async fn run_coach_player_loop(
         
         let player_config = g3_config.for_player()?;
         let ui_writer = llm::PlannerUiWriter::new();
-        let mut player_agent = Agent::new_autonomous_with_project_context_and_quiet(
-            player_config,
-            ui_writer,
-            None,
-            planner_config.quiet,
-        ).await?;
+        let mut player_agent = Agent::builder(player_config, ui_writer)
+            .autonomous(true)
+            .quiet(planner_config.quiet)
+            .build()
+            .await?;
         
         let player_prompt = if coach_feedback.is_empty() || turn == 1 {
             format!(
@@ -666,12 +665,11 @@ pub async fn run_coach_player_loop(
         
         let coach_config = g3_config.for_coach()?;
         let coach_ui_writer = llm::PlannerUiWriter::new();
-        let mut coach_agent = Agent::new_autonomous_with_project_context_and_quiet(
-            coach_config,
-            coach_ui_writer,
-            None,
-            planner_config.quiet,
-        ).await?;
+        let mut coach_agent = Agent::builder(coach_config, coach_ui_writer)
+            .autonomous(true)
+            .quiet(planner_config.quiet)
+            .build()
+            .await?;
         
         let coach_prompt = format!(
             "You are G3 in coach mode. Review the implementation against these requirements:\n\n{}\n\nCheck:\n1. Are requirements implemented correctly?\n2. Does the code compile?\n3. What's missing?\n\nProvide your feedback as a summary.\nIf implementation is COMPLETE, include 'IMPLEMENTATION_APPROVED' in your feedback.\nOtherwise, provide specific feedback for the player to fix.",
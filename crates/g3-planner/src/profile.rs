@@ -0,0 +1,369 @@
+//! Project profile: the durable facts discovery learns about a codebase.
+//!
+//! Discovery re-derives the build system, test command, layout and entry
+//! points of a project every run. The profile distills them into
+//! `.g3/profile.json` so later sessions can start from a compact summary
+//! (see `ProjectProfile::to_context`) instead. Refreshing is incremental:
+//! the detected fields are recomputed from what's on disk and the file is
+//! only rewritten when they changed, while `notes` (free-form, for humans or
+//! agents to add) are always kept.
+
+use anyhow::Result;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::heuristics::{entry_points, MEMBER_DIRS, SKIPPED_DIRS};
+
+/// Location of the profile, relative to the codebase root.
+pub const PROFILE_PATH: &str = ".g3/profile.json";
+
+/// Most key directories recorded.
+const MAX_KEY_DIRECTORIES: usize = 20;
+
+/// Files whose presence implies a convention worth following.
+const CONVENTION_FILES: &[(&str, &str)] = &[
+    ("rustfmt.toml", "Rust formatting configured in rustfmt.toml"),
+    (
+        ".rustfmt.toml",
+        "Rust formatting configured in .rustfmt.toml",
+    ),
+    ("clippy.toml", "Clippy lints configured in clippy.toml"),
+    (".editorconfig", "Editor settings in .editorconfig"),
+    (".prettierrc", "JS/TS formatting with Prettier"),
+    (".prettierrc.json", "JS/TS formatting with Prettier"),
+    (".eslintrc.json", "JS/TS linting with ESLint"),
+    ("eslint.config.js", "JS/TS linting with ESLint"),
+    ("ruff.toml", "Python linting with ruff"),
+    (
+        ".pre-commit-config.yaml",
+        "pre-commit hooks in .pre-commit-config.yaml",
+    ),
+    (
+        ".github/workflows",
+        "CI on GitHub Actions (.github/workflows)",
+    ),
+    ("AGENTS.md", "Agent instructions in AGENTS.md"),
+    (
+        "CONTRIBUTING.md",
+        "Contribution guidelines in CONTRIBUTING.md",
+    ),
+    ("tests", "Integration tests in tests/"),
+];
+
+/// Distilled facts about a codebase, persisted in `.g3/profile.json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectProfile {
+    /// Build systems found, primary first (e.g. "cargo", "npm")
+    pub build_systems: Vec<String>,
+    pub build_command: Option<String>,
+    pub test_command: Option<String>,
+    /// Top-level directories and workspace members
+    pub key_directories: Vec<String>,
+    pub entry_points: Vec<String>,
+    pub conventions: Vec<String>,
+    /// Free-form notes; never overwritten by a refresh
+    pub notes: Vec<String>,
+    /// When the detected fields last changed
+    pub updated_at: String,
+}
+
+impl ProjectProfile {
+    /// Detect a profile from the files under `root`.
+    pub fn detect(root: &Path) -> Self {
+        let mut profile = Self::default();
+
+        for build_system in detect_build_systems(root) {
+            // The first build system with a command wins
+            if profile.build_command.is_none() {
+                profile.build_command = build_system.build;
+            }
+            if profile.test_command.is_none() {
+                profile.test_command = build_system.test;
+            }
+            profile.build_systems.push(build_system.name);
+        }
+        profile.key_directories = key_directories(root);
+        profile.entry_points = entry_points(root);
+        profile.conventions = CONVENTION_FILES
+            .iter()
+            .filter(|(path, _)| root.join(path).exists())
+            .map(|(_, convention)| convention.to_string())
+            .collect();
+        profile.conventions.dedup();
+        profile
+    }
+
+    /// Load the profile saved under `root`, if any.
+    pub fn load(root: &Path) -> Option<Self> {
+        let content = fs::read_to_string(profile_path(root)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Save the profile under `root`.
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = profile_path(root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// Whether the detected fields match, ignoring notes and timestamps.
+    fn same_detected(&self, other: &Self) -> bool {
+        let detected = |p: &Self| Self {
+            notes: Vec::new(),
+            updated_at: String::new(),
+            ..p.clone()
+        };
+        detected(self) == detected(other)
+    }
+
+    /// A compact summary for the agent's context.
+    pub fn to_context(&self) -> String {
+        let mut lines = vec![format!("🧭 Project Profile (from {}):", PROFILE_PATH)];
+        if !self.build_systems.is_empty() {
+            lines.push(format!("- Build system: {}", self.build_systems.join(", ")));
+        }
+        if let Some(build) = &self.build_command {
+            lines.push(format!("- Build: `{}`", build));
+        }
+        if let Some(test) = &self.test_command {
+            lines.push(format!("- Test: `{}`", test));
+        }
+        if !self.key_directories.is_empty() {
+            lines.push(format!(
+                "- Key directories: {}",
+                self.key_directories.join(", ")
+            ));
+        }
+        if !self.entry_points.is_empty() {
+            lines.push(format!("- Entry points: {}", self.entry_points.join(", ")));
+        }
+        for convention in &self.conventions {
+            lines.push(format!("- {}", convention));
+        }
+        for note in &self.notes {
+            lines.push(format!("- Note: {}", note));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Path of the profile for the codebase at `root`.
+pub fn profile_path(root: &Path) -> PathBuf {
+    root.join(PROFILE_PATH)
+}
+
+/// Re-detect the profile of `root` and save it if anything changed.
+///
+/// Notes from the saved profile are carried over. Returns the current
+/// profile and whether it was written.
+pub fn refresh_profile(root: &Path) -> Result<(ProjectProfile, bool)> {
+    let mut detected = ProjectProfile::detect(root);
+    match ProjectProfile::load(root) {
+        Some(saved) if saved.same_detected(&detected) => Ok((saved, false)),
+        saved => {
+            if let Some(saved) = saved {
+                detected.notes = saved.notes;
+            }
+            detected.updated_at = Local::now().to_rfc3339();
+            detected.save(root)?;
+            Ok((detected, true))
+        }
+    }
+}
+
+/// A build system with its build and test commands.
+struct BuildSystem {
+    name: String,
+    build: Option<String>,
+    test: Option<String>,
+}
+
+impl BuildSystem {
+    fn new(name: &str, build: Option<&str>, test: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            build: build.map(str::to_string),
+            test: test.map(str::to_string),
+        }
+    }
+}
+
+/// Build systems under `root`, in order of preference.
+fn detect_build_systems(root: &Path) -> Vec<BuildSystem> {
+    let has = |path: &str| root.join(path).exists();
+    let mut found = Vec::new();
+
+    if has("Cargo.toml") {
+        found.push(BuildSystem::new(
+            "cargo",
+            Some("cargo build"),
+            Some("cargo test"),
+        ));
+    }
+    if has("package.json") {
+        let runner = if has("pnpm-lock.yaml") {
+            "pnpm"
+        } else if has("yarn.lock") {
+            "yarn"
+        } else {
+            "npm"
+        };
+        let scripts = package_scripts(&root.join("package.json"));
+        let has_script = |name: &str| scripts.iter().any(|s| s == name);
+        found.push(BuildSystem {
+            name: runner.to_string(),
+            build: has_script("build").then(|| format!("{} run build", runner)),
+            test: has_script("test").then(|| format!("{} test", runner)),
+        });
+    }
+    if has("pyproject.toml") || has("setup.py") {
+        found.push(BuildSystem::new("python", None, Some("pytest")));
+    }
+    if has("go.mod") {
+        found.push(BuildSystem::new(
+            "go",
+            Some("go build ./..."),
+            Some("go test ./..."),
+        ));
+    }
+    if has("pom.xml") {
+        found.push(BuildSystem::new(
+            "maven",
+            Some("mvn package"),
+            Some("mvn test"),
+        ));
+    }
+    if has("build.gradle") || has("build.gradle.kts") {
+        let gradle = if has("gradlew") {
+            "./gradlew"
+        } else {
+            "gradle"
+        };
+        found.push(BuildSystem {
+            name: "gradle".to_string(),
+            build: Some(format!("{} build", gradle)),
+            test: Some(format!("{} test", gradle)),
+        });
+    }
+    if has("CMakeLists.txt") {
+        found.push(BuildSystem::new(
+            "cmake",
+            Some("cmake --build build"),
+            Some("ctest --test-dir build"),
+        ));
+    }
+    if has("Makefile") {
+        let has_test_target = fs::read_to_string(root.join("Makefile"))
+            .is_ok_and(|m| m.lines().any(|l| l.starts_with("test:")));
+        found.push(BuildSystem::new(
+            "make",
+            Some("make"),
+            has_test_target.then_some("make test"),
+        ));
+    }
+    found
+}
+
+/// Names of the `scripts` in a `package.json`.
+fn package_scripts(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|json| {
+            json.get("scripts")
+                .and_then(|s| s.as_object())
+                .map(|scripts| scripts.keys().cloned().collect())
+        })
+        .unwrap_or_default()
+}
+
+/// Visible top-level directories, with workspace members listed individually.
+fn key_directories(root: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().to_str().map(str::to_string))
+        .filter(|name| !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()))
+        .collect();
+    dirs.sort();
+
+    let mut key_directories = Vec::new();
+    for dir in dirs {
+        key_directories.push(format!("{}/", dir));
+        if !MEMBER_DIRS.contains(&dir.as_str()) {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(root.join(&dir)) else {
+            continue;
+        };
+        let mut members: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().to_str().map(|n| format!("{}/{}/", dir, n)))
+            .collect();
+        members.sort();
+        key_directories.extend(members);
+    }
+    key_directories.truncate(MAX_KEY_DIRECTORIES);
+    key_directories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_and_refresh_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Cargo.toml"), "[workspace]\n").unwrap();
+        fs::write(root.join("rustfmt.toml"), "").unwrap();
+        fs::create_dir_all(root.join("crates/core/src")).unwrap();
+        fs::write(root.join("crates/core/src/lib.rs"), "").unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+
+        let profile = ProjectProfile::detect(root);
+        assert_eq!(profile.build_systems, ["cargo"]);
+        assert_eq!(profile.test_command.as_deref(), Some("cargo test"));
+        assert_eq!(profile.key_directories, ["crates/", "crates/core/"]);
+        assert_eq!(profile.entry_points, ["crates/core/src/lib.rs"]);
+        assert_eq!(
+            profile.conventions,
+            ["Rust formatting configured in rustfmt.toml"]
+        );
+
+        let (_, written) = refresh_profile(root).unwrap();
+        assert!(written);
+
+        // Notes survive refreshes, and unchanged projects aren't rewritten
+        let mut saved = ProjectProfile::load(root).unwrap();
+        saved.notes.push("Run clippy before committing".to_string());
+        saved.save(root).unwrap();
+        let (profile, written) = refresh_profile(root).unwrap();
+        assert!(!written);
+        assert_eq!(profile.notes, ["Run clippy before committing"]);
+
+        fs::write(
+            root.join("package.json"),
+            r#"{"scripts": {"test": "jest"}}"#,
+        )
+        .unwrap();
+        let (profile, written) = refresh_profile(root).unwrap();
+        assert!(written);
+        assert_eq!(profile.build_systems, ["cargo", "npm"]);
+        assert_eq!(profile.test_command.as_deref(), Some("cargo test"));
+        assert_eq!(profile.notes, ["Run clippy before committing"]);
+        assert!(profile
+            .to_context()
+            .contains("- Note: Run clippy before committing"));
+    }
+}
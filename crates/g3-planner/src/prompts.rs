@@ -29,19 +29,43 @@ TO GET A BETTER UNDERSTANDING OF THE CODEBASE.
 Your task is to analyze the codebase overview provided below and generate shell commands to explore it further - in particular, those
 you deem most relevant to the requirements given below.
 
-Your output MUST include:
-1. A summary report.  Use the heading {{SUMMARY BASED ON INITIAL INFO}}.
-   - retain as much information of that as you consider relevant to the requirements, and for making an implementation plan.
-   - Ideally that should not be more than 10000 tokens.
-2. A list of shell commands to explore the code. Use the heading {{CODE EXPLORATION COMMANDS}}.
-   - Try plan ahead for what you need for a deep dive into the code. Make sure the information is sparing.
-   - Carefully consider which commands give you the most relevant information, pick the top 25 commands.
-   - Use tools like `ls`, `rg` (ripgrep), `grep`, `sed`, `cat`, `head`, `tail` etc.
-   - Focus on commands that will help understand the code STRUCTURE without dumping large sections of file.
-   - e.g. for Rust you might try `rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.rs' '^(pub )?(struct|enum|type|union)`
-   - Mark the beginning and end of the commands with "```".
-
-DO NOT ADD ANY COMMENTS OR OTHER EXPLANATION IN THE COMMANDS SECTION, JUST INCLUDE THE SHELL COMMANDS."#;
+Respond with a single JSON object and nothing else (no prose before or after it), following this schema:
+
+{
+  "summary": "string - a summary report of the codebase: retain as much information as you consider relevant to the requirements and to making an implementation plan, ideally no more than 10000 tokens",
+  "commands": [
+    {
+      "command": "string - one shell command",
+      "rationale": "string - why this command, in one sentence",
+      "expected_signal": "string - what you expect to learn from its output",
+      "requirement": "string - optional: ID of the requirement this command explores"
+    }
+  ]
+}
+
+For the commands:
+- Try plan ahead for what you need for a deep dive into the code. Make sure the information is sparing.
+- Carefully consider which commands give you the most relevant information, pick the top 25 commands.
+- Use tools like `ls`, `rg` (ripgrep), `grep`, `sed`, `cat`, `head`, `tail` etc.
+- Focus on commands that will help understand the code STRUCTURE without dumping large sections of file.
+- e.g. for Rust you might try `rg --no-heading --line-number --with-filename --max-filesize 500K -g '*.rs' '^(pub )?(struct|enum|type|union)`
+- Each "command" is exactly one shell command, without comments.
+- Commands must only read: no writing to files (`>` only to /dev/null), `sed -i`, `find -exec`/`-delete`, `$(...)` or subshells.
+  Commands outside a read-only allowlist are rejected without running."#;
+
+/// Appended to the discovery prompt when the requirements have IDs.
+/// `{requirements}` is replaced with the list of IDs, titles and criteria.
+pub const DISCOVERY_PER_REQUIREMENT_NOTE: &str = r#"The requirements are structured, with these IDs:
+
+{requirements}
+Include at least one command per requirement that locates the code it will touch, and set "requirement" to
+that ID on each such command. Leave it out for commands about the codebase in general."#;
+
+/// Appended to the discovery prompt when several candidate plans are requested.
+/// `{index}` and `{count}` are replaced with the candidate number and total.
+pub const DISCOVERY_CANDIDATE_NOTE: &str = r#"This is candidate plan {index} of {count}. The other plans are written independently
+and merged afterwards, so take your own angle on what is most informative (e.g. entry points, call sites, tests,
+configuration) rather than the most obvious one."#;
 
 // =============================================================================
 // PLANNING MODE PROMPTS
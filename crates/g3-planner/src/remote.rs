@@ -0,0 +1,194 @@
+//! Fast start on a remote repository.
+//!
+//! A codebase given as a git URL (`https://github.com/owner/repo`,
+//! `git@host:owner/repo.git`, ...) is shallow-cloned into
+//! `~/.g3/repos/<host>/<path>` and explored there, so reviewing a repository
+//! doesn't need a manual clone first. A clone made by an earlier session is
+//! reused and moved to the remote HEAD, unless it has local changes.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::events::{PlannerEvent, StatusCallback};
+
+/// Where remote repositories are cloned.
+const REPOS_DIR: &str = "~/.g3/repos";
+
+/// Whether `codebase` is a git URL rather than a local path.
+pub fn is_git_url(codebase: &str) -> bool {
+    let codebase = codebase.trim();
+    if ["https://", "http://", "ssh://", "git://"]
+        .iter()
+        .any(|scheme| codebase.starts_with(scheme))
+    {
+        return true;
+    }
+    // scp-like syntax: user@host:path
+    match codebase.split_once(':') {
+        Some((user_host, path)) => {
+            user_host.contains('@') && !user_host.contains('/') && !path.is_empty()
+        }
+        None => false,
+    }
+}
+
+/// The directory a URL is cloned to: `<host>/<path>` under `repos_dir`,
+/// without the scheme, user, port and `.git` suffix.
+pub fn clone_dir(repos_dir: &Path, url: &str) -> PathBuf {
+    let url = url.trim().trim_end_matches('/');
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.to_string(),
+        // scp-like `git@host:owner/repo` has the path after the first ':'
+        None => url.replacen(':', "/", 1),
+    };
+    let (host, path) = rest.split_once('/').unwrap_or((&rest, ""));
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    let rest = format!("{}/{}", host, path);
+    let rest = rest.strip_suffix(".git").unwrap_or(&rest);
+
+    let mut dir = repos_dir.to_path_buf();
+    for (index, part) in rest.split('/').enumerate() {
+        // Drop a port, and anything that could leave `repos_dir`
+        let part = if index == 0 {
+            part.split(':').next().unwrap_or(part)
+        } else {
+            part
+        };
+        if part.is_empty() || part == "." || part == ".." {
+            continue;
+        }
+        dir.push(part);
+    }
+    dir
+}
+
+/// A local path to explore for `codebase`: the path itself, or for a git URL
+/// the directory it was cloned into.
+pub fn resolve_codebase(
+    codebase: &str,
+    status_callback: Option<&StatusCallback>,
+) -> Result<String> {
+    if !is_git_url(codebase) {
+        return Ok(codebase.to_string());
+    }
+    let emit = |event: PlannerEvent| {
+        if let Some(cb) = status_callback {
+            cb(&event);
+        }
+    };
+    let repos_dir = PathBuf::from(shellexpand::tilde(REPOS_DIR).as_ref());
+    let dir = clone_dir(&repos_dir, codebase);
+
+    if dir.join(".git").exists() {
+        update_clone(&dir, &emit);
+    } else {
+        emit(PlannerEvent::CloneStarted {
+            url: codebase.to_string(),
+        });
+        if let Some(parent) = dir.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let output = git(None)
+            .args(["clone", "--depth", "1", codebase])
+            .arg(&dir)
+            .output()
+            .context("Failed to run git clone")?;
+        if !output.status.success() {
+            bail!(
+                "git clone {} failed: {}",
+                codebase,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        emit(PlannerEvent::Cloned {
+            path: dir.display().to_string(),
+        });
+    }
+    Ok(dir.to_string_lossy().to_string())
+}
+
+/// Bring an earlier clone to the remote HEAD, unless it has local changes.
+/// Failing to update keeps the clone as it is.
+fn update_clone(dir: &Path, emit: &dyn Fn(PlannerEvent)) {
+    let clean = git(Some(dir))
+        .args(["status", "--porcelain"])
+        .output()
+        .is_ok_and(|o| o.status.success() && o.stdout.is_empty());
+    if !clean {
+        emit(PlannerEvent::warning(format!(
+            "Reusing {} as is: it has local changes",
+            dir.display()
+        )));
+        return;
+    }
+    let updated = git(Some(dir))
+        .args(["fetch", "-q", "--depth", "1", "origin", "HEAD"])
+        .output()
+        .is_ok_and(|o| o.status.success())
+        && git(Some(dir))
+            .args(["reset", "--hard", "-q", "FETCH_HEAD"])
+            .output()
+            .is_ok_and(|o| o.status.success());
+    if updated {
+        emit(PlannerEvent::CloneUpdated {
+            path: dir.display().to_string(),
+        });
+    } else {
+        emit(PlannerEvent::warning(format!(
+            "Could not update {}, using it as is",
+            dir.display()
+        )));
+    }
+}
+
+/// A git command that fails instead of prompting for credentials.
+fn git(dir: Option<&Path>) -> Command {
+    let mut command = Command::new("git");
+    command.env("GIT_TERMINAL_PROMPT", "0");
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_urls_and_clone_dirs() {
+        assert!(is_git_url("https://github.com/owner/repo"));
+        assert!(is_git_url("git@github.com:owner/repo.git"));
+        assert!(is_git_url("ssh://git@host:2222/team/repo.git"));
+        assert!(!is_git_url("/home/me/repo"));
+        assert!(!is_git_url("./repo"));
+        assert!(!is_git_url("C:\\code\\repo"));
+
+        let repos = Path::new("/repos");
+        assert_eq!(
+            clone_dir(repos, "https://github.com/owner/repo.git/"),
+            Path::new("/repos/github.com/owner/repo")
+        );
+        assert_eq!(
+            clone_dir(repos, "git@github.com:owner/repo.git"),
+            Path::new("/repos/github.com/owner/repo")
+        );
+        assert_eq!(
+            clone_dir(repos, "ssh://git@host:2222/team/repo"),
+            Path::new("/repos/host/team/repo")
+        );
+        assert_eq!(
+            clone_dir(repos, "https://host/../../etc"),
+            Path::new("/repos/host/etc")
+        );
+    }
+
+    #[test]
+    fn test_local_paths_are_kept() {
+        assert_eq!(
+            resolve_codebase("/tmp/project", None).unwrap(),
+            "/tmp/project"
+        );
+    }
+}
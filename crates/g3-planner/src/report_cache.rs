@@ -0,0 +1,188 @@
+//! Cache of the codebase report under `.g3/planner/`.
+//!
+//! `explore_codebase` runs dozens of `rg`/`find` passes over the tree, which
+//! takes tens of seconds on large monorepos. The report only depends on the
+//! files, so it is cached together with a fingerprint of the tree: the
+//! relative path, size and modification time of every file explore_codebase
+//! looks at. A walk that only stats files is far cheaper than the report, and
+//! when the fingerprint matches the cached report is reused as is.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::code_explore::{expand_tilde, explore_codebase_with, ExploreOptions};
+use crate::heuristics::SKIPPED_DIRS;
+
+/// Location of the cached report, relative to the codebase root.
+pub const REPORT_CACHE_PATH: &str = ".g3/planner/codebase_report.json";
+
+/// Bump when the report's content changes for the same files, so reports
+/// cached by older versions are regenerated.
+const REPORT_VERSION: u32 = 4;
+
+/// A codebase report and the fingerprint of the tree it was made from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedReport {
+    fingerprint: String,
+    created_at: String,
+    report: String,
+}
+
+/// The codebase report for `path`, reused from the cache when neither the
+/// tree nor the options changed since it was made.
+///
+/// Returns the report and whether it came from the cache. Failing to read
+/// or write the cache only costs a fresh exploration.
+pub fn explore_codebase_cached(path: &str, options: &ExploreOptions) -> (String, bool) {
+    let root = PathBuf::from(expand_tilde(path));
+    let options_hash = Sha256::digest(format!("{:?}", options).as_bytes());
+    let fingerprint = format!("{}:{:x}", tree_fingerprint(&root), options_hash);
+
+    if let Some(cached) = load(&root) {
+        if cached.fingerprint == fingerprint {
+            return (cached.report, true);
+        }
+    }
+
+    let report = explore_codebase_with(path, options);
+    let cached = CachedReport {
+        fingerprint,
+        created_at: chrono::Local::now().to_rfc3339(),
+        report,
+    };
+    // Not caching only slows down the next run
+    let _ = save(&root, &cached);
+    (cached.report, false)
+}
+
+/// Path of the cached report for the codebase at `root`.
+pub fn report_cache_path(root: &Path) -> PathBuf {
+    root.join(REPORT_CACHE_PATH)
+}
+
+fn load(root: &Path) -> Option<CachedReport> {
+    let content = fs::read_to_string(report_cache_path(root)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save(root: &Path, cached: &CachedReport) -> Result<()> {
+    let path = report_cache_path(root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string(cached)?)?;
+    Ok(())
+}
+
+/// Size and modification time of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub len: u64,
+    /// Nanoseconds since the Unix epoch, 0 when unknown
+    pub modified: u64,
+}
+
+/// A hash of every file's relative path, size and modification time under
+/// `root` (see `tree_stamps`).
+pub fn tree_fingerprint(root: &Path) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(REPORT_VERSION.to_le_bytes());
+    for (path, stamp) in tree_stamps(root) {
+        hasher.update(format!("{}\t{}\t{}\n", path, stamp.len, stamp.modified).as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Stamps of every file under `root` by relative path, skipping hidden
+/// entries and build/dependency directories the report ignores too.
+pub fn tree_stamps(root: &Path) -> BTreeMap<String, FileStamp> {
+    let mut entries = BTreeMap::new();
+    collect_entries(root, root, &mut entries);
+    entries
+}
+
+fn collect_entries(root: &Path, dir: &Path, entries: &mut BTreeMap<String, FileStamp>) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.filter_map(|e| e.ok()) {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_ref()) {
+                collect_entries(root, &path, entries);
+            }
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos() as u64);
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        entries.insert(
+            relative.display().to_string(),
+            FileStamp {
+                len: metadata.len(),
+                modified,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_reused_until_tree_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        let path = root.to_str().unwrap();
+        let options = ExploreOptions::default();
+
+        let (report, cached) = explore_codebase_cached(path, &options);
+        assert!(!cached);
+        assert!(report_cache_path(root).exists());
+        let (again, cached) = explore_codebase_cached(path, &options);
+        assert!(cached);
+        assert_eq!(again, report);
+
+        // Build output and the cache itself don't invalidate it
+        fs::write(root.join("target/debug/app"), "binary").unwrap();
+        assert!(explore_codebase_cached(path, &options).1);
+
+        fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+        assert!(!explore_codebase_cached(path, &options).1);
+        assert!(explore_codebase_cached(path, &options).1);
+
+        fs::write(root.join("src/lib.rs"), "pub fn library() {}\n").unwrap();
+        assert!(!explore_codebase_cached(path, &options).1);
+
+        // Nor does a report made with other options
+        let fewer_files = ExploreOptions {
+            max_files: 5,
+            ..ExploreOptions::default()
+        };
+        assert!(!explore_codebase_cached(path, &fewer_files).1);
+        assert!(explore_codebase_cached(path, &fewer_files).1);
+    }
+}
@@ -0,0 +1,364 @@
+//! Monorepo packages and the one a task targets.
+//!
+//! Exploring a whole monorepo spends the discovery budget on packages the
+//! task never touches. The packages come from the workspace manifests (Cargo
+//! members, npm/yarn workspaces, `pnpm-workspace.yaml`, `go.work`, uv
+//! members) or, in a Bazel workspace, from the directories holding a `BUILD`
+//! file. A configured target (`[discovery] target`), a `Target:` line in the
+//! requirements, or the requirements naming exactly one package scopes
+//! discovery and the agent's working directory to that package.
+
+use std::fs;
+use std::path::Path;
+
+use crate::ecosystems::{expand_members, npm_workspaces, read_json, read_toml, toml_strings};
+use crate::heuristics::SKIPPED_DIRS;
+
+/// Deepest directory searched for Bazel packages.
+const MAX_BAZEL_DEPTH: usize = 8;
+
+/// Most Bazel packages collected.
+const MAX_BAZEL_PACKAGES: usize = 500;
+
+/// Files marking the root of a Bazel workspace.
+const BAZEL_ROOTS: &[&str] = &["MODULE.bazel", "WORKSPACE", "WORKSPACE.bazel"];
+
+/// Labels that name the target package in the requirements.
+const TARGET_LABELS: &[&str] = &["target", "package"];
+
+/// A package of a monorepo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspacePackage {
+    /// Package name from its manifest, `//dir` for Bazel packages, otherwise
+    /// the directory
+    pub name: String,
+    /// Directory relative to the root
+    pub dir: String,
+    /// "cargo", "npm", "pnpm", "go", "uv", "bazel" or "directory"
+    pub kind: &'static str,
+}
+
+impl WorkspacePackage {
+    /// Whether `reference` (a name, a directory or a Bazel label) means this
+    /// package.
+    fn matches(&self, reference: &str) -> bool {
+        let reference = reference.trim_start_matches("./").trim_end_matches('/');
+        // `//pkg/api:server` is a target of the `//pkg/api` package
+        let reference = match reference.strip_prefix("//") {
+            Some(label) => label.split(':').next().unwrap_or(label),
+            None => reference,
+        };
+        !reference.is_empty() && (reference == self.dir || reference == self.name)
+    }
+}
+
+/// The packages of the workspace at `root`, in manifest order. Empty for a
+/// codebase that isn't a workspace.
+pub fn detect_workspace_packages(root: &Path) -> Vec<WorkspacePackage> {
+    let mut packages: Vec<WorkspacePackage> = Vec::new();
+    let mut add = |dir: String, name: Option<String>, kind: &'static str| {
+        if !packages.iter().any(|p| p.dir == dir) {
+            packages.push(WorkspacePackage {
+                name: name.unwrap_or_else(|| dir.clone()),
+                dir,
+                kind,
+            });
+        }
+    };
+
+    if let Some(manifest) = read_toml(&root.join("Cargo.toml")) {
+        let patterns = toml_strings(manifest.get("workspace").and_then(|w| w.get("members")));
+        for dir in expand_members(root, &patterns, "Cargo.toml") {
+            let name = read_toml(&root.join(&dir).join("Cargo.toml"))
+                .and_then(|m| m.get("package")?.get("name")?.as_str().map(String::from));
+            add(dir, name, "cargo");
+        }
+    }
+
+    let npm_name = |dir: &str| {
+        read_json(&root.join(dir).join("package.json"))
+            .and_then(|m| m.get("name")?.as_str().map(String::from))
+    };
+    if let Some(manifest) = read_json(&root.join("package.json")) {
+        for dir in expand_members(root, &npm_workspaces(&manifest), "package.json") {
+            let name = npm_name(&dir);
+            add(dir, name, "npm");
+        }
+    }
+    if let Ok(content) = fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+        for dir in expand_members(root, &pnpm_packages(&content), "package.json") {
+            let name = npm_name(&dir);
+            add(dir, name, "pnpm");
+        }
+    }
+
+    if let Ok(work) = fs::read_to_string(root.join("go.work")) {
+        for line in work.lines() {
+            let dir = line.trim().trim_start_matches("use").trim();
+            let Some(dir) = dir.strip_prefix("./") else {
+                continue;
+            };
+            let dir = dir.trim_end_matches('/').to_string();
+            let module = fs::read_to_string(root.join(&dir).join("go.mod"))
+                .ok()
+                .and_then(|m| {
+                    m.lines()
+                        .find_map(|l| l.trim().strip_prefix("module "))
+                        .map(|m| m.trim().to_string())
+                });
+            add(dir, module, "go");
+        }
+    }
+
+    if let Some(manifest) = read_toml(&root.join("pyproject.toml")) {
+        let patterns = toml_strings(
+            manifest
+                .get("tool")
+                .and_then(|t| t.get("uv"))
+                .and_then(|u| u.get("workspace"))
+                .and_then(|w| w.get("members")),
+        );
+        for dir in expand_members(root, &patterns, "pyproject.toml") {
+            let name = read_toml(&root.join(&dir).join("pyproject.toml"))
+                .and_then(|m| m.get("project")?.get("name")?.as_str().map(String::from));
+            add(dir, name, "uv");
+        }
+    }
+
+    if BAZEL_ROOTS.iter().any(|f| root.join(f).is_file()) {
+        let mut dirs = Vec::new();
+        collect_bazel_packages(root, "", 0, &mut dirs);
+        for dir in dirs {
+            let name = format!("//{}", dir);
+            add(dir, Some(name), "bazel");
+        }
+    }
+
+    packages
+}
+
+/// The package to scope discovery to: the `configured` target, else the
+/// one a `Target:` (or `Package:`) line of the requirements names, else the
+/// only package the requirements mention by name or directory.
+///
+/// A configured or labelled target that is no package but an existing
+/// directory is used as is. Errors when an explicit target matches nothing.
+pub fn select_target(
+    root: &Path,
+    packages: &[WorkspacePackage],
+    requirements_text: Option<&str>,
+    configured: Option<&str>,
+) -> Result<Option<WorkspacePackage>, String> {
+    let explicit = configured
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .or_else(|| requirements_text.and_then(target_label));
+    if let Some(reference) = explicit {
+        if let Some(package) = packages.iter().find(|p| p.matches(&reference)) {
+            return Ok(Some(package.clone()));
+        }
+        let dir = reference.trim_start_matches("./").trim_end_matches('/');
+        if !dir.is_empty()
+            && !dir.starts_with('/')
+            && !dir.contains("..")
+            && root.join(dir).is_dir()
+        {
+            return Ok(Some(WorkspacePackage {
+                name: dir.to_string(),
+                dir: dir.to_string(),
+                kind: "directory",
+            }));
+        }
+        return Err(format!(
+            "target `{}` is neither a workspace package nor a directory",
+            reference
+        ));
+    }
+
+    let Some(text) = requirements_text else {
+        return Ok(None);
+    };
+    let mut mentioned: Vec<&WorkspacePackage> = Vec::new();
+    for token in text.split_whitespace() {
+        // Sentence punctuation, but not the `./` or `//` a path starts with
+        let token = token
+            .trim_start_matches(['(', '[', '"', '\'', '`', '*'])
+            .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '"', '\'', '`', '*']);
+        for package in packages.iter().filter(|p| p.matches(token)) {
+            if !mentioned.contains(&package) {
+                mentioned.push(package);
+            }
+        }
+    }
+    Ok(match mentioned.as_slice() {
+        [only] => Some((*only).clone()),
+        _ => None,
+    })
+}
+
+/// The value of a `Target: name` line (also as a bullet or in bold).
+fn target_label(text: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let line = line.trim().trim_start_matches(['-', '*', '#', ' ']);
+        let (label, value) = line.split_once(':')?;
+        let label = label.trim_matches(['*', '_', ' ']).to_ascii_lowercase();
+        if !TARGET_LABELS.contains(&label.as_str()) {
+            return None;
+        }
+        let value = value
+            .trim_matches(|c: char| c.is_whitespace() || matches!(c, '*' | '_' | '`' | '"' | '\''));
+        (!value.is_empty() && !value.contains(char::is_whitespace)).then(|| value.to_string())
+    })
+}
+
+/// The `packages:` patterns of a `pnpm-workspace.yaml`, without exclusions.
+fn pnpm_packages(content: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let line = line.split(" #").next().unwrap_or(line).trim_end();
+        if line.is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '-']) {
+            in_packages = line.trim() == "packages:";
+            continue;
+        }
+        if !in_packages {
+            continue;
+        }
+        if let Some(item) = line.trim().strip_prefix('-') {
+            let pattern = item.trim().trim_matches(['"', '\'']);
+            if !pattern.is_empty() && !pattern.starts_with('!') {
+                patterns.push(pattern.to_string());
+            }
+        }
+    }
+    patterns
+}
+
+/// Directories below `root` that hold a `BUILD` or `BUILD.bazel` file,
+/// skipping Bazel's output links and the usual vendored directories.
+fn collect_bazel_packages(root: &Path, dir: &str, depth: usize, packages: &mut Vec<String>) {
+    if depth > MAX_BAZEL_DEPTH || packages.len() >= MAX_BAZEL_PACKAGES {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(root.join(dir)) else {
+        return;
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|e| e.file_name().to_str().map(String::from))
+        .filter(|n| {
+            !n.starts_with('.') && !n.starts_with("bazel-") && !SKIPPED_DIRS.contains(&n.as_str())
+        })
+        .collect();
+    names.sort();
+    for name in names {
+        let child = if dir.is_empty() {
+            name
+        } else {
+            format!("{}/{}", dir, name)
+        };
+        let path = root.join(&child);
+        if path.join("BUILD").is_file() || path.join("BUILD.bazel").is_file() {
+            packages.push(child.clone());
+        }
+        collect_bazel_packages(root, &child, depth + 1, packages);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_detect_workspace_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        );
+        write(
+            root,
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"app-core\"\n",
+        );
+        write(
+            root,
+            "pnpm-workspace.yaml",
+            "packages:\n  - 'web/*' # frontends\n  - '!web/legacy'\ncatalog:\n  - ignored\n",
+        );
+        write(root, "web/admin/package.json", "{\"name\": \"@app/admin\"}");
+        write(root, "MODULE.bazel", "");
+        write(root, "services/api/BUILD.bazel", "");
+        write(root, "bazel-out/BUILD", "");
+
+        let packages = detect_workspace_packages(root);
+        let found: Vec<(&str, &str, &str)> = packages
+            .iter()
+            .map(|p| (p.dir.as_str(), p.name.as_str(), p.kind))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("crates/core", "app-core", "cargo"),
+                ("web/admin", "@app/admin", "pnpm"),
+                ("services/api", "//services/api", "bazel"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        let package = |dir: &str, name: &str| WorkspacePackage {
+            name: name.to_string(),
+            dir: dir.to_string(),
+            kind: "npm",
+        };
+        let packages = [
+            package("packages/api", "@acme/api"),
+            package("packages/web", "@acme/web"),
+        ];
+        let select = |text: Option<&str>, configured: Option<&str>| {
+            select_target(root, &packages, text, configured).map(|t| t.map(|t| t.dir))
+        };
+
+        // The configured target wins, by name or directory
+        assert_eq!(
+            select(Some("Change packages/web."), Some("@acme/api")),
+            Ok(Some("packages/api".to_string()))
+        );
+        assert_eq!(
+            select(Some("**Target:** `./packages/web/`\n\nAdd a page."), None),
+            Ok(Some("packages/web".to_string()))
+        );
+        // A directory that is no package is scoped to as is
+        assert_eq!(select(None, Some("docs")), Ok(Some("docs".to_string())));
+        assert!(select(None, Some("packages/missing")).is_err());
+
+        // Exactly one mentioned package scopes; none or several don't
+        assert_eq!(
+            select(Some("Rate limit the endpoints of @acme/api."), None),
+            Ok(Some("packages/api".to_string()))
+        );
+        assert_eq!(
+            select(Some("Fix packages/api and packages/web"), None),
+            Ok(None)
+        );
+        assert_eq!(select(Some("Improve the API docs"), None), Ok(None));
+    }
+}
@@ -40,6 +40,7 @@
 //!         stream: false,
 //!         tools: None,
 //!         disable_thinking: false,
+//!         cancellation_token: None,
 //!     };
 //!
 //!     // Get a completion
@@ -77,6 +78,7 @@
 //!         stream: true,
 //!         tools: None,
 //!         disable_thinking: false,
+//!         cancellation_token: None,
 //!     };
 //!
 //!     let mut stream = provider.stream(request).await?;
@@ -112,7 +114,8 @@ use tracing::{debug, error};
 use crate::{
     streaming::{
         decode_utf8_streaming, make_final_chunk, make_final_chunk_with_reason, make_text_chunk,
-        make_tool_chunk, make_tool_streaming_active, make_tool_streaming_hint,
+        make_tool_chunk, make_tool_streaming_active, make_tool_streaming_hint, spawn_stream_task,
+        with_keepalives,
     },
     CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider, Message,
     MessageRole, Tool, ToolCall, Usage,
@@ -265,19 +268,25 @@ impl AnthropicProvider {
 
     fn convert_messages(
         &self,
-        messages: &[Message],
+        messages: Vec<Message>,
     ) -> Result<(Option<String>, Vec<AnthropicMessage>)> {
-        let mut system_message = None;
+        let mut system_message: Option<String> = None;
         let mut anthropic_messages = Vec::new();
 
-        for message in messages {
+        for mut message in messages {
+            let cache_control = message
+                .cache_control
+                .as_ref()
+                .map(Self::convert_cache_control);
             match message.role {
                 MessageRole::System => {
-                    if let Some(existing) = system_message {
+                    let content = message.into_full_content();
+                    if let Some(existing) = &mut system_message {
                         // Concatenate system messages instead of replacing
-                        system_message = Some(format!("{}\n\n{}", existing, message.content));
+                        existing.push_str("\n\n");
+                        existing.push_str(&content);
                     } else {
-                        system_message = Some(message.content.clone());
+                        system_message = Some(content);
                     }
                 }
                 MessageRole::User => {
@@ -285,23 +294,20 @@ impl AnthropicProvider {
                     let mut content_blocks: Vec<AnthropicContent> = Vec::new();
 
                     // Add any images attached to this message
-                    for image in &message.images {
+                    for image in std::mem::take(&mut message.images) {
                         content_blocks.push(AnthropicContent::Image {
                             source: AnthropicImageSource {
                                 source_type: "base64".to_string(),
-                                media_type: image.media_type.clone(),
-                                data: image.data.clone(),
+                                media_type: image.media_type,
+                                data: image.data,
                             },
                         });
                     }
 
                     // Add text content
                     content_blocks.push(AnthropicContent::Text {
-                        text: message.content.clone(),
-                        cache_control: message
-                            .cache_control
-                            .as_ref()
-                            .map(Self::convert_cache_control),
+                        text: message.into_full_content(),
+                        cache_control,
                     });
 
                     anthropic_messages.push(AnthropicMessage {
@@ -313,11 +319,8 @@ impl AnthropicProvider {
                     anthropic_messages.push(AnthropicMessage {
                         role: "assistant".to_string(),
                         content: vec![AnthropicContent::Text {
-                            text: message.content.clone(),
-                            cache_control: message
-                                .cache_control
-                                .as_ref()
-                                .map(Self::convert_cache_control),
+                            text: message.into_full_content(),
+                            cache_control,
                         }],
                     });
                 }
@@ -329,7 +332,7 @@ impl AnthropicProvider {
 
     fn create_request_body(
         &self,
-        messages: &[Message],
+        messages: Vec<Message>,
         tools: Option<&[Tool]>,
         streaming: bool,
         max_tokens: u32,
@@ -693,7 +696,7 @@ impl LLMProvider for AnthropicProvider {
         let temperature = request.temperature.unwrap_or(self.temperature);
 
         let request_body = self.create_request_body(
-            &request.messages,
+            request.messages,
             request.tools.as_deref(),
             false,
             max_tokens,
@@ -769,7 +772,7 @@ impl LLMProvider for AnthropicProvider {
         let temperature = request.temperature.unwrap_or(self.temperature);
 
         let request_body = self.create_request_body(
-            &request.messages,
+            request.messages,
             request.tools.as_deref(),
             true,
             max_tokens,
@@ -810,7 +813,8 @@ impl LLMProvider for AnthropicProvider {
 
         // Spawn task to process the stream
         let provider = self.clone();
-        tokio::spawn(async move {
+        spawn_stream_task(tx, request.cancellation_token, move |tx| async move {
+            let stream = with_keepalives(stream, tx.clone());
             let usage = provider.parse_streaming_response(stream, tx).await;
             // Log the final usage if available
             if let Some(usage) = usage {
@@ -1017,7 +1021,7 @@ mod tests {
             Message::new(MessageRole::Assistant, "Hi there!".to_string()),
         ];
 
-        let (system, anthropic_messages) = provider.convert_messages(&messages).unwrap();
+        let (system, anthropic_messages) = provider.convert_messages(messages).unwrap();
 
         assert_eq!(system, Some("You are a helpful assistant.".to_string()));
         assert_eq!(anthropic_messages.len(), 2);
@@ -1041,7 +1045,7 @@ mod tests {
         let messages = vec![Message::new(MessageRole::User, "Test message".to_string())];
 
         let request_body = provider
-            .create_request_body(&messages, None, false, 1000, 0.5, false)
+            .create_request_body(messages, None, false, 1000, 0.5, false)
             .unwrap();
 
         assert_eq!(request_body.model, "claude-3-haiku-20240307");
@@ -1094,7 +1098,7 @@ mod tests {
 
         // Test message WITHOUT cache_control
         let messages_without = vec![Message::new(MessageRole::User, "Hello".to_string())];
-        let (_, anthropic_messages_without) = provider.convert_messages(&messages_without).unwrap();
+        let (_, anthropic_messages_without) = provider.convert_messages(messages_without).unwrap();
         let json_without = serde_json::to_string(&anthropic_messages_without).unwrap();
 
         println!("Anthropic JSON without cache_control: {}", json_without);
@@ -1113,7 +1117,7 @@ mod tests {
             "Hello".to_string(),
             crate::CacheControl::ephemeral(),
         )];
-        let (_, anthropic_messages_with) = provider.convert_messages(&messages_with).unwrap();
+        let (_, anthropic_messages_with) = provider.convert_messages(messages_with).unwrap();
         let json_with = serde_json::to_string(&anthropic_messages_with).unwrap();
 
         println!("Anthropic JSON with cache_control: {}", json_with);
@@ -1149,7 +1153,7 @@ mod tests {
 
         let messages = vec![Message::new(MessageRole::User, "Test message".to_string())];
         let request_without = provider_without
-            .create_request_body(&messages, None, false, 1000, 0.5, false)
+            .create_request_body(messages.clone(), None, false, 1000, 0.5, false)
             .unwrap();
         let json_without = serde_json::to_string(&request_without).unwrap();
         assert!(
@@ -1171,7 +1175,7 @@ mod tests {
         .unwrap();
 
         let request_with = provider_with
-            .create_request_body(&messages, None, false, 20000, 0.5, false)
+            .create_request_body(messages.clone(), None, false, 20000, 0.5, false)
             .unwrap();
         let json_with = serde_json::to_string(&request_with).unwrap();
         assert!(
@@ -1189,7 +1193,7 @@ mod tests {
 
         // Test WITH thinking parameter but INSUFFICIENT max_tokens - thinking should be disabled
         let request_insufficient = provider_with
-            .create_request_body(&messages, None, false, 5000, 0.5, false) // Less than budget + 1024
+            .create_request_body(messages.clone(), None, false, 5000, 0.5, false) // Less than budget + 1024
             .unwrap();
         let json_insufficient = serde_json::to_string(&request_insufficient).unwrap();
         assert!(
@@ -1216,7 +1220,7 @@ mod tests {
 
         // With disable_thinking=false, thinking should be enabled (max_tokens is sufficient)
         let request_with_thinking = provider
-            .create_request_body(&messages, None, false, 20000, 0.5, false)
+            .create_request_body(messages.clone(), None, false, 20000, 0.5, false)
             .unwrap();
         let json_with = serde_json::to_string(&request_with_thinking).unwrap();
         assert!(
@@ -1226,7 +1230,7 @@ mod tests {
 
         // With disable_thinking=true, thinking should be disabled even with sufficient max_tokens
         let request_without_thinking = provider
-            .create_request_body(&messages, None, false, 20000, 0.5, true)
+            .create_request_body(messages.clone(), None, false, 20000, 0.5, true)
             .unwrap();
         let json_without = serde_json::to_string(&request_without_thinking).unwrap();
         assert!(
@@ -46,6 +46,7 @@
 //!         stream: false,
 //!         tools: None,
 //!         disable_thinking: false,
+//!         cancellation_token: None,
 //!     };
 //!
 //!     // Get a completion
@@ -58,7 +59,10 @@
 
 use anyhow::{anyhow, Result};
 use bytes::Bytes;
-use crate::streaming::{decode_utf8_streaming, is_incomplete_json_error, make_final_chunk};
+use crate::streaming::{
+    decode_utf8_streaming, is_incomplete_json_error, make_final_chunk, spawn_stream_task,
+    with_keepalives,
+};
 use futures_util::stream::StreamExt;
 use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
@@ -345,7 +349,7 @@ impl DatabricksProvider {
             .collect()
     }
 
-    fn convert_messages(&self, messages: &[Message]) -> Result<Vec<DatabricksMessage>> {
+    fn convert_messages(&self, messages: Vec<Message>) -> Result<Vec<DatabricksMessage>> {
         let mut databricks_messages = Vec::new();
 
         for message in messages {
@@ -356,7 +360,7 @@ impl DatabricksProvider {
             };
 
             // Always use simple string format (Databricks doesn't support cache_control)
-            let content = serde_json::Value::String(message.content.clone());
+            let content = serde_json::Value::String(message.into_full_content());
 
             databricks_messages.push(DatabricksMessage {
                 role: role.to_string(),
@@ -374,7 +378,7 @@ impl DatabricksProvider {
 
     fn create_request_body(
         &self,
-        messages: &[Message],
+        messages: Vec<Message>,
         tools: Option<&[Tool]>,
         streaming: bool,
         max_tokens: u32,
@@ -612,7 +616,7 @@ impl LLMProvider for DatabricksProvider {
         let temperature = request.temperature.unwrap_or(self.temperature);
 
         let request_body = self.create_request_body(
-            &request.messages,
+            request.messages,
             request.tools.as_deref(),
             false,
             max_tokens,
@@ -797,7 +801,7 @@ impl LLMProvider for DatabricksProvider {
         let temperature = request.temperature.unwrap_or(self.temperature);
 
         let request_body = self.create_request_body(
-            &request.messages,
+            request.messages,
             request.tools.as_deref(),
             true,
             max_tokens,
@@ -891,7 +895,8 @@ impl LLMProvider for DatabricksProvider {
 
         // Spawn task to process the stream
         let provider = self.clone();
-        tokio::spawn(async move {
+        spawn_stream_task(tx, request.cancellation_token, move |tx| async move {
+            let stream = with_keepalives(stream, tx.clone());
             provider.parse_streaming_response(stream, tx).await;
         });
 
@@ -1049,7 +1054,7 @@ mod tests {
             Message::new(MessageRole::Assistant, "Hi there!".to_string()),
         ];
 
-        let databricks_messages = provider.convert_messages(&messages).unwrap();
+        let databricks_messages = provider.convert_messages(messages).unwrap();
 
         assert_eq!(databricks_messages.len(), 3);
         assert_eq!(databricks_messages[0].role, "system");
@@ -1071,7 +1076,7 @@ mod tests {
         let messages = vec![Message::new(MessageRole::User, "Test message".to_string())];
 
         let request_body = provider
-            .create_request_body(&messages, None, false, 1000, 0.5)
+            .create_request_body(messages, None, false, 1000, 0.5)
             .unwrap();
 
         assert_eq!(request_body.max_tokens, 1000);
@@ -1165,7 +1170,7 @@ mod tests {
 
         // Test message WITHOUT cache_control
         let messages_without = vec![Message::new(MessageRole::User, "Hello".to_string())];
-        let databricks_messages_without = provider.convert_messages(&messages_without).unwrap();
+        let databricks_messages_without = provider.convert_messages(messages_without).unwrap();
         let json_without = serde_json::to_string(&databricks_messages_without).unwrap();
 
         println!("JSON without cache_control: {}", json_without);
@@ -1180,7 +1185,7 @@ mod tests {
             "Hello".to_string(),
             crate::CacheControl::ephemeral(),
         )];
-        let databricks_messages_with = provider.convert_messages(&messages_with).unwrap();
+        let databricks_messages_with = provider.convert_messages(messages_with).unwrap();
         let json_with = serde_json::to_string(&databricks_messages_with).unwrap();
 
         println!("JSON with cache_control: {}", json_with);
@@ -212,7 +212,7 @@ fn format_glm4(messages: &[Message]) -> String {
             MessageRole::User => "<|user|>",
             MessageRole::Assistant => "<|assistant|>",
         };
-        out.push_str(&format!("{}\n{}", role, msg.content));
+        out.push_str(&format!("{}\n{}", role, msg.full_content()));
     }
     out.push_str("<|assistant|>\n");
     out
@@ -227,7 +227,11 @@ fn format_qwen(messages: &[Message]) -> String {
             MessageRole::User => "user",
             MessageRole::Assistant => "assistant",
         };
-        out.push_str(&format!("<|im_start|>{}\n{}<|im_end|>\n", role, msg.content));
+        out.push_str(&format!(
+            "<|im_start|>{}\n{}<|im_end|>\n",
+            role,
+            msg.full_content()
+        ));
     }
     out.push_str("<|im_start|>assistant\n");
     out
@@ -242,7 +246,7 @@ fn format_mistral(messages: &[Message]) -> String {
         match msg.role {
             MessageRole::System if i == 0 => {
                 out.push_str("<s>[INST] ");
-                out.push_str(&msg.content);
+                out.push_str(&msg.full_content());
                 out.push_str("\n\n");
                 in_inst = true;
             }
@@ -251,13 +255,13 @@ fn format_mistral(messages: &[Message]) -> String {
                 if !in_inst {
                     out.push_str("<s>[INST] ");
                 }
-                out.push_str(&msg.content);
+                out.push_str(&msg.full_content());
                 out.push_str(" [/INST]");
                 in_inst = false;
             }
             MessageRole::Assistant => {
                 out.push(' ');
-                out.push_str(&msg.content);
+                out.push_str(&msg.full_content());
                 out.push_str("</s> ");
                 in_inst = false;
             }
@@ -276,13 +280,16 @@ fn format_llama(messages: &[Message]) -> String {
     for msg in messages {
         match msg.role {
             MessageRole::System => {
-                out.push_str(&format!("[INST] <<SYS>>\n{}\n<</SYS>>\n\n", msg.content));
+                out.push_str(&format!(
+                    "[INST] <<SYS>>\n{}\n<</SYS>>\n\n",
+                    msg.full_content()
+                ));
             }
             MessageRole::User => {
-                out.push_str(&format!("{} [/INST] ", msg.content));
+                out.push_str(&format!("{} [/INST] ", msg.full_content()));
             }
             MessageRole::Assistant => {
-                out.push_str(&format!("{} </s><s>[INST] ", msg.content));
+                out.push_str(&format!("{} </s><s>[INST] ", msg.full_content()));
             }
         }
     }
@@ -543,6 +550,7 @@ impl LLMProvider for EmbeddedProvider {
         let context_length = self.context_length;
         let threads = self.threads;
         let model_type = self.model_type.clone();
+        let cancellation_token = request.cancellation_token.clone().unwrap_or_default();
 
         tokio::task::spawn_blocking(move || {
             // Create adapter for model-specific tool format transformation (e.g., GLM)
@@ -568,6 +576,16 @@ impl LLMProvider for EmbeddedProvider {
             let mut stop_reason: Option<String> = None;
 
             for _ in 0..params.max_tokens {
+                // Stop generating once the request is cancelled or nobody is reading
+                if cancellation_token.is_cancelled() {
+                    debug!("Generation cancelled after {} tokens", token_count);
+                    let _ = tx.blocking_send(Err(anyhow::anyhow!("Request cancelled")));
+                    return;
+                }
+                if tx.is_closed() {
+                    return;
+                }
+
                 let new_token = prepared.sampler.sample(&prepared.ctx, prepared.batch.n_tokens() - 1);
                 prepared.sampler.accept(new_token);
 
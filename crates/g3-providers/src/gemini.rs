@@ -34,6 +34,7 @@
 //!         stream: false,
 //!         tools: None,
 //!         disable_thinking: false,
+//!         cancellation_token: None,
 //!     };
 //!
 //!     let response = provider.complete(request).await?;
@@ -53,7 +54,7 @@ use tracing::{debug, error};
 
 use crate::{
     CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider, Message,
-    MessageRole, Tool, ToolCall, Usage, streaming::make_text_chunk,
+    MessageRole, Tool, ToolCall, Usage, streaming::{make_keepalive_chunk, make_text_chunk, spawn_stream_task},
 };
 
 // ============================================================================
@@ -220,7 +221,7 @@ struct GeminiUsageMetadata {
 /// - Gemini uses "model" instead of "assistant"
 /// - System messages go in system_instruction, not contents
 /// - Gemini uses "parts" array with text objects
-fn convert_messages(messages: &[Message]) -> (Vec<GeminiContent>, Option<GeminiContent>) {
+fn convert_messages(messages: Vec<Message>) -> (Vec<GeminiContent>, Option<GeminiContent>) {
     let mut contents = Vec::new();
     let mut system_instruction = None;
 
@@ -230,20 +231,26 @@ fn convert_messages(messages: &[Message]) -> (Vec<GeminiContent>, Option<GeminiC
                 // System messages go to system_instruction
                 system_instruction = Some(GeminiContent {
                     role: None, // system_instruction doesn't need a role
-                    parts: vec![GeminiPart::Text { text: msg.content.clone() }],
+                    parts: vec![GeminiPart::Text {
+                        text: msg.into_full_content(),
+                    }],
                 });
             }
             MessageRole::User => {
                 contents.push(GeminiContent {
                     role: Some("user".to_string()),
-                    parts: vec![GeminiPart::Text { text: msg.content.clone() }],
+                    parts: vec![GeminiPart::Text {
+                        text: msg.into_full_content(),
+                    }],
                 });
             }
             MessageRole::Assistant => {
                 // Gemini uses "model" instead of "assistant"
                 contents.push(GeminiContent {
                     role: Some("model".to_string()),
-                    parts: vec![GeminiPart::Text { text: msg.content.clone() }],
+                    parts: vec![GeminiPart::Text {
+                        text: msg.into_full_content(),
+                    }],
                 });
             }
         }
@@ -390,6 +397,8 @@ async fn process_stream(
     while let Some(chunk_result) = response.chunk().await.transpose() {
         match chunk_result {
             Ok(bytes) => {
+                // Every frame restarts the consumer's stall watchdog
+                let _ = tx.try_send(Ok(make_keepalive_chunk()));
                 let text = match String::from_utf8(bytes.to_vec()) {
                     Ok(t) => t,
                     Err(e) => {
@@ -525,8 +534,9 @@ fn try_parse_json_from_buffer(buffer: &mut String) -> Option<String> {
 
 impl GeminiProvider {
     /// Build a GeminiRequest from a CompletionRequest.
-    fn build_request(&self, request: &CompletionRequest) -> GeminiRequest {
-        let (contents, system_instruction) = convert_messages(&request.messages);
+    fn build_request(&self, request: &mut CompletionRequest) -> GeminiRequest {
+        let (contents, system_instruction) =
+            convert_messages(std::mem::take(&mut request.messages));
         GeminiRequest {
             contents,
             system_instruction,
@@ -541,8 +551,8 @@ impl GeminiProvider {
 
 #[async_trait]
 impl LLMProvider for GeminiProvider {
-    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
-        let gemini_request = self.build_request(&request);
+    async fn complete(&self, mut request: CompletionRequest) -> Result<CompletionResponse> {
+        let gemini_request = self.build_request(&mut request);
 
         let url = self.get_api_url(false);
         debug!("Gemini request URL: {}", url);
@@ -584,8 +594,8 @@ impl LLMProvider for GeminiProvider {
         })
     }
 
-    async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
-        let gemini_request = self.build_request(&request);
+    async fn stream(&self, mut request: CompletionRequest) -> Result<CompletionStream> {
+        let gemini_request = self.build_request(&mut request);
 
         // For streaming, add alt=sse parameter
         let url = format!("{}&alt=sse", self.get_api_url(true));
@@ -607,7 +617,9 @@ impl LLMProvider for GeminiProvider {
         }
 
         let (tx, rx) = mpsc::channel(32);
-        tokio::spawn(process_stream(response, tx));
+        spawn_stream_task(tx, request.cancellation_token, |tx| {
+            process_stream(response, tx)
+        });
 
         Ok(ReceiverStream::new(rx))
     }
@@ -666,7 +678,7 @@ mod tests {
             Message::new(MessageRole::Assistant, "Hi there!".to_string()),
         ];
 
-        let (contents, system) = convert_messages(&messages);
+        let (contents, system) = convert_messages(messages);
 
         assert!(system.is_none());
         assert_eq!(contents.len(), 2);
@@ -681,7 +693,7 @@ mod tests {
             Message::new(MessageRole::User, "Hello".to_string()),
         ];
 
-        let (contents, system) = convert_messages(&messages);
+        let (contents, system) = convert_messages(messages);
 
         assert!(system.is_some());
         let sys = system.unwrap();
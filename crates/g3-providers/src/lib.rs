@@ -2,12 +2,16 @@ mod streaming;
 pub mod mock;
 pub use mock::{MockProvider, MockResponse, MockChunk};
 
-pub use streaming::{decode_utf8_streaming, is_incomplete_json_error, make_final_chunk, make_text_chunk, make_tool_chunk};
+pub use streaming::{decode_utf8_streaming, is_incomplete_json_error, make_final_chunk, make_keepalive_chunk, make_text_chunk, make_tool_chunk};
 
 use anyhow::Result;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 /// Trait for LLM providers
 #[async_trait::async_trait]
@@ -56,6 +60,10 @@ pub struct CompletionRequest {
     pub tools: Option<Vec<Tool>>,
     /// Force disable thinking mode for this request (used when max_tokens is too low)
     pub disable_thinking: bool,
+    /// Cancelling this token aborts a streaming request: the HTTP response is
+    /// dropped (closing the connection) and no further chunks are sent.
+    #[serde(skip)]
+    pub cancellation_token: Option<CancellationToken>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,9 +103,12 @@ impl CacheControl {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A stored body is serialized as its location rather than its content (see
+/// `Message::store_body`), so saving a session never reads it back.
+#[derive(Debug, Clone, Deserialize)]
 pub struct Message {
     pub role: MessageRole,
+    /// Message text, or a short preview when the body is stored on disk
     pub content: String,
     #[serde(skip)]
     pub images: Vec<ImageContent>,
@@ -107,6 +118,44 @@ pub struct Message {
     pub kind: MessageKind,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cache_control: Option<CacheControl>,
+    #[serde(default, rename = "body", deserialize_with = "deserialize_stored_body")]
+    pub stored_body: Option<Arc<StoredBody>>,
+}
+
+/// Location of a message body that was moved out of memory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredBody {
+    pub path: PathBuf,
+    /// Length of the full content in bytes
+    pub len: usize,
+}
+
+/// Characters of a stored body kept in memory as `Message::content`.
+const STORED_BODY_PREVIEW_CHARS: usize = 1_000;
+
+impl Serialize for Message {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let fields =
+            2 + self.cache_control.is_some() as usize + self.stored_body.is_some() as usize;
+        let mut state = serializer.serialize_struct("Message", fields)?;
+        state.serialize_field("role", &self.role)?;
+        state.serialize_field("content", &self.content)?;
+        if let Some(cache_control) = &self.cache_control {
+            state.serialize_field("cache_control", cache_control)?;
+        }
+        if let Some(body) = &self.stored_body {
+            state.serialize_field("body", body.as_ref())?;
+        }
+        state.end()
+    }
+}
+
+fn deserialize_stored_body<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Arc<StoredBody>>, D::Error> {
+    Ok(Option::<StoredBody>::deserialize(deserializer)?.map(Arc::new))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -129,6 +178,8 @@ pub enum MessageKind {
     Summary,
     /// Rehydrated content (restored from a fragment)
     Rehydrated,
+    /// Pinned message, kept verbatim when the context is summarized
+    Pinned,
 }
 
 /// Image content for multimodal messages
@@ -224,6 +275,19 @@ pub struct CompletionChunk {
     pub tool_call_streaming: Option<String>,
 }
 
+impl CompletionChunk {
+    /// True for a chunk that carries nothing, sent only to show the stream is
+    /// alive (see `streaming::with_keepalives`).
+    pub fn is_keepalive(&self) -> bool {
+        self.content.is_empty()
+            && !self.finished
+            && self.tool_calls.is_none()
+            && self.usage.is_none()
+            && self.stop_reason.is_none()
+            && self.tool_call_streaming.is_none()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCall {
     pub id: String,
@@ -279,6 +343,7 @@ impl Message {
             id: Self::generate_id(),
             kind: MessageKind::Regular,
             cache_control: None,
+            stored_body: None,
         }
     }
 
@@ -295,6 +360,7 @@ impl Message {
             id: Self::generate_id(),
             kind: MessageKind::Regular,
             cache_control: Some(cache_control),
+            stored_body: None,
         }
     }
 
@@ -307,6 +373,7 @@ impl Message {
             id: Self::generate_id(),
             kind,
             cache_control: None,
+            stored_body: None,
         }
     }
 
@@ -320,6 +387,106 @@ impl Message {
         self.kind == MessageKind::Summary
     }
 
+    /// Check if this message is pinned
+    pub fn is_pinned(&self) -> bool {
+        self.kind == MessageKind::Pinned
+    }
+
+    /// The full message content, loading a stored body from disk if needed.
+    ///
+    /// Requests are sent with their bodies already loaded (see `load_bodies`),
+    /// so providers building them never read from disk here; `content` only
+    /// holds a preview for stored bodies.
+    pub fn full_content(&self) -> Cow<'_, str> {
+        let Some(body) = &self.stored_body else {
+            return Cow::Borrowed(&self.content);
+        };
+        match std::fs::read_to_string(&body.path) {
+            Ok(content) => Cow::Owned(content),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load message body from {}: {}",
+                    body.path.display(),
+                    e
+                );
+                Cow::Borrowed(&self.content)
+            }
+        }
+    }
+
+    /// The full message content, consuming the message. In-memory content is
+    /// moved out rather than copied, and a stored body is read from disk once.
+    ///
+    /// Providers use this when building a request, so a stored body is only
+    /// loaded when its message is sent.
+    pub fn into_full_content(self) -> String {
+        if self.stored_body.is_some() {
+            if let Cow::Owned(content) = self.full_content() {
+                return content;
+            }
+        }
+        self.content
+    }
+
+    /// Length of the full content in bytes, without loading a stored body.
+    pub fn content_len(&self) -> usize {
+        self.stored_body
+            .as_ref()
+            .map_or(self.content.len(), |body| body.len)
+    }
+
+    /// Read a stored body back into `content` without blocking the runtime.
+    /// If it can't be read the preview is kept in its place.
+    pub async fn load_body(&mut self) {
+        let Some(body) = self.stored_body.take() else {
+            return;
+        };
+        match tokio::fs::read_to_string(&body.path).await {
+            Ok(content) => self.content = content,
+            Err(e) => tracing::warn!(
+                "Failed to load message body from {}: {}",
+                body.path.display(),
+                e
+            ),
+        }
+    }
+
+    /// Replace the content, dropping any stored body.
+    pub fn set_content(&mut self, content: String) {
+        self.content = content;
+        self.stored_body = None;
+    }
+
+    /// Move the content into a file under `dir` if it is longer than
+    /// `threshold` bytes, keeping only a preview in memory.
+    ///
+    /// Keeps cloning the conversation history cheap when it contains large
+    /// tool results. Returns whether the body was stored.
+    pub fn store_body(&mut self, dir: &Path, threshold: usize) -> std::io::Result<bool> {
+        if self.stored_body.is_some() || self.content.len() <= threshold {
+            return Ok(false);
+        }
+
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.txt", Self::generate_id()));
+        std::fs::write(&path, &self.content)?;
+
+        let len = self.content.len();
+        let preview: String = self
+            .content
+            .chars()
+            .take(STORED_BODY_PREVIEW_CHARS)
+            .collect();
+        self.content = format!(
+            "{}\n\n[... {} bytes total, full content in {}]",
+            preview,
+            len,
+            path.display()
+        );
+        self.stored_body = Some(Arc::new(StoredBody { path, len }));
+        Ok(true)
+    }
+
     /// Create a message with cache control, with provider validation
     pub fn with_cache_control_validated(
         role: MessageRole,
@@ -340,6 +507,13 @@ impl Message {
     }
 }
 
+/// Load the stored bodies of a request's messages before it is sent.
+pub async fn load_bodies(messages: &mut [Message]) {
+    for message in messages {
+        message.load_body().await;
+    }
+}
+
 /// Provider registry for managing multiple LLM providers
 pub struct ProviderRegistry {
     providers: HashMap<String, Box<dyn LLMProvider>>,
@@ -394,6 +568,55 @@ impl Default for ProviderRegistry {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_store_body_round_trip() {
+        let dir = std::env::temp_dir().join(format!("g3-bodies-{}", std::process::id()));
+        let content = format!("Tool result: {}", "x".repeat(5_000));
+
+        let mut small = Message::new(MessageRole::User, "short".to_string());
+        assert!(!small.store_body(&dir, 100).unwrap());
+
+        let mut msg = Message::new(MessageRole::User, content.clone());
+        assert!(msg.store_body(&dir, 100).unwrap());
+        assert!(msg.content.len() < content.len());
+        assert!(msg.content.starts_with("Tool result: "));
+        assert_eq!(msg.content_len(), content.len());
+        assert_eq!(msg.full_content(), content);
+        assert_eq!(msg.clone().into_full_content(), content);
+
+        // Serialization writes the location, and deserializing restores it
+        let json = serde_json::to_value(msg.clone()).unwrap();
+        assert_eq!(json["content"], msg.content);
+        assert_eq!(json["body"]["len"], content.len());
+        let restored: Message = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.stored_body, msg.stored_body);
+        assert_eq!(restored.full_content(), content);
+
+        msg.set_content("replaced".to_string());
+        assert_eq!(msg.full_content(), "replaced");
+        assert_eq!(msg.content_len(), 8);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_load_bodies() {
+        let dir = std::env::temp_dir().join(format!("g3-load-bodies-{}", std::process::id()));
+        let content = format!("Tool result: {}", "x".repeat(5_000));
+        let mut messages = vec![
+            Message::new(MessageRole::User, content.clone()),
+            Message::new(MessageRole::Assistant, "ok".to_string()),
+        ];
+        assert!(messages[0].store_body(&dir, 100).unwrap());
+
+        load_bodies(&mut messages).await;
+        assert!(messages[0].stored_body.is_none());
+        assert_eq!(messages[0].content, content);
+        assert_eq!(messages[1].content, "ok");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_message_serialization_without_cache_control() {
         let msg = Message::new(MessageRole::User, "Hello".to_string());
@@ -27,8 +27,8 @@
 //! ```
 
 use crate::{
-    CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider,
-    ToolCall, Usage,
+    streaming::spawn_stream_task, CompletionChunk, CompletionRequest, CompletionResponse,
+    CompletionStream, LLMProvider, ToolCall, Usage,
 };
 use anyhow::Result;
 use std::sync::{Arc, Mutex};
@@ -398,6 +398,8 @@ impl LLMProvider for MockProvider {
     }
 
     async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
+        let cancellation_token = request.cancellation_token.clone();
+
         // Record the request
         self.requests.lock().unwrap().push(request);
 
@@ -409,7 +411,7 @@ impl LLMProvider for MockProvider {
         let num_chunks = response.chunks.len();
 
         // Spawn a task to send chunks
-        tokio::spawn(async move {
+        spawn_stream_task(tx, cancellation_token, move |tx| async move {
             for (i, chunk) in response.chunks.into_iter().enumerate() {
                 let is_last = chunk.finished;
                 let completion_chunk = CompletionChunk {
@@ -522,6 +524,7 @@ mod tests {
             stream: false,
             tools: None,
             disable_thinking: false,
+            cancellation_token: None,
         };
 
         let response = provider.complete(request).await.unwrap();
@@ -541,6 +544,7 @@ mod tests {
             stream: true,
             tools: None,
             disable_thinking: false,
+            cancellation_token: None,
         };
 
         let mut stream = provider.stream(request).await.unwrap();
@@ -557,6 +561,35 @@ mod tests {
         assert_eq!(chunk_count, 3); // 2 content chunks + 1 finish chunk
     }
 
+    #[tokio::test]
+    async fn test_mock_provider_streaming_cancelled() {
+        let provider = MockProvider::new()
+            .with_response(MockResponse::streaming(vec!["one ", "two ", "three"]));
+        let token = tokio_util::sync::CancellationToken::new();
+        token.cancel();
+
+        let request = CompletionRequest {
+            messages: vec![],
+            max_tokens: None,
+            temperature: None,
+            stream: true,
+            tools: None,
+            disable_thinking: false,
+            cancellation_token: Some(token),
+        };
+
+        let mut stream = provider.stream(request).await.unwrap();
+        let mut results = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            results.push(chunk);
+        }
+
+        // The stream ends with an error and never reaches the finish chunk
+        let last = results.pop().unwrap();
+        assert!(last.unwrap_err().to_string().contains("cancelled"));
+        assert!(results.iter().all(|r| matches!(r, Ok(c) if !c.finished)));
+    }
+
     #[tokio::test]
     async fn test_mock_provider_multi_turn() {
         let provider = scenarios::multi_turn(vec!["First response", "Second response"]);
@@ -568,6 +601,7 @@ mod tests {
             stream: false,
             tools: None,
             disable_thinking: false,
+            cancellation_token: None,
         };
 
         let response1 = provider.complete(request.clone()).await.unwrap();
@@ -593,6 +627,7 @@ mod tests {
             stream: true,
             tools: None,
             disable_thinking: false,
+            cancellation_token: None,
         };
 
         let mut stream = provider.stream(request).await.unwrap();
@@ -621,6 +656,7 @@ mod tests {
             stream: false,
             tools: None,
             disable_thinking: false,
+            cancellation_token: None,
         };
 
         let request2 = CompletionRequest {
@@ -633,6 +669,7 @@ mod tests {
             stream: false,
             tools: None,
             disable_thinking: false,
+            cancellation_token: None,
         };
 
         provider.complete(request1).await.unwrap();
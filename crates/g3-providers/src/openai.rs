@@ -12,7 +12,7 @@ use tracing::{debug, error};
 use crate::{
     CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider, Message,
     MessageRole, Tool, ToolCall, Usage,
-    streaming::{make_text_chunk, make_final_chunk},
+    streaming::{make_text_chunk, make_final_chunk, spawn_stream_task, with_keepalives},
 };
 
 #[derive(Clone)]
@@ -378,7 +378,8 @@ impl LLMProvider for OpenAIProvider {
 
         // Spawn task to process the stream
         let provider = self.clone();
-        tokio::spawn(async move {
+        spawn_stream_task(tx, request.cancellation_token, move |tx| async move {
+            let stream = with_keepalives(stream, tx.clone());
             let usage = provider.parse_streaming_response(stream, tx).await;
             // Log the final usage if available
             if let Some(usage) = usage {
@@ -424,7 +425,7 @@ fn convert_messages(messages: &[Message]) -> Vec<serde_json::Value> {
                     MessageRole::User => "user",
                     MessageRole::Assistant => "assistant",
                 },
-                "content": msg.content,
+                "content": msg.full_content(),
             })
         })
         .collect()
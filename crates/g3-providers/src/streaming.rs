@@ -3,6 +3,14 @@
 //! This module provides common helpers used by multiple LLM providers
 //! for handling Server-Sent Events (SSE) streaming responses.
 
+use std::future::Future;
+
+use anyhow::{anyhow, Result};
+use futures_util::{Stream, StreamExt};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
 use crate::{CompletionChunk, ToolCall, Usage};
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -130,3 +138,71 @@ pub fn make_tool_streaming_active() -> CompletionChunk {
         tool_call_streaming: Some(String::new()), // Empty string signals "active" vs "detected"
     }
 }
+
+/// Create a keep-alive chunk: no content, only a sign that the connection
+/// is alive. Consumers skip it, but it restarts their stall watchdog.
+pub fn make_keepalive_chunk() -> CompletionChunk {
+    CompletionChunk {
+        content: String::new(),
+        finished: false,
+        usage: None,
+        tool_calls: None,
+        stop_reason: None,
+        tool_call_streaming: None,
+    }
+}
+
+/// Wrap a response's network frames so each one sends a keep-alive chunk on
+/// `tx`. Pings and thinking deltas that never become chunks of their own
+/// still show the stream is alive.
+///
+/// Keep-alives are dropped when the channel is full: the consumer has chunks
+/// to read, so it isn't waiting.
+pub fn with_keepalives<S: Stream + Unpin>(
+    stream: S,
+    tx: mpsc::Sender<Result<CompletionChunk>>,
+) -> impl Stream<Item = S::Item> + Unpin {
+    stream.inspect(move |_| {
+        let _ = tx.try_send(Ok(make_keepalive_chunk()));
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Stream Task Lifecycle
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Spawn the task (built by `task` from `tx`) that turns a provider response
+/// into chunks on `tx`.
+///
+/// The task is dropped as soon as `cancellation_token` is cancelled or the
+/// receiving `CompletionStream` is dropped. Dropping it drops the HTTP response
+/// it owns, which closes the connection so the provider stops generating (and
+/// billing) instead of streaming into a channel nobody reads. A cancelled
+/// stream ends with a "Request cancelled" error.
+pub fn spawn_stream_task<F, Fut>(
+    tx: mpsc::Sender<Result<CompletionChunk>>,
+    cancellation_token: Option<CancellationToken>,
+    task: F,
+) where
+    F: FnOnce(mpsc::Sender<Result<CompletionChunk>>) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let receiver_watch = tx.clone();
+    let task = task(tx);
+    tokio::spawn(async move {
+        let cancelled = async {
+            match cancellation_token {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = task => {}
+            _ = cancelled => {
+                debug!("Stream cancelled, aborting provider request");
+                let _ = receiver_watch.try_send(Err(anyhow!("Request cancelled")));
+            }
+            _ = receiver_watch.closed() => debug!("Stream receiver dropped, aborting provider request"),
+        }
+    });
+}
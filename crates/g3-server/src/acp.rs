@@ -0,0 +1,844 @@
+//! Agent Client Protocol server for running g3 inside an editor (`g3 acp`).
+//!
+//! ACP is the protocol Zed and other editors use to drive coding agents:
+//! newline-delimited JSON-RPC on stdin/stdout, with the editor as the client.
+//! Every ACP session (`session/new`) is a `ServerSession` working in the
+//! editor's project directory. `session/prompt` runs a task in it and streams
+//! the agent's output back as `session/update` notifications:
+//! - response text as `agent_message_chunk`
+//! - every tool call as a `tool_call`, followed by a `tool_call_update` with
+//!   its output once it finishes; calls that edit files also carry a `diff`
+//!   (old and new text) of each file, which the editor shows in its own diff
+//!   view
+//! - approvals of risky tool calls as `session/request_permission` requests
+//!
+//! `session/cancel` cancels the running task, ending the prompt with stop
+//! reason `cancelled`. MCP servers passed to `session/new` are added to the
+//! session's `[mcp]` servers.
+//!
+//! g3's tools read and write files on disk, not through the editor's buffers,
+//! and resolve relative paths against the directory g3 was started in, so
+//! editors should start it in the project.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+use g3_config::{Config, McpServerConfig};
+use g3_core::ui_writer::ToolObserver;
+use g3_core::AgentEvent;
+
+use crate::session::{ServerEvent, ServerSession, SessionEvent, SessionOptions};
+
+/// The ACP version spoken.
+const PROTOCOL_VERSION: u64 = 1;
+
+/// Tools that edit the files named by their `file_path` arguments.
+const EDIT_TOOLS: &[&str] = &["write_file", "str_replace", "edit_lines", "multi_edit"];
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Sends messages to the client and matches its responses to the requests
+/// sent to it.
+struct Client {
+    outgoing: mpsc::UnboundedSender<Value>,
+    last_id: AtomicU64,
+    pending: Mutex<HashMap<u64, oneshot::Sender<Value>>>,
+}
+
+impl Client {
+    fn send(&self, message: Value) {
+        let _ = self.outgoing.send(message);
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn respond(&self, id: Value, result: Result<Value, (i64, String)>) {
+        self.send(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message }
+            }),
+        });
+    }
+
+    /// Send a request and wait for its result.
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.last_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let (response_tx, response_rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, response_tx);
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+
+        let response = response_rx.await.context("The client went away")?;
+        if let Some(error) = response.get("error") {
+            bail!("{} failed: {}", method, error["message"]);
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Hand a response from the client to the request waiting for it.
+    fn resolve(&self, response: Value) {
+        let Some(id) = response.get("id").and_then(|v| v.as_u64()) else {
+            return;
+        };
+        match self.pending.lock().unwrap().remove(&id) {
+            Some(response_tx) => {
+                let _ = response_tx.send(response);
+            }
+            None => debug!("Response to unknown request {}", id),
+        }
+    }
+}
+
+/// The text of files about to be edited, taken on the agent's thread before
+/// the edit runs (`None`: the file didn't exist).
+#[derive(Default)]
+struct Snapshots(Mutex<HashMap<PathBuf, Option<String>>>);
+
+impl ToolObserver for Snapshots {
+    fn tool_starting(&self, tool: &str, args: Option<&Value>) {
+        let mut snapshots = self.0.lock().unwrap();
+        for path in edited_paths(tool, args) {
+            let text = std::fs::read_to_string(&path).ok();
+            snapshots.insert(path, text);
+        }
+    }
+}
+
+struct AcpSession {
+    session: ServerSession,
+    snapshots: Arc<Snapshots>,
+}
+
+/// The state of one ACP connection.
+pub struct AcpServer {
+    config: Config,
+    project_context: Option<String>,
+    client: Client,
+    sessions: Mutex<HashMap<String, Arc<AcpSession>>>,
+}
+
+impl AcpServer {
+    /// Sessions get `config` and `project_context` (AGENTS.md, memory, ...).
+    /// Returns the server and the receiver of the messages it sends.
+    pub fn new(
+        config: Config,
+        project_context: Option<String>,
+    ) -> (Self, mpsc::UnboundedReceiver<Value>) {
+        let (outgoing, receiver) = mpsc::unbounded_channel();
+        let server = Self {
+            config,
+            project_context,
+            client: Client {
+                outgoing,
+                last_id: AtomicU64::new(0),
+                pending: Mutex::new(HashMap::new()),
+            },
+            sessions: Mutex::new(HashMap::new()),
+        };
+        (server, receiver)
+    }
+
+    /// Handle a message from the client: a request, a notification or the
+    /// response to a request of ours. A prompt is answered once its task is
+    /// done, so every message should be handled in a task of its own.
+    pub async fn handle(self: Arc<Self>, message: Value) {
+        let Some(method) = message.get("method").and_then(|v| v.as_str()) else {
+            self.client.resolve(message);
+            return;
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        debug!("ACP message {}", method);
+
+        let Some(id) = message.get("id").cloned() else {
+            if method == "session/cancel" {
+                if let Ok(session) = self.session(&params) {
+                    session.session.cancel();
+                }
+            }
+            return;
+        };
+        let result = match method {
+            "initialize" => Ok(initialize_result()),
+            "authenticate" => Ok(json!({})),
+            "session/new" => self.new_session(&params).await,
+            "session/prompt" => self.prompt(&params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+        };
+        self.client.respond(id, result);
+    }
+
+    fn session(&self, params: &Value) -> Result<Arc<AcpSession>, (i64, String)> {
+        let session_id = params
+            .get("sessionId")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| (INVALID_PARAMS, "Missing sessionId".to_string()))?;
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| (INVALID_PARAMS, format!("Unknown session '{}'", session_id)))
+    }
+
+    async fn new_session(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let cwd = params
+            .get("cwd")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| (INVALID_PARAMS, "Missing cwd".to_string()))?;
+        let mut config = self.config.clone();
+        let servers = params.get("mcpServers").and_then(|v| v.as_array());
+        for server in servers.into_iter().flatten() {
+            let (name, server) = mcp_server_config(server)
+                .ok_or_else(|| (INVALID_PARAMS, format!("Unsupported MCP server {}", server)))?;
+            config.mcp.servers.insert(name, server);
+        }
+
+        let snapshots = Arc::new(Snapshots::default());
+        let options = SessionOptions {
+            working_dir: Some(cwd.to_string()),
+            tool_observer: Some(snapshots.clone()),
+        };
+        let session = ServerSession::start_with(config, self.project_context.clone(), options)
+            .await
+            .map_err(|e| (INTERNAL_ERROR, format!("{:#}", e)))?;
+        let session_id = session.id.clone();
+        info!("Started ACP session {} in {}", session_id, cwd);
+        self.sessions.lock().unwrap().insert(
+            session_id.clone(),
+            Arc::new(AcpSession { session, snapshots }),
+        );
+        Ok(json!({ "sessionId": session_id }))
+    }
+
+    /// Run the prompt as a task, streaming its events as `session/update`s,
+    /// and return why it stopped.
+    async fn prompt(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let session = self.session(params)?;
+        let session_id = session.session.id.clone();
+        let task = prompt_text(params.get("prompt").unwrap_or(&Value::Null));
+        if task.trim().is_empty() {
+            return Err((INVALID_PARAMS, "Empty prompt".to_string()));
+        }
+
+        let mut events = session.session.subscribe();
+        if !session.session.submit(task) {
+            return Err((INVALID_PARAMS, "A prompt is already running".to_string()));
+        }
+        let mut turn = Turn::new(session.snapshots.clone());
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("ACP session {} skipped {} events", session_id, skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => {
+                    return Err((INTERNAL_ERROR, "The session stopped".to_string()))
+                }
+            };
+            match event {
+                ServerEvent::Agent(event) => {
+                    for update in turn.updates(&event) {
+                        self.client.notify(
+                            "session/update",
+                            json!({ "sessionId": session_id, "update": update }),
+                        );
+                    }
+                }
+                ServerEvent::Session(SessionEvent::ApprovalRequired {
+                    approval_id,
+                    message,
+                    options,
+                }) => {
+                    let answer = self
+                        .request_permission(&session_id, &mut turn, &message, &options)
+                        .await;
+                    session.session.answer(approval_id, answer);
+                }
+                ServerEvent::Session(SessionEvent::TaskStarted { .. }) => {}
+                ServerEvent::Session(SessionEvent::TaskFinished { .. }) => {
+                    return Ok(json!({ "stopReason": "end_turn" }))
+                }
+                ServerEvent::Session(SessionEvent::TaskCancelled) => {
+                    return Ok(json!({ "stopReason": "cancelled" }))
+                }
+                ServerEvent::Session(SessionEvent::TaskFailed { error }) => {
+                    return Err((INTERNAL_ERROR, error))
+                }
+            }
+        }
+    }
+
+    /// Ask the user to approve the running tool call. Returns the index of
+    /// the option chosen; anything but a choice declines.
+    async fn request_permission(
+        &self,
+        session_id: &str,
+        turn: &mut Turn,
+        message: &str,
+        options: &[String],
+    ) -> usize {
+        let params = json!({
+            "sessionId": session_id,
+            "toolCall": { "toolCallId": turn.tool_call_id(), "title": message },
+            "options": permission_options(options)
+        });
+        let outcome = match self
+            .client
+            .request("session/request_permission", params)
+            .await
+        {
+            Ok(result) => result["outcome"].clone(),
+            Err(e) => {
+                warn!("{:#}", e);
+                Value::Null
+            }
+        };
+        let chosen = match outcome["outcome"].as_str() {
+            Some("selected") => outcome["optionId"].as_str().and_then(|id| id.parse().ok()),
+            _ => None,
+        };
+        chosen.unwrap_or_else(|| decline_index(options))
+    }
+}
+
+/// Serve ACP on stdin/stdout until the client closes stdin.
+pub async fn serve_acp(config: Config, project_context: Option<String>) -> Result<()> {
+    let (server, mut outgoing) = AcpServer::new(config, project_context);
+    let server = Arc::new(server);
+    tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(message) = outgoing.recv().await {
+            let line = format!("{}\n", message);
+            if stdout.write_all(line.as_bytes()).await.is_err() || stdout.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    info!("g3 ACP agent ready on stdio");
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Value>(&line) {
+            Ok(message) => {
+                tokio::spawn(server.clone().handle(message));
+            }
+            Err(e) => server.client.send(json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": PARSE_ERROR, "message": e.to_string() }
+            })),
+        }
+    }
+    Ok(())
+}
+
+/// A tool call reported to the client and not finished yet.
+struct OpenToolCall {
+    id: String,
+    tool: String,
+    args: Option<Value>,
+}
+
+/// Turns the `AgentEvent`s of a prompt into `session/update`s.
+struct Turn {
+    snapshots: Arc<Snapshots>,
+    tool_call: Option<OpenToolCall>,
+}
+
+impl Turn {
+    fn new(snapshots: Arc<Snapshots>) -> Self {
+        Self {
+            snapshots,
+            tool_call: None,
+        }
+    }
+
+    /// The ID of the running tool call, or a new one if none is running.
+    fn tool_call_id(&self) -> String {
+        match &self.tool_call {
+            Some(tool_call) => tool_call.id.clone(),
+            None => new_tool_call_id(),
+        }
+    }
+
+    fn updates(&mut self, event: &AgentEvent) -> Vec<Value> {
+        match event {
+            AgentEvent::AgentResponse { text } => vec![json!({
+                "sessionUpdate": "agent_message_chunk",
+                "content": { "type": "text", "text": text }
+            })],
+            AgentEvent::ToolHeader { tool, args } => {
+                let mut updates: Vec<Value> = self.finish(None).into_iter().collect();
+                let id = new_tool_call_id();
+                let locations: Vec<Value> = locations(tool, args.as_ref())
+                    .iter()
+                    .map(|path| json!({ "path": path }))
+                    .collect();
+                updates.push(json!({
+                    "sessionUpdate": "tool_call",
+                    "toolCallId": id,
+                    "title": tool_title(tool, args.as_ref()),
+                    "kind": tool_kind(tool),
+                    "status": "in_progress",
+                    "locations": locations,
+                    "rawInput": args
+                }));
+                self.tool_call = Some(OpenToolCall {
+                    id,
+                    tool: tool.clone(),
+                    args: args.clone(),
+                });
+                updates
+            }
+            AgentEvent::ToolResult {
+                output, success, ..
+            } => self.finish(Some((output, *success))).into_iter().collect(),
+            // Tools without a result (e.g. timed out) are done now
+            AgentEvent::ToolTiming { .. } => self.finish(None).into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Finish the open tool call, with its result if it has one: the diffs
+    /// of the files it edited, then its output.
+    fn finish(&mut self, result: Option<(&String, bool)>) -> Option<Value> {
+        let tool_call = self.tool_call.take()?;
+        let mut content = Vec::new();
+        for path in edited_paths(&tool_call.tool, tool_call.args.as_ref()) {
+            let old_text = self.snapshots.0.lock().unwrap().remove(&path).flatten();
+            let Ok(new_text) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if old_text.as_ref() != Some(&new_text) {
+                content.push(json!({
+                    "type": "diff",
+                    "path": path,
+                    "oldText": old_text,
+                    "newText": new_text
+                }));
+            }
+        }
+        let success = match result {
+            Some((output, success)) => {
+                content.push(json!({
+                    "type": "content",
+                    "content": { "type": "text", "text": output }
+                }));
+                success
+            }
+            None => true,
+        };
+        Some(json!({
+            "sessionUpdate": "tool_call_update",
+            "toolCallId": tool_call.id,
+            "status": if success { "completed" } else { "failed" },
+            "content": content
+        }))
+    }
+}
+
+fn new_tool_call_id() -> String {
+    format!("call_{}", &uuid::Uuid::new_v4().simple().to_string()[..12])
+}
+
+/// The result of `initialize`.
+fn initialize_result() -> Value {
+    json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "agentCapabilities": {
+            "loadSession": false,
+            "promptCapabilities": { "image": false, "audio": false, "embeddedContext": true },
+            "mcpCapabilities": { "http": true, "sse": false }
+        },
+        "authMethods": [],
+        "agentInfo": { "name": "g3", "version": env!("CARGO_PKG_VERSION") }
+    })
+}
+
+/// The task for a prompt's content blocks. Embedded resources are included
+/// with their URI; images and audio are not supported.
+fn prompt_text(prompt: &Value) -> String {
+    let blocks = prompt.as_array().map(Vec::as_slice).unwrap_or_default();
+    let parts: Vec<String> = blocks
+        .iter()
+        .filter_map(|block| match block["type"].as_str()? {
+            "text" => Some(block["text"].as_str()?.to_string()),
+            "resource_link" => Some(format!("@{}", block["uri"].as_str()?)),
+            "resource" => {
+                let resource = &block["resource"];
+                Some(format!(
+                    "Contents of {}:\n```\n{}\n```",
+                    resource["uri"].as_str()?,
+                    resource["text"].as_str()?
+                ))
+            }
+            _ => None,
+        })
+        .collect();
+    parts.join("\n\n")
+}
+
+/// An MCP server of `session/new` as an `[mcp.servers]` entry, with its
+/// name. `None` for transports g3 doesn't speak (SSE).
+fn mcp_server_config(server: &Value) -> Option<(String, McpServerConfig)> {
+    let name = server["name"].as_str()?.to_string();
+    // Both are lists of {name, value}
+    let pairs = |key: &str| -> HashMap<String, String> {
+        server[key]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|pair| {
+                Some((
+                    pair["name"].as_str()?.to_string(),
+                    pair["value"].as_str()?.to_string(),
+                ))
+            })
+            .collect()
+    };
+    let config = match server["type"].as_str() {
+        None | Some("stdio") => McpServerConfig {
+            command: Some(server["command"].as_str()?.to_string()),
+            args: server["args"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|arg| arg.as_str().map(str::to_string))
+                .collect(),
+            env: pairs("env"),
+            url: None,
+            headers: HashMap::new(),
+            enabled: true,
+        },
+        Some("http") => McpServerConfig {
+            command: None,
+            args: Vec::new(),
+            env: HashMap::new(),
+            url: Some(server["url"].as_str()?.to_string()),
+            headers: pairs("headers"),
+            enabled: true,
+        },
+        Some(_) => return None,
+    };
+    Some((name, config))
+}
+
+/// The options of an approval; yes/no prompts become allow and reject.
+fn permission_options(options: &[String]) -> Vec<Value> {
+    options
+        .iter()
+        .enumerate()
+        .map(|(index, option)| {
+            let (name, kind) = match option.as_str() {
+                "yes" => ("Allow", "allow_once"),
+                "no" => ("Reject", "reject_once"),
+                other => (other, "allow_once"),
+            };
+            json!({ "optionId": index.to_string(), "name": name, "kind": kind })
+        })
+        .collect()
+}
+
+/// The option declining an approval: "no", or else the last one.
+fn decline_index(options: &[String]) -> usize {
+    options
+        .iter()
+        .position(|option| option == "no")
+        .unwrap_or(options.len().saturating_sub(1))
+}
+
+/// The files an edit tool will change, as absolute paths: relative ones
+/// are taken from the call's `working_dir`, else the current directory.
+fn edited_paths(tool: &str, args: Option<&Value>) -> Vec<PathBuf> {
+    let Some(args) = args.filter(|_| EDIT_TOOLS.contains(&tool)) else {
+        return Vec::new();
+    };
+    let paths: Vec<&str> = match args.get("edits").and_then(|v| v.as_array()) {
+        Some(edits) => edits
+            .iter()
+            .filter_map(|edit| edit["file_path"].as_str())
+            .collect(),
+        None => args["file_path"].as_str().into_iter().collect(),
+    };
+    let base = std::env::current_dir().unwrap_or_default();
+    let base = match args["working_dir"].as_str() {
+        Some(dir) => base.join(expand_home(dir)),
+        None => base,
+    };
+    let mut resolved: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        let path = base.join(expand_home(path));
+        if !resolved.contains(&path) {
+            resolved.push(path);
+        }
+    }
+    resolved
+}
+
+/// `path` with a leading `~` replaced by the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Where the editor can follow the tool call: the files it edits or reads.
+fn locations(tool: &str, args: Option<&Value>) -> Vec<PathBuf> {
+    let edited = edited_paths(tool, args);
+    if !edited.is_empty() {
+        return edited;
+    }
+    match (tool, args.and_then(|args| args["file_path"].as_str())) {
+        ("read_file", Some(path)) => {
+            vec![std::env::current_dir()
+                .unwrap_or_default()
+                .join(expand_home(path))]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The tool and its main argument, e.g. `shell: cargo test`.
+fn tool_title(tool: &str, args: Option<&Value>) -> String {
+    const MAIN_ARGS: &[&str] = &["command", "file_path", "path", "pattern", "query", "url"];
+    let main_arg = args.and_then(|args| {
+        MAIN_ARGS
+            .iter()
+            .find_map(|key| args.get(*key).and_then(|v| v.as_str()))
+    });
+    match main_arg {
+        Some(value) => {
+            let value: String = value
+                .lines()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .take(80)
+                .collect();
+            format!("{}: {}", tool, value)
+        }
+        None => tool.to_string(),
+    }
+}
+
+/// The ACP tool kind, which editors pick icons by.
+fn tool_kind(tool: &str) -> &'static str {
+    match tool {
+        "read_file" | "read_many_files" | "read_output" | "read_image" | "list_dir"
+        | "notebook_read" | "git_status" | "git_diff" | "ast_outline" => "read",
+        "write_file" | "str_replace" | "multi_edit" | "edit_lines" | "regex_replace"
+        | "notebook_edit" | "rename_symbol" => "edit",
+        "grep" | "code_search" | "find_definition" | "find_references" | "symbol_lookup"
+        | "dead_code" => "search",
+        "shell" | "background_process" | "cargo" | "python" | "node_eval" | "notebook_run"
+        | "docker" | "k8s" | "sql_query" | "verify" | "git_commit" => "execute",
+        "http_request" | "research" => "fetch",
+        "todo_read" | "todo_write" | "plan_read" | "plan_write" => "think",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn request(
+        server: &Arc<AcpServer>,
+        outgoing: &mut mpsc::UnboundedReceiver<Value>,
+        method: &str,
+        params: Value,
+    ) -> Value {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        server.clone().handle(message).await;
+        outgoing.try_recv().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_protocol() {
+        let (server, mut outgoing) = AcpServer::new(Config::default(), None);
+        let server = Arc::new(server);
+
+        let init = request(
+            &server,
+            &mut outgoing,
+            "initialize",
+            json!({ "protocolVersion": 1 }),
+        )
+        .await;
+        assert_eq!(init["result"]["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(init["result"]["agentInfo"]["name"], "g3");
+
+        let no_cwd = request(&server, &mut outgoing, "session/new", json!({})).await;
+        assert_eq!(no_cwd["error"]["code"], INVALID_PARAMS);
+        let sse = request(
+            &server,
+            &mut outgoing,
+            "session/new",
+            json!({ "cwd": "/tmp", "mcpServers": [{ "type": "sse", "name": "x", "url": "http://x" }] }),
+        )
+        .await;
+        assert_eq!(sse["error"]["code"], INVALID_PARAMS);
+        let prompt = request(
+            &server,
+            &mut outgoing,
+            "session/prompt",
+            json!({ "sessionId": "nope", "prompt": [{ "type": "text", "text": "hi" }] }),
+        )
+        .await;
+        assert_eq!(prompt["error"]["code"], INVALID_PARAMS);
+        let unsupported = request(&server, &mut outgoing, "session/load", json!({})).await;
+        assert_eq!(unsupported["error"]["code"], METHOD_NOT_FOUND);
+
+        // Notifications and stray responses get no answer
+        let cancel = json!({ "jsonrpc": "2.0", "method": "session/cancel", "params": { "sessionId": "nope" } });
+        server.clone().handle(cancel).await;
+        server
+            .clone()
+            .handle(json!({ "jsonrpc": "2.0", "id": 7, "result": {} }))
+            .await;
+        assert!(outgoing.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_permission_requests_wait_for_the_client() {
+        let (server, mut outgoing) = AcpServer::new(Config::default(), None);
+        let server = Arc::new(server);
+        let options = vec!["yes".to_string(), "no".to_string()];
+
+        let asking = {
+            let server = server.clone();
+            let options = options.clone();
+            tokio::spawn(async move {
+                let mut turn = Turn::new(Arc::new(Snapshots::default()));
+                server
+                    .request_permission("s", &mut turn, "Allow shell: rm -rf build?", &options)
+                    .await
+            })
+        };
+        let request = outgoing.recv().await.unwrap();
+        assert_eq!(request["method"], "session/request_permission");
+        assert_eq!(request["params"]["options"][1]["kind"], "reject_once");
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": { "outcome": { "outcome": "selected", "optionId": "0" } }
+        });
+        server.clone().handle(response).await;
+        assert_eq!(asking.await.unwrap(), 0);
+
+        assert_eq!(decline_index(&options), 1);
+        assert_eq!(decline_index(&["a".to_string(), "b".to_string()]), 1);
+    }
+
+    #[test]
+    fn test_edits_are_reported_as_diffs() {
+        let dir = std::env::temp_dir().join(format!("g3-acp-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("main.rs");
+        std::fs::write(&file, "fn main() {}\n").unwrap();
+
+        let snapshots = Arc::new(Snapshots::default());
+        let mut turn = Turn::new(snapshots.clone());
+        let args = json!({ "file_path": file, "diff": "..." });
+        snapshots.tool_starting("str_replace", Some(&args));
+        let started = turn.updates(&AgentEvent::ToolHeader {
+            tool: "str_replace".to_string(),
+            args: Some(args),
+        });
+        std::fs::write(&file, "fn main() {\n    run();\n}\n").unwrap();
+        let finished = turn.updates(&AgentEvent::ToolResult {
+            tool: "str_replace".to_string(),
+            output: "✅ applied".to_string(),
+            success: true,
+        });
+
+        assert_eq!(started[0]["sessionUpdate"], "tool_call");
+        assert_eq!(started[0]["kind"], "edit");
+        assert_eq!(started[0]["locations"][0]["path"], json!(file));
+        let update = &finished[0];
+        assert_eq!(update["toolCallId"], started[0]["toolCallId"]);
+        assert_eq!(update["status"], "completed");
+        assert_eq!(update["content"][0]["type"], "diff");
+        assert_eq!(update["content"][0]["oldText"], "fn main() {}\n");
+        assert_eq!(
+            update["content"][0]["newText"],
+            "fn main() {\n    run();\n}\n"
+        );
+        assert_eq!(update["content"][1]["content"]["text"], "✅ applied");
+        // Finished with its result already
+        let timing = AgentEvent::ToolTiming {
+            duration: "1ms".to_string(),
+            tokens_delta: 0,
+            context_percentage: 1.0,
+        };
+        assert!(turn.updates(&timing).is_empty());
+
+        let text = turn.updates(&AgentEvent::AgentResponse {
+            text: "Done".to_string(),
+        });
+        assert_eq!(text[0]["sessionUpdate"], "agent_message_chunk");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_parameters() {
+        let prompt = json!([
+            { "type": "text", "text": "Explain this" },
+            { "type": "resource", "resource": { "uri": "file:///a.rs", "text": "fn a() {}" } },
+            { "type": "image", "data": "..." }
+        ]);
+        assert_eq!(
+            prompt_text(&prompt),
+            "Explain this\n\nContents of file:///a.rs:\n```\nfn a() {}\n```"
+        );
+
+        let (name, stdio) = mcp_server_config(&json!({
+            "name": "fs",
+            "command": "mcp-fs",
+            "args": ["--root", "."],
+            "env": [{ "name": "DEBUG", "value": "1" }]
+        }))
+        .unwrap();
+        assert_eq!(name, "fs");
+        assert_eq!(stdio.command.as_deref(), Some("mcp-fs"));
+        assert_eq!(stdio.args, ["--root", "."]);
+        assert_eq!(stdio.env["DEBUG"], "1");
+        let (_, http) = mcp_server_config(&json!({
+            "type": "http",
+            "name": "docs",
+            "url": "https://example.com/mcp",
+            "headers": [{ "name": "Authorization", "value": "Bearer t" }]
+        }))
+        .unwrap();
+        assert_eq!(http.url.as_deref(), Some("https://example.com/mcp"));
+        assert_eq!(http.headers["Authorization"], "Bearer t");
+
+        assert_eq!(
+            tool_title("shell", Some(&json!({ "command": "cargo test\nls" }))),
+            "shell: cargo test"
+        );
+        assert_eq!(tool_kind("grep"), "search");
+    }
+}
@@ -0,0 +1,784 @@
+//! HTTP server for driving g3 remotely (`g3 serve`).
+//!
+//! Every session is an `Agent` of its own whose UI output is streamed to
+//! clients as server-sent events, so a web UI or a remote script can run
+//! tasks without reimplementing the agent loop. Requests and responses are
+//! JSON:
+//! - `POST /sessions` - start a session; returns its `id`
+//! - `GET /sessions` - list the sessions
+//! - `DELETE /sessions/:id` - stop a session
+//! - `POST /sessions/:id/tasks` - run `{"task": "..."}`; 409 while a task runs
+//! - `POST /sessions/:id/cancel` - cancel the running task
+//! - `GET /sessions/:id/events` - the session's events as SSE, one JSON
+//!   object per event, tagged by `type`: the agent's `AgentEvent`s and the
+//!   `SessionEvent`s (`task_started`, `task_finished`, `task_failed`,
+//!   `task_cancelled`, `approval_required`)
+//! - `POST /sessions/:id/approvals/:approval_id` - answer an approval with
+//!   `{"approve": true}` or `{"answer": <option index>}`
+//! - `GET /sessions/:id/ws` - a WebSocket carrying the same events out and
+//!   `ClientMessage`s in (tasks, approvals, cancellation), for front-ends
+//!   that want a single duplex connection; the upgrade needs the token and,
+//!   from a browser, this server's origin
+//!
+//! Every request must carry the token, `[server] token` or one generated
+//! at startup, as `Authorization: Bearer <token>` or, for `EventSource` and
+//! WebSocket clients that can't set headers, a `token` query parameter. Its
+//! `Host`, and its `Origin` when a browser sends one, must name the address
+//! the server listens on, so a web page can't reach it through DNS
+//! rebinding or a cross-site request.
+//!
+//! `g3 mcp` serves g3's tools and sessions over the Model Context Protocol
+//! on stdio instead (see `mcp`), and `g3 acp` runs g3 as an editor's agent
+//! over the Agent Client Protocol (see `acp`).
+
+pub mod acp;
+pub mod mcp;
+mod session;
+
+pub use acp::{serve_acp, AcpServer};
+pub use mcp::{serve_mcp, McpServer};
+pub use session::{ServerEvent, ServerSession, SessionEvent, SessionOptions};
+
+use anyhow::{Context, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use g3_config::Config;
+
+/// What the handlers share.
+pub struct ServerState {
+    config: Config,
+    project_context: Option<String>,
+    sessions: Mutex<HashMap<String, Arc<ServerSession>>>,
+    /// `[server] token`, or a random one
+    token: String,
+    /// The `host:port` authorities requests may be addressed to
+    hosts: Vec<String>,
+}
+
+impl ServerState {
+    /// Sessions get `config` and `project_context` (AGENTS.md, memory, ...);
+    /// requests must be addressed to `addr`.
+    pub fn new(config: Config, project_context: Option<String>, addr: SocketAddr) -> Self {
+        let token = config
+            .server
+            .token
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+        let hosts = allowed_hosts(addr, &config.server.allowed_hosts);
+        Self {
+            config,
+            project_context,
+            sessions: Mutex::new(HashMap::new()),
+            token,
+            hosts,
+        }
+    }
+
+    /// The token requests must carry.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Whether `authority` (from `Host` or `Origin`) names this server.
+    fn is_allowed_host(&self, authority: &str) -> bool {
+        let authority = authority.to_ascii_lowercase();
+        let authority = if authority.ends_with(']') || !authority.contains(':') {
+            // No port: the scheme's default
+            format!("{}:80", authority)
+        } else {
+            authority
+        };
+        self.hosts.contains(&authority)
+    }
+
+    /// A browser request must come from a page served by this server.
+    fn check_origin(&self, headers: &HeaderMap) -> Result<(), ApiError> {
+        let Some(origin) = headers.get(header::ORIGIN) else {
+            return Ok(());
+        };
+        let authority = origin.to_str().ok().and_then(|o| {
+            o.strip_prefix("http://")
+                .or_else(|| o.strip_prefix("https://"))
+        });
+        match authority {
+            Some(authority) if self.is_allowed_host(authority) => Ok(()),
+            _ => Err(ApiError(
+                StatusCode::FORBIDDEN,
+                "Cross-origin requests are not allowed".to_string(),
+            )),
+        }
+    }
+
+    fn session(&self, id: &str) -> Result<Arc<ServerSession>, ApiError> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| ApiError::not_found(format!("No session '{}'", id)))
+    }
+}
+
+/// A session as listed by the API.
+#[derive(Debug, Serialize)]
+struct SessionInfo {
+    id: String,
+    running: bool,
+}
+
+impl From<&ServerSession> for SessionInfo {
+    fn from(session: &ServerSession) -> Self {
+        Self {
+            id: session.id.clone(),
+            running: session.is_running(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TaskRequest {
+    task: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApprovalAnswer {
+    /// Answer a yes/no prompt
+    approve: Option<bool>,
+    /// Pick an option by index
+    answer: Option<usize>,
+}
+
+impl ApprovalAnswer {
+    /// The index of the option chosen.
+    fn index(&self) -> Result<usize, ApiError> {
+        match (self.answer, self.approve) {
+            (Some(index), _) => Ok(index),
+            (None, Some(approve)) => Ok(usize::from(!approve)),
+            (None, None) => Err(ApiError(
+                StatusCode::BAD_REQUEST,
+                "Expected \"approve\" or \"answer\"".to_string(),
+            )),
+        }
+    }
+}
+
+/// A message from a WebSocket client, tagged by `type`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Run a task, or reply to the question the agent ended its turn with
+    Task { task: String },
+    /// Answer an `approval_required` event, like
+    /// `POST /sessions/:id/approvals/:approval_id`
+    Approval {
+        approval_id: u64,
+        approve: Option<bool>,
+        answer: Option<usize>,
+    },
+    /// Cancel the running task
+    Cancel,
+}
+
+/// An error response: the status and `{"error": "..."}`.
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn not_found(message: String) -> Self {
+        Self(StatusCode::NOT_FOUND, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// The server's routes.
+pub fn router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/sessions", post(create_session).get(list_sessions))
+        .route("/sessions/:id", delete(delete_session))
+        .route("/sessions/:id/tasks", post(submit_task))
+        .route("/sessions/:id/cancel", post(cancel_task))
+        .route("/sessions/:id/events", get(stream_events))
+        .route("/sessions/:id/ws", get(open_websocket))
+        .route(
+            "/sessions/:id/approvals/:approval_id",
+            post(answer_approval),
+        )
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
+}
+
+/// The `host:port` authorities of a server listening on `addr`: its
+/// address, the loopback names when it listens on loopback or on every
+/// interface, and `[server] allowed_hosts`.
+fn allowed_hosts(addr: SocketAddr, extra: &[String]) -> Vec<String> {
+    let port = addr.port();
+    let mut hosts = vec![addr.to_string()];
+    if addr.ip().is_loopback() || addr.ip().is_unspecified() {
+        hosts.extend(
+            ["localhost", "127.0.0.1", "[::1]"]
+                .iter()
+                .map(|host| format!("{}:{}", host, port)),
+        );
+    }
+    hosts.extend(extra.iter().map(|host| match host.rsplit_once(':') {
+        Some((_, p)) if p.parse::<u16>().is_ok() && !host.ends_with(']') => host.to_string(),
+        _ => format!("{}:{}", host, port),
+    }));
+    for host in &mut hosts {
+        host.make_ascii_lowercase();
+    }
+    hosts
+}
+
+/// A server bound to its address; `run` serves it.
+pub struct Server {
+    listener: tokio::net::TcpListener,
+    state: Arc<ServerState>,
+}
+
+impl Server {
+    /// Listen on `bind`, or `[server] bind`.
+    pub async fn bind(
+        config: Config,
+        project_context: Option<String>,
+        bind: Option<&str>,
+    ) -> Result<Self> {
+        let bind = bind.unwrap_or(&config.server.bind);
+        let addr: SocketAddr = bind
+            .parse()
+            .with_context(|| format!("Invalid address '{}'", bind))?;
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let state = Arc::new(ServerState::new(
+            config,
+            project_context,
+            listener.local_addr()?,
+        ));
+        Ok(Self { listener, state })
+    }
+
+    /// The address the server listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.listener
+            .local_addr()
+            .expect("a bound listener has an address")
+    }
+
+    /// The token clients must send.
+    pub fn token(&self) -> &str {
+        self.state.token()
+    }
+
+    /// Serve until the process is stopped.
+    pub async fn run(self) -> Result<()> {
+        info!("g3 server listening on http://{}", self.local_addr());
+        axum::serve(self.listener, router(self.state)).await?;
+        Ok(())
+    }
+}
+
+async fn authorize(
+    State(state): State<Arc<ServerState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let headers = request.headers();
+    let host = headers
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok());
+    if !host.is_some_and(|host| state.is_allowed_host(host)) {
+        return ApiError(StatusCode::FORBIDDEN, "Unexpected Host header".to_string())
+            .into_response();
+    }
+    if let Err(error) = state.check_origin(headers) {
+        return error.into_response();
+    }
+
+    let token = state.token.as_bytes();
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|bearer| constant_time_eq(bearer.as_bytes(), token));
+    let query = request.uri().query().unwrap_or_default();
+    let in_query = query.split('&').any(|pair| {
+        pair.strip_prefix("token=")
+            .is_some_and(|value| constant_time_eq(value.as_bytes(), token))
+    });
+    if !bearer && !in_query {
+        return ApiError(
+            StatusCode::UNAUTHORIZED,
+            "Missing or wrong token".to_string(),
+        )
+        .into_response();
+    }
+    next.run(request).await
+}
+
+/// Compare without returning early, so the time taken doesn't tell how
+/// much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn create_session(
+    State(state): State<Arc<ServerState>>,
+) -> Result<(StatusCode, Json<SessionInfo>), ApiError> {
+    let session = ServerSession::start(state.config.clone(), state.project_context.clone())
+        .await
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    let info = SessionInfo::from(&session);
+    state
+        .sessions
+        .lock()
+        .unwrap()
+        .insert(session.id.clone(), Arc::new(session));
+    Ok((StatusCode::CREATED, Json(info)))
+}
+
+async fn list_sessions(State(state): State<Arc<ServerState>>) -> Json<Vec<SessionInfo>> {
+    let sessions = state.sessions.lock().unwrap();
+    let mut list: Vec<SessionInfo> = sessions
+        .values()
+        .map(|session| SessionInfo::from(session.as_ref()))
+        .collect();
+    list.sort_by(|a, b| a.id.cmp(&b.id));
+    Json(list)
+}
+
+async fn delete_session(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let session = state.sessions.lock().unwrap().remove(&id);
+    match session {
+        Some(session) => {
+            // Otherwise its task would run on, tools and all, for nobody
+            session.cancel();
+            Ok(StatusCode::NO_CONTENT)
+        }
+        None => Err(ApiError::not_found(format!("No session '{}'", id))),
+    }
+}
+
+async fn submit_task(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+    Json(request): Json<TaskRequest>,
+) -> Result<(StatusCode, Json<SessionInfo>), ApiError> {
+    let session = state.session(&id)?;
+    if !session.submit(request.task) {
+        return Err(ApiError(
+            StatusCode::CONFLICT,
+            format!("Session '{}' is running a task", id),
+        ));
+    }
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(SessionInfo::from(session.as_ref())),
+    ))
+}
+
+async fn cancel_task(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if state.session(&id)?.cancel() {
+        Ok(StatusCode::ACCEPTED)
+    } else {
+        Err(ApiError(
+            StatusCode::CONFLICT,
+            format!("Session '{}' is not running a task", id),
+        ))
+    }
+}
+
+async fn stream_events(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let receiver = state.session(&id)?.subscribe();
+    let stream = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let data = serde_json::to_string(&event).unwrap_or_default();
+                    return Some((Ok(Event::default().data(data)), receiver));
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("SSE client fell behind; {} events skipped", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn answer_approval(
+    State(state): State<Arc<ServerState>>,
+    Path((id, approval_id)): Path<(String, u64)>,
+    Json(answer): Json<ApprovalAnswer>,
+) -> Result<StatusCode, ApiError> {
+    let session = state.session(&id)?;
+    if session.answer(approval_id, answer.index()?) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::not_found(format!(
+            "No pending approval {} in session '{}'",
+            approval_id, id
+        )))
+    }
+}
+
+/// Browsers let any page open a WebSocket to any server, so the upgrade's
+/// origin is checked here as well as in `authorize` (which also checks the
+/// token, passed in the query by browsers).
+async fn open_websocket(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    state.check_origin(&headers)?;
+    let session = state.session(&id)?;
+    Ok(upgrade.on_upgrade(move |socket| run_websocket(socket, session)))
+}
+
+/// Send the session's events to the socket and act on the client's
+/// messages until either side closes.
+async fn run_websocket(mut socket: WebSocket, session: Arc<ServerSession>) {
+    let mut events = session.subscribe();
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let data = serde_json::to_string(&event).unwrap_or_default();
+                    if socket.send(Message::Text(data)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("WebSocket client fell behind; {} events skipped", skipped);
+                }
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    if let Err(ApiError(_, error)) = handle_client_message(&session, &text) {
+                        let reply = serde_json::json!({ "type": "error", "message": error });
+                        if socket.send(Message::Text(reply.to_string())).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+                // Pings are answered by axum
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Act on a WebSocket client's message. Its effects show in the session's
+/// events; a message that can't be acted on is an error.
+fn handle_client_message(session: &ServerSession, text: &str) -> Result<(), ApiError> {
+    let message: ClientMessage = serde_json::from_str(text)
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, format!("Invalid message: {}", e)))?;
+    let refusal = match message {
+        ClientMessage::Task { task } => {
+            if session.submit(task) {
+                return Ok(());
+            }
+            format!("Session '{}' is running a task", session.id)
+        }
+        ClientMessage::Approval {
+            approval_id,
+            approve,
+            answer,
+        } => {
+            if session.answer(approval_id, ApprovalAnswer { approve, answer }.index()?) {
+                return Ok(());
+            }
+            format!("No pending approval {}", approval_id)
+        }
+        ClientMessage::Cancel => {
+            if session.cancel() {
+                return Ok(());
+            }
+            format!("Session '{}' is not running a task", session.id)
+        }
+    };
+    Err(ApiError(StatusCode::CONFLICT, refusal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    const ADDR: &str = "127.0.0.1:7340";
+
+    fn state(token: Option<&str>) -> Arc<ServerState> {
+        let mut config = Config::default();
+        config.server.token = token.map(str::to_string);
+        Arc::new(ServerState::new(config, None, ADDR.parse().unwrap()))
+    }
+
+    fn app(token: Option<&str>) -> Router {
+        router(state(token))
+    }
+
+    async fn status(app: &Router, method: &str, uri: &str, auth: Option<&str>) -> StatusCode {
+        send(app, method, uri, &[], auth).await
+    }
+
+    /// Send a request to `ADDR` with extra `headers`.
+    async fn send(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        headers: &[(header::HeaderName, &str)],
+        auth: Option<&str>,
+    ) -> StatusCode {
+        let mut request = axum::http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json");
+        if !headers.iter().any(|(name, _)| name == header::HOST) {
+            request = request.header(header::HOST, ADDR);
+        }
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+        if let Some(auth) = auth {
+            request = request.header(header::AUTHORIZATION, auth);
+        }
+        let body = if method == "POST" {
+            Body::from(r#"{"task": "ls", "approve": true}"#)
+        } else {
+            Body::empty()
+        };
+        app.clone()
+            .oneshot(request.body(body).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_requests_need_the_token() {
+        let app = app(Some("s3cret"));
+        assert_eq!(
+            status(&app, "GET", "/sessions", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&app, "GET", "/sessions", Some("Bearer wrong")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&app, "GET", "/sessions", Some("Bearer s3cret")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, "GET", "/sessions?token=s3cret", None).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_a_token_is_generated_when_none_is_configured() {
+        let state = state(None);
+        let token = state.token().to_string();
+        assert_eq!(token.len(), 32);
+        let app = router(state);
+        assert_eq!(
+            status(&app, "GET", "/sessions", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&app, "GET", "/sessions", Some(&format!("Bearer {}", token))).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_host_and_origin_must_name_the_server() {
+        let app = app(Some("s3cret"));
+        let auth = Some("Bearer s3cret");
+        for (headers, expected) in [
+            (vec![(header::HOST, "localhost:7340")], StatusCode::OK),
+            (
+                vec![(header::HOST, "evil.example:7340")],
+                StatusCode::FORBIDDEN,
+            ),
+            (vec![(header::HOST, "localhost:80")], StatusCode::FORBIDDEN),
+            (
+                vec![(header::ORIGIN, "http://localhost:7340")],
+                StatusCode::OK,
+            ),
+            (
+                vec![(header::ORIGIN, "https://evil.example")],
+                StatusCode::FORBIDDEN,
+            ),
+            (vec![(header::ORIGIN, "null")], StatusCode::FORBIDDEN),
+        ] {
+            assert_eq!(
+                send(&app, "GET", "/sessions", &headers, auth).await,
+                expected,
+                "{:?}",
+                headers
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_websocket_upgrades_need_the_token_and_origin() {
+        let app = app(Some("s3cret"));
+        let upgrade = [
+            (header::CONNECTION, "upgrade"),
+            (header::UPGRADE, "websocket"),
+            (header::SEC_WEBSOCKET_VERSION, "13"),
+            (header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ=="),
+        ];
+        let uri = "/sessions/nope/ws";
+        assert_eq!(
+            send(&app, "GET", uri, &upgrade, None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        let foreign = [
+            upgrade.as_slice(),
+            &[(header::ORIGIN, "https://evil.example")],
+        ]
+        .concat();
+        assert_eq!(
+            send(
+                &app,
+                "GET",
+                "/sessions/nope/ws?token=s3cret",
+                &foreign,
+                None
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+
+        let state = state(Some("s3cret"));
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ORIGIN, "http://attacker.test".parse().unwrap());
+        assert!(state.check_origin(&headers).is_err());
+        headers.insert(header::ORIGIN, "http://127.0.0.1:7340".parse().unwrap());
+        assert!(state.check_origin(&headers).is_ok());
+    }
+
+    #[test]
+    fn test_allowed_hosts() {
+        let extra = ["g3.internal".to_string(), "Proxy:443".to_string()];
+        assert_eq!(
+            allowed_hosts("0.0.0.0:7340".parse().unwrap(), &extra),
+            [
+                "0.0.0.0:7340",
+                "localhost:7340",
+                "127.0.0.1:7340",
+                "[::1]:7340",
+                "g3.internal:7340",
+                "proxy:443"
+            ]
+        );
+        assert_eq!(
+            allowed_hosts("10.0.0.5:80".parse().unwrap(), &[]),
+            ["10.0.0.5:80"]
+        );
+        let state = state(None);
+        assert!(state.is_allowed_host("LOCALHOST:7340"));
+        assert!(!state.is_allowed_host("localhost"));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"s3cret", b"s3cret"));
+        assert!(!constant_time_eq(b"s3cret", b"s3creT"));
+        assert!(!constant_time_eq(b"s3cret", b"s3cre"));
+    }
+
+    #[test]
+    fn test_client_messages() {
+        let parse = |text: &str| serde_json::from_str::<ClientMessage>(text).unwrap();
+        assert!(matches!(
+            parse(r#"{"type": "task", "task": "add a test"}"#),
+            ClientMessage::Task { task } if task == "add a test"
+        ));
+        assert!(matches!(
+            parse(r#"{"type": "approval", "approval_id": 2, "approve": false}"#),
+            ClientMessage::Approval {
+                approval_id: 2,
+                approve: Some(false),
+                answer: None
+            }
+        ));
+        assert!(matches!(
+            parse(r#"{"type": "cancel"}"#),
+            ClientMessage::Cancel
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_deleting_a_busy_session_cancels_its_task() {
+        let state = state(Some("s3cret"));
+        let (session, mut tasks) = ServerSession::without_agent("busy");
+        assert!(session.submit("cargo test".to_string()));
+        state
+            .sessions
+            .lock()
+            .unwrap()
+            .insert(session.id.clone(), Arc::new(session));
+        let (_, cancellation_token) = tasks.recv().await.unwrap();
+
+        let app = router(state.clone());
+        assert_eq!(
+            status(&app, "DELETE", "/sessions/busy", Some("Bearer s3cret")).await,
+            StatusCode::NO_CONTENT
+        );
+        assert!(cancellation_token.is_cancelled());
+        assert!(state.sessions.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_sessions_are_not_found() {
+        let app = app(Some("s3cret"));
+        for (method, uri) in [
+            ("POST", "/sessions/nope/tasks"),
+            ("POST", "/sessions/nope/cancel"),
+            ("GET", "/sessions/nope/events"),
+            ("POST", "/sessions/nope/approvals/1"),
+            ("DELETE", "/sessions/nope"),
+        ] {
+            assert_eq!(
+                status(&app, method, uri, Some("Bearer s3cret")).await,
+                StatusCode::NOT_FOUND,
+                "{}",
+                uri
+            );
+        }
+    }
+}
@@ -0,0 +1,397 @@
+//! MCP server for using g3 as the execution backend of another agent or an
+//! editor (`g3 mcp`).
+//!
+//! Speaks the Model Context Protocol as newline-delimited JSON-RPC on
+//! stdin/stdout, so it can be registered as a stdio server in Claude Desktop,
+//! editors and other MCP clients. It offers:
+//! - g3's own tools for the shell, files, code search, git and the plan
+//!   (`EXPOSED_TOOLS`), run by one agent kept for the whole connection
+//! - `g3_task`, which runs a task with a full agent in a session of its own
+//!   and returns its response; passing the returned `session_id` continues
+//!   the session with its context
+//!
+//! Requests run concurrently, so pings and `notifications/cancelled` are
+//! answered while a task runs; calls to g3's tools take turns on their agent,
+//! as do tasks of the same session. Nobody can approve risky tool calls (see
+//! `approval`), so they are declined unless `[approval] auto_approve` lets
+//! them run.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::{AbortHandle, LocalSet};
+use tracing::{debug, info};
+
+use g3_config::Config;
+use g3_core::tool_definitions::{create_tool_definitions, ToolConfig};
+use g3_core::ui_writer::HeadlessUiWriter;
+use g3_core::{Agent, ToolCall, ToolPolicy};
+
+/// Protocol revisions the server speaks, newest first.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// g3's tools offered to MCP clients.
+pub const EXPOSED_TOOLS: &[&str] = &[
+    "shell",
+    "read_file",
+    "read_many_files",
+    "write_file",
+    "str_replace",
+    "multi_edit",
+    "list_dir",
+    "grep",
+    "code_search",
+    "find_definition",
+    "find_references",
+    "symbol_lookup",
+    "ast_outline",
+    "git_status",
+    "git_diff",
+    "plan_read",
+    "plan_write",
+];
+
+/// The tool running a task with a full agent.
+const TASK_TOOL: &str = "g3_task";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// The state of one MCP connection.
+pub struct McpServer {
+    config: Config,
+    project_context: Option<String>,
+    /// Runs the exposed tools, built on first use
+    tools_agent: Mutex<Option<Agent<HeadlessUiWriter>>>,
+    /// Agents of the sessions started by `g3_task`, by session ID
+    sessions: RefCell<HashMap<String, Rc<Mutex<Agent<HeadlessUiWriter>>>>>,
+}
+
+impl McpServer {
+    /// Agents get `config` and `project_context` (AGENTS.md, memory, ...).
+    pub fn new(config: Config, project_context: Option<String>) -> Self {
+        Self {
+            config,
+            project_context,
+            tools_agent: Mutex::new(None),
+            sessions: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Handle a message from the client. Returns the response to a request;
+    /// notifications get none.
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        let method = message.get("method").and_then(|v| v.as_str())?;
+        let id = message.get("id")?.clone();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        debug!("MCP request {}", method);
+
+        let result = match method {
+            "initialize" => Ok(initialize_result(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_list() })),
+            "tools/call" => self.call_tool(&params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message }
+            }),
+        })
+    }
+
+    /// Run a `tools/call`. Failures of the tool itself are results flagged
+    /// `isError`, so the client's model sees them.
+    async fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| (INVALID_PARAMS, "Missing tool name".to_string()))?;
+        let args = params.get("arguments").cloned().unwrap_or(json!({}));
+        let output = if name == TASK_TOOL {
+            self.run_task(&args).await
+        } else if EXPOSED_TOOLS.contains(&name) {
+            self.run_tool(name, args).await
+        } else {
+            return Err((INVALID_PARAMS, format!("Unknown tool '{}'", name)));
+        };
+
+        let (text, is_error) = match output {
+            Ok(output) => {
+                let is_error = output.starts_with('❌');
+                (output, is_error)
+            }
+            Err(e) => (format!("{:#}", e), true),
+        };
+        Ok(json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": is_error
+        }))
+    }
+
+    async fn run_tool(&self, name: &str, args: Value) -> Result<String> {
+        let mut tools_agent = self.tools_agent.lock().await;
+        if tools_agent.is_none() {
+            let policy =
+                ToolPolicy::AllowOnly(EXPOSED_TOOLS.iter().map(|t| t.to_string()).collect());
+            let agent = self.build_agent(new_session_id(), policy).await?;
+            *tools_agent = Some(agent);
+        }
+        let agent = tools_agent.as_mut().expect("built above");
+        let tool_call = ToolCall {
+            tool: name.to_string(),
+            args,
+        };
+        agent.execute_tool_in_dir(&tool_call, None).await
+    }
+
+    async fn run_task(&self, args: &Value) -> Result<String> {
+        let task = args
+            .get("task")
+            .and_then(|v| v.as_str())
+            .context("Missing \"task\"")?;
+        let (session_id, agent) = match args.get("session_id").and_then(|v| v.as_str()) {
+            Some(session_id) => {
+                let agent = self.sessions.borrow().get(session_id).cloned();
+                let Some(agent) = agent else {
+                    bail!(
+                        "No session '{}' in this connection; omit session_id to start one",
+                        session_id
+                    )
+                };
+                (session_id.to_string(), agent)
+            }
+            None => {
+                let session_id = new_session_id();
+                let agent = self
+                    .build_agent(session_id.clone(), ToolPolicy::default())
+                    .await?;
+                let agent = Rc::new(Mutex::new(agent));
+                self.sessions
+                    .borrow_mut()
+                    .insert(session_id.clone(), agent.clone());
+                (session_id, agent)
+            }
+        };
+
+        // Tasks of the same session take turns
+        let mut agent = agent.lock().await;
+        info!("Running task in session {}", session_id);
+        let result = agent
+            .execute_task_with_timing(task, None, false, false, false, false, None)
+            .await?;
+        agent.save_session_continuation(Some(result.response.clone()));
+        Ok(format!(
+            "{}\n\n[g3 session_id: {}]",
+            result.response.trim_end(),
+            session_id
+        ))
+    }
+
+    async fn build_agent(
+        &self,
+        session_id: String,
+        tool_policy: ToolPolicy,
+    ) -> Result<Agent<HeadlessUiWriter>> {
+        // Nobody watches the UI output, and nobody can approve risky calls
+        let ui_writer = HeadlessUiWriter::new().default_confirm(false);
+        Agent::builder(self.config.clone(), ui_writer)
+            .project_context(self.project_context.clone())
+            .session_id(session_id)
+            .tool_policy(tool_policy)
+            .build()
+            .await
+    }
+}
+
+/// Serve MCP on stdin/stdout until the client closes stdin and the
+/// requests still running have finished.
+pub async fn serve_mcp(config: Config, project_context: Option<String>) -> Result<()> {
+    // Agents aren't `Send`, so requests run as local tasks on this thread
+    let server = Rc::new(McpServer::new(config, project_context));
+    LocalSet::new().run_until(serve(server)).await
+}
+
+async fn serve(server: Rc<McpServer>) -> Result<()> {
+    let (responses, mut outgoing) = mpsc::unbounded_channel::<Value>();
+    let writer = tokio::task::spawn_local(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(response) = outgoing.recv().await {
+            stdout
+                .write_all(format!("{}\n", response).as_bytes())
+                .await?;
+            stdout.flush().await?;
+        }
+        anyhow::Ok(())
+    });
+    // Requests still running, by their JSON-RPC ID, so they can be cancelled
+    let running: Rc<RefCell<HashMap<String, AbortHandle>>> = Rc::default();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    info!("g3 MCP server ready on stdio");
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                let _ = responses.send(json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": { "code": PARSE_ERROR, "message": e.to_string() }
+                }));
+                continue;
+            }
+        };
+
+        if message.get("method").and_then(|v| v.as_str()) == Some("notifications/cancelled") {
+            // A cancelled request gets no response; dropping its future
+            // stops a running tool as well
+            let id = message.pointer("/params/requestId").map(Value::to_string);
+            if let Some(request) = id.and_then(|id| running.borrow_mut().remove(&id)) {
+                debug!("MCP request cancelled");
+                request.abort();
+            }
+            continue;
+        }
+
+        let id = message.get("id").map(Value::to_string);
+        let request = tokio::task::spawn_local({
+            let server = server.clone();
+            let responses = responses.clone();
+            let running = running.clone();
+            let id = id.clone();
+            async move {
+                if let Some(response) = server.handle(message).await {
+                    let _ = responses.send(response);
+                }
+                if let Some(id) = id {
+                    running.borrow_mut().remove(&id);
+                }
+            }
+        });
+        if let Some(id) = id {
+            running.borrow_mut().insert(id, request.abort_handle());
+        }
+    }
+
+    // The writer stops once every running request has sent its response
+    drop(responses);
+    writer.await?
+}
+
+/// The result of `initialize`: the client's protocol revision if the server
+/// speaks it, else the newest.
+fn initialize_result(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(|v| v.as_str());
+    let version = requested
+        .filter(|v| PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "g3", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "g3 runs tools in the directory it was started in. Use g3_task to hand a whole task to a g3 agent."
+    })
+}
+
+/// The tools offered: the exposed g3 tools, then `g3_task`.
+fn tool_list() -> Vec<Value> {
+    let mut tools: Vec<Value> = create_tool_definitions(ToolConfig::default())
+        .into_iter()
+        .filter(|tool| EXPOSED_TOOLS.contains(&tool.name.as_str()))
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "inputSchema": tool.input_schema
+            })
+        })
+        .collect();
+    tools.push(json!({
+        "name": TASK_TOOL,
+        "description": "Hand a task to a g3 coding agent, which plans and carries it out with its own tools in this workspace, and return its final response. The response ends with the session_id; pass it to continue the same session with its context.",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "task": {
+                    "type": "string",
+                    "description": "What the agent should do"
+                },
+                "session_id": {
+                    "type": "string",
+                    "description": "Session to continue, from an earlier g3_task response"
+                }
+            },
+            "required": ["task"]
+        }
+    }));
+    tools
+}
+
+fn new_session_id() -> String {
+    format!("mcp_{}", &uuid::Uuid::new_v4().simple().to_string()[..8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn request(server: &McpServer, method: &str, params: Value) -> Value {
+        server
+            .handle(json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_protocol() {
+        let server = McpServer::new(Config::default(), None);
+
+        let init = request(
+            &server,
+            "initialize",
+            json!({ "protocolVersion": "2024-11-05", "capabilities": {} }),
+        )
+        .await;
+        assert_eq!(init["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(init["result"]["serverInfo"]["name"], "g3");
+        let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert_eq!(server.handle(initialized).await, None);
+
+        let list = request(&server, "tools/list", json!({})).await;
+        let names: Vec<&str> = list["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names.len(), EXPOSED_TOOLS.len() + 1, "{:?}", names);
+        assert!(names.contains(&"shell") && names.contains(&TASK_TOOL));
+        assert!(!names.contains(&"git_commit"));
+
+        let unknown = request(&server, "tools/call", json!({ "name": "git_commit" })).await;
+        assert_eq!(unknown["error"]["code"], INVALID_PARAMS);
+        let missing = request(&server, "tools/call", json!({ "name": TASK_TOOL })).await;
+        assert_eq!(missing["result"]["isError"], true);
+        let resumed = request(
+            &server,
+            "tools/call",
+            json!({ "name": TASK_TOOL, "arguments": { "task": "ls", "session_id": "nope" } }),
+        )
+        .await;
+        assert_eq!(resumed["result"]["isError"], true);
+
+        let unsupported = request(&server, "resources/list", json!({})).await;
+        assert_eq!(unsupported["error"]["code"], METHOD_NOT_FOUND);
+    }
+}
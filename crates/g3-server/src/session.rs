@@ -0,0 +1,417 @@
+//! A server session: an `Agent` on its own thread, running the tasks it is
+//! sent one at a time.
+//!
+//! The agent's `AgentEvent`s are re-broadcast to every subscriber together
+//! with the session's own `SessionEvent`s, in the order they happened. Its
+//! prompts (approval of risky tool calls) are answered through its
+//! `HeadlessUiWriter`: they are broadcast as `approval_required` and block
+//! the agent until `ServerSession::answer` is called or the approval times
+//! out, which declines the call.
+//!
+//! `ServerSession::cancel` cancels the running task's `CancellationToken`,
+//! which stops it at its next await, and declines its pending approvals so
+//! it gets there.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use g3_config::Config;
+use g3_core::ui_writer::{HeadlessUiWriter, PromptHandler, ToolObserver};
+use g3_core::{Agent, AgentEvent};
+
+/// Events kept for subscribers that fall behind.
+const EVENT_BUFFER: usize = 1024;
+
+/// What happened to a session's tasks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionEvent {
+    TaskStarted {
+        task: String,
+    },
+    TaskFinished {
+        response: String,
+    },
+    TaskFailed {
+        error: String,
+    },
+    TaskCancelled,
+    /// The agent waits for an answer at `/sessions/{id}/approvals/{approval_id}`
+    ApprovalRequired {
+        approval_id: u64,
+        message: String,
+        options: Vec<String>,
+    },
+}
+
+/// An event streamed to clients: an `AgentEvent` or a `SessionEvent`, both
+/// tagged by `type`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ServerEvent {
+    Agent(AgentEvent),
+    Session(SessionEvent),
+}
+
+/// Prompts waiting for an answer, by approval ID.
+#[derive(Default)]
+struct Approvals {
+    last_id: AtomicU64,
+    pending: Mutex<HashMap<u64, std_mpsc::Sender<usize>>>,
+}
+
+/// Answers the agent's prompts with the answers posted by clients.
+struct RemotePrompt {
+    approvals: Arc<Approvals>,
+    events: mpsc::UnboundedSender<SessionEvent>,
+    timeout: Duration,
+}
+
+impl PromptHandler for RemotePrompt {
+    fn choose(&self, message: &str, options: &[&str], default: usize) -> usize {
+        let approval_id = self.approvals.last_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let (answer_tx, answer_rx) = std_mpsc::channel();
+        self.approvals
+            .pending
+            .lock()
+            .unwrap()
+            .insert(approval_id, answer_tx);
+        let _ = self.events.send(SessionEvent::ApprovalRequired {
+            approval_id,
+            message: message.to_string(),
+            options: options.iter().map(|o| o.to_string()).collect(),
+        });
+
+        // Runs on the agent's own thread, which has nothing else to do meanwhile
+        let answer = answer_rx.recv_timeout(self.timeout).unwrap_or(default);
+        self.approvals.pending.lock().unwrap().remove(&approval_id);
+        answer.min(options.len().saturating_sub(1))
+    }
+}
+
+/// Optional settings of a new session.
+#[derive(Clone, Default)]
+pub struct SessionOptions {
+    /// Directory the tools run in, instead of the server's
+    pub working_dir: Option<String>,
+    /// Told about every tool before it runs
+    pub tool_observer: Option<Arc<dyn ToolObserver>>,
+}
+
+/// A session of the server. Dropping it stops the agent once its current
+/// task is done, and declines any pending approval.
+pub struct ServerSession {
+    pub id: String,
+    events: broadcast::Sender<ServerEvent>,
+    tasks: mpsc::UnboundedSender<(String, CancellationToken)>,
+    running: Arc<AtomicBool>,
+    approvals: Arc<Approvals>,
+    /// Cancels the last task submitted
+    cancellation_token: Mutex<CancellationToken>,
+}
+
+impl ServerSession {
+    /// Build an agent for a new session and start its thread.
+    pub async fn start(config: Config, project_context: Option<String>) -> Result<Self> {
+        Self::start_with(config, project_context, SessionOptions::default()).await
+    }
+
+    /// Like `start`, with `options`.
+    pub async fn start_with(
+        config: Config,
+        project_context: Option<String>,
+        options: SessionOptions,
+    ) -> Result<Self> {
+        let id = format!("server_{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let (session_events, session_queue) = mpsc::unbounded_channel();
+        let approvals = Arc::new(Approvals::default());
+
+        let prompt = RemotePrompt {
+            approvals: approvals.clone(),
+            events: session_events.clone(),
+            timeout: Duration::from_secs(config.server.approval_timeout_secs),
+        };
+        let mut ui_writer = HeadlessUiWriter::new()
+            .default_confirm(false)
+            .prompt_handler(Arc::new(prompt));
+        if let Some(observer) = options.tool_observer {
+            ui_writer = ui_writer.tool_observer(observer);
+        }
+
+        let (tasks, task_queue) = mpsc::unbounded_channel();
+        let running = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let worker = Worker {
+            id: id.clone(),
+            events: session_events,
+            running: running.clone(),
+            working_dir: options.working_dir,
+        };
+        std::thread::Builder::new()
+            .name(format!("g3-{}", id))
+            .spawn(move || worker.run(config, ui_writer, project_context, task_queue, ready_tx))?;
+        let agent_events = ready_rx.await.context("The session's thread exited")??;
+        tokio::spawn(forward_events(
+            id.clone(),
+            agent_events,
+            session_queue,
+            events.clone(),
+        ));
+
+        Ok(Self {
+            id,
+            events,
+            tasks,
+            running,
+            approvals,
+            cancellation_token: Mutex::new(CancellationToken::new()),
+        })
+    }
+
+    /// Whether a task is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Start `task`, unless one is running. Returns whether it was started.
+    pub fn submit(&self, task: String) -> bool {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        let cancellation_token = CancellationToken::new();
+        *self.cancellation_token.lock().unwrap() = cancellation_token.clone();
+        if self.tasks.send((task, cancellation_token)).is_err() {
+            self.running.store(false, Ordering::SeqCst);
+            return false;
+        }
+        true
+    }
+
+    /// Cancel the running task. Returns false if none is running.
+    pub fn cancel(&self) -> bool {
+        if !self.is_running() {
+            return false;
+        }
+        self.cancellation_token.lock().unwrap().cancel();
+        // Dropping the senders declines the approvals
+        self.approvals.pending.lock().unwrap().clear();
+        true
+    }
+
+    /// Receive the session's events from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Answer a pending approval with the index of an option. Returns false
+    /// if there is no such approval (answered, timed out or unknown).
+    pub fn answer(&self, approval_id: u64, answer: usize) -> bool {
+        self.approvals
+            .pending
+            .lock()
+            .unwrap()
+            .remove(&approval_id)
+            .is_some_and(|answer_tx| answer_tx.send(answer).is_ok())
+    }
+}
+
+#[cfg(test)]
+impl ServerSession {
+    /// A session without an agent thread: submitted tasks are left in the
+    /// returned queue.
+    pub(crate) fn without_agent(
+        id: &str,
+    ) -> (Self, mpsc::UnboundedReceiver<(String, CancellationToken)>) {
+        let (tasks, task_queue) = mpsc::unbounded_channel();
+        let session = Self {
+            id: id.to_string(),
+            events: broadcast::channel(EVENT_BUFFER).0,
+            tasks,
+            running: Arc::new(AtomicBool::new(false)),
+            approvals: Arc::new(Approvals::default()),
+            cancellation_token: Mutex::new(CancellationToken::new()),
+        };
+        (session, task_queue)
+    }
+}
+
+impl Drop for ServerSession {
+    fn drop(&mut self) {
+        // Dropping the senders declines the approvals
+        self.approvals.pending.lock().unwrap().clear();
+    }
+}
+
+/// Broadcast the agent's events and the session's as `ServerEvent`s until
+/// the agent is dropped.
+///
+/// The session's events are sent after what the agent published before
+/// them, so e.g. `task_finished` follows the task's last output.
+async fn forward_events(
+    id: String,
+    mut agent_events: broadcast::Receiver<AgentEvent>,
+    mut session_events: mpsc::UnboundedReceiver<SessionEvent>,
+    events: broadcast::Sender<ServerEvent>,
+) {
+    loop {
+        let event = tokio::select! {
+            biased;
+            event = agent_events.recv() => match event {
+                Ok(event) if event.is_display_only() => continue,
+                Ok(event) => ServerEvent::Agent(event),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Session {} skipped {} agent events", id, skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            Some(event) = session_events.recv() => ServerEvent::Session(event),
+        };
+        let _ = events.send(event);
+    }
+    // The agent is gone; pass on what the session reported last
+    while let Ok(event) = session_events.try_recv() {
+        let _ = events.send(ServerEvent::Session(event));
+    }
+}
+
+/// The session's thread.
+struct Worker {
+    id: String,
+    events: mpsc::UnboundedSender<SessionEvent>,
+    running: Arc<AtomicBool>,
+    working_dir: Option<String>,
+}
+
+impl Worker {
+    /// Build the agent, send its events on `ready` (or why it couldn't be
+    /// built), then run tasks until the session is dropped.
+    fn run(
+        self,
+        config: Config,
+        ui_writer: HeadlessUiWriter,
+        project_context: Option<String>,
+        mut task_queue: mpsc::UnboundedReceiver<(String, CancellationToken)>,
+        ready: oneshot::Sender<Result<broadcast::Receiver<AgentEvent>>>,
+    ) {
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                let _ = ready.send(Err(e.into()));
+                return;
+            }
+        };
+
+        runtime.block_on(async move {
+            let mut builder = Agent::builder(config, ui_writer)
+                .project_context(project_context)
+                .session_id(self.id.clone());
+            if let Some(working_dir) = &self.working_dir {
+                builder = builder.working_dir(working_dir.clone());
+            }
+            let agent = builder.build().await;
+            let mut agent = match agent {
+                Ok(agent) => {
+                    let _ = ready.send(Ok(agent.subscribe_events()));
+                    agent
+                }
+                Err(e) => {
+                    let _ = ready.send(Err(e));
+                    return;
+                }
+            };
+
+            while let Some((task, cancellation_token)) = task_queue.recv().await {
+                self.emit(SessionEvent::TaskStarted { task: task.clone() });
+                // Dropping the task's future stops a running tool as well
+                let result = tokio::select! {
+                    result = agent.execute_task_with_timing_cancellable(
+                        &task,
+                        None,
+                        false,
+                        false,
+                        false,
+                        false,
+                        cancellation_token.clone(),
+                        None,
+                    ) => result,
+                    _ = cancellation_token.cancelled() => {
+                        Err(anyhow::anyhow!("Operation cancelled by user"))
+                    }
+                };
+                let event = match result {
+                    Ok(result) => {
+                        agent.save_session_continuation(Some(result.response.clone()));
+                        SessionEvent::TaskFinished {
+                            response: result.response,
+                        }
+                    }
+                    Err(_) if cancellation_token.is_cancelled() => SessionEvent::TaskCancelled,
+                    Err(e) => SessionEvent::TaskFailed {
+                        error: format!("{:#}", e),
+                    },
+                };
+                self.running.store(false, Ordering::SeqCst);
+                self.emit(event);
+            }
+            debug!("Session {} stopped", self.id);
+        });
+    }
+
+    fn emit(&self, event: SessionEvent) {
+        let _ = self.events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompts_wait_for_remote_answers() {
+        let approvals = Arc::new(Approvals::default());
+        let (events, mut receiver) = mpsc::unbounded_channel();
+        let prompt = RemotePrompt {
+            approvals: approvals.clone(),
+            events,
+            timeout: Duration::from_secs(5),
+        };
+
+        let answering = std::thread::spawn(move || {
+            let Some(SessionEvent::ApprovalRequired { approval_id, .. }) = receiver.blocking_recv()
+            else {
+                panic!("expected an approval request");
+            };
+            let answer_tx = approvals.pending.lock().unwrap().remove(&approval_id);
+            answer_tx.unwrap().send(0).unwrap();
+        });
+        assert_eq!(
+            prompt.choose("Allow shell: rm -rf build?", &["yes", "no"], 1),
+            0
+        );
+        answering.join().unwrap();
+
+        // Nobody answers: the default
+        let prompt = RemotePrompt {
+            timeout: Duration::from_millis(10),
+            ..prompt
+        };
+        assert_eq!(
+            prompt.choose("Allow shell: rm -rf build?", &["yes", "no"], 1),
+            1
+        );
+        assert!(prompt.approvals.pending.lock().unwrap().is_empty());
+    }
+}
@@ -537,7 +537,10 @@ fn extract_session_summary(session: &Session) -> Option<String> {
         for entry in entries.flatten() {
             let path = entry.path();
             if path.is_dir() {
-                let log_file = path.join("session.json");
+                // g3 writes session.json compressed when session_compression is on
+                let log_file = Some(path.join("session.json"))
+                    .filter(|f| f.exists())
+                    .unwrap_or_else(|| path.join("session.json.zst"));
                 if log_file.exists() {
                     if let Ok(metadata) = log_file.metadata() {
                         if let Ok(modified) = metadata.modified() {
@@ -552,7 +555,11 @@ fn extract_session_summary(session: &Session) -> Option<String> {
     }
 
     let log_file = latest_log?.0;
-    let content = fs::read_to_string(&log_file).ok()?;
+    let content = if log_file.extension().is_some_and(|e| e == "zst") {
+        String::from_utf8(zstd::decode_all(fs::File::open(&log_file).ok()?).ok()?).ok()?
+    } else {
+        fs::read_to_string(&log_file).ok()?
+    };
 
     // Parse JSON and extract the last assistant message as summary
     let json: serde_json::Value = serde_json::from_str(&content).ok()?;